@@ -3,6 +3,8 @@
 
 use std::collections::HashMap;
 
+use crate::secret_masker::SecretMasker;
+
 /// A parsed workflow / action command.
 #[derive(Debug, Clone)]
 pub struct ActionCommand {
@@ -194,6 +196,31 @@ impl ActionCommand {
         Some(command)
     }
 
+    /// Render the command as `::command key=value,...::data` for tracing,
+    /// with every property value and the data passed through `masker`.
+    ///
+    /// Properties are sorted by key so the output is stable.
+    pub fn to_masked_string(&self, masker: &SecretMasker) -> String {
+        let mut keys: Vec<&String> = self.properties.keys().collect();
+        keys.sort();
+
+        let properties: Vec<String> = keys
+            .into_iter()
+            .map(|key| format!("{}={}", key, masker.mask_secrets(&self.properties[key])))
+            .collect();
+
+        if properties.is_empty() {
+            format!("::{}::{}", self.command, masker.mask_secrets(&self.data))
+        } else {
+            format!(
+                "::{} {}::{}",
+                self.command,
+                properties.join(","),
+                masker.mask_secrets(&self.data)
+            )
+        }
+    }
+
     /// Escape a value using the standard escape mappings (reverse order to avoid double-encoding).
     pub fn escape_value(value: &str) -> String {
         if value.is_empty() {
@@ -338,6 +365,28 @@ mod tests {
         assert!(escaped.contains("%25"));
     }
 
+    #[test]
+    fn test_to_masked_string() {
+        let cmds = make_commands(&["error"]);
+        let cmd = ActionCommand::try_parse_v2(
+            "::error title=token s3cr3t,file=app.js::leaked s3cr3t here",
+            &cmds,
+        )
+        .unwrap();
+
+        let masker = SecretMasker::new();
+        assert_eq!(
+            cmd.to_masked_string(&masker),
+            "::error file=app.js,title=token s3cr3t::leaked s3cr3t here"
+        );
+
+        masker.add_value("s3cr3t");
+        assert_eq!(
+            cmd.to_masked_string(&masker),
+            "::error file=app.js,title=token ***::leaked *** here"
+        );
+    }
+
     #[test]
     fn test_empty_message() {
         let cmds = make_commands(&["error"]);
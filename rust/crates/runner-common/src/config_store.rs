@@ -1,14 +1,36 @@
 // ConfigurationStore mapping `ConfigurationStore.cs`.
 // Handles loading/saving runner settings and credentials from disk.
-
-use crate::constants::{WellKnownConfigFile, WellKnownDirectory};
+//
+// Credentials may instead come from an external command named by
+// `RUNNER_CREDENTIALS_COMMAND`, e.g. one that reads them from a secret
+// manager. The command prints the same JSON as the `.credentials` file, plus
+// an optional `ExpiresOn` timestamp; its output is cached until shortly
+// before that time, or for the life of the process when it has none.
+//
+// With `RUNNER_ENCRYPT_CREDENTIALS` set, credential files are written
+// encrypted under the runner's RSA key (see `credential_encryption`).
+// Plaintext files from earlier versions are still read, and are encrypted
+// the next time the credentials are saved.
+//
+// A runner configured by the C# runner may only have `.runner_migrated` and
+// `.credentials_migrated`, written in the C# format: camelCase keys, an
+// optional UTF-8 BOM, and the OAuth client ID and authorization URL inside
+// the credential's `data`. `migrate_legacy_config` converts them into the
+// current `.runner` and `.credentials` files.
+
+use crate::constants::{self, WellKnownConfigFile, WellKnownDirectory};
 use crate::credential_data::CredentialData;
+use crate::credential_encryption;
 use crate::host_context::HostContext;
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use runner_sdk::StringUtil;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
 
 // ---------------------------------------------------------------------------
@@ -187,6 +209,204 @@ impl RunnerSettings {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Legacy (C# runner) files
+// ---------------------------------------------------------------------------
+
+/// Parse runner settings written by the C# runner.
+fn parse_legacy_settings(json: &str) -> Result<RunnerSettings> {
+    let value = parse_legacy_json(json)?;
+    serde_json::from_value(value).context("Failed to convert legacy runner settings")
+}
+
+/// Parse credentials written by the C# runner. The OAuth client ID and
+/// authorization URL are lifted out of `data` into their own fields.
+fn parse_legacy_credentials(json: &str) -> Result<CredentialData> {
+    let value = parse_legacy_json(json)?;
+    let mut creds: CredentialData =
+        serde_json::from_value(value).context("Failed to convert legacy credential data")?;
+
+    if creds.client_id.is_none() {
+        creds.client_id = creds.get_data("clientId").cloned();
+    }
+    if creds.authorization_url.is_none() {
+        creds.authorization_url = creds.get_data("authorizationUrl").cloned();
+    }
+    Ok(creds)
+}
+
+/// Parse a C# runner JSON file, with top-level keys in the PascalCase the
+/// current files use.
+fn parse_legacy_json(json: &str) -> Result<serde_json::Value> {
+    let value: serde_json::Value = serde_json::from_str(json.trim_start_matches('\u{feff}'))
+        .context("Failed to parse legacy configuration file")?;
+
+    match value {
+        serde_json::Value::Object(map) => Ok(serde_json::Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (pascal_case(&key), value))
+                .collect(),
+        )),
+        _ => anyhow::bail!("Legacy configuration file is not a JSON object"),
+    }
+}
+
+/// `agentId` → `AgentId`.
+fn pascal_case(key: &str) -> String {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Write `contents` to a temporary file next to `path` and rename it into
+/// place, so a crash never leaves a half-written file behind: `path` holds
+/// either its old contents or the new ones.
+pub fn write_atomically(path: &Path, contents: &str) -> Result<()> {
+    let tmp_path = write_temp_file(path, contents)?;
+    replace_with_temp_file(&tmp_path, path)
+}
+
+/// Write and flush `contents` to the temporary file for `path`.
+fn write_temp_file(path: &Path, contents: &str) -> Result<PathBuf> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let written = fs::File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(contents.as_bytes())?;
+        // Make the data durable before the rename makes it visible
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e).with_context(|| format!("Failed to write {:?}", tmp_path));
+    }
+    Ok(tmp_path)
+}
+
+/// Rename a file written by `write_temp_file` over `path`.
+fn replace_with_temp_file(tmp_path: &Path, path: &Path) -> Result<()> {
+    if let Err(e) = fs::rename(tmp_path, path) {
+        let _ = fs::remove_file(tmp_path);
+        return Err(e).with_context(|| format!("Failed to move {:?} into place", path));
+    }
+
+    // Persist the rename itself; best effort, as not every filesystem allows it
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        let _ = fs::File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Credentials command
+// ---------------------------------------------------------------------------
+
+/// Command output is refreshed this long before it expires.
+const COMMAND_CREDENTIALS_EXPIRY_SKEW_SECONDS: i64 = 60;
+
+/// The output of `RUNNER_CREDENTIALS_COMMAND`.
+#[derive(Debug, Clone, Deserialize)]
+struct CommandCredentials {
+    #[serde(flatten)]
+    credential: CredentialData,
+
+    /// When the credential stops being valid.
+    #[serde(default, rename = "ExpiresOn")]
+    expires_on: Option<DateTime<Utc>>,
+}
+
+impl CommandCredentials {
+    /// Whether the cached output must be fetched again.
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_on.is_some_and(|expires_on| {
+            now + Duration::seconds(COMMAND_CREDENTIALS_EXPIRY_SKEW_SECONDS) >= expires_on
+        })
+    }
+}
+
+/// How long the credentials command may run before it is killed.
+const CREDENTIALS_COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Run the credentials command through the platform shell and parse its
+/// standard output. The command is killed if it runs longer than `timeout`.
+fn run_credentials_command(
+    command: &str,
+    timeout: std::time::Duration,
+) -> Result<CommandCredentials> {
+    let mut child = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .with_context(|| format!("Failed to start credentials command '{}'", command))?;
+
+    // Drain both pipes while waiting so a full pipe cannot stall the command
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let deadline = std::time::Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("Failed to wait for credentials command '{}'", command))?
+        {
+            break status;
+        }
+        if std::time::Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!(
+                "Credentials command '{}' did not finish within {} seconds",
+                command,
+                timeout.as_secs_f64()
+            );
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    if !status.success() {
+        // stdout may hold part of a secret; only stderr is reported
+        anyhow::bail!(
+            "Credentials command '{}' failed with {}: {}",
+            command,
+            status,
+            String::from_utf8_lossy(&stderr).trim()
+        );
+    }
+
+    serde_json::from_slice(&stdout).with_context(|| {
+        format!(
+            "Failed to deserialize credential data from credentials command '{}'",
+            command
+        )
+    })
+}
+
+/// Read `pipe` to the end on a separate thread.
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
 // ---------------------------------------------------------------------------
 // ConfigurationStore
 // ---------------------------------------------------------------------------
@@ -200,12 +420,27 @@ pub struct ConfigurationStore {
     cred_file_path: PathBuf,
     migrated_cred_file_path: PathBuf,
     service_config_file_path: PathBuf,
+    rsa_key_file_path: PathBuf,
     root_folder: PathBuf,
 
     settings: Mutex<Option<RunnerSettings>>,
     migrated_settings: Mutex<Option<RunnerSettings>>,
     creds: Mutex<Option<CredentialData>>,
     migrated_creds: Mutex<Option<CredentialData>>,
+
+    /// Shell command that prints the credentials, replacing the file.
+    credentials_command: Option<String>,
+    command_creds: Mutex<Option<CommandCredentials>>,
+
+    /// Whether credential files are written encrypted.
+    encrypt_credentials: bool,
+}
+
+/// Credentials written to a temporary file by
+/// [`ConfigurationStore::stage_credential`], not yet moved into place.
+pub struct StagedCredential {
+    tmp_path: PathBuf,
+    credential: CredentialData,
 }
 
 impl ConfigurationStore {
@@ -220,14 +455,37 @@ impl ConfigurationStore {
             migrated_cred_file_path: context
                 .get_config_file(WellKnownConfigFile::MigratedCredentials),
             service_config_file_path: context.get_config_file(WellKnownConfigFile::Service),
+            rsa_key_file_path: context.get_config_file(WellKnownConfigFile::RSACredentials),
             root_folder: root,
             settings: Mutex::new(None),
             migrated_settings: Mutex::new(None),
             creds: Mutex::new(None),
             migrated_creds: Mutex::new(None),
+            credentials_command: std::env::var(constants::variables::agent::CREDENTIALS_COMMAND)
+                .ok()
+                .filter(|command| !command.trim().is_empty()),
+            command_creds: Mutex::new(None),
+            encrypt_credentials: std::env::var(constants::variables::agent::ENCRYPT_CREDENTIALS)
+                .ok()
+                .and_then(|value| StringUtil::convert_to_bool(&value))
+                .unwrap_or(false),
         }
     }
 
+    /// Write credential files encrypted under the runner's RSA key.
+    pub fn with_credential_encryption(mut self, enabled: bool) -> Self {
+        self.encrypt_credentials = enabled;
+        self
+    }
+
+    /// Read credentials from the output of `command` instead of the
+    /// credentials file, or from the file again with `None`.
+    pub fn with_credentials_command(mut self, command: Option<String>) -> Self {
+        self.credentials_command = command;
+        *self.command_creds.get_mut().unwrap() = None;
+        self
+    }
+
     /// Returns the root folder of the runner installation.
     pub fn root_folder(&self) -> &PathBuf {
         &self.root_folder
@@ -248,9 +506,11 @@ impl ConfigurationStore {
         self.migrated_config_file_path.exists()
     }
 
-    /// Check whether credentials are stored on disk.
+    /// Check whether credentials are stored on disk or come from a command.
     pub fn has_credentials(&self) -> bool {
-        self.cred_file_path.exists() || self.migrated_cred_file_path.exists()
+        self.credentials_command.is_some()
+            || self.cred_file_path.exists()
+            || self.migrated_cred_file_path.exists()
     }
 
     /// Load and return runner settings. Cached after first load.
@@ -292,15 +552,24 @@ impl ConfigurationStore {
     }
 
     /// Load and return credentials. Cached after first load.
+    ///
+    /// With a credentials command configured, the command's output is used
+    /// and the credentials file is not read.
     pub fn get_credentials(&self) -> Result<CredentialData> {
+        if let Some(ref command) = self.credentials_command {
+            return self.get_command_credentials(command);
+        }
+
         let mut guard = self.creds.lock().unwrap();
         if let Some(ref creds) = *guard {
             return Ok(creds.clone());
         }
 
-        let json = fs::read_to_string(&self.cred_file_path)
-            .with_context(|| format!("Failed to read credentials from {:?}", self.cred_file_path))?;
+        let contents = fs::read_to_string(&self.cred_file_path).with_context(|| {
+            format!("Failed to read credentials from {:?}", self.cred_file_path)
+        })?;
 
+        let json = self.decrypt_credential_file(&contents)?;
         let creds: CredentialData = serde_json::from_str(&json)
             .with_context(|| "Failed to deserialize credential data")?;
 
@@ -308,6 +577,22 @@ impl ConfigurationStore {
         Ok(creds)
     }
 
+    /// Return the credentials command's output, running it again once the
+    /// cached output is about to expire.
+    fn get_command_credentials(&self, command: &str) -> Result<CredentialData> {
+        if let Some(ref cached) = *self.command_creds.lock().unwrap() {
+            if !cached.is_expired(Utc::now()) {
+                return Ok(cached.credential.clone());
+            }
+        }
+
+        // The lock is not held while the command runs
+        let fetched = run_credentials_command(command, CREDENTIALS_COMMAND_TIMEOUT)?;
+        let credential = fetched.credential.clone();
+        *self.command_creds.lock().unwrap() = Some(fetched);
+        Ok(credential)
+    }
+
     /// Load migrated credentials.
     pub fn get_migrated_credentials(&self) -> Result<CredentialData> {
         let mut guard = self.migrated_creds.lock().unwrap();
@@ -319,13 +604,14 @@ impl ConfigurationStore {
             anyhow::bail!("Migrated credentials file does not exist");
         }
 
-        let json = fs::read_to_string(&self.migrated_cred_file_path).with_context(|| {
+        let contents = fs::read_to_string(&self.migrated_cred_file_path).with_context(|| {
             format!(
                 "Failed to read migrated credentials from {:?}",
                 self.migrated_cred_file_path
             )
         })?;
 
+        let json = self.decrypt_credential_file(&contents)?;
         let creds: CredentialData = serde_json::from_str(&json)
             .with_context(|| "Failed to deserialize migrated credential data")?;
 
@@ -335,13 +621,8 @@ impl ConfigurationStore {
 
     /// Save runner settings to disk.
     pub fn save_settings(&self, settings: &RunnerSettings) -> Result<()> {
-        // Delete existing file first (mirrors C# behavior for hidden files)
-        if self.config_file_path.exists() {
-            fs::remove_file(&self.config_file_path)?;
-        }
-
         let json = serde_json::to_string_pretty(settings)?;
-        fs::write(&self.config_file_path, &json)
+        write_atomically(&self.config_file_path, &json)
             .with_context(|| format!("Failed to write settings to {:?}", self.config_file_path))?;
 
         // Update cache
@@ -351,12 +632,8 @@ impl ConfigurationStore {
 
     /// Save migrated runner settings to disk.
     pub fn save_migrated_settings(&self, settings: &RunnerSettings) -> Result<()> {
-        if self.migrated_config_file_path.exists() {
-            fs::remove_file(&self.migrated_config_file_path)?;
-        }
-
         let json = serde_json::to_string_pretty(settings)?;
-        fs::write(&self.migrated_config_file_path, &json).with_context(|| {
+        write_atomically(&self.migrated_config_file_path, &json).with_context(|| {
             format!(
                 "Failed to write migrated settings to {:?}",
                 self.migrated_config_file_path
@@ -369,17 +646,9 @@ impl ConfigurationStore {
 
     /// Save credentials to disk.
     pub fn save_credential(&self, credential: &CredentialData) -> Result<()> {
-        if self.cred_file_path.exists() {
-            fs::remove_file(&self.cred_file_path)?;
-        }
-
-        let json = serde_json::to_string_pretty(credential)?;
-        fs::write(&self.cred_file_path, &json).with_context(|| {
-            format!(
-                "Failed to write credentials to {:?}",
-                self.cred_file_path
-            )
-        })?;
+        let json = self.encrypt_credential_file(credential)?;
+        write_atomically(&self.cred_file_path, &json)
+            .with_context(|| format!("Failed to write credentials to {:?}", self.cred_file_path))?;
 
         *self.creds.lock().unwrap() = Some(credential.clone());
         Ok(())
@@ -387,12 +656,8 @@ impl ConfigurationStore {
 
     /// Save migrated credentials to disk.
     pub fn save_migrated_credential(&self, credential: &CredentialData) -> Result<()> {
-        if self.migrated_cred_file_path.exists() {
-            fs::remove_file(&self.migrated_cred_file_path)?;
-        }
-
-        let json = serde_json::to_string_pretty(credential)?;
-        fs::write(&self.migrated_cred_file_path, &json).with_context(|| {
+        let json = self.encrypt_credential_file(credential)?;
+        write_atomically(&self.migrated_cred_file_path, &json).with_context(|| {
             format!(
                 "Failed to write migrated credentials to {:?}",
                 self.migrated_cred_file_path
@@ -403,12 +668,131 @@ impl ConfigurationStore {
         Ok(())
     }
 
+    /// Write `credential` to a temporary file next to the credentials file,
+    /// encrypted under `rsa_key_pem` instead of the current RSA key when
+    /// encryption is enabled. The credentials file is unchanged until
+    /// `commit_staged_credential`, so a key rotation can prepare credentials
+    /// for its new key before the server has accepted it.
+    pub fn stage_credential(
+        &self,
+        credential: &CredentialData,
+        rsa_key_pem: &str,
+    ) -> Result<StagedCredential> {
+        let mut contents = serde_json::to_string_pretty(credential)?;
+        if self.encrypt_credentials {
+            let key = credential_encryption::load_private_key(rsa_key_pem)?;
+            contents = credential_encryption::encrypt(&contents, &key)?;
+        }
+        let tmp_path = write_temp_file(&self.cred_file_path, &contents)?;
+        Ok(StagedCredential {
+            tmp_path,
+            credential: credential.clone(),
+        })
+    }
+
+    /// Move credentials written by `stage_credential` over the credentials
+    /// file.
+    pub fn commit_staged_credential(&self, staged: StagedCredential) -> Result<()> {
+        replace_with_temp_file(&staged.tmp_path, &self.cred_file_path)
+            .with_context(|| format!("Failed to write credentials to {:?}", self.cred_file_path))?;
+
+        *self.creds.lock().unwrap() = Some(staged.credential);
+        Ok(())
+    }
+
+    /// Delete credentials written by `stage_credential`, leaving the
+    /// credentials file as it was.
+    pub fn discard_staged_credential(&self, staged: StagedCredential) {
+        let _ = fs::remove_file(&staged.tmp_path);
+    }
+
+    /// Whether the credentials file is stored encrypted.
+    pub fn has_encrypted_credentials(&self) -> bool {
+        fs::read_to_string(&self.cred_file_path)
+            .map(|contents| credential_encryption::is_encrypted(&contents))
+            .unwrap_or(false)
+    }
+
+    /// The contents to write to a credential file: the JSON form of
+    /// `credential`, encrypted when credential encryption is enabled.
+    fn encrypt_credential_file(&self, credential: &CredentialData) -> Result<String> {
+        let json = serde_json::to_string_pretty(credential)?;
+        if !self.encrypt_credentials {
+            return Ok(json);
+        }
+
+        credential_encryption::encrypt(&json, &self.load_rsa_key()?)
+    }
+
+    /// The credential JSON in a credential file, decrypting it if needed.
+    /// Plaintext files are returned as-is whether or not encryption is enabled.
+    fn decrypt_credential_file(&self, contents: &str) -> Result<String> {
+        if !credential_encryption::is_encrypted(contents) {
+            return Ok(contents.to_string());
+        }
+
+        credential_encryption::decrypt(contents, &self.load_rsa_key()?)
+    }
+
+    /// Load the runner's RSA key, which encrypts the credential files.
+    fn load_rsa_key(&self) -> Result<rsa::RsaPrivateKey> {
+        let pem = fs::read_to_string(&self.rsa_key_file_path).with_context(|| {
+            format!(
+                "Failed to read the RSA key for credential encryption from {:?}",
+                self.rsa_key_file_path
+            )
+        })?;
+        credential_encryption::load_private_key(&pem)
+    }
+
+    /// Convert the C# runner's `.runner_migrated` and `.credentials_migrated`
+    /// into `.runner` and `.credentials` when only the former exist.
+    ///
+    /// Each file is migrated independently and existing files are never
+    /// overwritten. Returns whether anything was written.
+    pub fn migrate_legacy_config(&self) -> Result<bool> {
+        let mut migrated = false;
+
+        if !self.config_file_path.exists() && self.migrated_config_file_path.exists() {
+            let json = fs::read_to_string(&self.migrated_config_file_path).with_context(|| {
+                format!(
+                    "Failed to read legacy settings from {:?}",
+                    self.migrated_config_file_path
+                )
+            })?;
+            let settings = parse_legacy_settings(&json)?;
+            write_atomically(
+                &self.config_file_path,
+                &serde_json::to_string_pretty(&settings)?,
+            )?;
+            *self.settings.lock().unwrap() = Some(settings);
+            migrated = true;
+        }
+
+        if !self.cred_file_path.exists() && self.migrated_cred_file_path.exists() {
+            let contents =
+                fs::read_to_string(&self.migrated_cred_file_path).with_context(|| {
+                    format!(
+                        "Failed to read legacy credentials from {:?}",
+                        self.migrated_cred_file_path
+                    )
+                })?;
+            let creds = parse_legacy_credentials(&self.decrypt_credential_file(&contents)?)?;
+            write_atomically(&self.cred_file_path, &self.encrypt_credential_file(&creds)?)?;
+            *self.creds.lock().unwrap() = Some(creds);
+            migrated = true;
+        }
+
+        Ok(migrated)
+    }
+
     /// Delete credentials (both primary and migrated).
     pub fn delete_credential(&self) {
         let _ = fs::remove_file(&self.cred_file_path);
         let _ = fs::remove_file(&self.migrated_cred_file_path);
         *self.creds.lock().unwrap() = None;
         *self.migrated_creds.lock().unwrap() = None;
+        *self.command_creds.lock().unwrap() = None;
     }
 
     /// Delete migrated credentials only.
@@ -430,3 +814,341 @@ impl ConfigurationStore {
         settings.is_hosted_server()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(root: &std::path::Path) -> ConfigurationStore {
+        let context = Arc::new(HostContext::new("Test"));
+        let mut store = ConfigurationStore::new(&context);
+        store.cred_file_path = root.join(".credentials");
+        store.migrated_cred_file_path = root.join(".credentials_migrated");
+        store.rsa_key_file_path = root.join(".credentials_rsaparams");
+        store.config_file_path = root.join(".runner");
+        store.migrated_config_file_path = root.join(".runner_migrated");
+        store
+    }
+
+    const LEGACY_RUNNER: &str = "\u{feff}{
+  \"agentId\": 42,
+  \"agentName\": \"build-01\",
+  \"poolId\": 1,
+  \"poolName\": \"Default\",
+  \"serverUrl\": \"https://pipelines.actions.githubusercontent.com/abc/\",
+  \"gitHubUrl\": \"https://github.com/owner/repo\",
+  \"workFolder\": \"_work\",
+  \"useV2Flow\": true,
+  \"serverUrlV2\": \"https://broker.actions.githubusercontent.com/\"
+}";
+
+    const LEGACY_CREDENTIALS: &str = r#"{
+  "scheme": "OAuth",
+  "data": {
+    "clientId": "a3f1c2d4",
+    "authorizationUrl": "https://pipelines.actions.githubusercontent.com/abc/_apis/oauth2/token",
+    "requireFipsCryptography": "True"
+  }
+}"#;
+
+    #[test]
+    fn test_parse_legacy_files() {
+        let settings = parse_legacy_settings(LEGACY_RUNNER).unwrap();
+        assert_eq!(settings.agent_id, 42);
+        assert_eq!(settings.agent_name, "build-01");
+        assert_eq!(settings.pool_id, 1);
+        assert_eq!(settings.pool_name, "Default");
+        assert_eq!(settings.git_hub_url, "https://github.com/owner/repo");
+        assert_eq!(settings.work_folder, "_work");
+        assert!(settings.use_v2_flow);
+        assert_eq!(
+            settings.server_url_v2.as_deref(),
+            Some("https://broker.actions.githubusercontent.com/")
+        );
+
+        let creds = parse_legacy_credentials(LEGACY_CREDENTIALS).unwrap();
+        assert_eq!(creds.scheme, "OAuth");
+        assert_eq!(creds.client_id.as_deref(), Some("a3f1c2d4"));
+        assert_eq!(
+            creds.authorization_url.as_deref(),
+            Some("https://pipelines.actions.githubusercontent.com/abc/_apis/oauth2/token")
+        );
+        assert_eq!(creds.get_data("requireFipsCryptography").unwrap(), "True");
+
+        assert!(parse_legacy_settings("[]").is_err());
+    }
+
+    #[test]
+    fn test_migrate_legacy_config_writes_current_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".runner_migrated"), LEGACY_RUNNER).unwrap();
+        std::fs::write(dir.path().join(".credentials_migrated"), LEGACY_CREDENTIALS).unwrap();
+
+        assert!(store(dir.path()).migrate_legacy_config().unwrap());
+
+        // A fresh store reads the converted files from disk
+        let store = store(dir.path());
+        let runner: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join(".runner")).unwrap())
+                .unwrap();
+        assert_eq!(runner["AgentName"], "build-01");
+        assert_eq!(store.get_settings().unwrap().agent_id, 42);
+        assert_eq!(
+            store.get_credentials().unwrap().client_id.as_deref(),
+            Some("a3f1c2d4")
+        );
+        assert!(!dir.path().join(".runner.tmp").exists());
+
+        // Nothing left to do the second time
+        assert!(!store.migrate_legacy_config().unwrap());
+    }
+
+    #[test]
+    fn test_migrate_legacy_config_keeps_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".runner_migrated"), LEGACY_RUNNER).unwrap();
+        std::fs::write(dir.path().join(".credentials_migrated"), LEGACY_CREDENTIALS).unwrap();
+        let current = store(dir.path());
+        current
+            .save_credential(&CredentialData::new("OAuthAccessToken"))
+            .unwrap();
+
+        assert!(store(dir.path()).migrate_legacy_config().unwrap());
+        let store = store(dir.path());
+        assert_eq!(store.get_credentials().unwrap().scheme, "OAuthAccessToken");
+        assert_eq!(store.get_settings().unwrap().agent_name, "build-01");
+    }
+
+    fn write_rsa_key(root: &std::path::Path) {
+        use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+        let key = rsa::RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let pem = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+        std::fs::write(root.join(".credentials_rsaparams"), pem.as_bytes()).unwrap();
+    }
+
+    fn oauth_credential() -> CredentialData {
+        let mut creds = CredentialData::new("OAuth");
+        creds.client_id = Some("client-id".to_string());
+        creds
+            .data
+            .insert("clientId".to_string(), "client-id".to_string());
+        creds
+    }
+
+    #[test]
+    fn test_encrypted_credentials_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        write_rsa_key(dir.path());
+        store(dir.path())
+            .with_credential_encryption(true)
+            .save_credential(&oauth_credential())
+            .unwrap();
+
+        let on_disk = std::fs::read_to_string(dir.path().join(".credentials")).unwrap();
+        assert!(credential_encryption::is_encrypted(&on_disk));
+        assert!(!on_disk.contains("client-id"), "{}", on_disk);
+
+        // Reading needs only the key, not the setting
+        let creds = store(dir.path()).get_credentials().unwrap();
+        assert_eq!(creds.scheme, "OAuth");
+        assert_eq!(creds.client_id.as_deref(), Some("client-id"));
+        assert_eq!(creds.get_data("clientId").unwrap(), "client-id");
+    }
+
+    #[test]
+    fn test_plaintext_credentials_are_still_read() {
+        let dir = tempfile::tempdir().unwrap();
+        write_rsa_key(dir.path());
+        store(dir.path())
+            .with_credential_encryption(false)
+            .save_credential(&oauth_credential())
+            .unwrap();
+        assert!(std::fs::read_to_string(dir.path().join(".credentials"))
+            .unwrap()
+            .contains("client-id"));
+
+        let encrypting = store(dir.path()).with_credential_encryption(true);
+        let creds = encrypting.get_credentials().unwrap();
+        assert_eq!(creds.client_id.as_deref(), Some("client-id"));
+
+        // Saving again migrates the file to the encrypted form
+        encrypting.save_credential(&creds).unwrap();
+        let on_disk = std::fs::read_to_string(dir.path().join(".credentials")).unwrap();
+        assert!(credential_encryption::is_encrypted(&on_disk));
+    }
+
+    #[test]
+    fn test_encryption_requires_rsa_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = store(dir.path())
+            .with_credential_encryption(true)
+            .save_credential(&oauth_credential())
+            .unwrap_err();
+        assert!(err.to_string().contains("RSA key"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_credentials_command_bypasses_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let on_disk = store(dir.path());
+        let mut file_creds = CredentialData::new("OAuth");
+        file_creds
+            .data
+            .insert("clientId".to_string(), "from-file".to_string());
+        on_disk.save_credential(&file_creds).unwrap();
+
+        let script = dir.path().join("creds.sh");
+        let counter = dir.path().join("runs");
+        std::fs::write(
+            &script,
+            format!(
+                "echo run >> '{}'\n\
+                 echo '{{\"Scheme\":\"OAuthAccessToken\",\"Data\":{{\"token\":\"from-command\"}}}}'\n",
+                counter.display()
+            ),
+        )
+        .unwrap();
+
+        let store =
+            store(dir.path()).with_credentials_command(Some(format!("sh '{}'", script.display())));
+        assert!(store.has_credentials());
+        for _ in 0..2 {
+            let creds = store.get_credentials().unwrap();
+            assert_eq!(creds.scheme, "OAuthAccessToken");
+            assert_eq!(
+                creds.get_data("token").map(String::as_str),
+                Some("from-command")
+            );
+            assert!(creds.get_data("clientId").is_none());
+        }
+        // Output without an expiry is cached
+        assert_eq!(
+            std::fs::read_to_string(&counter).unwrap().lines().count(),
+            1
+        );
+
+        // Without the command the file is used again
+        let store = store.with_credentials_command(None);
+        assert_eq!(
+            store
+                .get_credentials()
+                .unwrap()
+                .get_data("clientId")
+                .map(String::as_str),
+            Some("from-file")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_credentials_command_reruns_after_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("runs");
+        // Expires within the refresh skew, so every call runs the command
+        let expires_on = (Utc::now() + Duration::seconds(30)).to_rfc3339();
+        let command = format!(
+            "echo run >> '{}'; echo '{{\"Scheme\":\"OAuthAccessToken\",\"ExpiresOn\":\"{}\"}}'",
+            counter.display(),
+            expires_on
+        );
+        let store = store(dir.path()).with_credentials_command(Some(command));
+
+        store.get_credentials().unwrap();
+        store.get_credentials().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&counter).unwrap().lines().count(),
+            2
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failing_credentials_command_reports_stderr() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path()).with_credentials_command(Some(
+            "echo token-$((40 + 2)); echo 'vault is sealed' >&2; exit 3".to_string(),
+        ));
+
+        let err = format!("{:#}", store.get_credentials().unwrap_err());
+        assert!(err.contains("vault is sealed"), "{}", err);
+        assert!(!err.contains("token-42"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_credentials_command_is_killed_after_timeout() {
+        let started = std::time::Instant::now();
+        let err = run_credentials_command("exec sleep 30", std::time::Duration::from_millis(200))
+            .unwrap_err();
+
+        assert!(err.to_string().contains("did not finish"), "{}", err);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_command_credentials_expiry() {
+        let now = Utc::now();
+        let credentials = |expires_on| CommandCredentials {
+            credential: CredentialData::new("OAuth"),
+            expires_on,
+        };
+        assert!(!credentials(None).is_expired(now));
+        assert!(!credentials(Some(now + Duration::minutes(10))).is_expired(now));
+        assert!(credentials(Some(now + Duration::seconds(10))).is_expired(now));
+        assert!(credentials(Some(now - Duration::minutes(1))).is_expired(now));
+    }
+
+    fn settings_named(name: &str) -> RunnerSettings {
+        RunnerSettings {
+            agent_name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_crash_before_rename_keeps_old_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        store(dir.path())
+            .save_settings(&settings_named("old"))
+            .unwrap();
+
+        // The new contents reach the temp file, then the process dies
+        let path = dir.path().join(".runner");
+        let tmp_path = write_temp_file(
+            &path,
+            &serde_json::to_string_pretty(&settings_named("new")).unwrap(),
+        )
+        .unwrap();
+        assert!(tmp_path.exists());
+        assert_eq!(store(dir.path()).get_settings().unwrap().agent_name, "old");
+
+        // The next save replaces both the file and the stale temp file
+        store(dir.path())
+            .save_settings(&settings_named("newer"))
+            .unwrap();
+        assert_eq!(
+            store(dir.path()).get_settings().unwrap().agent_name,
+            "newer"
+        );
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn test_failed_rename_keeps_old_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path());
+        store
+            .save_credential(&CredentialData::new("OAuth"))
+            .unwrap();
+
+        let path = dir.path().join(".credentials");
+        let tmp_path = write_temp_file(&path, "{\"Scheme\": \"Broken\"").unwrap();
+        std::fs::remove_file(&tmp_path).unwrap();
+        assert!(replace_with_temp_file(&tmp_path, &path).is_err());
+
+        let on_disk: CredentialData =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(on_disk.scheme, "OAuth");
+    }
+}
@@ -41,6 +41,10 @@ pub enum WellKnownConfigFile {
     Options,
     SetupInfo,
     Telemetry,
+    Path,
+    Env,
+    InFlightJobs,
+    PendingCompletions,
 }
 
 impl fmt::Display for WellKnownConfigFile {
@@ -190,6 +194,10 @@ pub mod command_line {
         pub const PAT: &str = "pat";
         pub const WINDOWS_LOGON_PASSWORD: &str = "windowslogonpassword";
         pub const JIT_CONFIG: &str = "jitconfig";
+        pub const TIMEOUT: &str = "timeout";
+        pub const WORKER_PATH: &str = "worker-path";
+        pub const METRICS_FILE: &str = "metrics-file";
+        pub const MAX_BACKOFF: &str = "max-backoff";
 
         /// Returns the list of arguments that contain secret values.
         pub fn secrets() -> &'static [&'static str] {
@@ -201,6 +209,7 @@ pub mod command_line {
     pub mod commands {
         pub const CONFIGURE: &str = "configure";
         pub const REMOVE: &str = "remove";
+        pub const ROTATE_KEY: &str = "rotatekey";
         pub const RUN: &str = "run";
         pub const WARMUP: &str = "warmup";
     }
@@ -210,6 +219,7 @@ pub mod command_line {
         pub const CHECK: &str = "check";
         pub const COMMIT: &str = "commit";
         pub const EPHEMERAL: &str = "ephemeral";
+        pub const GENERATE_ENV_FILES: &str = "generateEnvFiles";
         pub const GENERATE_SERVICE_CONFIG: &str = "generateServiceConfig";
         pub const HELP: &str = "help";
         pub const LOCAL: &str = "local";
@@ -236,6 +246,7 @@ pub mod return_code {
     pub const RUN_ONCE_RUNNER_UPDATING: i32 = 4;
     pub const SESSION_CONFLICT: i32 = 5;
     pub const RUNNER_CONFIGURATION_REFRESHED: i32 = 6;
+    pub const RUNNER_REMOVED: i32 = 7;
 }
 
 // ---------------------------------------------------------------------------
@@ -262,6 +273,24 @@ pub mod features {
         "actions_set_orchestration_id_env_for_actions";
     pub const SEND_JOB_LEVEL_ANNOTATIONS: &str = "actions_send_job_level_annotations";
     pub const EMIT_COMPOSITE_MARKERS: &str = "actions_runner_emit_composite_markers";
+    pub const DISABLE_STEP_OUTPUT_COMMANDS: &str = "DistributedTask.DisableStepOutputCommands";
+
+    /// Every flag above, for loading them from a job message.
+    pub const ALL: &[&str] = &[
+        DISK_SPACE_WARNING,
+        LOG_TEMPLATE_ERRORS_AS_DEBUG_MESSAGES,
+        USE_CONTAINER_PATH_FOR_TEMPLATE,
+        ALLOW_RUNNER_CONTAINER_HOOKS,
+        ADD_CHECK_RUN_ID_TO_JOB_CONTEXT,
+        DISPLAY_HELPFUL_ACTIONS_DOWNLOAD_ERRORS,
+        SNAPSHOT_PREFLIGHT_HOSTED_RUNNER_CHECK,
+        SNAPSHOT_PREFLIGHT_IMAGE_GEN_POOL_CHECK,
+        COMPARE_WORKFLOW_PARSER,
+        SET_ORCHESTRATION_ID_ENV_FOR_ACTIONS,
+        SEND_JOB_LEVEL_ANNOTATIONS,
+        EMIT_COMPOSITE_MARKERS,
+        DISABLE_STEP_OUTPUT_COMMANDS,
+    ];
 }
 
 // ---------------------------------------------------------------------------
@@ -386,6 +415,7 @@ pub mod variables {
         pub const REQUIRE_JOB_CONTAINER: &str = "ACTIONS_RUNNER_REQUIRE_JOB_CONTAINER";
         pub const RUNNER_DEBUG: &str = "ACTIONS_RUNNER_DEBUG";
         pub const STEP_DEBUG: &str = "ACTIONS_STEP_DEBUG";
+        pub const WARN_ON_MUTABLE_ACTION_REFS: &str = "ACTIONS_RUNNER_WARN_MUTABLE_ACTION_REFS";
     }
 
     pub mod agent {
@@ -397,10 +427,19 @@ pub mod variables {
         pub const ACTION_ARCHIVE_CACHE_DIRECTORY: &str = "ACTIONS_RUNNER_ACTION_ARCHIVE_CACHE";
         pub const SYMLINK_CACHED_ACTIONS: &str = "ACTIONS_RUNNER_SYMLINK_CACHED_ACTIONS";
         pub const EMIT_COMPOSITE_MARKERS: &str = "ACTIONS_RUNNER_EMIT_COMPOSITE_MARKERS";
+        pub const JOB_MAX_TIMEOUT: &str = "RUNNER_JOB_MAX_TIMEOUT";
+        pub const WORKER_PATH: &str = "RUNNER_WORKER_PATH";
+        pub const METRICS_FILE: &str = "RUNNER_METRICS_FILE";
+        pub const MAX_BACKOFF: &str = "RUNNER_MAX_BACKOFF";
+        pub const CREDENTIALS_COMMAND: &str = "RUNNER_CREDENTIALS_COMMAND";
+        pub const ENCRYPT_CREDENTIALS: &str = "RUNNER_ENCRYPT_CREDENTIALS";
+        pub const OUTPUT_ENCODING: &str = "ACTIONS_RUNNER_OUTPUT_ENCODING";
+        pub const USE_PTY: &str = "ACTIONS_RUNNER_USE_PTY";
     }
 
     pub mod system {
         pub const ACCESS_TOKEN: &str = "system.accessToken";
+        pub const GITHUB_TOKEN: &str = "system.github.token";
         pub const CULTURE: &str = "system.culture";
         pub const PHASE_DISPLAY_NAME: &str = "system.phaseDisplayName";
         pub const JOB_REQUEST_TYPE: &str = "system.jobRequestType";
@@ -0,0 +1,148 @@
+// At-rest encryption for the `.credentials` file.
+//
+// The credential JSON is sealed with a fresh AES-256-GCM key, and that key is
+// wrapped with RSA-OAEP (SHA-256) under the runner's RSA key from
+// `.credentials_rsaparams`. The file then holds a small JSON envelope instead
+// of the plaintext credential, so it is only readable together with the key.
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::{Oaep, RsaPrivateKey, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+
+/// Identifies the envelope format written by `encrypt`.
+pub const ALGORITHM: &str = "RSA-OAEP-SHA256+AES-256-GCM";
+
+/// Length of the random AES-256 content key.
+const CONTENT_KEY_LEN: usize = 32;
+
+/// The on-disk form of an encrypted credential file.
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedEnvelope {
+    #[serde(rename = "Algorithm")]
+    algorithm: String,
+    /// The AES key, wrapped with the RSA public key.
+    #[serde(rename = "Key")]
+    key: String,
+    #[serde(rename = "Nonce")]
+    nonce: String,
+    /// The AES-GCM ciphertext of the credential JSON, tag included.
+    #[serde(rename = "Data")]
+    data: String,
+}
+
+/// Parse the runner's RSA private key from its PKCS#8 PEM form.
+pub fn load_private_key(pem: &str) -> Result<RsaPrivateKey> {
+    RsaPrivateKey::from_pkcs8_pem(pem).context("Failed to parse RSA key")
+}
+
+/// Whether `contents` is an encrypted envelope rather than plaintext JSON.
+pub fn is_encrypted(contents: &str) -> bool {
+    serde_json::from_str::<EncryptedEnvelope>(contents).is_ok()
+}
+
+/// Encrypt `plaintext` for the holder of `key`, returning the envelope JSON.
+pub fn encrypt(plaintext: &str, key: &RsaPrivateKey) -> Result<String> {
+    let random = SystemRandom::new();
+    let mut content_key = [0u8; CONTENT_KEY_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    random
+        .fill(&mut content_key)
+        .and_then(|_| random.fill(&mut nonce))
+        .map_err(|_| anyhow::anyhow!("Failed to generate a credential encryption key"))?;
+
+    let sealing_key = LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, &content_key)
+            .map_err(|_| anyhow::anyhow!("Invalid credential encryption key"))?,
+    );
+    let mut data = plaintext.as_bytes().to_vec();
+    sealing_key
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt credentials"))?;
+
+    let wrapped_key = RsaPublicKey::from(key)
+        .encrypt(
+            &mut rand::thread_rng(),
+            Oaep::new::<sha2::Sha256>(),
+            &content_key,
+        )
+        .context("Failed to wrap the credential encryption key")?;
+
+    let envelope = EncryptedEnvelope {
+        algorithm: ALGORITHM.to_string(),
+        key: BASE64.encode(wrapped_key),
+        nonce: BASE64.encode(nonce),
+        data: BASE64.encode(data),
+    };
+    Ok(serde_json::to_string_pretty(&envelope)?)
+}
+
+/// Decrypt an envelope written by `encrypt`, returning the plaintext JSON.
+pub fn decrypt(contents: &str, key: &RsaPrivateKey) -> Result<String> {
+    let envelope: EncryptedEnvelope =
+        serde_json::from_str(contents).context("Failed to parse encrypted credentials")?;
+    if envelope.algorithm != ALGORITHM {
+        anyhow::bail!(
+            "Unsupported credential encryption algorithm '{}'",
+            envelope.algorithm
+        );
+    }
+
+    let wrapped_key = BASE64
+        .decode(&envelope.key)
+        .context("Invalid encrypted credential key")?;
+    let nonce: [u8; NONCE_LEN] = BASE64
+        .decode(&envelope.nonce)
+        .ok()
+        .and_then(|nonce| nonce.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid encrypted credential nonce"))?;
+    let mut data = BASE64
+        .decode(&envelope.data)
+        .context("Invalid encrypted credential data")?;
+
+    let content_key = key
+        .decrypt(Oaep::new::<sha2::Sha256>(), &wrapped_key)
+        .context("Failed to unwrap the credential encryption key; was the RSA key replaced?")?;
+    let opening_key = LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, &content_key)
+            .map_err(|_| anyhow::anyhow!("Invalid credential encryption key"))?,
+    );
+    let plaintext = opening_key
+        .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt credentials"))?;
+
+    String::from_utf8(plaintext.to_vec()).context("Decrypted credentials are not valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> RsaPrivateKey {
+        RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let key = key();
+        let plaintext = r#"{"Scheme":"OAuth","Data":{"clientId":"abc"}}"#;
+
+        let sealed = encrypt(plaintext, &key).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("clientId"), "{}", sealed);
+        assert_eq!(decrypt(&sealed, &key).unwrap(), plaintext);
+
+        assert!(!is_encrypted(plaintext));
+    }
+
+    #[test]
+    fn test_decrypt_fails_with_other_key() {
+        let sealed = encrypt("{}", &key()).unwrap();
+        let err = decrypt(&sealed, &key()).unwrap_err();
+        assert!(err.to_string().contains("Failed to unwrap"), "{}", err);
+    }
+}
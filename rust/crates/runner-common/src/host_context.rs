@@ -202,20 +202,7 @@ impl HostContext {
                     .join(constants::path::ACTIONS_DIRECTORY)
             }
 
-            WellKnownDirectory::Tools => {
-                // Check various environment variables for the tools directory
-                env::var("RUNNER_TOOL_CACHE")
-                    .ok()
-                    .or_else(|| env::var("RUNNER_TOOLSDIRECTORY").ok())
-                    .or_else(|| env::var("AGENT_TOOLSDIRECTORY").ok())
-                    .or_else(|| env::var(constants::variables::agent::TOOLS_DIRECTORY).ok())
-                    .filter(|s| !s.is_empty())
-                    .map(PathBuf::from)
-                    .unwrap_or_else(|| {
-                        self.get_directory(WellKnownDirectory::Work)
-                            .join(constants::path::TOOL_DIRECTORY)
-                    })
-            }
+            WellKnownDirectory::Tools => self.resolve_tools_directory(|name| env::var(name).ok()),
 
             WellKnownDirectory::Update => {
                 self.get_directory(WellKnownDirectory::Work)
@@ -233,6 +220,33 @@ impl HostContext {
         path
     }
 
+    /// Resolve the tool cache directory. `RUNNER_TOOL_CACHE` (and the legacy
+    /// tools directory variables) override the default of `_work/_tool`
+    /// under the runner root.
+    fn resolve_tools_directory(&self, lookup: impl Fn(&str) -> Option<String>) -> PathBuf {
+        [
+            "RUNNER_TOOL_CACHE",
+            "RUNNER_TOOLSDIRECTORY",
+            "AGENT_TOOLSDIRECTORY",
+            constants::variables::agent::TOOLS_DIRECTORY,
+        ]
+        .iter()
+        .filter_map(|name| lookup(name))
+        .find(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            self.get_directory(WellKnownDirectory::Work)
+                .join(constants::path::TOOL_DIRECTORY)
+        })
+    }
+
+    /// Resolve the tool cache directory and create it if it does not exist.
+    pub fn ensure_tool_cache_directory(&self) -> std::io::Result<PathBuf> {
+        let path = self.get_directory(WellKnownDirectory::Tools);
+        std::fs::create_dir_all(&path)?;
+        Ok(path)
+    }
+
     /// Set the work folder path explicitly (used after loading settings).
     /// This stores a "Work" directory override in the service instances map.
     pub fn set_work_folder(&self, work_folder: &str) {
@@ -284,6 +298,10 @@ impl HostContext {
             WellKnownConfigFile::Certificates => root.join(".certificates"),
             WellKnownConfigFile::Options => root.join(".options"),
             WellKnownConfigFile::SetupInfo => root.join(".setup_info"),
+            WellKnownConfigFile::Path => root.join(".path"),
+            WellKnownConfigFile::Env => root.join(".env"),
+            WellKnownConfigFile::InFlightJobs => root.join(".inflight_jobs"),
+            WellKnownConfigFile::PendingCompletions => root.join(".pending_completions"),
             WellKnownConfigFile::Telemetry => {
                 self.get_directory(WellKnownDirectory::Diag).join(".telemetry")
             }
@@ -321,6 +339,39 @@ impl HostContext {
         self.runner_shutdown_token.cancel();
     }
 
+    // -----------------------------------------------------------------------
+    // Host resources
+    // -----------------------------------------------------------------------
+
+    /// Detect the CPU and memory resources available to this process.
+    pub fn host_resources(&self) -> HostResources {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+
+        HostResources {
+            cpu_count: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            total_memory_bytes: system.total_memory(),
+            available_memory_bytes: system.available_memory(),
+        }
+    }
+
+    /// Free space, in bytes, on the disk that holds `path`.
+    ///
+    /// The disk is the one with the longest mount point containing `path`.
+    /// Returns `None` when no mounted disk could be matched.
+    pub fn available_disk_space(&self, path: &Path) -> Option<u64> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let disks = sysinfo::Disks::new_with_refreshed_list();
+        disks
+            .list()
+            .iter()
+            .filter(|disk| path.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().as_os_str().len())
+            .map(|disk| disk.available_space())
+    }
+
     // -----------------------------------------------------------------------
     // Misc
     // -----------------------------------------------------------------------
@@ -378,5 +429,99 @@ impl HostContext {
     }
 }
 
+/// CPU and memory resources detected on the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostResources {
+    /// Number of logical CPUs this process may run on.
+    pub cpu_count: usize,
+    /// Total physical memory, in bytes.
+    pub total_memory_bytes: u64,
+    /// Memory available for new allocations, in bytes.
+    pub available_memory_bytes: u64,
+}
+
+impl HostResources {
+    /// Total physical memory, in megabytes.
+    pub fn total_memory_mb(&self) -> u64 {
+        self.total_memory_bytes / (1024 * 1024)
+    }
+
+    /// Available memory, in megabytes.
+    pub fn available_memory_mb(&self) -> u64 {
+        self.available_memory_bytes / (1024 * 1024)
+    }
+}
+
 /// Internal marker type for storing the work folder override.
 struct WorkFolderOverride(PathBuf);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_cache_override_is_respected() {
+        let host = HostContext::new("Test");
+        host.set_root_override(PathBuf::from("/runner"));
+
+        let tools = host.resolve_tools_directory(|name| {
+            (name == "RUNNER_TOOL_CACHE").then(|| "/opt/hostedtoolcache".to_string())
+        });
+        assert_eq!(tools, PathBuf::from("/opt/hostedtoolcache"));
+
+        // RUNNER_TOOL_CACHE wins over the legacy variables; empty values are ignored
+        let tools = host.resolve_tools_directory(|name| match name {
+            "RUNNER_TOOL_CACHE" => Some(String::new()),
+            "AGENT_TOOLSDIRECTORY" => Some("/legacy/tools".to_string()),
+            _ => None,
+        });
+        assert_eq!(tools, PathBuf::from("/legacy/tools"));
+    }
+
+    #[test]
+    fn test_tool_cache_default_is_under_runner_root() {
+        let host = HostContext::new("Test");
+        host.set_root_override(PathBuf::from("/runner"));
+
+        let tools = host.resolve_tools_directory(|_| None);
+        assert_eq!(
+            tools,
+            PathBuf::from("/runner")
+                .join(constants::path::WORK_DIRECTORY)
+                .join(constants::path::TOOL_DIRECTORY)
+        );
+    }
+
+    #[test]
+    fn test_ensure_tool_cache_directory_creates_it() {
+        let root = tempfile::tempdir().unwrap();
+        let host = HostContext::new("Test");
+        host.set_root_override(root.path().to_path_buf());
+
+        let tools = host.ensure_tool_cache_directory().unwrap();
+        assert!(tools.is_dir());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    #[test]
+    fn test_host_resources_are_detected() {
+        let host = HostContext::new("Test");
+        let resources = host.host_resources();
+
+        assert!(resources.cpu_count > 0);
+        assert!(resources.total_memory_bytes > 0);
+        assert!(resources.available_memory_bytes > 0);
+        assert!(resources.available_memory_bytes <= resources.total_memory_bytes);
+        assert!(resources.total_memory_mb() > 0);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    #[test]
+    fn test_available_disk_space_for_temp_dir() {
+        let host = HostContext::new("Test");
+        let dir = tempfile::tempdir().unwrap();
+
+        let free = host.available_disk_space(dir.path());
+        assert!(free.is_some_and(|bytes| bytes > 0), "{:?}", free);
+    }
+}
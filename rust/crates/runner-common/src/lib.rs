@@ -6,13 +6,16 @@ pub mod action_result;
 pub mod config_store;
 pub mod constants;
 pub mod credential_data;
+pub mod credential_encryption;
 pub mod exceptions;
 pub mod host_context;
 pub mod http_client_factory;
 pub mod job_notification;
 pub mod logging;
+pub mod pending_completion;
 pub mod process_channel;
 pub mod process_invoker;
+pub mod run_server;
 pub mod runner_service;
 pub mod secret_masker;
 pub mod terminal;
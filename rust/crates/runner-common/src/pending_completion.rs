@@ -0,0 +1,111 @@
+// Job completions the worker could not deliver to the Run Service.
+//
+// When `completejob` keeps failing, the worker records the request in
+// `.pending_completions` in the runner root. The listener replays them on its
+// next startup with its own access token, so the server does not keep the job
+// "running" (and keep sending cancellations) forever. An entry is only removed
+// once the server has accepted it.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config_store::write_atomically;
+
+/// A `completejob` request that still has to reach the Run Service.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingCompletion {
+    /// Base URL of the Run Service the job came from.
+    #[serde(rename = "runServiceUrl")]
+    pub run_service_url: String,
+    /// The `completejob` body.
+    pub payload: serde_json::Value,
+}
+
+/// The persisted list of pending completions.
+pub struct PendingCompletionStore {
+    path: PathBuf,
+}
+
+impl PendingCompletionStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Append `completion` to the pending list.
+    pub fn add(&self, completion: PendingCompletion) -> Result<()> {
+        let mut completions = self.list();
+        completions.push(completion);
+        self.write(&completions)
+    }
+
+    /// Remove `completion` once it has been delivered.
+    pub fn remove(&self, completion: &PendingCompletion) -> Result<()> {
+        let mut completions = self.list();
+        let Some(index) = completions.iter().position(|c| c == completion) else {
+            return Ok(());
+        };
+        completions.remove(index);
+        if completions.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(e).with_context(|| format!("Failed to delete {:?}", self.path))
+                }
+                _ => Ok(()),
+            };
+        }
+        self.write(&completions)
+    }
+
+    /// Every pending completion. A missing or unreadable file means none.
+    pub fn list(&self) -> Vec<PendingCompletion> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn write(&self, completions: &[PendingCompletion]) -> Result<()> {
+        let json = serde_json::to_string_pretty(completions)?;
+        write_atomically(&self.path, &json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completion(job: &str) -> PendingCompletion {
+        PendingCompletion {
+            run_service_url: "https://run.example.com".to_string(),
+            payload: serde_json::json!({ "jobId": job }),
+        }
+    }
+
+    #[test]
+    fn test_entries_stay_until_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".pending_completions");
+        let store = PendingCompletionStore::new(path.clone());
+        assert!(store.list().is_empty());
+
+        for job in ["job-1", "job-2"] {
+            store.add(completion(job)).unwrap();
+        }
+        assert!(!dir.path().join(".pending_completions.tmp").exists());
+
+        // Listing does not consume: an undelivered entry is still there
+        let listed = store.list();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1].payload["jobId"], "job-2");
+        assert_eq!(store.list(), listed);
+
+        store.remove(&completion("job-1")).unwrap();
+        assert_eq!(store.list(), [completion("job-2")]);
+
+        store.remove(&completion("job-2")).unwrap();
+        assert!(!path.exists());
+        assert!(store.list().is_empty());
+        store.remove(&completion("job-2")).unwrap();
+    }
+}
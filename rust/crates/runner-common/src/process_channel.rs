@@ -2,9 +2,12 @@
 // Provides IPC between the listener and worker processes using pipes or streams.
 
 use anyhow::{Context, Result};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{UnixListener, UnixStream};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot};
 
 /// Message types for listener ↔ worker communication.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -15,6 +18,8 @@ pub enum MessageType {
     CancelRequest = 2,
     RunnerShutdown = 3,
     OperatingSystemShutdown = 4,
+    /// Sent by the worker when the job has finished.
+    JobCompleted = 5,
 }
 
 impl MessageType {
@@ -25,6 +30,7 @@ impl MessageType {
             2 => MessageType::CancelRequest,
             3 => MessageType::RunnerShutdown,
             4 => MessageType::OperatingSystemShutdown,
+            5 => MessageType::JobCompleted,
             _ => MessageType::NotInitialized,
         }
     }
@@ -38,6 +44,7 @@ impl std::fmt::Display for MessageType {
             MessageType::CancelRequest => write!(f, "CancelRequest"),
             MessageType::RunnerShutdown => write!(f, "RunnerShutdown"),
             MessageType::OperatingSystemShutdown => write!(f, "OperatingSystemShutdown"),
+            MessageType::JobCompleted => write!(f, "JobCompleted"),
         }
     }
 }
@@ -58,20 +65,217 @@ impl WorkerMessage {
     }
 }
 
+/// Magic bytes at the start of every frame.
+pub const FRAME_MAGIC: [u8; 4] = *b"GHRM";
+
+/// Size of the fixed frame header (magic, type, length, CRC32).
+pub const FRAME_HEADER_LEN: usize = 16;
+
+/// Largest body accepted from the wire, to avoid allocating for a garbage length.
+pub const MAX_BODY_LEN: usize = 256 * 1024 * 1024;
+
+/// Encode a message into a single frame.
+pub fn encode_frame(message_type: MessageType, body: &[u8]) -> Result<Vec<u8>> {
+    if body.len() > MAX_BODY_LEN {
+        anyhow::bail!(
+            "IPC message body is {} bytes, exceeding the {} byte limit",
+            body.len(),
+            MAX_BODY_LEN
+        );
+    }
+
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + body.len());
+    frame.extend_from_slice(&FRAME_MAGIC);
+    frame.extend_from_slice(&(message_type as i32).to_le_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&crc32fast::hash(body).to_le_bytes());
+    frame.extend_from_slice(body);
+    Ok(frame)
+}
+
+/// Write a single frame to a stream.
+pub async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message_type: MessageType,
+    body: &str,
+) -> Result<()> {
+    let frame = encode_frame(message_type, body.as_bytes())?;
+    writer.write_all(&frame).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read a single frame from a stream, validating the magic and checksum.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<WorkerMessage> {
+    let mut header = [0u8; FRAME_HEADER_LEN];
+    reader.read_exact(&mut header).await?;
+
+    if header[0..4] != FRAME_MAGIC {
+        anyhow::bail!(
+            "IPC frame has bad magic {:02x?} (expected {:02x?})",
+            &header[0..4],
+            FRAME_MAGIC
+        );
+    }
+
+    let message_type = MessageType::from_i32(i32::from_le_bytes(header[4..8].try_into()?));
+    let body_len = u32::from_le_bytes(header[8..12].try_into()?) as usize;
+    let expected_crc = u32::from_le_bytes(header[12..16].try_into()?);
+
+    if body_len > MAX_BODY_LEN {
+        anyhow::bail!(
+            "IPC frame body length {} exceeds the {} byte limit",
+            body_len,
+            MAX_BODY_LEN
+        );
+    }
+
+    let mut body_buf = vec![0u8; body_len];
+    reader.read_exact(&mut body_buf).await?;
+
+    let actual_crc = crc32fast::hash(&body_buf);
+    if actual_crc != expected_crc {
+        anyhow::bail!(
+            "IPC frame checksum mismatch: expected {:08x}, got {:08x}",
+            expected_crc,
+            actual_crc
+        );
+    }
+
+    let body = String::from_utf8(body_buf).context("IPC message body is not valid UTF-8")?;
+
+    Ok(WorkerMessage::new(message_type, body))
+}
+
+/// Number of frames that may be queued for the writer task before senders
+/// wait for the peer to catch up.
+pub const SEND_QUEUE_CAPACITY: usize = 16;
+
+/// Work item for the background writer task.
+enum WriterCommand {
+    /// A frame to write, and where to report whether the write succeeded.
+    Frame(Vec<u8>, oneshot::Sender<std::io::Result<()>>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Sending half of a connected channel: a bounded queue drained by a
+/// background task that owns the socket's write half.
+struct FrameSender {
+    queue: mpsc::Sender<WriterCommand>,
+    /// The write error that stopped the writer task, if any.
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl FrameSender {
+    fn spawn(writer: OwnedWriteHalf) -> Self {
+        let (queue, commands) = mpsc::channel(SEND_QUEUE_CAPACITY);
+        let error = Arc::new(Mutex::new(None));
+        tokio::spawn(run_writer(writer, commands, Arc::clone(&error)));
+        Self { queue, error }
+    }
+
+    /// The error to report once the writer task has stopped.
+    fn closed_error(&self) -> anyhow::Error {
+        match self.error.lock().unwrap().as_ref() {
+            Some(e) => anyhow::anyhow!("IPC channel write failed: {}", e),
+            None => anyhow::anyhow!("IPC channel writer has stopped"),
+        }
+    }
+}
+
+/// Write queued frames to the socket in order until the queue closes or a
+/// write fails.
+async fn run_writer(
+    mut writer: OwnedWriteHalf,
+    mut commands: mpsc::Receiver<WriterCommand>,
+    error: Arc<Mutex<Option<String>>>,
+) {
+    while let Some(command) = commands.recv().await {
+        match command {
+            WriterCommand::Frame(frame, done) => {
+                let written = async {
+                    writer.write_all(&frame).await?;
+                    writer.flush().await
+                }
+                .await;
+                if let Err(e) = written {
+                    *error.lock().unwrap() = Some(e.to_string());
+                    let _ = done.send(Err(e));
+                    return;
+                }
+                let _ = done.send(Ok(()));
+            }
+            WriterCommand::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+/// Number of received frames that may be buffered before the reader task
+/// stops reading from the socket.
+pub const RECEIVE_QUEUE_CAPACITY: usize = 16;
+
+/// Receiving half of a connected channel: a background task that owns the
+/// socket's read half reads whole frames into a bounded queue.
+struct FrameReceiver {
+    frames: mpsc::Receiver<Result<WorkerMessage>>,
+}
+
+impl FrameReceiver {
+    fn spawn(reader: OwnedReadHalf) -> Self {
+        let (frames, queue) = mpsc::channel(RECEIVE_QUEUE_CAPACITY);
+        tokio::spawn(run_reader(reader, frames));
+        Self { frames: queue }
+    }
+}
+
+/// Read frames from the socket into the queue until a read fails or the
+/// receiving half is dropped.
+async fn run_reader(mut reader: OwnedReadHalf, frames: mpsc::Sender<Result<WorkerMessage>>) {
+    loop {
+        let frame = tokio::select! {
+            frame = read_frame(&mut reader) => frame,
+            _ = frames.closed() => return,
+        };
+        let failed = frame.is_err();
+        if frames.send(frame).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
 /// IPC channel between the listener and worker processes.
 ///
 /// On Unix this uses a Unix domain socket pair. The listener creates a socket
 /// at a temp path and the worker connects to it.
 ///
-/// The wire protocol is simple:
+/// Each message is sent as one frame:
+/// - 4 bytes: magic `GHRM`
 /// - 4 bytes: message type as little-endian i32
 /// - 4 bytes: body length as little-endian u32
+/// - 4 bytes: CRC32 of the body as little-endian u32
 /// - N bytes: body as UTF-8 string
+///
+/// Frames with a bad magic or checksum are rejected rather than risking a
+/// desynchronised stream.
+///
+/// Outgoing frames are written by a background task fed through a queue of
+/// at most `SEND_QUEUE_CAPACITY` frames. `send_async` returns once its frame
+/// has been written, reporting any write error, so a peer that reads slowly
+/// holds up the sender rather than letting frames pile up.
+///
+/// Sending and receiving are handled by separate background tasks, so a large
+/// message in either direction never stalls the other. Both take `&self`, so
+/// one channel can send and receive at once, e.g. in two `select!` branches.
 pub struct ProcessChannel {
     /// For the server side (listener), the socket path.
     socket_path: Option<PathBuf>,
-    /// The connected stream for reading/writing messages.
-    stream: Option<UnixStream>,
+    /// Frames read from the connected stream. Locked while receiving, so
+    /// the channel can receive through `&self` while it sends.
+    receiver: Option<tokio::sync::Mutex<FrameReceiver>>,
+    /// Send queue feeding the write half of the connected stream.
+    sender: Option<FrameSender>,
     /// The listener (only set on the server side before accepting).
     listener: Option<UnixListener>,
 }
@@ -81,11 +285,20 @@ impl ProcessChannel {
     pub fn new() -> Self {
         Self {
             socket_path: None,
-            stream: None,
+            receiver: None,
+            sender: None,
             listener: None,
         }
     }
 
+    /// Wrap an already connected stream, such as one returned by
+    /// [`ProcessChannel::accept_second`].
+    pub fn from_stream(stream: UnixStream) -> Self {
+        let mut channel = Self::new();
+        channel.connect_stream(stream);
+        channel
+    }
+
     /// Start the server side (used by the listener process).
     ///
     /// Creates a Unix domain socket at the given path. Returns the socket path
@@ -119,7 +332,7 @@ impl ProcessChannel {
             .await
             .context("Failed to accept connection on IPC socket")?;
 
-        self.stream = Some(stream);
+        self.connect_stream(stream);
         Ok(())
     }
 
@@ -147,63 +360,71 @@ impl ProcessChannel {
             .await
             .with_context(|| format!("Failed to connect to IPC socket at {}", socket_path))?;
 
-        self.stream = Some(stream);
+        self.connect_stream(stream);
         Ok(())
     }
 
-    /// Send a message through the channel.
-    pub async fn send_async(
-        &mut self,
-        message_type: MessageType,
-        body: &str,
-    ) -> Result<()> {
-        let stream = self
-            .stream
-            .as_mut()
-            .ok_or_else(|| anyhow::anyhow!("Channel not connected"))?;
-
-        // Write message type as i32 LE
-        stream
-            .write_all(&(message_type as i32).to_le_bytes())
-            .await?;
-
-        // Write body length as u32 LE
-        let body_bytes = body.as_bytes();
-        stream
-            .write_all(&(body_bytes.len() as u32).to_le_bytes())
-            .await?;
+    /// Split a connected stream and start the reader and writer tasks for it.
+    fn connect_stream(&mut self, stream: UnixStream) {
+        let (reader, writer) = stream.into_split();
+        self.receiver = Some(tokio::sync::Mutex::new(FrameReceiver::spawn(reader)));
+        self.sender = Some(FrameSender::spawn(writer));
+    }
 
-        // Write body
-        stream.write_all(body_bytes).await?;
-        stream.flush().await?;
+    /// Send a message through the channel, returning once it has been
+    /// written to the socket.
+    ///
+    /// The frame is written by the writer task, so a large body does not hold
+    /// up receiving while it is written.
+    pub async fn send_async(&self, message_type: MessageType, body: &str) -> Result<()> {
+        let sender = self
+            .sender
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Channel not connected"))?;
+        let frame = encode_frame(message_type, body.as_bytes())?;
+        let (done, written) = oneshot::channel();
+        if sender
+            .queue
+            .send(WriterCommand::Frame(frame, done))
+            .await
+            .is_err()
+        {
+            return Err(sender.closed_error());
+        }
+        match written.await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(anyhow::Error::new(e).context("IPC channel write failed")),
+            Err(_) => Err(sender.closed_error()),
+        }
+    }
 
+    /// Wait until every message queued so far, including any whose
+    /// `send_async` was abandoned, has been written to the socket.
+    pub async fn flush(&self) -> Result<()> {
+        let sender = self
+            .sender
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Channel not connected"))?;
+        let (done, written) = oneshot::channel();
+        if sender.queue.send(WriterCommand::Flush(done)).await.is_err() || written.await.is_err() {
+            return Err(sender.closed_error());
+        }
         Ok(())
     }
 
-    /// Receive a message from the channel.
-    pub async fn receive_async(&mut self) -> Result<WorkerMessage> {
-        let stream = self
-            .stream
-            .as_mut()
+    /// Receive the next message from the channel.
+    ///
+    /// Cancel-safe: frames are read by a background task, so dropping this
+    /// future (e.g. in a `select!`) never loses part of a message.
+    pub async fn receive_async(&self) -> Result<WorkerMessage> {
+        let receiver = self
+            .receiver
+            .as_ref()
             .ok_or_else(|| anyhow::anyhow!("Channel not connected"))?;
-
-        // Read message type
-        let mut type_buf = [0u8; 4];
-        stream.read_exact(&mut type_buf).await?;
-        let message_type = MessageType::from_i32(i32::from_le_bytes(type_buf));
-
-        // Read body length
-        let mut len_buf = [0u8; 4];
-        stream.read_exact(&mut len_buf).await?;
-        let body_len = u32::from_le_bytes(len_buf) as usize;
-
-        // Read body
-        let mut body_buf = vec![0u8; body_len];
-        stream.read_exact(&mut body_buf).await?;
-        let body = String::from_utf8(body_buf)
-            .context("IPC message body is not valid UTF-8")?;
-
-        Ok(WorkerMessage::new(message_type, body))
+        match receiver.lock().await.frames.recv().await {
+            Some(frame) => frame,
+            None => Err(anyhow::anyhow!("IPC channel reader has stopped")),
+        }
     }
 }
 
@@ -221,3 +442,303 @@ impl Drop for ProcessChannel {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_round_trip_large_body() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = ProcessChannel::new();
+        let socket_path = server.start_server(dir.path()).unwrap();
+        let mut client = ProcessChannel::new();
+        let (accepted, connected) =
+            tokio::join!(server.accept(), client.start_client(&socket_path));
+        accepted.unwrap();
+        connected.unwrap();
+
+        // Larger than any socket buffer, with newlines and NULs in the body
+        let body: String = (0..4 * 1024 * 1024)
+            .map(|i| match i % 64 {
+                0 => '\n',
+                1 => '\0',
+                n => (b'a' + (n % 26) as u8) as char,
+            })
+            .collect();
+
+        let (sent, received) = tokio::join!(
+            client.send_async(MessageType::NewJobRequest, &body),
+            server.receive_async()
+        );
+        sent.unwrap();
+        let message = received.unwrap();
+        assert_eq!(message.message_type, MessageType::NewJobRequest);
+        assert_eq!(message.body, body);
+
+        // The stream stays in sync for the next frame
+        client
+            .send_async(MessageType::CancelRequest, "")
+            .await
+            .unwrap();
+        let message = server.receive_async().await.unwrap();
+        assert_eq!(message.message_type, MessageType::CancelRequest);
+        assert_eq!(message.body, "");
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_body_is_rejected() {
+        let mut frame = encode_frame(MessageType::NewJobRequest, b"{\"job\":1}").unwrap();
+        let last = frame.len() - 1;
+        frame[last] ^= 0xff;
+
+        let err = read_frame(&mut frame.as_slice()).await.unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_bad_magic_is_rejected() {
+        let mut frame = encode_frame(MessageType::CancelRequest, b"cancel").unwrap();
+        frame[0] = b'X';
+
+        let err = read_frame(&mut frame.as_slice()).await.unwrap_err();
+        assert!(err.to_string().contains("bad magic"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_length_is_rejected() {
+        let mut frame = encode_frame(MessageType::NewJobRequest, b"").unwrap();
+        frame[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let err = read_frame(&mut frame.as_slice()).await.unwrap_err();
+        assert!(err.to_string().contains("exceeds"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_slow_reader_blocks_sender() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = ProcessChannel::new();
+        let socket_path = server.start_server(dir.path()).unwrap();
+        let mut client = ProcessChannel::new();
+        let (accepted, connected) =
+            tokio::join!(server.accept(), client.start_client(&socket_path));
+        accepted.unwrap();
+        connected.unwrap();
+
+        // Nobody reads from the server side, so once the socket buffer is
+        // full the sender has to wait.
+        let body = "x".repeat(64 * 1024);
+        let mut sent = 0;
+        let blocked = loop {
+            let send = client.send_async(MessageType::NewJobRequest, &body);
+            match tokio::time::timeout(std::time::Duration::from_millis(200), send).await {
+                Ok(result) => {
+                    result.unwrap();
+                    sent += 1;
+                    if sent > 1000 {
+                        break false;
+                    }
+                }
+                Err(_) => break true,
+            }
+        };
+        assert!(blocked, "sender buffered {} frames without blocking", sent);
+
+        // Once the reader drains the stream, every accepted frame arrives intact
+        let reader = tokio::spawn(async move {
+            for _ in 0..sent {
+                let message = server.receive_async().await.unwrap();
+                assert_eq!(message.body.len(), 64 * 1024);
+            }
+            server
+        });
+        client.flush().await.unwrap();
+        reader.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_flush_reports_closed_peer() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = ProcessChannel::new();
+        let socket_path = server.start_server(dir.path()).unwrap();
+        let mut client = ProcessChannel::new();
+        let (accepted, connected) =
+            tokio::join!(server.accept(), client.start_client(&socket_path));
+        accepted.unwrap();
+        connected.unwrap();
+        drop(server);
+
+        // The first write may land in the socket buffer; keep going until the
+        // writer notices the peer is gone.
+        let mut failed = false;
+        for _ in 0..100 {
+            let _ = client
+                .send_async(MessageType::CancelRequest, "cancel")
+                .await;
+            if client.flush().await.is_err() {
+                failed = true;
+                break;
+            }
+        }
+        assert!(failed);
+        assert!(client
+            .send_async(MessageType::CancelRequest, "cancel")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_one_channel_sends_and_receives_concurrently() {
+        let dir = tempfile::tempdir().unwrap();
+        let (listener, worker) = connected_pair(dir.path()).await;
+
+        // The worker sends its own large message before reading anything, so
+        // the listener only gets its job through if it receives while sending.
+        let job = "j".repeat(8 * 1024 * 1024);
+        let log = "l".repeat(8 * 1024 * 1024);
+        let worker = tokio::spawn(async move {
+            worker
+                .send_async(MessageType::JobCompleted, &log)
+                .await
+                .unwrap();
+            let message = worker.receive_async().await.unwrap();
+            assert_eq!(message.body.len(), 8 * 1024 * 1024);
+        });
+
+        let send = listener.send_async(MessageType::NewJobRequest, &job);
+        tokio::pin!(send);
+        let mut sent = false;
+        let mut received = None;
+        while !sent || received.is_none() {
+            tokio::select! {
+                result = &mut send, if !sent => {
+                    result.unwrap();
+                    sent = true;
+                }
+                message = listener.receive_async(), if received.is_none() => {
+                    received = Some(message.unwrap());
+                }
+            }
+        }
+        let received = received.unwrap();
+        assert_eq!(received.message_type, MessageType::JobCompleted);
+        assert_eq!(received.body.len(), 8 * 1024 * 1024);
+        worker.await.unwrap();
+    }
+
+    async fn connected_pair(dir: &std::path::Path) -> (ProcessChannel, ProcessChannel) {
+        let mut server = ProcessChannel::new();
+        let socket_path = server.start_server(dir).unwrap();
+        let mut client = ProcessChannel::new();
+        let (accepted, connected) =
+            tokio::join!(server.accept(), client.start_client(&socket_path));
+        accepted.unwrap();
+        connected.unwrap();
+        (server, client)
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_cancel_arrives_while_large_message_transfers() {
+        let dir = tempfile::tempdir().unwrap();
+        let (job_server, job_client) = connected_pair(dir.path()).await;
+        let (cancel_server, cancel_client) = connected_pair(dir.path()).await;
+
+        // Far larger than the socket buffer, so it is still in flight below
+        let body = "j".repeat(32 * 1024 * 1024);
+        let body_len = body.len();
+        let job_send = tokio::spawn(async move {
+            job_server
+                .send_async(MessageType::NewJobRequest, &body)
+                .await
+                .unwrap();
+            job_server
+        });
+        tokio::task::yield_now().await;
+
+        cancel_server
+            .send_async(MessageType::CancelRequest, "")
+            .await
+            .unwrap();
+        let cancel = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            cancel_client.receive_async(),
+        )
+        .await
+        .expect("cancel was starved by the large message")
+        .unwrap();
+        assert_eq!(cancel.message_type, MessageType::CancelRequest);
+
+        let job = job_client.receive_async().await.unwrap();
+        assert_eq!(job.message_type, MessageType::NewJobRequest);
+        assert_eq!(job.body.len(), body_len);
+        job_send.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_receive_is_cancel_safe() {
+        let dir = tempfile::tempdir().unwrap();
+        let (server, client) = connected_pair(dir.path()).await;
+
+        let body = "r".repeat(8 * 1024 * 1024);
+        server
+            .send_async(MessageType::NewJobRequest, &body)
+            .await
+            .unwrap();
+        server
+            .send_async(MessageType::CancelRequest, "cancel")
+            .await
+            .unwrap();
+
+        // Abandon the receive over and over, as a select! loop would
+        let mut timeouts = 0;
+        let message = loop {
+            let receive = client.receive_async();
+            match tokio::time::timeout(std::time::Duration::from_micros(50), receive).await {
+                Ok(message) => break message.unwrap(),
+                Err(_) => timeouts += 1,
+            }
+        };
+        assert_eq!(
+            message.body.len(),
+            body.len(),
+            "after {} timeouts",
+            timeouts
+        );
+
+        let message = client.receive_async().await.unwrap();
+        assert_eq!(message.message_type, MessageType::CancelRequest);
+        assert_eq!(message.body, "cancel");
+    }
+
+    #[tokio::test]
+    async fn test_send_reports_write_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let (server, client) = connected_pair(dir.path()).await;
+        drop(server);
+
+        // A write can land in the socket buffer before the peer's close is
+        // seen; the write after that fails and send_async reports it.
+        let mut failed = false;
+        for _ in 0..100 {
+            if client
+                .send_async(MessageType::CancelRequest, "cancel")
+                .await
+                .is_err()
+            {
+                failed = true;
+                break;
+            }
+        }
+        assert!(failed);
+    }
+
+    #[tokio::test]
+    async fn test_unconnected_channel_is_rejected() {
+        let channel = ProcessChannel::new();
+        assert!(channel
+            .send_async(MessageType::CancelRequest, "")
+            .await
+            .is_err());
+        assert!(channel.receive_async().await.is_err());
+    }
+}
@@ -0,0 +1,260 @@
+// RunServer mapping `RunServer.cs`.
+// Client for the Actions Run Service's `completejob` call, shared by the
+// worker, which reports the jobs it ran, and the listener, which reports the
+// jobs a crashed worker left behind and the completions a worker could not
+// deliver.
+
+use anyhow::Result;
+use runner_sdk::TraceWriter;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::pending_completion::{PendingCompletion, PendingCompletionStore};
+use crate::util::task_result_util::TaskResult;
+
+/// Attempts at `completejob` before giving up.
+const COMPLETE_JOB_ATTEMPTS: u32 = 5;
+
+/// Delay before the first `completejob` retry; doubled after each attempt.
+const COMPLETE_JOB_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Upper bound on the delay between `completejob` attempts.
+const COMPLETE_JOB_MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// How long a single `completejob` request may take.
+const COMPLETE_JOB_TIMEOUT: Duration = Duration::from_secs(30);
+
+// TaskResult enum values map to camelCase string conclusions:
+//   Succeeded → "succeeded", SucceededWithIssues → "succeededWithIssues",
+//   Failed → "failed", Canceled → "canceled", Skipped → "skipped",
+//   Abandoned → "abandoned"
+pub fn conclusion_string(result: TaskResult) -> &'static str {
+    match result {
+        TaskResult::Succeeded => "succeeded",
+        TaskResult::SucceededWithIssues => "succeededWithIssues",
+        TaskResult::Failed => "failed",
+        TaskResult::Canceled => "canceled",
+        TaskResult::Skipped => "skipped",
+        TaskResult::Abandoned => "abandoned",
+    }
+}
+
+/// Minimal client for the Actions Run Service.
+pub struct RunServer {
+    /// Base URL of the Run Service (SystemVssConnection endpoint URL).
+    base_url: String,
+    /// OAuth access token from the SystemVssConnection endpoint.
+    access_token: String,
+    /// HTTP client
+    client: reqwest::Client,
+    max_attempts: u32,
+    retry_delay: Duration,
+    /// Where to record a completion that could not be delivered.
+    pending_completions: Option<PendingCompletionStore>,
+}
+
+impl RunServer {
+    /// Create a RunServer for the Run Service at `base_url`.
+    pub fn new(base_url: &str, access_token: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            access_token: access_token.to_string(),
+            client: reqwest::Client::new(),
+            max_attempts: COMPLETE_JOB_ATTEMPTS,
+            retry_delay: COMPLETE_JOB_RETRY_DELAY,
+            pending_completions: None,
+        }
+    }
+
+    /// Send requests with `client`, e.g. one configured for the runner's proxy.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Try `completejob` up to `max_attempts` times, waiting `retry_delay`
+    /// (doubling, capped at a minute) between attempts.
+    pub fn with_retry(mut self, max_attempts: u32, retry_delay: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Record the completion in `path` if every attempt fails, for the
+    /// listener to deliver on its next startup.
+    pub fn with_pending_completions(mut self, path: PathBuf) -> Self {
+        self.pending_completions = Some(PendingCompletionStore::new(path));
+        self
+    }
+
+    /// Report job completion to the Actions Run Service.
+    ///
+    /// POST {base_url}/completejob
+    ///
+    /// This is the critical call that tells the server the job is done.
+    /// Without this, the server considers the job still running and keeps
+    /// sending cancellation messages. `payload` carries at least `planId`,
+    /// `jobId` and `conclusion`.
+    ///
+    /// Failed attempts are retried with exponential backoff. If they all
+    /// fail, the request is recorded as a pending completion (when
+    /// configured) before the error is returned.
+    pub async fn complete_job(
+        &self,
+        payload: &serde_json::Value,
+        trace: &dyn TraceWriter,
+    ) -> Result<()> {
+        let url = format!("{}/completejob", self.base_url);
+
+        trace.info(&format!(
+            "Reporting job completion: planId={}, jobId={}, conclusion={}",
+            payload["planId"].as_str().unwrap_or_default(),
+            payload["jobId"].as_str().unwrap_or_default(),
+            payload["conclusion"].as_str().unwrap_or_default()
+        ));
+
+        let mut last_err = None;
+        let mut delay = self.retry_delay;
+        for attempt in 1..=self.max_attempts {
+            match self
+                .client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", self.access_token))
+                .header("Content-Type", "application/json")
+                .json(payload)
+                .timeout(COMPLETE_JOB_TIMEOUT)
+                .send()
+                .await
+            {
+                Ok(response) => {
+                    let status = response.status();
+                    if status.is_success() {
+                        trace.info(&format!(
+                            "Successfully reported job completion (HTTP {})",
+                            status
+                        ));
+                        return Ok(());
+                    }
+                    let body_text = response.text().await.unwrap_or_default();
+                    trace.warning(&format!(
+                        "CompleteJob attempt {}/{} failed: HTTP {} - {}",
+                        attempt, self.max_attempts, status, body_text
+                    ));
+                    last_err = Some(anyhow::anyhow!(
+                        "CompleteJob returned HTTP {}: {}",
+                        status,
+                        body_text
+                    ));
+                }
+                Err(e) => {
+                    trace.warning(&format!(
+                        "CompleteJob attempt {}/{} failed: {}",
+                        attempt, self.max_attempts, e
+                    ));
+                    last_err = Some(e.into());
+                }
+            }
+
+            if attempt < self.max_attempts {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(COMPLETE_JOB_MAX_RETRY_DELAY);
+            }
+        }
+
+        if let Some(store) = &self.pending_completions {
+            let pending = PendingCompletion {
+                run_service_url: self.base_url.clone(),
+                payload: payload.clone(),
+            };
+            match store.add(pending) {
+                Ok(()) => trace.warning(
+                    "Recorded the job completion as pending; the runner will report it when it next starts",
+                ),
+                Err(e) => trace.error(&format!(
+                    "Failed to record the pending job completion: {:#}",
+                    e
+                )),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            anyhow::anyhow!("CompleteJob failed after {} attempts", self.max_attempts)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runner_sdk::test_util::{MockHttpServer, MockResponse};
+
+    #[test]
+    fn test_conclusion_values() {
+        assert_eq!(conclusion_string(TaskResult::Succeeded), "succeeded");
+        assert_eq!(
+            conclusion_string(TaskResult::SucceededWithIssues),
+            "succeededWithIssues"
+        );
+        assert_eq!(conclusion_string(TaskResult::Abandoned), "abandoned");
+    }
+
+    /// A server answering one request with each status, in order.
+    async fn serve_statuses(statuses: Vec<&str>) -> MockHttpServer {
+        MockHttpServer::start(statuses.into_iter().map(MockResponse::new).collect()).await
+    }
+
+    fn run_server(base_url: &str) -> RunServer {
+        RunServer::new(base_url, "token").with_retry(3, Duration::ZERO)
+    }
+
+    fn payload(conclusion: &str) -> serde_json::Value {
+        serde_json::json!({
+            "planId": "plan-1",
+            "jobId": "job-1",
+            "conclusion": conclusion,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_complete_job_retries_until_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join(".pending_completions");
+        let server =
+            serve_statuses(vec!["503 Service Unavailable", "502 Bad Gateway", "200 OK"]).await;
+
+        run_server(server.url())
+            .with_pending_completions(marker.clone())
+            .complete_job(&payload("succeeded"), &runner_sdk::trace::NullTraceWriter)
+            .await
+            .unwrap();
+
+        let served = server.finish().await;
+        assert_eq!(served.len(), 3);
+        assert_eq!(served[2].method, "POST");
+        assert_eq!(served[2].target, "/completejob");
+        assert_eq!(served[2].body_json()["conclusion"], "succeeded");
+        assert!(!marker.exists());
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_record_pending_completion() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join(".pending_completions");
+        let server = serve_statuses(vec!["500 Internal Server Error"; 3]).await;
+        let url = server.url().to_string();
+
+        let err = run_server(&url)
+            .with_pending_completions(marker.clone())
+            .complete_job(&payload("failed"), &runner_sdk::trace::NullTraceWriter)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("HTTP 500"), "{}", err);
+        assert_eq!(server.finish().await.len(), 3);
+
+        let pending = PendingCompletionStore::new(marker).list();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].run_service_url, url);
+        assert_eq!(pending[0].payload["jobId"], "job-1");
+        assert_eq!(pending[0].payload["conclusion"], "failed");
+    }
+}
@@ -6,7 +6,7 @@ use crate::secret_masker::SecretMasker;
 use crate::tracing::Tracing;
 
 use runner_sdk::TraceWriter;
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::sync::Arc;
 use tokio::sync::broadcast;
 
@@ -50,6 +50,8 @@ pub struct Terminal {
     trace: Option<Tracing>,
     /// Secret masker for masking secrets in ReadSecret.
     secret_masker: Option<Arc<SecretMasker>>,
+    /// Whether output is colored with ANSI escape codes.
+    color: bool,
     /// Broadcast channel for cancel key press events.
     cancel_tx: broadcast::Sender<()>,
     /// Receiver side (kept alive to prevent channel closing).
@@ -64,6 +66,7 @@ impl Terminal {
             silent: false,
             trace: None,
             secret_masker: None,
+            color: Self::detect_color(),
             cancel_tx,
             _cancel_rx,
         }
@@ -79,11 +82,49 @@ impl Terminal {
         let _ = ctrlc_channel(&tx);
     }
 
+    /// Force color on or off, overriding detection.
+    pub fn set_color(&mut self, enabled: bool) {
+        self.color = enabled;
+    }
+
+    /// Whether output is colored.
+    pub fn color_enabled(&self) -> bool {
+        self.color
+    }
+
+    /// Whether to color output by default: not when `NO_COLOR` is set to a
+    /// non-empty value, `TERM` is `dumb`, or stdout is not a terminal (e.g.
+    /// redirected to a log file).
+    pub fn detect_color() -> bool {
+        color_supported(
+            std::env::var("NO_COLOR").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+            io::stdout().is_terminal(),
+        )
+    }
+
+    /// `text` in `color`, or plain when color is off.
+    pub fn paint(&self, text: &str, color: ConsoleColor) -> String {
+        if self.color && color != ConsoleColor::Default {
+            format!("{}{}{}", color.ansi_code(), text, ConsoleColor::reset())
+        } else {
+            text.to_string()
+        }
+    }
+
     /// Subscribe to cancel key press events.
     pub fn cancel_receiver(&self) -> broadcast::Receiver<()> {
         self.cancel_tx.subscribe()
     }
 
+    /// Whether stdin is attached to a terminal, so a user can answer prompts.
+    ///
+    /// False when input is piped or redirected, or when there is no console at
+    /// all (CI jobs, provisioning scripts, services).
+    pub fn is_interactive() -> bool {
+        io::stdin().is_terminal()
+    }
+
     /// Read a line from stdin.
     pub fn read_line(&self) -> String {
         if let Some(ref trace) = self.trace {
@@ -132,11 +173,10 @@ impl Terminal {
         }
 
         if !self.silent {
-            if let Some(color) = color {
-                print!("{}{}{}", color.ansi_code(), message, ConsoleColor::reset());
-            } else {
-                print!("{}", message);
-            }
+            print!(
+                "{}",
+                self.paint(message, color.unwrap_or(ConsoleColor::Default))
+            );
             let _ = io::stdout().flush();
         }
     }
@@ -148,11 +188,10 @@ impl Terminal {
         }
 
         if !self.silent {
-            if let Some(color) = color {
-                println!("{}{}{}", color.ansi_code(), line, ConsoleColor::reset());
-            } else {
-                println!("{}", line);
-            }
+            println!(
+                "{}",
+                self.paint(line, color.unwrap_or(ConsoleColor::Default))
+            );
         }
     }
 
@@ -168,12 +207,7 @@ impl Terminal {
         }
 
         if !self.silent {
-            eprintln!(
-                "{}{}{}",
-                ConsoleColor::Red.ansi_code(),
-                line,
-                ConsoleColor::reset()
-            );
+            eprintln!("{}", self.paint(line, ConsoleColor::Red));
         }
     }
 
@@ -185,12 +219,7 @@ impl Terminal {
         }
 
         if !self.silent {
-            eprintln!(
-                "{}{}{}",
-                ConsoleColor::Red.ansi_code(),
-                err,
-                ConsoleColor::reset()
-            );
+            eprintln!("{}", self.paint(&err.to_string(), ConsoleColor::Red));
         }
     }
 
@@ -206,12 +235,7 @@ impl Terminal {
     /// Write a success message with a checkmark prefix.
     pub fn write_success_message(&self, message: &str) {
         if !self.silent {
-            print!(
-                "{}√ {}",
-                ConsoleColor::Green.ansi_code(),
-                ConsoleColor::reset()
-            );
-            println!("{}", message);
+            println!("{} {}", self.paint("√", ConsoleColor::Green), message);
         }
     }
 }
@@ -226,6 +250,18 @@ impl Default for Terminal {
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Color decision from the `NO_COLOR` and `TERM` values and whether stdout
+/// is a terminal.
+fn color_supported(no_color: Option<&str>, term: Option<&str>, stdout_is_terminal: bool) -> bool {
+    if no_color.is_some_and(|value| !value.is_empty()) {
+        return false;
+    }
+    if term.is_some_and(|term| term.eq_ignore_ascii_case("dumb")) {
+        return false;
+    }
+    stdout_is_terminal
+}
+
 /// Set up a Ctrl+C handler that sends on the broadcast channel.
 fn ctrlc_channel(tx: &broadcast::Sender<()>) {
     let tx = tx.clone();
@@ -286,3 +322,33 @@ fn read_secret_line() -> String {
         input.trim_end_matches('\n').trim_end_matches('\r').to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_color_suppresses_color() {
+        assert!(color_supported(None, Some("xterm-256color"), true));
+        assert!(!color_supported(Some("1"), Some("xterm-256color"), true));
+        // An empty NO_COLOR does not count
+        assert!(color_supported(Some(""), Some("xterm"), true));
+        assert!(!color_supported(None, Some("dumb"), true));
+        assert!(!color_supported(None, Some("xterm"), false));
+    }
+
+    #[test]
+    fn test_paint_is_plain_without_color() {
+        let mut terminal = Terminal::new();
+        terminal.set_color(false);
+        assert!(!terminal.color_enabled());
+        assert_eq!(terminal.paint("failed", ConsoleColor::Red), "failed");
+
+        terminal.set_color(true);
+        assert_eq!(
+            terminal.paint("failed", ConsoleColor::Red),
+            "\x1b[31mfailed\x1b[0m"
+        );
+        assert_eq!(terminal.paint("plain", ConsoleColor::Default), "plain");
+    }
+}
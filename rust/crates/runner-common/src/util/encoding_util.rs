@@ -1,7 +1,17 @@
 // EncodingUtil mapping `Util/EncodingUtil.cs`.
 // Encoding/character set helpers.
+//
+// Process output is decoded as UTF-8 unless `ACTIONS_RUNNER_OUTPUT_ENCODING`
+// names another encoding, either in the runner's environment or in a step's
+// `env`. The value is an encoding label (`windows-1252`, `shift_jis`) or a
+// Windows code page, optionally prefixed with `cp` (`1252`, `cp866`). Output
+// is split into lines before it is decoded, so UTF-16, where a newline is not
+// a single `\n` byte, is not supported.
 
+use crate::constants;
 use crate::host_context::HostContext;
+use encoding_rs::Encoding;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Encoding utility helpers.
@@ -67,4 +77,129 @@ impl EncodingUtil {
             let _ = cancellation_token;
         }
     }
+
+    /// The encoding named by an encoding label or Windows code page number.
+    /// UTF-16 is rejected.
+    pub fn from_name(name: &str) -> Option<&'static Encoding> {
+        let name = name.trim();
+        if let Some(encoding) = Encoding::for_label(name.as_bytes()) {
+            return Some(encoding).filter(|encoding| !Self::is_utf16(encoding));
+        }
+
+        let code_page = name
+            .strip_prefix("cp")
+            .or_else(|| name.strip_prefix("CP"))
+            .unwrap_or(name)
+            .parse::<u16>()
+            .ok()?;
+        Self::from_code_page(code_page)
+    }
+
+    /// The encoding for a Windows code page.
+    pub fn from_code_page(code_page: u16) -> Option<&'static Encoding> {
+        match code_page {
+            65001 => Some(encoding_rs::UTF_8),
+            866 => Some(encoding_rs::IBM866),
+            932 => Some(encoding_rs::SHIFT_JIS),
+            936 => Some(encoding_rs::GBK),
+            949 => Some(encoding_rs::EUC_KR),
+            950 => Some(encoding_rs::BIG5),
+            20866 => Some(encoding_rs::KOI8_R),
+            21866 => Some(encoding_rs::KOI8_U),
+            874 | 1250..=1258 => Encoding::for_label(format!("windows-{}", code_page).as_bytes()),
+            _ => None,
+        }
+    }
+
+    /// The encoding process output is decoded from: the one named by
+    /// `ACTIONS_RUNNER_OUTPUT_ENCODING` in `environment`, else in the
+    /// runner's own environment, else UTF-8.
+    ///
+    /// Returns an error naming the value when it is not a known encoding.
+    pub fn output_encoding(
+        environment: &HashMap<String, String>,
+    ) -> Result<&'static Encoding, String> {
+        let configured = environment
+            .get(constants::variables::agent::OUTPUT_ENCODING)
+            .cloned()
+            .or_else(|| std::env::var(constants::variables::agent::OUTPUT_ENCODING).ok())
+            .filter(|name| !name.trim().is_empty());
+
+        match configured {
+            Some(name) => Self::from_name(&name).ok_or(name),
+            None => Ok(encoding_rs::UTF_8),
+        }
+    }
+
+    fn is_utf16(encoding: &'static Encoding) -> bool {
+        encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE
+    }
+
+    /// Decode bytes written in `encoding`; invalid sequences become U+FFFD.
+    pub fn decode(bytes: &[u8], encoding: &'static Encoding) -> String {
+        encoding.decode_without_bom_handling(bytes).0.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name_accepts_labels_and_code_pages() {
+        for name in ["windows-1252", "cp1252", "CP1252", "1252", " 1252 "] {
+            assert_eq!(
+                EncodingUtil::from_name(name),
+                Some(encoding_rs::WINDOWS_1252),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(EncodingUtil::from_name("65001"), Some(encoding_rs::UTF_8));
+        assert_eq!(EncodingUtil::from_name("cp866"), Some(encoding_rs::IBM866));
+        assert_eq!(EncodingUtil::from_name("932"), Some(encoding_rs::SHIFT_JIS));
+        assert_eq!(EncodingUtil::from_name("no-such-encoding"), None);
+        assert_eq!(EncodingUtil::from_name("437"), None);
+    }
+
+    #[test]
+    fn test_from_name_rejects_utf16() {
+        for name in ["1200", "1201", "cp1200", "utf-16", "utf-16le", "UTF-16BE"] {
+            assert_eq!(EncodingUtil::from_name(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_decode_cp1252() {
+        // "Größe: 5 €" in Windows-1252
+        let bytes = b"Gr\xf6\xdfe: 5 \x80";
+        let encoding = EncodingUtil::from_name("1252").unwrap();
+        assert_eq!(EncodingUtil::decode(bytes, encoding), "Größe: 5 €");
+        assert_eq!(
+            EncodingUtil::decode(bytes, encoding_rs::UTF_8),
+            "Gr\u{fffd}\u{fffd}e: 5 \u{fffd}"
+        );
+    }
+
+    #[test]
+    fn test_output_encoding_from_step_environment() {
+        let mut env = HashMap::new();
+        env.insert(
+            constants::variables::agent::OUTPUT_ENCODING.to_string(),
+            "cp1252".to_string(),
+        );
+        assert_eq!(
+            EncodingUtil::output_encoding(&env),
+            Ok(encoding_rs::WINDOWS_1252)
+        );
+
+        env.insert(
+            constants::variables::agent::OUTPUT_ENCODING.to_string(),
+            "klingon".to_string(),
+        );
+        assert_eq!(
+            EncodingUtil::output_encoding(&env),
+            Err("klingon".to_string())
+        );
+    }
 }
@@ -62,6 +62,101 @@ impl VarUtil {
         }
         merged
     }
+
+    /// Expand variable references in `input`, resolving names with `lookup`.
+    ///
+    /// Uses the platform syntax: `$VAR` and `${VAR}` on Linux/macOS, `%VAR%`
+    /// on Windows. Values are expanded recursively, so a variable may refer
+    /// to other variables. References to missing variables, and references
+    /// that would recurse into a variable already being expanded, are left
+    /// as written.
+    pub fn expand(input: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+        let mut active = Vec::new();
+        expand_references(input, CURRENT_PLATFORM, &lookup, &mut active)
+    }
+}
+
+/// Maximum nesting depth for `VarUtil::expand`.
+const MAX_EXPAND_DEPTH: usize = 50;
+
+fn expand_references(
+    input: &str,
+    platform: OsPlatform,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    active: &mut Vec<String>,
+) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some((start, len, name)) = find_reference(rest, platform) {
+        result.push_str(&rest[..start]);
+        let literal = &rest[start..start + len];
+
+        let in_cycle = active.iter().any(|a| VarUtil::env_var_keys_equal(a, name));
+        match lookup(name) {
+            Some(value) if !in_cycle && active.len() < MAX_EXPAND_DEPTH => {
+                active.push(name.to_string());
+                result.push_str(&expand_references(&value, platform, lookup, active));
+                active.pop();
+            }
+            _ => result.push_str(literal),
+        }
+
+        rest = &rest[start + len..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Find the first variable reference in `input`.
+///
+/// Returns the byte offset and length of the whole reference, and the name.
+fn find_reference(input: &str, platform: OsPlatform) -> Option<(usize, usize, &str)> {
+    let bytes = input.as_bytes();
+    let (marker, closing) = match platform {
+        OsPlatform::Windows => (b'%', None),
+        _ => (b'$', Some(b'}')),
+    };
+
+    let mut index = 0;
+    while let Some(offset) = bytes[index..].iter().position(|&b| b == marker) {
+        let start = index + offset;
+        let after = &input[start + 1..];
+
+        let found = match closing {
+            // %VAR%
+            None => after
+                .find('%')
+                .map(|end| (&after[..end], end + 2))
+                .filter(|(name, _)| is_variable_name(name)),
+            // ${VAR}
+            Some(_) if after.starts_with('{') => after
+                .find('}')
+                .map(|end| (&after[1..end], end + 2))
+                .filter(|(name, _)| is_variable_name(name)),
+            // $VAR
+            Some(_) => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                Some((&after[..end], end + 1)).filter(|(name, _)| is_variable_name(name))
+            }
+        };
+
+        if let Some((name, len)) = found {
+            return Some((start, len, name));
+        }
+        index = start + 1;
+    }
+
+    None
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
 }
 
 #[cfg(test)]
@@ -97,4 +192,92 @@ mod tests {
         assert!(VarUtil::env_var_keys_equal("PATH", "PATH"));
         assert!(!VarUtil::env_var_keys_equal("PATH", "path_other"));
     }
+
+    fn lookup<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    fn expand(input: &str, platform: OsPlatform, vars: &[(&str, &str)]) -> String {
+        expand_references(input, platform, &lookup(vars), &mut Vec::new())
+    }
+
+    #[test]
+    fn test_expand_nested_references() {
+        let vars = [
+            ("TOOL_ROOT", "/opt/tools"),
+            ("TOOL_BIN", "${TOOL_ROOT}/bin"),
+            ("TOOL_PATH", "$TOOL_BIN:/usr/bin"),
+        ];
+        assert_eq!(
+            expand("PATH=$TOOL_PATH", OsPlatform::Linux, &vars),
+            "PATH=/opt/tools/bin:/usr/bin"
+        );
+        assert_eq!(
+            expand("${TOOL_ROOT}x $TOOL_ROOT.y", OsPlatform::MacOS, &vars),
+            "/opt/toolsx /opt/tools.y"
+        );
+
+        let vars = [("TOOL_ROOT", "C:\\tools"), ("TOOL_BIN", "%TOOL_ROOT%\\bin")];
+        assert_eq!(
+            expand("%TOOL_BIN%;%PATH_EXT", OsPlatform::Windows, &vars),
+            "C:\\tools\\bin;%PATH_EXT"
+        );
+    }
+
+    #[test]
+    fn test_expand_uses_platform_syntax() {
+        let vars = [("NAME", "value")];
+        assert_eq!(
+            expand("$NAME %NAME%", OsPlatform::Linux, &vars),
+            "value %NAME%"
+        );
+        assert_eq!(
+            expand("$NAME %NAME%", OsPlatform::Windows, &vars),
+            "$NAME value"
+        );
+    }
+
+    #[test]
+    fn test_expand_leaves_missing_variables_literal() {
+        let vars = [("SET", "yes")];
+        assert_eq!(
+            expand(
+                "$MISSING ${MISSING} $SET $ 5$ ${} ${1X}",
+                OsPlatform::Linux,
+                &vars
+            ),
+            "$MISSING ${MISSING} yes $ 5$ ${} ${1X}"
+        );
+        assert_eq!(
+            expand("%MISSING% 100% %SET%", OsPlatform::Windows, &vars),
+            "%MISSING% 100% yes"
+        );
+    }
+
+    #[test]
+    fn test_expand_stops_at_cycles() {
+        let vars = [("A", "a($B)"), ("B", "b(${A})"), ("SELF", "$SELF!")];
+        assert_eq!(expand("$A", OsPlatform::Linux, &vars), "a(b(${A}))");
+        assert_eq!(expand("$SELF", OsPlatform::Linux, &vars), "$SELF!");
+
+        let vars = [("A", "%B%"), ("B", "%A%")];
+        assert_eq!(expand("%A%", OsPlatform::Windows, &vars), "%A%");
+    }
+
+    #[test]
+    fn test_expand_with_current_platform() {
+        let input = if cfg!(windows) {
+            "%GREETING%"
+        } else {
+            "$GREETING"
+        };
+        let result = VarUtil::expand(input, |name| {
+            (name == "GREETING").then(|| "hello".to_string())
+        });
+        assert_eq!(result, "hello");
+    }
 }
@@ -12,6 +12,8 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 
+use crate::message_listener::ListenerError;
+
 /// Maximum retries when creating a broker session.
 const MAX_SESSION_CREATE_RETRIES: u32 = 30;
 
@@ -160,6 +162,11 @@ impl BrokerMessageListener {
                     return Ok(());
                 }
                 Err(e) => {
+                    // A removed runner can never create a session
+                    if ListenerError::is_runner_removed(&e) {
+                        return Err(e);
+                    }
+
                     retry_count += 1;
                     if retry_count >= MAX_SESSION_CREATE_RETRIES {
                         return Err(e).context(format!(
@@ -220,11 +227,7 @@ impl BrokerMessageListener {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Broker session create failed with HTTP {}: {}",
-                status.as_u16(),
-                body
-            ));
+            return Err(ListenerError::from_session_status(status.as_u16(), body).into());
         }
 
         let session: BrokerSession = response
@@ -302,11 +305,7 @@ impl BrokerMessageListener {
 
         if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Broker get message failed with HTTP {}: {}",
-                status.as_u16(),
-                body
-            ));
+            return Err(ListenerError::from_status(status.as_u16(), body).into());
         }
 
         let message: BrokerMessage = response
@@ -488,6 +487,11 @@ impl BrokerMessageListener {
         self.session.as_ref().map(|s| s.session_id.as_str())
     }
 
+    /// Get the current access token (for use by external callers like Runner).
+    pub fn get_access_token(&self) -> Option<String> {
+        self.access_token.clone()
+    }
+
     /// Update the access token (e.g., after a ForceTokenRefresh message).
     pub fn set_access_token(&mut self, token: String) {
         self.access_token = Some(token);
@@ -32,15 +32,17 @@ impl ActionsCheck {
     async fn check_connectivity(server_url: &str) -> Result<String, anyhow::Error> {
         let url = Url::parse(server_url)?;
 
-        // Build the API URL
-        let api_url = Self::get_api_url(&url)?;
+        // Build the API URL from the server root
+        let mut root = url.clone();
+        root.set_path("");
+        let api_url = runner_sdk::UrlUtil::api_url(&root, "");
 
         // Try to connect
         let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(10))
             .build()?;
 
-        let response = client.get(api_url.as_str()).send().await?;
+        let response = client.get(&api_url).send().await?;
         let status = response.status();
 
         if status.is_success() || status.as_u16() == 401 || status.as_u16() == 403 {
@@ -57,21 +59,6 @@ impl ActionsCheck {
             ))
         }
     }
-
-    fn get_api_url(url: &Url) -> Result<Url, anyhow::Error> {
-        let host = url.host_str().unwrap_or("");
-
-        // GitHub.com
-        if host == "github.com" || host.ends_with(".github.com") {
-            return Ok(Url::parse("https://api.github.com")?);
-        }
-
-        // GitHub Enterprise Server (GHES)
-        // API endpoint is at /api/v3
-        let mut api_url = url.clone();
-        api_url.set_path("/api/v3");
-        Ok(api_url)
-    }
 }
 
 /// Check DNS resolution for GitHub Actions domains.
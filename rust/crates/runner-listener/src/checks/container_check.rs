@@ -0,0 +1,148 @@
+// Container runtime availability check.
+//
+// Jobs that use `container:` or `services:` need a container runtime. Without
+// one they fail mid-job with an opaque spawn error, so detect docker/podman up
+// front and confirm the current user can actually reach the daemon.
+
+use super::check_extension::CheckResult;
+use std::process::Command;
+
+const CHECK_NAME: &str = "Container Runtime";
+const CHECK_DESCRIPTION: &str =
+    "Check if docker or podman is installed and the current user can run containers";
+
+/// Runtimes probed by default, in order of preference.
+pub const DEFAULT_RUNTIMES: &[&str] = &["docker", "podman"];
+
+pub struct ContainerCheck;
+
+impl ContainerCheck {
+    /// Run the container runtime check against the given runtime commands.
+    /// The first runtime that reports a version is the one checked for access.
+    pub async fn run_check(runtimes: &[&str]) -> CheckResult {
+        let Some((runtime, version)) = runtimes
+            .iter()
+            .find_map(|runtime| Self::get_version(runtime).map(|v| (*runtime, v)))
+        else {
+            return CheckResult::fail(
+                CHECK_NAME,
+                CHECK_DESCRIPTION,
+                format!(
+                    "No container runtime found (tried {}). Install Docker or Podman to run jobs that use container or service containers.",
+                    runtimes.join(", ")
+                ),
+            );
+        };
+
+        match Self::check_access(runtime) {
+            Ok(()) => {
+                let mut result = CheckResult::pass(CHECK_NAME, CHECK_DESCRIPTION);
+                result.detail = Some(format!("{}: {}", runtime, version));
+                result
+            }
+            Err(e) => CheckResult::fail(
+                CHECK_NAME,
+                CHECK_DESCRIPTION,
+                format!(
+                    "{} ({}) is installed but the current user cannot run containers: {}. Make sure the daemon is running and the user can access its socket (for docker, add the user to the 'docker' group).",
+                    runtime, version, e
+                ),
+            ),
+        }
+    }
+
+    /// Run `<runtime> --version`, returning the version line if it succeeds.
+    fn get_version(runtime: &str) -> Option<String> {
+        let output = Command::new(runtime).arg("--version").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Run `<runtime> info`, which needs a reachable daemon (or, for podman,
+    /// a working user namespace) and fails on socket permission errors.
+    fn check_access(runtime: &str) -> Result<(), anyhow::Error> {
+        let output = Command::new(runtime)
+            .arg("info")
+            .output()
+            .map_err(|e| anyhow::anyhow!("failed to run '{} info': {}", runtime, e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!(
+                "'{} info' exited with code {}: {}",
+                runtime,
+                output.status.code().unwrap_or(-1),
+                stderr.trim()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a fake runtime script that prints a version and exits with
+    /// `info_exit` for `info`.
+    #[cfg(unix)]
+    fn stub_runtime(dir: &std::path::Path, info_exit: i32) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("docker");
+        let script = format!(
+            "#!/bin/sh\nif [ \"$1\" = \"--version\" ]; then echo 'Docker version 99.0.0, build stub'; exit 0; fi\nif [ \"$1\" = \"info\" ]; then echo 'permission denied while trying to connect to the Docker daemon socket' >&2; exit {}; fi\nexit 1\n",
+            info_exit
+        );
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stubbed_runtime_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = stub_runtime(dir.path(), 0);
+        let missing = dir.path().join("podman").to_string_lossy().to_string();
+
+        let result = ContainerCheck::run_check(&[&missing, &runtime]).await;
+        assert!(result.passed, "{:?}", result.detail);
+        let detail = result.detail.unwrap();
+        assert!(detail.contains("Docker version 99.0.0"), "{}", detail);
+        assert!(detail.starts_with(&runtime), "{}", detail);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_runtime_without_socket_access_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = stub_runtime(dir.path(), 1);
+
+        let result = ContainerCheck::run_check(&[&runtime]).await;
+        assert!(!result.passed);
+        let detail = result.detail.unwrap();
+        assert!(detail.contains("cannot run containers"), "{}", detail);
+        assert!(detail.contains("permission denied"), "{}", detail);
+        assert!(detail.contains("'docker' group"), "{}", detail);
+    }
+
+    #[tokio::test]
+    async fn test_absent_runtime_fails_with_guidance() {
+        let dir = tempfile::tempdir().unwrap();
+        let docker = dir.path().join("docker").to_string_lossy().to_string();
+        let podman = dir.path().join("podman").to_string_lossy().to_string();
+
+        let result = ContainerCheck::run_check(&[&docker, &podman]).await;
+        assert!(!result.passed);
+        let detail = result.detail.unwrap();
+        assert!(
+            detail.starts_with("No container runtime found"),
+            "{}",
+            detail
+        );
+        assert!(detail.contains("Install Docker or Podman"), "{}", detail);
+    }
+}
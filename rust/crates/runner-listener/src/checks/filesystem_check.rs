@@ -0,0 +1,117 @@
+// Filesystem write access check.
+//
+// Verifies that the runner can create and delete files in the work, temp,
+// and tool cache directories. Non-writable or full directories are a common
+// cause of job failures that otherwise only surface mid-job.
+
+use super::check_extension::CheckResult;
+use runner_common::constants::WellKnownDirectory;
+use runner_common::host_context::HostContext;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const CHECK_NAME: &str = "Filesystem Access";
+const CHECK_DESCRIPTION: &str = "Check write access to the work, temp, and tool cache directories";
+
+pub struct FilesystemCheck;
+
+impl FilesystemCheck {
+    /// The directories a job needs to write to, with a label for each.
+    pub fn directories(context: &HostContext) -> Vec<(&'static str, PathBuf)> {
+        vec![
+            ("work", context.get_directory(WellKnownDirectory::Work)),
+            ("temp", context.get_directory(WellKnownDirectory::Temp)),
+            (
+                "tool cache",
+                context.get_directory(WellKnownDirectory::Tools),
+            ),
+        ]
+    }
+
+    /// Run the write access check against the given directories.
+    pub async fn run_check(directories: &[(&'static str, PathBuf)]) -> CheckResult {
+        let failures: Vec<String> = directories
+            .iter()
+            .filter_map(|(label, path)| {
+                Self::check_writable(path)
+                    .err()
+                    .map(|e| format!("{} directory {}: {}", label, path.display(), e))
+            })
+            .collect();
+
+        if failures.is_empty() {
+            CheckResult::pass(CHECK_NAME, CHECK_DESCRIPTION)
+        } else {
+            CheckResult::fail(CHECK_NAME, CHECK_DESCRIPTION, failures.join("; "))
+        }
+    }
+
+    /// Create the directory if needed, then write and delete a probe file in it.
+    fn check_writable(dir: &Path) -> Result<(), anyhow::Error> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| anyhow::anyhow!("cannot create directory: {}", e))?;
+
+        let metadata = std::fs::metadata(dir)?;
+        if !metadata.is_dir() {
+            return Err(anyhow::anyhow!("not a directory"));
+        }
+        if metadata.permissions().readonly() {
+            return Err(anyhow::anyhow!("directory is read-only"));
+        }
+
+        let probe = dir.join(format!(".runner_write_check_{}", uuid::Uuid::new_v4()));
+        let written = std::fs::File::create(&probe).and_then(|mut file| {
+            file.write_all(b"runner write check")?;
+            file.sync_all()
+        });
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&probe);
+            return Err(anyhow::anyhow!("cannot write file: {}", e));
+        }
+
+        std::fs::remove_file(&probe).map_err(|e| anyhow::anyhow!("cannot delete file: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_writable_directories_pass() {
+        let root = tempfile::tempdir().unwrap();
+        let dirs = vec![
+            ("work", root.path().join("_work")),
+            ("temp", root.path().join("_work").join("_temp")),
+        ];
+
+        let result = FilesystemCheck::run_check(&dirs).await;
+        assert!(result.passed, "{:?}", result.detail);
+        // Probe files are cleaned up
+        assert_eq!(std::fs::read_dir(&dirs[1].1).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_read_only_temp_fails_naming_temp() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempfile::tempdir().unwrap();
+        let work = root.path().join("_work");
+        let temp = root.path().join("_temp");
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::create_dir_all(&temp).unwrap();
+        std::fs::set_permissions(&temp, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        let dirs = vec![("work", work), ("temp", temp.clone())];
+        let result = FilesystemCheck::run_check(&dirs).await;
+
+        std::fs::set_permissions(&temp, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(!result.passed);
+        let detail = result.detail.unwrap();
+        assert!(detail.starts_with("temp directory"), "{}", detail);
+        assert!(detail.contains(&temp.display().to_string()), "{}", detail);
+        assert!(!detail.contains("work directory"), "{}", detail);
+    }
+}
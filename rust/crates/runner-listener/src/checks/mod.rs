@@ -3,11 +3,15 @@
 
 pub mod check_extension;
 pub mod actions_check;
+pub mod container_check;
+pub mod filesystem_check;
 pub mod git_check;
 pub mod internet_check;
 pub mod nodejs_check;
+pub mod resources_check;
 
 use check_extension::CheckResult;
+use runner_common::host_context::HostContext;
 use runner_sdk::TraceWriter;
 
 /// Run all diagnostic checks and return the results.
@@ -15,6 +19,7 @@ use runner_sdk::TraceWriter;
 /// Otherwise, the server URL from the runner configuration is used.
 pub async fn run_all_checks(
     url: Option<&str>,
+    context: &HostContext,
     trace: &runner_common::Tracing,
 ) -> Vec<CheckResult> {
     let mut results = Vec::new();
@@ -40,6 +45,23 @@ pub async fn run_all_checks(
     let node_result = nodejs_check::NodeJsCheck::run_check().await;
     results.push(node_result);
 
+    // Filesystem write access
+    trace.info("Running filesystem check...");
+    let directories = filesystem_check::FilesystemCheck::directories(context);
+    let filesystem_result = filesystem_check::FilesystemCheck::run_check(&directories).await;
+    results.push(filesystem_result);
+
+    // Memory and disk space
+    trace.info("Running host resources check...");
+    let resources_result = resources_check::ResourcesCheck::run_check(context).await;
+    results.push(resources_result);
+
+    // Container runtime
+    trace.info("Running container runtime check...");
+    let container_result =
+        container_check::ContainerCheck::run_check(container_check::DEFAULT_RUNTIMES).await;
+    results.push(container_result);
+
     results
 }
 
@@ -0,0 +1,123 @@
+// Host resources check.
+//
+// Reports the CPU count, memory and free disk space available to the runner,
+// and fails when memory or the work directory's disk is nearly exhausted.
+// Running out of either usually surfaces as an unexplained mid-job crash.
+
+use super::check_extension::CheckResult;
+use runner_common::constants::WellKnownDirectory;
+use runner_common::host_context::{HostContext, HostResources};
+
+const CHECK_NAME: &str = "Host Resources";
+const CHECK_DESCRIPTION: &str = "Check available memory and free disk space in the work directory";
+
+/// Below this much available memory a job is likely to be killed.
+pub const MIN_AVAILABLE_MEMORY_MB: u64 = 256;
+
+/// Below this much free disk space a checkout or tool download is likely to fail.
+pub const MIN_FREE_DISK_MB: u64 = 1024;
+
+pub struct ResourcesCheck;
+
+impl ResourcesCheck {
+    /// Detect host resources and evaluate them.
+    pub async fn run_check(context: &HostContext) -> CheckResult {
+        let work = context.get_directory(WellKnownDirectory::Work);
+        let resources = context.host_resources();
+        // The work directory may not exist yet; measure the nearest existing ancestor.
+        let free_disk = work
+            .ancestors()
+            .find(|dir| dir.exists())
+            .and_then(|dir| context.available_disk_space(dir));
+
+        Self::evaluate(&resources, free_disk)
+    }
+
+    /// Turn detected resources into a check result.
+    fn evaluate(resources: &HostResources, free_disk_bytes: Option<u64>) -> CheckResult {
+        let mut failures = Vec::new();
+        if resources.available_memory_mb() < MIN_AVAILABLE_MEMORY_MB {
+            failures.push(format!(
+                "only {} MB of memory available, at least {} MB required",
+                resources.available_memory_mb(),
+                MIN_AVAILABLE_MEMORY_MB
+            ));
+        }
+        let free_disk_mb = free_disk_bytes.map(|bytes| bytes / (1024 * 1024));
+        if let Some(free) = free_disk_mb.filter(|free| *free < MIN_FREE_DISK_MB) {
+            failures.push(format!(
+                "only {} MB of disk space free in the work directory, at least {} MB required",
+                free, MIN_FREE_DISK_MB
+            ));
+        }
+
+        let summary = format!(
+            "{} CPU(s), {} MB of {} MB memory available, {} free disk",
+            resources.cpu_count,
+            resources.available_memory_mb(),
+            resources.total_memory_mb(),
+            free_disk_mb.map_or_else(|| "unknown".to_string(), |free| format!("{} MB", free)),
+        );
+
+        if failures.is_empty() {
+            let mut result = CheckResult::pass(CHECK_NAME, CHECK_DESCRIPTION);
+            result.detail = Some(summary);
+            result
+        } else {
+            CheckResult::fail(
+                CHECK_NAME,
+                CHECK_DESCRIPTION,
+                format!("{} ({})", failures.join("; "), summary),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn resources(available_memory_mb: u64) -> HostResources {
+        HostResources {
+            cpu_count: 4,
+            total_memory_bytes: 8192 * MB,
+            available_memory_bytes: available_memory_mb * MB,
+        }
+    }
+
+    #[test]
+    fn test_sufficient_resources_pass_with_summary() {
+        let result = ResourcesCheck::evaluate(&resources(4096), Some(20 * 1024 * MB));
+        assert!(result.passed);
+        assert_eq!(
+            result.detail.as_deref(),
+            Some("4 CPU(s), 4096 MB of 8192 MB memory available, 20480 MB free disk")
+        );
+    }
+
+    #[test]
+    fn test_low_memory_and_disk_fail() {
+        let result = ResourcesCheck::evaluate(&resources(100), Some(10 * MB));
+        assert!(!result.passed);
+        let detail = result.detail.unwrap();
+        assert!(
+            detail.contains("only 100 MB of memory available"),
+            "{}",
+            detail
+        );
+        assert!(
+            detail.contains("only 10 MB of disk space free"),
+            "{}",
+            detail
+        );
+    }
+
+    #[test]
+    fn test_unknown_disk_space_does_not_fail() {
+        let result = ResourcesCheck::evaluate(&resources(4096), None);
+        assert!(result.passed);
+        assert!(result.detail.unwrap().ends_with("unknown free disk"));
+    }
+}
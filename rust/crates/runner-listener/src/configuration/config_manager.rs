@@ -6,15 +6,17 @@ use anyhow::{Context, Result};
 use runner_common::config_store::{ConfigurationStore, RunnerSettings};
 use runner_common::constants::{self, WellKnownConfigFile, WellKnownDirectory};
 use runner_common::credential_data::CredentialData;
-use runner_common::host_context::HostContext;
+use runner_common::host_context::{HostContext, HostResources};
+use runner_common::terminal::{ConsoleColor, Terminal};
 use runner_common::tracing::Tracing;
 use runner_sdk::TraceWriter;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::command_settings::CommandSettings;
 use crate::configuration::prompt_manager::PromptManager;
-use crate::configuration::rsa_key_manager::RsaKeyManager;
+use crate::configuration::rsa_key_manager::{self, RsaKeyManager};
 use crate::configuration::validators;
 
 // ---------------------------------------------------------------------------
@@ -74,6 +76,21 @@ struct AgentPool {
     is_hosted: bool,
 }
 
+/// Environment variables captured into `.env`, matching the list in `env.sh`.
+const ENV_FILE_VARIABLES: &[&str] = &[
+    "LANG",
+    "JAVA_HOME",
+    "ANT_HOME",
+    "M2_HOME",
+    "ANDROID_HOME",
+    "ANDROID_SDK_ROOT",
+    "GRADLE_HOME",
+    "NVM_BIN",
+    "NVM_PATH",
+    "LD_LIBRARY_PATH",
+    "PERL5LIB",
+];
+
 // ---------------------------------------------------------------------------
 // ConfigManager
 // ---------------------------------------------------------------------------
@@ -85,13 +102,18 @@ struct AgentPool {
 pub struct ConfigManager {
     context: Arc<HostContext>,
     trace: Tracing,
+    terminal: Terminal,
 }
 
 impl ConfigManager {
     /// Create a new `ConfigManager`.
     pub fn new(context: Arc<HostContext>) -> Self {
         let trace = context.get_trace("ConfigManager");
-        Self { context, trace }
+        Self {
+            context,
+            trace,
+            terminal: Terminal::new(),
+        }
     }
 
     /// Configure the runner.
@@ -101,12 +123,13 @@ impl ConfigManager {
     /// 2. Validate inputs
     /// 3. Register the runner with GitHub
     /// 4. Save settings and credentials to disk
-    /// 5. Optionally generate service config
+    /// 5. Optionally generate service config and `.path`/`.env` files
     pub async fn configure_async(&self, settings: &CommandSettings) -> Result<()> {
         self.trace.info("Starting runner configuration");
 
         let config_store = ConfigurationStore::new(&self.context);
-        let prompt = PromptManager::new(settings.is_unattended());
+        let prompt = PromptManager::new(settings.is_unattended())
+            .with_secret_masker(self.context.secret_masker.clone());
 
         // Check if already configured
         if config_store.is_configured() {
@@ -122,14 +145,31 @@ impl ConfigManager {
         // 1. Get the GitHub URL
         let url = match settings.get_url() {
             Some(u) => u,
-            None => prompt.prompt_required("Enter the URL of the repository, org, or enterprise")?,
+            None => prompt.prompt_argument(
+                constants::command_line::args::URL,
+                "Enter the URL of the repository, org, or enterprise",
+            )?,
         };
-        validators::validate_url(&url)?;
+        let config_url = runner_sdk::UrlUtil::normalize_config_url(&url)?;
+        if runner_sdk::UrlUtil::is_insecure(&config_url) {
+            self.terminal.write_line(
+                &format!(
+                    "Warning: '{}' uses plain HTTP; the registration token will be sent unencrypted.",
+                    config_url
+                ),
+                Some(ConsoleColor::Yellow),
+            );
+        }
+        let scope = runner_sdk::UrlUtil::config_scope(&config_url)?;
+        let url = config_url.to_string();
 
         // 2. Get the registration token
         let token = match settings.get_token() {
             Some(t) => t,
-            None => prompt.prompt_required("Enter the registration token")?,
+            None => prompt.prompt_argument(
+                constants::command_line::args::TOKEN,
+                "Enter the registration token",
+            )?,
         };
 
         // 3. Get the runner name (default: hostname)
@@ -161,19 +201,17 @@ impl ConfigManager {
             .get_runner_group()
             .unwrap_or_else(|| "Default".to_string());
 
-        // 7. Parse the GitHub URL to determine API endpoints
-        let parsed_url = url::Url::parse(&url)
-            .context("Invalid URL")?;
-        let is_hosted = runner_sdk::UrlUtil::is_hosted_server(&parsed_url);
+        // 7. Determine whether the URL is hosted or GHES
+        let is_hosted = runner_sdk::UrlUtil::is_hosted_server(&config_url);
 
         self.trace.info(&format!(
-            "Registering runner '{}' at {} (hosted={})",
-            name, url, is_hosted
+            "Registering runner '{}' at {} ({:?}, hosted={})",
+            name, url, scope, is_hosted
         ));
 
         // 8. Exchange the registration token for an access token
         let (server_url, access_token, _client_id, _auth_url) =
-            self.exchange_registration_token(&url, &token, is_hosted).await?;
+            self.exchange_registration_token(&url, &token).await?;
 
         // 9. Generate RSA key pair for credential exchange
         let rsa_manager = RsaKeyManager::new(self.context.clone());
@@ -265,26 +303,90 @@ impl ConfigManager {
             svc_manager.generate_service_config(&runner_settings)?;
         }
 
+        // 15. Write .path/.env for the launcher scripts if requested
+        if settings.is_generate_env_files() {
+            let env: HashMap<String, String> = std::env::vars().collect();
+            Self::write_env_files(&self.context, &env)?;
+        }
+
         self.trace.info(&format!(
             "Runner '{}' configured successfully (ID: {})",
             registration.name, registration.id
         ));
 
-        println!("\n√ Runner successfully added");
-        println!("√ Runner connection is good\n");
+        self.terminal.write_empty_line();
+        self.terminal
+            .write_success_message("Runner successfully added");
+        self.terminal
+            .write_success_message("Runner connection is good");
+        self.terminal.write_empty_line();
 
         if settings.is_generate_service_config() {
-            println!("√ Service configuration generated\n");
+            self.terminal
+                .write_success_message("Service configuration generated");
+            self.terminal.write_empty_line();
         }
 
-        println!("# Runner settings");
-        println!("  Name: {}", registration.name);
-        println!("  URL: {}", url);
-        println!("  Work folder: {}", work);
+        self.terminal.write_line("# Runner settings", None);
+        self.terminal
+            .write_line(&format!("  Name: {}", registration.name), None);
+        self.terminal.write_line(&format!("  URL: {}", url), None);
+        self.terminal
+            .write_line(&format!("  Work folder: {}", work), None);
         if !labels.is_empty() {
-            println!("  Labels: {}", labels);
+            self.terminal
+                .write_line(&format!("  Labels: {}", labels), None);
+        }
+
+        Ok(())
+    }
+
+    /// Write the `.path` and `.env` files that the launcher scripts source.
+    ///
+    /// Mirrors `env.sh`: `.path` holds the resolved `PATH`, and `.env` holds
+    /// `KEY=VALUE` lines for the baseline variables (plus any `JAVA_HOME_*`).
+    /// Lines already in `.env` are kept, so user edits survive reconfiguring;
+    /// only variables not yet present are appended.
+    pub fn write_env_files(context: &HostContext, env: &HashMap<String, String>) -> Result<()> {
+        let path_file = context.get_config_file(WellKnownConfigFile::Path);
+        let path = env.get("PATH").map(String::as_str).unwrap_or_default();
+        std::fs::write(&path_file, format!("{}\n", path))
+            .with_context(|| format!("Failed to write {}", path_file.display()))?;
+
+        let env_file = context.get_config_file(WellKnownConfigFile::Env);
+        let existing = std::fs::read_to_string(&env_file).unwrap_or_default();
+        let mut lines: Vec<String> = existing
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(String::from)
+            .collect();
+        let is_present = |lines: &[String], key: &str| {
+            lines
+                .iter()
+                .any(|l| l.split_once('=').is_some_and(|(k, _)| k.trim() == key))
+        };
+
+        let mut java_homes: Vec<&String> =
+            env.keys().filter(|k| k.starts_with("JAVA_HOME_")).collect();
+        java_homes.sort();
+
+        let keys = ENV_FILE_VARIABLES
+            .iter()
+            .copied()
+            .chain(java_homes.into_iter().map(String::as_str));
+        for key in keys {
+            if let Some(value) = env.get(key) {
+                if !value.is_empty() && !is_present(&lines, key) {
+                    lines.push(format!("{}={}", key, value));
+                }
+            }
         }
 
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        std::fs::write(&env_file, contents)
+            .with_context(|| format!("Failed to write {}", env_file.display()))?;
+
         Ok(())
     }
 
@@ -301,7 +403,7 @@ impl ConfigManager {
         let config_store = ConfigurationStore::new(&self.context);
 
         if !config_store.is_configured() {
-            println!("Runner is not configured.");
+            self.terminal.write_line("Runner is not configured.", None);
             return Ok(());
         }
 
@@ -309,31 +411,24 @@ impl ConfigManager {
             .get_settings()
             .context("Failed to load runner settings for removal")?;
 
-        let prompt = PromptManager::new(settings.is_unattended());
+        let prompt = PromptManager::new(settings.is_unattended())
+            .with_secret_masker(self.context.secret_masker.clone());
 
         // Get the token for removal
         let token = match settings.get_token() {
             Some(t) => t,
             None => match settings.get_pat() {
                 Some(p) => p,
-                None => prompt.prompt_required("Enter the registration/PAT token to remove the runner")?,
+                None => prompt.prompt_argument(
+                    constants::command_line::args::TOKEN,
+                    "Enter the registration/PAT token to remove the runner",
+                )?,
             },
         };
 
-        // Determine the API URL
-        let parsed_url = url::Url::parse(&runner_settings.git_hub_url)
-            .or_else(|_| url::Url::parse(&runner_settings.server_url))
-            .context("No valid URL in runner settings")?;
-
-        let is_hosted = runner_sdk::UrlUtil::is_hosted_server(&parsed_url);
-
         // Exchange token if it's a registration token
         let (server_url, access_token, _, _) = self
-            .exchange_registration_token(
-                &runner_settings.git_hub_url,
-                &token,
-                is_hosted,
-            )
+            .exchange_registration_token(&runner_settings.git_hub_url, &token)
             .await
             .unwrap_or_else(|_| {
                 // If exchange fails, use the token directly (it might be a PAT)
@@ -376,7 +471,9 @@ impl ConfigManager {
         let _ = std::fs::remove_file(&service_path);
 
         self.trace.info("Runner removed successfully");
-        println!("\n√ Runner removed successfully");
+        self.terminal.write_empty_line();
+        self.terminal
+            .write_success_message("Runner removed successfully");
 
         Ok(())
     }
@@ -386,7 +483,8 @@ impl ConfigManager {
     // -----------------------------------------------------------------------
 
     /// Exchange a registration token for tenant credentials by calling
-    /// `POST api.github.com/actions/runner-registration` with `RemoteAuth <token>`.
+    /// `POST <api>/actions/runner-registration` with `RemoteAuth <token>`,
+    /// where `<api>` is `api.github.com` or the GHES `/api/v3` endpoint.
     ///
     /// This matches the C# `GetTenantCredential` method. The response contains
     /// the Actions service tenant URL, an OAuth access token, and whether to use
@@ -397,22 +495,11 @@ impl ConfigManager {
         &self,
         github_url: &str,
         token: &str,
-        is_hosted: bool,
     ) -> Result<(String, String, String, Option<String>)> {
-        let parsed = url::Url::parse(github_url).context("Invalid GitHub URL")?;
-
-        let api_url = if is_hosted {
-            format!(
-                "https://api.{}/actions/runner-registration",
-                parsed.host_str().unwrap_or("github.com")
-            )
-        } else {
-            format!(
-                "{}://{}/api/v3/actions/runner-registration",
-                parsed.scheme(),
-                parsed.host_str().unwrap_or("")
-            )
-        };
+        let mut server = url::Url::parse(github_url).context("Invalid GitHub URL")?;
+        // The registration URL names an owner or repo; the API is at the root
+        server.set_path("");
+        let api_url = runner_sdk::UrlUtil::api_url(&server, "actions/runner-registration");
 
         let body = serde_json::json!({
             "url": github_url,
@@ -561,6 +648,16 @@ impl ConfigManager {
         })
     }
 
+    /// Host resources reported as system capabilities, so autoscalers can
+    /// size work for the runner.
+    fn system_capabilities(resources: &HostResources) -> serde_json::Value {
+        serde_json::json!({
+            "ProcessorCount": resources.cpu_count.to_string(),
+            "TotalMemoryMB": resources.total_memory_mb().to_string(),
+            "AvailableMemoryMB": resources.available_memory_mb().to_string(),
+        })
+    }
+
     /// Register the runner with the Actions service.
     ///
     /// Matches the C# `_runnerServer.AddAgentAsync(poolId, agent)` call path.
@@ -602,28 +699,14 @@ impl ConfigManager {
         }
 
         // 3. Build the RSA public key in the format the server expects
-        //    C# sends exponent and modulus as base64-encoded byte arrays
-        use rsa::pkcs8::DecodePublicKey;
-        use rsa::traits::PublicKeyParts;
-        use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
-
-        let rsa_pub = rsa::RsaPublicKey::from_public_key_pem(public_key_pem)
-            .context("Failed to parse RSA public key PEM")?;
-        let exponent_bytes = rsa_pub.e().to_bytes_be();
-        let modulus_bytes = rsa_pub.n().to_bytes_be();
-        let exponent_b64 = BASE64.encode(&exponent_bytes);
-        let modulus_b64 = BASE64.encode(&modulus_bytes);
-
-        let public_key = serde_json::json!({
-            "exponent": exponent_b64,
-            "modulus": modulus_b64,
-        });
+        let public_key = rsa_key_manager::public_key_json(public_key_pem)?;
 
         let body = serde_json::json!({
             "name": name,
             "version": runner_sdk::build_constants::RunnerPackage::VERSION,
             "osDescription": format!("{} {}", constants::CURRENT_PLATFORM, constants::CURRENT_ARCHITECTURE),
             "labels": label_list,
+            "systemCapabilities": Self::system_capabilities(&self.context.host_resources()),
             "runnerGroupName": runner_group,
             "ephemeral": ephemeral,
             "disableUpdate": disable_update,
@@ -728,3 +811,108 @@ impl ConfigManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_context(root: &std::path::Path) -> Arc<HostContext> {
+        let context = HostContext::new("Runner");
+        context.set_root_override(root.to_path_buf());
+        context
+    }
+
+    #[test]
+    fn test_write_env_files() {
+        let root = tempfile::tempdir().unwrap();
+        let context = make_context(root.path());
+
+        let env: HashMap<String, String> = [
+            ("PATH", "/usr/local/bin:/usr/bin:/bin"),
+            ("LANG", "C.UTF-8"),
+            ("JAVA_HOME", "/opt/java/17"),
+            ("JAVA_HOME_11_X64", "/opt/java/11"),
+            ("HOME", "/home/runner"),
+            ("GRADLE_HOME", ""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        ConfigManager::write_env_files(&context, &env).unwrap();
+
+        let path = std::fs::read_to_string(root.path().join(".path")).unwrap();
+        assert_eq!(path, "/usr/local/bin:/usr/bin:/bin\n");
+
+        let dotenv = std::fs::read_to_string(root.path().join(".env")).unwrap();
+        assert_eq!(
+            dotenv,
+            "LANG=C.UTF-8\nJAVA_HOME=/opt/java/17\nJAVA_HOME_11_X64=/opt/java/11\n"
+        );
+    }
+
+    #[test]
+    fn test_write_env_files_keeps_existing_entries() {
+        let root = tempfile::tempdir().unwrap();
+        let context = make_context(root.path());
+        std::fs::write(root.path().join(".env"), "LANG=en_US.UTF-8\nCUSTOM=1\n").unwrap();
+
+        let env: HashMap<String, String> = [
+            ("PATH", "/bin"),
+            ("LANG", "C.UTF-8"),
+            ("JAVA_HOME", "/opt/java/17"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        ConfigManager::write_env_files(&context, &env).unwrap();
+
+        let dotenv = std::fs::read_to_string(root.path().join(".env")).unwrap();
+        assert_eq!(
+            dotenv,
+            "LANG=en_US.UTF-8\nCUSTOM=1\nJAVA_HOME=/opt/java/17\n"
+        );
+    }
+
+    #[test]
+    fn test_system_capabilities_report_resources() {
+        let resources = HostResources {
+            cpu_count: 8,
+            total_memory_bytes: 16 * 1024 * 1024 * 1024,
+            available_memory_bytes: 6 * 1024 * 1024 * 1024,
+        };
+
+        let capabilities = ConfigManager::system_capabilities(&resources);
+        assert_eq!(
+            capabilities,
+            serde_json::json!({
+                "ProcessorCount": "8",
+                "TotalMemoryMB": "16384",
+                "AvailableMemoryMB": "6144",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_configure_without_tty_errors_on_missing_value() {
+        let root = tempfile::tempdir().unwrap();
+        let manager = ConfigManager::new(make_context(root.path()));
+        let settings = CommandSettings::parse_from(&[
+            "configure".to_string(),
+            "--url".to_string(),
+            "https://github.com/owner/repo".to_string(),
+        ])
+        .with_interactive(false);
+
+        // Would block reading stdin if it prompted for the token
+        let err = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            manager.configure_async(&settings),
+        )
+        .await
+        .expect("configuration prompted instead of failing")
+        .unwrap_err();
+        assert_eq!(err.to_string(), "--token is required in unattended mode");
+    }
+}
@@ -2,7 +2,11 @@
 // Handles interactive and unattended prompts for runner configuration.
 
 use anyhow::Result;
+use runner_common::secret_masker::SecretMasker;
 use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+use crate::command_settings::CommandSettings;
 
 /// Manages user prompts during configuration.
 ///
@@ -10,6 +14,7 @@ use std::io::{self, BufRead, Write};
 /// In interactive mode, the user is prompted via stdin/stdout.
 pub struct PromptManager {
     unattended: bool,
+    secret_masker: Option<Arc<SecretMasker>>,
 }
 
 impl PromptManager {
@@ -17,7 +22,16 @@ impl PromptManager {
     ///
     /// If `unattended` is true, no interactive prompts are shown.
     pub fn new(unattended: bool) -> Self {
-        Self { unattended }
+        Self {
+            unattended,
+            secret_masker: None,
+        }
+    }
+
+    /// Register secrets entered at a prompt with `masker`.
+    pub fn with_secret_masker(mut self, masker: Arc<SecretMasker>) -> Self {
+        self.secret_masker = Some(masker);
+        self
     }
 
     /// Prompt for a required value (no default).
@@ -51,11 +65,7 @@ impl PromptManager {
     /// Prompt for a value with a default.
     ///
     /// In unattended mode, the default is used without prompting.
-    pub fn prompt_with_default(
-        &self,
-        prompt_text: &str,
-        default: &str,
-    ) -> Result<String> {
+    pub fn prompt_with_default(&self, prompt_text: &str, default: &str) -> Result<String> {
         if self.unattended {
             return Ok(default.to_string());
         }
@@ -77,11 +87,7 @@ impl PromptManager {
     /// Prompt for a yes/no confirmation.
     ///
     /// In unattended mode, returns the `default_yes` value.
-    pub fn prompt_yes_no(
-        &self,
-        prompt_text: &str,
-        default_yes: bool,
-    ) -> Result<bool> {
+    pub fn prompt_yes_no(&self, prompt_text: &str, default_yes: bool) -> Result<bool> {
         if self.unattended {
             return Ok(default_yes);
         }
@@ -102,7 +108,8 @@ impl PromptManager {
         }
     }
 
-    /// Prompt for a secret value (input is not echoed).
+    /// Prompt for a required secret value. Input is not echoed, and the
+    /// value is registered with the secret masker so it never reaches a log.
     ///
     /// In unattended mode, returns an error.
     pub fn prompt_secret(&self, prompt_text: &str) -> Result<String> {
@@ -113,55 +120,98 @@ impl PromptManager {
             ));
         }
 
-        print!("{}: ", prompt_text);
-        io::stdout().flush()?;
+        loop {
+            print!("{}: ", prompt_text);
+            io::stdout().flush()?;
 
-        // Try to disable echo for password input
-        #[cfg(unix)]
-        {
-            let stdin_handle = io::stdin();
-            let original = nix::sys::termios::tcgetattr(&stdin_handle).ok();
-
-            if let Some(ref orig) = original {
-                let mut noecho = orig.clone();
-                noecho.local_flags.remove(nix::sys::termios::LocalFlags::ECHO);
-                let _ = nix::sys::termios::tcsetattr(
-                    &stdin_handle,
-                    nix::sys::termios::SetArg::TCSANOW,
-                    &noecho,
-                );
+            let value = read_line_without_echo()?;
+            if !value.is_empty() {
+                if let Some(ref masker) = self.secret_masker {
+                    masker.add_value(&value);
+                }
+                return Ok(value);
             }
 
-            let mut input = String::new();
-            stdin_handle.lock().read_line(&mut input)?;
-            println!(); // Print newline since echo was disabled
-
-            // Restore echo
-            if let Some(ref orig) = original {
-                let _ = nix::sys::termios::tcsetattr(
-                    &stdin_handle,
-                    nix::sys::termios::SetArg::TCSANOW,
-                    orig,
-                );
-            }
+            println!("  (value is required)");
+        }
+    }
 
-            Ok(input.trim().to_string())
+    /// Prompt for the value of a required command-line argument, without
+    /// echoing it when the argument is a secret such as `--token` or `--pat`.
+    ///
+    /// In unattended mode, returns an error naming the missing flag.
+    pub fn prompt_argument(&self, argument: &str, prompt_text: &str) -> Result<String> {
+        if self.unattended {
+            return Err(anyhow::anyhow!(
+                "--{} is required in unattended mode",
+                argument
+            ));
         }
 
-        #[cfg(not(unix))]
-        {
-            let mut input = String::new();
-            io::stdin().lock().read_line(&mut input)?;
-            Ok(input.trim().to_string())
+        if Self::is_secret_argument(argument) {
+            self.prompt_secret(prompt_text)
+        } else {
+            self.prompt_required(prompt_text)
         }
     }
 
+    /// Whether an argument holds a secret and must be prompted for without echo.
+    pub fn is_secret_argument(argument: &str) -> bool {
+        CommandSettings::secret_arg_names()
+            .iter()
+            .any(|secret| secret.eq_ignore_ascii_case(argument))
+    }
+
     /// Whether we are in unattended mode.
     pub fn is_unattended(&self) -> bool {
         self.unattended
     }
 }
 
+/// Read a trimmed line from stdin with terminal echo turned off, where the
+/// terminal allows it.
+fn read_line_without_echo() -> Result<String> {
+    let mut input = String::new();
+
+    #[cfg(unix)]
+    {
+        let stdin_handle = io::stdin();
+        let original = nix::sys::termios::tcgetattr(&stdin_handle).ok();
+
+        if let Some(ref orig) = original {
+            let mut noecho = orig.clone();
+            noecho
+                .local_flags
+                .remove(nix::sys::termios::LocalFlags::ECHO);
+            let _ = nix::sys::termios::tcsetattr(
+                &stdin_handle,
+                nix::sys::termios::SetArg::TCSANOW,
+                &noecho,
+            );
+        }
+
+        let read = stdin_handle.lock().read_line(&mut input);
+
+        // Restore echo, even when the read failed
+        if let Some(ref orig) = original {
+            let _ = nix::sys::termios::tcsetattr(
+                &stdin_handle,
+                nix::sys::termios::SetArg::TCSANOW,
+                orig,
+            );
+            println!(); // Print newline since echo was disabled
+        }
+        read?;
+    }
+
+    #[cfg(not(unix))]
+    {
+        io::stdin().lock().read_line(&mut input)?;
+    }
+
+    Ok(input.trim().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,4 +236,29 @@ mod tests {
         assert!(pm.prompt_yes_no("test", true).unwrap());
         assert!(!pm.prompt_yes_no("test", false).unwrap());
     }
+
+    #[test]
+    fn test_secret_arguments_use_secret_prompt() {
+        use runner_common::constants::command_line::args;
+
+        assert!(PromptManager::is_secret_argument(args::TOKEN));
+        assert!(PromptManager::is_secret_argument(args::PAT));
+        assert!(PromptManager::is_secret_argument("Token"));
+        assert!(!PromptManager::is_secret_argument(args::URL));
+    }
+
+    #[test]
+    fn test_unattended_argument_names_missing_flag() {
+        use runner_common::constants::command_line::args;
+
+        let pm = PromptManager::new(true);
+        for (argument, expected) in [
+            (args::URL, "--url is required in unattended mode"),
+            (args::TOKEN, "--token is required in unattended mode"),
+            (args::PAT, "--pat is required in unattended mode"),
+        ] {
+            let err = pm.prompt_argument(argument, "Enter a value").unwrap_err();
+            assert_eq!(err.to_string(), expected);
+        }
+    }
 }
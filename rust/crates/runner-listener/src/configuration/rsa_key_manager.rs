@@ -1,18 +1,111 @@
 // RsaKeyManager mapping `RSAKeyManager.cs`.
 // Generates RSA key pairs and saves them to disk for OAuth credential exchange.
+//
+// The key can be rotated without re-registering the runner: the new private
+// key is staged next to the current one, along with the credentials
+// re-encrypted under it when they are stored encrypted. The public key is
+// then sent to the server, and only once the server accepts it are the
+// staged files renamed over the current ones. A rejected key leaves the
+// current key and credentials in place.
 
 use anyhow::{Context, Result};
-use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
-use rsa::RsaPrivateKey;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rsa::pkcs8::{DecodePublicKey, EncodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use runner_common::config_store::{ConfigurationStore, RunnerSettings};
 use runner_common::constants::WellKnownConfigFile;
 use runner_common::host_context::HostContext;
 use runner_common::tracing::Tracing;
 use runner_sdk::TraceWriter;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::configuration::credential_manager::CredentialManager;
+
 /// RSA key size in bits.
 const RSA_KEY_SIZE: usize = 2048;
 
+/// The public key in the form the server expects: exponent and modulus as
+/// base64-encoded big-endian byte arrays, as the C# runner sends them.
+pub fn public_key_json(public_key_pem: &str) -> Result<serde_json::Value> {
+    let public_key = RsaPublicKey::from_public_key_pem(public_key_pem)
+        .context("Failed to parse RSA public key PEM")?;
+    Ok(serde_json::json!({
+        "exponent": BASE64.encode(public_key.e().to_bytes_be()),
+        "modulus": BASE64.encode(public_key.n().to_bytes_be()),
+    }))
+}
+
+/// Sends a runner's new public key to the server during key rotation.
+#[async_trait]
+pub trait PublicKeyRegistrar: Send + Sync {
+    /// Replace the runner's public key; an error means the key was rejected.
+    async fn update_public_key(&self, public_key_pem: &str) -> Result<()>;
+}
+
+/// Updates the public key of a registered runner on the Actions service.
+pub struct AgentPublicKeyRegistrar {
+    context: Arc<HostContext>,
+    server_url: String,
+    pool_id: i32,
+    agent_id: u64,
+    token: String,
+}
+
+impl AgentPublicKeyRegistrar {
+    /// Update the runner described by `settings`, authenticating with `token`.
+    pub fn new(context: Arc<HostContext>, settings: &RunnerSettings, token: String) -> Self {
+        Self {
+            context,
+            server_url: settings.server_url.clone(),
+            pool_id: settings.pool_id,
+            agent_id: settings.agent_id,
+            token,
+        }
+    }
+}
+
+#[async_trait]
+impl PublicKeyRegistrar for AgentPublicKeyRegistrar {
+    async fn update_public_key(&self, public_key_pem: &str) -> Result<()> {
+        let client = runner_common::HttpClientFactory::create_client(&self.context.web_proxy)?;
+        let url = format!(
+            "{}/_apis/distributedtask/pools/{}/agents/{}",
+            self.server_url.trim_end_matches('/'),
+            self.pool_id,
+            self.agent_id
+        );
+        let body = serde_json::json!({
+            "id": self.agent_id,
+            "authorization": {
+                "publicKey": public_key_json(public_key_pem)?,
+            },
+        });
+
+        let response = client
+            .patch(&url)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/json;api-version=6.0-preview")
+            .json(&body)
+            .send()
+            .await
+            .context("Failed to send public key update request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Public key update failed with HTTP {}: {}",
+                status.as_u16(),
+                body_text
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Manages RSA key pairs for OAuth credential exchange.
 ///
 /// Maps `RSAKeyManager` in the C# runner. The RSA key pair is used to
@@ -34,21 +127,7 @@ impl RsaKeyManager {
     /// Returns the public key in PEM format (for sending to the server).
     pub fn generate_and_save_key(&self) -> Result<String> {
         self.trace.info("Generating RSA key pair...");
-
-        let mut rng = rand::thread_rng();
-        let private_key = RsaPrivateKey::new(&mut rng, RSA_KEY_SIZE)
-            .context("Failed to generate RSA private key")?;
-
-        // Serialize private key to PEM
-        let private_pem = private_key
-            .to_pkcs8_pem(LineEnding::LF)
-            .context("Failed to serialize RSA private key to PEM")?;
-
-        // Serialize public key to PEM
-        let public_key = private_key.to_public_key();
-        let public_pem = public_key
-            .to_public_key_pem(LineEnding::LF)
-            .context("Failed to serialize RSA public key to PEM")?;
+        let (private_pem, public_pem) = Self::generate_key_pair()?;
 
         // Save the private key to disk
         let key_path = self
@@ -61,17 +140,7 @@ impl RsaKeyManager {
                 .context("Failed to delete existing RSA key file")?;
         }
 
-        std::fs::write(&key_path, private_pem.as_bytes())
-            .context("Failed to write RSA private key to disk")?;
-
-        // Set restrictive permissions on Unix
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let perms = std::fs::Permissions::from_mode(0o600);
-            std::fs::set_permissions(&key_path, perms)
-                .context("Failed to set permissions on RSA key file")?;
-        }
+        write_private_key(&key_path, &private_pem)?;
 
         self.trace.info(&format!(
             "RSA key pair generated and saved to {:?}",
@@ -81,6 +150,119 @@ impl RsaKeyManager {
         Ok(public_pem)
     }
 
+    /// Rotate the key of the configured runner.
+    ///
+    /// Authenticates with the current credentials, registers a new public
+    /// key with the server and then switches to the new private key.
+    /// Encrypted credentials are re-encrypted under the new key.
+    pub async fn rotate_key(&self) -> Result<()> {
+        let config_store = ConfigurationStore::new(&self.context);
+        let encrypted = config_store.has_encrypted_credentials();
+        let config_store = config_store.with_credential_encryption(encrypted);
+        let settings = config_store
+            .get_settings()
+            .context("Failed to load runner settings for key rotation")?;
+        let credentials = config_store
+            .get_credentials()
+            .context("Failed to load credentials for key rotation")?;
+        let token = CredentialManager::new(self.context.clone())
+            .create_provider(&credentials)?
+            .get_token()
+            .await
+            .context("Failed to authenticate for key rotation")?;
+
+        let registrar = AgentPublicKeyRegistrar::new(self.context.clone(), &settings, token);
+        self.rotate_key_with(&registrar, &config_store).await
+    }
+
+    /// Generate a new key pair, register its public key through `registrar`
+    /// and swap in the new private key once the registrar accepts it.
+    /// Credentials that `config_store` holds encrypted are re-encrypted under
+    /// the new key and swapped in with it.
+    ///
+    /// If the registrar fails, the current key and credentials files are
+    /// left untouched.
+    pub async fn rotate_key_with(
+        &self,
+        registrar: &dyn PublicKeyRegistrar,
+        config_store: &ConfigurationStore,
+    ) -> Result<()> {
+        if !self.has_key() {
+            anyhow::bail!("The runner has no RSA key to rotate");
+        }
+
+        self.trace.info("Rotating RSA key pair...");
+        let (private_pem, public_pem) = Self::generate_key_pair()?;
+
+        let key_path = self
+            .context
+            .get_config_file(WellKnownConfigFile::RSACredentials);
+        let pending_path = pending_key_path(&key_path);
+        write_private_key(&pending_path, &private_pem)?;
+
+        // Encrypted credentials can only be read with the key they were
+        // written under, so they move to the new key together with it
+        let staged_credential = if config_store.has_encrypted_credentials() {
+            let staged = config_store
+                .get_credentials()
+                .and_then(|credentials| config_store.stage_credential(&credentials, &private_pem));
+            match staged {
+                Ok(staged) => Some(staged),
+                Err(e) => {
+                    let _ = std::fs::remove_file(&pending_path);
+                    return Err(e.context("Failed to re-encrypt credentials under the new RSA key"));
+                }
+            }
+        } else {
+            None
+        };
+
+        if let Err(e) = registrar.update_public_key(&public_pem).await {
+            let _ = std::fs::remove_file(&pending_path);
+            if let Some(staged) = staged_credential {
+                config_store.discard_staged_credential(staged);
+            }
+            self.trace.warning(&format!(
+                "New RSA key was rejected, keeping the current key: {:#}",
+                e
+            ));
+            return Err(e.context("Failed to register the new RSA public key"));
+        }
+
+        std::fs::rename(&pending_path, &key_path).with_context(|| {
+            format!(
+                "The server accepted the new RSA key but {:?} could not be moved into place",
+                pending_path
+            )
+        })?;
+        if let Some(staged) = staged_credential {
+            config_store.commit_staged_credential(staged)?;
+        }
+
+        self.trace.info("RSA key pair rotated");
+        Ok(())
+    }
+
+    /// Generate a key pair, returning the private and public keys as PEM.
+    fn generate_key_pair() -> Result<(String, String)> {
+        let mut rng = rand::thread_rng();
+        let private_key = RsaPrivateKey::new(&mut rng, RSA_KEY_SIZE)
+            .context("Failed to generate RSA private key")?;
+
+        // Serialize private key to PEM
+        let private_pem = private_key
+            .to_pkcs8_pem(LineEnding::LF)
+            .context("Failed to serialize RSA private key to PEM")?;
+
+        // Serialize public key to PEM
+        let public_key = private_key.to_public_key();
+        let public_pem = public_key
+            .to_public_key_pem(LineEnding::LF)
+            .context("Failed to serialize RSA public key to PEM")?;
+
+        Ok((private_pem.to_string(), public_pem))
+    }
+
     /// Load the existing RSA private key from disk.
     pub fn load_private_key(&self) -> Result<String> {
         let key_path = self
@@ -116,3 +298,192 @@ impl RsaKeyManager {
         Ok(())
     }
 }
+
+/// Where a rotated key is staged until the server accepts it.
+fn pending_key_path(key_path: &Path) -> PathBuf {
+    let mut name = key_path.as_os_str().to_owned();
+    name.push(".new");
+    PathBuf::from(name)
+}
+
+/// Write a private key readable only by the runner's user.
+fn write_private_key(path: &Path, private_pem: &str) -> Result<()> {
+    std::fs::write(path, private_pem.as_bytes())
+        .context("Failed to write RSA private key to disk")?;
+
+    // Set restrictive permissions on Unix
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = std::fs::Permissions::from_mode(0o600);
+        std::fs::set_permissions(path, perms)
+            .context("Failed to set permissions on RSA key file")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runner_common::credential_data::CredentialData;
+    use std::sync::Mutex;
+
+    /// Records the public key it is given, then accepts or rejects it.
+    struct FakeRegistrar {
+        accept: bool,
+        received: Mutex<Option<String>>,
+    }
+
+    impl FakeRegistrar {
+        fn new(accept: bool) -> Self {
+            Self {
+                accept,
+                received: Mutex::new(None),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl PublicKeyRegistrar for FakeRegistrar {
+        async fn update_public_key(&self, public_key_pem: &str) -> Result<()> {
+            *self.received.lock().unwrap() = Some(public_key_pem.to_string());
+            if self.accept {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!("HTTP 400: invalid public key"))
+            }
+        }
+    }
+
+    fn manager(root: &Path) -> RsaKeyManager {
+        let context = HostContext::new("Runner");
+        context.set_root_override(root.to_path_buf());
+        RsaKeyManager::new(context)
+    }
+
+    fn config_store(manager: &RsaKeyManager) -> ConfigurationStore {
+        ConfigurationStore::new(&manager.context).with_credential_encryption(true)
+    }
+
+    fn oauth_credential() -> CredentialData {
+        let mut credential = CredentialData::new("OAuth");
+        credential.client_id = Some("client-id".to_string());
+        credential
+    }
+
+    fn public_pem_of(private_pem: &str) -> String {
+        use rsa::pkcs8::DecodePrivateKey;
+        RsaPrivateKey::from_pkcs8_pem(private_pem)
+            .unwrap()
+            .to_public_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_swaps_key_after_server_accepts() {
+        let root = tempfile::tempdir().unwrap();
+        let manager = manager(root.path());
+        let old_public = manager.generate_and_save_key().unwrap();
+
+        let registrar = FakeRegistrar::new(true);
+        manager
+            .rotate_key_with(&registrar, &config_store(&manager))
+            .await
+            .unwrap();
+
+        let new_private = manager.load_private_key().unwrap();
+        let registered = registrar.received.lock().unwrap().clone().unwrap();
+        assert_eq!(public_pem_of(&new_private), registered);
+        assert_ne!(registered, old_public);
+        assert!(!pending_key_path(&root.path().join(".credentials_rsaparams")).exists());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_keeps_current_key_when_rejected() {
+        let root = tempfile::tempdir().unwrap();
+        let manager = manager(root.path());
+        manager.generate_and_save_key().unwrap();
+        let old_private = manager.load_private_key().unwrap();
+
+        let registrar = FakeRegistrar::new(false);
+        let err = manager
+            .rotate_key_with(&registrar, &config_store(&manager))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("invalid public key"),
+            "{:#}",
+            err
+        );
+
+        assert!(registrar.received.lock().unwrap().is_some());
+        assert_eq!(manager.load_private_key().unwrap(), old_private);
+        assert!(!pending_key_path(&root.path().join(".credentials_rsaparams")).exists());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_re_encrypts_credentials_under_new_key() {
+        let root = tempfile::tempdir().unwrap();
+        let manager = manager(root.path());
+        manager.generate_and_save_key().unwrap();
+        config_store(&manager)
+            .save_credential(&oauth_credential())
+            .unwrap();
+        let cred_path = root.path().join(".credentials");
+        let old_contents = std::fs::read_to_string(&cred_path).unwrap();
+
+        manager
+            .rotate_key_with(&FakeRegistrar::new(true), &config_store(&manager))
+            .await
+            .unwrap();
+
+        assert_ne!(std::fs::read_to_string(&cred_path).unwrap(), old_contents);
+        let credentials = ConfigurationStore::new(&manager.context)
+            .get_credentials()
+            .unwrap();
+        assert_eq!(credentials.client_id.as_deref(), Some("client-id"));
+        assert!(!root.path().join(".credentials.tmp").exists());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_rotation_keeps_encrypted_credentials() {
+        let root = tempfile::tempdir().unwrap();
+        let manager = manager(root.path());
+        manager.generate_and_save_key().unwrap();
+        config_store(&manager)
+            .save_credential(&oauth_credential())
+            .unwrap();
+        let cred_path = root.path().join(".credentials");
+        let old_contents = std::fs::read_to_string(&cred_path).unwrap();
+
+        manager
+            .rotate_key_with(&FakeRegistrar::new(false), &config_store(&manager))
+            .await
+            .unwrap_err();
+
+        assert_eq!(std::fs::read_to_string(&cred_path).unwrap(), old_contents);
+        assert!(!root.path().join(".credentials.tmp").exists());
+        let credentials = ConfigurationStore::new(&manager.context)
+            .get_credentials()
+            .unwrap();
+        assert_eq!(credentials.client_id.as_deref(), Some("client-id"));
+    }
+
+    #[test]
+    fn test_public_key_json() {
+        let root = tempfile::tempdir().unwrap();
+        let public_pem = manager(root.path()).generate_and_save_key().unwrap();
+        let json = public_key_json(&public_pem).unwrap();
+        // 65537
+        assert_eq!(json["exponent"], "AQAB");
+        assert_eq!(
+            BASE64
+                .decode(json["modulus"].as_str().unwrap())
+                .unwrap()
+                .len(),
+            RSA_KEY_SIZE / 8
+        );
+    }
+}
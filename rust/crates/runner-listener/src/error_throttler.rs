@@ -1,71 +1,115 @@
 // ErrorThrottler mapping the C# error throttling in Runner.cs.
-// Provides exponential backoff (1s to 60s) for retryable errors in the message loop.
+// Provides jittered exponential backoff (1s to 60s) for retryable errors in
+// the message loop. The jitter spreads retries out so a fleet of runners
+// that failed together does not retry in lockstep.
 
+use rand::Rng;
 use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 
 /// Minimum backoff delay.
 const MIN_BACKOFF: Duration = Duration::from_secs(1);
 
-/// Maximum backoff delay.
-const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Maximum backoff delay, unless configured otherwise.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
 
 /// Multiplier for exponential growth.
 const BACKOFF_MULTIPLIER: f64 = 2.0;
 
+/// Fraction by which a delay is randomly lengthened or shortened, unless
+/// configured otherwise.
+pub const DEFAULT_JITTER: f64 = 0.2;
+
 /// Exponential backoff error throttler.
 ///
-/// Each call to `increment_and_wait` doubles the delay (capped at 60s).
-/// Calling `reset` returns the delay to 1s.
+/// Each call to `increment_and_wait` doubles the backoff (capped at 60s).
+/// The delay actually waited is the backoff with up to ±20% jitter, and
+/// never exceeds the cap. Calling `reset` returns the backoff to 1s.
 pub struct ErrorThrottler {
+    /// The un-jittered exponential backoff.
+    backoff: Duration,
+    /// The delay the next wait will use.
     current_delay: Duration,
+    max_backoff: Duration,
+    jitter: f64,
 }
 
 impl ErrorThrottler {
     /// Create a new `ErrorThrottler` starting at the minimum backoff.
     pub fn new() -> Self {
-        Self {
+        let mut throttler = Self {
+            backoff: MIN_BACKOFF,
             current_delay: MIN_BACKOFF,
-        }
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            jitter: DEFAULT_JITTER,
+        };
+        throttler.current_delay = throttler.jittered(MIN_BACKOFF);
+        throttler
+    }
+
+    /// Cap the backoff at `max_backoff` instead of `DEFAULT_MAX_BACKOFF`.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff.max(MIN_BACKOFF);
+        self.current_delay = self.jittered(self.backoff);
+        self
+    }
+
+    /// Randomize delays by up to `jitter` (a fraction, clamped to 0..=1) of
+    /// the backoff in either direction; `0.0` disables jitter.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self.current_delay = self.jittered(self.backoff);
+        self
     }
 
     /// Reset the backoff delay to the minimum.
     pub fn reset(&mut self) {
-        self.current_delay = MIN_BACKOFF;
+        self.backoff = MIN_BACKOFF;
+        self.current_delay = self.jittered(MIN_BACKOFF);
     }
 
-    /// Returns the current delay without incrementing.
+    /// Returns the delay the next wait will use, jitter included.
     pub fn current_delay(&self) -> Duration {
         self.current_delay
     }
 
-    /// Increment the delay and sleep for the current period.
+    /// Returns the configured cap on the delay.
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+
+    /// Sleep for the current delay, then increment it.
     ///
     /// Returns `true` if the delay completed normally, `false` if cancelled.
     pub async fn increment_and_wait(&mut self, cancel: CancellationToken) -> bool {
         let delay = self.current_delay;
 
-        tracing::warn!(
-            "Error throttling: waiting {:.1}s before retry",
-            delay.as_secs_f64()
-        );
-
         let completed = tokio::select! {
             _ = tokio::time::sleep(delay) => true,
             _ = cancel.cancelled() => false,
         };
 
         // Increment delay for next time
-        let next_ms = (delay.as_millis() as f64 * BACKOFF_MULTIPLIER) as u64;
-        self.current_delay = Duration::from_millis(next_ms).min(MAX_BACKOFF);
+        self.increment();
 
         completed
     }
 
     /// Just increment the delay without waiting (useful when you handle the delay elsewhere).
     pub fn increment(&mut self) {
-        let next_ms = (self.current_delay.as_millis() as f64 * BACKOFF_MULTIPLIER) as u64;
-        self.current_delay = Duration::from_millis(next_ms).min(MAX_BACKOFF);
+        let next_ms = (self.backoff.as_millis() as f64 * BACKOFF_MULTIPLIER) as u64;
+        self.backoff = Duration::from_millis(next_ms).min(self.max_backoff);
+        self.current_delay = self.jittered(self.backoff);
+    }
+
+    /// `backoff` randomized by the jitter fraction, capped at the maximum.
+    fn jittered(&self, backoff: Duration) -> Duration {
+        let factor = if self.jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - self.jitter..=1.0 + self.jitter)
+        } else {
+            1.0
+        };
+        backoff.mul_f64(factor).min(self.max_backoff)
     }
 }
 
@@ -79,15 +123,29 @@ impl Default for ErrorThrottler {
 mod tests {
     use super::*;
 
+    fn without_jitter() -> ErrorThrottler {
+        ErrorThrottler::new().with_jitter(0.0)
+    }
+
+    /// Whether `delay` is within the jitter bounds around `backoff`.
+    fn within_jitter(delay: Duration, backoff: Duration) -> bool {
+        delay >= backoff.mul_f64(1.0 - DEFAULT_JITTER)
+            && delay <= backoff.mul_f64(1.0 + DEFAULT_JITTER)
+    }
+
     #[test]
     fn test_initial_delay() {
-        let throttler = ErrorThrottler::new();
+        let throttler = without_jitter();
         assert_eq!(throttler.current_delay(), MIN_BACKOFF);
+        assert!(within_jitter(
+            ErrorThrottler::new().current_delay(),
+            MIN_BACKOFF
+        ));
     }
 
     #[test]
     fn test_increment() {
-        let mut throttler = ErrorThrottler::new();
+        let mut throttler = without_jitter();
         throttler.increment();
         assert_eq!(throttler.current_delay(), Duration::from_secs(2));
         throttler.increment();
@@ -96,19 +154,59 @@ mod tests {
 
     #[test]
     fn test_max_backoff() {
-        let mut throttler = ErrorThrottler::new();
+        let mut throttler = without_jitter();
         for _ in 0..20 {
             throttler.increment();
         }
-        assert_eq!(throttler.current_delay(), MAX_BACKOFF);
+        assert_eq!(throttler.current_delay(), DEFAULT_MAX_BACKOFF);
     }
 
     #[test]
     fn test_reset() {
-        let mut throttler = ErrorThrottler::new();
+        let mut throttler = without_jitter();
         throttler.increment();
         throttler.increment();
         throttler.reset();
         assert_eq!(throttler.current_delay(), MIN_BACKOFF);
     }
+
+    #[test]
+    fn test_jittered_delay_grows_within_bounds() {
+        let mut throttler = ErrorThrottler::new();
+        let mut backoff = MIN_BACKOFF;
+        for _ in 0..5 {
+            let delay = throttler.current_delay();
+            assert!(
+                within_jitter(delay, backoff),
+                "{:?} vs {:?}",
+                delay,
+                backoff
+            );
+            throttler.increment();
+            backoff *= 2;
+        }
+        assert!(within_jitter(throttler.current_delay(), backoff));
+        assert_eq!(backoff, Duration::from_secs(32));
+    }
+
+    #[test]
+    fn test_jitter_varies_delays() {
+        let delays: std::collections::HashSet<Duration> = (0..20)
+            .map(|_| ErrorThrottler::new().current_delay())
+            .collect();
+        assert!(delays.len() > 1);
+    }
+
+    #[test]
+    fn test_configured_cap_applies_with_jitter() {
+        let cap = Duration::from_secs(5);
+        let mut throttler = ErrorThrottler::new().with_max_backoff(cap);
+        assert_eq!(throttler.max_backoff(), cap);
+        for _ in 0..10 {
+            throttler.increment();
+            assert!(throttler.current_delay() <= cap);
+        }
+        // At the cap, jitter can only shorten the delay
+        assert!(throttler.current_delay() >= cap.mul_f64(1.0 - DEFAULT_JITTER));
+    }
 }
@@ -3,15 +3,23 @@
 // manages run/cancel/wait lifecycle.
 
 use anyhow::{Context, Result};
-use runner_common::constants::{self, WellKnownDirectory};
+use runner_common::config_store::write_atomically;
+use runner_common::constants::{self, WellKnownConfigFile, WellKnownDirectory};
 use runner_common::host_context::HostContext;
-use runner_common::process_channel::ProcessChannel;
+use runner_common::pending_completion::{PendingCompletion, PendingCompletionStore};
+use runner_common::process_channel::{MessageType, ProcessChannel};
+use runner_common::run_server::{conclusion_string, RunServer};
 use runner_common::tracing::Tracing;
+use runner_common::util::task_result_util::{TaskResult, TaskResultUtil};
 use runner_sdk::TraceWriter;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+
+use crate::job_metrics::{JobMetrics, MetricsFileSink, WorkerJobSummary};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
@@ -44,6 +52,53 @@ pub struct AgentJobRequestMessage {
     pub steps: Option<serde_json::Value>,
     #[serde(default, rename = "contextData")]
     pub context_data: Option<serde_json::Value>,
+    /// Job-level `timeout-minutes` requested by the workflow.
+    #[serde(default, rename = "timeoutInMinutes")]
+    pub timeout_in_minutes: Option<u64>,
+}
+
+impl AgentJobRequestMessage {
+    /// The job timeout requested by the workflow, if any.
+    ///
+    /// Read from the message itself, falling back to the plan's
+    /// `timeoutInMinutes`.
+    pub fn requested_timeout(&self) -> Option<Duration> {
+        self.timeout_in_minutes
+            .or_else(|| {
+                self.plan
+                    .as_ref()
+                    .and_then(|plan| plan.get("timeoutInMinutes"))
+                    .and_then(|v| v.as_u64())
+            })
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes.saturating_mul(60)))
+    }
+
+    /// The plan ID from the nested plan reference.
+    pub fn plan_id(&self) -> String {
+        self.plan
+            .as_ref()
+            .and_then(|plan| plan.get("planId"))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// The Run Service URL, taken from the `SystemVssConnection` endpoint.
+    pub fn run_service_url(&self) -> Option<String> {
+        self.resources
+            .as_ref()
+            .and_then(|resources| resources.get("endpoints"))
+            .and_then(|endpoints| endpoints.as_array())
+            .and_then(|endpoints| {
+                endpoints
+                    .iter()
+                    .find(|e| e.get("name").and_then(|n| n.as_str()) == Some("SystemVssConnection"))
+            })
+            .and_then(|endpoint| endpoint.get("url"))
+            .and_then(|url| url.as_str())
+            .map(|url| url.to_string())
+    }
 }
 
 /// A job cancel message received from the server.
@@ -55,6 +110,252 @@ pub struct JobCancelMessage {
     pub timeout: Option<serde_json::Value>,
 }
 
+// ---------------------------------------------------------------------------
+// Worker lifecycle: timeouts and crash telemetry
+// ---------------------------------------------------------------------------
+
+/// Job execution timeout used when neither the job nor its plan specifies one.
+const DEFAULT_JOB_TIMEOUT: Duration = Duration::from_secs(360 * 60);
+
+/// How long a timed-out worker gets to honour the cancel request before
+/// it is killed.
+const WORKER_CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// How many times a worker is spawned before an IPC handshake failure is
+/// treated as fatal for the job.
+const WORKER_START_MAX_ATTEMPTS: u32 = 3;
+
+/// Base delay between worker start attempts.
+const WORKER_START_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// How long a freshly spawned worker gets to connect to the IPC socket.
+const WORKER_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Number of trailing worker stderr lines kept for crash reports.
+const WORKER_STDERR_TAIL_LINES: usize = 50;
+
+/// How long to wait for the worker's completion summary after it exits.
+const WORKER_SUMMARY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of worker crashes kept until they are drained; older ones are
+/// dropped so a runner whose workers keep crashing does not grow unbounded.
+const MAX_TELEMETRY_ISSUES: usize = 100;
+
+/// A failure to start a worker, before it has accepted the job message.
+#[derive(Debug, thiserror::Error)]
+enum WorkerStartError {
+    /// The worker was spawned but the IPC handshake failed. A fresh worker
+    /// may succeed, so this is retried.
+    #[error("Worker IPC handshake failed: {0:#}")]
+    Handshake(anyhow::Error),
+    /// The worker could not be spawned at all.
+    #[error("{0:#}")]
+    Fatal(anyhow::Error),
+}
+
+/// A worker that has connected and accepted the job message.
+struct StartedWorker {
+    child: tokio::process::Child,
+    stderr_task: Option<JoinHandle<()>>,
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+}
+
+/// How the dispatcher stopped waiting on a worker process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WorkerWait {
+    /// The worker exited on its own with the given exit code.
+    Exited(i32),
+    /// The job was cancelled and the worker was killed.
+    Cancelled,
+    /// The job timeout elapsed and the worker ignored the cancel request,
+    /// so it was killed.
+    TimedOut,
+}
+
+/// How a worker process ended.
+#[derive(Debug, Clone)]
+struct WorkerExit {
+    exit_code: i32,
+    /// Whether the exit was caused by the dispatcher cancelling the job
+    /// (explicitly or because the job timed out).
+    cancelled: bool,
+    /// The last lines the worker wrote to stderr.
+    stderr_tail: Vec<String>,
+    /// What the worker reported in its `JobCompleted` message, if it got
+    /// that far.
+    summary: Option<WorkerJobSummary>,
+}
+
+impl WorkerExit {
+    /// The job result implied by how the worker ended.
+    fn task_result(&self) -> TaskResult {
+        if TaskResultUtil::is_valid_return_code(self.exit_code) {
+            TaskResultUtil::translate_from_return_code(self.exit_code)
+        } else if self.cancelled {
+            TaskResult::Canceled
+        } else {
+            TaskResult::Failed
+        }
+    }
+}
+
+/// An internal telemetry issue, mapping the `Issue` the C# runner attaches
+/// to the job record with `INTERNAL_TELEMETRY_ISSUE_DATA_KEY` set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryIssue {
+    #[serde(rename = "type")]
+    pub issue_type: String,
+    pub message: String,
+    #[serde(default)]
+    pub data: HashMap<String, String>,
+}
+
+impl TelemetryIssue {
+    /// Build a `WORKER_CRASH` telemetry issue for a worker that exited
+    /// without a valid task-result return code.
+    pub fn worker_crash(job_id: Uuid, exit_code: i32, stderr_tail: &[String]) -> Self {
+        let mut message = format!(
+            "Worker process for job {} exited unexpectedly with code {}.",
+            job_id, exit_code
+        );
+        if !stderr_tail.is_empty() {
+            message.push_str(" Last stderr output:\n");
+            message.push_str(&stderr_tail.join("\n"));
+        }
+
+        let mut data = HashMap::new();
+        data.insert(
+            constants::INTERNAL_TELEMETRY_ISSUE_DATA_KEY.to_string(),
+            constants::WORKER_CRASH.to_string(),
+        );
+        data.insert("exitCode".to_string(), exit_code.to_string());
+
+        Self {
+            issue_type: "error".to_string(),
+            message,
+            data,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// In-flight job state - crash recovery
+// ---------------------------------------------------------------------------
+
+/// The minimal state of a job that has been handed to a worker.
+///
+/// Persisted while the job runs so that a listener restarted after a crash
+/// can find and reconcile the jobs it lost track of.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InFlightJob {
+    pub job_id: Uuid,
+    #[serde(default)]
+    pub plan_id: String,
+    #[serde(default)]
+    pub request_id: u64,
+    /// The worker's process ID, once the worker has started.
+    #[serde(default)]
+    pub worker_pid: Option<u32>,
+    /// Where to report the job's completion.
+    #[serde(default)]
+    pub run_service_url: Option<String>,
+}
+
+impl InFlightJob {
+    fn from_request(job_request: &AgentJobRequestMessage) -> Self {
+        Self {
+            job_id: job_request.job_id,
+            plan_id: job_request.plan_id(),
+            request_id: job_request.request_id,
+            worker_pid: None,
+            run_service_url: job_request.run_service_url(),
+        }
+    }
+}
+
+/// A job found in flight on startup, and the result to complete it with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanedJob {
+    pub job: InFlightJob,
+    pub result: TaskResult,
+}
+
+/// The persisted list of in-flight jobs (`.inflight_jobs` in the runner root).
+struct InFlightJobStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl InFlightJobStore {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Read the persisted jobs. A missing or unreadable file means none.
+    fn load(&self) -> Vec<InFlightJob> {
+        let _guard = self.lock.lock().unwrap();
+        self.read()
+    }
+
+    /// Apply `change` to the persisted jobs. The file is removed once no
+    /// job is left in flight.
+    fn update(&self, change: impl FnOnce(&mut Vec<InFlightJob>)) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut jobs = self.read();
+        change(&mut jobs);
+
+        if jobs.is_empty() {
+            match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e).with_context(|| format!("Failed to remove {:?}", self.path));
+                }
+                _ => return Ok(()),
+            }
+        }
+
+        let json = serde_json::to_string_pretty(&jobs)?;
+        write_atomically(&self.path, &json)
+    }
+
+    fn read(&self) -> Vec<InFlightJob> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Kill `pid` if it is still a running worker process.
+///
+/// The process must carry the worker's `--pipeIn` argument, so that a PID
+/// reused by an unrelated process after a reboot is left alone. Returns
+/// whether a worker was killed.
+fn kill_orphaned_worker(pid: u32) -> bool {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System, UpdateKind};
+
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::new().with_cmd(UpdateKind::Always),
+    );
+
+    match system.process(pid) {
+        Some(process)
+            if process.status() != ProcessStatus::Zombie
+                && process.cmd().iter().any(|arg| arg == "--pipeIn") =>
+        {
+            process.kill()
+        }
+        _ => false,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // WorkerDispatchInfo - tracks a running worker
 // ---------------------------------------------------------------------------
@@ -76,6 +377,27 @@ struct WorkerDispatchInfo {
 /// Maps `JobDispatcher` in the C# runner. Each incoming job request
 /// causes a new worker process to be spawned. The dispatcher communicates
 /// with the worker via IPC (Unix domain sockets) using `ProcessChannel`.
+/// A worker that crashed before it could complete its job, and the
+/// telemetry to complete the job with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkerCrash {
+    pub job: InFlightJob,
+    pub issue: TelemetryIssue,
+}
+
+impl WorkerCrash {
+    /// The `completejob` body failing the job with the crash attached,
+    /// like the C# listener's `ForceFailJob`.
+    pub fn completion_payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "planId": self.job.plan_id,
+            "jobId": self.job.job_id,
+            "conclusion": conclusion_string(TaskResult::Failed),
+            "telemetry": [self.issue],
+        })
+    }
+}
+
 pub struct JobDispatcher {
     context: Arc<HostContext>,
     trace: Tracing,
@@ -85,6 +407,19 @@ pub struct JobDispatcher {
     is_busy: Arc<Mutex<bool>>,
     /// Channel to signal that a run-once job has completed.
     run_once_tx: Option<mpsc::Sender<bool>>,
+    /// Abnormal worker exits waiting to be reported, at most
+    /// `MAX_TELEMETRY_ISSUES` of the latest.
+    telemetry: Arc<Mutex<Vec<WorkerCrash>>>,
+    /// Operator-configured upper bound on job duration (`--timeout`).
+    max_job_timeout: Option<Duration>,
+    /// Jobs handed to a worker, persisted for crash recovery.
+    in_flight: Arc<InFlightJobStore>,
+    /// Completions a worker could not deliver, replayed on startup.
+    pending_completions: PendingCompletionStore,
+    /// Operator-configured worker binary (`--worker-path`).
+    worker_path: Option<PathBuf>,
+    /// Where to append per-job metrics (`--metrics-file`).
+    metrics_sink: Option<Arc<MetricsFileSink>>,
     /// Cancellation token for the overall dispatcher.
     #[allow(dead_code)]
     shutdown_token: CancellationToken,
@@ -95,12 +430,24 @@ impl JobDispatcher {
     pub fn new(context: Arc<HostContext>) -> Self {
         let trace = context.get_trace("JobDispatcher");
         let shutdown_token = context.runner_shutdown_token();
+        let in_flight = Arc::new(InFlightJobStore::new(
+            context.get_config_file(WellKnownConfigFile::InFlightJobs),
+        ));
+        let pending_completions = PendingCompletionStore::new(
+            context.get_config_file(WellKnownConfigFile::PendingCompletions),
+        );
         Self {
             context,
             trace,
             workers: Arc::new(Mutex::new(HashMap::new())),
             is_busy: Arc::new(Mutex::new(false)),
             run_once_tx: None,
+            telemetry: Arc::new(Mutex::new(Vec::new())),
+            max_job_timeout: None,
+            in_flight,
+            pending_completions,
+            worker_path: None,
+            metrics_sink: None,
             shutdown_token,
         }
     }
@@ -110,11 +457,43 @@ impl JobDispatcher {
         self.run_once_tx = Some(tx);
     }
 
+    /// Set the maximum job timeout. Jobs requesting a longer timeout are
+    /// cancelled once this ceiling elapses.
+    pub fn set_max_job_timeout(&mut self, ceiling: Option<Duration>) {
+        self.max_job_timeout = ceiling;
+    }
+
+    /// Set the worker binary to spawn instead of the installed one.
+    pub fn set_worker_path(&mut self, path: Option<PathBuf>) {
+        self.worker_path = path;
+    }
+
+    /// Append a JSON line of metrics for every completed job to `path`.
+    pub fn set_metrics_file(&mut self, path: Option<PathBuf>) {
+        self.metrics_sink = path.map(|path| Arc::new(MetricsFileSink::new(path)));
+    }
+
     /// Whether the dispatcher currently has any running worker.
     pub fn is_busy(&self) -> bool {
         *self.is_busy.lock().unwrap()
     }
 
+    /// Drain the worker crashes recorded since the last call.
+    pub fn take_telemetry(&self) -> Vec<WorkerCrash> {
+        std::mem::take(&mut *self.telemetry.lock().unwrap())
+    }
+
+    /// Keep `crash`, dropping the oldest one once `MAX_TELEMETRY_ISSUES`
+    /// are waiting to be drained.
+    fn record_telemetry(telemetry: &Mutex<Vec<WorkerCrash>>, crash: WorkerCrash) {
+        let mut telemetry = telemetry.lock().unwrap();
+        if telemetry.len() >= MAX_TELEMETRY_ISSUES {
+            let excess = telemetry.len() + 1 - MAX_TELEMETRY_ISSUES;
+            telemetry.drain(..excess);
+        }
+        telemetry.push(crash);
+    }
+
     /// Dispatch a job request to a new worker process.
     /// Dispatch a job request to a new worker process.
     ///
@@ -164,29 +543,90 @@ impl JobDispatcher {
         // because the listener struct doesn't capture all fields.
         let job_body = raw_body;
 
+        let job_timeout =
+            Self::effective_job_timeout(job_request.requested_timeout(), self.max_job_timeout);
+        self.trace.info(&format!(
+            "Job {} will be cancelled after {:?}",
+            job_id, job_timeout
+        ));
+
+        // Persist the job so a listener restarted after a crash can reconcile it
+        let in_flight_job = InFlightJob::from_request(job_request);
+        if let Err(e) = self
+            .in_flight
+            .update(|jobs| jobs.push(in_flight_job.clone()))
+        {
+            self.trace.warning(&format!(
+                "Failed to persist in-flight state for job {}: {:#}",
+                job_id, e
+            ));
+        }
+
         let cancel_token = CancellationToken::new();
         let cancel_for_task = cancel_token.clone();
-        let context_clone = self.context.clone();
         let workers_clone = self.workers.clone();
+        let in_flight_clone = self.in_flight.clone();
         let is_busy_clone = self.is_busy.clone();
         let run_once_tx = self.run_once_tx.clone();
+        let telemetry_clone = self.telemetry.clone();
         let trace_clone = self.trace.clone();
         let worker_binary_clone = worker_binary.clone();
         let socket_path_clone = socket_path.clone();
+        let metrics_sink = self.metrics_sink.clone();
+        let request_id = job_request.request_id;
+        let job_display_name = job_request.job_display_name.clone();
+        let started_at = chrono::Utc::now();
 
         // Spawn the worker in a background task
         let handle: JoinHandle<Result<i32>> = tokio::spawn(async move {
-            let result = Self::run_worker(
-                context_clone,
+            let exit = Self::run_worker(
                 trace_clone.clone(),
                 worker_binary_clone,
                 socket_path_clone,
                 job_body,
                 channel,
                 cancel_for_task,
+                job_timeout,
+                &|pid| {
+                    let _ = in_flight_clone.update(|jobs| {
+                        if let Some(job) = jobs.iter_mut().find(|j| j.job_id == job_id) {
+                            job.worker_pid = Some(pid);
+                        }
+                    });
+                },
             )
             .await;
 
+            if let Some(sink) = &metrics_sink {
+                let metrics =
+                    Self::job_metrics(job_id, request_id, &job_display_name, started_at, &exit);
+                if let Err(e) = sink.append(&metrics) {
+                    trace_clone.warning(&format!(
+                        "Failed to write metrics for job {}: {:#}",
+                        job_id, e
+                    ));
+                }
+            }
+
+            let result = exit.map(|exit| {
+                if let Some(issue) = Self::crash_telemetry(job_id, &exit) {
+                    trace_clone.error(&format!(
+                        "Worker for job {} crashed — recording {} telemetry",
+                        job_id,
+                        constants::WORKER_CRASH
+                    ));
+                    trace_clone.info_object(&issue);
+                    Self::record_telemetry(
+                        &telemetry_clone,
+                        WorkerCrash {
+                            job: in_flight_job,
+                            issue,
+                        },
+                    );
+                }
+                exit.exit_code
+            });
+
             // Clean up
             {
                 let mut workers = workers_clone.lock().unwrap();
@@ -195,6 +635,12 @@ impl JobDispatcher {
                     *is_busy_clone.lock().unwrap() = false;
                 }
             }
+            if let Err(e) = in_flight_clone.update(|jobs| jobs.retain(|j| j.job_id != job_id)) {
+                trace_clone.warning(&format!(
+                    "Failed to clear in-flight state for job {}: {:#}",
+                    job_id, e
+                ));
+            }
 
             // Notify run-once completion
             if let Some(tx) = &run_once_tx {
@@ -238,30 +684,205 @@ impl JobDispatcher {
     }
 
     /// Run the worker process and communicate via IPC.
+    ///
+    /// `on_started` is called with the worker's PID once it has accepted the job.
+    #[allow(clippy::too_many_arguments)]
     async fn run_worker(
-        _context: Arc<HostContext>,
         trace: Tracing,
         worker_binary: PathBuf,
         socket_path: String,
         job_body: String,
         mut channel: ProcessChannel,
         cancel: CancellationToken,
-    ) -> Result<i32> {
+        job_timeout: Duration,
+        on_started: &(dyn Fn(u32) + Send + Sync),
+    ) -> Result<WorkerExit> {
+        let StartedWorker {
+            mut child,
+            stderr_task,
+            stderr_tail,
+        } = Self::start_worker_with_retry(
+            &trace,
+            &worker_binary,
+            &socket_path,
+            &job_body,
+            &mut channel,
+            WORKER_START_MAX_ATTEMPTS,
+            WORKER_START_RETRY_DELAY,
+        )
+        .await?;
+        if let Some(pid) = child.id() {
+            on_started(pid);
+        }
+
+        // Accept second connection (worker's channel_out), on which the
+        // worker reports the job's completion.
+        trace.info("Accepting worker's second IPC connection (channel_out)...");
+        let summary_task = match channel.accept_second().await {
+            Ok(stream) => {
+                trace.info("Worker channel_out accepted");
+                Some(tokio::spawn(Self::read_job_summary(
+                    ProcessChannel::from_stream(stream),
+                )))
+            }
+            Err(e) => {
+                trace.info(&format!(
+                    "Could not accept second IPC connection (non-fatal): {}",
+                    e
+                ));
+                None
+            }
+        };
+
+        // Wait for the worker to finish, for cancellation, or for the job timeout
+        let wait = Self::wait_for_worker(
+            &mut child,
+            &mut channel,
+            &cancel,
+            job_timeout,
+            WORKER_CANCEL_GRACE_PERIOD,
+            &trace,
+        )
+        .await?;
+        let (exit_code, cancelled) = match wait {
+            WorkerWait::Exited(code) => (code, false),
+            WorkerWait::Cancelled => (constants::return_code::TERMINATED_ERROR, true),
+            WorkerWait::TimedOut => (
+                TaskResultUtil::translate_to_return_code(TaskResult::Failed),
+                true,
+            ),
+        };
+
+        if let Some(task) = stderr_task {
+            let _ = task.await;
+        }
+        let stderr_tail = stderr_tail.lock().unwrap().drain(..).collect();
+
+        // The worker has exited, so its channel_out is closed or about to be
+        let summary = match summary_task {
+            Some(task) => tokio::time::timeout(WORKER_SUMMARY_TIMEOUT, task)
+                .await
+                .ok()
+                .and_then(|joined| joined.ok())
+                .flatten(),
+            None => None,
+        };
+
+        Ok(WorkerExit {
+            exit_code,
+            cancelled,
+            stderr_tail,
+            summary,
+        })
+    }
+
+    /// Read the worker's channel_out until it closes, keeping the last
+    /// `JobCompleted` summary.
+    async fn read_job_summary(channel: ProcessChannel) -> Option<WorkerJobSummary> {
+        let mut summary = None;
+        while let Ok(message) = channel.receive_async().await {
+            if message.message_type == MessageType::JobCompleted {
+                summary = serde_json::from_str(&message.body).ok().or(summary);
+            }
+        }
+        summary
+    }
+
+    /// The metrics line recorded for a finished job.
+    fn job_metrics(
+        job_id: Uuid,
+        request_id: u64,
+        job_display_name: &str,
+        started_at: chrono::DateTime<chrono::Utc>,
+        exit: &Result<WorkerExit>,
+    ) -> JobMetrics {
+        let (result, exit_code, summary) = match exit {
+            Ok(exit) => (exit.task_result(), Some(exit.exit_code), exit.summary),
+            Err(_) => (TaskResult::Failed, None, None),
+        };
+        JobMetrics::new(
+            job_id,
+            request_id,
+            job_display_name,
+            result,
+            exit_code,
+            started_at,
+            chrono::Utc::now(),
+            summary,
+        )
+    }
+
+    /// Start a worker and hand it the job, retrying with a fresh worker
+    /// process when the IPC handshake fails.
+    ///
+    /// Only handshake failures are retried: once the worker has accepted the
+    /// job message, failures belong to the job and are not retried here. The
+    /// delay between attempts grows linearly with the attempt number.
+    async fn start_worker_with_retry(
+        trace: &Tracing,
+        worker_binary: &Path,
+        socket_path: &str,
+        job_body: &str,
+        channel: &mut ProcessChannel,
+        max_attempts: u32,
+        retry_delay: Duration,
+    ) -> Result<StartedWorker> {
+        let mut attempt = 1;
+        loop {
+            match Self::start_worker(trace, worker_binary, socket_path, job_body, channel).await {
+                Ok(started) => return Ok(started),
+                Err(WorkerStartError::Handshake(e)) if attempt < max_attempts => {
+                    let delay = retry_delay * attempt;
+                    trace.warning(&format!(
+                        "Worker IPC handshake failed (attempt {}/{}): {:#} — retrying in {:?}",
+                        attempt, max_attempts, e, delay
+                    ));
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(WorkerStartError::Handshake(e)) => {
+                    return Err(e.context(format!(
+                        "Worker failed to start after {} attempts",
+                        max_attempts
+                    )));
+                }
+                Err(WorkerStartError::Fatal(e)) => return Err(e),
+            }
+        }
+    }
+
+    /// Spawn a worker process, wait for it to connect its inbound IPC channel
+    /// and send it the job message.
+    async fn start_worker(
+        trace: &Tracing,
+        worker_binary: &Path,
+        socket_path: &str,
+        job_body: &str,
+        channel: &mut ProcessChannel,
+    ) -> std::result::Result<StartedWorker, WorkerStartError> {
         trace.info(&format!(
             "Starting worker process: {:?} --pipeIn {} --pipeOut {}",
             worker_binary, socket_path, socket_path
         ));
 
-        let mut child = tokio::process::Command::new(&worker_binary)
+        let mut child = tokio::process::Command::new(worker_binary)
             .arg("--pipeIn")
-            .arg(&socket_path)
+            .arg(socket_path)
             .arg("--pipeOut")
-            .arg(&socket_path)
+            .arg(socket_path)
             .stdin(std::process::Stdio::null())
             .stdout(std::process::Stdio::inherit())
-            .stderr(std::process::Stdio::inherit())
+            .stderr(std::process::Stdio::piped())
             .spawn()
-            .context("Failed to spawn worker process")?;
+            .context("Failed to spawn worker process")
+            .map_err(WorkerStartError::Fatal)?;
+
+        // Forward the worker's stderr while keeping its tail for crash reports.
+        let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+        let stderr_task = child
+            .stderr
+            .take()
+            .map(|stderr| tokio::spawn(Self::forward_stderr(stderr, stderr_tail.clone())));
 
         trace.info(&format!(
             "Worker process spawned with PID: {}",
@@ -275,58 +896,272 @@ impl JobDispatcher {
 
         // Accept first connection (worker's channel_in)
         trace.info("Waiting for worker to connect to IPC socket (channel_in)...");
-        channel.accept().await.context("Failed to accept worker IPC connection (channel_in)")?;
-        trace.info("Worker channel_in connected");
+        let handshake = async {
+            tokio::select! {
+                accepted = channel.accept() => {
+                    accepted.context("Failed to accept worker IPC connection (channel_in)")?;
+                }
+                status = child.wait() => {
+                    anyhow::bail!(
+                        "Worker exited before connecting to the IPC socket ({})",
+                        status.map(|s| s.to_string()).unwrap_or_else(|e| e.to_string())
+                    );
+                }
+                _ = tokio::time::sleep(WORKER_HANDSHAKE_TIMEOUT) => {
+                    anyhow::bail!(
+                        "Worker did not connect to the IPC socket within {:?}",
+                        WORKER_HANDSHAKE_TIMEOUT
+                    );
+                }
+            }
+            trace.info("Worker channel_in connected");
 
-        // Send the job request to the worker on the first (inbound) channel
-        trace.info("Sending job request to worker via IPC...");
-        channel
-            .send_async(
-                runner_common::process_channel::MessageType::NewJobRequest,
-                &job_body,
-            )
-            .await
-            .context("Failed to send job request to worker via IPC")?;
-        trace.info("Job request sent to worker");
+            // Send the job request to the worker on the first (inbound) channel
+            trace.info("Sending job request to worker via IPC...");
+            channel
+                .send_async(MessageType::NewJobRequest, job_body)
+                .await
+                .context("Failed to send job request to worker via IPC")?;
+            trace.info("Job request sent to worker");
+            Ok(())
+        };
 
-        // Accept second connection (worker's channel_out) — we don't actively
-        // read from it right now, but accepting prevents the worker from stalling.
-        trace.info("Accepting worker's second IPC connection (channel_out)...");
-        match channel.accept_second().await {
-            Ok(_stream) => {
-                trace.info("Worker channel_out accepted");
-            }
-            Err(e) => {
-                trace.info(&format!(
-                    "Could not accept second IPC connection (non-fatal): {}",
-                    e
-                ));
-            }
+        if let Err(e) = handshake.await {
+            let _ = child.kill().await;
+            return Err(WorkerStartError::Handshake(e));
         }
 
-        // Wait for the worker to finish or for cancellation
-        let exit_code = tokio::select! {
+        Ok(StartedWorker {
+            child,
+            stderr_task,
+            stderr_tail,
+        })
+    }
+
+    /// Wait for the worker process to exit.
+    ///
+    /// If the job is cancelled the worker is killed immediately. If
+    /// `job_timeout` elapses first, a cancel request is sent to the worker
+    /// over IPC; a worker still running after `cancel_grace_period` is killed.
+    async fn wait_for_worker(
+        child: &mut tokio::process::Child,
+        channel: &mut ProcessChannel,
+        cancel: &CancellationToken,
+        job_timeout: Duration,
+        cancel_grace_period: Duration,
+        trace: &Tracing,
+    ) -> Result<WorkerWait> {
+        tokio::select! {
             status = child.wait() => {
                 let status = status.context("Failed to wait for worker process")?;
-                #[cfg(unix)]
-                {
-                    use std::os::unix::process::ExitStatusExt;
-                    status.code().or_else(|| status.signal().map(|s| 128 + s)).unwrap_or(1)
-                }
-                #[cfg(not(unix))]
-                {
-                    status.code().unwrap_or(1)
-                }
+                return Ok(WorkerWait::Exited(Self::exit_code(status)));
             }
             _ = cancel.cancelled() => {
                 trace.info("Worker cancellation requested — sending kill signal");
                 let _ = child.kill().await;
                 let _ = child.wait().await;
-                constants::return_code::TERMINATED_ERROR
+                return Ok(WorkerWait::Cancelled);
             }
-        };
+            _ = tokio::time::sleep(job_timeout) => {}
+        }
+
+        trace.warning(&format!(
+            "Job exceeded its maximum execution time of {:?} — sending cancel to worker",
+            job_timeout
+        ));
+        if let Err(e) = channel.send_async(MessageType::CancelRequest, "").await {
+            trace.warning(&format!("Failed to send cancel request to worker: {}", e));
+        }
+
+        match tokio::time::timeout(cancel_grace_period, child.wait()).await {
+            Ok(status) => {
+                let status = status.context("Failed to wait for worker process")?;
+                Ok(WorkerWait::Exited(Self::exit_code(status)))
+            }
+            Err(_) => {
+                trace.warning(&format!(
+                    "Worker did not exit within {:?} of the cancel request — sending kill signal",
+                    cancel_grace_period
+                ));
+                let _ = child.kill().await;
+                let _ = child.wait().await;
+                Ok(WorkerWait::TimedOut)
+            }
+        }
+    }
+
+    /// Map a worker exit status to an exit code, treating death by signal
+    /// as `128 + signal` like a shell does.
+    fn exit_code(status: std::process::ExitStatus) -> i32 {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            status
+                .code()
+                .or_else(|| status.signal().map(|s| 128 + s))
+                .unwrap_or(1)
+        }
+        #[cfg(not(unix))]
+        {
+            status.code().unwrap_or(1)
+        }
+    }
+
+    /// The timeout to enforce for a job: the workflow-requested timeout (or
+    /// `DEFAULT_JOB_TIMEOUT`), capped at the operator-configured ceiling.
+    fn effective_job_timeout(requested: Option<Duration>, ceiling: Option<Duration>) -> Duration {
+        let timeout = requested.unwrap_or(DEFAULT_JOB_TIMEOUT);
+        match ceiling {
+            Some(ceiling) => timeout.min(ceiling),
+            None => timeout,
+        }
+    }
+
+    /// Echo the worker's stderr line by line, keeping the last
+    /// `WORKER_STDERR_TAIL_LINES` in `tail`. Reads until EOF even past
+    /// output that is not valid UTF-8, so the worker never blocks on (or
+    /// gets EPIPE from) a pipe nobody drains.
+    async fn forward_stderr(stderr: impl AsyncRead + Unpin, tail: Arc<Mutex<VecDeque<String>>>) {
+        let mut reader = BufReader::new(stderr);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let line = String::from_utf8_lossy(&buf)
+                .trim_end_matches(['\r', '\n'])
+                .to_string();
+            eprintln!("{}", line);
+            let mut tail = tail.lock().unwrap();
+            if tail.len() == WORKER_STDERR_TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+    }
+
+    /// Build the crash telemetry for a worker exit, if the exit was abnormal.
+    ///
+    /// A worker that finishes normally exits with a return code translated
+    /// from its `TaskResult`; anything else (a signal, a panic, an unhandled
+    /// error) is reported as `WORKER_CRASH`. Cancellation is not a crash.
+    fn crash_telemetry(job_id: Uuid, exit: &WorkerExit) -> Option<TelemetryIssue> {
+        if exit.cancelled || TaskResultUtil::is_valid_return_code(exit.exit_code) {
+            return None;
+        }
+        Some(TelemetryIssue::worker_crash(
+            job_id,
+            exit.exit_code,
+            &exit.stderr_tail,
+        ))
+    }
 
-        Ok(exit_code)
+    /// Find the jobs a previous listener process left in flight and clear
+    /// their persisted state. Call on startup, before dispatching any job.
+    ///
+    /// A worker that is still running has lost its listener, so it is killed
+    /// and its job is `Abandoned`. A job whose worker is already gone is
+    /// `Failed`.
+    pub fn reconcile_orphaned_jobs(&self) -> Vec<OrphanedJob> {
+        let orphans: Vec<OrphanedJob> = self
+            .in_flight
+            .load()
+            .into_iter()
+            .map(|job| {
+                let killed = job.worker_pid.is_some_and(kill_orphaned_worker);
+                let result = if killed {
+                    TaskResult::Abandoned
+                } else {
+                    TaskResult::Failed
+                };
+                self.trace.warning(&format!(
+                    "Job {} (plan {}) was left in flight by a previous runner process{} — completing it as {}",
+                    job.job_id,
+                    job.plan_id,
+                    if killed { "; killed its worker" } else { "" },
+                    result
+                ));
+                OrphanedJob { job, result }
+            })
+            .collect();
+
+        if let Err(e) = self.in_flight.update(|jobs| jobs.clear()) {
+            self.trace
+                .warning(&format!("Failed to clear in-flight job state: {:#}", e));
+        }
+        orphans
+    }
+
+    /// Report an orphaned job's result to the Run Service.
+    ///
+    /// POST {run_service_url}/completejob
+    pub async fn complete_orphaned_job(
+        &self,
+        orphan: &OrphanedJob,
+        access_token: &str,
+    ) -> Result<()> {
+        let base = orphan
+            .job
+            .run_service_url
+            .as_deref()
+            .context("No Run Service URL recorded for the job")?;
+
+        let body = serde_json::json!({
+            "planId": orphan.job.plan_id,
+            "jobId": orphan.job.job_id,
+            "conclusion": conclusion_string(orphan.result),
+        });
+        self.run_server(base, access_token)?
+            .complete_job(&body, &self.trace)
+            .await
+    }
+
+    /// Fail a crashed worker's job on the Run Service, with the crash
+    /// attached as telemetry.
+    ///
+    /// POST {run_service_url}/completejob
+    pub async fn complete_crashed_job(
+        &self,
+        crash: &WorkerCrash,
+        access_token: &str,
+    ) -> Result<()> {
+        let base = crash
+            .job
+            .run_service_url
+            .as_deref()
+            .context("No Run Service URL recorded for the job")?;
+        self.run_server(base, access_token)?
+            .complete_job(&crash.completion_payload(), &self.trace)
+            .await
+    }
+
+    /// The completions workers could not deliver.
+    pub fn pending_completions(&self) -> Vec<PendingCompletion> {
+        self.pending_completions.list()
+    }
+
+    /// Deliver a completion a worker recorded as pending, and forget it once
+    /// the server has accepted it. A failed delivery stays pending.
+    pub async fn complete_pending_job(
+        &self,
+        pending: &PendingCompletion,
+        access_token: &str,
+    ) -> Result<()> {
+        self.run_server(&pending.run_service_url, access_token)?
+            .complete_job(&pending.payload, &self.trace)
+            .await?;
+        self.pending_completions.remove(pending)
+    }
+
+    /// A single-attempt Run Service client using the runner's proxy; the
+    /// next startup retries whatever is still pending.
+    fn run_server(&self, base: &str, access_token: &str) -> Result<RunServer> {
+        let client = runner_common::HttpClientFactory::create_client(&self.context.web_proxy)?;
+        Ok(RunServer::new(base, access_token)
+            .with_client(client)
+            .with_retry(1, Duration::ZERO))
     }
 
     /// Cancel a running job.
@@ -391,8 +1226,14 @@ impl JobDispatcher {
         }
     }
 
-    /// Find the worker binary path.
+    /// Find the worker binary path. A configured `--worker-path` takes
+    /// precedence over the installed binary.
     fn find_worker_binary(&self) -> Result<PathBuf> {
+        if let Some(ref path) = self.worker_path {
+            Self::validate_worker_path(path)?;
+            return Ok(path.clone());
+        }
+
         let bin_dir = self.context.get_directory(WellKnownDirectory::Bin);
 
         // Check for Runner.Worker binary (the Rust binary name)
@@ -427,9 +1268,763 @@ impl JobDispatcher {
         ))
     }
 
+    /// Check that a worker binary override exists and is executable.
+    pub fn validate_worker_path(path: &Path) -> Result<()> {
+        let metadata = std::fs::metadata(path).map_err(|e| {
+            anyhow::anyhow!("Worker binary {} does not exist: {}", path.display(), e)
+        })?;
+        if !metadata.is_file() {
+            anyhow::bail!("Worker binary {} is not a file", path.display());
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if metadata.permissions().mode() & 0o111 == 0 {
+                anyhow::bail!("Worker binary {} is not executable", path.display());
+            }
+        }
+        Ok(())
+    }
+
     /// Get the list of currently running job IDs.
     pub fn running_job_ids(&self) -> Vec<Uuid> {
         let workers = self.workers.lock().unwrap();
         workers.keys().cloned().collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runner_common::util::task_result_util::TaskResult;
+    use runner_sdk::test_util::{MockHttpServer, MockResponse};
+
+    fn worker_exit(exit_code: i32, cancelled: bool, stderr: &[&str]) -> WorkerExit {
+        WorkerExit {
+            exit_code,
+            cancelled,
+            stderr_tail: stderr.iter().map(|s| s.to_string()).collect(),
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_signal_exit_produces_worker_crash_telemetry() {
+        let job_id = Uuid::new_v4();
+        // SIGSEGV is reported as 128 + 11
+        let exit = worker_exit(139, false, &["thread 'main' panicked", "stack overflow"]);
+
+        let issue = JobDispatcher::crash_telemetry(job_id, &exit).expect("crash telemetry");
+        assert_eq!(issue.issue_type, "error");
+        assert_eq!(
+            issue.data[constants::INTERNAL_TELEMETRY_ISSUE_DATA_KEY],
+            constants::WORKER_CRASH
+        );
+        assert_eq!(issue.data["exitCode"], "139");
+        assert!(issue.message.contains(&job_id.to_string()));
+        assert!(issue.message.contains("stack overflow"));
+    }
+
+    #[test]
+    fn test_telemetry_keeps_only_the_latest_issues() {
+        let dispatcher = JobDispatcher::new(HostContext::new("Test"));
+        for exit_code in 0..MAX_TELEMETRY_ISSUES as i32 + 5 {
+            JobDispatcher::record_telemetry(&dispatcher.telemetry, worker_crash(None, exit_code));
+        }
+
+        let crashes = dispatcher.take_telemetry();
+        assert_eq!(crashes.len(), MAX_TELEMETRY_ISSUES);
+        assert_eq!(crashes[0].issue.data["exitCode"], "5");
+        assert!(dispatcher.take_telemetry().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stderr_is_drained_past_invalid_utf8() {
+        let tail = Arc::new(Mutex::new(VecDeque::new()));
+        let stderr: &[u8] = b"first\r\nbad \xff\xfe bytes\nlast";
+        JobDispatcher::forward_stderr(stderr, tail.clone()).await;
+
+        let tail: Vec<String> = tail.lock().unwrap().iter().cloned().collect();
+        assert_eq!(tail, vec!["first", "bad \u{fffd}\u{fffd} bytes", "last"]);
+    }
+
+    #[test]
+    fn test_generic_exit_code_produces_worker_crash_telemetry() {
+        let exit = worker_exit(1, false, &[]);
+        let issue = JobDispatcher::crash_telemetry(Uuid::new_v4(), &exit).expect("crash telemetry");
+        assert!(!issue.message.contains("stderr"));
+    }
+
+    #[test]
+    fn test_task_result_exit_is_not_a_crash() {
+        for result in [
+            TaskResult::Succeeded,
+            TaskResult::Failed,
+            TaskResult::Canceled,
+        ] {
+            let exit_code = TaskResultUtil::translate_to_return_code(result);
+            let exit = worker_exit(exit_code, false, &[]);
+            assert!(JobDispatcher::crash_telemetry(Uuid::new_v4(), &exit).is_none());
+        }
+    }
+
+    #[test]
+    fn test_effective_job_timeout_is_capped_by_ceiling() {
+        let hour = Duration::from_secs(60 * 60);
+        let ceiling = Duration::from_secs(10 * 60);
+
+        // A job requesting a very long timeout is capped to the ceiling
+        assert_eq!(
+            JobDispatcher::effective_job_timeout(Some(72 * hour), Some(ceiling)),
+            ceiling
+        );
+        // A shorter requested timeout is kept
+        assert_eq!(
+            JobDispatcher::effective_job_timeout(Some(Duration::from_secs(60)), Some(ceiling)),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            JobDispatcher::effective_job_timeout(None, Some(ceiling)),
+            ceiling
+        );
+        assert_eq!(JobDispatcher::effective_job_timeout(Some(hour), None), hour);
+    }
+
+    #[test]
+    fn test_effective_job_timeout_defaults() {
+        assert_eq!(
+            JobDispatcher::effective_job_timeout(None, None),
+            DEFAULT_JOB_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn test_requested_timeout_from_message() {
+        let message: AgentJobRequestMessage = serde_json::from_value(serde_json::json!({
+            "jobId": Uuid::new_v4(),
+            "timeoutInMinutes": 4320
+        }))
+        .unwrap();
+        assert_eq!(
+            message.requested_timeout(),
+            Some(Duration::from_secs(4320 * 60))
+        );
+    }
+
+    #[test]
+    fn test_huge_requested_timeout_saturates() {
+        let message: AgentJobRequestMessage = serde_json::from_value(serde_json::json!({
+            "jobId": Uuid::new_v4(),
+            "timeoutInMinutes": u64::MAX
+        }))
+        .unwrap();
+        assert_eq!(
+            message.requested_timeout(),
+            Some(Duration::from_secs(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_requested_timeout_from_plan() {
+        let message: AgentJobRequestMessage = serde_json::from_value(serde_json::json!({
+            "jobId": Uuid::new_v4(),
+            "plan": { "planId": Uuid::new_v4(), "timeoutInMinutes": 45 }
+        }))
+        .unwrap();
+        assert_eq!(
+            message.requested_timeout(),
+            Some(Duration::from_secs(45 * 60))
+        );
+
+        let message: AgentJobRequestMessage = serde_json::from_value(serde_json::json!({
+            "jobId": Uuid::new_v4(),
+        }))
+        .unwrap();
+        assert_eq!(message.requested_timeout(), None);
+    }
+
+    /// Connect a server/client `ProcessChannel` pair, standing in for the
+    /// listener and worker ends of the IPC socket.
+    #[cfg(unix)]
+    async fn connected_channels(dir: &std::path::Path) -> (ProcessChannel, ProcessChannel) {
+        let mut server = ProcessChannel::new();
+        let socket_path = server.start_server(dir).unwrap();
+        let mut client = ProcessChannel::new();
+        let (accepted, connected) =
+            tokio::join!(server.accept(), client.start_client(&socket_path));
+        accepted.unwrap();
+        connected.unwrap();
+        (server, client)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_worker_is_killed_at_ceiling() {
+        let trace = HostContext::new("Runner").get_trace("JobDispatcher");
+        let dir = tempfile::tempdir().unwrap();
+        let (mut channel, _worker_channel) = connected_channels(dir.path()).await;
+        let mut child = tokio::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+
+        let requested = Some(Duration::from_secs(72 * 60 * 60));
+        let ceiling = Some(Duration::from_millis(200));
+        let timeout = JobDispatcher::effective_job_timeout(requested, ceiling);
+
+        let cancel = CancellationToken::new();
+        let started = std::time::Instant::now();
+        let wait = JobDispatcher::wait_for_worker(
+            &mut child,
+            &mut channel,
+            &cancel,
+            timeout,
+            Duration::from_millis(100),
+            &trace,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(wait, WorkerWait::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(child.try_wait().unwrap().is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hung_worker_is_cancelled_then_killed_after_timeout() {
+        let trace = HostContext::new("Runner").get_trace("JobDispatcher");
+        let dir = tempfile::tempdir().unwrap();
+        let (mut channel, worker_channel) = connected_channels(dir.path()).await;
+        // A worker that never completes and ignores the cancel request
+        let mut child = tokio::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+
+        let cancel = CancellationToken::new();
+        let wait = JobDispatcher::wait_for_worker(
+            &mut child,
+            &mut channel,
+            &cancel,
+            Duration::from_millis(100),
+            Duration::from_millis(100),
+            &trace,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(wait, WorkerWait::TimedOut);
+        assert!(child.try_wait().unwrap().is_some());
+
+        let message = worker_channel.receive_async().await.unwrap();
+        assert_eq!(message.message_type, MessageType::CancelRequest);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_worker_exiting_on_cancel_is_not_killed() {
+        let trace = HostContext::new("Runner").get_trace("JobDispatcher");
+        let dir = tempfile::tempdir().unwrap();
+        let (mut channel, _worker_channel) = connected_channels(dir.path()).await;
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "sleep 0.2; exit 103"])
+            .spawn()
+            .unwrap();
+
+        let cancel = CancellationToken::new();
+        let wait = JobDispatcher::wait_for_worker(
+            &mut child,
+            &mut channel,
+            &cancel,
+            Duration::from_millis(50),
+            Duration::from_secs(10),
+            &trace,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(wait, WorkerWait::Exited(103));
+    }
+
+    /// Write an executable shell script standing in for the worker binary.
+    #[cfg(unix)]
+    fn fake_worker(dir: &std::path::Path, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("fake-worker.sh");
+        std::fs::write(
+            &path,
+            format!("#!/bin/sh\ncd {:?}\necho x >> attempts\n{}\n", dir, script),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    fn attempts(dir: &std::path::Path) -> usize {
+        std::fs::read_to_string(dir.join("attempts"))
+            .map(|s| s.lines().count())
+            .unwrap_or(0)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_worker_start_retries_then_succeeds() {
+        let trace = HostContext::new("Runner").get_trace("JobDispatcher");
+        let dir = tempfile::tempdir().unwrap();
+        let mut channel = ProcessChannel::new();
+        let socket_path = channel.start_server(dir.path()).unwrap();
+
+        // The first worker dies before connecting; the second one stays up and
+        // signals the test (acting as its IPC client) to connect.
+        let worker = fake_worker(
+            dir.path(),
+            "if [ -f first ]; then touch second; exec sleep 30; else touch first; exit 1; fi",
+        );
+        let ready = dir.path().join("second");
+        let client = tokio::spawn(async move {
+            while !ready.exists() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            let mut worker_channel = ProcessChannel::new();
+            worker_channel.start_client(&socket_path).await.unwrap();
+            worker_channel.receive_async().await.unwrap()
+        });
+
+        let mut started = JobDispatcher::start_worker_with_retry(
+            &trace,
+            &worker,
+            dir.path().join("unused").to_str().unwrap(),
+            "{\"jobId\":\"test\"}",
+            &mut channel,
+            3,
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+
+        let message = client.await.unwrap();
+        assert_eq!(message.message_type, MessageType::NewJobRequest);
+        assert_eq!(message.body, "{\"jobId\":\"test\"}");
+        assert_eq!(attempts(dir.path()), 2);
+        let _ = started.child.kill().await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_worker_start_retries_exhausted() {
+        let trace = HostContext::new("Runner").get_trace("JobDispatcher");
+        let dir = tempfile::tempdir().unwrap();
+        let mut channel = ProcessChannel::new();
+        channel.start_server(dir.path()).unwrap();
+        let worker = fake_worker(dir.path(), "exit 1");
+
+        let result = JobDispatcher::start_worker_with_retry(
+            &trace,
+            &worker,
+            "unused",
+            "{}",
+            &mut channel,
+            3,
+            Duration::from_millis(10),
+        )
+        .await;
+
+        let err = result.err().expect("start should fail");
+        assert!(format!("{:#}", err).contains("after 3 attempts"));
+        assert_eq!(attempts(dir.path()), 3);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_worker_spawn_failure_is_not_retried() {
+        let trace = HostContext::new("Runner").get_trace("JobDispatcher");
+        let dir = tempfile::tempdir().unwrap();
+        let mut channel = ProcessChannel::new();
+        channel.start_server(dir.path()).unwrap();
+
+        let result = JobDispatcher::start_worker(
+            &trace,
+            &dir.path().join("missing-worker"),
+            "unused",
+            "{}",
+            &mut channel,
+        )
+        .await;
+
+        assert!(matches!(result, Err(WorkerStartError::Fatal(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_job_summary_is_read_from_channel_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let (channel, worker_channel) = connected_channels(dir.path()).await;
+        worker_channel
+            .send_async(
+                MessageType::JobCompleted,
+                r#"{"resultCode":102,"errorCount":3,"warningCount":1}"#,
+            )
+            .await
+            .unwrap();
+        worker_channel.flush().await.unwrap();
+        drop(worker_channel);
+
+        let summary = JobDispatcher::read_job_summary(channel).await;
+        assert_eq!(
+            summary,
+            Some(WorkerJobSummary {
+                result_code: 102,
+                error_count: 3,
+                warning_count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_completed_job_appends_metrics_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = MetricsFileSink::new(dir.path().join("jobs.jsonl"));
+        let job_id = Uuid::new_v4();
+        let started_at = chrono::Utc::now() - chrono::Duration::seconds(2);
+
+        let mut exit = worker_exit(
+            TaskResultUtil::translate_to_return_code(TaskResult::Failed),
+            false,
+            &[],
+        );
+        exit.summary = Some(WorkerJobSummary {
+            result_code: exit.exit_code,
+            error_count: 2,
+            warning_count: 4,
+        });
+        let metrics = JobDispatcher::job_metrics(job_id, 42, "build", started_at, &Ok(exit));
+        sink.append(&metrics).unwrap();
+
+        let content = std::fs::read_to_string(sink.path()).unwrap();
+        assert_eq!(content.lines().count(), 1);
+        let line: serde_json::Value = serde_json::from_str(content.trim_end()).unwrap();
+        assert_eq!(line["jobId"], job_id.to_string());
+        assert_eq!(line["requestId"], 42);
+        assert_eq!(line["jobDisplayName"], "build");
+        assert_eq!(line["result"], "Failed");
+        assert_eq!(line["exitCode"], 102);
+        assert_eq!(line["errorCount"], 2);
+        assert_eq!(line["warningCount"], 4);
+        assert!(line["durationMs"].as_u64().unwrap() >= 2000);
+        assert!(line["startedAt"].is_string());
+        assert!(line["completedAt"].is_string());
+    }
+
+    #[test]
+    fn test_metrics_result_for_cancelled_and_failed_workers() {
+        let started_at = chrono::Utc::now();
+        let cancelled = worker_exit(constants::return_code::TERMINATED_ERROR, true, &[]);
+        let metrics =
+            JobDispatcher::job_metrics(Uuid::new_v4(), 1, "job", started_at, &Ok(cancelled));
+        assert_eq!(metrics.result, "Canceled");
+        assert_eq!(metrics.error_count, 0);
+
+        let never_started = Err(anyhow::anyhow!("Worker failed to start"));
+        let metrics =
+            JobDispatcher::job_metrics(Uuid::new_v4(), 1, "job", started_at, &never_started);
+        assert_eq!(metrics.result, "Failed");
+        assert_eq!(metrics.exit_code, None);
+    }
+
+    #[test]
+    fn test_cancelled_exit_is_not_a_crash() {
+        let exit = worker_exit(constants::return_code::TERMINATED_ERROR, true, &[]);
+        assert!(JobDispatcher::crash_telemetry(Uuid::new_v4(), &exit).is_none());
+    }
+
+    fn dispatcher_in(root: &std::path::Path) -> JobDispatcher {
+        let context = HostContext::new("Runner");
+        context.set_root_override(root.to_path_buf());
+        JobDispatcher::new(context)
+    }
+
+    fn persist_in_flight(root: &std::path::Path, jobs: &[InFlightJob]) {
+        std::fs::write(
+            root.join(".inflight_jobs"),
+            serde_json::to_string(jobs).unwrap(),
+        )
+        .unwrap();
+    }
+
+    /// Answer one request per status line, in order, then stop listening.
+    async fn serve_statuses(statuses: &[&str]) -> String {
+        let responses = statuses.iter().map(|status| MockResponse::new(status));
+        MockHttpServer::start(responses.collect())
+            .await
+            .url()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_pending_completion_is_kept_until_accepted() {
+        let root = tempfile::tempdir().unwrap();
+        let dispatcher = dispatcher_in(root.path());
+        let pending = PendingCompletion {
+            run_service_url: serve_statuses(&["503 Service Unavailable", "200 OK"]).await,
+            payload: serde_json::json!({ "planId": "plan-1", "jobId": "job-1" }),
+        };
+        PendingCompletionStore::new(root.path().join(".pending_completions"))
+            .add(pending.clone())
+            .unwrap();
+
+        assert!(dispatcher
+            .complete_pending_job(&pending, "token")
+            .await
+            .is_err());
+        assert_eq!(
+            dispatcher.pending_completions(),
+            std::slice::from_ref(&pending)
+        );
+
+        dispatcher
+            .complete_pending_job(&pending, "token")
+            .await
+            .unwrap();
+        assert!(dispatcher.pending_completions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_orphaned_job_is_completed_through_run_service() {
+        let root = tempfile::tempdir().unwrap();
+        let dispatcher = dispatcher_in(root.path());
+        let orphan = OrphanedJob {
+            job: InFlightJob {
+                job_id: Uuid::new_v4(),
+                plan_id: "plan-1".to_string(),
+                request_id: 1,
+                worker_pid: None,
+                run_service_url: Some(
+                    serve_statuses(&["500 Internal Server Error", "200 OK"]).await,
+                ),
+            },
+            result: TaskResult::Abandoned,
+        };
+
+        let err = dispatcher
+            .complete_orphaned_job(&orphan, "token")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("HTTP 500"), "{}", err);
+        dispatcher
+            .complete_orphaned_job(&orphan, "token")
+            .await
+            .unwrap();
+    }
+
+    fn worker_crash(run_service_url: Option<String>, exit_code: i32) -> WorkerCrash {
+        let job_id = Uuid::new_v4();
+        WorkerCrash {
+            job: InFlightJob {
+                job_id,
+                plan_id: "plan-1".to_string(),
+                request_id: 1,
+                worker_pid: None,
+                run_service_url,
+            },
+            issue: TelemetryIssue::worker_crash(job_id, exit_code, &[]),
+        }
+    }
+
+    #[test]
+    fn test_worker_crash_fails_the_job_with_its_telemetry() {
+        let crash = worker_crash(None, 139);
+        let payload = crash.completion_payload();
+        assert_eq!(payload["jobId"], crash.job.job_id.to_string());
+        assert_eq!(payload["conclusion"], "failed");
+        assert_eq!(payload["telemetry"][0]["type"], "error");
+        assert_eq!(
+            payload["telemetry"][0]["data"][constants::INTERNAL_TELEMETRY_ISSUE_DATA_KEY],
+            constants::WORKER_CRASH
+        );
+    }
+
+    #[tokio::test]
+    async fn test_crashed_job_is_completed_through_run_service() {
+        let root = tempfile::tempdir().unwrap();
+        let dispatcher = dispatcher_in(root.path());
+
+        let crash = worker_crash(Some(serve_statuses(&["200 OK"]).await), 139);
+        dispatcher
+            .complete_crashed_job(&crash, "token")
+            .await
+            .unwrap();
+
+        let err = dispatcher
+            .complete_crashed_job(&worker_crash(None, 139), "token")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No Run Service URL"), "{}", err);
+    }
+
+    #[test]
+    fn test_in_flight_jobs_are_written_atomically() {
+        let root = tempfile::tempdir().unwrap();
+        let store = InFlightJobStore::new(root.path().join(".inflight_jobs"));
+        let job = InFlightJob {
+            job_id: Uuid::new_v4(),
+            plan_id: "plan-1".to_string(),
+            request_id: 1,
+            worker_pid: None,
+            run_service_url: None,
+        };
+
+        store.update(|jobs| jobs.push(job.clone())).unwrap();
+        assert_eq!(store.load(), vec![job]);
+        assert!(!root.path().join(".inflight_jobs.tmp").exists());
+
+        store.update(|jobs| jobs.clear()).unwrap();
+        assert!(!root.path().join(".inflight_jobs").exists());
+    }
+
+    #[test]
+    fn test_in_flight_job_from_request() {
+        let request: AgentJobRequestMessage = serde_json::from_str(
+            r#"{
+                "jobId": "5a0f4c9e-8a43-4c8b-9f3e-0d5f1f3f6b21",
+                "requestId": 42,
+                "plan": {"planId": "plan-1"},
+                "resources": {"endpoints": [
+                    {"name": "Other", "url": "https://other.example.com/"},
+                    {"name": "SystemVssConnection", "url": "https://run.example.com/"}
+                ]}
+            }"#,
+        )
+        .unwrap();
+
+        let job = InFlightJob::from_request(&request);
+        assert_eq!(job.job_id, request.job_id);
+        assert_eq!(job.plan_id, "plan-1");
+        assert_eq!(job.request_id, 42);
+        assert_eq!(job.worker_pid, None);
+        assert_eq!(
+            job.run_service_url.as_deref(),
+            Some("https://run.example.com/")
+        );
+    }
+
+    #[test]
+    fn test_persisted_in_flight_job_is_reconciled_on_startup() {
+        let root = tempfile::tempdir().unwrap();
+        let job = InFlightJob {
+            job_id: Uuid::new_v4(),
+            plan_id: "plan-1".to_string(),
+            request_id: 7,
+            worker_pid: None,
+            run_service_url: Some("https://run.example.com".to_string()),
+        };
+        persist_in_flight(root.path(), std::slice::from_ref(&job));
+
+        let dispatcher = dispatcher_in(root.path());
+        let orphans = dispatcher.reconcile_orphaned_jobs();
+
+        assert_eq!(
+            orphans,
+            vec![OrphanedJob {
+                job,
+                result: TaskResult::Failed
+            }]
+        );
+        // The record is cleared, so the job is only reconciled once
+        assert!(!root.path().join(".inflight_jobs").exists());
+        assert!(dispatcher.reconcile_orphaned_jobs().is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_leaves_unrelated_process_running() {
+        let root = tempfile::tempdir().unwrap();
+        // A PID reused by a process that is not a worker
+        persist_in_flight(
+            root.path(),
+            &[InFlightJob {
+                job_id: Uuid::new_v4(),
+                plan_id: "plan-1".to_string(),
+                request_id: 1,
+                worker_pid: Some(std::process::id()),
+                run_service_url: None,
+            }],
+        );
+
+        let orphans = dispatcher_in(root.path()).reconcile_orphaned_jobs();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].result, TaskResult::Failed);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_orphaned_running_worker_is_killed_and_abandoned() {
+        let root = tempfile::tempdir().unwrap();
+        let worker = fake_worker(root.path(), "while true; do sleep 0.1; done");
+        let mut child = std::process::Command::new(&worker)
+            .arg("--pipeIn")
+            .arg("/tmp/orphaned.sock")
+            .spawn()
+            .unwrap();
+
+        persist_in_flight(
+            root.path(),
+            &[InFlightJob {
+                job_id: Uuid::new_v4(),
+                plan_id: "plan-1".to_string(),
+                request_id: 1,
+                worker_pid: Some(child.id()),
+                run_service_url: None,
+            }],
+        );
+
+        let orphans = dispatcher_in(root.path()).reconcile_orphaned_jobs();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].result, TaskResult::Abandoned);
+
+        let status = child.wait().unwrap();
+        assert!(!status.success());
+    }
+
+    #[test]
+    fn test_worker_path_override_is_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let worker = dir.path().join("Patched.Worker");
+        std::fs::write(&worker, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&worker, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let mut dispatcher = JobDispatcher::new(HostContext::new("Test"));
+        dispatcher.set_worker_path(Some(worker.clone()));
+        assert_eq!(dispatcher.find_worker_binary().unwrap(), worker);
+    }
+
+    #[test]
+    fn test_missing_worker_path_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("Missing.Worker");
+
+        let mut dispatcher = JobDispatcher::new(HostContext::new("Test"));
+        dispatcher.set_worker_path(Some(missing.clone()));
+        let err = dispatcher.find_worker_binary().unwrap_err().to_string();
+        assert!(err.contains("does not exist"), "{}", err);
+        assert!(err.contains(&missing.display().to_string()), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_executable_worker_path_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let worker = dir.path().join("Runner.Worker");
+        std::fs::write(&worker, "").unwrap();
+
+        let err = JobDispatcher::validate_worker_path(&worker)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("is not executable"), "{}", err);
+        assert!(JobDispatcher::validate_worker_path(dir.path()).is_err());
+    }
+}
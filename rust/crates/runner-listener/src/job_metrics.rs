@@ -0,0 +1,231 @@
+// Per-job metrics for the `--metrics-file` sink.
+//
+// The dispatcher appends one JSON object per line for every job it finishes:
+// the job's identity, result, timing and annotation totals. The annotation
+// totals come from the worker's `JobCompleted` IPC message; a worker that
+// crashed before sending it is recorded with zero annotations.
+//
+// When an append would grow the file past its size limit, the file is
+// rotated first: `metrics.jsonl` becomes `metrics.jsonl.1`, `.1` becomes
+// `.2`, and so on up to `MAX_ROTATED_FILES`, dropping the oldest.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use runner_common::util::task_result_util::TaskResult;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Size at which the metrics file is rotated, unless configured otherwise.
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Number of rotated files kept next to the active one.
+pub const MAX_ROTATED_FILES: u32 = 3;
+
+/// The body of the worker's `JobCompleted` IPC message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerJobSummary {
+    pub result_code: i32,
+    #[serde(default)]
+    pub error_count: u32,
+    #[serde(default)]
+    pub warning_count: u32,
+}
+
+/// One line of the metrics file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobMetrics {
+    pub job_id: Uuid,
+    pub request_id: u64,
+    pub job_display_name: String,
+    /// The job result, e.g. `Succeeded` or `Canceled`.
+    pub result: String,
+    /// The worker's exit code; absent when the worker never started.
+    pub exit_code: Option<i32>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub error_count: u32,
+    pub warning_count: u32,
+}
+
+impl JobMetrics {
+    /// Metrics for a job that ran from `started_at` to `completed_at`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        job_id: Uuid,
+        request_id: u64,
+        job_display_name: &str,
+        result: TaskResult,
+        exit_code: Option<i32>,
+        started_at: DateTime<Utc>,
+        completed_at: DateTime<Utc>,
+        summary: Option<WorkerJobSummary>,
+    ) -> Self {
+        let summary = summary.unwrap_or_default();
+        Self {
+            job_id,
+            request_id,
+            job_display_name: job_display_name.to_string(),
+            result: result.to_string(),
+            exit_code,
+            started_at,
+            completed_at,
+            duration_ms: (completed_at - started_at).num_milliseconds().max(0) as u64,
+            error_count: summary.error_count,
+            warning_count: summary.warning_count,
+        }
+    }
+}
+
+/// Appends job metrics to a size-rotated JSON lines file.
+pub struct MetricsFileSink {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Serializes appends and rotation between concurrently finishing jobs.
+    lock: Mutex<()>,
+}
+
+impl MetricsFileSink {
+    /// Create a sink writing to `path`, rotated at `DEFAULT_MAX_FILE_BYTES`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes: DEFAULT_MAX_FILE_BYTES,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Rotate the file once it would exceed `max_bytes`.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// The active metrics file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one job's metrics as a JSON line.
+    pub fn append(&self, metrics: &JobMetrics) -> Result<()> {
+        let mut line = serde_json::to_string(metrics).context("Failed to serialize job metrics")?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().unwrap();
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let current = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if current > 0 && current + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to append job metrics to {}", self.path.display()))
+    }
+
+    /// Shift `path.N` to `path.N+1` and the active file to `path.1`.
+    fn rotate(&self) -> Result<()> {
+        for index in (1..MAX_ROTATED_FILES).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(index + 1))
+                    .with_context(|| format!("Failed to rotate {}", from.display()))?;
+            }
+        }
+        std::fs::rename(&self.path, self.rotated_path(1))
+            .with_context(|| format!("Failed to rotate {}", self.path.display()))
+    }
+
+    /// The path of the `index`-th rotated file.
+    fn rotated_path(&self, index: u32) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(name: &str) -> JobMetrics {
+        let started_at = Utc::now();
+        JobMetrics::new(
+            Uuid::new_v4(),
+            7,
+            name,
+            TaskResult::Succeeded,
+            Some(100),
+            started_at,
+            started_at + chrono::Duration::milliseconds(1500),
+            Some(WorkerJobSummary {
+                result_code: 100,
+                error_count: 1,
+                warning_count: 2,
+            }),
+        )
+    }
+
+    #[test]
+    fn test_append_writes_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = MetricsFileSink::new(dir.path().join("metrics/jobs.jsonl"));
+
+        sink.append(&metrics("build")).unwrap();
+        sink.append(&metrics("test")).unwrap();
+
+        let content = std::fs::read_to_string(sink.path()).unwrap();
+        let lines: Vec<JobMetrics> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].job_display_name, "build");
+        assert_eq!(lines[0].duration_ms, 1500);
+        assert_eq!(lines[0].error_count, 1);
+        assert_eq!(lines[0].warning_count, 2);
+        assert_eq!(lines[1].job_display_name, "test");
+    }
+
+    #[test]
+    fn test_file_is_rotated_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.jsonl");
+        let line_len = serde_json::to_string(&metrics("job-0")).unwrap().len() as u64 + 1;
+        // Room for two lines per file
+        let sink = MetricsFileSink::new(&path).with_max_bytes(line_len * 2);
+
+        for i in 0..9 {
+            sink.append(&metrics(&format!("job-{}", i))).unwrap();
+        }
+
+        let names = |path: &Path| -> Vec<String> {
+            std::fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| {
+                    serde_json::from_str::<JobMetrics>(line)
+                        .unwrap()
+                        .job_display_name
+                })
+                .collect()
+        };
+        assert_eq!(names(&path), vec!["job-8"]);
+        assert_eq!(names(&sink.rotated_path(1)), vec!["job-6", "job-7"]);
+        assert_eq!(names(&sink.rotated_path(2)), vec!["job-4", "job-5"]);
+        assert_eq!(names(&sink.rotated_path(3)), vec!["job-2", "job-3"]);
+        assert!(!sink.rotated_path(4).exists());
+    }
+}
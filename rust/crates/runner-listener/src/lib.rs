@@ -11,6 +11,7 @@ pub mod command_settings;
 pub mod configuration;
 pub mod error_throttler;
 pub mod job_dispatcher;
+pub mod job_metrics;
 pub mod message_listener;
 pub mod runner;
 pub mod runner_config_updater;
@@ -2,6 +2,8 @@
 // Creates sessions, polls for messages, handles OAuth refresh, session conflicts, clock skew.
 
 use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
 use runner_common::config_store::{ConfigurationStore, RunnerSettings};
 use runner_common::constants;
 use runner_common::credential_data::CredentialData;
@@ -24,6 +26,84 @@ const GET_MESSAGE_TIMEOUT: Duration = Duration::from_secs(30);
 /// Delay before re-creating a session after a conflict (5s).
 const SESSION_CONFLICT_DELAY: Duration = Duration::from_secs(5);
 
+/// Refresh the access token when it expires within this window, so a poll
+/// never goes out with a token that lapses mid-request.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+// ---------------------------------------------------------------------------
+// Errors
+// ---------------------------------------------------------------------------
+
+/// Marker in a session-create `404` body saying the runner itself is gone,
+/// rather than the pool or endpoint.
+const RUNNER_NOT_FOUND_MARKER: &str = "TaskAgentNotFoundException";
+
+/// A failed poll for the next message, classified by whether retrying can
+/// ever succeed.
+#[derive(Debug, thiserror::Error)]
+pub enum ListenerError {
+    /// The runner was removed from the server (HTTP 410). Fatal.
+    #[error("The runner no longer exists on the server (HTTP {status}): {body}")]
+    RunnerDeleted { status: u16, body: String },
+    /// The runner's credentials are not allowed to listen (HTTP 403). Fatal.
+    #[error("The runner is not authorized to listen for jobs (HTTP {status}): {body}")]
+    AccessDenied { status: u16, body: String },
+    /// Any other failure, e.g. the service being unavailable. Retried with
+    /// backoff.
+    #[error("Get message failed with HTTP {status}: {body}")]
+    Transient { status: u16, body: String },
+}
+
+impl ListenerError {
+    /// Classify a failed get-message response by its HTTP status.
+    pub fn from_status(status: u16, body: String) -> Self {
+        match status {
+            410 => Self::RunnerDeleted { status, body },
+            403 => Self::AccessDenied { status, body },
+            _ => Self::Transient { status, body },
+        }
+    }
+
+    /// Classify a failed session-create response. Besides `410`, a `404`
+    /// naming the runner means it was removed; anything else is retried.
+    pub fn from_session_status(status: u16, body: String) -> Self {
+        match status {
+            410 => Self::RunnerDeleted { status, body },
+            404 if body.contains(RUNNER_NOT_FOUND_MARKER) => Self::RunnerDeleted { status, body },
+            _ => Self::Transient { status, body },
+        }
+    }
+
+    /// Whether the message loop must stop instead of retrying.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, Self::Transient { .. })
+    }
+
+    /// The exit code for the listener when this error stops the loop.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::RunnerDeleted { .. } => constants::return_code::RUNNER_REMOVED,
+            Self::AccessDenied { .. } => constants::return_code::TERMINATED_ERROR,
+            Self::Transient { .. } => constants::return_code::RETRYABLE_ERROR,
+        }
+    }
+
+    /// Whether `error` says the runner was removed from the server.
+    pub fn is_runner_removed(error: &anyhow::Error) -> bool {
+        matches!(
+            error.downcast_ref::<ListenerError>(),
+            Some(ListenerError::RunnerDeleted { .. })
+        )
+    }
+
+    /// The fatal `ListenerError` behind `error`, if there is one.
+    pub fn fatal(error: &anyhow::Error) -> Option<&ListenerError> {
+        error
+            .downcast_ref::<ListenerError>()
+            .filter(|listener_error| listener_error.is_fatal())
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Message types (wire format)
 // ---------------------------------------------------------------------------
@@ -114,8 +194,13 @@ pub struct MessageListener {
     last_message_id: u64,
     /// Access token for the current session.
     access_token: Option<String>,
+    /// When `access_token` expires, if it is a JWT with an `exp` claim.
+    access_token_expires_at: Option<DateTime<Utc>>,
     /// Server clock skew detected during authentication.
     clock_skew: Duration,
+    /// Broker base URL from the last `BrokerMigration` in this session.
+    /// Messages are polled from it directly until the session is recreated.
+    broker_base_url: Option<String>,
 }
 
 impl MessageListener {
@@ -130,7 +215,9 @@ impl MessageListener {
             credentials: None,
             last_message_id: 0,
             access_token: None,
+            access_token_expires_at: None,
             clock_skew: Duration::ZERO,
+            broker_base_url: None,
         }
     }
 
@@ -179,9 +266,15 @@ impl MessageListener {
                         session.session_id, session.owner_name
                     ));
                     self.session = Some(session);
+                    self.broker_base_url = None;
                     return Ok(());
                 }
                 Err(e) => {
+                    // A removed runner can never create a session
+                    if ListenerError::is_runner_removed(&e) {
+                        return Err(e);
+                    }
+
                     // Check for session conflict (HTTP 409)
                     let err_str = format!("{:?}", e);
                     if err_str.contains("409") || err_str.contains("Conflict") {
@@ -233,7 +326,7 @@ impl MessageListener {
     ) -> Result<TaskAgentSession> {
         // Obtain an access token using the credential data
         let token = self.obtain_access_token(credentials).await?;
-        self.access_token = Some(token.clone());
+        self.set_access_token(token.clone());
 
         let client = runner_common::HttpClientFactory::create_client(&self.context.web_proxy)?;
 
@@ -263,11 +356,7 @@ impl MessageListener {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Session create failed with HTTP {}: {}",
-                status.as_u16(),
-                body
-            ));
+            return Err(ListenerError::from_session_status(status.as_u16(), body).into());
         }
 
         // Detect clock skew from server Date header
@@ -303,6 +392,8 @@ impl MessageListener {
         &mut self,
         cancel: CancellationToken,
     ) -> Result<Option<TaskAgentMessage>> {
+        self.refresh_expiring_access_token().await;
+
         let session = self
             .session
             .as_ref()
@@ -318,6 +409,29 @@ impl MessageListener {
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No access token available"))?;
 
+        // Once migrated, skip the Actions service and go straight to the broker
+        if let Some(broker_base_url) = self.broker_base_url.clone() {
+            return match self
+                .get_message_from_broker(&broker_base_url, session, settings, token, cancel)
+                .await
+            {
+                Ok(Some(message)) => {
+                    self.record_message(&message);
+                    Ok(Some(message))
+                }
+                Ok(None) => Ok(None),
+                Err(e) if ListenerError::fatal(&e).is_some() => Err(e),
+                Err(e) => {
+                    self.trace.warning(&format!(
+                        "Broker message request failed: {}. Rediscovering the broker through the Actions service",
+                        e
+                    ));
+                    self.broker_base_url = None;
+                    Ok(None)
+                }
+            };
+        }
+
         let client = runner_common::HttpClientFactory::create_client(&self.context.web_proxy)?;
 
         let base = settings.server_url.trim_end_matches('/');
@@ -375,7 +489,7 @@ impl MessageListener {
             if let Some(creds) = &self.credentials {
                 match self.obtain_access_token(creds).await {
                     Ok(new_token) => {
-                        self.access_token = Some(new_token);
+                        self.set_access_token(new_token);
                     }
                     Err(e) => {
                         self.trace.warning(&format!("Failed to refresh access token: {}", e));
@@ -387,11 +501,7 @@ impl MessageListener {
 
         if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Get message failed with HTTP {}: {}",
-                status.as_u16(),
-                body
-            ));
+            return Err(ListenerError::from_status(status.as_u16(), body).into());
         }
 
         let body_text = response
@@ -411,20 +521,41 @@ impl MessageListener {
             let migration: BrokerMigrationBody = serde_json::from_str(&message.body)
                 .context("Failed to parse BrokerMigration body")?;
 
-            match self.get_message_from_broker(&migration.broker_base_url, session, settings, token, cancel.clone()).await {
-                Ok(Some(broker_msg)) => {
-                    message = broker_msg;
-                }
-                Ok(None) => {
-                    return Ok(None);
-                }
+            let broker_message = match self
+                .get_message_from_broker(
+                    &migration.broker_base_url,
+                    session,
+                    settings,
+                    token,
+                    cancel.clone(),
+                )
+                .await
+            {
+                Ok(broker_message) => broker_message,
                 Err(e) => {
                     self.trace.warning(&format!("Broker message request failed: {}", e));
                     return Ok(None);
                 }
+            };
+
+            self.trace.info(&format!(
+                "Polling the broker at {} directly for the rest of this session",
+                migration.broker_base_url
+            ));
+            self.broker_base_url = Some(migration.broker_base_url);
+
+            match broker_message {
+                Some(broker_msg) => message = broker_msg,
+                None => return Ok(None),
             }
         }
 
+        self.record_message(&message);
+        Ok(Some(message))
+    }
+
+    /// Remember the ID of a received message for the next poll.
+    fn record_message(&mut self, message: &TaskAgentMessage) {
         if message.message_id > 0 {
             self.last_message_id = message.message_id;
         }
@@ -433,8 +564,6 @@ impl MessageListener {
             "Received message #{}: type={}",
             message.message_id, message.message_type
         ));
-
-        Ok(Some(message))
     }
 
     /// Follow up a BrokerMigration by getting the real message from the V2 broker.
@@ -527,11 +656,7 @@ impl MessageListener {
 
             if !status.is_success() {
                 let body = response.text().await.unwrap_or_default();
-                return Err(anyhow::anyhow!(
-                    "Broker message request failed with HTTP {}: {}",
-                    status.as_u16(),
-                    body
-                ));
+                return Err(ListenerError::from_status(status.as_u16(), body).into());
             }
 
             let body_text = response
@@ -575,16 +700,30 @@ impl MessageListener {
     }
 
     /// Acknowledge a runner request to the broker (best-effort, short timeout).
+    ///
+    /// The broker is the one named by the last `BrokerMigration`; without
+    /// one it is derived from the configured server URL, which on GHES is
+    /// the server itself.
     pub async fn acknowledge_message_async(&self, runner_request_id: &str) -> Result<()> {
         let session = self.session.as_ref()
             .ok_or_else(|| anyhow::anyhow!("No active session"))?;
         let token = self.access_token.as_ref()
             .ok_or_else(|| anyhow::anyhow!("No access token"))?;
+        let base = match (&self.broker_base_url, &self.settings) {
+            (Some(broker_base_url), _) => broker_base_url.clone(),
+            (None, Some(settings)) => {
+                let server_url = url::Url::parse(&settings.server_url)
+                    .context("Invalid server URL in runner settings")?;
+                runner_sdk::UrlUtil::broker_url(&server_url)
+            }
+            (None, None) => anyhow::bail!("No broker URL or settings loaded"),
+        };
 
         let client = runner_common::HttpClientFactory::create_client(&self.context.web_proxy)?;
 
         let url = format!(
-            "https://broker.actions.githubusercontent.com/acknowledge?sessionId={}&status=Online&runnerVersion={}&os={}&architecture={}",
+            "{}/acknowledge?sessionId={}&status=Online&runnerVersion={}&os={}&architecture={}",
+            base.trim_end_matches('/'),
             session.session_id,
             runner_sdk::build_constants::RunnerPackage::VERSION,
             constants::CURRENT_PLATFORM.label_name(),
@@ -653,6 +792,7 @@ impl MessageListener {
 
     /// Delete the session on the server.
     pub async fn delete_session_async(&mut self) -> Result<()> {
+        self.broker_base_url = None;
         let session = match self.session.take() {
             Some(s) => s,
             None => return Ok(()),
@@ -691,6 +831,49 @@ impl MessageListener {
         Ok(())
     }
 
+    /// Store a new access token along with its expiry.
+    fn set_access_token(&mut self, token: String) {
+        self.access_token_expires_at = token_expiry(&token);
+        self.access_token = Some(token);
+    }
+
+    /// Whether the access token expires within `TOKEN_REFRESH_MARGIN` of `now`.
+    ///
+    /// Tokens without a readable expiry are only refreshed after a 401.
+    fn access_token_expiring(&self, now: DateTime<Utc>) -> bool {
+        match self.access_token_expires_at {
+            Some(expires_at) => {
+                let margin = chrono::Duration::from_std(TOKEN_REFRESH_MARGIN)
+                    .unwrap_or_else(|_| chrono::Duration::zero());
+                expires_at - margin <= now
+            }
+            None => false,
+        }
+    }
+
+    /// Refresh the access token ahead of its expiry. On failure the current
+    /// token is kept; a 401 on the next request triggers another attempt.
+    async fn refresh_expiring_access_token(&mut self) {
+        if !self.access_token_expiring(Utc::now()) {
+            return;
+        }
+        let Some(creds) = &self.credentials else {
+            return;
+        };
+
+        self.trace
+            .info("Access token is about to expire — refreshing");
+        match self.obtain_access_token(creds).await {
+            Ok(new_token) => {
+                self.set_access_token(new_token);
+            }
+            Err(e) => {
+                self.trace
+                    .warning(&format!("Failed to refresh access token: {}", e));
+            }
+        }
+    }
+
     /// Obtain an access token from the credential data.
     async fn obtain_access_token(&self, credentials: &CredentialData) -> Result<String> {
         // If the credential data has an OAuth access token, use that directly
@@ -799,3 +982,278 @@ impl MessageListener {
         self.clock_skew
     }
 }
+
+/// The `exp` claim of a JWT access token. The signature is not verified;
+/// the expiry only decides when to ask for a new token.
+fn token_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let payload = token.split('.').nth(1)?;
+    let claims = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&claims).ok()?;
+    DateTime::from_timestamp(claims.get("exp")?.as_i64()?, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runner_sdk::test_util::{MockHttpServer, MockResponse};
+
+    /// An unsigned JWT expiring at `exp`.
+    fn jwt(exp: DateTime<Utc>) -> String {
+        let encode = |value: serde_json::Value| {
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(value.to_string())
+        };
+        format!(
+            "{}.{}.signature",
+            encode(serde_json::json!({"alg": "none", "typ": "JWT"})),
+            encode(serde_json::json!({"exp": exp.timestamp()}))
+        )
+    }
+
+    fn listener_with_token(token: String, refreshed: &str) -> MessageListener {
+        let mut listener = MessageListener::new(HostContext::new("Test"));
+        let mut credentials = CredentialData::new("OAuthAccessToken");
+        credentials
+            .data
+            .insert("accessToken".to_string(), refreshed.to_string());
+        listener.credentials = Some(credentials);
+        listener.set_access_token(token);
+        listener
+    }
+
+    #[test]
+    fn test_token_expiry_reads_exp_claim() {
+        let exp = DateTime::from_timestamp(Utc::now().timestamp() + 3600, 0).unwrap();
+        assert_eq!(token_expiry(&jwt(exp)), Some(exp));
+        assert_eq!(token_expiry("not-a-jwt"), None);
+        assert_eq!(token_expiry("a.!!!.c"), None);
+    }
+
+    #[tokio::test]
+    async fn test_near_expired_token_is_refreshed_before_polling() {
+        let server = MockHttpServer::start(vec![MockResponse::new("202 Accepted")]).await;
+        let expiring = jwt(Utc::now() + chrono::Duration::seconds(30));
+        let refreshed = jwt(Utc::now() + chrono::Duration::hours(1));
+        let mut listener = listener_with_token(expiring, &refreshed);
+        let mut settings = RunnerSettings::default();
+        settings.server_url = server.url().to_string();
+        listener.settings = Some(settings);
+        listener.session = Some(TaskAgentSession {
+            session_id: "session".to_string(),
+            owner_name: "owner".to_string(),
+            use_fips_encryption: false,
+            encryption_key: None,
+        });
+
+        let message = listener
+            .get_next_message_async(CancellationToken::new())
+            .await
+            .unwrap();
+        assert!(message.is_none());
+        let requests = server.finish().await;
+        let authorization = requests[0].header("authorization").unwrap_or_default();
+        assert_eq!(authorization, format!("Bearer {}", refreshed));
+        assert_eq!(listener.get_access_token(), Some(refreshed));
+    }
+
+    #[tokio::test]
+    async fn test_token_far_from_expiry_is_kept() {
+        let current = jwt(Utc::now() + chrono::Duration::hours(1));
+        let mut listener = listener_with_token(current.clone(), "other");
+
+        listener.refresh_expiring_access_token().await;
+        assert_eq!(listener.get_access_token(), Some(current));
+
+        // Opaque tokens have no expiry and are left alone until a 401
+        listener.set_access_token("opaque".to_string());
+        listener.refresh_expiring_access_token().await;
+        assert_eq!(listener.get_access_token(), Some("opaque".to_string()));
+    }
+
+    #[test]
+    fn test_listener_error_classification() {
+        let deleted = ListenerError::from_status(410, "gone".to_string());
+        assert!(matches!(deleted, ListenerError::RunnerDeleted { .. }));
+        assert!(deleted.is_fatal());
+        assert_eq!(deleted.exit_code(), constants::return_code::RUNNER_REMOVED);
+
+        let denied = ListenerError::from_status(403, String::new());
+        assert!(matches!(denied, ListenerError::AccessDenied { .. }));
+        assert!(denied.is_fatal());
+        assert_eq!(denied.exit_code(), constants::return_code::TERMINATED_ERROR);
+
+        let transient = ListenerError::from_status(503, "busy".to_string());
+        assert!(!transient.is_fatal());
+        assert_eq!(
+            transient.exit_code(),
+            constants::return_code::RETRYABLE_ERROR
+        );
+    }
+
+    #[test]
+    fn test_fatal_finds_listener_error_behind_anyhow() {
+        let error: anyhow::Error = ListenerError::from_status(410, String::new()).into();
+        assert!(ListenerError::fatal(&error).is_some());
+
+        let error: anyhow::Error = ListenerError::from_status(500, String::new()).into();
+        assert!(ListenerError::fatal(&error).is_none());
+        assert!(ListenerError::fatal(&anyhow::anyhow!("connection reset")).is_none());
+    }
+
+    #[test]
+    fn test_session_status_detects_removed_runner() {
+        let removed = |status, body: &str| {
+            let error: anyhow::Error =
+                ListenerError::from_session_status(status, body.to_string()).into();
+            ListenerError::is_runner_removed(&error)
+        };
+        assert!(removed(410, ""));
+        assert!(removed(
+            404,
+            r#"{"typeKey":"TaskAgentNotFoundException","message":"Runner 7 not found"}"#
+        ));
+        // A 404 for anything else (e.g. a wrong pool) is still retried
+        assert!(!removed(404, "Not Found"));
+        assert!(!removed(409, "Conflict"));
+    }
+
+    /// Answer one request per `(status, JSON body)`, in order.
+    async fn serve_responses(responses: Vec<(&str, String)>) -> MockHttpServer {
+        let responses = responses
+            .into_iter()
+            .map(|(status, body)| MockResponse::json(status, body));
+        MockHttpServer::start(responses.collect()).await
+    }
+
+    /// The request line of each request `server` received.
+    async fn request_lines(server: MockHttpServer) -> Vec<String> {
+        let requests = server.finish().await;
+        requests
+            .iter()
+            .map(|request| format!("{} {}", request.method, request.target))
+            .collect()
+    }
+
+    fn message_json(message_id: u64, message_type: &str, body: &str) -> String {
+        serde_json::json!({
+            "messageId": message_id,
+            "messageType": message_type,
+            "body": body,
+        })
+        .to_string()
+    }
+
+    fn listener_with_session(server_url: String) -> MessageListener {
+        let mut listener =
+            listener_with_token(jwt(Utc::now() + chrono::Duration::hours(1)), "unused");
+        let mut settings = RunnerSettings::default();
+        settings.server_url = server_url;
+        listener.settings = Some(settings);
+        listener.session = Some(TaskAgentSession {
+            session_id: "session".to_string(),
+            owner_name: "owner".to_string(),
+            use_fips_encryption: false,
+            encryption_key: None,
+        });
+        listener
+    }
+
+    #[tokio::test]
+    async fn test_broker_url_is_reused_after_migration() {
+        let broker = serve_responses(vec![
+            ("200 OK", message_json(1, "RunnerJobRequest", "first")),
+            ("200 OK", message_json(2, "RunnerJobRequest", "second")),
+        ])
+        .await;
+        let broker_url = broker.url().to_string();
+        let migration = serde_json::json!({ "brokerBaseUrl": broker_url }).to_string();
+        let server = serve_responses(vec![(
+            "200 OK",
+            message_json(0, "BrokerMigration", &migration),
+        )])
+        .await;
+        let mut listener = listener_with_session(server.url().to_string());
+
+        let first = listener
+            .get_next_message_async(CancellationToken::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.body, "first");
+        assert_eq!(
+            listener.broker_base_url.as_deref(),
+            Some(broker_url.as_str())
+        );
+
+        // The second poll goes straight to the broker; the Actions service
+        // only answers once
+        let second = listener
+            .get_next_message_async(CancellationToken::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.body, "second");
+        assert_eq!(listener.last_message_id, 2);
+
+        assert_eq!(server.finish().await.len(), 1);
+        let broker_requests = request_lines(broker).await;
+        assert_eq!(broker_requests.len(), 2);
+        assert!(broker_requests
+            .iter()
+            .all(|r| r.starts_with("GET /message?sessionId=session")));
+
+        // A new session rediscovers the broker
+        listener.delete_session_async().await.unwrap();
+        assert!(listener.broker_base_url.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_broker_failure_forgets_broker_url() {
+        let broker = serve_responses(vec![("500 Internal Server Error", String::new())]).await;
+        let mut listener = listener_with_session("http://127.0.0.1:9".to_string());
+        listener.broker_base_url = Some(broker.url().to_string());
+
+        let message = listener
+            .get_next_message_async(CancellationToken::new())
+            .await
+            .unwrap();
+        assert!(message.is_none());
+        assert!(listener.broker_base_url.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_acknowledge_targets_migrated_broker() {
+        let broker = serve_responses(vec![("200 OK", String::new())]).await;
+        let mut listener = listener_with_session("http://127.0.0.1:9".to_string());
+        listener.broker_base_url = Some(format!("{}/", broker.url()));
+
+        listener
+            .acknowledge_message_async("request-1")
+            .await
+            .unwrap();
+        let requests = request_lines(broker).await;
+        assert!(
+            requests[0].starts_with("POST /acknowledge?sessionId=session&"),
+            "{:?}",
+            requests
+        );
+    }
+
+    #[tokio::test]
+    async fn test_acknowledge_falls_back_to_server_url() {
+        let server = serve_responses(vec![("200 OK", String::new())]).await;
+        let listener = listener_with_session(server.url().to_string());
+
+        listener
+            .acknowledge_message_async("request-1")
+            .await
+            .unwrap();
+        let requests = request_lines(server).await;
+        assert!(
+            requests[0].starts_with("POST /acknowledge?sessionId=session&"),
+            "{:?}",
+            requests
+        );
+    }
+}
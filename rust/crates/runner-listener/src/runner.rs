@@ -4,9 +4,10 @@
 
 use anyhow::{Context, Result};
 use runner_common::config_store::{ConfigurationStore, RunnerSettings};
-use runner_common::constants::{self, WellKnownDirectory};
+use runner_common::constants::{self, WellKnownConfigFile, WellKnownDirectory};
 use runner_common::host_context::HostContext;
 use runner_common::runner_service::ShutdownReason;
+use runner_common::terminal::{ConsoleColor, Terminal};
 use runner_common::tracing::Tracing;
 use runner_sdk::TraceWriter;
 use std::sync::Arc;
@@ -18,9 +19,10 @@ use crate::broker_message_listener::{BrokerMessageListener, BrokerMessageType};
 use crate::checks;
 use crate::command_settings::CommandSettings;
 use crate::configuration::config_manager::ConfigManager;
+use crate::configuration::rsa_key_manager::RsaKeyManager;
 use crate::error_throttler::ErrorThrottler;
 use crate::job_dispatcher::{AgentJobRequestMessage, JobCancelMessage, JobDispatcher};
-use crate::message_listener::{MessageListener, MessageType};
+use crate::message_listener::{ListenerError, MessageListener, MessageType};
 use crate::runner_config_updater::{RunnerConfigUpdater, RunnerRefreshConfigMessage};
 use crate::self_updater::{AgentRefreshMessage, SelfUpdater};
 use crate::self_updater_v2::{RunnerRefreshMessage, SelfUpdaterV2};
@@ -51,6 +53,36 @@ struct AcquireJobRequest {
     billing_owner_id: String,
 }
 
+/// How many jobs the runner takes before exiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunMode {
+    /// Keep taking jobs until shut down.
+    Continuous,
+    /// `--once`: exit after one job, keeping the configuration so the runner
+    /// can be started again.
+    Once,
+    /// An ephemeral runner is deregistered by the server after its one job,
+    /// so its local configuration is deleted when it exits.
+    Ephemeral,
+}
+
+impl RunMode {
+    fn from_settings(settings: &CommandSettings, runner_settings: &RunnerSettings) -> Self {
+        if runner_settings.is_ephemeral {
+            RunMode::Ephemeral
+        } else if settings.is_once() {
+            RunMode::Once
+        } else {
+            RunMode::Continuous
+        }
+    }
+
+    /// Whether the runner exits after a single job.
+    fn is_single_job(self) -> bool {
+        self != RunMode::Continuous
+    }
+}
+
 /// Grace period before force shutdown.
 #[allow(dead_code)]
 const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(30);
@@ -69,13 +101,18 @@ const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(30);
 pub struct Runner {
     context: Arc<HostContext>,
     trace: Tracing,
+    terminal: Terminal,
 }
 
 impl Runner {
     /// Create a new `Runner`.
     pub fn new(context: Arc<HostContext>) -> Self {
         let trace = context.get_trace("Runner");
-        Self { context, trace }
+        Self {
+            context,
+            trace,
+            terminal: Terminal::new(),
+        }
     }
 
     /// Parse CLI args and dispatch to the appropriate command handler.
@@ -109,6 +146,7 @@ impl Runner {
         match settings.command() {
             Some("configure") => self.configure(&settings).await,
             Some("remove") => self.remove(&settings).await,
+            Some("rotatekey") => self.rotate_key().await,
             Some("warmup") => self.warmup().await,
             Some("run") | None => self.run_async(&settings).await,
             Some(cmd) => {
@@ -151,6 +189,22 @@ impl Runner {
         Ok(constants::return_code::SUCCESS)
     }
 
+    /// Handle the "rotatekey" command.
+    async fn rotate_key(&self) -> Result<i32> {
+        self.trace.info("Executing 'rotatekey' command");
+
+        if !ConfigurationStore::new(&self.context).is_configured() {
+            anyhow::bail!("The runner is not configured, so it has no key to rotate");
+        }
+        RsaKeyManager::new(self.context.clone())
+            .rotate_key()
+            .await
+            .context("Key rotation failed")?;
+
+        self.terminal.write_success_message("Runner key rotated");
+        Ok(constants::return_code::SUCCESS)
+    }
+
     /// Handle the "warmup" command.
     async fn warmup(&self) -> Result<i32> {
         self.trace.info("Executing 'warmup' command");
@@ -191,6 +245,7 @@ impl Runner {
         println!("Commands:");
         println!("  ./config.sh         Configure the runner");
         println!("  ./config.sh remove  Remove the runner");
+        println!("  ./config.sh rotatekey  Rotate the runner's RSA key");
         println!("  ./run.sh            Run the runner interactively");
         println!();
         println!("Options:");
@@ -207,7 +262,12 @@ impl Runner {
         println!("  --unattended        Run in unattended mode (no prompts)");
         println!("  --ephemeral         Configure as an ephemeral runner");
         println!("  --disableupdate     Disable automatic runner updates");
+        println!("  --generateEnvFiles  Write .path and .env files for the launcher scripts");
         println!("  --once              Run one job and then exit");
+        println!("  --timeout <minutes> Maximum job duration, capping longer job timeouts");
+        println!("  --worker-path <path> Worker binary to run jobs with (testing patched workers)");
+        println!("  --metrics-file <path> Append a JSON line of metrics per completed job");
+        println!("  --max-backoff <seconds> Longest wait between retries of server errors");
         println!("  --pat <pat>         Personal access token (for remove)");
         Ok(constants::return_code::SUCCESS)
     }
@@ -216,18 +276,21 @@ impl Runner {
     async fn run_checks(&self, settings: &CommandSettings) -> Result<i32> {
         self.trace.info("Running connectivity checks");
         let url = settings.get_url();
-        let results = checks::run_all_checks(url.as_deref(), &self.trace).await;
+        let results = checks::run_all_checks(url.as_deref(), &self.context, &self.trace).await;
 
         let output = checks::format_check_results(&results);
-        println!("{}", output);
+        self.terminal.write_line(&output, None);
 
         let all_passed = results.iter().all(|r| r.passed);
 
+        self.terminal.write_empty_line();
         if all_passed {
-            println!("\nAll checks passed.");
+            self.terminal
+                .write_line("All checks passed.", Some(ConsoleColor::Green));
             Ok(constants::return_code::SUCCESS)
         } else {
-            println!("\nSome checks failed.");
+            self.terminal
+                .write_line("Some checks failed.", Some(ConsoleColor::Red));
             Ok(constants::return_code::TERMINATED_ERROR)
         }
     }
@@ -250,6 +313,16 @@ impl Runner {
 
         // Verify the runner is configured
         let config_store = ConfigurationStore::new(&self.context);
+        match config_store.migrate_legacy_config() {
+            Ok(true) => self
+                .trace
+                .info("Converted legacy runner settings and credentials"),
+            Ok(false) => {}
+            Err(e) => self.trace.warning(&format!(
+                "Failed to convert legacy runner configuration: {:#}",
+                e
+            )),
+        }
         if !config_store.is_configured() {
             self.trace.error(
                 "Runner is not configured. Run './config.sh' first.",
@@ -271,16 +344,16 @@ impl Runner {
         }
 
         // Determine run mode
-        let is_run_once = settings.is_once() || runner_settings.is_ephemeral;
+        let run_mode = RunMode::from_settings(settings, &runner_settings);
         let is_v2_flow = runner_settings.use_v2_flow;
 
         self.trace.info(&format!(
-            "Runner settings: name={}, pool={}, ephemeral={}, v2_flow={}, run_once={}",
+            "Runner settings: name={}, pool={}, ephemeral={}, v2_flow={}, run_mode={:?}",
             runner_settings.agent_name,
             runner_settings.pool_name,
             runner_settings.is_ephemeral,
             is_v2_flow,
-            is_run_once,
+            run_mode,
         ));
 
         // Set up Ctrl+C / SIGTERM handler
@@ -310,10 +383,45 @@ impl Runner {
 
         // Set up the job dispatcher
         let mut job_dispatcher = JobDispatcher::new(self.context.clone());
+        match settings.get_job_max_timeout() {
+            Ok(ceiling) => job_dispatcher.set_max_job_timeout(ceiling),
+            Err(e) => {
+                self.trace.error(&format!("{:#}", e));
+                self.terminal.write_error(&format!("{:#}", e));
+                return Ok(constants::return_code::TERMINATED_ERROR);
+            }
+        }
+        if let Some(worker_path) = settings.get_worker_path() {
+            if let Err(e) = JobDispatcher::validate_worker_path(&worker_path) {
+                self.trace.error(&format!("{:#}", e));
+                self.terminal.write_error(&format!("{:#}", e));
+                return Ok(constants::return_code::TERMINATED_ERROR);
+            }
+            self.trace.info(&format!(
+                "Using worker binary override: {}",
+                worker_path.display()
+            ));
+            job_dispatcher.set_worker_path(Some(worker_path));
+        }
+        if let Some(metrics_file) = settings.get_metrics_file() {
+            self.trace.info(&format!(
+                "Writing job metrics to {}",
+                metrics_file.display()
+            ));
+            job_dispatcher.set_metrics_file(Some(metrics_file));
+        }
+
+        let max_backoff = settings.get_max_backoff();
+        if let Some(max_backoff) = max_backoff {
+            self.trace.info(&format!(
+                "Backing off at most {}s between retries",
+                max_backoff.as_secs()
+            ));
+        }
 
         // Run-once channel
         let (run_once_tx, mut run_once_rx) = mpsc::channel::<bool>(1);
-        if is_run_once {
+        if run_mode.is_single_job() {
             job_dispatcher.set_run_once_channel(run_once_tx);
         }
 
@@ -322,8 +430,9 @@ impl Runner {
             self.run_v2_message_loop(
                 &runner_settings,
                 &job_dispatcher,
-                is_run_once,
+                run_mode,
                 &mut run_once_rx,
+                max_backoff,
                 shutdown_token.clone(),
             )
             .await
@@ -331,8 +440,9 @@ impl Runner {
             self.run_v1_message_loop(
                 &runner_settings,
                 &job_dispatcher,
-                is_run_once,
+                run_mode,
                 &mut run_once_rx,
+                max_backoff,
                 shutdown_token.clone(),
             )
             .await
@@ -344,6 +454,70 @@ impl Runner {
         result
     }
 
+    /// The runner was removed on the server: delete its local configuration,
+    /// which can never connect again, and exit with `RUNNER_REMOVED` so an
+    /// orchestrator knows to provision a new runner.
+    fn handle_runner_removed(&self, error: &anyhow::Error) -> i32 {
+        self.trace.error(&format!(
+            "{:#}. Removing the local runner configuration.",
+            error
+        ));
+        self.terminal.write_error(
+            "This runner has been removed from GitHub. Its local configuration was deleted; configure a new runner to continue.",
+        );
+
+        self.delete_local_config();
+        constants::return_code::RUNNER_REMOVED
+    }
+
+    /// Whether the server has now said twice in a row that the runner was
+    /// removed. The configuration cannot be recovered once deleted, so a
+    /// single report, which may be a server glitch, is only retried.
+    fn runner_removal_confirmed(&self, suspected: &mut bool, error: &anyhow::Error) -> bool {
+        if *suspected {
+            return true;
+        }
+        *suspected = true;
+        self.trace.warning(&format!(
+            "{:#}. Retrying to confirm the runner was removed.",
+            error
+        ));
+        false
+    }
+
+    /// Exit after the single job of a `--once` or ephemeral run. The server
+    /// deregisters an ephemeral runner once its job is done, so its local
+    /// configuration goes too; a `--once` runner keeps it and can be started
+    /// again.
+    fn finish_single_job(&self, run_mode: RunMode) -> i32 {
+        if run_mode == RunMode::Ephemeral {
+            self.trace.info(
+                "Ephemeral runner finished its job — removing the local runner configuration",
+            );
+            self.delete_local_config();
+        }
+        constants::return_code::SUCCESS
+    }
+
+    /// The message loop's throttler, capped at `max_backoff` when configured.
+    fn error_throttler(max_backoff: Option<Duration>) -> ErrorThrottler {
+        match max_backoff {
+            Some(max_backoff) => ErrorThrottler::new().with_max_backoff(max_backoff),
+            None => ErrorThrottler::new(),
+        }
+    }
+
+    /// Delete the runner's settings, credentials and RSA key.
+    fn delete_local_config(&self) {
+        let config_store = ConfigurationStore::new(&self.context);
+        config_store.delete_settings();
+        config_store.delete_credential();
+        let _ = std::fs::remove_file(
+            self.context
+                .get_config_file(WellKnownConfigFile::RSACredentials),
+        );
+    }
+
     // -----------------------------------------------------------------------
     // V1 message loop (legacy Actions service)
     // -----------------------------------------------------------------------
@@ -353,20 +527,39 @@ impl Runner {
         &self,
         runner_settings: &RunnerSettings,
         job_dispatcher: &JobDispatcher,
-        is_run_once: bool,
+        run_mode: RunMode,
         run_once_rx: &mut mpsc::Receiver<bool>,
+        max_backoff: Option<Duration>,
         shutdown_token: CancellationToken,
     ) -> Result<i32> {
         let mut listener = MessageListener::new(self.context.clone());
-        let mut error_throttler = ErrorThrottler::new();
+        let mut error_throttler = Self::error_throttler(max_backoff);
 
         // Create session
-        listener
-            .create_session_async(shutdown_token.clone())
-            .await
-            .context("Failed to create V1 session")?;
+        let mut removal_suspected = false;
+        loop {
+            match listener.create_session_async(shutdown_token.clone()).await {
+                Ok(()) => break,
+                Err(e) if ListenerError::is_runner_removed(&e) => {
+                    if self.runner_removal_confirmed(&mut removal_suspected, &e) {
+                        return Ok(self.handle_runner_removed(&e));
+                    }
+                    if !error_throttler
+                        .increment_and_wait(shutdown_token.clone())
+                        .await
+                    {
+                        return Err(e).context("Failed to create V1 session");
+                    }
+                }
+                Err(e) => return Err(e).context("Failed to create V1 session"),
+            }
+        }
+        error_throttler.reset();
+        removal_suspected = false;
 
         self.trace.info("V1 session created — entering message loop");
+        self.reconcile_orphaned_jobs(job_dispatcher, listener.get_access_token())
+            .await;
         println!(
             "√ Connected to GitHub\n\n{} Listening for Jobs",
             chrono::Local::now().format("%Y-%m-%d %H:%M:%SZ")
@@ -380,7 +573,13 @@ impl Runner {
             }
 
             // Poll for the next message
-            match listener.get_next_message_async(shutdown_token.clone()).await {
+            let next = listener
+                .get_next_message_async(shutdown_token.clone())
+                .await;
+            if next.is_ok() {
+                removal_suspected = false;
+            }
+            match next {
                 Ok(Some(message)) => {
                     error_throttler.reset();
 
@@ -475,7 +674,7 @@ impl Runner {
                             self.trace.info("Received agent refresh message (V1 update)");
                             match serde_json::from_str::<AgentRefreshMessage>(&message.body) {
                                 Ok(refresh_msg) => {
-                                    if let Err(e) = self
+                                    match self
                                         .handle_v1_update(
                                             &refresh_msg,
                                             runner_settings,
@@ -483,14 +682,16 @@ impl Runner {
                                         )
                                         .await
                                     {
-                                        self.trace.error(&format!(
-                                            "V1 self-update failed: {:?}",
-                                            e
-                                        ));
-                                    } else {
-                                        // Update was successful — exit for restart
-                                        let _ = listener.delete_session_async().await;
-                                        return Ok(constants::return_code::RUNNER_UPDATING);
+                                        Ok(true) => {
+                                            // Update was prepared — exit for restart
+                                            let _ = listener.delete_session_async().await;
+                                            return Ok(constants::return_code::RUNNER_UPDATING);
+                                        }
+                                        Ok(false) => {}
+                                        Err(e) => {
+                                            self.trace
+                                                .error(&format!("V1 self-update failed: {:?}", e));
+                                        }
                                     }
                                 }
                                 Err(e) => {
@@ -528,9 +729,21 @@ impl Runner {
                 }
 
                 Err(e) => {
-                    self.trace.error(&format!(
-                        "Error polling for V1 messages: {:?}",
-                        e
+                    if ListenerError::is_runner_removed(&e) {
+                        if self.runner_removal_confirmed(&mut removal_suspected, &e) {
+                            return Ok(self.handle_runner_removed(&e));
+                        }
+                    } else if let Some(fatal) = ListenerError::fatal(&e) {
+                        self.trace
+                            .error(&format!("Stopping the message loop: {}", fatal));
+                        return Ok(fatal.exit_code());
+                    } else {
+                        self.trace
+                            .error(&format!("Error polling for V1 messages: {:?}", e));
+                    }
+                    self.trace.warning(&format!(
+                        "Backing off for {:.1}s before retrying",
+                        error_throttler.current_delay().as_secs_f64()
                     ));
                     if !error_throttler
                         .increment_and_wait(shutdown_token.clone())
@@ -541,13 +754,16 @@ impl Runner {
                 }
             }
 
+            self.report_worker_crashes(job_dispatcher, listener.get_access_token())
+                .await;
+
             // Check run-once completion
-            if is_run_once {
+            if run_mode.is_single_job() {
                 if let Ok(_completed) = run_once_rx.try_recv() {
                     self.trace
                         .info("Run-once job completed — exiting message loop");
                     let _ = listener.delete_session_async().await;
-                    return Ok(constants::return_code::SUCCESS);
+                    return Ok(self.finish_single_job(run_mode));
                 }
             }
         }
@@ -567,20 +783,39 @@ impl Runner {
         &self,
         runner_settings: &RunnerSettings,
         job_dispatcher: &JobDispatcher,
-        is_run_once: bool,
+        run_mode: RunMode,
         run_once_rx: &mut mpsc::Receiver<bool>,
+        max_backoff: Option<Duration>,
         shutdown_token: CancellationToken,
     ) -> Result<i32> {
         let mut listener = BrokerMessageListener::new(self.context.clone());
-        let mut error_throttler = ErrorThrottler::new();
+        let mut error_throttler = Self::error_throttler(max_backoff);
 
         // Create broker session
-        listener
-            .create_session_async(shutdown_token.clone())
-            .await
-            .context("Failed to create V2 broker session")?;
+        let mut removal_suspected = false;
+        loop {
+            match listener.create_session_async(shutdown_token.clone()).await {
+                Ok(()) => break,
+                Err(e) if ListenerError::is_runner_removed(&e) => {
+                    if self.runner_removal_confirmed(&mut removal_suspected, &e) {
+                        return Ok(self.handle_runner_removed(&e));
+                    }
+                    if !error_throttler
+                        .increment_and_wait(shutdown_token.clone())
+                        .await
+                    {
+                        return Err(e).context("Failed to create V2 broker session");
+                    }
+                }
+                Err(e) => return Err(e).context("Failed to create V2 broker session"),
+            }
+        }
+        error_throttler.reset();
+        removal_suspected = false;
 
         self.trace.info("V2 broker session created — entering message loop");
+        self.reconcile_orphaned_jobs(job_dispatcher, listener.get_access_token())
+            .await;
         println!(
             "√ Connected to GitHub (V2)\n\n{} Listening for Jobs",
             chrono::Local::now().format("%Y-%m-%d %H:%M:%SZ")
@@ -593,7 +828,13 @@ impl Runner {
                 break;
             }
 
-            match listener.get_next_message_async(shutdown_token.clone()).await {
+            let next = listener
+                .get_next_message_async(shutdown_token.clone())
+                .await;
+            if next.is_ok() {
+                removal_suspected = false;
+            }
+            match next {
                 Ok(Some(message)) => {
                     error_throttler.reset();
 
@@ -640,7 +881,7 @@ impl Runner {
                             self.trace.info("Received runner refresh (V2 update)");
                             match serde_json::from_str::<RunnerRefreshMessage>(&message.body) {
                                 Ok(refresh_msg) => {
-                                    if let Err(e) = self
+                                    match self
                                         .handle_v2_update(
                                             &refresh_msg,
                                             runner_settings,
@@ -648,13 +889,15 @@ impl Runner {
                                         )
                                         .await
                                     {
-                                        self.trace.error(&format!(
-                                            "V2 self-update failed: {:?}",
-                                            e
-                                        ));
-                                    } else {
-                                        let _ = listener.delete_session_async().await;
-                                        return Ok(constants::return_code::RUNNER_UPDATING);
+                                        Ok(true) => {
+                                            let _ = listener.delete_session_async().await;
+                                            return Ok(constants::return_code::RUNNER_UPDATING);
+                                        }
+                                        Ok(false) => {}
+                                        Err(e) => {
+                                            self.trace
+                                                .error(&format!("V2 self-update failed: {:?}", e));
+                                        }
                                     }
                                 }
                                 Err(e) => {
@@ -736,9 +979,21 @@ impl Runner {
                 }
 
                 Err(e) => {
-                    self.trace.error(&format!(
-                        "Error polling V2 broker: {:?}",
-                        e
+                    if ListenerError::is_runner_removed(&e) {
+                        if self.runner_removal_confirmed(&mut removal_suspected, &e) {
+                            return Ok(self.handle_runner_removed(&e));
+                        }
+                    } else if let Some(fatal) = ListenerError::fatal(&e) {
+                        self.trace
+                            .error(&format!("Stopping the message loop: {}", fatal));
+                        return Ok(fatal.exit_code());
+                    } else {
+                        self.trace
+                            .error(&format!("Error polling V2 broker: {:?}", e));
+                    }
+                    self.trace.warning(&format!(
+                        "Backing off for {:.1}s before retrying",
+                        error_throttler.current_delay().as_secs_f64()
                     ));
                     if !error_throttler
                         .increment_and_wait(shutdown_token.clone())
@@ -749,13 +1004,16 @@ impl Runner {
                 }
             }
 
+            self.report_worker_crashes(job_dispatcher, listener.get_access_token())
+                .await;
+
             // Check run-once completion
-            if is_run_once {
+            if run_mode.is_single_job() {
                 if let Ok(_completed) = run_once_rx.try_recv() {
                     self.trace
                         .info("Run-once job completed — exiting V2 message loop");
                     let _ = listener.delete_session_async().await;
-                    return Ok(constants::return_code::SUCCESS);
+                    return Ok(self.finish_single_job(run_mode));
                 }
             }
         }
@@ -765,6 +1023,86 @@ impl Runner {
     }
 
     // -----------------------------------------------------------------------
+    // Crash recovery
+    // -----------------------------------------------------------------------
+
+    /// Complete the jobs a previous listener process left in flight, and
+    /// deliver completions a worker could not, so the server does not keep
+    /// waiting on them.
+    async fn reconcile_orphaned_jobs(
+        &self,
+        job_dispatcher: &JobDispatcher,
+        access_token: Option<String>,
+    ) {
+        for orphan in job_dispatcher.reconcile_orphaned_jobs() {
+            let Some(token) = access_token.as_deref() else {
+                self.trace.warning(&format!(
+                    "No access token — cannot report orphaned job {}",
+                    orphan.job.job_id
+                ));
+                continue;
+            };
+            match job_dispatcher.complete_orphaned_job(&orphan, token).await {
+                Ok(()) => self.trace.info(&format!(
+                    "Reported orphaned job {} as {}",
+                    orphan.job.job_id, orphan.result
+                )),
+                Err(e) => self.trace.warning(&format!(
+                    "Failed to report orphaned job {}: {:#}",
+                    orphan.job.job_id, e
+                )),
+            }
+        }
+
+        for pending in job_dispatcher.pending_completions() {
+            let job_id = pending.payload["jobId"].as_str().unwrap_or_default();
+            let Some(token) = access_token.as_deref() else {
+                self.trace.warning(&format!(
+                    "No access token — cannot report pending completion of job {}",
+                    job_id
+                ));
+                continue;
+            };
+            match job_dispatcher.complete_pending_job(&pending, token).await {
+                Ok(()) => self
+                    .trace
+                    .info(&format!("Reported pending completion of job {}", job_id)),
+                Err(e) => self.trace.warning(&format!(
+                    "Failed to report pending completion of job {}: {:#}",
+                    job_id, e
+                )),
+            }
+        }
+    }
+
+    /// Fail the jobs whose worker crashed before completing them, with the
+    /// crash telemetry attached.
+    async fn report_worker_crashes(
+        &self,
+        job_dispatcher: &JobDispatcher,
+        access_token: Option<String>,
+    ) {
+        for crash in job_dispatcher.take_telemetry() {
+            let Some(token) = access_token.as_deref() else {
+                self.trace.warning(&format!(
+                    "No access token — cannot report crashed job {}",
+                    crash.job.job_id
+                ));
+                continue;
+            };
+            match job_dispatcher.complete_crashed_job(&crash, token).await {
+                Ok(()) => self.trace.info(&format!(
+                    "Reported crashed job {} as failed",
+                    crash.job.job_id
+                )),
+                Err(e) => self.trace.warning(&format!(
+                    "Failed to report crashed job {}: {:#}",
+                    crash.job.job_id, e
+                )),
+            }
+        }
+    }
+
     // -----------------------------------------------------------------------
     // Broker job acquisition
     // -----------------------------------------------------------------------
@@ -849,30 +1187,49 @@ impl Runner {
     // Self-update handlers
     // -----------------------------------------------------------------------
 
+    /// Why a self-update message should be declined, if it should.
+    ///
+    /// Ephemeral runners implicitly decline updates: they only live for one
+    /// job, so applying an update mid-life is wasted work. This is separate
+    /// from `disable_update`, which the user sets explicitly and which is also
+    /// reported to the server when polling. Config refreshes are not affected.
+    fn update_decline_reason(runner_settings: &RunnerSettings) -> Option<&'static str> {
+        if runner_settings.disable_update {
+            Some("Self-update is disabled")
+        } else if runner_settings.is_ephemeral {
+            Some("Ephemeral runners do not self-update")
+        } else {
+            None
+        }
+    }
+
     /// Handle a V1 self-update (AgentRefreshMessage).
+    ///
+    /// Returns `true` if an update was prepared and the runner should exit
+    /// to restart.
     async fn handle_v1_update(
         &self,
         message: &AgentRefreshMessage,
         runner_settings: &RunnerSettings,
         cancel: CancellationToken,
-    ) -> Result<()> {
-        // Check if updates are disabled
-        if runner_settings.disable_update {
+    ) -> Result<bool> {
+        if let Some(reason) = Self::update_decline_reason(runner_settings) {
             self.trace
-                .info("Self-update is disabled — ignoring AgentRefreshMessage");
-            return Ok(());
+                .info(&format!("{} — ignoring AgentRefreshMessage", reason));
+            return Ok(false);
         }
 
         let updater = SelfUpdater::new(self.context.clone());
 
         if !updater.needs_update(&message.target_version) {
-            return Ok(());
+            return Ok(false);
         }
 
         let update_dir = updater
             .download_latest_runner(
                 &message.target_version,
                 message.download_url.as_deref(),
+                message.hash_value.as_deref(),
                 cancel,
             )
             .await?;
@@ -880,26 +1237,29 @@ impl Runner {
         let _script = updater.generate_update_script(&update_dir)?;
 
         self.trace.info("V1 self-update prepared — runner will restart");
-        Ok(())
+        Ok(true)
     }
 
     /// Handle a V2 self-update (RunnerRefreshMessage).
+    ///
+    /// Returns `true` if an update was prepared and the runner should exit
+    /// to restart.
     async fn handle_v2_update(
         &self,
         message: &RunnerRefreshMessage,
         runner_settings: &RunnerSettings,
         cancel: CancellationToken,
-    ) -> Result<()> {
-        if runner_settings.disable_update {
+    ) -> Result<bool> {
+        if let Some(reason) = Self::update_decline_reason(runner_settings) {
             self.trace
-                .info("Self-update is disabled — ignoring RunnerRefreshMessage");
-            return Ok(());
+                .info(&format!("{} — ignoring RunnerRefreshMessage", reason));
+            return Ok(false);
         }
 
         let updater = SelfUpdaterV2::new(self.context.clone());
 
         if !updater.needs_update(&message.target_version) {
-            return Ok(());
+            return Ok(false);
         }
 
         let update_dir = updater.download_and_verify(message, cancel).await?;
@@ -907,6 +1267,215 @@ impl Runner {
         let _script = updater.generate_update_script(&update_dir)?;
 
         self.trace.info("V2 self-update prepared — runner will restart");
-        Ok(())
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runner_sdk::test_util::{MockHttpServer, MockResponse};
+
+    fn ephemeral_settings() -> RunnerSettings {
+        let mut settings = RunnerSettings::default();
+        settings.is_ephemeral = true;
+        settings
+    }
+
+    #[test]
+    fn test_update_decline_reason() {
+        assert_eq!(
+            Runner::update_decline_reason(&RunnerSettings::default()),
+            None
+        );
+        assert_eq!(
+            Runner::update_decline_reason(&ephemeral_settings()),
+            Some("Ephemeral runners do not self-update")
+        );
+
+        let mut disabled = ephemeral_settings();
+        disabled.disable_update = true;
+        assert_eq!(
+            Runner::update_decline_reason(&disabled),
+            Some("Self-update is disabled")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ephemeral_runner_ignores_v1_update() {
+        let runner = Runner::new(HostContext::new("Runner"));
+        let message = AgentRefreshMessage {
+            target_version: "99.0.0".to_string(),
+            // Unreachable: the update must be declined before any download
+            download_url: Some("http://127.0.0.1:9/runner.tar.gz".to_string()),
+            hash_value: None,
+        };
+
+        let restart = runner
+            .handle_v1_update(&message, &ephemeral_settings(), CancellationToken::new())
+            .await
+            .unwrap();
+        assert!(!restart);
+    }
+
+    #[tokio::test]
+    async fn test_ephemeral_runner_ignores_v2_update() {
+        let runner = Runner::new(HostContext::new("Runner"));
+        let message = RunnerRefreshMessage {
+            target_version: "99.0.0".to_string(),
+            download_url: "http://127.0.0.1:9/runner.tar.gz".to_string(),
+            hash_value: "deadbeef".to_string(),
+        };
+
+        let restart = runner
+            .handle_v2_update(&message, &ephemeral_settings(), CancellationToken::new())
+            .await
+            .unwrap();
+        assert!(!restart);
+    }
+
+    /// Answer `count` requests with `410 Gone`, as for a runner deleted in
+    /// the UI.
+    async fn serve_gone(count: usize) -> String {
+        MockHttpServer::start(vec![MockResponse::new("410 Gone"); count])
+            .await
+            .url()
+            .to_string()
+    }
+
+    /// A configured runner whose server answers `gone` times with `410 Gone`.
+    async fn removed_runner(root: &std::path::Path, gone: usize) -> Arc<HostContext> {
+        let context = HostContext::new("Runner");
+        context.set_root_override(root.to_path_buf());
+
+        let mut settings = RunnerSettings::default();
+        settings.agent_id = 7;
+        settings.agent_name = "removed".to_string();
+        settings.server_url = serve_gone(gone).await;
+        let mut credentials =
+            runner_common::credential_data::CredentialData::new("OAuthAccessToken");
+        credentials
+            .data
+            .insert("accessToken".to_string(), "token".to_string());
+
+        let store = ConfigurationStore::new(&context);
+        store.save_settings(&settings).unwrap();
+        store.save_credential(&credentials).unwrap();
+        assert!(store.is_configured() && store.has_credentials());
+        context
+    }
+
+    async fn run_v1_loop(context: &Arc<HostContext>, cancel: CancellationToken) -> Result<i32> {
+        let settings = ConfigurationStore::new(context).get_settings().unwrap();
+        let runner = Runner::new(context.clone());
+        let (_run_once_tx, mut run_once_rx) = mpsc::channel(1);
+        runner
+            .run_v1_message_loop(
+                &settings,
+                &JobDispatcher::new(context.clone()),
+                RunMode::Continuous,
+                &mut run_once_rx,
+                None,
+                cancel,
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_removed_runner_deletes_config_and_exits() {
+        let root = tempfile::tempdir().unwrap();
+        let context = removed_runner(root.path(), 2).await;
+
+        let exit_code = run_v1_loop(&context, CancellationToken::new())
+            .await
+            .unwrap();
+
+        let store = ConfigurationStore::new(&context);
+        assert_eq!(exit_code, constants::return_code::RUNNER_REMOVED);
+        assert!(!store.is_configured());
+        assert!(!store.has_credentials());
+    }
+
+    #[tokio::test]
+    async fn test_single_removal_report_keeps_config() {
+        let root = tempfile::tempdir().unwrap();
+        let context = removed_runner(root.path(), 1).await;
+
+        // Stop while backing off before the confirming retry
+        let cancel = CancellationToken::new();
+        let stop = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            stop.cancel();
+        });
+        assert!(run_v1_loop(&context, cancel).await.is_err());
+
+        let store = ConfigurationStore::new(&context);
+        assert!(store.is_configured());
+        assert!(store.has_credentials());
+    }
+
+    #[test]
+    fn test_message_loop_throttler_uses_configured_cap() {
+        let cap = Duration::from_secs(300);
+        assert_eq!(Runner::error_throttler(Some(cap)).max_backoff(), cap);
+        assert_eq!(
+            Runner::error_throttler(None).max_backoff(),
+            crate::error_throttler::DEFAULT_MAX_BACKOFF
+        );
+    }
+
+    #[test]
+    fn test_run_mode_from_settings() {
+        let args = |args: &[&str]| {
+            CommandSettings::parse_from(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+        };
+        let persistent = RunnerSettings::default();
+
+        assert_eq!(
+            RunMode::from_settings(&args(&["run"]), &persistent),
+            RunMode::Continuous
+        );
+        assert_eq!(
+            RunMode::from_settings(&args(&["run", "--once"]), &persistent),
+            RunMode::Once
+        );
+        assert_eq!(
+            RunMode::from_settings(&args(&["run"]), &ephemeral_settings()),
+            RunMode::Ephemeral
+        );
+        assert_eq!(
+            RunMode::from_settings(&args(&["run", "--once"]), &ephemeral_settings()),
+            RunMode::Ephemeral
+        );
+        assert!(!RunMode::Continuous.is_single_job());
+        assert!(RunMode::Once.is_single_job());
+        assert!(RunMode::Ephemeral.is_single_job());
+    }
+
+    #[test]
+    fn test_single_job_cleanup_depends_on_run_mode() {
+        for (run_mode, keeps_config) in [(RunMode::Once, true), (RunMode::Ephemeral, false)] {
+            let root = tempfile::tempdir().unwrap();
+            let context = HostContext::new("Runner");
+            context.set_root_override(root.path().to_path_buf());
+
+            let store = ConfigurationStore::new(&context);
+            store.save_settings(&RunnerSettings::default()).unwrap();
+            store
+                .save_credential(&runner_common::credential_data::CredentialData::new(
+                    "OAuth",
+                ))
+                .unwrap();
+            let rsa_key = context.get_config_file(WellKnownConfigFile::RSACredentials);
+            std::fs::write(&rsa_key, "{}").unwrap();
+
+            let exit_code = Runner::new(context.clone()).finish_single_job(run_mode);
+
+            assert_eq!(exit_code, constants::return_code::SUCCESS, "{:?}", run_mode);
+            assert_eq!(store.is_configured(), keeps_config, "{:?}", run_mode);
+            assert_eq!(store.has_credentials(), keeps_config, "{:?}", run_mode);
+            assert_eq!(rsa_key.exists(), keeps_config, "{:?}", run_mode);
+        }
     }
 }
@@ -6,7 +6,7 @@ use anyhow::{Context, Result};
 use runner_common::constants::{self, WellKnownDirectory};
 use runner_common::host_context::HostContext;
 use runner_common::tracing::Tracing;
-use runner_sdk::TraceWriter;
+use runner_sdk::{IOUtil, TraceWriter};
 use serde::Deserialize;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -84,11 +84,15 @@ impl SelfUpdater {
 
     /// Download the latest runner package and prepare for update.
     ///
+    /// Interrupted downloads resume from where they stopped on the next
+    /// retry. When `hash_value` is provided the archive's SHA256 must match.
+    ///
     /// Returns the path to the update directory on success.
     pub async fn download_latest_runner(
         &self,
         target_version: &str,
         download_url: Option<&str>,
+        hash_value: Option<&str>,
         cancel: CancellationToken,
     ) -> Result<PathBuf> {
         let update_dir = self.context.get_directory(WellKnownDirectory::Update);
@@ -144,6 +148,17 @@ impl SelfUpdater {
             }
         }
 
+        match hash_value {
+            Some(hash) if !hash.is_empty() => {
+                self.trace.info("Verifying SHA256 hash...");
+                verify_sha256(&archive_path, hash)?;
+                self.trace.info("SHA256 hash verified successfully");
+            }
+            _ => self
+                .trace
+                .warning("No hash value provided — skipping verification"),
+        }
+
         // Extract the archive
         self.trace.info("Extracting update archive...");
         self.extract_archive(&archive_path, &update_dir)?;
@@ -326,29 +341,67 @@ start "" "%RUNNER_ROOT%\bin\Runner.Listener.exe" run
     }
 
     /// Download a file from a URL to a local path.
+    ///
+    /// The body is streamed into `<dest>.partial`, which is kept when the
+    /// transfer fails. The next call asks for the remainder with an HTTP
+    /// `Range` header and appends to it; a server that ignores the range
+    /// (HTTP 200) restarts the download from scratch. The partial file is
+    /// renamed to `dest` once complete.
     async fn download_file(&self, url: &str, dest: &Path) -> Result<()> {
         let client = runner_common::HttpClientFactory::create_client(&self.context.web_proxy)?;
+        let partial_path = partial_download_path(dest);
+        let resume_from = std::fs::metadata(&partial_path)
+            .map(|m| m.len())
+            .unwrap_or(0);
+
+        let mut request = client.get(url);
+        if resume_from > 0 {
+            self.trace
+                .info(&format!("Resuming download from byte {}", resume_from));
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+        }
 
-        let response = client
-            .get(url)
+        let mut response = request
             .send()
             .await
             .context("Failed to send download request")?;
 
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "Download failed with HTTP {}",
-                response.status().as_u16()
-            ));
-        }
+        let status = response.status();
+        let mut file = match status {
+            reqwest::StatusCode::PARTIAL_CONTENT => std::fs::OpenOptions::new()
+                .append(true)
+                .open(&partial_path)
+                .context("Failed to open partial download")?,
+            // The partial file already holds the whole archive
+            reqwest::StatusCode::RANGE_NOT_SATISFIABLE if resume_from > 0 => {
+                std::fs::rename(&partial_path, dest)
+                    .context("Failed to move completed download into place")?;
+                return Ok(());
+            }
+            s if s.is_success() => {
+                std::fs::File::create(&partial_path).context("Failed to create partial download")?
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Download failed with HTTP {}",
+                    status.as_u16()
+                ));
+            }
+        };
 
-        let bytes = response
-            .bytes()
+        while let Some(chunk) = response
+            .chunk()
             .await
-            .context("Failed to read download response body")?;
+            .context("Failed to read download response body")?
+        {
+            file.write_all(&chunk)
+                .context("Failed to write downloaded file to disk")?;
+        }
+        file.sync_all()?;
+        drop(file);
 
-        std::fs::write(dest, &bytes)
-            .context("Failed to write downloaded file to disk")?;
+        std::fs::rename(&partial_path, dest)
+            .context("Failed to move completed download into place")?;
 
         Ok(())
     }
@@ -389,3 +442,128 @@ start "" "%RUNNER_ROOT%\bin\Runner.Listener.exe" run
         Ok(())
     }
 }
+
+/// Where an in-progress download of `dest` is kept.
+fn partial_download_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_os_string();
+    name.push(".partial");
+    PathBuf::from(name)
+}
+
+/// Verify that the SHA256 of a file matches the expected hex digest.
+pub fn verify_sha256(file_path: &Path, expected_hex: &str) -> Result<()> {
+    let computed_hex = hex::encode(
+        IOUtil::sha256_file(file_path).context("Failed to hash file for verification")?,
+    );
+
+    let expected_lower = expected_hex.to_lowercase();
+    if computed_hex != expected_lower {
+        return Err(anyhow::anyhow!(
+            "SHA256 mismatch: expected={}, computed={}",
+            expected_lower,
+            computed_hex
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runner_sdk::test_util::{MockHttpServer, MockResponse};
+    use sha2::{Digest, Sha256};
+
+    /// Serve one scripted response per connection; returns the archive URL.
+    async fn serve(responses: Vec<MockResponse>) -> (String, MockHttpServer) {
+        let server = MockHttpServer::start(responses).await;
+        (format!("{}/runner.tar.gz", server.url()), server)
+    }
+
+    /// The `Range` header of each request the server received.
+    async fn ranges(server: MockHttpServer) -> Vec<Option<String>> {
+        let requests = server.finish().await;
+        requests
+            .iter()
+            .map(|request| request.header("range").map(str::to_string))
+            .collect()
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    fn archive_bytes() -> Vec<u8> {
+        (0..64 * 1024).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_download_resumes_with_range() {
+        let archive = archive_bytes();
+        let split = archive.len() / 3;
+        let (url, server) = serve(vec![
+            // Connection drops after the first third
+            MockResponse::new("200 OK")
+                .with_content_length(archive.len())
+                .with_body(&archive[..split]),
+            MockResponse::new("206 Partial Content")
+                .with_header(
+                    "Content-Range",
+                    &format!("bytes {}-{}/{}", split, archive.len() - 1, archive.len()),
+                )
+                .with_body(&archive[split..]),
+        ])
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("runner-update.tar.gz");
+        let updater = SelfUpdater::new(HostContext::new("Test"));
+
+        assert!(updater.download_file(&url, &dest).await.is_err());
+        assert!(!dest.exists());
+        assert_eq!(
+            std::fs::metadata(partial_download_path(&dest))
+                .unwrap()
+                .len(),
+            split as u64
+        );
+
+        updater.download_file(&url, &dest).await.unwrap();
+
+        assert_eq!(
+            ranges(server).await,
+            vec![None, Some(format!("bytes={}-", split))]
+        );
+        assert_eq!(std::fs::read(&dest).unwrap(), archive);
+        assert!(!partial_download_path(&dest).exists());
+        verify_sha256(&dest, &sha256_hex(&archive)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ignored_range_restarts_download() {
+        let archive = archive_bytes();
+        let (url, server) =
+            serve(vec![MockResponse::new("200 OK").with_body(archive.clone())]).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("runner-update.tar.gz");
+        std::fs::write(partial_download_path(&dest), b"stale partial data").unwrap();
+
+        let updater = SelfUpdater::new(HostContext::new("Test"));
+        updater.download_file(&url, &dest).await.unwrap();
+
+        assert_eq!(ranges(server).await, vec![Some("bytes=18-".to_string())]);
+        assert_eq!(std::fs::read(&dest).unwrap(), archive);
+    }
+
+    #[test]
+    fn test_verify_sha256_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("archive");
+        std::fs::write(&file, b"runner").unwrap();
+
+        verify_sha256(&file, &sha256_hex(b"runner").to_uppercase()).unwrap();
+        let err = verify_sha256(&file, &sha256_hex(b"other")).unwrap_err();
+        assert!(err.to_string().contains("SHA256 mismatch"));
+    }
+}
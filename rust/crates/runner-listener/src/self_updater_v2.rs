@@ -9,7 +9,6 @@ use runner_common::host_context::HostContext;
 use runner_common::tracing::Tracing;
 use runner_sdk::TraceWriter;
 use serde::Deserialize;
-use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
@@ -178,24 +177,7 @@ impl SelfUpdaterV2 {
 
     /// Verify the SHA256 hash of the downloaded file.
     fn verify_hash(&self, file_path: &Path, expected_hex: &str) -> Result<()> {
-        let data = std::fs::read(file_path)
-            .context("Failed to read file for hash verification")?;
-
-        let mut hasher = Sha256::new();
-        hasher.update(&data);
-        let computed = hasher.finalize();
-        let computed_hex = hex::encode(computed);
-
-        let expected_lower = expected_hex.to_lowercase();
-        if computed_hex != expected_lower {
-            return Err(anyhow::anyhow!(
-                "SHA256 mismatch: expected={}, computed={}",
-                expected_lower,
-                computed_hex
-            ));
-        }
-
-        Ok(())
+        crate::self_updater::verify_sha256(file_path, expected_hex)
     }
 
     /// Generate the platform-specific update script (delegates to the V1 updater logic).
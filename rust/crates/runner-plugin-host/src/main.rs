@@ -5,12 +5,13 @@
 // Usage:
 //   Runner.PluginHost action <plugin-name>
 //
-// The execution context JSON is read from stdin (one line).
+// The execution context JSON is read from stdin. It is usually a single
+// line, but may span several; reading stops once a complete JSON value has
+// arrived, so the worker does not have to close stdin.
 // All output is written to stdout using `##[...]` action commands so the
 // runner worker can parse trace / error messages.
 
 use anyhow::{Context, Result};
-use runner_plugins::{DownloadArtifactPlugin, PublishArtifactPlugin};
 use runner_sdk::{ActionPlugin, ActionPluginContext, StringUtil, TraceWriter};
 use std::io::BufRead;
 use std::process::ExitCode;
@@ -82,9 +83,10 @@ fn escape(input: &str) -> String {
 /// Resolve a plugin implementation by its fully-qualified type name.
 ///
 /// The C# host uses reflection (`Type.GetType`) to instantiate the plugin.
-/// In Rust we use a simple match against known type names. The names match the
-/// fully-qualified C# type names for backwards compatibility with the worker
-/// which passes these names as arguments.
+/// In Rust each plugin module contributes a table of names and factories to
+/// `runner_plugins::registered_plugins`. The names match the fully-qualified
+/// C# type names for backwards compatibility with the worker which passes
+/// these names as arguments.
 fn resolve_plugin(type_name: &str) -> Option<Box<dyn ActionPlugin>> {
     // Normalise: the worker may pass the full assembly-qualified name
     // e.g. "GitHub.Runner.Plugins.Artifact.PublishArtifact, Runner.Plugins"
@@ -95,19 +97,9 @@ fn resolve_plugin(type_name: &str) -> Option<Box<dyn ActionPlugin>> {
         .unwrap_or(type_name)
         .trim();
 
-    match normalized {
-        // Full C# type names
-        "GitHub.Runner.Plugins.Artifact.PublishArtifact" => {
-            Some(Box::new(PublishArtifactPlugin))
-        }
-        "GitHub.Runner.Plugins.Artifact.DownloadArtifact" => {
-            Some(Box::new(DownloadArtifactPlugin))
-        }
-        // Short names for convenience
-        "PublishArtifact" => Some(Box::new(PublishArtifactPlugin)),
-        "DownloadArtifact" => Some(Box::new(DownloadArtifactPlugin)),
-        _ => None,
-    }
+    runner_plugins::registered_plugins()
+        .find(|(name, _)| *name == normalized)
+        .map(|(_, factory)| factory())
 }
 
 // ---------------------------------------------------------------------------
@@ -133,6 +125,28 @@ fn main() -> ExitCode {
     }
 }
 
+/// Read a serialized execution context: lines up to and including the one
+/// that completes a JSON value, or everything up to EOF.
+///
+/// Input that is not valid JSON stops at the first non-empty line, so the
+/// deserialization error can report it instead of waiting for more input.
+fn read_execution_context<R: BufRead>(mut reader: R) -> Result<String> {
+    let mut context = String::new();
+    loop {
+        if reader.read_line(&mut context)? == 0 {
+            break;
+        }
+        if context.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<serde::de::IgnoredAny>(&context) {
+            Err(e) if e.is_eof() => continue,
+            _ => break,
+        }
+    }
+    Ok(context.trim().to_string())
+}
+
 fn run_plugin() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
 
@@ -156,16 +170,9 @@ fn run_plugin() -> Result<()> {
         anyhow::bail!("Assembly qualified name must not be empty");
     }
 
-    // Read the serialized execution context from stdin (one line).
-    let stdin = std::io::stdin();
-    let serialized_context = {
-        let mut line = String::new();
-        stdin
-            .lock()
-            .read_line(&mut line)
-            .context("Failed to read execution context from stdin")?;
-        line.trim_end().to_string()
-    };
+    // Read the serialized execution context from stdin.
+    let serialized_context = read_execution_context(std::io::stdin().lock())
+        .context("Failed to read execution context from stdin")?;
 
     if serialized_context.is_empty() {
         anyhow::bail!("Execution context from stdin must not be empty");
@@ -239,11 +246,62 @@ mod tests {
         assert!(resolve_plugin(full).is_some());
     }
 
+    #[test]
+    fn resolve_every_registered_plugin() {
+        for (name, _) in runner_plugins::registered_plugins() {
+            assert!(resolve_plugin(name).is_some(), "{name}");
+            assert!(
+                resolve_plugin(&format!("{name}, Runner.Plugins")).is_some(),
+                "{name}"
+            );
+        }
+        assert!(resolve_plugin("GitHub.Runner.Plugins.Repository.v1_1.CheckoutTask").is_some());
+    }
+
     #[test]
     fn resolve_unknown_plugin() {
         assert!(resolve_plugin("NoSuchPlugin").is_none());
     }
 
+    #[test]
+    fn malformed_context_error_names_position() {
+        let serialized_context =
+            r#"{"inputs": {"name": "artifact"}, "variables": {"a": "b",}, "endpoints": []}"#;
+        let err = StringUtil::convert_from_json::<ActionPluginContext>(serialized_context)
+            .context("Failed to deserialize execution context")
+            .unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "Failed to deserialize execution context: trailing comma at line 1, column 57 (byte 56)"
+        );
+    }
+
+    #[test]
+    fn read_single_line_context_without_eof() {
+        // A second context line must not be consumed or waited for
+        let input = "{\"inputs\": {}, \"variables\": {}, \"endpoints\": []}\nnext\n";
+        let mut reader = std::io::Cursor::new(input);
+        let context = read_execution_context(&mut reader).unwrap();
+        assert_eq!(
+            context,
+            r#"{"inputs": {}, "variables": {}, "endpoints": []}"#
+        );
+        assert_eq!(reader.position(), input.find("next").unwrap() as u64);
+    }
+
+    #[test]
+    fn read_multi_line_context() {
+        let input = "{\n  \"inputs\": {\"name\": \"line1\\nline2\"},\n  \"variables\": {},\n  \"endpoints\": []\n}\n";
+        let context = read_execution_context(input.as_bytes()).unwrap();
+        let parsed: ActionPluginContext = StringUtil::convert_from_json(&context).unwrap();
+        assert_eq!(parsed.inputs["name"], "line1\nline2");
+
+        // Truncated input is returned as-is for the deserializer to report
+        let context = read_execution_context("{\n  \"inputs\": {".as_bytes()).unwrap();
+        assert_eq!(context, "{\n  \"inputs\": {");
+        assert_eq!(read_execution_context("\n\n".as_bytes()).unwrap(), "");
+    }
+
     #[test]
     fn plugin_trace_writer_output() {
         // Just verify construction doesn't panic
@@ -0,0 +1,88 @@
+// Artifact name validation shared by the upload paths.
+//
+// Mirrors the rules GitHub applies to artifact names, so a bad name fails
+// before anything is uploaded instead of being rejected by the server.
+
+use anyhow::Result;
+
+/// Characters GitHub does not allow in an artifact name.
+pub const INVALID_ARTIFACT_NAME_CHARS: &[char] = &['"', ':', '<', '>', '|', '*', '?', '\\', '/'];
+
+/// Check `name` against GitHub's artifact name rules.
+///
+/// A name must not be empty, must not start or end with whitespace, and must
+/// not contain control characters or any of `INVALID_ARTIFACT_NAME_CHARS`.
+pub fn validate_artifact_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        anyhow::bail!("Artifact name can not be empty string");
+    }
+
+    if name.trim() != name {
+        anyhow::bail!(
+            "Artifact name is not valid: '{name}'. It cannot start or end with whitespace"
+        );
+    }
+
+    if let Some(ch) = name.chars().find(|ch| ch.is_control()) {
+        anyhow::bail!(
+            "Artifact name is not valid: {}. It cannot contain control characters ({:?})",
+            name.escape_debug(),
+            ch
+        );
+    }
+
+    if let Some(ch) = name
+        .chars()
+        .find(|ch| INVALID_ARTIFACT_NAME_CHARS.contains(ch))
+    {
+        anyhow::bail!(
+            "Artifact name is not valid: {name}. It contains '{ch}', but cannot contain \
+             '\"', ':', '<', '>', '|', '*', '?', '\\', and '/'"
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_names() {
+        let cases: &[(&str, Option<&str>)] = &[
+            ("my-artifact", None),
+            ("build output 1.2.3", None),
+            ("logs_(linux)", None),
+            ("résumé", None),
+            ("", Some("can not be empty")),
+            ("   ", Some("can not be empty")),
+            (" leading", Some("start or end with whitespace")),
+            ("trailing\t", Some("start or end with whitespace")),
+            ("tab\tinside", Some("control characters")),
+            ("bell\u{7}", Some("control characters")),
+            ("quote\"d", Some("contains '\"'")),
+            ("c:drive", Some("contains ':'")),
+            ("<angle", Some("contains '<'")),
+            ("angle>", Some("contains '>'")),
+            ("pi|pe", Some("contains '|'")),
+            ("star*", Some("contains '*'")),
+            ("what?", Some("contains '?'")),
+            ("back\\slash", Some("contains '\\'")),
+            ("for/ward", Some("contains '/'")),
+        ];
+
+        for (name, expected) in cases {
+            match (validate_artifact_name(name), expected) {
+                (Ok(()), None) => {}
+                (Err(e), Some(expected)) => assert!(
+                    e.to_string().contains(expected),
+                    "{name:?}: expected '{expected}', got '{e}'"
+                ),
+                (result, expected) => {
+                    panic!("{name:?}: expected {expected:?}, got {result:?}")
+                }
+            }
+        }
+    }
+}
@@ -5,12 +5,13 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use runner_sdk::{ActionPlugin, ActionPluginContext, TraceWriter, VssUtil};
+use runner_sdk::{ActionPlugin, ActionPluginContext, PathUtil, TraceWriter, VssUtil};
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 use crate::artifact::file_container_server::FileContainerServer;
 use crate::artifact::pipelines_server::PipelinesServer;
+use crate::artifact::results_server::{self, ResultsServer};
 
 /// Input names for the download-artifact action.
 mod input_names {
@@ -20,6 +21,8 @@ mod input_names {
     pub const ARTIFACT_NAME: &str = "artifact";
     /// The local path to download the artifact into.
     pub const PATH: &str = "path";
+    /// Glob of the files within the artifact to download, e.g. `**/*.log`.
+    pub const PATTERN: &str = "pattern";
 }
 
 /// Well-known variable keys used by the download-artifact plugin.
@@ -66,12 +69,31 @@ impl ActionPlugin for DownloadArtifactPlugin {
             target_path_input
         };
 
+        // Relative paths must stay inside the workspace.
         let target_path: PathBuf = if Path::new(&target_path_raw).is_absolute() {
             PathBuf::from(&target_path_raw)
         } else {
-            Path::new(&default_working_directory).join(&target_path_raw)
+            PathUtil::resolve_within(
+                Path::new(&default_working_directory),
+                Path::new(&target_path_raw),
+            )?
         };
 
+        let pattern = read_pattern(context)?;
+
+        // Newer runs store artifacts through the v4 (Results Service) API.
+        if let Some(results_url) = results_server::v4_service_url(context) {
+            return download_v4(
+                context,
+                &results_url,
+                &artifact_name,
+                &target_path,
+                pattern.as_ref(),
+                trace,
+            )
+            .await;
+        }
+
         // -----------------------------------------------------------
         // 2. Read build ID
         // -----------------------------------------------------------
@@ -126,7 +148,7 @@ impl ActionPlugin for DownloadArtifactPlugin {
         // 5. Download files from the file container
         // -----------------------------------------------------------
 
-        let file_container = FileContainerServer::new(
+        let mut file_container = FileContainerServer::new(
             http_client,
             &base_url,
             &auth_token,
@@ -134,6 +156,10 @@ impl ActionPlugin for DownloadArtifactPlugin {
             container_id,
             container_path,
         );
+        if let Some(pattern) = pattern {
+            trace.info(&format!("Downloading files matching '{pattern}'"));
+            file_container = file_container.with_file_pattern(pattern);
+        }
 
         file_container
             .download_from_container(trace, &target_path.to_string_lossy())
@@ -145,6 +171,62 @@ impl ActionPlugin for DownloadArtifactPlugin {
     }
 }
 
+/// The `pattern` input as a glob, if one was given.
+fn read_pattern(context: &ActionPluginContext) -> Result<Option<glob::Pattern>> {
+    match context.get_input(input_names::PATTERN, false)? {
+        Some(pattern) if !pattern.trim().is_empty() => glob::Pattern::new(pattern.trim())
+            .map(Some)
+            .with_context(|| format!("Invalid pattern: {pattern}")),
+        _ => Ok(None),
+    }
+}
+
+/// Download and extract the artifact zip through the v4 artifact API.
+async fn download_v4(
+    context: &ActionPluginContext,
+    results_url: &str,
+    artifact_name: &str,
+    target_path: &Path,
+    pattern: Option<&glob::Pattern>,
+    trace: &dyn TraceWriter,
+) -> Result<()> {
+    trace.info(&format!(
+        "Downloading artifact '{}' to: '{}'",
+        artifact_name,
+        target_path.display(),
+    ));
+
+    let (_, auth_token) = resolve_connection(context)?;
+    let http_client = VssUtil::create_http_client(&runner_sdk::RunnerWebProxy::new());
+    let results = ResultsServer::new(http_client, results_url, &auth_token)?;
+
+    let artifact = results
+        .get_artifact(artifact_name)
+        .await
+        .context("Failed to query artifact")?
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "The artifact '{}' could not be found, or is no longer available",
+                artifact_name,
+            )
+        })?;
+
+    let download_url = results
+        .get_signed_artifact_url(&artifact.name)
+        .await
+        .context("Failed to get artifact download URL")?;
+    let zip = tempfile::NamedTempFile::new().context("Failed to create the artifact zip")?;
+    results
+        .download_blob(&download_url, zip.path())
+        .await
+        .context("Failed to download artifact files")?;
+    let files = results_server::unpack_artifact(zip.path(), target_path, pattern)?;
+
+    trace.info(&format!("{files} files download succeed."));
+    trace.info("Artifact download finished.");
+    Ok(())
+}
+
 /// Resolve the `SystemVssConnection` endpoint from the plugin context.
 ///
 /// Returns `(base_url, access_token)`.
@@ -202,11 +284,25 @@ mod tests {
                     p
                 },
             }),
+            data: HashMap::new(),
         });
 
         ctx
     }
 
+    #[test]
+    fn pattern_input_is_parsed() {
+        let mut ctx = make_context("test-artifact");
+        assert!(read_pattern(&ctx).unwrap().is_none());
+
+        ctx.inputs
+            .insert("pattern".to_string(), " **/*.log ".to_string());
+        assert_eq!(read_pattern(&ctx).unwrap().unwrap().as_str(), "**/*.log");
+
+        ctx.inputs.insert("pattern".to_string(), "[".to_string());
+        assert!(read_pattern(&ctx).is_err());
+    }
+
     #[test]
     fn resolve_connection_works() {
         let ctx = make_context("test-artifact");
@@ -232,4 +328,22 @@ mod tests {
         };
         assert_eq!(target, "my-art");
     }
+
+    #[tokio::test]
+    async fn relative_path_escaping_workspace_is_rejected() {
+        let workspace = tempfile::tempdir().unwrap();
+        let mut ctx = make_context("my-artifact");
+        ctx.inputs
+            .insert("path".to_string(), "../../etc".to_string());
+        ctx.context.insert(
+            "github".to_string(),
+            serde_json::json!({ "workspace": workspace.path().to_string_lossy() }),
+        );
+
+        let err = DownloadArtifactPlugin
+            .run(&mut ctx, &runner_sdk::trace::NullTraceWriter)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("resolves outside of"), "{err}");
+    }
 }
@@ -4,8 +4,9 @@
 // Handles parallel upload / download of artifact files in chunks with retry.
 
 use anyhow::{Context, Result};
+use reqwest::header::AUTHORIZATION;
 use reqwest::{Client, StatusCode};
-use runner_sdk::TraceWriter;
+use runner_sdk::{TraceWriter, VssUtil};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicI32, Ordering};
@@ -21,6 +22,19 @@ const MAX_RETRIES: u32 = 3;
 /// Maximum concurrent uploads (matches C# cap of 2).
 const MAX_CONCURRENT_UPLOADS: usize = 2;
 
+/// How download patterns match paths: `*` stays within one directory and
+/// `**` spans any number of them.
+const PATTERN_MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Whether a file at `relative_path` inside an artifact matches `pattern`.
+pub fn matches_file_pattern(pattern: &glob::Pattern, relative_path: &str) -> bool {
+    pattern.matches_with(relative_path, PATTERN_MATCH_OPTIONS)
+}
+
 // ---------------------------------------------------------------------------
 // Container item types returned by the file container REST API
 // ---------------------------------------------------------------------------
@@ -55,11 +69,14 @@ pub struct FileContainerItem {
 pub struct FileContainerServer {
     client: Client,
     base_url: String,
-    auth_token: String,
+    /// `Authorization` header value sent with every request.
+    auth_header: String,
     #[allow(dead_code)]
     project_id: Uuid,
     container_id: i64,
     container_path: String,
+    /// Only files matching this pattern are downloaded.
+    file_pattern: Option<glob::Pattern>,
 }
 
 /// Holds the results of a parallel upload operation.
@@ -84,6 +101,16 @@ struct DownloadInfo {
     local_path: PathBuf,
 }
 
+/// What a download will do with each container item.
+#[derive(Debug, Default)]
+struct DownloadPlan {
+    folders: Vec<PathBuf>,
+    empty_files: Vec<PathBuf>,
+    downloads: Vec<DownloadInfo>,
+    /// Files left out because they do not match the file pattern.
+    skipped: usize,
+}
+
 /// Holds the results of a parallel download operation.
 #[derive(Debug, Default)]
 struct DownloadResult {
@@ -117,13 +144,21 @@ impl FileContainerServer {
         Self {
             client,
             base_url: base_url.trim_end_matches('/').to_string(),
-            auth_token: auth_token.to_string(),
+            auth_header: VssUtil::bearer_header(auth_token),
             project_id,
             container_id,
             container_path: container_path.to_string(),
+            file_pattern: None,
         }
     }
 
+    /// Download only the files whose path inside the artifact matches
+    /// `pattern`, e.g. `logs/*.txt` or `**/*.json`.
+    pub fn with_file_pattern(mut self, pattern: glob::Pattern) -> Self {
+        self.file_pattern = Some(pattern);
+        self
+    }
+
     // -----------------------------------------------------------------------
     // REST URL helpers
     // -----------------------------------------------------------------------
@@ -168,7 +203,8 @@ impl FileContainerServer {
     // Public API – Download
     // -----------------------------------------------------------------------
 
-    /// Download all files in the container to `destination`.
+    /// Download all files in the container to `destination`, or only the
+    /// ones matching the file pattern when one is set.
     ///
     /// Mirrors `DownloadFromContainerAsync` from the C# implementation.
     pub async fn download_from_container(
@@ -191,66 +227,35 @@ impl FileContainerServer {
         let mut items = container_items;
         items.sort_by(|a, b| a.path.cmp(&b.path));
 
-        let mut folders_created: u32 = 0;
-        let mut empty_files_created: u32 = 0;
-        let mut download_files: Vec<DownloadInfo> = Vec::new();
-
-        for item in &items {
-            // Verify the item path starts with the container path
-            if !item
-                .path
-                .to_lowercase()
-                .starts_with(&self.container_path.to_lowercase())
-            {
-                anyhow::bail!(
-                    "Item {} is not under #/{}/{}",
-                    item.path,
-                    self.container_id,
-                    self.container_path,
-                );
-            }
+        let plan = self.plan_download(trace, &items, destination)?;
+        if plan.skipped > 0 {
+            trace.info(&format!(
+                "{} files skipped, they do not match the pattern.",
+                plan.skipped
+            ));
+        }
 
-            let local_relative_path = item.path[self.container_path.len()..]
-                .trim_start_matches('/');
-            let local_path = Path::new(destination).join(local_relative_path);
+        let mut folders_created: u32 = 0;
+        for folder in &plan.folders {
+            fs::create_dir_all(folder)
+                .await
+                .with_context(|| format!("Failed to create directory {}", folder.display()))?;
+            folders_created += 1;
+        }
 
-            match item.item_type {
-                ContainerItemType::Folder => {
-                    trace.verbose(&format!("Ensure folder exists: {}", local_path.display()));
-                    fs::create_dir_all(&local_path).await.with_context(|| {
-                        format!("Failed to create directory {}", local_path.display())
-                    })?;
-                    folders_created += 1;
-                }
-                ContainerItemType::File => {
-                    if item.file_length == 0 {
-                        trace.verbose(&format!(
-                            "Create empty file at: {}",
-                            local_path.display()
-                        ));
-                        if let Some(parent) = local_path.parent() {
-                            fs::create_dir_all(parent).await?;
-                        }
-                        // Create (or truncate) the file
-                        fs::write(&local_path, b"").await.with_context(|| {
-                            format!("Failed to create empty file {}", local_path.display())
-                        })?;
-                        empty_files_created += 1;
-                    } else {
-                        trace.verbose(&format!(
-                            "Prepare download {} to {}",
-                            item.path,
-                            local_path.display()
-                        ));
-                        download_files.push(DownloadInfo {
-                            item_path: item.path.clone(),
-                            local_path,
-                        });
-                    }
-                }
+        let mut empty_files_created: u32 = 0;
+        for file in &plan.empty_files {
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent).await?;
             }
+            // Create (or truncate) the file
+            fs::write(file, b"")
+                .await
+                .with_context(|| format!("Failed to create empty file {}", file.display()))?;
+            empty_files_created += 1;
         }
 
+        let download_files = plan.downloads;
         if folders_created > 0 {
             trace.info(&format!("{folders_created} folders created."));
         }
@@ -310,6 +315,76 @@ impl FileContainerServer {
         }
     }
 
+    /// Decide where each container item goes under `destination`, and which
+    /// files are skipped by the file pattern.
+    fn plan_download(
+        &self,
+        trace: &dyn TraceWriter,
+        items: &[FileContainerItem],
+        destination: &str,
+    ) -> Result<DownloadPlan> {
+        let mut plan = DownloadPlan::default();
+
+        for item in items {
+            // Verify the item path starts with the container path
+            if !item
+                .path
+                .to_lowercase()
+                .starts_with(&self.container_path.to_lowercase())
+            {
+                anyhow::bail!(
+                    "Item {} is not under #/{}/{}",
+                    item.path,
+                    self.container_id,
+                    self.container_path,
+                );
+            }
+
+            let local_relative_path =
+                item.path[self.container_path.len()..].trim_start_matches('/');
+            let local_path = Path::new(destination).join(local_relative_path);
+
+            match item.item_type {
+                // With a pattern, folders are created only as the parents of
+                // matching files.
+                ContainerItemType::Folder if self.file_pattern.is_some() => {}
+                ContainerItemType::Folder => {
+                    trace.verbose(&format!("Ensure folder exists: {}", local_path.display()));
+                    plan.folders.push(local_path);
+                }
+                ContainerItemType::File => {
+                    if let Some(ref pattern) = self.file_pattern {
+                        if !matches_file_pattern(pattern, local_relative_path) {
+                            trace.verbose(&format!(
+                                "Skip {}, it does not match the pattern",
+                                item.path
+                            ));
+                            plan.skipped += 1;
+                            continue;
+                        }
+                    }
+
+                    if item.file_length == 0 {
+                        trace.verbose(&format!("Create empty file at: {}", local_path.display()));
+                        plan.empty_files.push(local_path);
+                    } else {
+                        trace.verbose(&format!(
+                            "Prepare download {} to {}",
+                            item.path,
+                            local_path.display()
+                        ));
+                        plan.downloads.push(DownloadInfo {
+                            item_path: item.path.clone(),
+                            local_path,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
     // -----------------------------------------------------------------------
     // Public API – Upload
     // -----------------------------------------------------------------------
@@ -426,7 +501,7 @@ impl FileContainerServer {
         let response = self
             .client
             .get(&url)
-            .bearer_auth(&self.auth_token)
+            .header(AUTHORIZATION, &self.auth_header)
             .send()
             .await
             .context("Failed to query container items")?;
@@ -473,7 +548,7 @@ impl FileContainerServer {
         for file_info in files.iter() {
             let sem = semaphore.clone();
             let client = self.client.clone();
-            let auth = self.auth_token.clone();
+            let auth = self.auth_header.clone();
             let url = self.download_file_url(&file_info.item_path);
             let item_path = file_info.item_path.clone();
             let local_path = file_info.local_path.clone();
@@ -527,7 +602,7 @@ impl FileContainerServer {
         for file_path in files {
             let sem = semaphore.clone();
             let client = self.client.clone();
-            let auth = self.auth_token.clone();
+            let auth = self.auth_header.clone();
 
             // Build the container item path:
             //   container_path.trim_end('/') + "/" + relative_path_from_source
@@ -605,7 +680,7 @@ async fn attempt_download(client: &Client, auth: &str, url: &str, local_path: &P
 
     let response = client
         .get(url)
-        .bearer_auth(auth)
+        .header(AUTHORIZATION, auth)
         .send()
         .await
         .context("Failed to send download request")?;
@@ -664,7 +739,7 @@ async fn attempt_upload(client: &Client, auth: &str, url: &str, file_path: &Path
 
     let response = client
         .put(url)
-        .bearer_auth(auth)
+        .header(AUTHORIZATION, auth)
         .header("Content-Type", "application/octet-stream")
         .header("Content-Length", file_size.to_string())
         .header(
@@ -809,6 +884,91 @@ mod tests {
         assert_eq!(item.item_type, ContainerItemType::Folder);
     }
 
+    struct NullTrace;
+
+    impl TraceWriter for NullTrace {
+        fn info(&self, _message: &str) {}
+        fn verbose(&self, _message: &str) {}
+    }
+
+    fn item(path: &str, item_type: ContainerItemType, file_length: i64) -> FileContainerItem {
+        FileContainerItem {
+            path: path.to_string(),
+            item_type,
+            file_length,
+        }
+    }
+
+    fn container_items() -> Vec<FileContainerItem> {
+        vec![
+            item("drop/logs", ContainerItemType::Folder, 0),
+            item("drop/logs/build.log", ContainerItemType::File, 10),
+            item("drop/logs/empty.log", ContainerItemType::File, 0),
+            item("drop/logs/nested/test.log", ContainerItemType::File, 5),
+            item("drop/readme.md", ContainerItemType::File, 3),
+        ]
+    }
+
+    fn server() -> FileContainerServer {
+        FileContainerServer::new(
+            Client::new(),
+            "https://example.com",
+            "tok",
+            Uuid::nil(),
+            1,
+            "drop",
+        )
+    }
+
+    #[test]
+    fn plan_without_pattern_queues_everything() {
+        let plan = server()
+            .plan_download(&NullTrace, &container_items(), "/out")
+            .unwrap();
+        assert_eq!(plan.folders, vec![PathBuf::from("/out/logs")]);
+        assert_eq!(plan.empty_files, vec![PathBuf::from("/out/logs/empty.log")]);
+        assert_eq!(plan.downloads.len(), 3);
+        assert_eq!(plan.skipped, 0);
+    }
+
+    #[test]
+    fn plan_with_pattern_skips_non_matching_files() {
+        let server = server().with_file_pattern(glob::Pattern::new("logs/*.log").unwrap());
+        let plan = server
+            .plan_download(&NullTrace, &container_items(), "/out")
+            .unwrap();
+
+        let queued: Vec<&str> = plan
+            .downloads
+            .iter()
+            .map(|d| d.item_path.as_str())
+            .collect();
+        assert_eq!(queued, vec!["drop/logs/build.log"]);
+        assert_eq!(
+            plan.downloads[0].local_path,
+            PathBuf::from("/out/logs/build.log")
+        );
+        assert_eq!(plan.empty_files, vec![PathBuf::from("/out/logs/empty.log")]);
+        assert!(plan.folders.is_empty());
+        // `*` does not cross directories
+        assert_eq!(plan.skipped, 2);
+
+        let server = self::server().with_file_pattern(glob::Pattern::new("**/*.log").unwrap());
+        let plan = server
+            .plan_download(&NullTrace, &container_items(), "/out")
+            .unwrap();
+        let queued: Vec<&str> = plan
+            .downloads
+            .iter()
+            .map(|d| d.item_path.as_str())
+            .collect();
+        assert_eq!(
+            queued,
+            vec!["drop/logs/build.log", "drop/logs/nested/test.log"]
+        );
+        assert_eq!(plan.skipped, 1);
+    }
+
     #[tokio::test]
     async fn collect_files_recursive_works() {
         let tmp = tempfile::tempdir().unwrap();
@@ -2,7 +2,27 @@
 //
 // Maps the C# `Runner.Plugins.Artifact` namespace.
 
+pub mod artifact_name;
 pub mod download_artifact;
 pub mod file_container_server;
 pub mod pipelines_server;
 pub mod publish_artifact;
+pub mod results_server;
+
+use crate::PluginFactory;
+use download_artifact::DownloadArtifactPlugin;
+use publish_artifact::PublishArtifactPlugin;
+
+/// The artifact plugins, keyed by the type names the worker passes.
+pub const PLUGINS: &[(&str, PluginFactory)] = &[
+    // Full C# type names
+    ("GitHub.Runner.Plugins.Artifact.PublishArtifact", || {
+        Box::new(PublishArtifactPlugin)
+    }),
+    ("GitHub.Runner.Plugins.Artifact.DownloadArtifact", || {
+        Box::new(DownloadArtifactPlugin)
+    }),
+    // Short names for convenience
+    ("PublishArtifact", || Box::new(PublishArtifactPlugin)),
+    ("DownloadArtifact", || Box::new(DownloadArtifactPlugin)),
+];
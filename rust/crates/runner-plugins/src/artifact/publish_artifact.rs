@@ -5,12 +5,14 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use runner_sdk::{ActionPlugin, ActionPluginContext, TraceWriter, VssUtil};
+use runner_sdk::{ActionPlugin, ActionPluginContext, PathUtil, TraceWriter, VssUtil};
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
+use crate::artifact::artifact_name::validate_artifact_name;
 use crate::artifact::file_container_server::FileContainerServer;
 use crate::artifact::pipelines_server::PipelinesServer;
+use crate::artifact::results_server::{self, ResultsServer};
 
 /// Input names for the publish-artifact action.
 mod input_names {
@@ -28,10 +30,6 @@ mod variables {
     pub const CONTAINER_ID: &str = "build.containerId";
 }
 
-/// Characters that are invalid in an artifact name (mirroring Path.GetInvalidFileNameChars()
-/// on .NET but restricted to the set the server actually rejects).
-const INVALID_ARTIFACT_NAME_CHARS: &[char] = &['\\', '/', '"', ':', '<', '>', '|', '*', '?'];
-
 /// Plugin that uploads build artifacts.
 ///
 /// Maps `PublishArtifact` (C# `IRunnerActionPlugin`).
@@ -56,16 +54,7 @@ impl ActionPlugin for PublishArtifactPlugin {
                 .unwrap_or_default(),
         };
 
-        if artifact_name.trim().is_empty() {
-            anyhow::bail!("Artifact name can not be empty string");
-        }
-
-        if artifact_name.contains(INVALID_ARTIFACT_NAME_CHARS) {
-            anyhow::bail!(
-                "Artifact name is not valid: {artifact_name}. \
-                 It cannot contain '\\', '/', '\"', ':', '<', '>', '|', '*', and '?'"
-            );
-        }
+        validate_artifact_name(&artifact_name)?;
 
         let target_path_raw = context
             .get_input(input_names::PATH, true)?
@@ -74,10 +63,14 @@ impl ActionPlugin for PublishArtifactPlugin {
             .get_github_context("workspace")
             .unwrap_or_else(|| ".".to_string());
 
+        // Relative paths must stay inside the workspace.
         let target_path: PathBuf = if Path::new(&target_path_raw).is_absolute() {
             PathBuf::from(&target_path_raw)
         } else {
-            Path::new(&default_working_directory).join(&target_path_raw)
+            PathUtil::resolve_within(
+                Path::new(&default_working_directory),
+                Path::new(&target_path_raw),
+            )?
         };
 
         let full_path = std::fs::canonicalize(&target_path).with_context(|| {
@@ -90,6 +83,11 @@ impl ActionPlugin for PublishArtifactPlugin {
             anyhow::bail!("Path does not exist {}", target_path.display());
         }
 
+        // Newer runs store artifacts through the v4 (Results Service) API.
+        if let Some(results_url) = results_server::v4_service_url(context) {
+            return publish_v4(context, &results_url, &artifact_name, &full_path, trace).await;
+        }
+
         // -----------------------------------------------------------
         // 2. Read build / container IDs from variables
         // -----------------------------------------------------------
@@ -176,6 +174,46 @@ impl ActionPlugin for PublishArtifactPlugin {
     }
 }
 
+/// Upload the artifact as a zip through the v4 artifact API.
+async fn publish_v4(
+    context: &ActionPluginContext,
+    results_url: &str,
+    artifact_name: &str,
+    full_path: &Path,
+    trace: &dyn TraceWriter,
+) -> Result<()> {
+    let (_, auth_token) = resolve_connection(context)?;
+    let http_client = VssUtil::create_http_client(&runner_sdk::RunnerWebProxy::new());
+    let results = ResultsServer::new(http_client, results_url, &auth_token)?;
+
+    let zip = tempfile::NamedTempFile::new().context("Failed to create the artifact zip")?;
+    let (size, hash) = results_server::pack_artifact(full_path, zip.path())?;
+
+    let upload_url = results
+        .create_artifact(artifact_name)
+        .await
+        .context("Failed to create artifact")?;
+    results
+        .upload_blob(&upload_url, zip.path())
+        .await
+        .context("Failed to upload artifact files")?;
+
+    trace.info(&format!(
+        "Uploaded '{size}' bytes from '{}' to server",
+        full_path.display(),
+    ));
+
+    let artifact_id = results
+        .finalize_artifact(artifact_name, size, &hash)
+        .await
+        .context("Failed to finalize artifact")?;
+
+    trace.info(&format!(
+        "Finalized artifact {artifact_name} ({artifact_id}) with SHA-256 {hash}"
+    ));
+    Ok(())
+}
+
 /// Resolve the `SystemVssConnection` endpoint from the plugin context.
 ///
 /// Returns `(base_url, access_token)`.
@@ -239,6 +277,7 @@ mod tests {
                     p
                 },
             }),
+            data: HashMap::new(),
         });
 
         ctx
@@ -260,18 +299,49 @@ mod tests {
 
     #[test]
     fn invalid_artifact_name_chars() {
+        use crate::artifact::artifact_name::INVALID_ARTIFACT_NAME_CHARS;
+
         for ch in INVALID_ARTIFACT_NAME_CHARS {
             let name = format!("test{ch}artifact");
             assert!(
-                name.contains(INVALID_ARTIFACT_NAME_CHARS),
-                "Expected '{name}' to contain invalid char '{ch}'"
+                validate_artifact_name(&name).is_err(),
+                "Expected '{name}' to be rejected for '{ch}'"
             );
         }
     }
 
     #[test]
     fn empty_artifact_name_is_invalid() {
-        let name = "   ";
-        assert!(name.trim().is_empty());
+        assert!(validate_artifact_name("   ").is_err());
+    }
+
+    #[tokio::test]
+    async fn invalid_name_fails_before_upload() {
+        let mut ctx = make_context();
+        ctx.inputs
+            .insert("name".to_string(), "bad:name".to_string());
+        let err = PublishArtifactPlugin
+            .run(&mut ctx, &runner_sdk::trace::NullTraceWriter)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("contains ':'"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn relative_path_escaping_workspace_is_rejected() {
+        let workspace = tempfile::tempdir().unwrap();
+        let mut ctx = make_context();
+        ctx.inputs
+            .insert("path".to_string(), "../../etc".to_string());
+        ctx.context.insert(
+            "github".to_string(),
+            serde_json::json!({ "workspace": workspace.path().to_string_lossy() }),
+        );
+
+        let err = PublishArtifactPlugin
+            .run(&mut ctx, &runner_sdk::trace::NullTraceWriter)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("resolves outside of"), "{err}");
     }
 }
@@ -0,0 +1,654 @@
+// ResultsServer – a client for the Actions Artifacts v4 API.
+//
+// v4 artifacts live in the Results Service rather than a file container.
+// The service speaks Twirp (JSON RPCs under `/twirp/<service>/<method>`)
+// and hands out signed URLs into a content-addressed blob store:
+//
+//   upload:   CreateArtifact -> PUT zip to the signed URL -> FinalizeArtifact
+//             (with the size and SHA-256 of the zip)
+//   download: ListArtifacts -> GetSignedArtifactURL -> GET zip from the URL
+//
+// Every call names the run and job by their backend IDs, which are read from
+// the `Actions.Results:<run>:<job>` scope of the access token. The service is
+// used when the server sets the `actions_uses_artifact_v4` flag and the
+// `SystemVssConnection` endpoint carries a `ResultsServiceUrl`; otherwise the
+// plugins fall back to the file container (v1) API.
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use runner_sdk::{ActionPluginContext, StringUtil, VssUtil};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+use crate::artifact::file_container_server::matches_file_pattern;
+
+/// Variable the server sets when the job should use v4 artifacts.
+pub const ARTIFACTS_V4_FLAG: &str = "actions_uses_artifact_v4";
+
+/// Endpoint data key holding the Results Service URL.
+const RESULTS_SERVICE_URL: &str = "ResultsServiceUrl";
+
+/// The Twirp service that manages artifacts.
+const ARTIFACT_SERVICE: &str = "github.actions.results.api.v1.ArtifactService";
+
+/// Artifact version recorded by `CreateArtifact`.
+const ARTIFACT_VERSION: i32 = 4;
+
+/// The Results Service URL, when the server has enabled v4 artifacts for
+/// this job.
+pub fn v4_service_url(context: &ActionPluginContext) -> Option<String> {
+    let enabled = context
+        .get_variable(ARTIFACTS_V4_FLAG)
+        .and_then(|value| StringUtil::convert_to_bool(value))
+        .unwrap_or(false);
+    if !enabled {
+        return None;
+    }
+
+    context
+        .endpoints
+        .iter()
+        .find(|e| e.name.eq_ignore_ascii_case("SystemVssConnection"))
+        .and_then(|e| e.data.get(RESULTS_SERVICE_URL))
+        .filter(|url| !url.is_empty())
+        .cloned()
+}
+
+/// An artifact as listed by the Results Service.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResultsArtifact {
+    pub name: String,
+    /// The artifact ID, sent as a string because it is an int64.
+    #[serde(rename = "database_id", default)]
+    pub id: String,
+    /// Size of the zip in bytes, also an int64 string.
+    #[serde(default)]
+    pub size: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateArtifactRequest<'a> {
+    workflow_run_backend_id: &'a str,
+    workflow_job_run_backend_id: &'a str,
+    name: &'a str,
+    version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateArtifactResponse {
+    #[serde(default)]
+    ok: bool,
+    #[serde(default)]
+    signed_upload_url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct FinalizeArtifactRequest<'a> {
+    workflow_run_backend_id: &'a str,
+    workflow_job_run_backend_id: &'a str,
+    name: &'a str,
+    size: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FinalizeArtifactResponse {
+    #[serde(default)]
+    ok: bool,
+    #[serde(default)]
+    artifact_id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ListArtifactsRequest<'a> {
+    workflow_run_backend_id: &'a str,
+    workflow_job_run_backend_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name_filter: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListArtifactsResponse {
+    #[serde(default)]
+    artifacts: Vec<ResultsArtifact>,
+}
+
+#[derive(Debug, Serialize)]
+struct GetSignedArtifactUrlRequest<'a> {
+    workflow_run_backend_id: &'a str,
+    workflow_job_run_backend_id: &'a str,
+    name: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetSignedArtifactUrlResponse {
+    #[serde(default)]
+    signed_url: String,
+}
+
+/// A wrapper around the Results Service artifact RPCs and its blob store.
+#[derive(Debug)]
+pub struct ResultsServer {
+    client: Client,
+    base_url: String,
+    auth_header: String,
+    run_backend_id: String,
+    job_backend_id: String,
+}
+
+impl ResultsServer {
+    /// Create a new `ResultsServer`.
+    ///
+    /// * `client`     – a pre-configured `reqwest::Client`
+    /// * `base_url`   – the Results Service URL from the `ResultsServiceUrl` endpoint data
+    /// * `auth_token` – the access token; it must carry an `Actions.Results` scope
+    pub fn new(client: Client, base_url: &str, auth_token: &str) -> Result<Self> {
+        let (run_backend_id, job_backend_id) = backend_ids(auth_token)?;
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            auth_header: VssUtil::bearer_header(auth_token),
+            run_backend_id,
+            job_backend_id,
+        })
+    }
+
+    // -----------------------------------------------------------------------
+    // Public API
+    // -----------------------------------------------------------------------
+
+    /// Create a v4 artifact and return the signed URL to upload its zip to.
+    pub async fn create_artifact(&self, name: &str) -> Result<String> {
+        let request = CreateArtifactRequest {
+            workflow_run_backend_id: &self.run_backend_id,
+            workflow_job_run_backend_id: &self.job_backend_id,
+            name,
+            version: ARTIFACT_VERSION,
+        };
+        let response: CreateArtifactResponse = self.call("CreateArtifact", &request).await?;
+        if !response.ok || response.signed_upload_url.is_empty() {
+            anyhow::bail!("The Results Service did not accept artifact '{name}'");
+        }
+        Ok(response.signed_upload_url)
+    }
+
+    /// Finalize an uploaded artifact, returning its ID.
+    ///
+    /// `hash` is the hex SHA-256 of the uploaded zip.
+    pub async fn finalize_artifact(&self, name: &str, size: u64, hash: &str) -> Result<String> {
+        let request = FinalizeArtifactRequest {
+            workflow_run_backend_id: &self.run_backend_id,
+            workflow_job_run_backend_id: &self.job_backend_id,
+            name,
+            size: size.to_string(),
+            hash: Some(format!("sha256:{hash}")),
+        };
+        let response: FinalizeArtifactResponse = self.call("FinalizeArtifact", &request).await?;
+        if !response.ok {
+            anyhow::bail!("The Results Service did not finalize artifact '{name}'");
+        }
+        Ok(response.artifact_id)
+    }
+
+    /// Get a named artifact of the run.
+    ///
+    /// Returns `None` if the run has no artifact with that name.
+    pub async fn get_artifact(&self, name: &str) -> Result<Option<ResultsArtifact>> {
+        let request = ListArtifactsRequest {
+            workflow_run_backend_id: &self.run_backend_id,
+            workflow_job_run_backend_id: &self.job_backend_id,
+            name_filter: Some(name.to_string()),
+        };
+        let response: ListArtifactsResponse = self.call("ListArtifacts", &request).await?;
+        Ok(response.artifacts.into_iter().find(|a| a.name == name))
+    }
+
+    /// Get a signed URL to download the zip of a named artifact.
+    pub async fn get_signed_artifact_url(&self, name: &str) -> Result<String> {
+        let request = GetSignedArtifactUrlRequest {
+            workflow_run_backend_id: &self.run_backend_id,
+            workflow_job_run_backend_id: &self.job_backend_id,
+            name,
+        };
+        let response: GetSignedArtifactUrlResponse =
+            self.call("GetSignedArtifactURL", &request).await?;
+        if response.signed_url.is_empty() {
+            anyhow::bail!("The Results Service returned no download URL for artifact '{name}'");
+        }
+        Ok(response.signed_url)
+    }
+
+    /// Upload the zip at `zip_path` to the blob store at `signed_url`,
+    /// streaming it from disk.
+    pub async fn upload_blob(&self, signed_url: &str, zip_path: &Path) -> Result<()> {
+        let file = tokio::fs::File::open(zip_path)
+            .await
+            .with_context(|| format!("Failed to open {}", zip_path.display()))?;
+        let size = file.metadata().await?.len();
+        let response = self
+            .client
+            .put(signed_url)
+            .header("x-ms-blob-type", "BlockBlob")
+            .header("Content-Type", "application/zip")
+            .header("Content-Length", size)
+            .body(reqwest::Body::from(file))
+            .send()
+            .await
+            .context("Failed to upload artifact blob")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Failed to upload artifact blob (HTTP {status}): {text}");
+        }
+        Ok(())
+    }
+
+    /// Download a zip from the blob store at `signed_url` to `destination`,
+    /// streaming it to disk. Returns the number of bytes written.
+    pub async fn download_blob(&self, signed_url: &str, destination: &Path) -> Result<u64> {
+        let mut response = self
+            .client
+            .get(signed_url)
+            .send()
+            .await
+            .context("Failed to download artifact blob")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Failed to download artifact blob (HTTP {status}): {text}");
+        }
+
+        let mut file = tokio::fs::File::create(destination)
+            .await
+            .with_context(|| format!("Failed to create {}", destination.display()))?;
+        let mut size = 0;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .context("Failed to read artifact blob")?
+        {
+            file.write_all(&chunk).await?;
+            size += chunk.len() as u64;
+        }
+        file.flush().await?;
+        Ok(size)
+    }
+
+    // -----------------------------------------------------------------------
+    // Internal: Twirp calls
+    // -----------------------------------------------------------------------
+
+    fn method_url(&self, method: &str) -> String {
+        format!("{}/twirp/{ARTIFACT_SERVICE}/{method}", self.base_url)
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, request: &impl Serialize) -> Result<T> {
+        let response = self
+            .client
+            .post(self.method_url(method))
+            .header(AUTHORIZATION, &self.auth_header)
+            .json(request)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send {method} request"))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("{method} failed (HTTP {status}): {text}");
+        }
+
+        response
+            .json()
+            .await
+            .with_context(|| format!("Failed to deserialize {method} response"))
+    }
+}
+
+/// The run and job backend IDs from the `Actions.Results:<run>:<job>` scope
+/// of a JWT access token.
+fn backend_ids(token: &str) -> Result<(String, String)> {
+    let claims: serde_json::Value = token
+        .split('.')
+        .nth(1)
+        .and_then(|payload| URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok())
+        .and_then(|payload| serde_json::from_slice(&payload).ok())
+        .context("The access token is not a JWT")?;
+
+    claims
+        .get("scp")
+        .and_then(|scp| scp.as_str())
+        .unwrap_or_default()
+        .split(' ')
+        .find_map(|scope| {
+            let mut parts = scope.split(':');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("Actions.Results"), Some(run), Some(job)) => {
+                    Some((run.to_string(), job.to_string()))
+                }
+                _ => None,
+            }
+        })
+        .context("The access token has no Actions.Results scope")
+}
+
+/// Zip a file or directory for upload into `zip_path`, returning the size
+/// and hex SHA-256 of the zip. A directory's contents are stored relative to
+/// the directory. Files are streamed, so large artifacts are never held in
+/// memory.
+pub fn pack_artifact(path: &Path, zip_path: &Path) -> Result<(u64, String)> {
+    let zip_file = File::create(zip_path)
+        .with_context(|| format!("Failed to create {}", zip_path.display()))?;
+    let mut writer = zip::ZipWriter::new(BufWriter::new(zip_file));
+    let options = zip::write::SimpleFileOptions::default();
+
+    let mut add_file = |file: &Path, name: &str| -> Result<()> {
+        writer
+            .start_file(name, options)
+            .with_context(|| format!("Failed to add {} to the artifact", file.display()))?;
+        let mut contents =
+            File::open(file).with_context(|| format!("Failed to read {}", file.display()))?;
+        std::io::copy(&mut contents, &mut writer)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        Ok(())
+    };
+
+    if path.is_file() {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        add_file(path, &name)?;
+    } else {
+        let mut pending = vec![path.to_path_buf()];
+        let mut files = Vec::new();
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir)
+                .with_context(|| format!("Failed to read {}", dir.display()))?
+            {
+                let entry_path = entry?.path();
+                if entry_path.is_dir() {
+                    pending.push(entry_path);
+                } else {
+                    files.push(entry_path);
+                }
+            }
+        }
+        files.sort();
+        for file in files {
+            let relative = file.strip_prefix(path).unwrap_or(&file);
+            let name = relative.to_string_lossy().replace('\\', "/");
+            add_file(&file, &name)?;
+        }
+    }
+
+    writer
+        .finish()
+        .context("Failed to write the artifact zip")?
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .context("Failed to write the artifact zip")?;
+
+    let mut hasher = Sha256::new();
+    let size = std::io::copy(&mut File::open(zip_path)?, &mut hasher)
+        .context("Failed to hash the artifact zip")?;
+    Ok((size, hex::encode(hasher.finalize())))
+}
+
+/// Extract the downloaded artifact zip at `zip_path` into `destination`,
+/// keeping only the files that match `pattern` when one is given. Returns
+/// the number of files written.
+pub fn unpack_artifact(
+    zip_path: &Path,
+    destination: &Path,
+    pattern: Option<&glob::Pattern>,
+) -> Result<usize> {
+    let zip_file =
+        File::open(zip_path).with_context(|| format!("Failed to open {}", zip_path.display()))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(zip_file))
+        .context("The artifact is not a valid zip")?;
+    std::fs::create_dir_all(destination)
+        .with_context(|| format!("Failed to create {}", destination.display()))?;
+
+    let mut count = 0;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        // Entries that would land outside the destination are rejected
+        let relative = entry.enclosed_name().with_context(|| {
+            format!("Artifact entry '{}' escapes the target path", entry.name())
+        })?;
+        let target = destination.join(&relative);
+
+        if entry.is_dir() {
+            if pattern.is_none() {
+                std::fs::create_dir_all(&target)?;
+            }
+            continue;
+        }
+        if let Some(pattern) = pattern {
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if !matches_file_pattern(pattern, &relative) {
+                continue;
+            }
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = File::create(&target)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        std::io::copy(&mut entry, &mut file)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runner_sdk::action_plugin::{EndpointAuthorization, ServiceEndpoint};
+    use runner_sdk::test_util::{MockHttpServer, MockResponse};
+    use std::collections::HashMap;
+
+    fn token() -> String {
+        let claims = serde_json::json!({
+            "scp": "Actions.GenericRead:00000000 Actions.Results:run-1:job-2",
+        });
+        format!(
+            "eyJhbGciOiJub25lIn0.{}.sig",
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    /// Answer one request with each JSON body, in order.
+    async fn mock_server(responses: Vec<&str>) -> MockHttpServer {
+        let responses = responses
+            .into_iter()
+            .map(|body| MockResponse::json("200 OK", body));
+        MockHttpServer::start(responses.collect()).await
+    }
+
+    #[test]
+    fn backend_ids_come_from_token_scope() {
+        assert_eq!(
+            backend_ids(&token()).unwrap(),
+            ("run-1".to_string(), "job-2".to_string())
+        );
+        assert!(backend_ids("not-a-jwt").is_err());
+    }
+
+    #[test]
+    fn v4_requires_flag_and_results_url() {
+        let mut ctx = ActionPluginContext::new();
+        ctx.endpoints.push(ServiceEndpoint {
+            name: "SystemVssConnection".to_string(),
+            url: "https://pipelines.example.com".to_string(),
+            authorization: Some(EndpointAuthorization {
+                scheme: "OAuth".to_string(),
+                parameters: HashMap::new(),
+            }),
+            data: HashMap::from([(
+                "ResultsServiceUrl".to_string(),
+                "https://results.example.com/".to_string(),
+            )]),
+        });
+        assert_eq!(v4_service_url(&ctx), None);
+
+        ctx.variables
+            .insert(ARTIFACTS_V4_FLAG.to_string(), "true".to_string());
+        assert_eq!(
+            v4_service_url(&ctx).as_deref(),
+            Some("https://results.example.com/")
+        );
+
+        ctx.endpoints[0].data.clear();
+        assert_eq!(v4_service_url(&ctx), None);
+    }
+
+    #[tokio::test]
+    async fn upload_request_and_response_shapes() {
+        let mock = mock_server(vec![
+            r#"{"ok":true,"signed_upload_url":"https://blob.example.com/upload?sig=1"}"#,
+            "",
+            r#"{"ok":true,"artifact_id":"1234"}"#,
+        ])
+        .await;
+        let url = mock.url().to_string();
+        let server = ResultsServer::new(Client::new(), &format!("{url}/"), &token()).unwrap();
+        let zip = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(zip.path(), "zip").unwrap();
+
+        let signed_url = server.create_artifact("logs").await.unwrap();
+        assert_eq!(signed_url, "https://blob.example.com/upload?sig=1");
+        server
+            .upload_blob(&format!("{url}/blob?sig=1"), zip.path())
+            .await
+            .unwrap();
+        let id = server.finalize_artifact("logs", 3, "abc").await.unwrap();
+        assert_eq!(id, "1234");
+
+        let requests = mock.finish().await;
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(
+            requests[0].target,
+            "/twirp/github.actions.results.api.v1.ArtifactService/CreateArtifact"
+        );
+        assert_eq!(
+            requests[0].body_json(),
+            serde_json::json!({
+                "workflow_run_backend_id": "run-1",
+                "workflow_job_run_backend_id": "job-2",
+                "name": "logs",
+                "version": 4,
+            })
+        );
+        assert_eq!(requests[1].method, "PUT");
+        assert_eq!(requests[1].target, "/blob?sig=1");
+        assert_eq!(requests[1].body_text(), "zip");
+        assert_eq!(
+            requests[2].body_json(),
+            serde_json::json!({
+                "workflow_run_backend_id": "run-1",
+                "workflow_job_run_backend_id": "job-2",
+                "name": "logs",
+                "size": "3",
+                "hash": "sha256:abc",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn download_request_and_response_shapes() {
+        let mock = mock_server(vec![
+            r#"{"artifacts":[{"workflow_run_backend_id":"run-1","workflow_job_run_backend_id":"job-2","database_id":"99","name":"logs","size":"3"}]}"#,
+            r#"{"signed_url":"https://blob.example.com/logs.zip?sig=2"}"#,
+            r#"{"artifacts":[]}"#,
+        ])
+        .await;
+        let server = ResultsServer::new(Client::new(), mock.url(), &token()).unwrap();
+
+        let artifact = server.get_artifact("logs").await.unwrap().unwrap();
+        assert_eq!(artifact.id, "99");
+        assert_eq!(artifact.size, "3");
+        assert_eq!(
+            server.get_signed_artifact_url("logs").await.unwrap(),
+            "https://blob.example.com/logs.zip?sig=2"
+        );
+        assert!(server.get_artifact("missing").await.unwrap().is_none());
+
+        let requests = mock.finish().await;
+        assert!(
+            requests[0].target.ends_with("/ListArtifacts"),
+            "{}",
+            requests[0].target
+        );
+        assert_eq!(
+            requests[0].body_json(),
+            serde_json::json!({
+                "workflow_run_backend_id": "run-1",
+                "workflow_job_run_backend_id": "job-2",
+                "name_filter": "logs",
+            })
+        );
+        assert!(
+            requests[1].target.ends_with("/GetSignedArtifactURL"),
+            "{}",
+            requests[1].target
+        );
+        assert_eq!(requests[1].body_json()["name"], "logs");
+    }
+
+    #[tokio::test]
+    async fn blob_download_is_written_to_disk() {
+        let mock = mock_server(vec!["zip-bytes"]).await;
+        let url = mock.url().to_string();
+        let server = ResultsServer::new(Client::new(), &url, &token()).unwrap();
+        let zip = tempfile::NamedTempFile::new().unwrap();
+
+        let size = server
+            .download_blob(&format!("{url}/logs.zip?sig=2"), zip.path())
+            .await
+            .unwrap();
+        assert_eq!(size, 9);
+        assert_eq!(std::fs::read_to_string(zip.path()).unwrap(), "zip-bytes");
+        mock.finish().await;
+    }
+
+    #[test]
+    fn pack_and_unpack_round_trip() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(source.path().join("nested")).unwrap();
+        std::fs::write(source.path().join("a.txt"), "alpha").unwrap();
+        std::fs::write(source.path().join("nested/b.txt"), "beta").unwrap();
+
+        let zip = tempfile::NamedTempFile::new().unwrap();
+        let (size, hash) = pack_artifact(source.path(), zip.path()).unwrap();
+        let data = std::fs::read(zip.path()).unwrap();
+        assert_eq!(size, data.len() as u64);
+        assert_eq!(hash, hex::encode(Sha256::digest(&data)));
+
+        let target = tempfile::tempdir().unwrap();
+        assert_eq!(unpack_artifact(zip.path(), target.path(), None).unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(target.path().join("nested/b.txt")).unwrap(),
+            "beta"
+        );
+
+        let filtered = tempfile::tempdir().unwrap();
+        let pattern = glob::Pattern::new("nested/*").unwrap();
+        assert_eq!(
+            unpack_artifact(zip.path(), filtered.path(), Some(&pattern)).unwrap(),
+            1
+        );
+        assert!(filtered.path().join("nested/b.txt").exists());
+        assert!(!filtered.path().join("a.txt").exists());
+    }
+}
@@ -1,6 +1,6 @@
 // runner-plugins: Plugin implementations for the GitHub Actions Runner.
 // This crate maps the C# `Runner.Plugins` project and provides artifact
-// upload/download plugins as well as a (minimal) repository module.
+// upload/download plugins and the repository checkout plugin.
 
 pub mod artifact;
 pub mod repository;
@@ -10,3 +10,32 @@ pub use artifact::download_artifact::DownloadArtifactPlugin;
 pub use artifact::file_container_server::FileContainerServer;
 pub use artifact::pipelines_server::PipelinesServer;
 pub use artifact::publish_artifact::PublishArtifactPlugin;
+pub use repository::checkout::CheckoutPlugin;
+
+use runner_sdk::ActionPlugin;
+
+/// Creates a fresh instance of a plugin.
+pub type PluginFactory = fn() -> Box<dyn ActionPlugin>;
+
+/// The plugin table of every module, in lookup order.
+const PLUGIN_TABLES: &[&[(&str, PluginFactory)]] = &[artifact::PLUGINS, repository::PLUGINS];
+
+/// Every registered plugin name with its factory.
+pub fn registered_plugins() -> impl Iterator<Item = &'static (&'static str, PluginFactory)> {
+    PLUGIN_TABLES.iter().flat_map(|table| table.iter())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_registry_has_no_duplicate_names() {
+        let mut seen = HashSet::new();
+        for (name, _) in registered_plugins() {
+            assert!(seen.insert(*name), "duplicate plugin name {name}");
+        }
+        assert!(seen.contains("GitHub.Runner.Plugins.Repository.v1_1.CheckoutTask"));
+    }
+}
@@ -0,0 +1,652 @@
+// Checkout – fetches a repository into the workspace with the git CLI.
+//
+// Maps `CheckoutTask` from `Runner.Plugins.Repository.v1_1`. Only the plain
+// fetch-and-checkout path is implemented: the directory is initialised (or
+// reused), the requested ref is fetched from `origin`, and `FETCH_HEAD` is
+// checked out, followed by the submodules when asked for. The token is handed
+// to git through `GIT_CONFIG_*` environment variables rather than the command
+// line, and is masked in git's output.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use runner_sdk::{
+    ActionPlugin, ActionPluginContext, PathUtil, ProcessInvoker, TraceWriter, WhichUtil,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+/// Input names for the checkout action.
+mod input_names {
+    /// `owner/name` of the repository; defaults to `github.repository`.
+    pub const REPOSITORY: &str = "repository";
+    /// Branch, tag or SHA to check out; defaults to the triggering commit.
+    pub const REF: &str = "ref";
+    /// Whether to discard local changes in an existing checkout.
+    pub const CLEAN: &str = "clean";
+    /// Number of commits to fetch; `0` fetches the full history.
+    pub const FETCH_DEPTH: &str = "fetch-depth";
+    /// `true` to check out submodules, `recursive` for nested ones too.
+    pub const SUBMODULES: &str = "submodules";
+    /// Checkout directory, relative to the runner workspace.
+    pub const PATH: &str = "path";
+    /// Token used to authenticate the fetch.
+    pub const TOKEN: &str = "token";
+}
+
+const DEFAULT_SERVER_URL: &str = "https://github.com";
+
+/// Replaces secrets in anything the plugin writes to the log.
+const MASK: &str = "***";
+
+/// Plugin that checks out a repository.
+///
+/// Maps `CheckoutTask` (C# `IRunnerActionPlugin`).
+pub struct CheckoutPlugin;
+
+#[async_trait]
+impl ActionPlugin for CheckoutPlugin {
+    async fn run(&self, context: &mut ActionPluginContext, trace: &dyn TraceWriter) -> Result<()> {
+        let options = CheckoutOptions::from_context(context)?;
+        let git = WhichUtil::which("git", true)?.context("git was not found on the PATH")?;
+        checkout(&options, &ProcessGitInvoker::new(git), trace).await
+    }
+}
+
+/// Runs git commands; a seam so tests can record the invocations.
+#[async_trait]
+trait GitInvoker: Send + Sync {
+    /// Run git with `arguments` and `environment` in `directory`, failing on
+    /// a non-zero exit.
+    async fn invoke(
+        &self,
+        directory: &Path,
+        arguments: &[String],
+        environment: &HashMap<String, String>,
+        secrets: &[String],
+        trace: &dyn TraceWriter,
+    ) -> Result<()>;
+}
+
+/// Check out the repository described by `options` using `git`.
+async fn checkout(
+    options: &CheckoutOptions,
+    git: &dyn GitInvoker,
+    trace: &dyn TraceWriter,
+) -> Result<()> {
+    std::fs::create_dir_all(&options.path)
+        .with_context(|| format!("Failed to create {}", options.path.display()))?;
+    trace.info(&format!(
+        "Checking out '{}' ({}) into '{}'",
+        options.repository_url,
+        options.target(),
+        options.path.display()
+    ));
+
+    let existing = options.path.join(".git").is_dir();
+    let environment = options.git_environment();
+    let secrets = options.secrets();
+    for arguments in options.git_commands(existing) {
+        git.invoke(&options.path, &arguments, &environment, &secrets, trace)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Which submodules to check out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Submodules {
+    None,
+    TopLevel,
+    Recursive,
+}
+
+/// The resolved inputs of one checkout.
+#[derive(Debug)]
+struct CheckoutOptions {
+    repository_url: String,
+    git_ref: String,
+    /// The commit to check out when no ref input was given.
+    sha: Option<String>,
+    path: PathBuf,
+    clean: bool,
+    fetch_depth: u32,
+    submodules: Submodules,
+    /// Origin of the GitHub server, e.g. `https://github.com`; the token is
+    /// only sent to URLs under it.
+    server_origin: String,
+    token: Option<String>,
+}
+
+impl CheckoutOptions {
+    fn from_context(context: &ActionPluginContext) -> Result<Self> {
+        let repository = match context.get_input(input_names::REPOSITORY, false)? {
+            Some(repository) if !repository.is_empty() => repository,
+            _ => context
+                .get_github_context("repository")
+                .context("Input required and not supplied: repository")?,
+        };
+        let server_url = context
+            .get_github_context("server_url")
+            .unwrap_or_else(|| DEFAULT_SERVER_URL.to_string());
+        let repository_url = format!(
+            "{}/{}",
+            server_url.trim_end_matches('/'),
+            repository.trim_matches('/')
+        );
+        let server_origin = url::Url::parse(&server_url)
+            .with_context(|| format!("Invalid github.server_url: {server_url}"))?
+            .origin()
+            .ascii_serialization();
+
+        let (git_ref, sha) = match context.get_input(input_names::REF, false)? {
+            Some(git_ref) if !git_ref.is_empty() => (git_ref, None),
+            _ => (
+                context.get_github_context("ref").unwrap_or_default(),
+                context.get_github_context("sha"),
+            ),
+        };
+        if git_ref.is_empty() && sha.is_none() {
+            anyhow::bail!("Input required and not supplied: ref");
+        }
+
+        let clean = match context.get_input(input_names::CLEAN, false)? {
+            Some(clean) if !clean.is_empty() => runner_sdk::StringUtil::convert_to_bool(&clean)
+                .with_context(|| {
+                    format!("Input '{}' is not a boolean: {clean}", input_names::CLEAN)
+                })?,
+            _ => true,
+        };
+        let fetch_depth = match context.get_input(input_names::FETCH_DEPTH, false)? {
+            Some(depth) if !depth.is_empty() => depth.parse().with_context(|| {
+                format!(
+                    "Input '{}' is not a number: {depth}",
+                    input_names::FETCH_DEPTH
+                )
+            })?,
+            _ => 0,
+        };
+        let submodules = match context.get_input(input_names::SUBMODULES, false)? {
+            Some(submodules) if submodules.eq_ignore_ascii_case("recursive") => {
+                Submodules::Recursive
+            }
+            Some(submodules) if !submodules.is_empty() => {
+                match runner_sdk::StringUtil::convert_to_bool(&submodules) {
+                    Some(true) => Submodules::TopLevel,
+                    Some(false) => Submodules::None,
+                    None => anyhow::bail!(
+                        "Input '{}' must be true, false or recursive: {submodules}",
+                        input_names::SUBMODULES
+                    ),
+                }
+            }
+            _ => Submodules::None,
+        };
+
+        let workspace = context
+            .get_github_context("workspace")
+            .context("github.workspace is not set")?;
+        let path = match context.get_input(input_names::PATH, false)? {
+            Some(path) if !path.is_empty() => {
+                // Like v1 of actions/checkout, paths are relative to the
+                // runner workspace, the parent of the repository directory.
+                let runner_workspace =
+                    context.get_runner_context("workspace").unwrap_or_else(|| {
+                        Path::new(&workspace)
+                            .parent()
+                            .unwrap_or(Path::new(&workspace))
+                            .to_string_lossy()
+                            .to_string()
+                    });
+                PathUtil::resolve_within(Path::new(&runner_workspace), Path::new(&path))?
+            }
+            _ => PathBuf::from(workspace),
+        };
+
+        let token = match context.get_input(input_names::TOKEN, false)? {
+            Some(token) if !token.is_empty() => Some(token),
+            _ => context
+                .get_github_context("token")
+                .filter(|t| !t.is_empty()),
+        };
+
+        Ok(Self {
+            repository_url,
+            git_ref,
+            sha,
+            path,
+            clean,
+            fetch_depth,
+            submodules,
+            server_origin,
+            token,
+        })
+    }
+
+    /// The ref or commit handed to `git fetch`.
+    fn target(&self) -> &str {
+        self.sha.as_deref().unwrap_or(&self.git_ref)
+    }
+
+    /// The environment for every git command: no credential prompts, and
+    /// the token as an `http.<server>/.extraheader` config entry if one was
+    /// given. Scoping the header to the server keeps it from being sent to
+    /// submodules hosted elsewhere.
+    fn git_environment(&self) -> HashMap<String, String> {
+        let mut environment = HashMap::from([("GIT_TERMINAL_PROMPT".to_string(), "0".to_string())]);
+        if let Some(token) = &self.token {
+            let basic = BASE64.encode(format!("x-access-token:{token}"));
+            environment.insert("GIT_CONFIG_COUNT".to_string(), "1".to_string());
+            environment.insert(
+                "GIT_CONFIG_KEY_0".to_string(),
+                format!("http.{}/.extraheader", self.server_origin),
+            );
+            environment.insert(
+                "GIT_CONFIG_VALUE_0".to_string(),
+                format!("AUTHORIZATION: basic {basic}"),
+            );
+        }
+        environment
+    }
+
+    /// The values that must never reach the log: the token and the header
+    /// that carries it.
+    fn secrets(&self) -> Vec<String> {
+        self.token
+            .iter()
+            .flat_map(|token| {
+                [
+                    token.clone(),
+                    BASE64.encode(format!("x-access-token:{token}")),
+                ]
+            })
+            .collect()
+    }
+
+    /// The git argument lists, in order, for a directory that does or does
+    /// not already hold a repository.
+    fn git_commands(&self, existing: bool) -> Vec<Vec<String>> {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let depth = (self.fetch_depth > 0).then(|| format!("--depth={}", self.fetch_depth));
+        let recursive =
+            (self.submodules == Submodules::Recursive).then(|| "--recursive".to_string());
+
+        let mut commands = Vec::new();
+        if existing {
+            if self.clean {
+                commands.push(args(&["clean", "-ffdx"]));
+                commands.push(args(&["reset", "--hard", "HEAD"]));
+            }
+            commands.push(args(&["remote", "set-url", "origin", &self.repository_url]));
+        } else {
+            commands.push(args(&["init"]));
+            commands.push(args(&["remote", "add", "origin", &self.repository_url]));
+        }
+
+        let mut fetch = args(&["fetch", "--no-tags", "--prune", "--progress"]);
+        fetch.extend(depth.clone());
+        fetch.extend(args(&["origin", self.target()]));
+        commands.push(fetch);
+        commands.push(args(&["checkout", "--progress", "--force", "FETCH_HEAD"]));
+
+        if self.submodules != Submodules::None {
+            let mut sync = args(&["submodule", "sync"]);
+            sync.extend(recursive.clone());
+            commands.push(sync);
+
+            let mut update = args(&["submodule", "update", "--init", "--force"]);
+            update.extend(depth);
+            update.extend(recursive);
+            commands.push(update);
+        }
+        commands
+    }
+}
+
+/// Replace every secret in `line` with `***`.
+fn mask(line: &str, secrets: &[String]) -> String {
+    secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .fold(line.to_string(), |line, secret| {
+            line.replace(secret.as_str(), MASK)
+        })
+}
+
+/// Runs the git CLI through `ProcessInvoker`.
+struct ProcessGitInvoker {
+    git: PathBuf,
+}
+
+impl ProcessGitInvoker {
+    fn new(git: PathBuf) -> Self {
+        Self { git }
+    }
+}
+
+#[async_trait]
+impl GitInvoker for ProcessGitInvoker {
+    async fn invoke(
+        &self,
+        directory: &Path,
+        arguments: &[String],
+        environment: &HashMap<String, String>,
+        secrets: &[String],
+        trace: &dyn TraceWriter,
+    ) -> Result<()> {
+        trace.info(&format!(
+            "[command]git {}",
+            mask(&arguments.join(" "), secrets)
+        ));
+
+        // The invoker's own diagnostics include the arguments, so they are
+        // collected here and masked before they are traced
+        let diagnostics = Arc::new(CollectingTraceWriter::default());
+        let mut invoker = ProcessInvoker::new(diagnostics.clone());
+        let mut stdout_rx = invoker
+            .take_stdout_receiver()
+            .context("stdout receiver already taken")?;
+        let mut stderr_rx = invoker
+            .take_stderr_receiver()
+            .context("stderr receiver already taken")?;
+
+        let git = self.git.to_string_lossy();
+        let directory = directory.to_string_lossy();
+        let execution = invoker.execute_with_arguments(
+            &directory,
+            &git,
+            arguments,
+            Some(environment),
+            true,
+            false,
+            CancellationToken::new(),
+        );
+        tokio::pin!(execution);
+
+        // Forward git's output while it runs
+        let result = loop {
+            tokio::select! {
+                result = &mut execution => break result,
+                Some(event) = stdout_rx.recv() => trace.info(&mask(&event.data, secrets)),
+                Some(event) = stderr_rx.recv() => trace.info(&mask(&event.data, secrets)),
+            }
+        };
+        while let Ok(event) = stdout_rx.try_recv() {
+            trace.info(&mask(&event.data, secrets));
+        }
+        while let Ok(event) = stderr_rx.try_recv() {
+            trace.info(&mask(&event.data, secrets));
+        }
+        for line in diagnostics.lines.lock().unwrap().drain(..) {
+            trace.verbose(&mask(&line, secrets));
+        }
+
+        let subcommand = arguments.first().map(String::as_str).unwrap_or_default();
+        result.with_context(|| format!("git {subcommand} failed"))?;
+        Ok(())
+    }
+}
+
+/// Keeps the lines traced by a `ProcessInvoker`.
+#[derive(Default)]
+struct CollectingTraceWriter {
+    lines: Mutex<Vec<String>>,
+}
+
+impl TraceWriter for CollectingTraceWriter {
+    fn info(&self, message: &str) {
+        self.lines.lock().unwrap().push(message.to_string());
+    }
+
+    fn verbose(&self, message: &str) {
+        self.info(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records git invocations instead of running git.
+    #[derive(Default)]
+    struct RecordingGitInvoker {
+        invocations: Mutex<Vec<String>>,
+        environments: Mutex<Vec<HashMap<String, String>>>,
+    }
+
+    #[async_trait]
+    impl GitInvoker for RecordingGitInvoker {
+        async fn invoke(
+            &self,
+            _directory: &Path,
+            arguments: &[String],
+            environment: &HashMap<String, String>,
+            _secrets: &[String],
+            _trace: &dyn TraceWriter,
+        ) -> Result<()> {
+            self.invocations.lock().unwrap().push(arguments.join(" "));
+            self.environments.lock().unwrap().push(environment.clone());
+            Ok(())
+        }
+    }
+
+    struct NullTrace;
+
+    impl TraceWriter for NullTrace {
+        fn info(&self, _message: &str) {}
+        fn verbose(&self, _message: &str) {}
+    }
+
+    fn context(workspace: &Path) -> ActionPluginContext {
+        let mut context = ActionPluginContext::new();
+        context.context.insert(
+            "github".to_string(),
+            serde_json::json!({
+                "repository": "octo/hello",
+                "ref": "refs/heads/main",
+                "sha": "abc123",
+                "token": "ghs_secret",
+                "workspace": workspace.join("hello").to_string_lossy(),
+            }),
+        );
+        context
+    }
+
+    fn header() -> String {
+        format!(
+            "AUTHORIZATION: basic {}",
+            BASE64.encode("x-access-token:ghs_secret")
+        )
+    }
+
+    #[test]
+    fn test_defaults_come_from_github_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = CheckoutOptions::from_context(&context(dir.path())).unwrap();
+
+        assert_eq!(options.repository_url, "https://github.com/octo/hello");
+        assert_eq!(options.target(), "abc123");
+        assert_eq!(options.path, dir.path().join("hello"));
+        assert!(options.clean);
+        assert_eq!(options.fetch_depth, 0);
+        assert_eq!(options.submodules, Submodules::None);
+    }
+
+    #[test]
+    fn test_path_must_stay_in_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut context = context(dir.path());
+        context
+            .inputs
+            .insert("path".to_string(), "../escape".to_string());
+        assert!(CheckoutOptions::from_context(&context).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_new_checkout_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = CheckoutOptions::from_context(&context(dir.path())).unwrap();
+        let git = RecordingGitInvoker::default();
+
+        checkout(&options, &git, &NullTrace).await.unwrap();
+
+        assert!(options.path.is_dir());
+        assert_eq!(
+            *git.invocations.lock().unwrap(),
+            [
+                "init",
+                "remote add origin https://github.com/octo/hello",
+                "fetch --no-tags --prune --progress origin abc123",
+                "checkout --progress --force FETCH_HEAD",
+            ]
+        );
+        for environment in git.environments.lock().unwrap().iter() {
+            assert_eq!(
+                environment["GIT_CONFIG_KEY_0"],
+                "http.https://github.com/.extraheader"
+            );
+            assert_eq!(environment["GIT_CONFIG_VALUE_0"], header());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_depth_and_submodules() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut context = context(dir.path());
+        for (name, value) in [
+            ("repository", "octo/other"),
+            ("ref", "v2"),
+            ("fetch-depth", "1"),
+            ("path", "other"),
+            ("clean", "false"),
+            ("submodules", "recursive"),
+        ] {
+            context.inputs.insert(name.to_string(), value.to_string());
+        }
+        let options = CheckoutOptions::from_context(&context).unwrap();
+        assert_eq!(
+            options.path,
+            std::fs::canonicalize(dir.path()).unwrap().join("other")
+        );
+        std::fs::create_dir_all(options.path.join(".git")).unwrap();
+        let git = RecordingGitInvoker::default();
+
+        checkout(&options, &git, &NullTrace).await.unwrap();
+
+        assert_eq!(
+            *git.invocations.lock().unwrap(),
+            [
+                "remote set-url origin https://github.com/octo/other",
+                "fetch --no-tags --prune --progress --depth=1 origin v2",
+                "checkout --progress --force FETCH_HEAD",
+                "submodule sync --recursive",
+                "submodule update --init --force --depth=1 --recursive",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_submodules_input_is_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut context = context(dir.path());
+        context
+            .inputs
+            .insert("submodules".to_string(), "true".to_string());
+        let options = CheckoutOptions::from_context(&context).unwrap();
+        assert_eq!(options.submodules, Submodules::TopLevel);
+
+        context
+            .inputs
+            .insert("submodules".to_string(), "sometimes".to_string());
+        assert!(CheckoutOptions::from_context(&context).is_err());
+    }
+
+    #[test]
+    fn test_token_stays_off_the_command_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = CheckoutOptions::from_context(&context(dir.path())).unwrap();
+        for arguments in options.git_commands(false) {
+            assert!(
+                !arguments.iter().any(|arg| arg.contains("ghs_secret")),
+                "{:?}",
+                arguments
+            );
+        }
+
+        let environment = options.git_environment();
+        assert_eq!(environment["GIT_CONFIG_COUNT"], "1");
+        assert_eq!(
+            mask(&environment["GIT_CONFIG_VALUE_0"], &options.secrets()),
+            "AUTHORIZATION: basic ***"
+        );
+    }
+
+    #[test]
+    fn test_auth_header_is_scoped_to_the_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut context = context(dir.path());
+        context.context.get_mut("github").unwrap()["server_url"] =
+            serde_json::json!("https://ghe.example.com:8443/");
+        let options = CheckoutOptions::from_context(&context).unwrap();
+
+        assert_eq!(
+            options.repository_url,
+            "https://ghe.example.com:8443/octo/hello"
+        );
+        assert_eq!(
+            options.git_environment()["GIT_CONFIG_KEY_0"],
+            "http.https://ghe.example.com:8443/.extraheader"
+        );
+    }
+
+    #[test]
+    fn test_no_token_no_auth_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut context = context(dir.path());
+        context.context.get_mut("github").unwrap()["token"] = serde_json::json!("");
+        let options = CheckoutOptions::from_context(&context).unwrap();
+
+        let environment = options.git_environment();
+        assert_eq!(environment["GIT_TERMINAL_PROMPT"], "0");
+        assert!(!environment.contains_key("GIT_CONFIG_COUNT"));
+    }
+
+    #[tokio::test]
+    async fn test_process_invoker_runs_git() {
+        let Ok(Some(git)) = WhichUtil::which("git", false) else {
+            return;
+        };
+        let dir = tempfile::tempdir().unwrap();
+
+        ProcessGitInvoker::new(git.clone())
+            .invoke(
+                dir.path(),
+                &["init".to_string()],
+                &HashMap::new(),
+                &[],
+                &NullTrace,
+            )
+            .await
+            .unwrap();
+        assert!(dir.path().join(".git").is_dir());
+
+        let err = ProcessGitInvoker::new(git)
+            .invoke(
+                dir.path(),
+                &["no-such-command".to_string()],
+                &HashMap::new(),
+                &[],
+                &NullTrace,
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("git no-such-command failed"),
+            "{}",
+            err
+        );
+    }
+}
@@ -1,7 +1,14 @@
-// Repository plugin module.
+// Repository plugin module – check out source repositories.
 //
 // Maps the C# `Runner.Plugins.Repository` namespace.
-// Checkout is handled externally (by the `actions/checkout` action), so this
-// module is minimal. It exists as a placeholder to mirror the C# project
-// structure and can be extended in the future if repository-related plugin
-// logic is needed.
+
+pub mod checkout;
+
+use crate::PluginFactory;
+use checkout::CheckoutPlugin;
+
+/// The repository plugins, keyed by the type names the worker passes.
+pub const PLUGINS: &[(&str, PluginFactory)] =
+    &[("GitHub.Runner.Plugins.Repository.v1_1.CheckoutTask", || {
+        Box::new(CheckoutPlugin)
+    })];
@@ -33,6 +33,10 @@ pub struct ServiceEndpoint {
 
     /// Authorization parameters.
     pub authorization: Option<EndpointAuthorization>,
+
+    /// Additional endpoint data (e.g., `ResultsServiceUrl`).
+    #[serde(default)]
+    pub data: HashMap<String, String>,
 }
 
 /// Authorization information for a service endpoint.
@@ -212,6 +216,7 @@ mod tests {
                     p
                 },
             }),
+            data: HashMap::new(),
         });
         let json = serde_json::to_string(&ctx).unwrap();
         let deserialized: ActionPluginContext = serde_json::from_str(&json).unwrap();
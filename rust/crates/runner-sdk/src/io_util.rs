@@ -1,9 +1,14 @@
 use anyhow::{Context, Result};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::{fs, thread, time::Duration};
 
+/// Read size used when hashing files, so large files are never held in memory.
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
 /// The executable file extension for the current platform.
 #[cfg(target_os = "windows")]
 pub const EXE_EXTENSION: &str = ".exe";
@@ -33,6 +38,28 @@ impl IOUtil {
     /// If the initial removal fails (e.g. due to transient locks), the function
     /// retries up to 3 times with a small delay between attempts.
     pub fn delete_directory(path: &Path) -> Result<()> {
+        Self::delete_directory_with_retry(path, 3, Duration::from_millis(100))
+    }
+
+    /// Recursively delete a directory, retrying transient failures.
+    ///
+    /// Access-denied and busy errors (antivirus scans, lingering handles on
+    /// Windows) are retried up to `attempts` times in total, waiting
+    /// `delay * n` after the n-th failure. Read-only attributes are cleared
+    /// before each attempt. Any other error fails immediately.
+    pub fn delete_directory_with_retry(path: &Path, attempts: u32, delay: Duration) -> Result<()> {
+        Self::delete_directory_with(path, attempts, delay, |p| fs::remove_dir_all(p))
+    }
+
+    fn delete_directory_with<F>(
+        path: &Path,
+        attempts: u32,
+        delay: Duration,
+        mut remove: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&Path) -> std::io::Result<()>,
+    {
         if !path.exists() {
             return Ok(());
         }
@@ -60,37 +87,55 @@ impl IOUtil {
             return Ok(());
         }
 
-        let max_retries = 3;
-        let mut last_err = None;
+        let attempts = attempts.max(1);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
 
-        for attempt in 0..max_retries {
             // Try to remove read-only attributes on files before deletion
             if let Err(e) = Self::remove_readonly_recursive(path) {
                 tracing::debug!(
                     "Failed to remove readonly attributes (attempt {}): {}",
-                    attempt + 1,
+                    attempt,
                     e
                 );
             }
 
-            match fs::remove_dir_all(path) {
+            match remove(path) {
                 Ok(()) => return Ok(()),
+                Err(e) if attempt < attempts && Self::is_transient_delete_error(&e) => {
+                    tracing::debug!(
+                        "Deleting '{}' failed (attempt {}), retrying: {}",
+                        path.display(),
+                        attempt,
+                        e
+                    );
+                    thread::sleep(delay * attempt);
+                }
                 Err(e) => {
-                    last_err = Some(e);
-                    if attempt < max_retries - 1 {
-                        thread::sleep(Duration::from_millis(100 * (attempt as u64 + 1)));
-                    }
+                    return Err(e).with_context(|| {
+                        format!(
+                            "Failed to delete directory '{}' after {} attempt(s)",
+                            path.display(),
+                            attempt
+                        )
+                    });
                 }
             }
         }
+    }
 
-        Err(last_err.unwrap()).with_context(|| {
-            format!(
-                "Failed to delete directory '{}' after {} retries",
-                path.display(),
-                max_retries
-            )
-        })
+    /// Whether a delete failure is likely a transient lock worth retrying.
+    fn is_transient_delete_error(error: &std::io::Error) -> bool {
+        // ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION, ERROR_DIR_NOT_EMPTY
+        #[cfg(windows)]
+        const BUSY_CODES: &[i32] = &[32, 33, 145];
+        // EBUSY
+        #[cfg(not(windows))]
+        const BUSY_CODES: &[i32] = &[16];
+
+        error.kind() == std::io::ErrorKind::PermissionDenied
+            || matches!(error.raw_os_error(), Some(code) if BUSY_CODES.contains(&code))
     }
 
     /// Delete a single file, removing the read-only attribute if necessary.
@@ -174,6 +219,24 @@ impl IOUtil {
         Self::get_root_path().join("externals")
     }
 
+    /// Compute the SHA256 digest of a file, reading it in fixed-size chunks.
+    pub fn sha256_file(path: &Path) -> Result<[u8; 32]> {
+        let mut file = fs::File::open(path)
+            .with_context(|| format!("Failed to open {} for hashing", path.display()))?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .with_context(|| format!("Failed to read {} for hashing", path.display()))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        Ok(hasher.finalize().into())
+    }
+
     /// Compare two path strings using the platform-appropriate case sensitivity.
     pub fn paths_equal(a: &str, b: &str) -> bool {
         match FILE_PATH_STRING_COMPARISON {
@@ -322,4 +385,88 @@ mod tests {
         #[cfg(not(target_os = "windows"))]
         assert_eq!(EXE_EXTENSION, "");
     }
+
+    #[test]
+    fn sha256_file_streams_large_file() {
+        use std::io::Write;
+
+        // 14 MiB spread over many buffer-sized reads
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("large.bin");
+        let mut file = fs::File::create(&path).unwrap();
+        let line = b"runner\n".repeat(1 << 15);
+        for _ in 0..(1 << 6) {
+            file.write_all(&line).unwrap();
+        }
+        drop(file);
+
+        assert_eq!(
+            hex::encode(IOUtil::sha256_file(&path).unwrap()),
+            "f575ba66f138baff7cab8bd1592e5fb6bd699cc6c0fdb9c96c6f82565590d1d9"
+        );
+    }
+
+    #[test]
+    fn sha256_file_empty_and_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty");
+        fs::write(&path, b"").unwrap();
+        assert_eq!(
+            hex::encode(IOUtil::sha256_file(&path).unwrap()),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert!(IOUtil::sha256_file(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn delete_directory_retries_transient_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("work");
+        fs::create_dir_all(target.join("nested")).unwrap();
+        fs::write(target.join("nested/file.txt"), "data").unwrap();
+
+        let mut calls = 0;
+        IOUtil::delete_directory_with(&target, 3, Duration::from_millis(1), |p| {
+            calls += 1;
+            if calls == 1 {
+                Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+            } else {
+                fs::remove_dir_all(p)
+            }
+        })
+        .unwrap();
+
+        assert_eq!(calls, 2);
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn delete_directory_reports_exhausted_retries() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut calls = 0;
+        let err = IOUtil::delete_directory_with(dir.path(), 3, Duration::from_millis(1), |_| {
+            calls += 1;
+            Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+        })
+        .unwrap_err();
+
+        assert_eq!(calls, 3);
+        assert!(format!("{err:#}").contains("after 3 attempt(s)"), "{err:#}");
+        assert!(dir.path().exists());
+    }
+
+    #[test]
+    fn delete_directory_does_not_retry_other_errors() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut calls = 0;
+        let result = IOUtil::delete_directory_with(dir.path(), 3, Duration::from_millis(1), |_| {
+            calls += 1;
+            Err(std::io::Error::other("disk on fire"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
 }
@@ -9,6 +9,8 @@ pub mod io_util;
 pub mod path_util;
 pub mod process_invoker;
 pub mod string_util;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 pub mod trace;
 pub mod url_util;
 pub mod vss_util;
@@ -24,7 +26,7 @@ pub use path_util::PathUtil;
 pub use process_invoker::{ProcessDataReceivedEventArgs, ProcessExitCodeError, ProcessInvoker};
 pub use string_util::StringUtil;
 pub use trace::TraceWriter;
-pub use url_util::UrlUtil;
+pub use url_util::{RunnerScope, UrlUtil};
 pub use vss_util::VssUtil;
 pub use web_proxy::RunnerWebProxy;
 pub use which_util::WhichUtil;
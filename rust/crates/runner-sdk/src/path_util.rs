@@ -1,5 +1,6 @@
 use crate::io_util::FILE_PATH_STRING_COMPARISON;
 use crate::io_util::FilePathComparison;
+use std::path::{Component, Path, PathBuf};
 
 /// PATH environment variable name (platform-specific).
 ///
@@ -72,6 +73,72 @@ impl PathUtil {
         format!("{path}{separator}{current_path}")
     }
 
+    /// Whether `candidate` resolves to `root` or a path beneath it.
+    ///
+    /// Both paths are canonicalized, so `..` segments and symlinks pointing
+    /// outside `root` are detected. Returns false if `root` does not exist.
+    pub fn contains(root: &Path, candidate: &Path) -> bool {
+        match (
+            std::fs::canonicalize(root),
+            Self::canonicalize_lenient(candidate),
+        ) {
+            (Ok(root), Ok(candidate)) => candidate.starts_with(root),
+            _ => false,
+        }
+    }
+
+    /// Resolve `relative` against `root`, rejecting results that escape it.
+    ///
+    /// The target does not need to exist yet (e.g. a download destination);
+    /// its deepest existing ancestor is canonicalized and the remainder is
+    /// normalized lexically. An absolute `relative` is checked as-is.
+    pub fn resolve_within(root: &Path, relative: &Path) -> anyhow::Result<PathBuf> {
+        let canonical_root = std::fs::canonicalize(root)
+            .map_err(|e| anyhow::anyhow!("Path does not exist {}: {e}", root.display()))?;
+        let resolved = Self::canonicalize_lenient(&canonical_root.join(relative))?;
+
+        if !resolved.starts_with(&canonical_root) {
+            anyhow::bail!(
+                "Path '{}' resolves outside of '{}'",
+                relative.display(),
+                canonical_root.display()
+            );
+        }
+
+        Ok(resolved)
+    }
+
+    /// Canonicalize the deepest existing ancestor of `path` and append the
+    /// rest, folding `.` and `..` lexically.
+    fn canonicalize_lenient(path: &Path) -> std::io::Result<PathBuf> {
+        let mut existing = path;
+        let mut tail = Vec::new();
+        let mut base = loop {
+            match std::fs::canonicalize(existing) {
+                Ok(p) => break p,
+                Err(e) => match (existing.parent(), existing.components().next_back()) {
+                    (Some(parent), Some(last)) => {
+                        tail.push(last);
+                        existing = parent;
+                    }
+                    _ => return Err(e),
+                },
+            }
+        };
+
+        for component in tail.into_iter().rev() {
+            match component {
+                Component::ParentDir => {
+                    base.pop();
+                }
+                Component::CurDir => {}
+                other => base.push(other),
+            }
+        }
+
+        Ok(base)
+    }
+
     /// The platform-specific PATH entry separator character.
     fn path_separator() -> char {
         if cfg!(target_os = "windows") {
@@ -114,4 +181,52 @@ mod tests {
         let result = PathUtil::prepend_path_value("/new", &current);
         assert_eq!(result, current);
     }
+
+    #[test]
+    fn resolve_within_accepts_sub_paths() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("src/app")).unwrap();
+        let canonical_root = std::fs::canonicalize(root.path()).unwrap();
+
+        assert_eq!(
+            PathUtil::resolve_within(root.path(), Path::new("src/app")).unwrap(),
+            canonical_root.join("src/app")
+        );
+        assert_eq!(
+            PathUtil::resolve_within(root.path(), Path::new("src/../out/new")).unwrap(),
+            canonical_root.join("out/new")
+        );
+        assert_eq!(
+            PathUtil::resolve_within(root.path(), Path::new(".")).unwrap(),
+            canonical_root
+        );
+        assert!(PathUtil::contains(
+            root.path(),
+            &root.path().join("src/app")
+        ));
+    }
+
+    #[test]
+    fn resolve_within_rejects_parent_escapes() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("src")).unwrap();
+
+        let err = PathUtil::resolve_within(root.path(), Path::new("../../etc")).unwrap_err();
+        assert!(err.to_string().contains("resolves outside of"));
+        assert!(PathUtil::resolve_within(root.path(), Path::new("src/../../x")).is_err());
+        assert!(PathUtil::resolve_within(root.path(), Path::new("missing/../../x")).is_err());
+        assert!(!PathUtil::contains(root.path(), &root.path().join("..")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_within_rejects_symlink_escapes() {
+        let outside = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("link")).unwrap();
+
+        assert!(PathUtil::resolve_within(root.path(), Path::new("link")).is_err());
+        assert!(PathUtil::resolve_within(root.path(), Path::new("link/new-file")).is_err());
+        assert!(!PathUtil::contains(root.path(), &root.path().join("link")));
+    }
 }
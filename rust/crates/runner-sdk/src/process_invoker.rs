@@ -1,10 +1,13 @@
 use crate::trace::TraceWriter;
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use encoding_rs::Encoding;
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, PtySize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufRead;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
@@ -36,8 +39,21 @@ pub struct ProcessDataReceivedEventArgs {
 /// and delivers output lines through channels.
 ///
 /// Maps `ProcessInvoker.cs` from the C# SDK.
+///
+/// With `with_pty(true)` the process runs attached to a pseudo-terminal
+/// instead of pipes, so tools that check `isatty` keep their interactive
+/// behaviour (colors, line buffering). Stdout and stderr then share the
+/// terminal and are both delivered on the stdout channel.
+///
+/// Output is decoded as UTF-8 unless `with_output_encoding` names another
+/// encoding, e.g. the OEM code page `cmd.exe` writes in. Bytes that are
+/// invalid in the encoding become U+FFFD rather than ending the stream.
 pub struct ProcessInvoker {
     trace: Arc<dyn TraceWriter>,
+    /// Whether to run the process attached to a pseudo-terminal.
+    use_pty: bool,
+    /// The encoding the process writes its output in.
+    output_encoding: &'static Encoding,
     /// Channel for stdout lines. Subscribe via `take_stdout_receiver`.
     stdout_tx: mpsc::UnboundedSender<ProcessDataReceivedEventArgs>,
     stdout_rx: Option<mpsc::UnboundedReceiver<ProcessDataReceivedEventArgs>>,
@@ -53,6 +69,8 @@ impl ProcessInvoker {
         let (stderr_tx, stderr_rx) = mpsc::unbounded_channel();
         Self {
             trace,
+            use_pty: false,
+            output_encoding: encoding_rs::UTF_8,
             stdout_tx,
             stdout_rx: Some(stdout_rx),
             stderr_tx,
@@ -60,6 +78,18 @@ impl ProcessInvoker {
         }
     }
 
+    /// Run processes attached to a pseudo-terminal instead of pipes.
+    pub fn with_pty(mut self, enabled: bool) -> Self {
+        self.use_pty = enabled;
+        self
+    }
+
+    /// Decode the process output from `encoding` instead of UTF-8.
+    pub fn with_output_encoding(mut self, encoding: &'static Encoding) -> Self {
+        self.output_encoding = encoding;
+        self
+    }
+
     /// Take the stdout receiver. Can only be called once; subsequent calls return `None`.
     pub fn take_stdout_receiver(
         &mut self,
@@ -95,6 +125,63 @@ impl ProcessInvoker {
         require_exit_code_zero: bool,
         kill_process_on_cancel: bool,
         cancellation_token: CancellationToken,
+    ) -> Result<i32> {
+        // The C# version passes a single arguments string to ProcessStartInfo.Arguments.
+        // We do a simple shell-like split for cross-platform compatibility.
+        self.execute_split(
+            working_directory,
+            file_name,
+            arguments,
+            shell_split(arguments),
+            environment,
+            require_exit_code_zero,
+            kill_process_on_cancel,
+            cancellation_token,
+        )
+        .await
+    }
+
+    /// Execute a process with its arguments given one by one, so they reach
+    /// the process exactly as passed, without any quoting.
+    ///
+    /// Otherwise behaves like [`ProcessInvoker::execute`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn execute_with_arguments(
+        &self,
+        working_directory: &str,
+        file_name: &str,
+        arguments: &[String],
+        environment: Option<&HashMap<String, String>>,
+        require_exit_code_zero: bool,
+        kill_process_on_cancel: bool,
+        cancellation_token: CancellationToken,
+    ) -> Result<i32> {
+        self.execute_split(
+            working_directory,
+            file_name,
+            &arguments.join(" "),
+            arguments.to_vec(),
+            environment,
+            require_exit_code_zero,
+            kill_process_on_cancel,
+            cancellation_token,
+        )
+        .await
+    }
+
+    /// Execute a process; `arguments` is only used for diagnostics, the
+    /// process receives `argv`.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_split(
+        &self,
+        working_directory: &str,
+        file_name: &str,
+        arguments: &str,
+        argv: Vec<String>,
+        environment: Option<&HashMap<String, String>>,
+        require_exit_code_zero: bool,
+        kill_process_on_cancel: bool,
+        cancellation_token: CancellationToken,
     ) -> Result<i32> {
         assert!(!file_name.is_empty(), "file_name must not be empty");
 
@@ -111,41 +198,38 @@ impl ProcessInvoker {
         self.trace.info(&format!(
             "  Force kill process on cancellation: '{kill_process_on_cancel}'"
         ));
+        self.trace
+            .info(&format!("  Use pseudo-terminal: '{}'", self.use_pty));
+        self.trace.info(&format!(
+            "  Output encoding: '{}'",
+            self.output_encoding.name()
+        ));
 
-        let mut cmd = Command::new(file_name);
-
-        // Split arguments by whitespace for argument passing.
-        // The C# version passes a single arguments string to ProcessStartInfo.Arguments.
-        // We do a simple shell-like split for cross-platform compatibility.
-        if !arguments.is_empty() {
-            for arg in shell_split(arguments) {
-                cmd.arg(arg);
-            }
+        if self.use_pty {
+            return self
+                .execute_in_pty(
+                    working_directory,
+                    file_name,
+                    arguments,
+                    argv,
+                    environment,
+                    require_exit_code_zero,
+                    kill_process_on_cancel,
+                    cancellation_token,
+                )
+                .await;
         }
 
+        let mut cmd = Command::new(file_name);
+        cmd.args(argv);
+
         if !working_directory.is_empty() && Path::new(working_directory).is_dir() {
             cmd.current_dir(working_directory);
         }
 
         // Set environment variables
-        if let Some(env) = environment {
-            for (key, value) in env {
-                cmd.env(key, value);
-            }
-        }
-
-        // Always set GITHUB_ACTIONS=true
-        cmd.env("GITHUB_ACTIONS", "true");
-
-        // Set CI=true if not already set
-        if std::env::var("CI").is_err() {
-            if let Some(env) = environment {
-                if !env.contains_key("CI") {
-                    cmd.env("CI", "true");
-                }
-            } else {
-                cmd.env("CI", "true");
-            }
+        for (key, value) in process_environment(environment) {
+            cmd.env(key, value);
         }
 
         cmd.stdout(std::process::Stdio::piped());
@@ -165,13 +249,10 @@ impl ProcessInvoker {
         let stdout = child.stdout.take();
         let stdout_tx = self.stdout_tx.clone();
         let trace_clone = self.trace.clone();
+        let encoding = self.output_encoding;
         let stdout_task = tokio::spawn(async move {
             if let Some(stdout) = stdout {
-                let reader = BufReader::new(stdout);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    let _ = stdout_tx.send(ProcessDataReceivedEventArgs { data: line });
-                }
+                forward_lines(BufReader::new(stdout), encoding, &stdout_tx).await;
             }
             trace_clone.info("STDOUT stream read finished.");
         });
@@ -182,11 +263,7 @@ impl ProcessInvoker {
         let trace_clone2 = self.trace.clone();
         let stderr_task = tokio::spawn(async move {
             if let Some(stderr) = stderr {
-                let reader = BufReader::new(stderr);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    let _ = stderr_tx.send(ProcessDataReceivedEventArgs { data: line });
-                }
+                forward_lines(BufReader::new(stderr), encoding, &stderr_tx).await;
             }
             trace_clone2.info("STDERR stream read finished.");
         });
@@ -200,7 +277,7 @@ impl ProcessInvoker {
                 was_cancelled = false;
                 match status {
                     Ok(s) => {
-                        exit_code = s.code().unwrap_or(-1);
+                        exit_code = status_exit_code(&s);
                     }
                     Err(e) => {
                         return Err(e).context("Failed to wait for process");
@@ -239,6 +316,166 @@ impl ProcessInvoker {
         Ok(exit_code)
     }
 
+    /// Execute a process attached to a pseudo-terminal.
+    ///
+    /// Output is read from the terminal line by line and sent to the stdout
+    /// channel with the trailing carriage return removed.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_in_pty(
+        &self,
+        working_directory: &str,
+        file_name: &str,
+        arguments: &str,
+        argv: Vec<String>,
+        environment: Option<&HashMap<String, String>>,
+        require_exit_code_zero: bool,
+        kill_process_on_cancel: bool,
+        cancellation_token: CancellationToken,
+    ) -> Result<i32> {
+        let pair = native_pty_system()
+            .openpty(PtySize::default())
+            .context("Failed to open a pseudo-terminal")?;
+
+        let mut cmd = CommandBuilder::new(file_name);
+        cmd.args(argv);
+        if !working_directory.is_empty() && Path::new(working_directory).is_dir() {
+            cmd.cwd(working_directory);
+        }
+        for (key, value) in process_environment(environment) {
+            cmd.env(key, value);
+        }
+
+        let start = std::time::Instant::now();
+        let mut child = pair.slave.spawn_command(cmd).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to start process '{file_name}' with arguments '{arguments}': {e}"
+            )
+        })?;
+        // Only the child should hold the slave side, so reads see EOF once it exits.
+        drop(pair.slave);
+
+        let pid = child.process_id().unwrap_or(0);
+        self.trace.info(&format!(
+            "Process started with process id {pid} on a pseudo-terminal, waiting for process exit."
+        ));
+
+        // Spawn terminal reader (blocking reads on the pty master)
+        let reader = pair
+            .master
+            .try_clone_reader()
+            .map_err(|e| anyhow::anyhow!("Failed to read from pseudo-terminal: {e}"))?;
+        let stdout_tx = self.stdout_tx.clone();
+        let trace_clone = self.trace.clone();
+        let encoding = self.output_encoding;
+        let output_task = tokio::task::spawn_blocking(move || {
+            let mut reader = std::io::BufReader::new(reader);
+            let mut line = Vec::new();
+            // Reads fail with EIO once the child side is closed; treat that as EOF.
+            while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+                let data = decode_line(&line, encoding);
+                let _ = stdout_tx.send(ProcessDataReceivedEventArgs { data });
+                line.clear();
+            }
+            trace_clone.info("PTY stream read finished.");
+        });
+
+        // Wait for process exit or cancellation
+        let killer = child.clone_killer();
+        let mut wait_task = tokio::task::spawn_blocking(move || child.wait());
+        let exit_code: i32;
+        let was_cancelled;
+
+        tokio::select! {
+            status = &mut wait_task => {
+                was_cancelled = false;
+                match status {
+                    Ok(Ok(s)) => {
+                        exit_code = s.exit_code() as i32;
+                    }
+                    Ok(Err(e)) => {
+                        return Err(e).context("Failed to wait for process");
+                    }
+                    Err(e) => {
+                        return Err(e).context("Failed to wait for process");
+                    }
+                }
+            }
+            _ = cancellation_token.cancelled() => {
+                was_cancelled = true;
+                self.trace.info("Cancellation requested.");
+                exit_code = self
+                    .cancel_and_kill_pty_process(pid, killer, &mut wait_task, kill_process_on_cancel)
+                    .await;
+            }
+        }
+
+        // Closing the master ends the read once the remaining output is drained
+        drop(pair.master);
+        let _ = output_task.await;
+
+        let elapsed = start.elapsed();
+        self.trace.info(&format!(
+            "Finished process {pid} with exit code {exit_code}, and elapsed time {elapsed:.2?}."
+        ));
+
+        if was_cancelled {
+            anyhow::bail!("Process was cancelled");
+        }
+
+        if exit_code != 0 && require_exit_code_zero {
+            return Err(ProcessExitCodeError {
+                exit_code,
+                file_name: file_name.to_string(),
+                arguments: arguments.to_string(),
+            }
+            .into());
+        }
+
+        Ok(exit_code)
+    }
+
+    /// Graceful cancellation for a pseudo-terminal process: SIGINT → SIGTERM → kill.
+    async fn cancel_and_kill_pty_process(
+        &self,
+        pid: u32,
+        mut killer: Box<dyn ChildKiller + Send + Sync>,
+        wait_task: &mut tokio::task::JoinHandle<std::io::Result<portable_pty::ExitStatus>>,
+        kill_immediately: bool,
+    ) -> i32 {
+        let exit_code = |result: std::result::Result<
+            std::io::Result<portable_pty::ExitStatus>,
+            tokio::task::JoinError,
+        >| {
+            match result {
+                Ok(Ok(status)) => status.exit_code() as i32,
+                _ => -1,
+            }
+        };
+
+        if !kill_immediately {
+            for (signal, timeout) in [
+                (Signal::Int, SIGINT_TIMEOUT),
+                (Signal::Term, SIGTERM_TIMEOUT),
+            ] {
+                if !send_signal(pid, signal) {
+                    self.trace.info(&format!(
+                        "{signal:?} signal failed to send to process {pid}."
+                    ));
+                    continue;
+                }
+                if let Ok(result) = tokio::time::timeout(timeout, &mut *wait_task).await {
+                    self.trace
+                        .info(&format!("Process exited after {signal:?} signal."));
+                    return exit_code(result);
+                }
+            }
+        }
+
+        self.trace.info("Killing process on the pseudo-terminal.");
+        let _ = killer.kill();
+        exit_code(wait_task.await)
+    }
+
     /// Attempt graceful cancellation: SIGINT → SIGTERM → SIGKILL.
     /// If `kill_immediately` is true, skip signals and go straight to kill.
     async fn cancel_and_kill_process(
@@ -363,6 +600,56 @@ enum Signal {
     Term,
 }
 
+/// Send a signal to a process by id. Returns `true` if the signal was sent.
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: Signal) -> bool {
+    let sig = match signal {
+        Signal::Int => nix::sys::signal::Signal::SIGINT,
+        Signal::Term => nix::sys::signal::Signal::SIGTERM,
+    };
+    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), sig).is_ok()
+}
+
+#[cfg(not(unix))]
+fn send_signal(_pid: u32, _signal: Signal) -> bool {
+    false
+}
+
+/// The variables applied to a child process, in sorted order.
+///
+/// Precedence, lowest to highest: runner defaults (`CI=true`, unless the
+/// runner's own environment already has `CI`), then the caller's
+/// `environment`, then `GITHUB_ACTIONS=true`, which cannot be overridden.
+fn process_environment(environment: Option<&HashMap<String, String>>) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::new();
+    if std::env::var("CI").is_err() {
+        vars.insert("CI".to_string(), "true".to_string());
+    }
+    if let Some(env) = environment {
+        vars.extend(env.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    vars.insert("GITHUB_ACTIONS".to_string(), "true".to_string());
+    vars
+}
+
+/// The exit code of a finished process.
+///
+/// A process killed by a signal has no exit code; like a shell, report it as
+/// `128 + signal` so the failure stays visible instead of becoming `-1`.
+fn status_exit_code(status: &std::process::ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    -1
+}
+
 /// Simple argument splitting. Splits on whitespace but respects double-quoted
 /// and single-quoted strings. This is a minimal implementation; for production
 /// use, consider the `shell-words` crate.
@@ -408,6 +695,28 @@ fn shell_split(input: &str) -> Vec<String> {
     args
 }
 
+/// Read `reader` line by line, decoding each line from `encoding`.
+async fn forward_lines<R: AsyncBufRead + Unpin>(
+    mut reader: R,
+    encoding: &'static Encoding,
+    tx: &mpsc::UnboundedSender<ProcessDataReceivedEventArgs>,
+) {
+    let mut line = Vec::new();
+    while matches!(reader.read_until(b'\n', &mut line).await, Ok(n) if n > 0) {
+        let _ = tx.send(ProcessDataReceivedEventArgs {
+            data: decode_line(&line, encoding),
+        });
+        line.clear();
+    }
+}
+
+/// Decode one line of output, without its `\n` or `\r\n` terminator.
+fn decode_line(line: &[u8], encoding: &'static Encoding) -> String {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    encoding.decode_without_bom_handling(line).0.into_owned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -435,6 +744,25 @@ mod tests {
         assert_eq!(args, vec!["hello", "world foo", "bar"]);
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn execute_reports_signal_as_exit_code() {
+        let invoker = make_invoker();
+        let exit_code = invoker
+            .execute(
+                "",
+                "sh",
+                "-c 'kill -TERM $$'",
+                None,
+                false,
+                false,
+                CancellationToken::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(exit_code, 128 + 15);
+    }
+
     #[test]
     fn shell_split_empty() {
         let args = shell_split("");
@@ -473,6 +801,40 @@ mod tests {
         assert!(lines[0].contains("hello"));
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn execute_with_arguments_passes_them_verbatim() {
+        let mut invoker = make_invoker();
+        let mut rx = invoker.take_stdout_receiver().unwrap();
+        let arguments = vec![
+            "%s|%s\\n".to_string(),
+            "two words".to_string(),
+            "it's \"quoted\"".to_string(),
+        ];
+
+        let handle = tokio::spawn(async move {
+            invoker
+                .execute_with_arguments(
+                    "",
+                    "printf",
+                    &arguments,
+                    None,
+                    true,
+                    false,
+                    CancellationToken::new(),
+                )
+                .await
+        });
+
+        let mut lines = Vec::new();
+        while let Some(evt) = rx.recv().await {
+            lines.push(evt.data);
+        }
+
+        assert_eq!(handle.await.unwrap().unwrap(), 0);
+        assert_eq!(lines, ["two words|it's \"quoted\""]);
+    }
+
     #[tokio::test]
     async fn execute_nonexistent() {
         let invoker = make_invoker();
@@ -542,4 +904,113 @@ mod tests {
         let exit_code = handle.await.unwrap().unwrap();
         assert_eq!(exit_code, 0);
     }
+
+    #[test]
+    fn process_environment_precedence_and_order() {
+        let mut env = HashMap::new();
+        env.insert("ZETA".to_string(), "last".to_string());
+        env.insert("ALPHA".to_string(), "first".to_string());
+        env.insert("CI".to_string(), "false".to_string());
+        env.insert("GITHUB_ACTIONS".to_string(), "false".to_string());
+
+        let vars = process_environment(Some(&env));
+
+        // The caller can override CI but not GITHUB_ACTIONS
+        assert_eq!(vars["CI"], "false");
+        assert_eq!(vars["GITHUB_ACTIONS"], "true");
+        assert_eq!(vars["ALPHA"], "first");
+        let keys: Vec<&str> = vars.keys().map(String::as_str).collect();
+        assert_eq!(keys, vec!["ALPHA", "CI", "GITHUB_ACTIONS", "ZETA"]);
+    }
+
+    #[cfg(unix)]
+    async fn isatty_output(use_pty: bool) -> Vec<String> {
+        let mut invoker = make_invoker().with_pty(use_pty);
+        let mut rx = invoker.take_stdout_receiver().unwrap();
+        let cancel = CancellationToken::new();
+
+        let handle = tokio::spawn(async move {
+            invoker
+                .execute(
+                    "",
+                    "sh",
+                    "-c 'if [ -t 1 ]; then echo tty; else echo notty; fi'",
+                    None,
+                    true,
+                    false,
+                    cancel,
+                )
+                .await
+        });
+
+        let mut lines = Vec::new();
+        while let Some(evt) = rx.recv().await {
+            lines.push(evt.data);
+        }
+        assert_eq!(handle.await.unwrap().unwrap(), 0);
+        lines
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn execute_in_pty_reports_tty() {
+        assert_eq!(isatty_output(true).await, vec!["tty"]);
+        assert_eq!(isatty_output(false).await, vec!["notty"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn execute_in_pty_cancel() {
+        let invoker = make_invoker().with_pty(true);
+        let cancel = CancellationToken::new();
+        let cancel_clone = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            cancel_clone.cancel();
+        });
+
+        let result = invoker
+            .execute("", "sleep", "30", None, false, false, cancel)
+            .await;
+        assert!(result.unwrap_err().to_string().contains("cancelled"));
+    }
+
+    #[test]
+    fn decode_line_uses_output_encoding() {
+        // "café – ok" in Windows-1252
+        let line = b"caf\xe9 \x96 ok\r\n";
+        assert_eq!(decode_line(line, encoding_rs::WINDOWS_1252), "café – ok");
+        assert_eq!(
+            decode_line(line, encoding_rs::UTF_8),
+            "caf\u{fffd} \u{fffd} ok"
+        );
+        assert_eq!(decode_line(b"plain\n", encoding_rs::UTF_8), "plain");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn execute_decodes_non_utf8_output() {
+        let mut invoker = make_invoker().with_output_encoding(encoding_rs::WINDOWS_1252);
+        let mut rx = invoker.take_stdout_receiver().unwrap();
+
+        invoker
+            .execute(
+                "",
+                "printf",
+                r"'caf\351\nna\357ve\n'",
+                None,
+                true,
+                false,
+                CancellationToken::new(),
+            )
+            .await
+            .unwrap();
+        drop(invoker);
+
+        let mut lines = Vec::new();
+        while let Some(event) = rx.recv().await {
+            lines.push(event.data);
+        }
+        assert_eq!(lines, vec!["café", "naïve"]);
+    }
 }
@@ -14,9 +14,66 @@ impl StringUtil {
     }
 
     /// Deserialize a JSON string into a value of type `T`.
+    ///
+    /// On failure the error keeps serde's description of the problem and
+    /// names its line, column and byte offset. It never quotes values from
+    /// the JSON, which may hold secrets; only the names of missing or
+    /// unexpected fields and variants are kept.
     pub fn convert_from_json<T: DeserializeOwned>(json: &str) -> Result<T> {
-        let value = serde_json::from_str(json)?;
-        Ok(value)
+        serde_json::from_str(json).map_err(|e| {
+            let position = format!(" at line {} column {}", e.line(), e.column());
+            let message = e.to_string();
+            let message = message.strip_suffix(&position).unwrap_or(&message);
+            let problem = match e.classify() {
+                serde_json::error::Category::Syntax | serde_json::error::Category::Eof => {
+                    message.to_string()
+                }
+                serde_json::error::Category::Data => Self::describe_json_data_error(message),
+                serde_json::error::Category::Io => "Failed to read JSON".to_string(),
+            };
+            anyhow::anyhow!(
+                "{} at line {}, column {} (byte {})",
+                problem,
+                e.line(),
+                e.column(),
+                Self::json_byte_offset(json, e.line(), e.column())
+            )
+        })
+    }
+
+    /// Describe a serde data error without the input values it quotes,
+    /// e.g. `invalid type: string "s3cr3t", expected i32` becomes
+    /// `invalid type, expected i32`.
+    fn describe_json_data_error(message: &str) -> String {
+        for prefix in [
+            "missing field",
+            "unknown field",
+            "duplicate field",
+            "unknown variant",
+        ] {
+            let name = message
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.strip_prefix(" `"))
+                .and_then(|rest| rest.split('`').next());
+            if let Some(name) = name {
+                return format!("{} `{}`", prefix, name);
+            }
+        }
+        match (message.split_once(": "), message.rsplit_once(", expected ")) {
+            (Some((kind, _)), Some((_, expected))) => format!("{}, expected {}", kind, expected),
+            _ => "Unexpected JSON value".to_string(),
+        }
+    }
+
+    /// The byte offset of a serde_json error position (1-based line and
+    /// column) in `json`.
+    fn json_byte_offset(json: &str, line: usize, column: usize) -> usize {
+        let line_start: usize = json
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        (line_start + column.saturating_sub(1)).min(json.len())
     }
 
     /// Convert a string to a boolean.
@@ -107,6 +164,50 @@ mod tests {
         assert_eq!(parsed, obj);
     }
 
+    #[test]
+    fn convert_from_json_reports_error_position() {
+        let json = "{\n  \"name\": \"test\",\n  \"value\": 4x2\n}";
+        let err = StringUtil::convert_from_json::<TestObj>(json)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "expected `,` or `}` at line 3, column 13 (byte 32)");
+
+        let err = StringUtil::convert_from_json::<TestObj>(r#"{"name": "secret"#)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "EOF while parsing a string at line 1, column 16 (byte 15)"
+        );
+    }
+
+    #[test]
+    fn convert_from_json_names_fields_but_not_values() {
+        let err =
+            StringUtil::convert_from_json::<TestObj>(r#"{"name": "s3cr3t", "value": "s3cr3t"}"#)
+                .unwrap_err()
+                .to_string();
+        assert_eq!(
+            err,
+            "invalid type, expected i32 at line 1, column 36 (byte 35)"
+        );
+
+        let err = StringUtil::convert_from_json::<TestObj>(r#"{"name": "s3cr3t"}"#)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "missing field `value` at line 1, column 18 (byte 17)");
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        enum Shape {
+            Circle,
+        }
+        let err = StringUtil::convert_from_json::<Shape>(r#""Square""#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("unknown variant `Square`"), "{}", err);
+    }
+
     #[test]
     fn convert_to_bool_true_values() {
         assert_eq!(StringUtil::convert_to_bool("1"), Some(true));
@@ -0,0 +1,211 @@
+// Test helpers shared by the runner crates.
+// Built for this crate's tests and, through the `test-util` feature, for the
+// other crates' tests; they enable the feature only in `[dev-dependencies]`.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// A request received by a [`MockHttpServer`].
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    /// The path and query, e.g. `/completejob`.
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl MockRequest {
+    /// The value of header `name`, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The body as (lossy) UTF-8.
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    /// The body parsed as JSON.
+    pub fn body_json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).expect("request body is not JSON")
+    }
+}
+
+/// A canned response for a [`MockHttpServer`].
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    content_length: Option<usize>,
+}
+
+impl MockResponse {
+    /// An empty response with `status`, e.g. `"200 OK"`.
+    pub fn new(status: &str) -> Self {
+        Self {
+            status: status.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            content_length: None,
+        }
+    }
+
+    /// A response with `status` and a JSON `body`.
+    pub fn json(status: &str, body: impl Into<String>) -> Self {
+        Self::new(status)
+            .with_header("Content-Type", "application/json")
+            .with_body(body.into())
+    }
+
+    /// Add a header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Send `body`.
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Declare a `Content-Length` other than the body's, e.g. to cut a
+    /// download short.
+    pub fn with_content_length(mut self, length: usize) -> Self {
+        self.content_length = Some(length);
+        self
+    }
+}
+
+/// An HTTP/1.1 server on a local port, speaking just enough of the protocol
+/// over raw TCP for client tests: every connection gets one response and is
+/// closed.
+pub struct MockHttpServer {
+    url: String,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    task: JoinHandle<()>,
+}
+
+impl MockHttpServer {
+    /// Answer one request with each of `responses` in order, then stop.
+    pub async fn start(responses: Vec<MockResponse>) -> Self {
+        let mut responses = VecDeque::from(responses);
+        let count = responses.len();
+        Self::serve(Some(count), move |_| responses.pop_front().unwrap()).await
+    }
+
+    /// Answer every request with whatever `respond` returns for it.
+    pub async fn start_with(
+        respond: impl FnMut(&MockRequest) -> MockResponse + Send + 'static,
+    ) -> Self {
+        Self::serve(None, respond).await
+    }
+
+    async fn serve(
+        count: Option<usize>,
+        mut respond: impl FnMut(&MockRequest) -> MockResponse + Send + 'static,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let task = tokio::spawn(async move {
+            let mut served = 0;
+            while count.is_none_or(|count| served < count) {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let request = read_request(&mut socket).await;
+                let response = respond(&request);
+                recorded.lock().unwrap().push(request);
+                write_response(&mut socket, &response).await;
+                served += 1;
+            }
+        });
+        Self {
+            url,
+            requests,
+            task,
+        }
+    }
+
+    /// The server's base URL, e.g. `http://127.0.0.1:4711`.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The requests received so far.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Wait until every response given to [`MockHttpServer::start`] has been
+    /// served, and return the requests.
+    pub async fn finish(self) -> Vec<MockRequest> {
+        self.task.await.unwrap();
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+async fn read_request(socket: &mut TcpStream) -> MockRequest {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 64 * 1024];
+    let header_end = loop {
+        let n = socket.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed before the request headers ended");
+        data.extend_from_slice(&buf[..n]);
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default().to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    while data.len() < header_end + content_length {
+        let n = socket.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed before the request body ended");
+        data.extend_from_slice(&buf[..n]);
+    }
+
+    MockRequest {
+        method,
+        target,
+        headers,
+        body: data[header_end..].to_vec(),
+    }
+}
+
+async fn write_response(socket: &mut TcpStream, response: &MockResponse) {
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.content_length.unwrap_or(response.body.len())
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    // The client may hang up early, e.g. once it has the status it needs
+    let _ = socket.write_all(head.as_bytes()).await;
+    let _ = socket.write_all(&response.body).await;
+    let _ = socket.shutdown().await;
+}
@@ -1,7 +1,22 @@
 use crate::string_util::StringUtil;
+use anyhow::Result;
 use reqwest::header::HeaderMap;
 use url::Url;
 
+/// The broker serving every hosted (github.com / ghe.com) runner.
+const HOSTED_BROKER_URL: &str = "https://broker.actions.githubusercontent.com";
+
+/// Where a runner is registered, as named by its configuration URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunnerScope {
+    /// `https://github.com/<owner>/<repo>`
+    Repository { owner: String, repo: String },
+    /// `https://github.com/<org>`
+    Organization { org: String },
+    /// `https://github.com/enterprises/<enterprise>`
+    Enterprise { enterprise: String },
+}
+
 /// URL utility functions mapping `UrlUtil.cs`.
 pub struct UrlUtil;
 
@@ -30,6 +45,120 @@ impl UrlUtil {
             || host.ends_with(".ghe.com")
     }
 
+    /// Validate and normalize the URL passed to `config.sh --url`.
+    ///
+    /// Only http(s) URLs naming a repository, organization, or enterprise are
+    /// accepted. Surrounding whitespace, trailing slashes, the query and the
+    /// fragment are dropped.
+    pub fn normalize_config_url(input: &str) -> Result<Url> {
+        let input = input.trim();
+        if input.is_empty() {
+            anyhow::bail!("URL cannot be empty");
+        }
+
+        let mut url =
+            Url::parse(input).map_err(|e| anyhow::anyhow!("Invalid URL '{}': {}", input, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("URL must use HTTP or HTTPS scheme, got '{}'", url.scheme());
+        }
+        if url.host_str().is_none() {
+            anyhow::bail!("URL must have a host");
+        }
+
+        let path = url.path().trim_end_matches('/').to_string();
+        url.set_path(&path);
+        url.set_query(None);
+        url.set_fragment(None);
+        Self::config_scope(&url)?;
+        Ok(url)
+    }
+
+    /// The registration scope named by a configuration URL.
+    pub fn config_scope(url: &Url) -> Result<RunnerScope> {
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        match segments.as_slice() {
+            [enterprises, enterprise] if enterprises.eq_ignore_ascii_case("enterprises") => {
+                Ok(RunnerScope::Enterprise {
+                    enterprise: enterprise.to_string(),
+                })
+            }
+            [owner, repo] => Ok(RunnerScope::Repository {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            }),
+            [org] => Ok(RunnerScope::Organization {
+                org: org.to_string(),
+            }),
+            _ => anyhow::bail!(
+                "'{}' is not a repository, organization, or enterprise URL",
+                url
+            ),
+        }
+    }
+
+    /// Whether `url` would send credentials unencrypted to another machine.
+    pub fn is_insecure(url: &Url) -> bool {
+        let local = match url.host() {
+            Some(url::Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+            Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+            Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+            None => false,
+        };
+        url.scheme() == "http" && !local
+    }
+
+    /// The REST API URL for `path` on the GitHub instance rooted at `base`.
+    ///
+    /// Hosted servers serve the API from the `api.` subdomain; GHES serves it
+    /// from `/api/v3` under `base`, keeping any port and path prefix.
+    pub fn api_url(base: &Url, path: &str) -> String {
+        let root = if Self::is_hosted_server(base) {
+            let host = base.host_str().unwrap_or("github.com");
+            format!(
+                "{}://api.{}{}",
+                base.scheme(),
+                host.strip_prefix("www.").unwrap_or(host),
+                base.port().map(|p| format!(":{}", p)).unwrap_or_default()
+            )
+        } else {
+            format!(
+                "{}{}/api/v3",
+                base.origin().ascii_serialization(),
+                base.path().trim_end_matches('/')
+            )
+        };
+        Self::join(&root, path)
+    }
+
+    /// The broker URL for the Actions service at `base`.
+    ///
+    /// Hosted runners share one broker; GHES serves broker requests from
+    /// its own Actions service URL.
+    pub fn broker_url(base: &Url) -> String {
+        let host = base.host_str().unwrap_or("").to_lowercase();
+        if Self::is_hosted_server(base) || host.ends_with(".actions.githubusercontent.com") {
+            return HOSTED_BROKER_URL.to_string();
+        }
+        format!(
+            "{}{}",
+            base.origin().ascii_serialization(),
+            base.path().trim_end_matches('/')
+        )
+    }
+
+    /// `root` and `path` joined by exactly one `/`.
+    fn join(root: &str, path: &str) -> String {
+        let path = path.trim_start_matches('/');
+        if path.is_empty() {
+            root.to_string()
+        } else {
+            format!("{}/{}", root, path)
+        }
+    }
+
     /// Embed username and password into a URL for credential-based access.
     ///
     /// If both `username` and `password` are empty, returns the URL unchanged.
@@ -103,6 +232,128 @@ mod tests {
         assert!(!UrlUtil::is_hosted_server(&url));
     }
 
+    #[test]
+    fn api_url_github_com() {
+        std::env::remove_var("GITHUB_ACTIONS_RUNNER_FORCE_GHES");
+        let url = Url::parse("https://github.com/owner/repo").unwrap();
+        assert_eq!(
+            UrlUtil::api_url(&url, "actions/runner-registration"),
+            "https://api.github.com/actions/runner-registration"
+        );
+        let url = Url::parse("https://www.github.com").unwrap();
+        assert_eq!(UrlUtil::api_url(&url, ""), "https://api.github.com");
+        let url = Url::parse("https://mycompany.ghe.com").unwrap();
+        assert_eq!(
+            UrlUtil::api_url(&url, "/repos/o/r"),
+            "https://api.mycompany.ghe.com/repos/o/r"
+        );
+    }
+
+    #[test]
+    fn api_url_ghes() {
+        std::env::remove_var("GITHUB_ACTIONS_RUNNER_FORCE_GHES");
+        let url = Url::parse("https://github.mycompany.com").unwrap();
+        assert_eq!(
+            UrlUtil::api_url(&url, "actions/runner-registration"),
+            "https://github.mycompany.com/api/v3/actions/runner-registration"
+        );
+        assert_eq!(
+            UrlUtil::api_url(&url, ""),
+            "https://github.mycompany.com/api/v3"
+        );
+    }
+
+    #[test]
+    fn api_url_ghes_with_port_and_path() {
+        std::env::remove_var("GITHUB_ACTIONS_RUNNER_FORCE_GHES");
+        let url = Url::parse("http://github.mycompany.com:8080/github/").unwrap();
+        assert_eq!(
+            UrlUtil::api_url(&url, "repos/o/r"),
+            "http://github.mycompany.com:8080/github/api/v3/repos/o/r"
+        );
+    }
+
+    #[test]
+    fn broker_url_hosted_and_ghes() {
+        std::env::remove_var("GITHUB_ACTIONS_RUNNER_FORCE_GHES");
+        for hosted in [
+            "https://github.com",
+            "https://pipelines.actions.githubusercontent.com/abc123",
+        ] {
+            assert_eq!(
+                UrlUtil::broker_url(&Url::parse(hosted).unwrap()),
+                HOSTED_BROKER_URL
+            );
+        }
+        let url = Url::parse("https://github.mycompany.com:8443/_services/pipelines/").unwrap();
+        assert_eq!(
+            UrlUtil::broker_url(&url),
+            "https://github.mycompany.com:8443/_services/pipelines"
+        );
+    }
+
+    #[test]
+    fn normalize_config_url_scopes() {
+        let cases = [
+            (
+                " https://github.com/owner/repo/ ",
+                "https://github.com/owner/repo",
+                RunnerScope::Repository {
+                    owner: "owner".to_string(),
+                    repo: "repo".to_string(),
+                },
+            ),
+            (
+                "https://github.com/my-org?tab=repositories",
+                "https://github.com/my-org",
+                RunnerScope::Organization {
+                    org: "my-org".to_string(),
+                },
+            ),
+            (
+                "https://ghes.example.com/enterprises/acme//",
+                "https://ghes.example.com/enterprises/acme",
+                RunnerScope::Enterprise {
+                    enterprise: "acme".to_string(),
+                },
+            ),
+        ];
+        for (input, normalized, scope) in cases {
+            let url = UrlUtil::normalize_config_url(input).unwrap();
+            assert_eq!(url.as_str(), normalized);
+            assert_eq!(UrlUtil::config_scope(&url).unwrap(), scope);
+        }
+    }
+
+    #[test]
+    fn normalize_config_url_rejects_bad_urls() {
+        for input in [
+            "",
+            "ftp://github.com/owner/repo",
+            "ssh://git@github.com/owner/repo",
+            "file:///owner/repo",
+            "github.com/owner/repo",
+            "https://github.com/",
+            "https://github.com/owner/repo/tree/main",
+        ] {
+            assert!(
+                UrlUtil::normalize_config_url(input).is_err(),
+                "expected '{}' to be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn http_is_insecure_except_on_localhost() {
+        let insecure = |url: &str| UrlUtil::is_insecure(&Url::parse(url).unwrap());
+        assert!(insecure("http://ghes.example.com/org"));
+        assert!(!insecure("https://ghes.example.com/org"));
+        assert!(!insecure("http://localhost:3000/org"));
+        assert!(!insecure("http://127.0.0.1/org"));
+        assert!(!insecure("http://[::1]/org"));
+    }
+
     #[test]
     fn credential_embedded_url_both() {
         let url = Url::parse("https://github.com/repo").unwrap();
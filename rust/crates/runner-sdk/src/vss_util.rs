@@ -1,6 +1,10 @@
+use crate::action_plugin::EndpointAuthorization;
 use crate::string_util::StringUtil;
 use crate::web_proxy::RunnerWebProxy;
+use anyhow::Result;
+use base64::Engine;
 use reqwest::Client;
+use std::collections::HashMap;
 use std::time::Duration;
 
 /// VSS / HTTP client utility functions mapping `VssUtil.cs`.
@@ -117,6 +121,46 @@ impl VssUtil {
         let proxy = RunnerWebProxy::new();
         Self::create_http_client(&proxy)
     }
+
+    /// The `Authorization` header value for a bearer `token`.
+    pub fn bearer_header(token: &str) -> String {
+        format!("Bearer {}", token)
+    }
+
+    /// The `Authorization` header value for a service endpoint.
+    pub fn endpoint_auth_header(authorization: &EndpointAuthorization) -> Result<String> {
+        Self::auth_header(&authorization.scheme, &authorization.parameters)
+    }
+
+    /// The `Authorization` header value for an endpoint authorization
+    /// `scheme` and its `parameters`.
+    ///
+    /// `OAuth` and `Token` access tokens are sent as bearer tokens; a
+    /// `PersonalAccessToken` is sent as basic auth with an empty user name.
+    pub fn auth_header(scheme: &str, parameters: &HashMap<String, String>) -> Result<String> {
+        let parameter = |names: &[&str]| {
+            parameters
+                .iter()
+                .find(|(key, _)| names.iter().any(|name| key.eq_ignore_ascii_case(name)))
+                .map(|(_, value)| value.as_str())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("No {} in the '{}' endpoint authorization", names[0], scheme)
+                })
+        };
+
+        if scheme.eq_ignore_ascii_case("OAuth") || scheme.eq_ignore_ascii_case("Token") {
+            Ok(Self::bearer_header(parameter(&["AccessToken"])?))
+        } else if scheme.eq_ignore_ascii_case("PersonalAccessToken") {
+            let token = parameter(&["AccessToken", "PersonalAccessToken"])?;
+            Ok(format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!(":{}", token))
+            ))
+        } else {
+            anyhow::bail!("Unsupported endpoint authorization scheme '{}'", scheme)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -176,6 +220,55 @@ mod tests {
         clear_env();
     }
 
+    fn authorization(scheme: &str, key: &str, token: &str) -> EndpointAuthorization {
+        EndpointAuthorization {
+            scheme: scheme.to_string(),
+            parameters: HashMap::from([(key.to_string(), token.to_string())]),
+        }
+    }
+
+    #[test]
+    fn bearer_header_format() {
+        assert_eq!(VssUtil::bearer_header("abc"), "Bearer abc");
+    }
+
+    #[test]
+    fn oauth_and_token_schemes_use_bearer() {
+        for scheme in ["OAuth", "oauth", "Token"] {
+            let auth = authorization(scheme, "AccessToken", "secret");
+            assert_eq!(
+                VssUtil::endpoint_auth_header(&auth).unwrap(),
+                "Bearer secret"
+            );
+        }
+        let auth = authorization("OAuth", "accesstoken", "secret");
+        assert_eq!(
+            VssUtil::endpoint_auth_header(&auth).unwrap(),
+            "Bearer secret"
+        );
+    }
+
+    #[test]
+    fn personal_access_token_uses_basic() {
+        // base64(":pat")
+        let expected = "Basic OnBhdA==";
+        for key in ["AccessToken", "PersonalAccessToken"] {
+            let auth = authorization("PersonalAccessToken", key, "pat");
+            assert_eq!(VssUtil::endpoint_auth_header(&auth).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn missing_token_or_unknown_scheme_fails() {
+        let auth = authorization("OAuth", "Other", "secret");
+        assert!(VssUtil::endpoint_auth_header(&auth).is_err());
+        let auth = authorization("OAuth", "AccessToken", "");
+        assert!(VssUtil::endpoint_auth_header(&auth).is_err());
+        let err = VssUtil::endpoint_auth_header(&authorization("Certificate", "AccessToken", "x"))
+            .unwrap_err();
+        assert!(err.to_string().contains("Certificate"), "{}", err);
+    }
+
     #[test]
     fn create_client_succeeds() {
         clear_env();
@@ -35,33 +35,11 @@ impl WhichUtil {
             return Ok(None);
         }
 
-        let path_segments: Vec<&str> = path_var.split(Self::path_separator()).collect();
-
-        for segment in &path_segments {
-            if segment.is_empty() {
-                continue;
-            }
-            let dir = Path::new(segment);
-            if !dir.is_dir() {
-                continue;
-            }
-
-            // On Windows, try PATHEXT extensions
-            #[cfg(target_os = "windows")]
-            {
-                if let Some(found) = Self::find_with_pathext(dir, command) {
-                    return Ok(Some(found));
-                }
-            }
-
-            // On Unix (or Windows fallback for already-extensioned commands)
-            #[cfg(not(target_os = "windows"))]
-            {
-                let candidate = dir.join(command);
-                if candidate.is_file() && Self::is_executable(&candidate) {
-                    return Ok(Some(candidate));
-                }
-            }
+        if let Some(found) = Self::search(command, &path_var, Self::pathext().as_deref())
+            .into_iter()
+            .next()
+        {
+            return Ok(Some(found));
         }
 
         if require {
@@ -72,42 +50,63 @@ impl WhichUtil {
         Ok(None)
     }
 
-    /// Find all occurrences of `command` on the system PATH.
+    /// Find all occurrences of `command` on the system PATH, in PATH order.
+    /// Useful for diagnostics when an unexpected binary shadows another.
     pub fn which_all(command: &str) -> Vec<std::path::PathBuf> {
         if command.is_empty() {
             return Vec::new();
         }
 
         let path_var = std::env::var("PATH").unwrap_or_default();
-        let path_segments: Vec<&str> = path_var.split(Self::path_separator()).collect();
-        let mut results = Vec::new();
+        Self::search(command, &path_var, Self::pathext().as_deref())
+    }
 
-        for segment in &path_segments {
-            if segment.is_empty() {
-                continue;
-            }
-            let dir = Path::new(segment);
-            if !dir.is_dir() {
-                continue;
-            }
+    /// Locate the first occurrence of `command` in `path_var`, a PATH value
+    /// other than the runner's own, such as a step's environment.
+    pub fn which_in(command: &str, path_var: &str) -> Option<std::path::PathBuf> {
+        if command.is_empty() {
+            return None;
+        }
+        Self::search(command, path_var, Self::pathext().as_deref())
+            .into_iter()
+            .next()
+    }
 
-            #[cfg(target_os = "windows")]
-            {
-                if let Some(found) = Self::find_with_pathext(dir, command) {
-                    results.push(found);
-                }
-            }
+    /// The PATHEXT extensions to try on Windows; `None` on other platforms,
+    /// where the execute permission decides instead.
+    fn pathext() -> Option<String> {
+        if cfg!(target_os = "windows") {
+            Some(
+                std::env::var("PATHEXT").unwrap_or_else(|_| {
+                    ".COM;.EXE;.BAT;.CMD;.VBS;.VBE;.JS;.JSE;.WSF;.WSH".to_string()
+                }),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Search each directory in `path_var` for `command`, returning every
+    /// match in PATH order.
+    fn search(command: &str, path_var: &str, pathext: Option<&str>) -> Vec<std::path::PathBuf> {
+        path_var
+            .split(Self::path_separator())
+            .filter(|segment| !segment.is_empty())
+            .map(Path::new)
+            .filter(|dir| dir.is_dir())
+            .filter_map(|dir| Self::find_in_dir(dir, command, pathext))
+            .collect()
+    }
 
-            #[cfg(not(target_os = "windows"))]
-            {
+    /// Look for `command` in a single directory.
+    fn find_in_dir(dir: &Path, command: &str, pathext: Option<&str>) -> Option<std::path::PathBuf> {
+        match pathext {
+            Some(pathext) => Self::find_with_pathext(dir, command, pathext),
+            None => {
                 let candidate = dir.join(command);
-                if candidate.is_file() && Self::is_executable(&candidate) {
-                    results.push(candidate);
-                }
+                (candidate.is_file() && Self::is_executable(&candidate)).then_some(candidate)
             }
         }
-
-        results
     }
 
     /// Returns the PATH separator for the current platform.
@@ -137,12 +136,8 @@ impl WhichUtil {
         path.is_file()
     }
 
-    /// On Windows, search for the command considering PATHEXT extensions.
-    #[cfg(target_os = "windows")]
-    fn find_with_pathext(dir: &Path, command: &str) -> Option<std::path::PathBuf> {
-        let pathext = std::env::var("PATHEXT")
-            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD;.VBS;.VBE;.JS;.JSE;.WSF;.WSH".to_string());
-
+    /// Search for the command considering PATHEXT extensions (Windows rules).
+    fn find_with_pathext(dir: &Path, command: &str, pathext: &str) -> Option<std::path::PathBuf> {
         let extensions: Vec<&str> = pathext.split(';').filter(|s| !s.is_empty()).collect();
 
         // Check if command already has a known extension
@@ -208,9 +203,82 @@ mod tests {
         }
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn which_in_searches_given_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tool = dir.path().join("my-tool");
+        std::fs::write(&tool, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let path_var = format!("/nonexistent:{}", dir.path().display());
+        assert_eq!(WhichUtil::which_in("my-tool", &path_var), Some(tool));
+        assert_eq!(WhichUtil::which_in("my-tool", "/nonexistent"), None);
+    }
+
     #[test]
     fn which_all_empty_for_missing() {
         let results = WhichUtil::which_all("nonexistent_command_xyz_123");
         assert!(results.is_empty());
     }
+
+    #[cfg(unix)]
+    fn write_file(dir: &Path, name: &str, mode: u32) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join(name);
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn search_skips_non_executable_files() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        write_file(first.path(), "tool", 0o644);
+        let executable = write_file(second.path(), "tool", 0o755);
+        write_file(second.path(), "data", 0o644);
+
+        let path_var = format!("{}:{}", first.path().display(), second.path().display());
+        assert_eq!(WhichUtil::search("tool", &path_var, None), vec![executable]);
+        assert!(WhichUtil::search("data", &path_var, None).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn search_returns_all_matches_in_path_order() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let a = write_file(first.path(), "tool", 0o755);
+        let b = write_file(second.path(), "tool", 0o700);
+
+        let path_var = format!(
+            "{}::/nonexistent_dir_xyz:{}",
+            second.path().display(),
+            first.path().display()
+        );
+        assert_eq!(WhichUtil::search("tool", &path_var, None), vec![b, a]);
+    }
+
+    #[test]
+    fn search_appends_pathext_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let node = dir.path().join("node.exe");
+        std::fs::write(&node, "").unwrap();
+        std::fs::write(dir.path().join("node"), "").unwrap();
+
+        let path_var = dir.path().to_string_lossy().to_string();
+        let pathext = Some(".com;.exe;.cmd");
+        assert_eq!(
+            WhichUtil::search("node", &path_var, pathext),
+            vec![node.clone()]
+        );
+        // A command that already carries a PATHEXT extension is used as-is
+        assert_eq!(WhichUtil::search("node.exe", &path_var, pathext).len(), 1);
+        assert!(WhichUtil::search("npm", &path_var, pathext).is_empty());
+    }
 }
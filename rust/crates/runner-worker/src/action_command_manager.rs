@@ -4,6 +4,8 @@
 use std::collections::{HashMap, HashSet};
 
 use runner_common::action_command::ActionCommand;
+use runner_common::constants;
+use runner_common::util::task_result_util::TaskResult;
 
 use crate::execution_context::ExecutionContext;
 
@@ -66,17 +68,14 @@ impl ActionCommandManager {
             return false;
         }
 
-        // Check for stop-commands token resume
-        if let Some(ref token) = self.stop_token.clone() {
-            // Look for the resume token: `::TOKEN::`
-            if let Some(cmd) = ActionCommand::try_parse_v2(line, &self.registered_commands_with_token(token)) {
-                if cmd.command == *token {
-                    self.stop_token = None;
-                    context.debug("Resuming workflow commands.");
-                    return true;
-                }
+        // While commands are stopped, every line is plain output (including
+        // `::cmd::` lines) until the resume token `::TOKEN::`.
+        if let Some(ref token) = self.stop_token {
+            if !token.is_empty() && line.trim() == format!("::{}::", token) {
+                self.stop_token = None;
+                context.debug("Resuming workflow commands.");
+                return true;
             }
-            // While stopped, don't process any commands
             return false;
         }
 
@@ -86,9 +85,11 @@ impl ActionCommandManager {
             None => return false,
         };
 
-        // Echo the command if echoing is enabled
-        if self.echo_on_action_command {
-            context.write_command(line);
+        // Echo the command if echoing is enabled. Values are masked, and
+        // add-mask is never echoed since its data is the secret itself.
+        if self.echo_on_action_command && cmd.command != "add-mask" {
+            let masked = cmd.to_masked_string(context.secret_masker());
+            context.write_command(&masked);
         }
 
         // Dispatch to the appropriate handler
@@ -96,13 +97,6 @@ impl ActionCommandManager {
         true
     }
 
-    /// Helper to build a registered commands set that includes the stop token.
-    fn registered_commands_with_token(&self, token: &str) -> HashSet<String> {
-        let mut set = HashSet::new();
-        set.insert(token.to_string());
-        set
-    }
-
     /// Dispatch a parsed command to its handler.
     fn dispatch_command(&mut self, context: &mut ExecutionContext, cmd: &ActionCommand) {
         match cmd.command.as_str() {
@@ -132,6 +126,10 @@ impl ActionCommandManager {
     // -----------------------------------------------------------------------
 
     fn handle_set_output(&self, context: &mut ExecutionContext, cmd: &ActionCommand) {
+        if !self.check_deprecated_command(context, cmd) {
+            return;
+        }
+
         let name = match cmd.properties.get("name") {
             Some(name) if !name.is_empty() => name.clone(),
             _ => {
@@ -146,6 +144,10 @@ impl ActionCommandManager {
     }
 
     fn handle_set_env(&self, context: &mut ExecutionContext, cmd: &ActionCommand) {
+        if !self.check_unsecure_command(context, cmd) {
+            return;
+        }
+
         let name = match cmd.properties.get("name") {
             Some(name) if !name.is_empty() => name.clone(),
             _ => {
@@ -171,6 +173,10 @@ impl ActionCommandManager {
     }
 
     fn handle_add_path(&self, context: &mut ExecutionContext, cmd: &ActionCommand) {
+        if !self.check_unsecure_command(context, cmd) {
+            return;
+        }
+
         let path = cmd.data.trim().to_string();
         if path.is_empty() {
             context.warning("'add-path' command requires a non-empty path.");
@@ -258,6 +264,10 @@ impl ActionCommandManager {
     }
 
     fn handle_save_state(&self, context: &mut ExecutionContext, cmd: &ActionCommand) {
+        if !self.check_deprecated_command(context, cmd) {
+            return;
+        }
+
         let name = match cmd.properties.get("name") {
             Some(name) if !name.is_empty() => name.clone(),
             _ => {
@@ -273,12 +283,23 @@ impl ActionCommandManager {
 
     fn handle_stop_commands(&mut self, context: &mut ExecutionContext, cmd: &ActionCommand) {
         let token = cmd.data.trim().to_string();
-        if token.is_empty() || token == "pause-logging" {
-            context.warning(
-                "The stop-commands token must not be empty or 'pause-logging'.",
-            );
+
+        // A token that is empty, 'pause-logging', or a command name would let
+        // the output that follows resume (or never stop) command processing.
+        let forbidden = token.is_empty()
+            || token.eq_ignore_ascii_case("pause-logging")
+            || self.registered_commands.contains(&token);
+        if forbidden
+            && !is_truthy(
+                context,
+                constants::variables::actions::ALLOW_UNSUPPORTED_STOP_COMMAND_TOKENS,
+            )
+        {
+            context.error(constants::UNSUPPORTED_STOP_COMMAND_TOKEN_DISABLED);
+            context.set_result(TaskResult::Failed);
             return;
         }
+
         context.debug(&format!("Stopping workflow commands until token: {}", token));
         self.stop_token = Some(token);
     }
@@ -287,6 +308,65 @@ impl ActionCommandManager {
     // Helpers
     // -----------------------------------------------------------------------
 
+    /// Gate the stdout `set-env` / `add-path` commands, which were removed for
+    /// security reasons. They are rejected with an error that fails the step
+    /// unless `ACTIONS_ALLOW_UNSECURE_COMMANDS` is `true`.
+    ///
+    /// Returns `true` if the command should still be processed.
+    fn check_unsecure_command(&self, context: &mut ExecutionContext, cmd: &ActionCommand) -> bool {
+        if is_truthy(
+            context,
+            constants::variables::actions::ALLOW_UNSUPPORTED_COMMANDS,
+        ) {
+            return true;
+        }
+
+        context
+            .error(&constants::UNSUPPORTED_COMMAND_MESSAGE_DISABLED.replace("{0}", &cmd.command));
+        context.set_result(TaskResult::Failed);
+        false
+    }
+
+    /// Apply the deprecation policy for the stdout `set-output` / `save-state`
+    /// commands, which have been replaced by environment files.
+    ///
+    /// Warns by default. When `DistributedTask.DisableStepOutputCommands` is
+    /// set the command is rejected with an error and fails the step, unless
+    /// the user opted back in with `ACTIONS_ALLOW_UNSECURE_COMMANDS`.
+    /// Either way the `UNSUPPORTED_COMMAND` telemetry is recorded.
+    ///
+    /// Returns `true` if the command should still be processed.
+    fn check_deprecated_command(
+        &self,
+        context: &mut ExecutionContext,
+        cmd: &ActionCommand,
+    ) -> bool {
+        {
+            let mut global = context.global_mut();
+            let entry = format!("{}: {}", constants::UNSUPPORTED_COMMAND, cmd.command);
+            if !global.job_telemetry.contains(&entry) {
+                global.job_telemetry.push(entry);
+            }
+        }
+
+        let disabled = is_truthy(context, constants::features::DISABLE_STEP_OUTPUT_COMMANDS);
+        let allowed = is_truthy(
+            context,
+            constants::variables::actions::ALLOW_UNSUPPORTED_COMMANDS,
+        );
+
+        if disabled && !allowed {
+            context.error(
+                &constants::UNSUPPORTED_COMMAND_MESSAGE_DISABLED.replace("{0}", &cmd.command),
+            );
+            context.set_result(TaskResult::Failed);
+            return false;
+        }
+
+        context.warning(&constants::UNSUPPORTED_COMMAND_MESSAGE.replace("{0}", &cmd.command));
+        true
+    }
+
     /// Format an annotation message with optional file/line/col properties.
     fn format_annotation_message(&self, cmd: &ActionCommand) -> String {
         let mut parts = Vec::new();
@@ -320,6 +400,19 @@ impl ActionCommandManager {
     }
 }
 
+/// Whether a job variable or environment variable is set to `true`.
+fn is_truthy(context: &ExecutionContext, name: &str) -> bool {
+    let global = context.global();
+    let value = global.variables.get(name).or_else(|| {
+        global
+            .environment_variables
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
+    });
+    value.is_some_and(|v| v.eq_ignore_ascii_case("true"))
+}
+
 impl Default for ActionCommandManager {
     fn default() -> Self {
         Self::new()
@@ -330,33 +423,15 @@ impl Default for ActionCommandManager {
 mod tests {
     use super::*;
     use crate::execution_context::{ExecutionContext, Global};
-    use crate::feature_manager::FeatureManager;
-    use crate::variables::Variables;
     use runner_common::host_context::HostContext;
-    use tokio_util::sync::CancellationToken;
 
     fn make_test_context() -> ExecutionContext {
         let host = HostContext::new("Test");
         let global = Global {
-            variables: Variables::new(),
-            endpoints: Vec::new(),
-            file_table: Vec::new(),
-            environment_variables: HashMap::new(),
-            job_display_name: "test".to_string(),
-            job_id: "j1".to_string(),
-            plan_id: "p1".to_string(),
-            timeline_id: "t1".to_string(),
-            pipeline_directory: "/tmp".to_string(),
             workspace_directory: "/tmp/w".to_string(),
             temp_directory: "/tmp/t".to_string(),
-            prepend_path: Vec::new(),
-            container_info: None,
-            service_containers: Vec::new(),
-            job_telemetry: Vec::new(),
-            environment_url: None,
-            cancel_token: CancellationToken::new(),
-            feature_manager: FeatureManager::empty(),
             write_debug: true,
+            ..Global::for_test("/tmp")
         };
         ExecutionContext::new_root(host, global, "test".to_string())
     }
@@ -413,4 +488,215 @@ mod tests {
         mgr.try_process_command(&mut ctx, "::echo::off");
         assert!(!mgr.echo_on_action_command);
     }
+
+    #[test]
+    fn test_echoed_command_masks_secrets() {
+        let mut mgr = ActionCommandManager::new();
+        let mut ctx = make_test_context();
+        ctx.secret_masker().add_value("hunter2token");
+
+        mgr.try_process_command(&mut ctx, "::echo::on");
+        mgr.try_process_command(&mut ctx, "::set-output name=token::hunter2token");
+        mgr.try_process_command(&mut ctx, "::add-mask::brandnewsecret");
+
+        let lines = ctx.log_lines();
+        assert!(lines
+            .iter()
+            .any(|l| l == "##[command]::set-output name=token::***"));
+        assert!(!lines
+            .iter()
+            .any(|l| l.contains("hunter2token") || l.contains("brandnewsecret")));
+    }
+
+    #[test]
+    fn test_set_output_warns_as_deprecated() {
+        let mut mgr = ActionCommandManager::new();
+        let mut ctx = make_test_context();
+        mgr.try_process_command(&mut ctx, "::set-output name=result::hello");
+        mgr.try_process_command(&mut ctx, "::save-state name=pid::42");
+
+        assert_eq!(ctx.outputs.get("result"), Some(&"hello".to_string()));
+        assert_eq!(ctx.outputs.get("STATE_pid"), Some(&"42".to_string()));
+        assert!(ctx.result().is_none());
+        assert!(ctx
+            .log_lines()
+            .iter()
+            .any(|l| l.starts_with("##[warning]The `set-output` command is deprecated")));
+        assert!(ctx
+            .log_lines()
+            .iter()
+            .any(|l| l.starts_with("##[warning]The `save-state` command is deprecated")));
+        assert_eq!(
+            ctx.global().job_telemetry,
+            vec![
+                "UNSUPPORTED_COMMAND: set-output".to_string(),
+                "UNSUPPORTED_COMMAND: save-state".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_output_disabled() {
+        let mut mgr = ActionCommandManager::new();
+        let mut ctx = make_test_context();
+        ctx.set_variable(
+            constants::features::DISABLE_STEP_OUTPUT_COMMANDS,
+            "true",
+            false,
+        );
+
+        mgr.try_process_command(&mut ctx, "::set-output name=result::hello");
+        mgr.try_process_command(&mut ctx, "::set-output name=other::world");
+
+        assert!(ctx.outputs.is_empty());
+        assert_eq!(ctx.result(), Some(TaskResult::Failed));
+        assert!(ctx
+            .log_lines()
+            .iter()
+            .any(|l| l.starts_with("##[error]The `set-output` command is disabled")));
+        // Telemetry is recorded once per command
+        assert_eq!(
+            ctx.global().job_telemetry,
+            vec!["UNSUPPORTED_COMMAND: set-output".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_set_output_disabled_with_opt_in() {
+        let mut mgr = ActionCommandManager::new();
+        let mut ctx = make_test_context();
+        ctx.set_variable(
+            constants::features::DISABLE_STEP_OUTPUT_COMMANDS,
+            "true",
+            false,
+        );
+        ctx.global_mut().environment_variables.insert(
+            constants::variables::actions::ALLOW_UNSUPPORTED_COMMANDS.to_string(),
+            "true".to_string(),
+        );
+
+        mgr.try_process_command(&mut ctx, "::set-output name=result::hello");
+
+        assert_eq!(ctx.outputs.get("result"), Some(&"hello".to_string()));
+        assert!(ctx.result().is_none());
+    }
+
+    #[test]
+    fn test_set_env_and_add_path_blocked_by_default() {
+        let mut mgr = ActionCommandManager::new();
+        let mut ctx = make_test_context();
+
+        mgr.try_process_command(&mut ctx, "::set-env name=MY_VAR::value");
+        mgr.try_process_command(&mut ctx, "::add-path::/opt/tool/bin");
+
+        assert!(!ctx.global().environment_variables.contains_key("MY_VAR"));
+        assert!(ctx.global().prepend_path.is_empty());
+        assert_eq!(ctx.result(), Some(TaskResult::Failed));
+        for command in ["set-env", "add-path"] {
+            let expected = format!("##[error]The `{}` command is disabled", command);
+            assert!(ctx.log_lines().iter().any(|l| l.starts_with(&expected)));
+        }
+    }
+
+    #[test]
+    fn test_set_env_and_add_path_allowed_when_opted_in() {
+        let mut mgr = ActionCommandManager::new();
+        let mut ctx = make_test_context();
+        ctx.global_mut().environment_variables.insert(
+            constants::variables::actions::ALLOW_UNSUPPORTED_COMMANDS.to_string(),
+            "true".to_string(),
+        );
+
+        mgr.try_process_command(&mut ctx, "::set-env name=MY_VAR::value");
+        mgr.try_process_command(&mut ctx, "::add-path::/opt/tool/bin");
+
+        assert_eq!(
+            ctx.global().environment_variables.get("MY_VAR"),
+            Some(&"value".to_string())
+        );
+        assert_eq!(ctx.global().prepend_path, vec!["/opt/tool/bin".to_string()]);
+        assert!(ctx.result().is_none());
+    }
+
+    #[test]
+    fn test_opt_in_accepts_only_true() {
+        for (value, allowed) in [("TRUE", true), ("1", false), ("yes", false)] {
+            let mut mgr = ActionCommandManager::new();
+            let mut ctx = make_test_context();
+            ctx.global_mut().environment_variables.insert(
+                constants::variables::actions::ALLOW_UNSUPPORTED_COMMANDS.to_string(),
+                value.to_string(),
+            );
+
+            mgr.try_process_command(&mut ctx, "::add-path::/opt/tool/bin");
+
+            assert_eq!(
+                ctx.global().prepend_path.len(),
+                usize::from(allowed),
+                "value {:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_stopped_commands_are_plain_output() {
+        let mut mgr = ActionCommandManager::new();
+        let mut ctx = make_test_context();
+
+        assert!(mgr.try_process_command(&mut ctx, "::stop-commands::resume-7f3a"));
+        assert!(!mgr.try_process_command(&mut ctx, "::error::not an annotation"));
+        assert!(!mgr.try_process_command(&mut ctx, "::set-output name=x::y"));
+        // A different token does not resume
+        assert!(!mgr.try_process_command(&mut ctx, "::other-token::"));
+        assert!(ctx.result().is_none());
+        assert!(ctx.outputs.is_empty());
+        assert!(!ctx.log_lines().iter().any(|l| l.starts_with("##[error]")));
+
+        assert!(mgr.try_process_command(&mut ctx, "::resume-7f3a::"));
+        assert!(mgr.stop_token.is_none());
+        assert!(mgr.try_process_command(&mut ctx, "::error::real annotation"));
+        assert!(ctx
+            .log_lines()
+            .iter()
+            .any(|l| l == "##[error]real annotation"));
+    }
+
+    #[test]
+    fn test_stop_commands_rejects_forbidden_tokens() {
+        for token in ["", "pause-logging", "PAUSE-LOGGING", "set-env"] {
+            let mut mgr = ActionCommandManager::new();
+            let mut ctx = make_test_context();
+
+            mgr.try_process_command(&mut ctx, &format!("::stop-commands::{}", token));
+
+            assert!(mgr.stop_token.is_none(), "token {:?}", token);
+            assert_eq!(ctx.result(), Some(TaskResult::Failed));
+            let expected = format!(
+                "##[error]{}",
+                constants::UNSUPPORTED_STOP_COMMAND_TOKEN_DISABLED
+            );
+            assert!(ctx.log_lines().contains(&expected));
+
+            // Commands keep being processed
+            assert!(mgr.try_process_command(&mut ctx, "::debug::still processing"));
+        }
+    }
+
+    #[test]
+    fn test_stop_commands_forbidden_token_allowed_when_opted_in() {
+        let mut mgr = ActionCommandManager::new();
+        let mut ctx = make_test_context();
+        ctx.global_mut().environment_variables.insert(
+            constants::variables::actions::ALLOW_UNSUPPORTED_STOP_COMMAND_TOKENS.to_string(),
+            "true".to_string(),
+        );
+
+        mgr.try_process_command(&mut ctx, "::stop-commands::pause-logging");
+
+        assert_eq!(mgr.stop_token.as_deref(), Some("pause-logging"));
+        assert!(ctx.result().is_none());
+        assert!(mgr.try_process_command(&mut ctx, "::pause-logging::"));
+        assert!(mgr.stop_token.is_none());
+    }
 }
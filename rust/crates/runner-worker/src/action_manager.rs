@@ -1,30 +1,220 @@
 // ActionManager mapping `ActionManager.cs`.
 // Downloads actions from GitHub, caches resolved actions, and handles container images.
+//
+// Three kinds of reference arrive in the job message:
+// - `repository` references to another repository (`owner/repo@ref`), downloaded
+//   into the actions directory and cached there by resolved commit
+// - `repository` references with `repositoryType: "self"` (`uses: ./path`),
+//   which point into the job's own workspace and are used in place
+// - `containerRegistry` references (`uses: docker://image`), pulled by image
 
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use runner_common::constants;
 use runner_common::host_context::HostContext;
 
+use crate::container::docker_command_manager::DockerCommandManager;
 use crate::execution_context::ExecutionContext;
 use crate::worker::{ActionReference, JobStep};
 
+/// Where a resolved action lives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolvedAction {
+    /// An action downloaded from a repository into the actions directory,
+    /// pinned to the commit its ref resolved to.
+    Repository { directory: String, sha: String },
+    /// An action in the job's own workspace. The directory may not exist
+    /// until a checkout step has run.
+    Local { directory: String },
+    /// A container image run directly as the action.
+    ContainerImage { image: String },
+}
+
+impl ResolvedAction {
+    /// The local directory holding the action, if it has one.
+    pub fn directory(&self) -> Option<&str> {
+        match self {
+            ResolvedAction::Repository { directory, .. } | ResolvedAction::Local { directory } => {
+                Some(directory)
+            }
+            ResolvedAction::ContainerImage { .. } => None,
+        }
+    }
+}
+
 /// Result of preparing actions.
 #[derive(Debug)]
 pub struct PrepareResult {
-    /// Map of action reference key → resolved local directory.
-    pub resolved_actions: HashMap<String, String>,
+    /// Map of action reference key → resolved action.
+    pub resolved_actions: HashMap<String, ResolvedAction>,
 
     /// Any warnings generated during resolution.
     pub warnings: Vec<String>,
 }
 
+/// Looks up and downloads action repositories.
+#[async_trait]
+pub trait ActionDownloader: Send + Sync {
+    /// Resolve `git_ref` of `owner/repo` to a full commit SHA, using the
+    /// REST API at `api_url`.
+    async fn resolve_sha(
+        &self,
+        api_url: &str,
+        owner: &str,
+        repo: &str,
+        git_ref: &str,
+        token: Option<&str>,
+    ) -> Result<String>;
+
+    /// Download the tarball of `owner/repo` at `sha` to `destination`, using
+    /// the REST API at `api_url`.
+    async fn download_tarball(
+        &self,
+        api_url: &str,
+        owner: &str,
+        repo: &str,
+        sha: &str,
+        destination: &Path,
+        token: Option<&str>,
+    ) -> Result<()>;
+}
+
+/// Downloads actions from the GitHub REST API.
+pub struct GitHubActionDownloader {
+    client: reqwest::Client,
+}
+
+impl GitHubActionDownloader {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn get(&self, url: &str, token: Option<&str>) -> reqwest::RequestBuilder {
+        let mut request = self.client.get(url);
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        request.header("User-Agent", "GitHubActionsRunner")
+    }
+}
+
+impl Default for GitHubActionDownloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ActionDownloader for GitHubActionDownloader {
+    async fn resolve_sha(
+        &self,
+        api_url: &str,
+        owner: &str,
+        repo: &str,
+        git_ref: &str,
+        token: Option<&str>,
+    ) -> Result<String> {
+        let url = format!(
+            "{}/repos/{}/{}/commits/{}",
+            api_url.trim_end_matches('/'),
+            owner,
+            repo,
+            git_ref
+        );
+        let sha = self
+            .get(&url, token)
+            .header("Accept", "application/vnd.github.sha")
+            .send()
+            .await
+            .context("HTTP request failed")?
+            .error_for_status()
+            .context("HTTP response error")?
+            .text()
+            .await
+            .context("Failed to read response body")?;
+
+        let sha = sha.trim();
+        if !is_commit_sha(sha) {
+            anyhow::bail!("Unexpected commit SHA '{}'", sha);
+        }
+        Ok(sha.to_lowercase())
+    }
+
+    async fn download_tarball(
+        &self,
+        api_url: &str,
+        owner: &str,
+        repo: &str,
+        sha: &str,
+        destination: &Path,
+        token: Option<&str>,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/repos/{}/{}/tarball/{}",
+            api_url.trim_end_matches('/'),
+            owner,
+            repo,
+            sha
+        );
+        let bytes = self
+            .get(&url, token)
+            .send()
+            .await
+            .context("HTTP request failed")?
+            .error_for_status()
+            .context("HTTP response error")?
+            .bytes()
+            .await
+            .context("Failed to read response body")?;
+
+        std::fs::write(destination, &bytes)
+            .with_context(|| format!("Failed to write archive to {:?}", destination))?;
+
+        Ok(())
+    }
+}
+
+/// Whether the job asked for a warning on actions referenced by tag or
+/// branch rather than by commit.
+fn warn_on_mutable_refs(context: &ExecutionContext) -> bool {
+    let name = constants::variables::actions::WARN_ON_MUTABLE_ACTION_REFS;
+    context
+        .global()
+        .variables
+        .get(name)
+        .or_else(|| std::env::var(name).ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("true") || v == "1")
+}
+
+/// The REST API URL for the job's GitHub server, from the github context,
+/// so actions download from GHES rather than github.com there.
+fn github_api_url(context: &ExecutionContext) -> String {
+    context
+        .github_context()
+        .map(|github| github.api_url.clone())
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| {
+            let github_com = url::Url::parse("https://github.com").expect("valid URL");
+            runner_sdk::UrlUtil::api_url(&github_com, "")
+        })
+}
+
+/// Whether `git_ref` is a full 40-character commit SHA.
+fn is_commit_sha(git_ref: &str) -> bool {
+    git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
 /// Manages action download, caching, and resolution.
 pub struct ActionManager {
-    /// Cache of already-resolved actions (key → local path).
-    cache: HashMap<String, String>,
+    /// Cache of already-resolved actions.
+    cache: HashMap<String, ResolvedAction>,
+    downloader: Arc<dyn ActionDownloader>,
 }
 
 impl ActionManager {
@@ -32,13 +222,21 @@ impl ActionManager {
     pub fn new() -> Self {
         Self {
             cache: HashMap::new(),
+            downloader: Arc::new(GitHubActionDownloader::new()),
         }
     }
 
+    /// Use a different downloader for repository actions.
+    pub fn with_downloader(mut self, downloader: Arc<dyn ActionDownloader>) -> Self {
+        self.downloader = downloader;
+        self
+    }
+
     /// Prepare all actions referenced by steps.
     ///
     /// Downloads action repositories from GitHub, extracts them, and resolves
-    /// their local paths in the runner's actions directory.
+    /// their local paths in the runner's actions directory. Local actions are
+    /// resolved against the workspace and container images are pulled.
     pub async fn prepare_actions_async(
         &mut self,
         context: &mut ExecutionContext,
@@ -55,13 +253,13 @@ impl ActionManager {
 
         for step in steps {
             if let Some(action_ref) = step.action_reference() {
-                let cache_key = self.build_cache_key(&action_ref);
+                let cache_key = Self::build_cache_key(&action_ref);
 
                 // Check if already resolved
-                if let Some(cached_path) = self.cache.get(&cache_key) {
+                if let Some(cached) = self.cache.get(&cache_key) {
                     result
                         .resolved_actions
-                        .insert(cache_key.clone(), cached_path.clone());
+                        .insert(cache_key.clone(), cached.clone());
                     continue;
                 }
 
@@ -70,16 +268,13 @@ impl ActionManager {
                     .resolve_action(context, &action_ref, &actions_dir)
                     .await
                 {
-                    Ok(resolved_path) => {
+                    Ok(resolved) => {
                         context.debug(&format!(
-                            "Resolved action '{}' to '{}'",
-                            cache_key, resolved_path
+                            "Resolved action '{}' to {:?}",
+                            cache_key, resolved
                         ));
-                        self.cache
-                            .insert(cache_key.clone(), resolved_path.clone());
-                        result
-                            .resolved_actions
-                            .insert(cache_key, resolved_path);
+                        self.cache.insert(cache_key.clone(), resolved.clone());
+                        result.resolved_actions.insert(cache_key, resolved);
                     }
                     Err(e) => {
                         let warning = format!(
@@ -93,11 +288,75 @@ impl ActionManager {
             }
         }
 
+        self.pull_action_images(context, &mut result).await;
+
         Ok(result)
     }
 
-    /// Build a cache key for an action reference.
-    fn build_cache_key(&self, action_ref: &ActionReference) -> String {
+    /// Resolve one action reference, e.g. a `uses` step of a composite action.
+    pub async fn resolve_async(
+        &mut self,
+        context: &mut ExecutionContext,
+        action_ref: &ActionReference,
+    ) -> Result<ResolvedAction> {
+        let cache_key = Self::build_cache_key(action_ref);
+        if let Some(cached) = self.cache.get(&cache_key) {
+            return Ok(cached.clone());
+        }
+
+        let actions_dir = context
+            .host_context()
+            .get_directory(constants::WellKnownDirectory::Actions);
+        let resolved = self
+            .resolve_action(context, action_ref, &actions_dir)
+            .await
+            .with_context(|| format!("Failed to resolve action '{}'", cache_key))?;
+        self.cache.insert(cache_key, resolved.clone());
+        Ok(resolved)
+    }
+
+    /// Pull the images of container actions up front, so a missing image
+    /// fails fast. A failed pull is only a warning; the container handler
+    /// pulls again when the step runs.
+    async fn pull_action_images(&self, context: &mut ExecutionContext, result: &mut PrepareResult) {
+        let mut images: Vec<String> = result
+            .resolved_actions
+            .values()
+            .filter_map(|resolved| match resolved {
+                ResolvedAction::ContainerImage { image } => Some(image.clone()),
+                _ => None,
+            })
+            .collect();
+        if images.is_empty() {
+            return;
+        }
+        images.sort();
+        images.dedup();
+
+        if !cfg!(target_os = "linux") {
+            context.debug("Skipping container action image pull: containers require Linux");
+            return;
+        }
+
+        let docker = DockerCommandManager::new();
+        for image in images {
+            context.info(&format!("Pulling container action image '{}'", image));
+            if let Err(e) = docker.pull_image(&image, context.cancel_token()).await {
+                let warning = format!("Failed to pull image '{}': {:#}", image, e);
+                context.warning(&warning);
+                result.warnings.push(warning);
+            }
+        }
+    }
+
+    /// Build the key under which an action reference is resolved.
+    pub fn build_cache_key(action_ref: &ActionReference) -> String {
+        if Self::is_container_reference(action_ref) {
+            return format!("docker://{}", Self::container_image(action_ref));
+        }
+        if Self::is_local_reference(action_ref) {
+            return format!("./{}", action_ref.path.trim_start_matches("./"));
+        }
         if action_ref.path.is_empty() {
             format!("{}@{}", action_ref.name, action_ref.git_ref)
         } else {
@@ -105,156 +364,239 @@ impl ActionManager {
         }
     }
 
-    /// Resolve a single action reference to a local directory.
+    /// Whether the reference names a container image (`docker://image`).
+    fn is_container_reference(action_ref: &ActionReference) -> bool {
+        action_ref
+            .ref_type
+            .eq_ignore_ascii_case("containerRegistry")
+            || action_ref.repository_type == "Container"
+            || action_ref.name.starts_with("docker://")
+    }
+
+    /// Whether the reference points into the job's own repository (`./path`).
+    fn is_local_reference(action_ref: &ActionReference) -> bool {
+        action_ref.repository_type.eq_ignore_ascii_case("self")
+    }
+
+    /// The image of a container reference, without the `docker://` prefix.
+    fn container_image(action_ref: &ActionReference) -> String {
+        let image = action_ref
+            .extra
+            .get("image")
+            .and_then(|image| image.as_str())
+            .filter(|image| !image.is_empty())
+            .unwrap_or(&action_ref.name);
+        image.trim_start_matches("docker://").to_string()
+    }
+
+    /// Resolve a single action reference.
     async fn resolve_action(
         &self,
         context: &mut ExecutionContext,
         action_ref: &ActionReference,
         actions_dir: &Path,
-    ) -> Result<String> {
+    ) -> Result<ResolvedAction> {
+        if Self::is_container_reference(action_ref) {
+            return self.resolve_container_action(context, action_ref);
+        }
+        if Self::is_local_reference(action_ref) {
+            return self.resolve_local_action(context, action_ref);
+        }
         match action_ref.repository_type.as_str() {
-            "GitHub" | "" => self.resolve_github_action(context, action_ref, actions_dir).await,
-            "Container" => self.resolve_container_action(context, action_ref),
+            "GitHub" | "" => {
+                self.resolve_github_action(context, action_ref, actions_dir)
+                    .await
+            }
             other => {
                 anyhow::bail!("Unsupported repository type: {}", other);
             }
         }
     }
 
+    /// Resolve an action in the job's own workspace.
+    ///
+    /// The directory is not checked here: the repository is usually checked
+    /// out by an earlier step of the same job.
+    fn resolve_local_action(
+        &self,
+        context: &mut ExecutionContext,
+        action_ref: &ActionReference,
+    ) -> Result<ResolvedAction> {
+        let workspace = context.global().workspace_directory.clone();
+        if workspace.is_empty() {
+            anyhow::bail!(
+                "Cannot resolve local action '{}': the workspace directory is not set",
+                action_ref.path
+            );
+        }
+        let relative = action_ref.path.trim_start_matches("./");
+        let directory = if relative.is_empty() {
+            PathBuf::from(&workspace)
+        } else {
+            Path::new(&workspace).join(relative)
+        };
+        context.debug(&format!("Local action: {:?}", directory));
+        Ok(ResolvedAction::Local {
+            directory: directory.to_string_lossy().to_string(),
+        })
+    }
+
     /// Resolve a GitHub-hosted action.
+    ///
+    /// Tags and branches are resolved to a commit first and the resolution is
+    /// written to the job log, so the log records exactly which code ran.
+    /// Downloads are cached in the actions directory by commit, as
+    /// `owner/repo/<sha>`, so every ref that points at the same commit shares
+    /// one copy across steps and jobs. When a ref moves to a new commit the
+    /// new commit is downloaded; the old copy is never served for it again.
     async fn resolve_github_action(
         &self,
         context: &mut ExecutionContext,
         action_ref: &ActionReference,
         actions_dir: &Path,
-    ) -> Result<String> {
-        // Build the target directory
-        let parts: Vec<&str> = action_ref.name.splitn(2, '/').collect();
-        if parts.len() < 2 {
-            anyhow::bail!("Invalid action reference: {}", action_ref.name);
-        }
-
-        let owner = parts[0];
-        let repo = parts[1];
+    ) -> Result<ResolvedAction> {
+        let (owner, repo) = action_ref
+            .name
+            .split_once('/')
+            .with_context(|| format!("Invalid action reference: {}", action_ref.name))?;
         let git_ref = &action_ref.git_ref;
+        let token = self.find_access_token(context);
+        let api_url = github_api_url(context);
 
-        let action_dir = actions_dir
-            .join(owner)
-            .join(repo)
-            .join(git_ref);
+        // A ref that is already a commit needs no lookup
+        let sha = if is_commit_sha(git_ref) {
+            git_ref.to_lowercase()
+        } else {
+            let sha = self
+                .downloader
+                .resolve_sha(&api_url, owner, repo, git_ref, token.as_deref())
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to resolve '{}/{}@{}' to a commit",
+                        owner, repo, git_ref
+                    )
+                })?;
+            context.info(&format!(
+                "Resolved action '{}/{}@{}' to commit {}",
+                owner, repo, git_ref, sha
+            ));
+            if warn_on_mutable_refs(context) {
+                context.warning(&format!(
+                    "Action '{}/{}@{}' uses the mutable ref '{}'. Pin it to commit {} so the \
+                     same code runs every time.",
+                    owner, repo, git_ref, git_ref, sha
+                ));
+            }
+            sha
+        };
 
-        // Check if already cached on disk
+        let action_dir = actions_dir.join(owner).join(repo).join(&sha);
         if action_dir.exists() {
-            let sub_path = if action_ref.path.is_empty() {
-                action_dir.clone()
-            } else {
-                action_dir.join(&action_ref.path)
-            };
-            return Ok(sub_path.to_string_lossy().to_string());
-        }
-
-        // Check for action archive cache
-        let archive_cache = std::env::var(constants::variables::agent::ACTION_ARCHIVE_CACHE_DIRECTORY).ok();
-        if let Some(ref cache_dir) = archive_cache {
-            let cache_path = PathBuf::from(cache_dir)
-                .join(format!("{}_{}_{}.tar.gz", owner, repo, git_ref));
-
-            if cache_path.exists() {
-                context.info(&format!("Using cached action archive: {:?}", cache_path));
-                self.extract_archive(&cache_path, &action_dir)?;
-
-                let sub_path = if action_ref.path.is_empty() {
-                    action_dir.clone()
-                } else {
-                    action_dir.join(&action_ref.path)
-                };
-                return Ok(sub_path.to_string_lossy().to_string());
-            }
+            context.debug(&format!(
+                "Using cached action '{}/{}@{}' ({})",
+                owner, repo, git_ref, sha
+            ));
+        } else {
+            context.info(&format!(
+                "Downloading action '{}/{}@{}' ({})...",
+                owner, repo, git_ref, sha
+            ));
+            self.download_action(
+                context,
+                &api_url,
+                owner,
+                repo,
+                &sha,
+                &action_dir,
+                token.as_deref(),
+            )
+            .await?;
         }
 
-        // Download from GitHub
-        context.info(&format!(
-            "Downloading action '{}/{}@{}'...",
-            owner, repo, git_ref
-        ));
-
-        let download_url = format!(
-            "https://api.github.com/repos/{}/{}/tarball/{}",
-            owner, repo, git_ref
-        );
-
-        // Find the access token from endpoints
-        let token = self.find_access_token(context);
-
-        let temp_dir = context.global().temp_directory.clone();
-        let archive_path = PathBuf::from(&temp_dir)
-            .join(format!("action_{}.tar.gz", uuid::Uuid::new_v4().as_simple()));
-
-        self.download_archive(&download_url, &archive_path, token.as_deref())
-            .await
-            .context("Failed to download action archive")?;
-
-        // Extract
-        self.extract_archive(&archive_path, &action_dir)?;
-
-        // Clean up temp archive
-        let _ = std::fs::remove_file(&archive_path);
-
         let sub_path = if action_ref.path.is_empty() {
-            action_dir.clone()
+            action_dir
         } else {
             action_dir.join(&action_ref.path)
         };
-
-        Ok(sub_path.to_string_lossy().to_string())
+        Ok(ResolvedAction::Repository {
+            directory: sub_path.to_string_lossy().to_string(),
+            sha,
+        })
     }
 
-    /// Resolve a container action (just returns the image reference).
-    fn resolve_container_action(
+    /// Download `owner/repo` at `sha` and extract it into `action_dir`.
+    ///
+    /// The archive is extracted into a staging directory that is renamed into
+    /// place, so an interrupted download never leaves a partial cache entry.
+    #[allow(clippy::too_many_arguments)]
+    async fn download_action(
         &self,
         context: &mut ExecutionContext,
-        action_ref: &ActionReference,
-    ) -> Result<String> {
-        context.debug(&format!("Container action: {}", action_ref.name));
-        Ok(action_ref.name.clone())
-    }
-
-    /// Download an archive file from a URL.
-    async fn download_archive(
-        &self,
-        url: &str,
-        destination: &Path,
+        api_url: &str,
+        owner: &str,
+        repo: &str,
+        sha: &str,
+        action_dir: &Path,
         token: Option<&str>,
     ) -> Result<()> {
-        let client = reqwest::Client::new();
-        let mut request = client.get(url);
-
-        if let Some(token) = token {
-            request = request.header("Authorization", format!("token {}", token));
+        if let Some(parent) = action_dir.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {:?}", parent))?;
         }
+        let id = uuid::Uuid::new_v4().as_simple().to_string();
+        let staging = action_dir.with_file_name(format!("{}.{}.tmp", sha, id));
 
-        request = request.header("User-Agent", "GitHubActionsRunner");
-
-        let response = request
-            .send()
-            .await
-            .context("HTTP request failed")?
-            .error_for_status()
-            .context("HTTP response error")?;
-
-        let bytes = response.bytes().await.context("Failed to read response body")?;
+        // Check for action archive cache
+        let cached_archive =
+            std::env::var(constants::variables::agent::ACTION_ARCHIVE_CACHE_DIRECTORY)
+                .ok()
+                .map(|dir| PathBuf::from(dir).join(format!("{}_{}_{}.tar.gz", owner, repo, sha)))
+                .filter(|path| path.exists());
 
-        // Ensure parent directory exists
-        if let Some(parent) = destination.parent() {
-            std::fs::create_dir_all(parent)?;
+        let extracted = if let Some(cache_path) = cached_archive {
+            context.info(&format!("Using cached action archive: {:?}", cache_path));
+            self.extract_archive(&cache_path, &staging)
+        } else {
+            let archive_path = action_dir.with_file_name(format!("{}.{}.tar.gz", sha, id));
+            self.downloader
+                .download_tarball(api_url, owner, repo, sha, &archive_path, token)
+                .await
+                .context("Failed to download action archive")?;
+            let extracted = self.extract_archive(&archive_path, &staging);
+            let _ = std::fs::remove_file(&archive_path);
+            extracted
+        };
+        if let Err(e) = extracted {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(e);
         }
 
-        std::fs::write(destination, &bytes)
-            .with_context(|| format!("Failed to write archive to {:?}", destination))?;
-
+        if let Err(e) = std::fs::rename(&staging, action_dir) {
+            let _ = std::fs::remove_dir_all(&staging);
+            // Another worker may have finished the same download first
+            if !action_dir.exists() {
+                return Err(e)
+                    .with_context(|| format!("Failed to move action into {:?}", action_dir));
+            }
+        }
         Ok(())
     }
 
+    /// Resolve a container action to its image.
+    fn resolve_container_action(
+        &self,
+        context: &mut ExecutionContext,
+        action_ref: &ActionReference,
+    ) -> Result<ResolvedAction> {
+        let image = Self::container_image(action_ref);
+        if image.is_empty() {
+            anyhow::bail!("Container action reference has no image");
+        }
+        context.debug(&format!("Container action: {}", image));
+        Ok(ResolvedAction::ContainerImage { image })
+    }
+
     /// Extract a tar.gz archive to a destination directory.
     fn extract_archive(&self, archive: &Path, destination: &Path) -> Result<()> {
         use flate2::read::GzDecoder;
@@ -319,10 +661,10 @@ impl Default for ActionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_build_cache_key() {
-        let mgr = ActionManager::new();
         let action_ref = ActionReference {
             name: "actions/checkout".to_string(),
             git_ref: "v4".to_string(),
@@ -331,12 +673,14 @@ mod tests {
             ref_type: String::new(),
             extra: Default::default(),
         };
-        assert_eq!(mgr.build_cache_key(&action_ref), "actions/checkout@v4");
+        assert_eq!(
+            ActionManager::build_cache_key(&action_ref),
+            "actions/checkout@v4"
+        );
     }
 
     #[test]
     fn test_build_cache_key_with_path() {
-        let mgr = ActionManager::new();
         let action_ref = ActionReference {
             name: "actions/runner".to_string(),
             git_ref: "main".to_string(),
@@ -346,8 +690,367 @@ mod tests {
             extra: Default::default(),
         };
         assert_eq!(
-            mgr.build_cache_key(&action_ref),
+            ActionManager::build_cache_key(&action_ref),
             "actions/runner/sub/action@main"
         );
     }
+
+    fn reference(json: serde_json::Value) -> ActionReference {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn test_context(workspace: &str) -> ExecutionContext {
+        use crate::execution_context::Global;
+
+        let global = Global {
+            workspace_directory: workspace.to_string(),
+            ..Global::for_test("")
+        };
+        ExecutionContext::new_root(HostContext::new("Test"), global, "test".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_container_reference() {
+        let mgr = ActionManager::new();
+        let mut context = test_context("/work/repo");
+        let actions_dir = PathBuf::from("/nonexistent/actions");
+
+        let by_type = reference(serde_json::json!({
+            "type": "containerRegistry",
+            "image": "alpine:3.19"
+        }));
+        assert_eq!(
+            mgr.resolve_action(&mut context, &by_type, &actions_dir)
+                .await
+                .unwrap(),
+            ResolvedAction::ContainerImage {
+                image: "alpine:3.19".to_string()
+            }
+        );
+        assert_eq!(
+            ActionManager::build_cache_key(&by_type),
+            "docker://alpine:3.19"
+        );
+
+        let by_name = reference(serde_json::json!({"name": "docker://ghcr.io/org/tool:1"}));
+        let resolved = mgr
+            .resolve_action(&mut context, &by_name, &actions_dir)
+            .await
+            .unwrap();
+        assert_eq!(
+            resolved,
+            ResolvedAction::ContainerImage {
+                image: "ghcr.io/org/tool:1".to_string()
+            }
+        );
+        assert_eq!(resolved.directory(), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_local_reference() {
+        let mgr = ActionManager::new();
+        let mut context = test_context("/work/repo");
+        let local = reference(serde_json::json!({
+            "type": "repository",
+            "repositoryType": "self",
+            "path": "./.github/actions/setup"
+        }));
+
+        let resolved = mgr
+            .resolve_action(&mut context, &local, Path::new("/nonexistent/actions"))
+            .await
+            .unwrap();
+        let expected = Path::new("/work/repo").join(".github/actions/setup");
+        assert_eq!(
+            resolved,
+            ResolvedAction::Local {
+                directory: expected.to_string_lossy().to_string()
+            }
+        );
+        assert_eq!(
+            ActionManager::build_cache_key(&local),
+            "./.github/actions/setup"
+        );
+
+        let mut no_workspace = test_context("");
+        assert!(mgr
+            .resolve_action(&mut no_workspace, &local, Path::new("/nonexistent/actions"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_pinned_reference_from_actions_directory() {
+        let mgr = ActionManager::new();
+        let mut context = test_context("/work/repo");
+        let actions_dir = tempfile::tempdir().unwrap();
+        let sha = "8f4b7f84864484a7bf31766abe9204da3cbe65b3";
+        std::fs::create_dir_all(actions_dir.path().join("actions/checkout").join(sha)).unwrap();
+
+        // A commit ref is looked up on disk without contacting GitHub
+        let remote = reference(serde_json::json!({
+            "type": "repository",
+            "repositoryType": "GitHub",
+            "name": "actions/checkout",
+            "ref": sha
+        }));
+        let resolved = mgr
+            .resolve_action(&mut context, &remote, actions_dir.path())
+            .await
+            .unwrap();
+        let expected = actions_dir.path().join("actions/checkout").join(sha);
+        assert_eq!(
+            resolved,
+            ResolvedAction::Repository {
+                directory: expected.to_string_lossy().to_string(),
+                sha: sha.to_string(),
+            }
+        );
+        assert_eq!(resolved.directory(), Some(expected.to_str().unwrap()));
+        assert!(!context
+            .log_lines()
+            .iter()
+            .any(|line| line.starts_with("Resolved action")));
+    }
+
+    /// Serves a tarball for whichever commit `sha` currently names.
+    #[derive(Default)]
+    struct FakeDownloader {
+        sha: std::sync::Mutex<String>,
+        requested_refs: std::sync::Mutex<Vec<String>>,
+        api_urls: std::sync::Mutex<Vec<String>>,
+        resolves: AtomicUsize,
+        downloads: AtomicUsize,
+    }
+
+    impl FakeDownloader {
+        fn pointing_at(sha: &str) -> Arc<Self> {
+            let downloader = Self::default();
+            *downloader.sha.lock().unwrap() = sha.to_string();
+            Arc::new(downloader)
+        }
+    }
+
+    #[async_trait]
+    impl ActionDownloader for FakeDownloader {
+        async fn resolve_sha(
+            &self,
+            api_url: &str,
+            owner: &str,
+            repo: &str,
+            git_ref: &str,
+            _: Option<&str>,
+        ) -> Result<String> {
+            self.resolves.fetch_add(1, Ordering::SeqCst);
+            self.api_urls.lock().unwrap().push(api_url.to_string());
+            self.requested_refs
+                .lock()
+                .unwrap()
+                .push(format!("{}/{}@{}", owner, repo, git_ref));
+            Ok(self.sha.lock().unwrap().clone())
+        }
+
+        async fn download_tarball(
+            &self,
+            api_url: &str,
+            owner: &str,
+            repo: &str,
+            sha: &str,
+            destination: &Path,
+            _: Option<&str>,
+        ) -> Result<()> {
+            self.downloads.fetch_add(1, Ordering::SeqCst);
+            self.api_urls.lock().unwrap().push(api_url.to_string());
+            let file = std::fs::File::create(destination)?;
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            let content = format!("name: {}\n", sha);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(
+                &mut header,
+                format!("{}-{}-{}/action.yml", owner, repo, &sha[..7]),
+                content.as_bytes(),
+            )?;
+            builder.into_inner()?.finish()?;
+            Ok(())
+        }
+    }
+
+    fn checkout(git_ref: &str) -> ActionReference {
+        reference(serde_json::json!({
+            "type": "repository",
+            "repositoryType": "GitHub",
+            "name": "actions/checkout",
+            "ref": git_ref
+        }))
+    }
+
+    const SHA_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const SHA_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    #[tokio::test]
+    async fn test_second_resolution_reuses_downloaded_action() {
+        let actions_dir = tempfile::tempdir().unwrap();
+        let downloader = FakeDownloader::pointing_at(SHA_A);
+        let mut context = test_context("/work/repo");
+
+        let first = ActionManager::new()
+            .with_downloader(downloader.clone())
+            .resolve_action(&mut context, &checkout("v4"), actions_dir.path())
+            .await
+            .unwrap();
+        let directory = actions_dir.path().join("actions/checkout").join(SHA_A);
+        assert_eq!(first.directory(), Some(directory.to_str().unwrap()));
+        assert_eq!(
+            std::fs::read_to_string(directory.join("action.yml")).unwrap(),
+            format!("name: {}\n", SHA_A)
+        );
+
+        // A later job, and another ref naming the same commit, reuse the copy
+        for git_ref in ["v4", "v4.1.7"] {
+            let again = ActionManager::new()
+                .with_downloader(downloader.clone())
+                .resolve_action(&mut context, &checkout(git_ref), actions_dir.path())
+                .await
+                .unwrap();
+            assert_eq!(again, first);
+        }
+        assert_eq!(downloader.resolves.load(Ordering::SeqCst), 3);
+        assert_eq!(downloader.downloads.load(Ordering::SeqCst), 1);
+
+        // No staging directories or archives are left behind
+        let entries: Vec<_> = std::fs::read_dir(actions_dir.path().join("actions/checkout"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec![std::ffi::OsString::from(SHA_A)]);
+    }
+
+    #[tokio::test]
+    async fn test_moved_ref_downloads_new_commit() {
+        let actions_dir = tempfile::tempdir().unwrap();
+        let downloader = FakeDownloader::pointing_at(SHA_A);
+        let mut context = test_context("/work/repo");
+        let mgr = ActionManager::new().with_downloader(downloader.clone());
+
+        mgr.resolve_action(&mut context, &checkout("v4"), actions_dir.path())
+            .await
+            .unwrap();
+        *downloader.sha.lock().unwrap() = SHA_B.to_string();
+        let moved = mgr
+            .resolve_action(&mut context, &checkout("v4"), actions_dir.path())
+            .await
+            .unwrap();
+
+        let directory = actions_dir.path().join("actions/checkout").join(SHA_B);
+        assert_eq!(moved.directory(), Some(directory.to_str().unwrap()));
+        assert!(directory.join("action.yml").exists());
+        assert_eq!(downloader.downloads.load(Ordering::SeqCst), 2);
+
+        // Pinning the commit skips the lookup
+        mgr.resolve_action(&mut context, &checkout(SHA_A), actions_dir.path())
+            .await
+            .unwrap();
+        assert_eq!(downloader.resolves.load(Ordering::SeqCst), 2);
+        assert_eq!(downloader.downloads.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_tag_is_resolved_to_commit() {
+        let actions_dir = tempfile::tempdir().unwrap();
+        let downloader = FakeDownloader::pointing_at(SHA_A);
+        let mut context = test_context("/work/repo");
+
+        let resolved = ActionManager::new()
+            .with_downloader(downloader.clone())
+            .resolve_action(&mut context, &checkout("v4"), actions_dir.path())
+            .await
+            .unwrap();
+        let ResolvedAction::Repository { directory, sha } = resolved else {
+            panic!("expected a repository action");
+        };
+        assert_eq!(sha, SHA_A);
+        assert!(directory.ends_with(SHA_A), "{}", directory);
+        assert_eq!(
+            *downloader.requested_refs.lock().unwrap(),
+            vec!["actions/checkout@v4".to_string()]
+        );
+
+        let logged = format!("Resolved action 'actions/checkout@v4' to commit {}", SHA_A);
+        assert!(context.log_lines().contains(&logged));
+        assert!(!context
+            .log_lines()
+            .iter()
+            .any(|line| line.starts_with("##[warning]")));
+    }
+
+    #[tokio::test]
+    async fn test_mutable_ref_warning_is_opt_in() {
+        let actions_dir = tempfile::tempdir().unwrap();
+        let downloader = FakeDownloader::pointing_at(SHA_A);
+        let mut context = test_context("/work/repo");
+        context.set_variable(
+            constants::variables::actions::WARN_ON_MUTABLE_ACTION_REFS,
+            "true",
+            false,
+        );
+        let mgr = ActionManager::new().with_downloader(downloader);
+
+        mgr.resolve_action(&mut context, &checkout("main"), actions_dir.path())
+            .await
+            .unwrap();
+        mgr.resolve_action(&mut context, &checkout(SHA_A), actions_dir.path())
+            .await
+            .unwrap();
+
+        let warnings: Vec<&String> = context
+            .log_lines()
+            .iter()
+            .filter(|line| line.starts_with("##[warning]"))
+            .collect();
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(
+            warnings[0].contains("mutable ref 'main'"),
+            "{}",
+            warnings[0]
+        );
+        assert!(warnings[0].contains(SHA_A), "{}", warnings[0]);
+    }
+
+    #[tokio::test]
+    async fn test_actions_download_from_the_jobs_github_server() {
+        let actions_dir = tempfile::tempdir().unwrap();
+        let downloader = FakeDownloader::pointing_at(SHA_A);
+        let mgr = ActionManager::new().with_downloader(downloader.clone());
+
+        let mut context = test_context("/work/repo");
+        mgr.resolve_action(&mut context, &checkout("v4"), actions_dir.path())
+            .await
+            .unwrap();
+
+        let mut context = test_context("/work/repo");
+        context.set_github_context(crate::github_context::GitHubContext {
+            api_url: "https://ghes.example.com/api/v3".to_string(),
+            ..Default::default()
+        });
+        *downloader.sha.lock().unwrap() = SHA_B.to_string();
+        mgr.resolve_action(&mut context, &checkout("v6"), actions_dir.path())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *downloader.api_urls.lock().unwrap(),
+            vec![
+                // No github context: github.com
+                "https://api.github.com",
+                "https://api.github.com",
+                // GHES: the resolve, then the download of the new commit
+                "https://ghes.example.com/api/v3",
+                "https://ghes.example.com/api/v3",
+            ]
+        );
+    }
 }
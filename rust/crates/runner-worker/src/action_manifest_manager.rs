@@ -7,6 +7,8 @@ use std::path::Path;
 
 use runner_common::constants;
 
+use crate::worker::ActionReference;
+
 /// Parsed action definition from action.yml / action.yaml.
 #[derive(Debug, Clone)]
 pub struct ActionDefinition {
@@ -106,6 +108,67 @@ pub struct ActionStepDefinition {
     pub timeout_in_minutes: Option<u32>,
 }
 
+impl ActionStepDefinition {
+    /// The action reference of a `uses` step, in the form the job message
+    /// uses for top-level steps.
+    ///
+    /// - `docker://image` → a container registry reference
+    /// - `./path` → a reference into the job's own repository
+    /// - `owner/repo[/path]@ref` → a repository reference
+    pub fn action_reference(&self) -> Result<Option<ActionReference>> {
+        let uses = match self.uses.as_deref().map(str::trim) {
+            Some(uses) => uses,
+            None => return Ok(None),
+        };
+
+        if let Some(image) = uses.strip_prefix("docker://") {
+            let mut extra = HashMap::new();
+            extra.insert("image".to_string(), serde_json::Value::from(image));
+            return Ok(Some(ActionReference {
+                ref_type: "containerRegistry".to_string(),
+                extra,
+                ..ActionReference::default()
+            }));
+        }
+
+        if uses.starts_with("./") || uses.starts_with(".\\") {
+            return Ok(Some(ActionReference {
+                path: uses.to_string(),
+                repository_type: "self".to_string(),
+                ref_type: "repository".to_string(),
+                ..ActionReference::default()
+            }));
+        }
+
+        let (name_and_path, git_ref) = uses
+            .rsplit_once('@')
+            .filter(|(_, git_ref)| !git_ref.is_empty())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Expected format {{owner}}/{{repo}}[/path]@ref. Actual '{}'",
+                    uses
+                )
+            })?;
+        let mut parts = name_and_path.splitn(3, '/');
+        let (owner, repo) = match (parts.next(), parts.next()) {
+            (Some(owner), Some(repo)) if !owner.is_empty() && !repo.is_empty() => (owner, repo),
+            _ => anyhow::bail!(
+                "Expected format {{owner}}/{{repo}}[/path]@ref. Actual '{}'",
+                uses
+            ),
+        };
+
+        Ok(Some(ActionReference {
+            name: format!("{}/{}", owner, repo),
+            git_ref: git_ref.to_string(),
+            path: parts.next().unwrap_or_default().to_string(),
+            repository_type: "GitHub".to_string(),
+            ref_type: "repository".to_string(),
+            ..ActionReference::default()
+        }))
+    }
+}
+
 /// Input definition from action.yml.
 #[derive(Debug, Clone, serde::Deserialize)]
 struct InputDef {
@@ -299,15 +362,22 @@ impl ActionManifestManager {
     }
 
     /// Parse the `steps` array in a composite action's `runs` section.
+    ///
+    /// Every step must have exactly one of `run` or `uses`.
     fn parse_composite_steps(runs_yaml: &serde_yaml::Value) -> Result<Vec<ActionStepDefinition>> {
-        let steps_yaml = match runs_yaml.get("steps").and_then(|v| v.as_sequence()) {
-            Some(seq) => seq,
-            None => return Ok(Vec::new()),
+        let steps_yaml = match runs_yaml.get("steps") {
+            Some(serde_yaml::Value::Sequence(seq)) => seq,
+            Some(_) => anyhow::bail!("Composite action 'runs.steps' must be a list of steps"),
+            None => anyhow::bail!("Composite action is missing 'runs.steps'"),
         };
 
         let mut steps = Vec::new();
 
         for (i, step_yaml) in steps_yaml.iter().enumerate() {
+            if !step_yaml.is_mapping() {
+                anyhow::bail!("Composite step {} must be a mapping", i + 1);
+            }
+
             let step = ActionStepDefinition {
                 id: step_yaml
                     .get("id")
@@ -317,10 +387,7 @@ impl ActionManifestManager {
                     .get("name")
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string()),
-                condition: step_yaml
-                    .get("if")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string()),
+                condition: step_yaml.get("if").and_then(scalar_string),
                 uses: step_yaml
                     .get("uses")
                     .and_then(|v| v.as_str())
@@ -348,6 +415,18 @@ impl ActionManifestManager {
                     .map(|v| v as u32),
             };
 
+            match (&step.run, &step.uses) {
+                (Some(_), Some(_)) => {
+                    anyhow::bail!("Composite step {} cannot have both 'run' and 'uses'", i + 1)
+                }
+                (None, None) => {
+                    anyhow::bail!("Composite step {} must have either 'run' or 'uses'", i + 1)
+                }
+                _ => {}
+            }
+            step.action_reference()
+                .with_context(|| format!("Invalid 'uses' in composite step {}", i + 1))?;
+
             steps.push(step);
         }
 
@@ -355,6 +434,16 @@ impl ActionManifestManager {
     }
 }
 
+/// The string form of a YAML scalar, e.g. `if: true`.
+fn scalar_string(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
 /// Parse a YAML mapping into a HashMap<String, String>.
 fn parse_string_map(value: Option<&serde_yaml::Value>) -> HashMap<String, String> {
     let mut map = HashMap::new();
@@ -441,6 +530,81 @@ runs:
         assert_eq!(def.steps[1].uses, Some("actions/checkout@v4".to_string()));
     }
 
+    #[test]
+    fn test_parse_composite_run_and_uses_steps() {
+        let yaml = r#"
+name: 'Build and publish'
+runs:
+  using: composite
+  steps:
+    - name: Build
+      if: ${{ inputs.build == 'true' }}
+      run: |
+        make
+        make check
+      shell: bash
+      working-directory: src
+      env:
+        CC: clang
+    - id: setup
+      if: true
+      uses: actions/setup-node/sub/dir@v4
+      with:
+        node-version: 20
+    - uses: ./.github/actions/publish
+    - uses: docker://alpine:3.19
+"#;
+
+        let def = ActionManifestManager::parse_action_yaml(yaml).unwrap();
+        assert_eq!(def.steps.len(), 4);
+
+        let build = &def.steps[0];
+        assert_eq!(build.name.as_deref(), Some("Build"));
+        assert_eq!(
+            build.condition.as_deref(),
+            Some("${{ inputs.build == 'true' }}")
+        );
+        assert_eq!(build.run.as_deref(), Some("make\nmake check\n"));
+        assert_eq!(build.shell.as_deref(), Some("bash"));
+        assert_eq!(build.working_directory.as_deref(), Some("src"));
+        assert_eq!(build.env.as_ref().unwrap()["CC"], "clang");
+        assert!(build.action_reference().unwrap().is_none());
+
+        let setup = &def.steps[1];
+        assert_eq!(setup.condition.as_deref(), Some("true"));
+        assert_eq!(setup.with["node-version"], "20");
+        let reference = setup.action_reference().unwrap().unwrap();
+        assert_eq!(reference.name, "actions/setup-node");
+        assert_eq!(reference.path, "sub/dir");
+        assert_eq!(reference.git_ref, "v4");
+
+        let local = def.steps[2].action_reference().unwrap().unwrap();
+        assert_eq!(local.repository_type, "self");
+        assert_eq!(local.path, "./.github/actions/publish");
+
+        let docker = def.steps[3].action_reference().unwrap().unwrap();
+        assert_eq!(docker.ref_type, "containerRegistry");
+        assert_eq!(docker.extra["image"], "alpine:3.19");
+    }
+
+    #[test]
+    fn test_invalid_composite_actions_are_rejected() {
+        let missing_steps = "runs:\n  using: composite\n";
+        let err = ActionManifestManager::parse_action_yaml(missing_steps).unwrap_err();
+        assert!(err.to_string().contains("missing 'runs.steps'"), "{}", err);
+
+        let both = "runs:\n  using: composite\n  steps:\n    - run: echo\n      uses: a/b@v1\n";
+        let err = ActionManifestManager::parse_action_yaml(both).unwrap_err();
+        assert!(err.to_string().contains("both 'run' and 'uses'"), "{}", err);
+
+        let neither = "runs:\n  using: composite\n  steps:\n    - name: nothing\n";
+        assert!(ActionManifestManager::parse_action_yaml(neither).is_err());
+
+        let bad_uses = "runs:\n  using: composite\n  steps:\n    - uses: checkout\n";
+        let err = ActionManifestManager::parse_action_yaml(bad_uses).unwrap_err();
+        assert!(format!("{:#}", err).contains("{owner}/{repo}"), "{:#}", err);
+    }
+
     #[test]
     fn test_parse_docker_action() {
         let yaml = r#"
@@ -44,6 +44,10 @@ pub struct ContainerInfo {
 
     /// User-specified volume mounts from the workflow.
     pub user_mountvolumes: Vec<String>,
+
+    /// The container's own `PATH`, read once it has started. Paths added by
+    /// steps are prepended to it.
+    pub container_runtime_path: Option<String>,
 }
 
 impl ContainerInfo {
@@ -63,27 +67,31 @@ impl ContainerInfo {
             is_job_container: false,
             container_network_alias: None,
             user_mountvolumes: Vec::new(),
+            container_runtime_path: None,
         }
     }
 
     /// Translate a host path to a container path using path mappings.
+    ///
+    /// The longest mapped prefix wins, and a prefix only matches whole path
+    /// components, so `/work` does not capture `/workspace`.
     pub fn translate_to_container_path(&self, host_path: &str) -> String {
-        for (host_prefix, container_prefix) in &self.path_mappings {
-            if host_path.starts_with(host_prefix.as_str()) {
-                return host_path.replacen(host_prefix.as_str(), container_prefix.as_str(), 1);
-            }
-        }
-        host_path.to_string()
+        translate_path(
+            host_path,
+            self.path_mappings
+                .iter()
+                .map(|(host, container)| (host.as_str(), container.as_str())),
+        )
     }
 
     /// Translate a container path back to a host path.
     pub fn translate_to_host_path(&self, container_path: &str) -> String {
-        for (host_prefix, container_prefix) in &self.path_mappings {
-            if container_path.starts_with(container_prefix.as_str()) {
-                return container_path.replacen(container_prefix.as_str(), host_prefix.as_str(), 1);
-            }
-        }
-        container_path.to_string()
+        translate_path(
+            container_path,
+            self.path_mappings
+                .iter()
+                .map(|(host, container)| (container.as_str(), host.as_str())),
+        )
     }
 
     /// Build the full list of `-v` volume mount arguments for `docker create`.
@@ -121,6 +129,27 @@ impl ContainerInfo {
     }
 }
 
+/// Replace the longest `from` prefix of `path` that ends on a path boundary.
+fn translate_path<'a>(path: &str, mappings: impl Iterator<Item = (&'a str, &'a str)>) -> String {
+    let best = mappings
+        .filter(|(from, _)| !from.is_empty())
+        .filter(|(from, _)| {
+            let from = from.trim_end_matches(['/', '\\']);
+            path.strip_prefix(from).is_some_and(|rest| {
+                rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\')
+            })
+        })
+        .max_by_key(|(from, _)| from.trim_end_matches(['/', '\\']).len());
+
+    match best {
+        Some((from, to)) => {
+            let rest = &path[from.trim_end_matches(['/', '\\']).len()..];
+            format!("{}{}", to.trim_end_matches('/'), rest)
+        }
+        None => path.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,6 +180,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_path_translation_uses_longest_whole_component_prefix() {
+        let mut container = ContainerInfo::new("test");
+        container
+            .path_mappings
+            .insert("/runner/_work".to_string(), "/__w".to_string());
+        container.path_mappings.insert(
+            "/runner/_work/repo/repo".to_string(),
+            "/github/workspace".to_string(),
+        );
+
+        assert_eq!(
+            container.translate_to_container_path("/runner/_work/repo/repo/src"),
+            "/github/workspace/src"
+        );
+        assert_eq!(
+            container.translate_to_container_path("/runner/_work/_temp/script.sh"),
+            "/__w/_temp/script.sh"
+        );
+        assert_eq!(
+            container.translate_to_container_path("/runner/_work/repo/repository"),
+            "/__w/repo/repository"
+        );
+        assert_eq!(
+            container.translate_to_container_path("/runner/_workspace"),
+            "/runner/_workspace"
+        );
+        assert_eq!(container.translate_to_host_path("/__w"), "/runner/_work");
+    }
+
     #[test]
     fn test_build_volume_args() {
         let mut container = ContainerInfo::new("test");
@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 
-use runner_common::constants;
+use runner_common::constants::{self, WellKnownDirectory};
 use runner_common::host_context::HostContext;
 
 use crate::container::container_info::ContainerInfo;
@@ -213,6 +213,21 @@ impl ContainerOperationProvider {
             "/github/workspace".to_string(),
         );
 
+        // Script files and file commands live in the temp directory, and
+        // JavaScript actions run with the runner's bundled node
+        let temp = context.global().temp_directory.clone();
+        let externals = context
+            .host_context()
+            .get_directory(WellKnownDirectory::Externals)
+            .to_string_lossy()
+            .to_string();
+        for (host, mount) in [(temp, "/__w/_temp"), (externals, "/__e")] {
+            if !host.is_empty() {
+                container.volumes.push(format!("{}:{}", host, mount));
+                container.path_mappings.insert(host, mount.to_string());
+            }
+        }
+
         // Set entrypoint to keep container running
         container.entrypoint = Some("tail".to_string());
 
@@ -242,6 +257,16 @@ impl ContainerOperationProvider {
             &container_id[..12.min(container_id.len())]
         ));
 
+        // Steps prepend their added paths to the container's own PATH
+        match self
+            .docker
+            .inspect_container_path(&container_id, context.cancel_token())
+            .await
+        {
+            Ok(path) => container.container_runtime_path = path,
+            Err(e) => context.debug(&format!("Could not read the container's PATH: {:#}", e)),
+        }
+
         Ok(container)
     }
 
@@ -180,7 +180,10 @@ impl DockerCommandManager {
     ) -> Result<i32> {
         let mut args = vec!["exec".to_string()];
 
-        for (key, value) in environment {
+        for (key, value) in environment
+            .iter()
+            .collect::<std::collections::BTreeMap<_, _>>()
+        {
             args.push("-e".to_string());
             args.push(format!("{}={}", key, value));
         }
@@ -308,6 +311,16 @@ impl DockerCommandManager {
         self.run_docker_command(&args, cancel).await
     }
 
+    /// The `PATH` a running container was started with, if it sets one.
+    pub async fn inspect_container_path(
+        &self,
+        container_id: &str,
+        cancel: CancellationToken,
+    ) -> Result<Option<String>> {
+        let inspect = self.inspect_container(container_id, cancel).await?;
+        Ok(path_from_inspect(&inspect))
+    }
+
     // -----------------------------------------------------------------------
     // Internal helpers
     // -----------------------------------------------------------------------
@@ -367,10 +380,41 @@ impl Default for DockerCommandManager {
     }
 }
 
+/// The `PATH` entry of `Config.Env` in `docker inspect` output.
+fn path_from_inspect(inspect: &str) -> Option<String> {
+    let inspect: serde_json::Value = serde_json::from_str(inspect).ok()?;
+    inspect
+        .get(0)?
+        .pointer("/Config/Env")?
+        .as_array()?
+        .iter()
+        .filter_map(|entry| entry.as_str()?.strip_prefix("PATH="))
+        .next()
+        .map(str::to_string)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_path_from_inspect() {
+        let inspect = r#"[{"Id": "abc123", "Config": {"Env": [
+            "HOME=/root",
+            "PATH=/usr/local/bin:/usr/bin:/bin",
+            "LANG=C.UTF-8"
+        ]}}]"#;
+        assert_eq!(
+            path_from_inspect(inspect).as_deref(),
+            Some("/usr/local/bin:/usr/bin:/bin")
+        );
+        assert_eq!(
+            path_from_inspect(r#"[{"Config": {"Env": ["HOME=/root"]}}]"#),
+            None
+        );
+        assert_eq!(path_from_inspect("not json"), None);
+    }
+
     #[test]
     fn test_docker_command_manager_new() {
         let mgr = DockerCommandManager::new();
@@ -2,6 +2,7 @@
 // THE central mutable state for a running job. Holds variables, endpoints,
 // step queues, logging methods, result tracking, and expression context building.
 
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use runner_common::host_context::HostContext;
 use runner_common::secret_masker::SecretMasker;
@@ -117,6 +118,34 @@ pub struct Global {
     pub write_debug: bool,
 }
 
+#[cfg(test)]
+impl Global {
+    /// Job state for tests, with every job directory set to `directory`.
+    pub(crate) fn for_test(directory: &str) -> Self {
+        Self {
+            variables: Variables::new(),
+            endpoints: Vec::new(),
+            file_table: Vec::new(),
+            environment_variables: HashMap::new(),
+            job_display_name: "test-job".to_string(),
+            job_id: "job-1".to_string(),
+            plan_id: "plan-1".to_string(),
+            timeline_id: "tl-1".to_string(),
+            pipeline_directory: directory.to_string(),
+            workspace_directory: directory.to_string(),
+            temp_directory: directory.to_string(),
+            prepend_path: Vec::new(),
+            container_info: None,
+            service_containers: Vec::new(),
+            job_telemetry: Vec::new(),
+            environment_url: None,
+            cancel_token: CancellationToken::new(),
+            feature_manager: FeatureManager::empty(),
+            write_debug: false,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ExecutionContext
 // ---------------------------------------------------------------------------
@@ -165,18 +194,29 @@ pub struct ExecutionContext {
     /// Steps context (accumulated step outcomes/outputs).
     steps_context: StepsContext,
 
+    /// Job-level expression contexts from the job message (`matrix`,
+    /// `strategy`, `needs`).
+    job_contexts: HashMap<String, serde_json::Value>,
+
     /// The secret masker reference for output sanitization.
     secret_masker: Arc<SecretMasker>,
 
     /// Accumulated log lines for this context.
     log_lines: Vec<String>,
 
+    /// When each log line was written, parallel to `log_lines`. Never
+    /// decreases, even if the system clock is set back.
+    log_timestamps: Vec<DateTime<Utc>>,
+
     /// Whether this context has been completed.
     is_completed: bool,
 
     /// Embedded file command state.
     pub file_command_paths: HashMap<String, String>,
 
+    /// Markdown written to `GITHUB_STEP_SUMMARY`, pending upload.
+    pub step_summary: Option<String>,
+
     /// Depth counter for child contexts (composite action recursion guard).
     depth: u32,
 }
@@ -203,10 +243,13 @@ impl ExecutionContext {
             runner_context: None,
             github_context: None,
             steps_context: StepsContext::new(),
+            job_contexts: HashMap::new(),
             secret_masker,
             log_lines: Vec::new(),
+            log_timestamps: Vec::new(),
             is_completed: false,
             file_command_paths: HashMap::new(),
+            step_summary: None,
             depth: 0,
         }
     }
@@ -227,10 +270,13 @@ impl ExecutionContext {
             runner_context: self.runner_context.clone(),
             github_context: self.github_context.clone(),
             steps_context: self.steps_context.clone(),
+            job_contexts: self.job_contexts.clone(),
             secret_masker: Arc::clone(&self.secret_masker),
             log_lines: Vec::new(),
+            log_timestamps: Vec::new(),
             is_completed: false,
             file_command_paths: self.file_command_paths.clone(),
+            step_summary: None,
             depth: self.depth + 1,
         }
     }
@@ -251,10 +297,13 @@ impl ExecutionContext {
             runner_context: self.runner_context.clone(),
             github_context: self.github_context.clone(),
             steps_context: StepsContext::new(),
+            job_contexts: self.job_contexts.clone(),
             secret_masker: Arc::clone(&self.secret_masker),
             log_lines: Vec::new(),
+            log_timestamps: Vec::new(),
             is_completed: false,
             file_command_paths: self.file_command_paths.clone(),
+            step_summary: None,
             depth: self.depth + 1,
         }
     }
@@ -352,11 +401,24 @@ impl ExecutionContext {
         self.github_context = Some(ctx);
     }
 
+    /// Set a job-level expression context such as `matrix`.
+    pub fn set_job_context(&mut self, name: &str, value: serde_json::Value) {
+        self.job_contexts.insert(name.to_string(), value);
+    }
+
     /// Set the result.
     pub fn set_result(&mut self, result: TaskResult) {
         self.result = Some(result);
     }
 
+    /// Set a job variable, warning instead if it is read-only.
+    pub fn set_variable(&mut self, name: &str, value: &str, is_secret: bool) {
+        let updated = self.global.read().variables.set(name, value, is_secret);
+        if !updated {
+            self.warning(&format!("Can't update read-only variable '{}'", name));
+        }
+    }
+
     // -----------------------------------------------------------------------
     // Logging
     // -----------------------------------------------------------------------
@@ -364,7 +426,7 @@ impl ExecutionContext {
     /// Write a standard output line.
     pub fn write(&mut self, message: &str) {
         let masked = self.secret_masker.mask_secrets(message);
-        self.log_lines.push(masked.clone());
+        self.push_log_line(masked.clone());
         tracing::info!(target: "step", "[{}] {}", self.display_name, masked);
     }
 
@@ -377,7 +439,7 @@ impl ExecutionContext {
     pub fn debug(&mut self, message: &str) {
         if self.global.read().write_debug {
             let masked = self.secret_masker.mask_secrets(message);
-            self.log_lines.push(format!("##[debug]{}", masked));
+            self.push_log_line(format!("##[debug]{}", masked));
             tracing::debug!(target: "step", "[{}] {}", self.display_name, masked);
         }
     }
@@ -385,32 +447,32 @@ impl ExecutionContext {
     /// Write a warning message.
     pub fn warning(&mut self, message: &str) {
         let masked = self.secret_masker.mask_secrets(message);
-        self.log_lines.push(format!("##[warning]{}", masked));
+        self.push_log_line(format!("##[warning]{}", masked));
         tracing::warn!(target: "step", "[{}] {}", self.display_name, masked);
     }
 
     /// Write an error message.
     pub fn error(&mut self, message: &str) {
         let masked = self.secret_masker.mask_secrets(message);
-        self.log_lines.push(format!("##[error]{}", masked));
+        self.push_log_line(format!("##[error]{}", masked));
         tracing::error!(target: "step", "[{}] {}", self.display_name, masked);
     }
 
     /// Write a section / group header.
     pub fn section(&mut self, message: &str) {
         let masked = self.secret_masker.mask_secrets(message);
-        self.log_lines.push(format!("##[group]{}", masked));
+        self.push_log_line(format!("##[group]{}", masked));
         tracing::info!(target: "step", "[{}] >> {}", self.display_name, masked);
     }
 
     /// Write an end-group marker.
     pub fn end_section(&mut self) {
-        self.log_lines.push("##[endgroup]".to_string());
+        self.push_log_line("##[endgroup]".to_string());
     }
 
     /// Write a ::command to the output stream.
     pub fn write_command(&mut self, command: &str) {
-        self.log_lines.push(format!("##[command]{}", command));
+        self.push_log_line(format!("##[command]{}", command));
         tracing::info!(target: "step", "[{}] [command]{}", self.display_name, command);
     }
 
@@ -419,6 +481,26 @@ impl ExecutionContext {
         &self.log_lines
     }
 
+    /// Get all log lines recorded in this context with the time each was
+    /// written.
+    pub fn timestamped_log_lines(&self) -> impl Iterator<Item = (DateTime<Utc>, &str)> {
+        self.log_timestamps
+            .iter()
+            .copied()
+            .zip(self.log_lines.iter().map(String::as_str))
+    }
+
+    /// Record a log line, stamped with the time it was written.
+    fn push_log_line(&mut self, line: String) {
+        let now = Utc::now();
+        let timestamp = self
+            .log_timestamps
+            .last()
+            .map_or(now, |&last| last.max(now));
+        self.log_timestamps.push(timestamp);
+        self.log_lines.push(line);
+    }
+
     // -----------------------------------------------------------------------
     // Completion
     // -----------------------------------------------------------------------
@@ -478,6 +560,11 @@ impl ExecutionContext {
         // steps context
         ctx.insert("steps".to_string(), self.steps_context.to_value());
 
+        // matrix, strategy and needs contexts
+        for (name, value) in &self.job_contexts {
+            ctx.insert(name.clone(), value.clone());
+        }
+
         // env context
         let global = self.global.read();
         let mut env_map = global.environment_variables.clone();
@@ -514,25 +601,11 @@ mod tests {
     fn make_test_context() -> ExecutionContext {
         let host = HostContext::new("Test");
         let global = Global {
-            variables: Variables::new(),
-            endpoints: Vec::new(),
-            file_table: Vec::new(),
-            environment_variables: HashMap::new(),
-            job_display_name: "test-job".to_string(),
-            job_id: "job-1".to_string(),
-            plan_id: "plan-1".to_string(),
-            timeline_id: "tl-1".to_string(),
             pipeline_directory: "/tmp/pipeline".to_string(),
             workspace_directory: "/tmp/pipeline/workspace".to_string(),
             temp_directory: "/tmp/runner_temp".to_string(),
-            prepend_path: Vec::new(),
-            container_info: None,
-            service_containers: Vec::new(),
-            job_telemetry: Vec::new(),
-            environment_url: None,
-            cancel_token: CancellationToken::new(),
-            feature_manager: FeatureManager::empty(),
             write_debug: true,
+            ..Global::for_test("")
         };
         ExecutionContext::new_root(host, global, "test-job".to_string())
     }
@@ -547,6 +620,47 @@ mod tests {
         assert_eq!(ctx.log_lines().len(), 4);
     }
 
+    #[test]
+    fn test_log_lines_carry_increasing_timestamps() {
+        let mut ctx = make_test_context();
+        ctx.write("first");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        ctx.warning("second");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        ctx.write_command("third");
+
+        let lines: Vec<_> = ctx.timestamped_log_lines().collect();
+        let text: Vec<&str> = lines.iter().map(|(_, line)| *line).collect();
+        assert_eq!(text, vec!["first", "##[warning]second", "##[command]third"]);
+        assert!(lines[0].0 < lines[1].0);
+        assert!(lines[1].0 < lines[2].0);
+        assert!(lines[2].0 <= Utc::now());
+    }
+
+    #[test]
+    fn test_set_variable_warns_on_read_only() {
+        let mut ctx = make_test_context();
+        ctx.global()
+            .variables
+            .set_read_only("system.github.token", "ghs_original", false);
+
+        ctx.set_variable("System.GitHub.Token", "ghs_replaced", false);
+        ctx.set_variable("MY_VAR", "value", false);
+
+        assert_eq!(
+            ctx.global().variables.get("system.github.token"),
+            Some("ghs_original".to_string())
+        );
+        assert_eq!(
+            ctx.global().variables.get("my_var"),
+            Some("value".to_string())
+        );
+        assert_eq!(
+            ctx.log_lines(),
+            &["##[warning]Can't update read-only variable 'System.GitHub.Token'".to_string()]
+        );
+    }
+
     #[test]
     fn test_context_completion() {
         let mut ctx = make_test_context();
@@ -572,4 +686,51 @@ mod tests {
         ctx.complete(TaskResult::Failed, Some("should be ignored"));
         assert_eq!(ctx.result(), Some(TaskResult::Succeeded));
     }
+
+    #[test]
+    fn test_matrix_strategy_and_needs_contexts() {
+        use crate::expressions::try_evaluate_condition;
+        use crate::worker::AgentJobRequestMessage;
+
+        let message: AgentJobRequestMessage = serde_json::from_str(
+            r#"{
+                "jobId": "job-1",
+                "contextData": {
+                    "matrix": {"t": 2, "d": [
+                        {"k": "node", "v": "20"},
+                        {"k": "os", "v": "ubuntu-latest"}
+                    ]},
+                    "strategy": {"t": 2, "d": [
+                        {"k": "fail-fast", "v": {"t": 3, "b": true}},
+                        {"k": "job-index", "v": {"t": 4, "n": 1}}
+                    ]},
+                    "needs": {"t": 2, "d": [
+                        {"k": "build", "v": {"t": 2, "d": [
+                            {"k": "result", "v": "success"},
+                            {"k": "outputs", "v": {"t": 2, "d": [{"k": "version", "v": "1.2.3"}]}}
+                        ]}}
+                    ]}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let mut ctx = make_test_context();
+        for (name, value) in message.job_expression_contexts() {
+            ctx.set_job_context(&name, value);
+        }
+        let step = ctx.create_step_context("step-1".to_string(), "Test".to_string());
+        let expression_context = serde_json::to_value(step.build_expression_context()).unwrap();
+
+        let evaluate = |condition: &str| {
+            try_evaluate_condition(condition, TaskResult::Succeeded, false, &expression_context)
+                .unwrap()
+        };
+        assert!(evaluate("matrix.node == '20'"));
+        assert!(!evaluate("matrix.node == '18'"));
+        assert!(evaluate("matrix.os == 'ubuntu-latest'"));
+        assert!(evaluate("strategy.fail-fast && strategy.job-index == 1"));
+        assert!(evaluate("needs.build.result == 'success'"));
+        assert!(evaluate("needs.build.outputs.version == '1.2.3'"));
+    }
 }
@@ -0,0 +1,113 @@
+// expressions/hash_files.rs mapping the `hashFiles()` expression function
+// (`src/Misc/expressionFunc/hashFiles` in the C# runner).
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use runner_sdk::{IOUtil, PathUtil};
+use sha2::{Digest, Sha256};
+
+/// Compute `hashFiles(...)` over the files matched by `patterns`.
+///
+/// Patterns are globs relative to `workspace`; an argument may hold several
+/// newline-separated patterns and a leading `!` excludes matches. Only
+/// regular files inside the workspace are hashed. The result is the SHA256
+/// of the concatenated per-file digests in path order, or an empty string
+/// if nothing matched.
+pub(super) fn hash_files(workspace: &Path, patterns: &[String]) -> Result<String> {
+    let mut includes = Vec::new();
+    let mut excludes = Vec::new();
+    for line in patterns.iter().flat_map(|p| p.lines()) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.strip_prefix('!') {
+            Some(exclude) => excludes.push(
+                glob::Pattern::new(&workspace.join(exclude.trim()).to_string_lossy())
+                    .with_context(|| format!("Invalid hashFiles pattern '{line}'"))?,
+            ),
+            None => includes.push(workspace.join(line).to_string_lossy().to_string()),
+        }
+    }
+
+    let mut files = BTreeSet::<PathBuf>::new();
+    for pattern in &includes {
+        let matches = glob::glob(pattern)
+            .with_context(|| format!("Invalid hashFiles pattern '{pattern}'"))?;
+        for path in matches.flatten() {
+            if path.is_file()
+                && !excludes.iter().any(|e| e.matches_path(&path))
+                && PathUtil::contains(workspace, &path)
+            {
+                files.insert(path);
+            }
+        }
+    }
+
+    if files.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut hasher = Sha256::new();
+    for file in &files {
+        hasher.update(IOUtil::sha256_file(file)?);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha256(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+
+    #[test]
+    fn test_hash_files_combines_file_digests_in_path_order() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workspace.path().join("sub")).unwrap();
+        std::fs::write(workspace.path().join("b.lock"), "b").unwrap();
+        std::fs::write(workspace.path().join("sub/a.lock"), "a").unwrap();
+        std::fs::write(workspace.path().join("readme.md"), "docs").unwrap();
+
+        let mut combined = Sha256::new();
+        combined.update(sha256(b"b"));
+        combined.update(sha256(b"a"));
+        let expected = hex::encode(combined.finalize());
+
+        let hash = hash_files(workspace.path(), &["**/*.lock".to_string()]).unwrap();
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_hash_files_excludes_and_no_match() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("keep.txt"), "keep").unwrap();
+        std::fs::write(workspace.path().join("skip.txt"), "skip").unwrap();
+
+        let hash = hash_files(workspace.path(), &["*.txt\n!skip.txt".to_string()]).unwrap();
+        let expected = hex::encode(Sha256::digest(sha256(b"keep")));
+        assert_eq!(hash, expected);
+
+        assert_eq!(
+            hash_files(workspace.path(), &["*.none".to_string()]).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_hash_files_ignores_files_outside_workspace() {
+        let root = tempfile::tempdir().unwrap();
+        let workspace = root.path().join("ws");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(root.path().join("secret.txt"), "secret").unwrap();
+
+        assert_eq!(
+            hash_files(&workspace, &["../*.txt".to_string()]).unwrap(),
+            ""
+        );
+    }
+}
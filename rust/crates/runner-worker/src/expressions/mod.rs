@@ -1,10 +1,20 @@
 // expressions/mod.rs mapping expression evaluation in `ExpressionManager.cs`.
 // Evaluates GitHub Actions workflow expressions: always(), success(), failure(),
-// cancelled(), hashFiles(), and general ${{ ... }} interpolation.
+// cancelled(), fromJSON(), hashFiles(), and general ${{ ... }} interpolation.
 
-use std::collections::HashMap;
+mod hash_files;
+mod parser;
+
+use std::cmp::Ordering;
 
 use runner_common::util::task_result_util::TaskResult;
+use runner_sdk::StringUtil;
+
+use parser::{ArithOp, CompareOp, Expr};
+
+/// The job status functions. A condition that calls none of them is
+/// implicitly `success() && (...)`.
+const STATUS_FUNCTIONS: &[&str] = &["success", "failure", "always", "cancelled"];
 
 /// Evaluate a step condition expression.
 ///
@@ -14,421 +24,370 @@ use runner_common::util::task_result_util::TaskResult;
 /// - `always()` — always true
 /// - `cancelled()` — true if the job was cancelled
 ///
+/// Status functions are ordinary boolean-valued calls, so they can be
+/// combined with other operands using `!`, `&&`, `||` and parentheses.
+///
 /// The condition string is the raw `if:` value from the workflow YAML.
 /// Returns `true` if the step should execute, `false` if it should be skipped.
+/// A condition that fails to parse or evaluate is `false`.
 pub fn evaluate_condition(
     condition: &str,
     job_status: TaskResult,
     is_cancelled: bool,
     expression_context: &serde_json::Value,
 ) -> bool {
+    try_evaluate_condition(condition, job_status, is_cancelled, expression_context).unwrap_or_else(
+        |e| {
+            tracing::warn!("{:#}", e);
+            false
+        },
+    )
+}
+
+/// Like [`evaluate_condition`], but reports a condition that fails to parse
+/// or evaluate as an error instead of `false`.
+pub fn try_evaluate_condition(
+    condition: &str,
+    job_status: TaskResult,
+    is_cancelled: bool,
+    expression_context: &serde_json::Value,
+) -> anyhow::Result<bool> {
     let trimmed = condition.trim();
 
     // Empty condition defaults to success()
     if trimmed.is_empty() {
-        return matches!(job_status, TaskResult::Succeeded);
-    }
-
-    // Normalize: strip outer ${{ }} if present
-    let expr = if trimmed.starts_with("${{") && trimmed.ends_with("}}") {
-        trimmed[3..trimmed.len() - 2].trim()
-    } else {
-        trimmed
-    };
-
-    // Check for status functions (case-insensitive)
-    let lower = expr.to_lowercase();
-
-    // Handle simple status function calls
-    if lower == "always()" {
-        return true;
+        return Ok(matches!(job_status, TaskResult::Succeeded));
     }
 
-    if lower == "cancelled()" {
-        return is_cancelled;
-    }
-
-    if lower == "failure()" {
-        return matches!(job_status, TaskResult::Failed);
-    }
-
-    if lower == "success()" {
-        return matches!(job_status, TaskResult::Succeeded);
-    }
-
-    // Handle compound expressions with status functions
-    if contains_status_function(&lower) {
-        return evaluate_compound_condition(expr, job_status, is_cancelled, expression_context);
-    }
+    let expr = parser::parse(strip_expression_syntax(trimmed))
+        .map_err(|e| anyhow::anyhow!("Invalid condition '{}': {:#}", condition, e))?;
 
     // If no status function is referenced, implicitly wrap with success() &&
     // i.e., the step only runs if previous steps succeeded AND the expression is true
-    if !matches!(job_status, TaskResult::Succeeded) {
-        return false;
+    if !expr.calls_any(STATUS_FUNCTIONS) && !matches!(job_status, TaskResult::Succeeded) {
+        return Ok(false);
     }
 
-    evaluate_expression(expr, expression_context)
+    let evaluator = Evaluator {
+        context: expression_context,
+        status: Some((job_status, is_cancelled)),
+    };
+    evaluator
+        .evaluate(&expr)
+        .map(|value| value.is_truthy())
+        .map_err(|e| anyhow::anyhow!("Failed to evaluate condition '{}': {:#}", condition, e))
 }
 
-/// Check if a condition string contains a status function.
-fn contains_status_function(lower: &str) -> bool {
-    lower.contains("always()")
-        || lower.contains("cancelled()")
-        || lower.contains("failure()")
-        || lower.contains("success()")
+/// Evaluate an expression against the expression context and convert the
+/// result to a string. Returns an empty string if the expression is invalid.
+pub fn resolve_value(expr: &str, context: &serde_json::Value) -> String {
+    let evaluator = Evaluator {
+        context,
+        status: None,
+    };
+    parser::parse(strip_expression_syntax(expr.trim()))
+        .and_then(|expr| evaluator.evaluate(&expr))
+        .map(|value| value.to_string_value())
+        .unwrap_or_default()
 }
 
-/// Evaluate a compound condition that mixes status functions with other expressions.
-fn evaluate_compound_condition(
-    expr: &str,
-    job_status: TaskResult,
-    is_cancelled: bool,
-    expression_context: &serde_json::Value,
-) -> bool {
-    let lower = expr.to_lowercase();
-
-    // Handle common patterns
-    // "always() && ..."
-    if lower.starts_with("always()") {
-        if let Some(rest) = lower.strip_prefix("always()") {
-            let rest = rest.trim();
-            if rest.is_empty() {
-                return true;
-            }
-            if let Some(rest) = rest.strip_prefix("&&") {
-                return evaluate_expression(rest.trim(), expression_context);
-            }
-        }
-        return true;
+/// Strip the outer `${{ }}` if present.
+fn strip_expression_syntax(expr: &str) -> &str {
+    if expr.starts_with("${{") && expr.ends_with("}}") {
+        expr[3..expr.len() - 2].trim()
+    } else {
+        expr
     }
+}
 
-    // "failure() && ..."
-    if lower.starts_with("failure()") {
-        if !matches!(job_status, TaskResult::Failed) {
-            return false;
-        }
-        if let Some(rest) = lower.strip_prefix("failure()") {
-            let rest = rest.trim();
-            if rest.is_empty() {
-                return true;
-            }
-            if let Some(rest) = rest.strip_prefix("&&") {
-                return evaluate_expression(rest.trim(), expression_context);
-            }
-        }
-        return true;
-    }
+// ---------------------------------------------------------------------------
+// Evaluation
+// ---------------------------------------------------------------------------
+
+/// The result of evaluating an expression node.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// An object or array from the expression context.
+    Json(serde_json::Value),
+}
 
-    // "cancelled() && ..."
-    if lower.starts_with("cancelled()") {
-        if !is_cancelled {
-            return false;
+impl Value {
+    fn from_json(json: &serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(s) => Value::String(s.clone()),
+            other => Value::Json(other.clone()),
         }
-        if let Some(rest) = lower.strip_prefix("cancelled()") {
-            let rest = rest.trim();
-            if rest.is_empty() {
-                return true;
-            }
-            if let Some(rest) = rest.strip_prefix("&&") {
-                return evaluate_expression(rest.trim(), expression_context);
-            }
-        }
-        return true;
     }
 
-    // "success() && ..."
-    if lower.starts_with("success()") {
-        if !matches!(job_status, TaskResult::Succeeded) {
-            return false;
-        }
-        if let Some(rest) = lower.strip_prefix("success()") {
-            let rest = rest.trim();
-            if rest.is_empty() {
-                return true;
-            }
-            if let Some(rest) = rest.strip_prefix("&&") {
-                return evaluate_expression(rest.trim(), expression_context);
-            }
+    /// Truthiness per GitHub's coercion rules: `null`, `false`, `0`, `-0`,
+    /// `NaN` and `''` are falsy; everything else, including the strings
+    /// `'0'`, `'false'` and `' '`, objects and arrays, is truthy.
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::String(s) => !s.is_empty(),
+            Value::Json(_) => true,
         }
-        return true;
     }
 
-    // Handle "!cancelled()" pattern
-    if lower.contains("!cancelled()") || lower.contains("! cancelled()") {
-        if is_cancelled {
-            return false;
-        }
-        // Remove the !cancelled() and evaluate the rest
-        let cleaned = lower
-            .replace("!cancelled()", "true")
-            .replace("! cancelled()", "true");
-        return evaluate_expression(&cleaned, expression_context);
-    }
-
-    // Handle || (OR) patterns
-    if lower.contains("||") {
-        let parts: Vec<&str> = expr.split("||").collect();
-        for part in parts {
-            let part = part.trim();
-            let part_lower = part.to_lowercase();
-            let result = if part_lower == "always()" {
-                true
-            } else if part_lower == "failure()" {
-                matches!(job_status, TaskResult::Failed)
-            } else if part_lower == "cancelled()" {
-                is_cancelled
-            } else if part_lower == "success()" {
-                matches!(job_status, TaskResult::Succeeded)
-            } else {
-                evaluate_expression(part, expression_context)
-            };
-
-            if result {
-                return true;
-            }
+    /// Number coercion: null is 0, booleans are 1/0, strings are parsed
+    /// (see [`parse_number`]), and objects and arrays are NaN.
+    fn to_number(&self) -> f64 {
+        match self {
+            Value::Null => 0.0,
+            Value::Bool(b) => f64::from(u8::from(*b)),
+            Value::Number(n) => *n,
+            Value::String(s) => parse_number(s),
+            Value::Json(_) => f64::NAN,
         }
-        return false;
     }
 
-    // Fallback: evaluate as a simple expression
-    evaluate_expression(expr, expression_context)
-}
-
-/// Evaluate a simple expression against the expression context.
-///
-/// Supports:
-/// - Boolean literals: true, false
-/// - String comparison: `github.event_name == 'push'`
-/// - Context access: `env.MY_VAR`
-/// - Negation: `!expr`
-/// - contains(): `contains(github.event.head_commit.message, '[skip ci]')`
-/// - startsWith(), endsWith()
-fn evaluate_expression(expr: &str, context: &serde_json::Value) -> bool {
-    let trimmed = expr.trim();
-
-    if trimmed.is_empty() || trimmed == "true" {
-        return true;
-    }
-
-    if trimmed == "false" {
-        return false;
-    }
-
-    // Handle negation
-    if let Some(inner) = trimmed.strip_prefix('!') {
-        return !evaluate_expression(inner.trim(), context);
-    }
-
-    // Handle == comparison
-    if let Some((left, right)) = split_comparison(trimmed, "==") {
-        let left_val = resolve_value(left.trim(), context);
-        let right_val = resolve_value(right.trim(), context);
-        return left_val.eq_ignore_ascii_case(&right_val);
-    }
-
-    // Handle != comparison
-    if let Some((left, right)) = split_comparison(trimmed, "!=") {
-        let left_val = resolve_value(left.trim(), context);
-        let right_val = resolve_value(right.trim(), context);
-        return !left_val.eq_ignore_ascii_case(&right_val);
-    }
-
-    // Handle && (AND)
-    if trimmed.contains("&&") {
-        let parts: Vec<&str> = trimmed.split("&&").collect();
-        return parts
-            .iter()
-            .all(|p| evaluate_expression(p.trim(), context));
-    }
-
-    // Handle || (OR)
-    if trimmed.contains("||") {
-        let parts: Vec<&str> = trimmed.split("||").collect();
-        return parts
-            .iter()
-            .any(|p| evaluate_expression(p.trim(), context));
-    }
-
-    // Handle contains(haystack, needle)
-    if let Some(args) = extract_function_args(trimmed, "contains") {
-        if let Some((haystack, needle)) = split_function_args(&args) {
-            let h = resolve_value(haystack.trim(), context).to_lowercase();
-            let n = resolve_value(needle.trim(), context).to_lowercase();
-            return h.contains(&n);
+    fn to_string_value(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) if n.is_nan() => "NaN".to_string(),
+            Value::Number(n) if n.is_infinite() => {
+                if *n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+            }
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => format!("{}", *n as i64),
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => s.clone(),
+            Value::Json(json) => json.to_string(),
         }
     }
 
-    // Handle startsWith(string, prefix)
-    if let Some(args) = extract_function_args(trimmed, "startswith") {
-        if let Some((s, prefix)) = split_function_args(&args) {
-            let sv = resolve_value(s.trim(), context).to_lowercase();
-            let pv = resolve_value(prefix.trim(), context).to_lowercase();
-            return sv.starts_with(&pv);
+    /// Equality: values of the same type compare directly, strings
+    /// case-sensitively; values of different types are compared as numbers.
+    fn loose_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Json(a), Value::Json(b)) => a == b,
+            (Value::Json(_), _) | (_, Value::Json(_)) => false,
+            (a, b) => a.to_number() == b.to_number(),
         }
     }
 
-    // Handle endsWith(string, suffix)
-    if let Some(args) = extract_function_args(trimmed, "endswith") {
-        if let Some((s, suffix)) = split_function_args(&args) {
-            let sv = resolve_value(s.trim(), context).to_lowercase();
-            let fv = resolve_value(suffix.trim(), context).to_lowercase();
-            return sv.ends_with(&fv);
+    /// Ordering for `<`, `<=`, `>`, `>=`: strings compare case-sensitively,
+    /// like equality, and everything else as numbers.
+    fn loose_cmp(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Json(_), _) | (_, Value::Json(_)) => None,
+            (a, b) => a.to_number().partial_cmp(&b.to_number()),
         }
     }
-
-    // Handle hashFiles() — always true for condition evaluation purposes
-    let lower = trimmed.to_lowercase();
-    if lower.starts_with("hashfiles(") {
-        return true;
-    }
-
-    // Try to resolve as a context value and check truthiness
-    let resolved = resolve_value(trimmed, context);
-    is_truthy(&resolved)
 }
 
-/// Resolve a value from the expression context.
-///
-/// Handles:
-/// - String literals: 'value'
-/// - Numeric literals: 42
-/// - Context paths: github.event_name, env.MY_VAR
-fn resolve_value(expr: &str, context: &serde_json::Value) -> String {
-    let trimmed = expr.trim();
-
-    // String literal
-    if (trimmed.starts_with('\'') && trimmed.ends_with('\''))
-        || (trimmed.starts_with('"') && trimmed.ends_with('"'))
-    {
-        return trimmed[1..trimmed.len() - 1].to_string();
-    }
-
-    // Numeric literal
-    if trimmed.parse::<f64>().is_ok() {
-        return trimmed.to_string();
+/// Convert a string to a number the way GitHub does: surrounding whitespace
+/// is ignored, an empty string is 0, decimal (with sign and exponent),
+/// `0x` hex, `0o` octal and `Infinity` are accepted, and anything else is NaN.
+fn parse_number(value: &str) -> f64 {
+    let value = value.trim();
+    if value.is_empty() {
+        return 0.0;
     }
-
-    // Boolean literals
-    if trimmed == "true" {
-        return "true".to_string();
+    if let Some(hex) = value.strip_prefix("0x") {
+        return i64::from_str_radix(hex, 16).map_or(f64::NAN, |n| n as f64);
     }
-    if trimmed == "false" {
-        return "false".to_string();
+    if let Some(octal) = value.strip_prefix("0o") {
+        return i64::from_str_radix(octal, 8).map_or(f64::NAN, |n| n as f64);
     }
-
-    // Context path: navigate the JSON value
-    let parts: Vec<&str> = trimmed.split('.').collect();
-    let mut current = context;
-
-    for part in &parts {
-        // Handle bracket notation: steps['step-id']
-        if let Some(bracket_start) = part.find('[') {
-            let key = &part[..bracket_start];
-            if !key.is_empty() {
-                current = match current.get(key) {
-                    Some(v) => v,
-                    None => return String::new(),
-                };
-            }
-            let inner = &part[bracket_start + 1..part.len() - 1];
-            let inner = inner.trim_matches('\'').trim_matches('"');
-            current = match current.get(inner) {
-                Some(v) => v,
-                None => return String::new(),
-            };
-        } else {
-            current = match current.get(*part) {
-                Some(v) => v,
-                None => return String::new(),
-            };
-        }
+    match value {
+        "Infinity" | "+Infinity" => return f64::INFINITY,
+        "-Infinity" => return f64::NEG_INFINITY,
+        _ => {}
     }
-
-    match current {
-        serde_json::Value::String(s) => s.clone(),
-        serde_json::Value::Number(n) => n.to_string(),
-        serde_json::Value::Bool(b) => b.to_string(),
-        serde_json::Value::Null => String::new(),
-        other => other.to_string(),
+    // Rust also accepts "inf" and "nan", which GitHub does not
+    if !value
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return f64::NAN;
     }
+    value.parse().unwrap_or(f64::NAN)
 }
 
-/// Check if a string value is "truthy" in GitHub Actions expressions.
-fn is_truthy(value: &str) -> bool {
-    if value.is_empty() {
-        return false;
-    }
-    if value == "0" || value == "false" || value == "null" {
-        return false;
-    }
-    true
+struct Evaluator<'a> {
+    context: &'a serde_json::Value,
+    /// Job status and cancellation, for the status functions. `None` when
+    /// evaluating outside of a step condition.
+    status: Option<(TaskResult, bool)>,
 }
 
-/// Split a comparison expression on an operator.
-fn split_comparison<'a>(expr: &'a str, op: &str) -> Option<(&'a str, &'a str)> {
-    // Find the operator outside of string literals
-    let mut in_string = false;
-    let mut string_char = ' ';
-    let bytes = expr.as_bytes();
-    let op_bytes = op.as_bytes();
-
-    let mut i = 0;
-    while i < bytes.len() {
-        let c = bytes[i] as char;
-        if in_string {
-            if c == string_char {
-                in_string = false;
+impl Evaluator<'_> {
+    fn evaluate(&self, expr: &Expr) -> anyhow::Result<Value> {
+        Ok(match expr {
+            Expr::Null => Value::Null,
+            Expr::Bool(b) => Value::Bool(*b),
+            Expr::Number(n) => Value::Number(*n),
+            Expr::String(s) => Value::String(s.clone()),
+            Expr::Context(name) => property(&Value::Json(self.context.clone()), name),
+            Expr::Property(object, name) => property(&self.evaluate(object)?, name),
+            Expr::Index(object, index) => {
+                let object = self.evaluate(object)?;
+                match self.evaluate(index)? {
+                    Value::Number(n) => match object {
+                        Value::Json(serde_json::Value::Array(items)) if n >= 0.0 => items
+                            .get(n as usize)
+                            .map(Value::from_json)
+                            .unwrap_or(Value::Null),
+                        _ => Value::Null,
+                    },
+                    key => property(&object, &key.to_string_value()),
+                }
             }
-        } else if c == '\'' || c == '"' {
-            in_string = true;
-            string_char = c;
-        } else if i + op_bytes.len() <= bytes.len() && &bytes[i..i + op_bytes.len()] == op_bytes {
-            return Some((&expr[..i], &expr[i + op.len()..]));
-        }
-        i += 1;
+            Expr::Not(operand) => Value::Bool(!self.evaluate(operand)?.is_truthy()),
+            Expr::Negate(operand) => Value::Number(-self.evaluate(operand)?.to_number()),
+            // Division by zero follows floating point: Infinity, or NaN for 0 / 0
+            Expr::Arith(op, left, right) => {
+                let left = self.evaluate(left)?.to_number();
+                let right = self.evaluate(right)?.to_number();
+                Value::Number(match op {
+                    ArithOp::Add => left + right,
+                    ArithOp::Sub => left - right,
+                    ArithOp::Mul => left * right,
+                    ArithOp::Div => left / right,
+                    ArithOp::Rem => left % right,
+                })
+            }
+            Expr::Compare(op, left, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Value::Bool(match op {
+                    CompareOp::Eq => left.loose_eq(&right),
+                    CompareOp::Ne => !left.loose_eq(&right),
+                    CompareOp::Lt => left.loose_cmp(&right) == Some(Ordering::Less),
+                    CompareOp::Le => matches!(
+                        left.loose_cmp(&right),
+                        Some(Ordering::Less | Ordering::Equal)
+                    ),
+                    CompareOp::Gt => left.loose_cmp(&right) == Some(Ordering::Greater),
+                    CompareOp::Ge => matches!(
+                        left.loose_cmp(&right),
+                        Some(Ordering::Greater | Ordering::Equal)
+                    ),
+                })
+            }
+            // && and || short-circuit and yield the deciding operand
+            Expr::And(left, right) => {
+                let left = self.evaluate(left)?;
+                if left.is_truthy() {
+                    self.evaluate(right)?
+                } else {
+                    left
+                }
+            }
+            Expr::Or(left, right) => {
+                let left = self.evaluate(left)?;
+                if left.is_truthy() {
+                    left
+                } else {
+                    self.evaluate(right)?
+                }
+            }
+            Expr::Call(name, args) => self.call(name, args)?,
+        })
     }
 
-    None
-}
+    fn call(&self, name: &str, args: &[Expr]) -> anyhow::Result<Value> {
+        let function = name.to_lowercase();
 
-/// Extract function arguments from a function call like "funcName(args)".
-fn extract_function_args(expr: &str, func_name: &str) -> Option<String> {
-    let lower = expr.to_lowercase();
-    let prefix = format!("{}(", func_name);
-    if lower.starts_with(&prefix) && expr.ends_with(')') {
-        let start = prefix.len();
-        let end = expr.len() - 1;
-        Some(expr[start..end].to_string())
-    } else {
-        None
-    }
-}
+        if STATUS_FUNCTIONS.contains(&function.as_str()) {
+            if !args.is_empty() {
+                anyhow::bail!("{}() does not take arguments", name);
+            }
+            let (job_status, is_cancelled) = self
+                .status
+                .ok_or_else(|| anyhow::anyhow!("{}() is only available in conditions", name))?;
+            return Ok(Value::Bool(match function.as_str() {
+                "success" => matches!(job_status, TaskResult::Succeeded),
+                "failure" => matches!(job_status, TaskResult::Failed),
+                "cancelled" => is_cancelled,
+                _ => true,
+            }));
+        }
 
-/// Split function arguments (handles one comma separator).
-fn split_function_args(args: &str) -> Option<(String, String)> {
-    // Find comma outside of string literals
-    let mut depth = 0;
-    let mut in_string = false;
-    let mut string_char = ' ';
-
-    for (i, c) in args.char_indices() {
-        if in_string {
-            if c == string_char {
-                in_string = false;
+        let args = args
+            .iter()
+            .map(|arg| self.evaluate(arg))
+            .collect::<anyhow::Result<Vec<Value>>>()?;
+
+        match (function.as_str(), args.as_slice()) {
+            ("contains", [Value::Json(serde_json::Value::Array(items)), item]) => Ok(Value::Bool(
+                items.iter().any(|i| Value::from_json(i).loose_eq(item)),
+            )),
+            ("contains", [search, item]) => Ok(Value::Bool(
+                search
+                    .to_string_value()
+                    .to_lowercase()
+                    .contains(&item.to_string_value().to_lowercase()),
+            )),
+            ("startswith", [s, prefix]) => Ok(Value::Bool(
+                s.to_string_value()
+                    .to_lowercase()
+                    .starts_with(&prefix.to_string_value().to_lowercase()),
+            )),
+            ("endswith", [s, suffix]) => Ok(Value::Bool(
+                s.to_string_value()
+                    .to_lowercase()
+                    .ends_with(&suffix.to_string_value().to_lowercase()),
+            )),
+            ("fromjson", [json]) => {
+                let text = json.to_string_value();
+                StringUtil::convert_from_json(text.trim())
+                    .map(|json| Value::from_json(&json))
+                    .map_err(|e| anyhow::anyhow!("fromJSON(): {}", e))
+            }
+            ("hashfiles", patterns) => {
+                let workspace = self
+                    .context
+                    .get("github")
+                    .and_then(|github| github.get("workspace"))
+                    .and_then(serde_json::Value::as_str)
+                    .filter(|workspace| !workspace.is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("hashFiles() requires github.workspace"))?;
+                let patterns: Vec<String> = patterns.iter().map(Value::to_string_value).collect();
+                Ok(Value::String(hash_files::hash_files(
+                    std::path::Path::new(workspace),
+                    &patterns,
+                )?))
             }
-        } else if c == '\'' || c == '"' {
-            in_string = true;
-            string_char = c;
-        } else if c == '(' {
-            depth += 1;
-        } else if c == ')' {
-            depth -= 1;
-        } else if c == ',' && depth == 0 {
-            return Some((args[..i].to_string(), args[i + 1..].to_string()));
+            _ => anyhow::bail!(
+                "Unrecognized function '{}' with {} argument(s)",
+                name,
+                args.len()
+            ),
         }
     }
+}
 
-    None
+/// Look up a property of an object, preferring an exact key match and
+/// falling back to a case-insensitive one. Missing properties are null.
+fn property(object: &Value, name: &str) -> Value {
+    let Value::Json(serde_json::Value::Object(map)) = object else {
+        return Value::Null;
+    };
+    map.get(name)
+        .or_else(|| {
+            map.iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        })
+        .map(Value::from_json)
+        .unwrap_or(Value::Null)
 }
 
 #[cfg(test)]
@@ -575,11 +534,397 @@ mod tests {
 
     #[test]
     fn test_is_truthy() {
-        assert!(is_truthy("hello"));
-        assert!(is_truthy("1"));
-        assert!(is_truthy("true"));
-        assert!(!is_truthy(""));
-        assert!(!is_truthy("0"));
-        assert!(!is_truthy("false"));
+        // GitHub's truthiness table
+        let falsy = [
+            Value::Null,
+            Value::Bool(false),
+            Value::Number(0.0),
+            Value::Number(-0.0),
+            Value::Number(f64::NAN),
+            Value::String(String::new()),
+        ];
+        for value in falsy {
+            assert!(!value.is_truthy(), "{:?} should be falsy", value);
+        }
+
+        let truthy = [
+            Value::Bool(true),
+            Value::Number(1.0),
+            Value::Number(-2.5),
+            Value::Number(f64::INFINITY),
+            Value::String(" ".to_string()),
+            Value::String("0".to_string()),
+            Value::String("0.0".to_string()),
+            Value::String("false".to_string()),
+            Value::String("null".to_string()),
+            Value::String("hello".to_string()),
+            Value::Json(serde_json::json!({})),
+            Value::Json(serde_json::json!([])),
+        ];
+        for value in truthy {
+            assert!(value.is_truthy(), "{:?} should be truthy", value);
+        }
+    }
+
+    #[test]
+    fn test_truthiness_in_conditions() {
+        let ctx = serde_json::json!({ "env": { "ZERO": "0", "FALSE": "false", "SPACE": " " } });
+        let eval = |c: &str| evaluate_condition(c, TaskResult::Succeeded, false, &ctx);
+
+        assert!(eval("' '"));
+        assert!(eval("'0'"));
+        assert!(eval("'0.0'"));
+        assert!(eval("'false'"));
+        assert!(eval("env.ZERO && env.FALSE && env.SPACE"));
+        assert!(!eval("''"));
+        assert!(!eval("0"));
+        assert!(!eval("0.0"));
+        assert!(!eval("null"));
+        assert!(!eval("env.MISSING"));
+    }
+
+    #[test]
+    fn test_number_coercion() {
+        assert_eq!(parse_number(""), 0.0);
+        assert_eq!(parse_number("  12 "), 12.0);
+        assert_eq!(parse_number("-1.5e2"), -150.0);
+        assert_eq!(parse_number("0x1F"), 31.0);
+        assert_eq!(parse_number("0o17"), 15.0);
+        assert_eq!(parse_number("Infinity"), f64::INFINITY);
+        assert!(parse_number("inf").is_nan());
+        assert!(parse_number("nan").is_nan());
+        assert!(parse_number("abc").is_nan());
+
+        let ctx = serde_json::json!({});
+        let eval = |c: &str| evaluate_condition(c, TaskResult::Succeeded, false, &ctx);
+        assert!(eval("'0x10' == 16"));
+        assert!(eval("' ' == 0"));
+        assert!(eval("true == 1 && false == '0'"));
+        assert!(!eval("'abc' == 0"));
+    }
+
+    #[test]
+    fn test_status_functions_in_grouped_condition() {
+        let condition = "(failure() || cancelled()) && env.X == 'Y'";
+        let ctx = serde_json::json!({ "env": { "X": "Y" } });
+
+        assert!(evaluate_condition(
+            condition,
+            TaskResult::Failed,
+            false,
+            &ctx
+        ));
+        assert!(evaluate_condition(
+            condition,
+            TaskResult::Canceled,
+            true,
+            &ctx
+        ));
+        assert!(!evaluate_condition(
+            condition,
+            TaskResult::Succeeded,
+            false,
+            &ctx
+        ));
+
+        // The env.X path keeps its case, so the lookup still finds the value
+        let other = serde_json::json!({ "env": { "X": "Z" } });
+        assert!(!evaluate_condition(
+            condition,
+            TaskResult::Failed,
+            false,
+            &other
+        ));
+        let missing = serde_json::json!({ "env": {} });
+        assert!(!evaluate_condition(
+            condition,
+            TaskResult::Failed,
+            false,
+            &missing
+        ));
+    }
+
+    #[test]
+    fn test_status_functions_anywhere_in_condition() {
+        let ctx = serde_json::json!({ "github": { "ref": "refs/heads/Release" } });
+        assert!(evaluate_condition(
+            "github.ref == 'refs/heads/Release' && (always())",
+            TaskResult::Failed,
+            false,
+            &ctx
+        ));
+        assert!(evaluate_condition(
+            "!cancelled() && github.ref == 'refs/heads/Release'",
+            TaskResult::Failed,
+            false,
+            &ctx
+        ));
+        assert!(!evaluate_condition(
+            "!cancelled() && github.ref == 'refs/heads/Release'",
+            TaskResult::Canceled,
+            true,
+            &ctx
+        ));
+        assert!(evaluate_condition(
+            "success() || github.ref == 'refs/heads/Release'",
+            TaskResult::Failed,
+            false,
+            &ctx
+        ));
+    }
+
+    #[test]
+    fn test_not_cancelled_keeps_literal_case() {
+        // Exact-case keys win over case-insensitive matches, so lowercasing
+        // the condition would select the other entry
+        let ctx = serde_json::json!({
+            "github": { "ref": "refs/heads/Main" },
+            "env": { "BRANCHES": "{\"Main\": 1, \"main\": 2}" }
+        });
+        let condition =
+            "!cancelled() && github.ref == 'refs/heads/Main' && fromJSON(env.BRANCHES).Main == 1";
+        assert!(evaluate_condition(
+            condition,
+            TaskResult::Succeeded,
+            false,
+            &ctx
+        ));
+        assert!(!evaluate_condition(
+            condition,
+            TaskResult::Canceled,
+            true,
+            &ctx
+        ));
+        assert_eq!(
+            resolve_value(
+                "fromJSON('{\"Main\": \"Main\", \"main\": \"main\"}').Main",
+                &ctx
+            ),
+            "Main"
+        );
+    }
+
+    #[test]
+    fn test_always_with_chained_comparisons() {
+        let ctx = serde_json::json!({
+            "steps": { "x": { "outcome": "failure" } },
+            "env": { "A": "X", "B": "Y" }
+        });
+        let eval = |c: &str, status: TaskResult| evaluate_condition(c, status, false, &ctx);
+
+        // Every operand is evaluated, with its literal case intact
+        assert!(eval(
+            "always() && steps.x.outcome == 'failure'",
+            TaskResult::Failed
+        ));
+        assert!(eval(
+            "always() && env.A == 'X' && env.B != 'y'",
+            TaskResult::Failed
+        ));
+        assert!(!eval(
+            "always() && env.A == 'x' && env.B != 'y'",
+            TaskResult::Failed
+        ));
+        assert!(!eval(
+            "always() && env.A == 'X' && env.B != 'Y'",
+            TaskResult::Failed
+        ));
+        assert!(eval(
+            "always() && (env.A == 'x' || env.B == 'Y')",
+            TaskResult::Failed
+        ));
+        assert!(eval(
+            "always() && env.A == 'x' || env.B == 'Y'",
+            TaskResult::Canceled
+        ));
+    }
+
+    #[test]
+    fn test_invalid_condition_is_false() {
+        let ctx = serde_json::json!({});
+        assert!(!evaluate_condition(
+            "always() &&",
+            TaskResult::Succeeded,
+            false,
+            &ctx
+        ));
+        assert!(!evaluate_condition(
+            "unknownFn()",
+            TaskResult::Succeeded,
+            false,
+            &ctx
+        ));
+    }
+
+    #[test]
+    fn test_typed_comparisons() {
+        let ctx = serde_json::json!({
+            "github": { "run_attempt": 2, "event": { "pull_request": { "draft": false } } }
+        });
+        let run = |expr: &str| evaluate_condition(expr, TaskResult::Succeeded, false, &ctx);
+        assert!(run("github.event.pull_request.draft == false"));
+        assert!(run("github.run_attempt == '2'"));
+        assert!(run("github.run_attempt > 1 && github.run_attempt <= 2"));
+    }
+
+    #[test]
+    fn test_compound_condition_keeps_operand_case() {
+        // Lowercasing the condition would read env.stage instead of env.Stage
+        let ctx = serde_json::json!({ "env": { "Stage": "Prod", "stage": "dev" } });
+        let eval = |c: &str| evaluate_condition(c, TaskResult::Failed, false, &ctx);
+
+        assert!(eval("always() && env.Stage == 'Prod'"));
+        assert!(!eval("always() && env.Stage == 'dev'"));
+        assert!(eval("failure() && env.stage == 'dev'"));
+        assert!(eval("always() && startsWith(env.Stage, 'Pro')"));
+    }
+
+    #[test]
+    fn test_string_equality_is_case_sensitive() {
+        let ctx = serde_json::json!({
+            "github": { "ref": "refs/heads/main" },
+            "env": { "CITY": "Zürich" }
+        });
+        let eval = |c: &str| evaluate_condition(c, TaskResult::Succeeded, false, &ctx);
+        assert!(eval("github.ref == 'refs/heads/main'"));
+        assert!(!eval("github.ref == 'refs/heads/Main'"));
+        assert!(eval("github.ref != 'refs/heads/Main'"));
+        assert!(eval("env.CITY == 'Zürich'"));
+        assert!(!eval("env.CITY == 'ZÜRICH'"));
+
+        // Types are still coerced
+        assert!(eval("'1' == 1 && 'true' != true && null == 0"));
+    }
+
+    #[test]
+    fn test_hash_files_in_expressions() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("Cargo.lock"), "lock").unwrap();
+        let ctx = serde_json::json!({
+            "github": { "workspace": workspace.path().to_string_lossy() }
+        });
+
+        let hash = resolve_value("${{ hashFiles('**/Cargo.lock') }}", &ctx);
+        assert_eq!(hash.len(), 64);
+        assert!(evaluate_condition(
+            "hashFiles('Cargo.lock') != ''",
+            TaskResult::Succeeded,
+            false,
+            &ctx
+        ));
+        assert!(!evaluate_condition(
+            "hashFiles('*.none')",
+            TaskResult::Succeeded,
+            false,
+            &ctx
+        ));
+        assert!(!evaluate_condition(
+            "hashFiles('Cargo.lock')",
+            TaskResult::Succeeded,
+            false,
+            &serde_json::json!({})
+        ));
+    }
+
+    fn needs_context() -> serde_json::Value {
+        serde_json::json!({
+            "needs": {
+                "build": {
+                    "result": "success",
+                    "outputs": {
+                        "version": "1.2.3",
+                        "matrix": "{\"include\":[{\"os\":\"linux\",\"node\":20}]}",
+                        "targets": "[\"x64\", \"arm64\"]",
+                        "count": " 3 "
+                    }
+                },
+                "lint": { "result": "failure", "outputs": {} }
+            }
+        })
+    }
+
+    #[test]
+    fn test_needs_result_and_outputs() {
+        let ctx = needs_context();
+        let eval = |condition: &str| {
+            try_evaluate_condition(condition, TaskResult::Succeeded, false, &ctx).unwrap()
+        };
+        assert!(eval("needs.build.result == 'success'"));
+        assert!(eval("needs.lint.result != 'success'"));
+        assert!(eval("needs.build.outputs.version == '1.2.3'"));
+        assert!(!eval("needs.deploy.result == 'success'"));
+        assert!(!eval("needs.lint.outputs.version"));
+        assert_eq!(
+            resolve_value("${{ needs.build.outputs.version }}", &ctx),
+            "1.2.3"
+        );
+    }
+
+    #[test]
+    fn test_from_json() {
+        let ctx = needs_context();
+        let eval = |condition: &str| {
+            try_evaluate_condition(condition, TaskResult::Succeeded, false, &ctx).unwrap()
+        };
+        assert!(eval(
+            "fromJSON(needs.build.outputs.matrix).include[0].os == 'linux'"
+        ));
+        assert!(eval(
+            "fromJSON(needs.build.outputs.matrix).include[0].node == 20"
+        ));
+        assert!(eval(
+            "contains(fromJson(needs.build.outputs.targets), 'arm64')"
+        ));
+        assert!(eval("fromJSON(needs.build.outputs.count) == 3"));
+        assert!(eval("fromJSON('true')"));
+        assert_eq!(
+            resolve_value("fromJSON(needs.build.outputs.targets)[1]", &ctx),
+            "arm64"
+        );
+
+        let err = try_evaluate_condition(
+            "fromJSON(needs.build.outputs.version)",
+            TaskResult::Succeeded,
+            false,
+            &ctx,
+        )
+        .unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(
+            message.contains("fromJSON(): trailing characters at line 1, column 4 (byte 3)"),
+            "{}",
+            message
+        );
+        assert!(!message.contains("1.2.3"), "{}", message);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let ctx = serde_json::json!({
+            "github": { "run_attempt": "3" },
+            "matrix": { "shard": 2, "offset": -1.5 }
+        });
+        let value = |expr: &str| resolve_value(expr, &ctx);
+        assert_eq!(value("github.run_attempt - 1"), "2");
+        assert_eq!(value("matrix.shard * 100"), "200");
+        assert_eq!(value("1 + 2 * 3"), "7");
+        assert_eq!(value("(1 + 2) * 3"), "9");
+        assert_eq!(value("10 - 4 - 3"), "3");
+        assert_eq!(value("7 % 3 + 7 / 2"), "4.5");
+        assert_eq!(value("matrix.offset * -2"), "3");
+        assert_eq!(value("-matrix.shard + 1e1"), "8");
+        assert_eq!(value("'5' + true"), "6");
+        assert_eq!(value("'abc' + 1"), "NaN");
+
+        // Division by zero
+        assert_eq!(value("1 / 0"), "Infinity");
+        assert_eq!(value("-1 / 0"), "-Infinity");
+        assert_eq!(value("0 / 0"), "NaN");
+        assert_eq!(value("5 % 0"), "NaN");
+
+        let eval = |c: &str| evaluate_condition(c, TaskResult::Succeeded, false, &ctx);
+        assert!(eval("github.run_attempt - 1 > 1"));
+        assert!(eval("matrix.offset < -1 && -0.5 > matrix.offset"));
+        assert!(!eval("0 / 0"));
     }
 }
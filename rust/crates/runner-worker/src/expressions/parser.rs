@@ -0,0 +1,551 @@
+// Expression parser mapping `GitHub.DistributedTask.Expressions2.Parser`.
+// Tokenizes a workflow expression and builds an expression tree with the
+// GitHub Actions operator precedence (highest first):
+//
+//   ( )  [ ]  .        grouping, index, property access
+//   !  -               logical not, negation
+//   *  /  %            multiplication, division, remainder
+//   +  -               addition, subtraction
+//   <  <=  >  >=       comparison
+//   ==  !=             equality
+//   &&                 logical and
+//   ||                 logical or
+
+use anyhow::{bail, Result};
+
+/// A node of a parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// A top-level named value such as `github`, `env` or `steps`.
+    Context(String),
+    /// `object.name`
+    Property(Box<Expr>, String),
+    /// `object[index]`
+    Index(Box<Expr>, Box<Expr>),
+    /// `name(args...)`. Function names are matched case-insensitively.
+    Call(String, Vec<Expr>),
+    Not(Box<Expr>),
+    /// Unary `-`
+    Negate(Box<Expr>),
+    Arith(ArithOp, Box<Expr>, Box<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+/// Comparison and equality operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Arithmetic operators. Operands are coerced to numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Expr {
+    /// Whether the expression calls `name` anywhere. Only function names are
+    /// compared ignoring case; literals and property names keep their case.
+    pub fn calls_any(&self, names: &[&str]) -> bool {
+        match self {
+            Expr::Call(name, args) => {
+                names.iter().any(|n| name.eq_ignore_ascii_case(n))
+                    || args.iter().any(|a| a.calls_any(names))
+            }
+            Expr::Property(object, _) | Expr::Not(object) | Expr::Negate(object) => {
+                object.calls_any(names)
+            }
+            Expr::Index(a, b)
+            | Expr::Arith(_, a, b)
+            | Expr::Compare(_, a, b)
+            | Expr::And(a, b)
+            | Expr::Or(a, b) => a.calls_any(names) || b.calls_any(names),
+            Expr::Null | Expr::Bool(_) | Expr::Number(_) | Expr::String(_) | Expr::Context(_) => {
+                false
+            }
+        }
+    }
+}
+
+/// Parse an expression (without the surrounding `${{ }}`).
+pub fn parse(input: &str) -> Result<Expr> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_or()?;
+    if let Some(token) = parser.peek() {
+        bail!("Unexpected token {:?} in expression '{}'", token, input);
+    }
+    Ok(expr)
+}
+
+// ---------------------------------------------------------------------------
+// Lexer
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Dot,
+    Not,
+    And,
+    Or,
+    Compare(CompareOp),
+    Arith(ArithOp),
+    Number(f64),
+    String(String),
+    Ident(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            c if c.is_whitespace() => {
+                i += 1;
+            }
+            '(' | ')' | '[' | ']' | ',' | '.' => {
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    ',' => Token::Comma,
+                    _ => Token::Dot,
+                });
+                i += 1;
+            }
+            '!' if next == Some('=') => {
+                tokens.push(Token::Compare(CompareOp::Ne));
+                i += 2;
+            }
+            '!' => {
+                tokens.push(Token::Not);
+                i += 1;
+            }
+            '=' if next == Some('=') => {
+                tokens.push(Token::Compare(CompareOp::Eq));
+                i += 2;
+            }
+            '<' | '>' => {
+                let or_equal = next == Some('=');
+                tokens.push(Token::Compare(match (c, or_equal) {
+                    ('<', false) => CompareOp::Lt,
+                    ('<', true) => CompareOp::Le,
+                    (_, false) => CompareOp::Gt,
+                    (_, true) => CompareOp::Ge,
+                }));
+                i += if or_equal { 2 } else { 1 };
+            }
+            '+' | '-' | '*' | '/' | '%' => {
+                tokens.push(Token::Arith(match c {
+                    '+' => ArithOp::Add,
+                    '-' => ArithOp::Sub,
+                    '*' => ArithOp::Mul,
+                    '/' => ArithOp::Div,
+                    _ => ArithOp::Rem,
+                }));
+                i += 1;
+            }
+            '&' if next == Some('&') => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            '\'' => {
+                // Single-quoted string; a doubled quote is an escaped quote
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        Some('\'') if chars.get(i + 1) == Some(&'\'') => {
+                            value.push('\'');
+                            i += 2;
+                        }
+                        Some('\'') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&ch) => {
+                            value.push(ch);
+                            i += 1;
+                        }
+                        None => bail!("Unterminated string literal in expression '{}'", input),
+                    }
+                }
+                tokens.push(Token::String(value));
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric()
+                        || chars[i] == '.'
+                        || ((chars[i] == '+' || chars[i] == '-')
+                            && matches!(chars[i - 1], 'e' | 'E')))
+                {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(Token::Number(parse_number(&text).ok_or_else(|| {
+                    anyhow::anyhow!("Invalid number '{}' in expression '{}'", text, input)
+                })?));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '-')
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            other => bail!("Unexpected character '{}' in expression '{}'", other, input),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parse a number literal: decimal, exponent, or `0x` hexadecimal.
+fn parse_number(text: &str) -> Option<f64> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        return i64::from_str_radix(hex, 16).ok().map(|n| n as f64);
+    }
+    text.parse::<f64>().ok()
+}
+
+// ---------------------------------------------------------------------------
+// Parser
+// ---------------------------------------------------------------------------
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            bail!("Expected {:?}, found {:?}", token, self.peek())
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut left = self.parse_and()?;
+        while self.eat(&Token::Or) {
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut left = self.parse_equality()?;
+        while self.eat(&Token::And) {
+            let right = self.parse_equality()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_equality(&mut self) -> Result<Expr> {
+        let mut left = self.parse_comparison()?;
+        while let Some(Token::Compare(op @ (CompareOp::Eq | CompareOp::Ne))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            let right = self.parse_comparison()?;
+            left = Expr::Compare(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr> {
+        let mut left = self.parse_additive()?;
+        while let Some(Token::Compare(
+            op @ (CompareOp::Lt | CompareOp::Le | CompareOp::Gt | CompareOp::Ge),
+        )) = self.peek()
+        {
+            let op = *op;
+            self.pos += 1;
+            let right = self.parse_additive()?;
+            left = Expr::Compare(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr> {
+        let mut left = self.parse_multiplicative()?;
+        while let Some(Token::Arith(op @ (ArithOp::Add | ArithOp::Sub))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            let right = self.parse_multiplicative()?;
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr> {
+        let mut left = self.parse_unary()?;
+        while let Some(Token::Arith(op @ (ArithOp::Mul | ArithOp::Div | ArithOp::Rem))) =
+            self.peek()
+        {
+            let op = *op;
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&Token::Arith(ArithOp::Sub)) {
+            // A negative literal is a number, not a negation
+            return Ok(match self.parse_unary()? {
+                Expr::Number(n) => Expr::Number(-n),
+                operand => Expr::Negate(Box::new(operand)),
+            });
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat(&Token::Dot) {
+                match self.next() {
+                    Some(Token::Ident(name)) => {
+                        expr = Expr::Property(Box::new(expr), name);
+                    }
+                    other => bail!("Expected a property name after '.', found {:?}", other),
+                }
+            } else if self.eat(&Token::LBracket) {
+                let index = self.parse_or()?;
+                self.expect(&Token::RBracket)?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::String(s)) => Ok(Expr::String(s)),
+            Some(Token::Ident(name)) => {
+                if self.eat(&Token::LParen) {
+                    let mut args = Vec::new();
+                    if !self.eat(&Token::RParen) {
+                        loop {
+                            args.push(self.parse_or()?);
+                            if self.eat(&Token::RParen) {
+                                break;
+                            }
+                            self.expect(&Token::Comma)?;
+                        }
+                    }
+                    return Ok(Expr::Call(name, args));
+                }
+                Ok(match name.as_str() {
+                    "null" => Expr::Null,
+                    "true" => Expr::Bool(true),
+                    "false" => Expr::Bool(false),
+                    _ => Expr::Context(name),
+                })
+            }
+            other => bail!("Unexpected token {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str) -> Expr {
+        Expr::Call(name.to_string(), Vec::new())
+    }
+
+    #[test]
+    fn test_parse_precedence_and_grouping() {
+        let expr = parse("(failure() || cancelled()) && env.X == 'Y'").unwrap();
+        assert_eq!(
+            expr,
+            Expr::And(
+                Box::new(Expr::Or(
+                    Box::new(call("failure")),
+                    Box::new(call("cancelled"))
+                )),
+                Box::new(Expr::Compare(
+                    CompareOp::Eq,
+                    Box::new(Expr::Property(
+                        Box::new(Expr::Context("env".to_string())),
+                        "X".to_string()
+                    )),
+                    Box::new(Expr::String("Y".to_string())),
+                )),
+            )
+        );
+
+        // && binds tighter than ||
+        let expr = parse("a || b && c").unwrap();
+        assert!(matches!(expr, Expr::Or(_, ref rhs) if matches!(**rhs, Expr::And(_, _))));
+    }
+
+    #[test]
+    fn test_parse_literals_and_access() {
+        assert_eq!(parse("'it''s'").unwrap(), Expr::String("it's".to_string()));
+        assert_eq!(parse("0xff").unwrap(), Expr::Number(255.0));
+        assert_eq!(parse("1.5e3").unwrap(), Expr::Number(1500.0));
+        assert_eq!(parse("null").unwrap(), Expr::Null);
+        assert_eq!(
+            parse("steps['my-step'].outputs.result").unwrap(),
+            Expr::Property(
+                Box::new(Expr::Property(
+                    Box::new(Expr::Index(
+                        Box::new(Expr::Context("steps".to_string())),
+                        Box::new(Expr::String("my-step".to_string()))
+                    )),
+                    "outputs".to_string()
+                )),
+                "result".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_arithmetic_precedence() {
+        let num = |n: f64| Box::new(Expr::Number(n));
+        // * binds tighter than +, and + tighter than comparison
+        assert_eq!(
+            parse("1 + 2 * 3 > -4").unwrap(),
+            Expr::Compare(
+                CompareOp::Gt,
+                Box::new(Expr::Arith(
+                    ArithOp::Add,
+                    num(1.0),
+                    Box::new(Expr::Arith(ArithOp::Mul, num(2.0), num(3.0)))
+                )),
+                num(-4.0),
+            )
+        );
+        // Left associative
+        assert_eq!(
+            parse("8 - 2 - 1").unwrap(),
+            Expr::Arith(
+                ArithOp::Sub,
+                Box::new(Expr::Arith(ArithOp::Sub, num(8.0), num(2.0))),
+                num(1.0)
+            )
+        );
+        assert_eq!(parse("-1.5e-3").unwrap(), Expr::Number(-0.0015));
+        assert_eq!(
+            parse("-github.run_attempt").unwrap(),
+            Expr::Negate(Box::new(Expr::Property(
+                Box::new(Expr::Context("github".to_string())),
+                "run_attempt".to_string()
+            )))
+        );
+        // Names may contain '-', so subtraction needs surrounding spaces
+        assert_eq!(
+            parse("strategy.job-index").unwrap(),
+            Expr::Property(
+                Box::new(Expr::Context("strategy".to_string())),
+                "job-index".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("a ==").is_err());
+        assert!(parse("(a || b").is_err());
+        assert!(parse("'unterminated").is_err());
+        assert!(parse("a b").is_err());
+        assert!(parse("a = b").is_err());
+        assert!(parse("1 +").is_err());
+        assert!(parse("* 2").is_err());
+    }
+
+    #[test]
+    fn test_calls_any() {
+        let expr = parse("!(github.ref == 'x' || Cancelled())").unwrap();
+        assert!(expr.calls_any(&["cancelled"]));
+        assert!(!expr.calls_any(&["failure"]));
+    }
+
+    #[test]
+    fn test_status_call_keeps_operand_case() {
+        let expr = parse("Always() && github.Ref == 'refs/heads/Main'").unwrap();
+        assert!(expr.calls_any(&["always"]));
+        assert_eq!(
+            expr,
+            Expr::And(
+                Box::new(Expr::Call("Always".to_string(), vec![])),
+                Box::new(Expr::Compare(
+                    CompareOp::Eq,
+                    Box::new(Expr::Property(
+                        Box::new(Expr::Context("github".to_string())),
+                        "Ref".to_string()
+                    )),
+                    Box::new(Expr::String("refs/heads/Main".to_string())),
+                )),
+            )
+        );
+    }
+}
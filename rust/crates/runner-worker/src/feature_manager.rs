@@ -3,8 +3,17 @@
 
 use std::collections::HashMap;
 
+use runner_common::constants::features;
+
 use crate::worker::AgentJobRequestMessage;
 
+/// Variable name prefixes that mark a feature flag. Flags are stored without them.
+const FEATURE_PREFIXES: &[&str] = &[
+    "system.runner.features.",
+    "actions.runner.",
+    "distributedtask.",
+];
+
 /// Manages feature flags for the current job.
 ///
 /// Feature flags are passed as variables in the job message with a
@@ -18,47 +27,34 @@ pub struct FeatureManager {
 impl FeatureManager {
     /// Create a `FeatureManager` from a job message.
     ///
-    /// Extracts feature flags from the message variables.
-    /// Feature flags are variables whose names start with the feature flag prefix.
-    pub fn new(message: &AgentJobRequestMessage) -> Self {
-        let mut features = HashMap::new();
+    /// A message variable is a feature flag when its name starts with one of
+    /// the feature flag prefixes or is one of `constants::features::ALL`
+    /// (e.g. `actions_send_job_level_annotations`). A flag is enabled when its
+    /// value is `true` or `1`. `ACTIONS_RUNNER_FEATURE_<flag>` environment
+    /// variables on the runner machine override the message.
+    pub fn from_message(message: &AgentJobRequestMessage) -> Self {
+        let mut manager = Self::empty();
 
         for (var_name, var_value) in &message.variables {
-            // Feature flags start with specific prefixes
-            let name_lower = var_name.to_lowercase();
-
-            // Check for "system.runner.features." prefix (GitHub Actions convention)
-            if let Some(flag) = name_lower.strip_prefix("system.runner.features.") {
-                let enabled = var_value.value.eq_ignore_ascii_case("true")
-                    || var_value.value == "1";
-                features.insert(flag.to_string(), enabled);
-            }
-
-            // Also check "actions.runner." prefix
-            if let Some(flag) = name_lower.strip_prefix("actions.runner.") {
-                let enabled = var_value.value.eq_ignore_ascii_case("true")
-                    || var_value.value == "1";
-                features.insert(flag.to_string(), enabled);
-            }
-
-            // Check for DistributedTask feature flags
-            if let Some(flag) = name_lower.strip_prefix("distributedtask.") {
-                let enabled = var_value.value.eq_ignore_ascii_case("true")
-                    || var_value.value == "1";
-                features.insert(flag.to_string(), enabled);
+            let is_flag = FEATURE_PREFIXES
+                .iter()
+                .any(|prefix| var_name.to_lowercase().starts_with(prefix))
+                || features::ALL
+                    .iter()
+                    .any(|flag| flag.eq_ignore_ascii_case(var_name));
+            if is_flag {
+                manager.set_feature(var_name, Self::parse_enabled(&var_value.value));
             }
         }
 
-        // Also check environment variables for feature flags
         for (key, value) in std::env::vars() {
             let key_lower = key.to_lowercase();
             if let Some(flag) = key_lower.strip_prefix("actions_runner_feature_") {
-                let enabled = value.eq_ignore_ascii_case("true") || value == "1";
-                features.insert(flag.to_string(), enabled);
+                manager.set_feature(flag, Self::parse_enabled(&value));
             }
         }
 
-        Self { features }
+        manager
     }
 
     /// Create an empty `FeatureManager` with no features enabled.
@@ -70,10 +66,33 @@ impl FeatureManager {
 
     /// Check if a feature flag is enabled.
     ///
-    /// The flag name is case-insensitive.
-    pub fn is_feature_enabled(&self, flag: &str) -> bool {
+    /// The flag name is case-insensitive and may include its prefix, as the
+    /// constants in `runner_common::constants::features` do.
+    pub fn is_enabled(&self, flag: &str) -> bool {
+        self.features
+            .get(&Self::normalize(flag))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Enable or disable a feature flag.
+    pub fn set_feature(&mut self, flag: &str, enabled: bool) {
+        self.features.insert(Self::normalize(flag), enabled);
+    }
+
+    /// Whether a flag value turns the flag on.
+    fn parse_enabled(value: &str) -> bool {
+        value.eq_ignore_ascii_case("true") || value == "1"
+    }
+
+    /// The stored form of a flag name: lowercase, without its prefix.
+    fn normalize(flag: &str) -> String {
         let lower = flag.to_lowercase();
-        self.features.get(&lower).copied().unwrap_or(false)
+        FEATURE_PREFIXES
+            .iter()
+            .find_map(|prefix| lower.strip_prefix(prefix))
+            .map(str::to_string)
+            .unwrap_or(lower)
     }
 
     /// Check if the "debug" feature is enabled (ACTIONS_STEP_DEBUG).
@@ -86,12 +105,12 @@ impl FeatureManager {
 
     /// Check if Node20 force is enabled.
     pub fn is_force_node20(&self) -> bool {
-        self.is_feature_enabled("forceactionsnode20")
+        self.is_enabled("forceactionsnode20")
     }
 
     /// Check if Node20 to Node24 migration warnings are enabled.
     pub fn is_node24_migration_warning(&self) -> bool {
-        self.is_feature_enabled("node24migrationwarning")
+        self.is_enabled("node24migrationwarning")
     }
 
     /// Get all enabled feature flags.
@@ -168,7 +187,7 @@ mod tests {
     #[test]
     fn test_empty_features() {
         let fm = FeatureManager::empty();
-        assert!(!fm.is_feature_enabled("anything"));
+        assert!(!fm.is_enabled("anything"));
         assert_eq!(fm.total_features(), 0);
     }
 
@@ -179,9 +198,9 @@ mod tests {
             ("system.runner.features.disabledfeature", "false"),
         ]);
 
-        let fm = FeatureManager::new(&msg);
-        assert!(fm.is_feature_enabled("testfeature"));
-        assert!(!fm.is_feature_enabled("disabledfeature"));
+        let fm = FeatureManager::from_message(&msg);
+        assert!(fm.is_enabled("testfeature"));
+        assert!(!fm.is_enabled("disabledfeature"));
     }
 
     #[test]
@@ -190,9 +209,42 @@ mod tests {
             ("system.runner.features.MyFeature", "true"),
         ]);
 
-        let fm = FeatureManager::new(&msg);
-        assert!(fm.is_feature_enabled("myfeature"));
-        assert!(fm.is_feature_enabled("MYFEATURE"));
+        let fm = FeatureManager::from_message(&msg);
+        assert!(fm.is_enabled("myfeature"));
+        assert!(fm.is_enabled("MYFEATURE"));
+    }
+
+    #[test]
+    fn test_prefixed_flag_names() {
+        let msg = make_message(vec![
+            ("DistributedTask.UseContainerPathForTemplate", "true"),
+            ("actions.runner.usenode24bydefault", "1"),
+        ]);
+
+        let fm = FeatureManager::from_message(&msg);
+        assert!(fm.is_enabled(features::USE_CONTAINER_PATH_FOR_TEMPLATE));
+        assert!(fm.is_enabled(runner_common::constants::node_migration::USE_NODE24_BY_DEFAULT_FLAG));
+        assert!(fm.is_enabled("usecontainerpathfortemplate"));
+
+        let mut fm = FeatureManager::empty();
+        fm.set_feature("DistributedTask.UseContainerPathForTemplate", true);
+        assert!(fm.is_enabled("usecontainerpathfortemplate"));
+    }
+
+    #[test]
+    fn test_known_flags_from_message() {
+        let msg = make_message(vec![
+            ("actions_send_job_level_annotations", "true"),
+            ("ACTIONS_RUNNER_EMIT_COMPOSITE_MARKERS", "false"),
+            ("actions_unrelated_setting", "true"),
+        ]);
+
+        let fm = FeatureManager::from_message(&msg);
+        assert!(fm.is_enabled(features::SEND_JOB_LEVEL_ANNOTATIONS));
+        assert!(!fm.is_enabled(features::EMIT_COMPOSITE_MARKERS));
+        assert!(!fm.is_enabled(features::DISPLAY_HELPFUL_ACTIONS_DOWNLOAD_ERRORS));
+        assert!(!fm.is_enabled("actions_unrelated_setting"));
+        assert_eq!(fm.total_features(), 2);
     }
 
     #[test]
@@ -203,7 +255,7 @@ mod tests {
             ("system.runner.features.c", "true"),
         ]);
 
-        let fm = FeatureManager::new(&msg);
+        let fm = FeatureManager::from_message(&msg);
         let enabled = fm.enabled_features();
         assert_eq!(enabled.len(), 2);
         assert!(enabled.contains(&"a".to_string()));
@@ -5,7 +5,10 @@
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader};
 
+use runner_common::constants::features;
+
 use crate::execution_context::ExecutionContext;
+use crate::handlers::step_host::job_container;
 
 /// Well-known file command names mapped to environment variable names.
 const FILE_COMMANDS: &[(&str, &str)] = &[
@@ -16,8 +19,13 @@ const FILE_COMMANDS: &[(&str, &str)] = &[
     ("GITHUB_STATE", "GITHUB_STATE"),
 ];
 
-/// Maximum summary size in kilobytes (1024 KB).
-const MAX_SUMMARY_SIZE_KB: usize = 1024;
+/// Directory under the job's temp directory that holds the file command files.
+/// The temp directory is mounted into the job container, so steps running
+/// there can write to them too.
+const FILE_COMMAND_DIRECTORY: &str = "_runner_file_commands";
+
+/// Maximum summary size in kilobytes, as enforced by the Results Service.
+const MAX_SUMMARY_SIZE_KB: usize = crate::results_client::MAX_STEP_SUMMARY_SIZE_KB;
 
 /// Manages file-based commands that steps use to communicate environment changes,
 /// outputs, and summaries back to the runner.
@@ -28,13 +36,29 @@ impl FileCommandManager {
     ///
     /// Creates temporary files for each file command and sets the corresponding
     /// environment variable pointing to the file path.
+    ///
+    /// The runner always reads the files through their host paths, kept in
+    /// `file_command_paths`. When the job runs in a container, the step host
+    /// hands the step their container paths; with
+    /// `USE_CONTAINER_PATH_FOR_TEMPLATE` the job environment, and so the `env`
+    /// context, carries the container paths as well.
     pub fn initialize_file_commands(context: &mut ExecutionContext) {
-        let temp_dir = context.global().temp_directory.clone();
+        let directory = format!(
+            "{}/{}",
+            context.global().temp_directory,
+            FILE_COMMAND_DIRECTORY
+        );
+        if let Err(e) = std::fs::create_dir_all(&directory) {
+            context.warning(&format!(
+                "Failed to create file command directory {}: {}",
+                directory, e
+            ));
+        }
 
         for &(name, env_var) in FILE_COMMANDS {
             let file_path = format!(
                 "{}/{}_{}.txt",
-                temp_dir,
+                directory,
                 name.to_lowercase(),
                 uuid::Uuid::new_v4().as_simple()
             );
@@ -52,10 +76,27 @@ impl FileCommandManager {
             context.file_command_paths.insert(name.to_string(), file_path.clone());
 
             // Set the environment variable so the step knows where to write
+            let env_path = Self::template_path(context, &file_path);
             context
                 .global_mut()
                 .environment_variables
-                .insert(env_var.to_string(), file_path);
+                .insert(env_var.to_string(), env_path);
+        }
+    }
+
+    /// The path to show for a file command file in the job environment: the
+    /// container path when the job runs in a container and
+    /// `USE_CONTAINER_PATH_FOR_TEMPLATE` is enabled, the host path otherwise.
+    pub fn template_path(context: &ExecutionContext, host_path: &str) -> String {
+        let use_container_path = context
+            .global()
+            .feature_manager
+            .is_enabled(features::USE_CONTAINER_PATH_FOR_TEMPLATE);
+        match job_container(context) {
+            Some(container) if use_container_path => {
+                container.translate_to_container_path(host_path)
+            }
+            _ => host_path.to_string(),
         }
     }
 
@@ -232,18 +273,20 @@ impl FileCommandManager {
             return;
         }
 
-        let size_kb = content.len() / 1024;
-        if size_kb > MAX_SUMMARY_SIZE_KB {
-            context.warning(&format!(
-                "Step summary is too large ({} KB, max {} KB). Summary will be truncated.",
-                size_kb, MAX_SUMMARY_SIZE_KB
+        if content.len() > MAX_SUMMARY_SIZE_KB * 1024 {
+            context.error(&format!(
+                "$GITHUB_STEP_SUMMARY upload aborted, supports content up to a size of {}k, got {}k.",
+                MAX_SUMMARY_SIZE_KB,
+                content.len() / 1024
             ));
+            return;
         }
 
         context.debug(&format!(
             "GITHUB_STEP_SUMMARY: {} bytes processed",
             content.len()
         ));
+        context.step_summary = Some(content);
     }
 
     /// Process the GITHUB_STATE file – saves state for post steps.
@@ -298,37 +341,91 @@ impl FileCommandManager {
 mod tests {
     use super::*;
     use crate::execution_context::Global;
-    use crate::feature_manager::FeatureManager;
-    use crate::variables::Variables;
     use runner_common::host_context::HostContext;
-    use tokio_util::sync::CancellationToken;
 
     fn make_ctx() -> ExecutionContext {
         let host = HostContext::new("Test");
         let global = Global {
-            variables: Variables::new(),
-            endpoints: Vec::new(),
-            file_table: Vec::new(),
-            environment_variables: HashMap::new(),
-            job_display_name: "test".to_string(),
-            job_id: "j1".to_string(),
-            plan_id: "p1".to_string(),
-            timeline_id: "t1".to_string(),
-            pipeline_directory: "/tmp".to_string(),
             workspace_directory: "/tmp/w".to_string(),
             temp_directory: std::env::temp_dir().to_string_lossy().to_string(),
-            prepend_path: Vec::new(),
-            container_info: None,
-            service_containers: Vec::new(),
-            job_telemetry: Vec::new(),
-            environment_url: None,
-            cancel_token: CancellationToken::new(),
-            feature_manager: FeatureManager::empty(),
             write_debug: true,
+            ..Global::for_test("/tmp")
         };
         ExecutionContext::new_root(host, global, "test".to_string())
     }
 
+    fn container_ctx(use_container_path: bool) -> (ExecutionContext, tempfile::TempDir) {
+        let temp = tempfile::tempdir().unwrap();
+        let host_temp = temp.path().to_string_lossy().to_string();
+        let ctx = make_ctx();
+        {
+            let mut global = ctx.global_mut();
+            global.temp_directory = host_temp.clone();
+            let mut container = crate::container::container_info::ContainerInfo::new("ubuntu");
+            container.container_id = Some("abc123".to_string());
+            container
+                .path_mappings
+                .insert(host_temp, "/__w/_temp".to_string());
+            global.container_info = Some(container);
+            global.feature_manager.set_feature(
+                features::USE_CONTAINER_PATH_FOR_TEMPLATE,
+                use_container_path,
+            );
+        }
+        (ctx, temp)
+    }
+
+    #[test]
+    fn test_file_commands_use_container_paths_in_job_container() {
+        let (mut ctx, temp) = container_ctx(true);
+        FileCommandManager::initialize_file_commands(&mut ctx);
+
+        let host_output = ctx.file_command_paths["GITHUB_OUTPUT"].clone();
+        let host_dir = temp.path().join(FILE_COMMAND_DIRECTORY);
+        assert!(std::path::Path::new(&host_output).starts_with(&host_dir));
+
+        let env_output = ctx.global().environment_variables["GITHUB_OUTPUT"].clone();
+        let file_name = std::path::Path::new(&host_output)
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        assert_eq!(
+            env_output,
+            format!("/__w/_temp/{}/{}", FILE_COMMAND_DIRECTORY, file_name)
+        );
+
+        // The step writes through the mount; the runner reads the host file
+        std::fs::write(&host_output, "result=from-container\n").unwrap();
+        FileCommandManager::process_file_commands(&mut ctx);
+        assert_eq!(
+            ctx.outputs.get("result"),
+            Some(&"from-container".to_string())
+        );
+        assert!(!std::path::Path::new(&host_output).exists());
+    }
+
+    #[test]
+    fn test_file_commands_keep_host_paths_without_feature() {
+        let (mut ctx, _temp) = container_ctx(false);
+        FileCommandManager::initialize_file_commands(&mut ctx);
+
+        let host_env = ctx.file_command_paths["GITHUB_ENV"].clone();
+        assert_eq!(ctx.global().environment_variables["GITHUB_ENV"], host_env);
+
+        // Not started yet: host paths even with the feature enabled
+        let (ctx, _temp) = container_ctx(true);
+        ctx.global_mut()
+            .container_info
+            .as_mut()
+            .unwrap()
+            .container_id = None;
+        assert_eq!(
+            FileCommandManager::template_path(&ctx, "/somewhere/file.txt"),
+            "/somewhere/file.txt"
+        );
+    }
+
     #[test]
     fn test_process_env_file_simple() {
         let mut ctx = make_ctx();
@@ -392,4 +489,68 @@ mod tests {
 
         assert_eq!(ctx.outputs.get("result"), Some(&"success".to_string()));
     }
+
+    #[test]
+    fn test_process_summary_file() {
+        let mut ctx = make_ctx();
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(tmp.path(), "## Results\n\n- 3 passed\n").unwrap();
+
+        FileCommandManager::process_summary_file(&mut ctx, tmp.path().to_str().unwrap());
+
+        assert_eq!(
+            ctx.step_summary.as_deref(),
+            Some("## Results\n\n- 3 passed\n")
+        );
+    }
+
+    #[test]
+    fn test_process_summary_file_over_limit() {
+        let mut ctx = make_ctx();
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(tmp.path(), "x".repeat(MAX_SUMMARY_SIZE_KB * 1024 + 1)).unwrap();
+
+        FileCommandManager::process_summary_file(&mut ctx, tmp.path().to_str().unwrap());
+
+        assert!(ctx.step_summary.is_none());
+        assert!(ctx
+            .log_lines()
+            .iter()
+            .any(|l| l.starts_with("##[error]$GITHUB_STEP_SUMMARY upload aborted")));
+
+        // Exactly at the limit is accepted
+        let mut ctx = make_ctx();
+        std::fs::write(tmp.path(), "x".repeat(MAX_SUMMARY_SIZE_KB * 1024)).unwrap();
+        FileCommandManager::process_summary_file(&mut ctx, tmp.path().to_str().unwrap());
+        assert!(ctx.step_summary.is_some());
+    }
+
+    #[test]
+    fn test_summary_file_is_per_step() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = make_ctx();
+        root.global_mut().temp_directory = temp.path().to_string_lossy().to_string();
+
+        let mut first = root.create_step_context("s1".to_string(), "one".to_string());
+        FileCommandManager::initialize_file_commands(&mut first);
+        let first_path = first.file_command_paths["GITHUB_STEP_SUMMARY"].clone();
+        assert_eq!(
+            first
+                .global()
+                .environment_variables
+                .get("GITHUB_STEP_SUMMARY"),
+            Some(&first_path)
+        );
+        std::fs::write(&first_path, "first step").unwrap();
+        FileCommandManager::process_file_commands(&mut first);
+        assert_eq!(first.step_summary.as_deref(), Some("first step"));
+        assert!(!std::path::Path::new(&first_path).exists());
+
+        let mut second = root.create_step_context("s2".to_string(), "two".to_string());
+        FileCommandManager::initialize_file_commands(&mut second);
+        let second_path = second.file_command_paths["GITHUB_STEP_SUMMARY"].clone();
+        assert_ne!(first_path, second_path);
+        FileCommandManager::process_file_commands(&mut second);
+        assert!(second.step_summary.is_none());
+    }
 }
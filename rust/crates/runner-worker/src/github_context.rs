@@ -120,18 +120,33 @@ pub struct GitHubContext {
 
 impl GitHubContext {
     /// Build the GitHubContext from a job message and known variables.
+    ///
+    /// Values come from the message's `github` context data, falling back to
+    /// the matching `system.github.*` variable.
     pub fn from_message(
         message: &AgentJobRequestMessage,
         variables: &HashMap<String, String>,
     ) -> Self {
+        let github = message
+            .context_data
+            .get("github")
+            .map(context_data_to_json)
+            .unwrap_or_default();
+
         let get_var = |name: &str| -> String {
-            variables
+            github
                 .get(name)
-                .cloned()
+                .and_then(|v| match v {
+                    serde_json::Value::String(s) => Some(s.clone()),
+                    serde_json::Value::Number(n) => Some(n.to_string()),
+                    serde_json::Value::Bool(b) => Some(b.to_string()),
+                    _ => None,
+                })
+                .or_else(|| variables.get(&format!("system.github.{}", name)).cloned())
                 .unwrap_or_default()
         };
 
-        let repository = get_var("system.github.repository");
+        let repository = get_var("repository");
         let repo_parts: Vec<&str> = repository.splitn(2, '/').collect();
         let repository_owner = if repo_parts.len() >= 2 {
             repo_parts[0].to_string()
@@ -139,46 +154,49 @@ impl GitHubContext {
             String::new()
         };
 
-        let git_ref = get_var("system.github.ref");
+        let git_ref = get_var("ref");
         let ref_name = Self::extract_ref_name(&git_ref);
         let ref_type = Self::extract_ref_type(&git_ref);
 
-        // Parse event payload
-        let event_str = get_var("system.github.event");
-        let event: serde_json::Value = serde_json::from_str(&event_str)
-            .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+        let event = Self::load_event(&github, variables);
 
-        let server_url = get_var("system.github.server_url");
-        let api_url = if server_url == "https://github.com" || server_url.is_empty() {
-            "https://api.github.com".to_string()
+        let server_url = get_var("server_url");
+        let server = url::Url::parse(if server_url.is_empty() {
+            "https://github.com"
         } else {
-            format!("{}/api/v3", server_url)
+            &server_url
+        });
+        let api_url = match &server {
+            Ok(server) => runner_sdk::UrlUtil::api_url(server, ""),
+            Err(_) => format!("{}/api/v3", server_url),
         };
-        let graphql_url = if server_url == "https://github.com" || server_url.is_empty() {
-            "https://api.github.com/graphql".to_string()
-        } else {
-            format!("{}/api/graphql", server_url)
+        // GHES serves GraphQL beside the REST API rather than under `/api/v3`
+        let graphql_url = match &server {
+            Ok(server) if runner_sdk::UrlUtil::is_hosted_server(server) => {
+                runner_sdk::UrlUtil::api_url(server, "graphql")
+            }
+            _ => format!("{}/api/graphql", server_url.trim_end_matches('/')),
         };
 
         Self {
-            workflow: get_var("system.github.workflow"),
-            workflow_ref: get_var("system.github.workflow_ref"),
-            workflow_sha: get_var("system.github.workflow_sha"),
-            run_id: get_var("system.github.run_id"),
-            run_number: get_var("system.github.run_number"),
-            run_attempt: get_var("system.github.run_attempt"),
-            actor: get_var("system.github.actor"),
-            triggering_actor: get_var("system.github.triggering_actor"),
+            workflow: get_var("workflow"),
+            workflow_ref: get_var("workflow_ref"),
+            workflow_sha: get_var("workflow_sha"),
+            run_id: get_var("run_id"),
+            run_number: get_var("run_number"),
+            run_attempt: get_var("run_attempt"),
+            actor: get_var("actor"),
+            triggering_actor: get_var("triggering_actor"),
             repository: repository.clone(),
             repository_owner,
-            repository_id: get_var("system.github.repository_id"),
-            repository_owner_id: get_var("system.github.repository_owner_id"),
-            event_name: get_var("system.github.event_name"),
+            repository_id: get_var("repository_id"),
+            repository_owner_id: get_var("repository_owner_id"),
+            event_name: get_var("event_name"),
             event,
-            sha: get_var("system.github.sha"),
+            sha: get_var("sha"),
             git_ref,
-            head_ref: get_var("system.github.head_ref"),
-            base_ref: get_var("system.github.base_ref"),
+            head_ref: get_var("head_ref"),
+            base_ref: get_var("base_ref"),
             server_url: if server_url.is_empty() {
                 "https://github.com".to_string()
             } else {
@@ -187,18 +205,17 @@ impl GitHubContext {
             api_url,
             graphql_url,
             ref_name,
-            ref_protected: get_var("system.github.ref_protected")
-                .eq_ignore_ascii_case("true"),
+            ref_protected: get_var("ref_protected").eq_ignore_ascii_case("true"),
             ref_type,
-            workspace: get_var("system.github.workspace"),
+            workspace: get_var("workspace"),
             job: message.job_display_name.clone(),
             action: String::new(),
             action_path: String::new(),
             action_ref: String::new(),
             action_repository: String::new(),
             action_status: String::new(),
-            token: get_var("system.github.token"),
-            retention_days: get_var("system.github.retention_days"),
+            token: get_var("token"),
+            retention_days: get_var("retention_days"),
             repositoryurl: format!(
                 "{}/{}",
                 "https://github.com",
@@ -208,6 +225,59 @@ impl GitHubContext {
         }
     }
 
+    /// Load the webhook event payload.
+    ///
+    /// Sources, in order: the `event` member of the `github` context data,
+    /// the file named by `GITHUB_EVENT_PATH`, then the `system.github.event`
+    /// variable. Falls back to an empty object.
+    fn load_event(
+        github: &serde_json::Value,
+        variables: &HashMap<String, String>,
+    ) -> serde_json::Value {
+        if let Some(event) = github.get("event").filter(|e| e.is_object()) {
+            return event.clone();
+        }
+
+        let event_path = variables
+            .get("GITHUB_EVENT_PATH")
+            .cloned()
+            .or_else(|| std::env::var("GITHUB_EVENT_PATH").ok())
+            .filter(|p| !p.is_empty());
+        if let Some(path) = event_path {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => match serde_json::from_str::<serde_json::Value>(&content) {
+                    Ok(event) if event.is_object() => return event,
+                    Ok(_) => tracing::warn!("Event payload in {} is not an object", path),
+                    Err(e) => tracing::warn!("Failed to parse event payload {}: {}", path, e),
+                },
+                Err(e) => tracing::warn!("Failed to read event payload {}: {}", path, e),
+            }
+        }
+
+        variables
+            .get("system.github.event")
+            .and_then(|event| serde_json::from_str(event).ok())
+            .filter(|event: &serde_json::Value| event.is_object())
+            .unwrap_or(serde_json::Value::Object(serde_json::Map::new()))
+    }
+
+    /// Point `action_path`, `action_ref` and `action_repository` at the
+    /// action about to run.
+    ///
+    /// Empty values keep the current ones, so `run:` steps inside a composite
+    /// action still see the composite's ref and repository.
+    pub fn set_action(&mut self, path: &str, git_ref: &str, repository: &str) {
+        for (field, value) in [
+            (&mut self.action_path, path),
+            (&mut self.action_ref, git_ref),
+            (&mut self.action_repository, repository),
+        ] {
+            if !value.is_empty() {
+                *field = value.to_string();
+            }
+        }
+    }
+
     /// Convert to a serde_json::Value for expression evaluation.
     pub fn to_value(&self) -> serde_json::Value {
         serde_json::to_value(self).unwrap_or(serde_json::Value::Object(serde_json::Map::new()))
@@ -241,6 +311,57 @@ impl GitHubContext {
     }
 }
 
+/// Convert serialized `PipelineContextData` into plain JSON.
+///
+/// Context data in the job message is tagged by type: strings are bare JSON
+/// strings, `{"t":1,"a":[...]}` is an array, `{"t":2,"d":[{"k":..,"v":..}]}`
+/// (or `"t":5` for case-sensitive) is a dictionary, `{"t":3,"b":..}` is a
+/// boolean and `{"t":4,"n":..}` is a number. Values that are already plain
+/// JSON are returned unchanged.
+pub fn context_data_to_json(data: &serde_json::Value) -> serde_json::Value {
+    let serde_json::Value::Object(map) = data else {
+        return data.clone();
+    };
+    match map.get("t").and_then(|t| t.as_i64()) {
+        Some(0) => map.get("s").cloned().unwrap_or_default(),
+        Some(1) => serde_json::Value::Array(
+            map.get("a")
+                .and_then(|a| a.as_array())
+                .map(|items| items.iter().map(context_data_to_json).collect())
+                .unwrap_or_default(),
+        ),
+        Some(2) | Some(5) => serde_json::Value::Object(
+            map.get("d")
+                .and_then(|d| d.as_array())
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .filter_map(|entry| {
+                            let key = entry.get("k")?.as_str()?.to_string();
+                            let value =
+                                entry.get("v").map(context_data_to_json).unwrap_or_default();
+                            Some((key, value))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+        Some(3) => map
+            .get("b")
+            .cloned()
+            .unwrap_or(serde_json::Value::Bool(false)),
+        Some(4) => map
+            .get("n")
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!(0)),
+        _ => serde_json::Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), context_data_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,4 +412,148 @@ mod tests {
         assert_eq!(val.get("repository").unwrap().as_str(), Some("owner/repo"));
         assert_eq!(val.get("sha").unwrap().as_str(), Some("abc123"));
     }
+
+    #[test]
+    fn test_set_action_keeps_unset_fields() {
+        let mut ctx = GitHubContext::default();
+        ctx.set_action("/work/_actions/octo/setup/v2", "v2", "octo/setup");
+        ctx.set_action("/work/_actions/octo/setup/v2", "", "");
+
+        assert_eq!(
+            evaluate(&ctx, "github.action_path"),
+            "/work/_actions/octo/setup/v2"
+        );
+        assert_eq!(evaluate(&ctx, "github.action_ref"), "v2");
+        assert_eq!(evaluate(&ctx, "github.action_repository"), "octo/setup");
+    }
+
+    fn message_with_github(github: serde_json::Value) -> AgentJobRequestMessage {
+        let mut message: AgentJobRequestMessage =
+            serde_json::from_value(serde_json::json!({ "jobDisplayName": "build" })).unwrap();
+        message.context_data.insert("github".to_string(), github);
+        message
+    }
+
+    fn evaluate(ctx: &GitHubContext, expr: &str) -> String {
+        let value = serde_json::json!({ "github": ctx.to_value() });
+        crate::expressions::resolve_value(expr, &value)
+    }
+
+    #[test]
+    fn test_push_event_from_context_data() {
+        // Serialized PipelineContextData, as sent in the job message
+        let github = serde_json::json!({
+            "t": 2,
+            "d": [
+                { "k": "sha", "v": "0123abcd" },
+                { "k": "ref", "v": "refs/heads/main" },
+                { "k": "repository", "v": "octo/hello" },
+                { "k": "actor", "v": "octocat" },
+                { "k": "run_id", "v": "42" },
+                { "k": "event_name", "v": "push" },
+                { "k": "event", "v": { "t": 2, "d": [
+                    { "k": "forced", "v": { "t": 3, "b": false } },
+                    { "k": "head_commit", "v": { "t": 2, "d": [
+                        { "k": "message", "v": "Fix build" }
+                    ] } },
+                    { "k": "commits", "v": { "t": 1, "a": [
+                        { "t": 2, "d": [{ "k": "id", "v": "0123abcd" }] }
+                    ] } }
+                ] } }
+            ]
+        });
+        let ctx = GitHubContext::from_message(&message_with_github(github), &HashMap::new());
+
+        assert_eq!(ctx.sha, "0123abcd");
+        assert_eq!(ctx.git_ref, "refs/heads/main");
+        assert_eq!(ctx.repository, "octo/hello");
+        assert_eq!(ctx.repository_owner, "octo");
+        assert_eq!(ctx.actor, "octocat");
+        assert_eq!(ctx.run_id, "42");
+        assert_eq!(ctx.ref_name, "main");
+
+        assert_eq!(
+            evaluate(&ctx, "github.event.head_commit.message"),
+            "Fix build"
+        );
+        assert_eq!(evaluate(&ctx, "github.event.commits[0].id"), "0123abcd");
+        assert_eq!(evaluate(&ctx, "github.event.forced"), "false");
+    }
+
+    #[test]
+    fn test_pull_request_event_from_event_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let event_path = dir.path().join("event.json");
+        std::fs::write(
+            &event_path,
+            serde_json::json!({
+                "action": "opened",
+                "number": 7,
+                "pull_request": {
+                    "number": 7,
+                    "draft": false,
+                    "head": { "ref": "feature" },
+                    "labels": [{ "name": "bug" }]
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let github = serde_json::json!({ "event_name": "pull_request", "sha": "feedbeef" });
+        let mut variables = HashMap::new();
+        variables.insert(
+            "GITHUB_EVENT_PATH".to_string(),
+            event_path.to_string_lossy().to_string(),
+        );
+        variables.insert("system.github.actor".to_string(), "monalisa".to_string());
+        let ctx = GitHubContext::from_message(&message_with_github(github), &variables);
+
+        assert_eq!(ctx.event_name, "pull_request");
+        assert_eq!(ctx.sha, "feedbeef");
+        assert_eq!(ctx.actor, "monalisa");
+        assert_eq!(evaluate(&ctx, "github.event.pull_request.number"), "7");
+        assert_eq!(
+            evaluate(&ctx, "github.event.pull_request.head.ref"),
+            "feature"
+        );
+        assert_eq!(
+            evaluate(&ctx, "github.event.pull_request.labels[0].name"),
+            "bug"
+        );
+        assert_eq!(evaluate(&ctx, "github.event.pull_request.missing"), "");
+    }
+
+    #[test]
+    fn test_missing_event_is_empty_object() {
+        let ctx = GitHubContext::from_message(
+            &message_with_github(serde_json::json!({})),
+            &HashMap::new(),
+        );
+        assert_eq!(ctx.event, serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_api_urls_for_github_com_and_ghes() {
+        std::env::remove_var("GITHUB_ACTIONS_RUNNER_FORCE_GHES");
+        let urls = |server_url: &str| {
+            let github = serde_json::json!({ "server_url": server_url });
+            let ctx = GitHubContext::from_message(&message_with_github(github), &HashMap::new());
+            (ctx.api_url, ctx.graphql_url)
+        };
+
+        let hosted = (
+            "https://api.github.com".to_string(),
+            "https://api.github.com/graphql".to_string(),
+        );
+        assert_eq!(urls("https://github.com"), hosted);
+        assert_eq!(urls(""), hosted);
+        assert_eq!(
+            urls("https://ghes.example.com:8443"),
+            (
+                "https://ghes.example.com:8443/api/v3".to_string(),
+                "https://ghes.example.com:8443/api/graphql".to_string()
+            )
+        );
+    }
 }
@@ -8,9 +8,11 @@ use std::collections::HashMap;
 
 use runner_common::constants;
 
+use crate::action_manager::{ActionManager, ResolvedAction};
+use crate::action_manifest_manager::{ActionManifestManager, ActionStepDefinition};
 use crate::execution_context::{ExecutionContext, IStep};
-use crate::handlers::handler::{ActionContext, Handler, HandlerData};
-use crate::action_manifest_manager::{ActionDefinition, ActionStepDefinition};
+use crate::handlers::handler::{ActionContext, Handler, HandlerData, HandlerFactory};
+use crate::handlers::script_handler::ScriptHandlerHelpers;
 
 /// Handler for composite actions (action.yml with `using: composite`).
 pub struct CompositeActionHandler;
@@ -45,7 +47,7 @@ impl Handler for CompositeActionHandler {
 
         // Load the action definition
         let action_dir = &data.action_context.action_directory;
-        let definition = crate::action_manifest_manager::ActionManifestManager::load_action(action_dir)?;
+        let definition = ActionManifestManager::load_action(action_dir)?;
 
         context.info(&format!(
             "Composite action '{}' has {} steps.",
@@ -170,18 +172,44 @@ impl IStep for CompositeStep {
             if let Some(ref uses) = self.step_definition.uses {
                 // This is a nested action reference
                 context.info(&format!("Uses: {}", uses));
+                let reference = self
+                    .step_definition
+                    .action_reference()?
+                    .context("Composite step has no action reference")?;
+
+                // Nested actions run their main entry point only; pre and
+                // post entry points are not supported inside composites.
+                let resolved = ActionManager::new()
+                    .resolve_async(context, &reference)
+                    .await?;
+                let action_context = match resolved {
+                    ResolvedAction::ContainerImage { image } => ActionContext {
+                        reference: Some(reference),
+                        action_type: "docker".to_string(),
+                        image: Some(image),
+                        ..ActionContext::default()
+                    },
+                    ResolvedAction::Repository { directory, .. }
+                    | ResolvedAction::Local { directory } => {
+                        let definition = ActionManifestManager::load_action(&directory)?;
+                        ActionContext::from_definition(Some(reference), directory, &definition)
+                    }
+                };
 
-                // For nested actions, we would need to resolve and run them
-                // via the action manager. For now, log it.
-                context.warning("Nested action references in composite actions require full action resolution.");
-                Ok(())
+                let handler = HandlerFactory::create(&action_context.action_type);
+                let handler_data = HandlerData {
+                    inputs: self.step_definition.with.clone(),
+                    environment: self.composite_env.clone(),
+                    action_context,
+                };
+                handler.run_async(context, &handler_data).await
             } else if let Some(ref run) = self.step_definition.run {
                 // This is an inline script step
                 let shell = self
                     .step_definition
                     .shell
                     .clone()
-                    .unwrap_or_else(|| crate::handlers::script_handler::ScriptHandlerHelpers::get_default_shell());
+                    .unwrap_or_else(ScriptHandlerHelpers::get_default_shell_option);
 
                 let mut inputs = HashMap::new();
                 inputs.insert("script".to_string(), run.clone());
@@ -220,4 +248,137 @@ mod tests {
         let handler = CompositeActionHandler::new();
         let _ = handler;
     }
+
+    #[cfg(unix)]
+    fn test_context(work: &std::path::Path) -> ExecutionContext {
+        use crate::execution_context::Global;
+        use crate::github_context::GitHubContext;
+        use runner_common::host_context::HostContext;
+
+        let work_path = work.to_string_lossy().to_string();
+        let global = Global::for_test(&work_path);
+        let mut ctx =
+            ExecutionContext::new_root(HostContext::new("Test"), global, "build".to_string());
+        ctx.set_github_context(GitHubContext::default());
+        ctx
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_composite_steps_see_action_path() {
+        use crate::worker::ActionReference;
+
+        let work = tempfile::tempdir().unwrap();
+        let action_dir = tempfile::tempdir().unwrap();
+        let action_path = action_dir.path().to_string_lossy().to_string();
+        std::fs::write(
+            action_dir.path().join("action.yml"),
+            format!(
+                r#"name: Bundle
+runs:
+  using: composite
+  steps:
+    - if: github.action_path == '{action_path}' && github.action_repository == 'octo/bundle'
+      run: echo "$GITHUB_ACTION_PATH@$GITHUB_ACTION_REF" > "$GITHUB_ACTION_PATH/ran"
+      shell: bash
+"#
+            ),
+        )
+        .unwrap();
+
+        let mut ctx = test_context(work.path());
+
+        let data = HandlerData {
+            inputs: HashMap::new(),
+            environment: HashMap::new(),
+            action_context: ActionContext {
+                reference: Some(ActionReference {
+                    name: "octo/bundle".to_string(),
+                    git_ref: "v1".to_string(),
+                    path: String::new(),
+                    repository_type: "GitHub".to_string(),
+                    ref_type: "repository".to_string(),
+                    extra: HashMap::new(),
+                }),
+                action_directory: action_path.clone(),
+                action_type: "composite".to_string(),
+                ..ActionContext::default()
+            },
+        };
+
+        CompositeActionHandler::new()
+            .run_async(&mut ctx, &data)
+            .await
+            .unwrap();
+
+        let ran = std::fs::read_to_string(action_dir.path().join("ran")).unwrap();
+        assert_eq!(ran.trim(), format!("{action_path}@v1"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_composite_runs_run_and_nested_uses_steps() {
+        let work = tempfile::tempdir().unwrap();
+        let marker = work.path().join("ran");
+        let marker_path = marker.to_string_lossy().to_string();
+
+        let inner_dir = work.path().join(".github/actions/inner");
+        std::fs::create_dir_all(&inner_dir).unwrap();
+        std::fs::write(
+            inner_dir.join("action.yml"),
+            format!(
+                r#"name: Inner
+inputs:
+  greeting:
+    default: hi
+runs:
+  using: composite
+  steps:
+    - run: echo "inner $INPUT_GREETING" >> "{marker_path}"
+      shell: bash
+"#
+            ),
+        )
+        .unwrap();
+
+        let outer_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            outer_dir.path().join("action.yml"),
+            format!(
+                r#"name: Outer
+runs:
+  using: composite
+  steps:
+    - run: echo outer >> "{marker_path}"
+      shell: bash
+    - uses: ./.github/actions/inner
+      with:
+        greeting: hello
+    - if: false
+      run: echo skipped >> "{marker_path}"
+      shell: bash
+"#
+            ),
+        )
+        .unwrap();
+
+        let mut ctx = test_context(work.path());
+        let data = HandlerData {
+            inputs: HashMap::new(),
+            environment: HashMap::new(),
+            action_context: ActionContext {
+                action_directory: outer_dir.path().to_string_lossy().to_string(),
+                action_type: "composite".to_string(),
+                ..ActionContext::default()
+            },
+        };
+
+        CompositeActionHandler::new()
+            .run_async(&mut ctx, &data)
+            .await
+            .unwrap();
+
+        let ran = std::fs::read_to_string(&marker).unwrap();
+        assert_eq!(ran, "outer\ninner hello\n");
+    }
 }
@@ -10,6 +10,7 @@ use runner_common::constants::{CURRENT_PLATFORM, OsPlatform};
 use crate::container::container_info::ContainerInfo;
 use crate::execution_context::ExecutionContext;
 use crate::handlers::handler::{Handler, HandlerData};
+use crate::handlers::step_host::step_process_environment;
 
 /// Handler for Docker container-based actions.
 pub struct ContainerActionHandler;
@@ -85,10 +86,7 @@ impl Handler for ContainerActionHandler {
         };
 
         // Build environment for the container
-        let mut env = context.global().environment_variables.clone();
-        for (k, v) in &context.step_environment {
-            env.insert(k.clone(), v.clone());
-        }
+        let mut env = step_process_environment(context);
         for (key, value) in &data.inputs {
             let env_name = format!("INPUT_{}", key.to_uppercase().replace(' ', "_"));
             env.insert(env_name, value.clone());
@@ -139,6 +137,7 @@ impl Handler for ContainerActionHandler {
             is_job_container: false,
             container_network_alias: None,
             user_mountvolumes: Vec::new(),
+            container_runtime_path: None,
         };
 
         // Create and start the container
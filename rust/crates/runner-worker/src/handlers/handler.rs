@@ -2,8 +2,10 @@
 // Defines the interface for step execution handlers and a factory to create them.
 
 use async_trait::async_trait;
+use runner_common::util::var_util::VarUtil;
 use std::collections::HashMap;
 
+use crate::action_manifest_manager::ActionDefinition;
 use crate::execution_context::ExecutionContext;
 use crate::worker::ActionReference;
 
@@ -48,6 +50,26 @@ pub struct ActionContext {
     pub dockerfile: Option<String>,
 }
 
+impl ActionContext {
+    /// The context for running the main entry point of a loaded action.
+    pub fn from_definition(
+        reference: Option<ActionReference>,
+        action_directory: String,
+        definition: &ActionDefinition,
+    ) -> Self {
+        Self {
+            reference,
+            action_directory,
+            entry_point: definition.runs.main.clone().unwrap_or_default(),
+            action_type: definition.runs.using.clone(),
+            pre_entry_point: definition.runs.pre.clone(),
+            post_entry_point: definition.runs.post.clone(),
+            image: definition.runs.image.clone(),
+            dockerfile: definition.runs.dockerfile.clone(),
+        }
+    }
+}
+
 /// Trait for step execution handlers.
 #[async_trait]
 pub trait Handler: Send + Sync {
@@ -82,10 +104,34 @@ pub trait Handler: Send + Sync {
                 .insert(env_name, value.clone());
         }
 
-        // Merge handler-level environment
+        // Merge handler-level environment, expanding references to job variables
+        let variables = context.global().variables.clone();
         for (key, value) in &data.environment {
-            context.step_environment.insert(key.clone(), value.clone());
+            let value = VarUtil::expand(value, |name| variables.get(name));
+            context.step_environment.insert(key.clone(), value);
+        }
+
+        // github.action_path / action_ref / action_repository for this action.
+        // Only repository actions have a ref and repository of their own.
+        if let Some(mut github) = context.github_context().cloned() {
+            let action = &data.action_context;
+            let (git_ref, repository) = match action.reference {
+                Some(ref r) if matches!(r.repository_type.as_str(), "GitHub" | "") => {
+                    (r.git_ref.as_str(), r.name.as_str())
+                }
+                _ => ("", ""),
+            };
+            github.set_action(&action.action_directory, git_ref, repository);
+            context.set_github_context(github);
         }
+
+        // Standard GITHUB_* and RUNNER_* variables cannot be overridden by the step
+        context
+            .step_environment
+            .extend(super::step_host::github_environment(context));
+        context
+            .step_environment
+            .extend(super::step_host::runner_environment(context));
     }
 }
 
@@ -150,4 +196,42 @@ mod tests {
         };
         assert!(data.inputs.is_empty());
     }
+
+    #[test]
+    fn test_prepare_execution_expands_environment() {
+        use crate::execution_context::Global;
+        use crate::variables::Variables;
+        use runner_common::host_context::HostContext;
+
+        let variables = Variables::new();
+        variables.set("TOOL_ROOT", "/opt/tools", false);
+        variables.set("TOOL_BIN", "${TOOL_ROOT}/bin", false);
+        let global = Global {
+            variables,
+            ..Global::for_test("")
+        };
+        let mut ctx =
+            ExecutionContext::new_root(HostContext::new("Test"), global, "test".to_string());
+
+        let mut environment = HashMap::new();
+        environment.insert("TOOL_PATH".to_string(), "$TOOL_BIN:$UNKNOWN".to_string());
+        let data = HandlerData {
+            inputs: HashMap::new(),
+            environment,
+            action_context: ActionContext::default(),
+        };
+
+        let handler = HandlerFactory::create("script");
+        handler.prepare_execution(&mut ctx, &data);
+
+        let expected = if cfg!(windows) {
+            "$TOOL_BIN:$UNKNOWN"
+        } else {
+            "/opt/tools/bin:$UNKNOWN"
+        };
+        assert_eq!(
+            ctx.step_environment.get("TOOL_PATH").map(String::as_str),
+            Some(expected)
+        );
+    }
 }
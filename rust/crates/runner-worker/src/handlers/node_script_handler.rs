@@ -12,7 +12,9 @@ use runner_common::util::node_util::NodeUtil;
 
 use crate::execution_context::ExecutionContext;
 use crate::handlers::handler::{Handler, HandlerData};
-use crate::handlers::step_host::{DefaultStepHost, StepHost};
+use crate::handlers::step_host::{
+    create_step_host, prepend_path, resolve_working_directory, step_process_environment,
+};
 
 /// Handler for Node.js-based actions (node12, node16, node20, node24).
 pub struct NodeScriptActionHandler;
@@ -39,10 +41,10 @@ impl NodeScriptActionHandler {
         let global = context.global();
         let use_node24_by_default = global
             .feature_manager
-            .is_feature_enabled(constants::node_migration::USE_NODE24_BY_DEFAULT_FLAG);
+            .is_enabled(constants::node_migration::USE_NODE24_BY_DEFAULT_FLAG);
         let require_node24 = global
             .feature_manager
-            .is_feature_enabled(constants::node_migration::REQUIRE_NODE24_FLAG);
+            .is_enabled(constants::node_migration::REQUIRE_NODE24_FLAG);
         drop(global);
 
         let workflow_env = Some(context.global().environment_variables.clone());
@@ -102,10 +104,7 @@ impl Handler for NodeScriptActionHandler {
         context.debug(&format!("Script: {}", script_path));
 
         // Build environment
-        let mut env = context.global().environment_variables.clone();
-        for (k, v) in &context.step_environment {
-            env.insert(k.clone(), v.clone());
-        }
+        let mut env = step_process_environment(context);
 
         // Inject INPUT_* environment variables
         for (key, value) in &data.inputs {
@@ -116,37 +115,24 @@ impl Handler for NodeScriptActionHandler {
         // Inject actions runtime environment variables
         self.inject_runtime_env(context, &mut env);
 
-        // Prepend paths
-        let prepend = context.global().prepend_path.clone();
-        if !prepend.is_empty() {
-            let current_path = env
-                .get(runner_common::constants::PATH_VARIABLE)
-                .cloned()
-                .or_else(|| std::env::var(runner_common::constants::PATH_VARIABLE).ok())
-                .unwrap_or_default();
-
-            let separator = if cfg!(windows) { ";" } else { ":" };
-            let new_path = format!("{}{}{}", prepend.join(separator), separator, current_path);
-            env.insert(
-                runner_common::constants::PATH_VARIABLE.to_string(),
-                new_path,
-            );
-        }
+        // Run on the host or in the job container
+        let step_host = create_step_host(context);
+
+        // Prepend paths added by earlier steps
+        prepend_path(context, &mut env);
 
         // Working directory
-        let working_directory = data
-            .inputs
-            .get("working-directory")
-            .cloned()
-            .unwrap_or_else(|| context.global().workspace_directory.clone());
+        let working_directory = resolve_working_directory(
+            context,
+            data.inputs.get("working-directory").map(String::as_str),
+        )?;
 
         // Execute
-        let step_host = DefaultStepHost::new();
         let step_output = step_host
             .execute_async(
                 &working_directory,
-                &node_binary_str,
-                &script_path,
+                &step_host.resolve_path(&node_binary_str),
+                &step_host.resolve_path(&script_path),
                 &env,
                 context.cancel_token(),
             )
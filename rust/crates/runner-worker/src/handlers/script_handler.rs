@@ -7,9 +7,14 @@ use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::path::Path;
 
+use runner_sdk::WhichUtil;
+
 use crate::execution_context::ExecutionContext;
 use crate::handlers::handler::{Handler, HandlerData};
-use crate::handlers::step_host::{DefaultStepHost, StepHost};
+use crate::handlers::step_host::{
+    create_step_host, job_container, prepend_path, quote_argument, resolve_working_directory,
+    step_process_environment, PathStyle,
+};
 
 /// Script handler for `run:` steps.
 pub struct ScriptHandler;
@@ -45,12 +50,20 @@ impl Handler for ScriptHandler {
         let shell = data
             .inputs
             .get("shell")
+            .filter(|shell| !shell.trim().is_empty())
             .cloned()
-            .unwrap_or_else(|| ScriptHandlerHelpers::get_default_shell());
+            .unwrap_or_else(ScriptHandlerHelpers::get_default_shell_option);
 
         // Parse shell options
         let (shell_command, shell_args, file_extension) =
             ScriptHandlerHelpers::parse_shell_option_string(&shell);
+        let script = ScriptHandlerHelpers::fix_up_script_contents(&shell_command, &script);
+
+        // Determine working directory, failing before anything is written
+        let working_directory = resolve_working_directory(
+            context,
+            data.inputs.get("working-directory").map(String::as_str),
+        )?;
 
         // Write the script to a temp file
         let temp_dir = context.global().temp_directory.clone();
@@ -78,45 +91,32 @@ impl Handler for ScriptHandler {
         context.debug(&format!("Script file: {}", script_file));
         context.debug(&format!("Shell: {} {}", shell_command, shell_args.join(" ")));
 
-        // Build the final command arguments
-        let mut args = shell_args.clone();
-        args.push(script_file.clone());
+        // Run on the host or in the job container
+        let step_host = create_step_host(context);
+        let in_container = job_container(context).is_some();
 
-        let arguments = args.join(" ");
+        // Build the final command arguments
+        let arguments = ScriptHandlerHelpers::format_arguments(
+            &shell_args,
+            &step_host.resolve_path(&script_file),
+        );
 
         // Build environment
-        let mut env = context.global().environment_variables.clone();
-        for (k, v) in &context.step_environment {
-            env.insert(k.clone(), v.clone());
-        }
+        let mut env = step_process_environment(context);
 
-        // Prepend paths
-        let prepend = context.global().prepend_path.clone();
-        if !prepend.is_empty() {
-            let current_path = env
-                .get(runner_common::constants::PATH_VARIABLE)
-                .cloned()
-                .or_else(|| std::env::var(runner_common::constants::PATH_VARIABLE).ok())
-                .unwrap_or_default();
-
-            let separator = if cfg!(windows) { ";" } else { ":" };
-            let new_path = format!("{}{}{}", prepend.join(separator), separator, current_path);
-            env.insert(
-                runner_common::constants::PATH_VARIABLE.to_string(),
-                new_path,
-            );
-        }
+        // Prepend paths added by earlier steps
+        prepend_path(context, &mut env);
 
-        // Determine working directory
-        let working_directory = data
-            .inputs
-            .get("working-directory")
-            .cloned()
-            .unwrap_or_else(|| context.global().workspace_directory.clone());
+        // `shell: python` runs whichever Python the job has set up
+        let shell_command = if shell.trim().eq_ignore_ascii_case("python") && !in_container {
+            let python = ScriptHandlerHelpers::resolve_python(&env);
+            context.debug(&format!("Python interpreter: {}", python));
+            python
+        } else {
+            shell_command
+        };
 
         // Execute via StepHost
-        let step_host = DefaultStepHost::new();
-
         let step_output = step_host
             .execute_async(
                 &working_directory,
@@ -160,67 +160,64 @@ pub struct ScriptHandlerHelpers;
 
 impl ScriptHandlerHelpers {
     /// Get the default shell for the current platform.
+    ///
+    /// `pwsh` on Windows, falling back to Windows PowerShell; `bash` elsewhere,
+    /// falling back to `sh` when bash is not installed.
     pub fn get_default_shell() -> String {
-        if cfg!(windows) {
-            "pwsh".to_string()
+        let (preferred, fallback) = if cfg!(windows) {
+            ("pwsh", "powershell")
         } else {
-            "bash".to_string()
+            ("bash", "sh")
+        };
+        match WhichUtil::which(preferred, false) {
+            Ok(Some(_)) => preferred.to_string(),
+            _ => fallback.to_string(),
         }
     }
 
+    /// The shell option used when a step has no `shell:`.
+    ///
+    /// An implicit bash runs like an explicit `shell: bash`, with `-eo
+    /// pipefail`, so a failing command anywhere in a pipeline fails the step.
+    pub fn get_default_shell_option() -> String {
+        Self::get_default_shell()
+    }
+
     /// Parse a shell option string into (command, args, file_extension).
     ///
-    /// Supported shells:
-    /// - `bash` → ("bash", ["--noprofile", "--norc", "-e", "-o", "pipefail", "{0}"], "sh")
-    /// - `sh` → ("sh", ["-e", "{0}"], "sh")
-    /// - `pwsh` → ("pwsh", ["-command", ". '{0}'"], "ps1")
-    /// - `powershell` → ("powershell", ["-command", ". '{0}'"], "ps1")
-    /// - `python` → ("python", ["{0}"], "py")
-    /// - `cmd` → ("cmd", ["/D", "/E:ON", "/V:OFF", "/S", "/C", "call \"{0}\""], "cmd")
-    /// - Custom → split on whitespace
+    /// The args are an argument template in which `{0}` stands for the
+    /// script file; see `format_arguments`. Supported shells:
+    /// - `bash` → ("bash", ["--noprofile", "--norc", "-eo", "pipefail", "'{0}'"], "sh")
+    /// - `sh` → ("sh", ["-e", "'{0}'"], "sh")
+    /// - `pwsh` → ("pwsh", ["-command", "\". '{0}'\""], "ps1")
+    /// - `powershell` → ("powershell", ["-command", "\". '{0}'\""], "ps1")
+    /// - `python` → ("python3", ["'{0}'"], "py")
+    /// - `cmd` → ("cmd", ["/D", "/E:ON", "/V:OFF", "/S", "/C", "'CALL \"{0}\"'"], "cmd")
+    /// - Custom, e.g. `perl {0}` → the first word is the command and the rest
+    ///   is the argument template
     pub fn parse_shell_option_string(shell: &str) -> (String, Vec<String>, String) {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect();
         match shell.to_lowercase().as_str() {
             "bash" => (
                 "bash".to_string(),
-                vec![
-                    "--noprofile".to_string(),
-                    "--norc".to_string(),
-                    "-e".to_string(),
-                    "-o".to_string(),
-                    "pipefail".to_string(),
-                ],
-                "sh".to_string(),
-            ),
-            "sh" => (
-                "sh".to_string(),
-                vec!["-e".to_string()],
+                args(&["--noprofile", "--norc", "-eo", "pipefail", "'{0}'"]),
                 "sh".to_string(),
             ),
+            "sh" => ("sh".to_string(), args(&["-e", "'{0}'"]), "sh".to_string()),
             "pwsh" => (
                 "pwsh".to_string(),
-                vec!["-command".to_string(), ". ".to_string()],
+                args(&["-command", "\". '{0}'\""]),
                 "ps1".to_string(),
             ),
             "powershell" => (
                 "powershell".to_string(),
-                vec!["-command".to_string(), ". ".to_string()],
+                args(&["-command", "\". '{0}'\""]),
                 "ps1".to_string(),
             ),
-            "python" => (
-                "python3".to_string(),
-                vec![],
-                "py".to_string(),
-            ),
+            "python" => ("python3".to_string(), args(&["'{0}'"]), "py".to_string()),
             "cmd" => (
                 "cmd".to_string(),
-                vec![
-                    "/D".to_string(),
-                    "/E:ON".to_string(),
-                    "/V:OFF".to_string(),
-                    "/S".to_string(),
-                    "/C".to_string(),
-                    "call".to_string(),
-                ],
+                args(&["/D", "/E:ON", "/V:OFF", "/S", "/C", "'CALL \"{0}\"'"]),
                 "cmd".to_string(),
             ),
             _ => {
@@ -238,6 +235,130 @@ impl ScriptHandlerHelpers {
         }
     }
 
+    /// Locate the interpreter for `shell: python`.
+    ///
+    /// In order of preference: the active virtualenv (`VIRTUAL_ENV`), the
+    /// installation `setup-python` exported as `pythonLocation` (inside
+    /// `RUNNER_TOOL_CACHE`), the step's `PATH`, then the runner's own `PATH`.
+    /// Falls back to a bare `python3`.
+    pub fn resolve_python(env: &HashMap<String, String>) -> String {
+        let runner_path = std::env::var(PathStyle::current().variable).ok();
+        Self::find_python(env, runner_path.as_deref())
+    }
+
+    fn find_python(env: &HashMap<String, String>, runner_path: Option<&str>) -> String {
+        let style = PathStyle::current();
+        let names: &[&str] = if cfg!(windows) {
+            &["python", "python3"]
+        } else {
+            &["python3", "python"]
+        };
+
+        // Installations keep the interpreter in bin/, or on Windows in the
+        // root (setup-python) or Scripts\ (a virtualenv)
+        let mut install_dirs = Vec::new();
+        for name in ["VIRTUAL_ENV", "pythonLocation"] {
+            if let Some(root) = env.get(name).filter(|root| !root.is_empty()) {
+                let root = Path::new(root);
+                if cfg!(windows) {
+                    install_dirs.push(root.join("Scripts"));
+                    install_dirs.push(root.to_path_buf());
+                } else {
+                    install_dirs.push(root.join("bin"));
+                }
+            }
+        }
+        let install_path = install_dirs
+            .iter()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join(&style.separator.to_string());
+        let step_path = env
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(style.variable))
+            .map(|(_, value)| value.as_str());
+
+        for path_var in [Some(install_path.as_str()), step_path, runner_path]
+            .into_iter()
+            .flatten()
+        {
+            for name in names {
+                if let Some(found) = WhichUtil::which_in(name, path_var) {
+                    return found.to_string_lossy().to_string();
+                }
+            }
+        }
+        "python3".to_string()
+    }
+
+    /// Substitute the script file into an argument template.
+    ///
+    /// Every `{0}` is replaced by the path, escaped for the quotes around it
+    /// so that splitting the arguments yields the path unchanged: spaces,
+    /// quotes and Windows backslashes included. A bare `{0}` is quoted. A
+    /// template without `{0}` gets the path appended as the last argument.
+    pub fn format_arguments(args: &[String], script_file: &str) -> String {
+        let template = args.join(" ");
+        if !template.contains("{0}") {
+            let quoted = quote_argument(script_file);
+            return if template.is_empty() {
+                quoted
+            } else {
+                format!("{} {}", template, quoted)
+            };
+        }
+
+        // Track the quoting the same way the arguments are split
+        let mut formatted = String::with_capacity(template.len() + script_file.len());
+        let (mut in_single_quote, mut in_double_quote, mut escape_next) = (false, false, false);
+        let mut rest = template.as_str();
+        while let Some(index) = rest.find("{0}") {
+            for ch in rest[..index].chars() {
+                if escape_next {
+                    escape_next = false;
+                    continue;
+                }
+                match ch {
+                    '\\' if !in_single_quote => escape_next = true,
+                    '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+                    '"' if !in_single_quote => in_double_quote = !in_double_quote,
+                    _ => {}
+                }
+            }
+            formatted.push_str(&rest[..index]);
+            if in_single_quote {
+                // Close the quotes around an escaped single quote
+                formatted.push_str(&script_file.replace('\'', "'\\''"));
+            } else if in_double_quote {
+                formatted.push_str(&script_file.replace('\\', "\\\\").replace('"', "\\\""));
+            } else {
+                formatted.push_str(&quote_argument(script_file));
+            }
+            rest = &rest[index + 3..];
+        }
+        formatted.push_str(rest);
+        formatted
+    }
+
+    /// Adjust the script body for the shell that runs it.
+    ///
+    /// PowerShell does not stop on errors or propagate a native command's
+    /// exit code by default, so the script is made to do both.
+    pub fn fix_up_script_contents(shell_command: &str, script: &str) -> String {
+        let basename = Path::new(shell_command)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(shell_command);
+        match basename.to_lowercase().as_str() {
+            "pwsh" | "powershell" => format!(
+                "$ErrorActionPreference = 'stop'\n{}\n\
+                 if ((Test-Path -LiteralPath variable:\\LASTEXITCODE)) {{ exit $LASTEXITCODE }}\n",
+                script
+            ),
+            _ => script.to_string(),
+        }
+    }
+
     /// Get the script file extension for a given shell command.
     pub fn get_script_file_extension(shell: &str) -> String {
         let basename = Path::new(shell)
@@ -292,6 +413,295 @@ mod tests {
         assert_eq!(ext, "sh"); // defaults for unknown
     }
 
+    fn test_context(temp_directory: &str) -> ExecutionContext {
+        use crate::execution_context::Global;
+        use runner_common::host_context::HostContext;
+
+        let global = Global::for_test(temp_directory);
+        ExecutionContext::new_root(HostContext::new("Test"), global, "test".to_string())
+    }
+
+    async fn run_script(script: &str, shell: Option<&str>) -> ExecutionContext {
+        let temp = tempfile::tempdir().unwrap();
+        let mut context = test_context(temp.path().to_str().unwrap());
+        let mut inputs = HashMap::new();
+        inputs.insert("script".to_string(), script.to_string());
+        if let Some(shell) = shell {
+            inputs.insert("shell".to_string(), shell.to_string());
+        }
+        let data = HandlerData {
+            inputs,
+            environment: HashMap::new(),
+            action_context: Default::default(),
+        };
+        ScriptHandler::new()
+            .run_async(&mut context, &data)
+            .await
+            .unwrap();
+        context
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_pipeline_fails_step_under_default_bash() {
+        if ScriptHandlerHelpers::get_default_shell() != "bash" {
+            return;
+        }
+        let context = run_script("false | true\necho unreachable", None).await;
+        assert_eq!(
+            context.result(),
+            Some(runner_common::util::task_result_util::TaskResult::Failed)
+        );
+        assert!(!context.log_lines().iter().any(|line| line == "unreachable"));
+
+        let context = run_script("true | true", None).await;
+        assert_eq!(context.result(), None);
+    }
+
+    #[cfg(unix)]
+    fn fake_python(dir: &Path) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::create_dir_all(dir).unwrap();
+        let python = dir.join("python3");
+        std::fs::write(&python, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&python, std::fs::Permissions::from_mode(0o755)).unwrap();
+        python.to_string_lossy().to_string()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_python_prefers_active_installation() {
+        let root = tempfile::tempdir().unwrap();
+        let venv = fake_python(&root.path().join("venv/bin"));
+        let tool_cache = fake_python(&root.path().join("toolcache/Python/3.12.1/x64/bin"));
+        let on_path = fake_python(&root.path().join("usr/bin"));
+
+        let mut env = HashMap::new();
+        env.insert(
+            "PATH".to_string(),
+            root.path().join("usr/bin").to_string_lossy().to_string(),
+        );
+        assert_eq!(ScriptHandlerHelpers::find_python(&env, None), on_path);
+
+        env.insert(
+            "pythonLocation".to_string(),
+            root.path()
+                .join("toolcache/Python/3.12.1/x64")
+                .to_string_lossy()
+                .to_string(),
+        );
+        assert_eq!(ScriptHandlerHelpers::find_python(&env, None), tool_cache);
+
+        env.insert(
+            "VIRTUAL_ENV".to_string(),
+            root.path().join("venv").to_string_lossy().to_string(),
+        );
+        assert_eq!(ScriptHandlerHelpers::find_python(&env, None), venv);
+
+        assert_eq!(
+            ScriptHandlerHelpers::find_python(&HashMap::new(), Some("/nonexistent")),
+            "python3"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_python_script_exit_code_propagates() {
+        if WhichUtil::which("python3", false).ok().flatten().is_none() {
+            return;
+        }
+        let script = "import sys\nprint('from ' + 'python')\nsys.exit(3)\n";
+        let context = run_script(script, Some("python")).await;
+        assert_eq!(
+            context.result(),
+            Some(runner_common::util::task_result_util::TaskResult::Failed)
+        );
+        let lines = context.log_lines();
+        assert!(
+            lines.iter().any(|line| line == "from python"),
+            "{:?}",
+            lines
+        );
+        assert!(lines
+            .iter()
+            .any(|line| line.ends_with("Process completed with exit code 3.")));
+
+        let context = run_script("print('ok')", Some("python")).await;
+        assert_eq!(context.result(), None);
+    }
+
+    #[tokio::test]
+    async fn test_missing_working_directory_fails_step() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut context = test_context(temp.path().to_str().unwrap());
+        let mut inputs = HashMap::new();
+        inputs.insert("script".to_string(), "echo hi".to_string());
+        inputs.insert(
+            "working-directory".to_string(),
+            "does-not-exist".to_string(),
+        );
+        let data = HandlerData {
+            inputs,
+            environment: HashMap::new(),
+            action_context: Default::default(),
+        };
+
+        let err = ScriptHandler::new()
+            .run_async(&mut context, &data)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("The working-directory 'does-not-exist' does not exist"),
+            "{}",
+            err
+        );
+        // No script file was left behind
+        assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_step_fails_with_the_script_exit_code() {
+        let context = run_script("echo before\nexit 3", Some("bash")).await;
+        assert_eq!(
+            context.result(),
+            Some(runner_common::util::task_result_util::TaskResult::Failed)
+        );
+        assert!(context
+            .log_lines()
+            .iter()
+            .any(|line| line.ends_with("Process completed with exit code 3.")));
+    }
+
+    fn command_line(shell: &str, script_file: &str) -> String {
+        let (cmd, args, _) = ScriptHandlerHelpers::parse_shell_option_string(shell);
+        format!(
+            "{} {}",
+            cmd,
+            ScriptHandlerHelpers::format_arguments(&args, script_file)
+        )
+    }
+
+    #[test]
+    fn test_built_in_shell_command_lines() {
+        assert_eq!(
+            command_line("bash", "/tmp/s.sh"),
+            "bash --noprofile --norc -eo pipefail '/tmp/s.sh'"
+        );
+        assert_eq!(command_line("sh", "/tmp/s.sh"), "sh -e '/tmp/s.sh'");
+        assert_eq!(
+            command_line("pwsh", "/tmp/s.ps1"),
+            "pwsh -command \". '/tmp/s.ps1'\""
+        );
+        assert_eq!(
+            command_line("PowerShell", "/tmp/s.ps1"),
+            "powershell -command \". '/tmp/s.ps1'\""
+        );
+        assert_eq!(command_line("python", "/tmp/s.py"), "python3 '/tmp/s.py'");
+        assert_eq!(
+            command_line("cmd", r"C:\temp\s.cmd"),
+            r#"cmd /D /E:ON /V:OFF /S /C 'CALL "C:\temp\s.cmd"'"#
+        );
+    }
+
+    #[test]
+    fn test_custom_shell_template() {
+        let (cmd, args, ext) = ScriptHandlerHelpers::parse_shell_option_string("perl {0}");
+        assert_eq!(cmd, "perl");
+        assert_eq!(ext, "pl");
+        assert_eq!(
+            ScriptHandlerHelpers::format_arguments(&args, "/tmp/s.pl"),
+            "'/tmp/s.pl'"
+        );
+        assert_eq!(
+            command_line("/usr/bin/env ruby --disable-gems {0} extra", "/tmp/s.sh"),
+            "/usr/bin/env ruby --disable-gems '/tmp/s.sh' extra"
+        );
+        // Without `{0}` the script file is appended
+        assert_eq!(
+            command_line("/usr/bin/env ruby", "/tmp/s.sh"),
+            "/usr/bin/env ruby '/tmp/s.sh'"
+        );
+    }
+
+    #[test]
+    fn test_script_path_is_escaped_for_its_quotes() {
+        // Backslashes are literal in single quotes but not in double quotes
+        assert_eq!(
+            command_line("pwsh", r"C:\Users\me\s.ps1"),
+            r#"pwsh -command ". 'C:\\Users\\me\\s.ps1'""#
+        );
+        assert_eq!(
+            command_line("bash", r"C:\Users\me\s.sh"),
+            r"bash --noprofile --norc -eo pipefail 'C:\Users\me\s.sh'"
+        );
+        assert_eq!(
+            command_line("bash", "/tmp/it's here/s.sh"),
+            r"bash --noprofile --norc -eo pipefail '/tmp/it'\''s here/s.sh'"
+        );
+        assert_eq!(
+            command_line("perl {0}", "/tmp/my dir/s.pl"),
+            "perl '/tmp/my dir/s.pl'"
+        );
+        assert_eq!(
+            command_line(r#"perl "{0}""#, r#"/tmp/a"b\s.pl"#),
+            r#"perl "/tmp/a\"b\\s.pl""#
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_script_runs_from_path_with_spaces_and_quotes() {
+        let root = tempfile::tempdir().unwrap();
+        let temp = root.path().join("it's a dir");
+        std::fs::create_dir_all(&temp).unwrap();
+
+        for shell in ["bash", "sh {0}"] {
+            let mut context = test_context(temp.to_str().unwrap());
+            let data = HandlerData {
+                inputs: HashMap::from([
+                    ("script".to_string(), "echo ran".to_string()),
+                    ("shell".to_string(), shell.to_string()),
+                ]),
+                environment: HashMap::new(),
+                action_context: Default::default(),
+            };
+            ScriptHandler::new()
+                .run_async(&mut context, &data)
+                .await
+                .unwrap();
+            assert_eq!(context.result(), None, "{}", shell);
+            assert!(
+                context.log_lines().iter().any(|line| line == "ran"),
+                "{}: {:?}",
+                shell,
+                context.log_lines()
+            );
+        }
+    }
+
+    #[test]
+    fn test_default_shell_option() {
+        let option = ScriptHandlerHelpers::get_default_shell_option();
+        if cfg!(windows) {
+            assert!(option == "pwsh" || option == "powershell", "{}", option);
+        } else {
+            assert!(option == "bash" || option == "sh", "{}", option);
+        }
+    }
+
+    #[test]
+    fn test_fix_up_powershell_script() {
+        let script = ScriptHandlerHelpers::fix_up_script_contents("pwsh", "Write-Host hi");
+        assert!(script.starts_with("$ErrorActionPreference = 'stop'\nWrite-Host hi\n"));
+        assert!(script.contains("exit $LASTEXITCODE"));
+        assert_eq!(
+            ScriptHandlerHelpers::fix_up_script_contents("bash", "echo hi"),
+            "echo hi"
+        );
+    }
+
     #[test]
     fn test_get_script_file_extension() {
         assert_eq!(ScriptHandlerHelpers::get_script_file_extension("bash"), "sh");
@@ -3,12 +3,19 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use tokio_util::sync::CancellationToken;
 
+use runner_common::constants;
+use runner_common::util::encoding_util::EncodingUtil;
+use runner_sdk::PathUtil;
 use runner_sdk::ProcessInvoker;
 use runner_sdk::TraceWriter;
 
+use crate::container::container_info::ContainerInfo;
+use crate::execution_context::ExecutionContext;
+
 /// Result of a step execution including the exit code and captured output lines.
 pub struct StepHostOutput {
     /// The process exit code.
@@ -23,6 +30,11 @@ pub struct StepHostOutput {
 /// `ContainerStepHost` runs processes inside a Docker container via `docker exec`.
 #[async_trait]
 pub trait StepHost: Send + Sync {
+    /// Translate a host path to the path the process sees.
+    fn resolve_path(&self, path: &str) -> String {
+        path.to_string()
+    }
+
     /// Execute a process.
     ///
     /// Returns the exit code and all captured output lines.
@@ -73,7 +85,17 @@ impl StepHost for DefaultStepHost {
         cancel_token: CancellationToken,
     ) -> Result<StepHostOutput> {
         let trace = std::sync::Arc::new(StepHostTraceWriter);
-        let mut invoker = ProcessInvoker::new(trace);
+        let encoding = EncodingUtil::output_encoding(environment).unwrap_or_else(|name| {
+            tracing::warn!(
+                target: "step_host",
+                "Unsupported output encoding '{}', decoding output as UTF-8",
+                name
+            );
+            encoding_rs::UTF_8
+        });
+        let mut invoker = ProcessInvoker::new(trace)
+            .with_output_encoding(encoding)
+            .with_pty(pty_requested(environment));
 
         // Take the output receivers so we can capture lines
         let mut stdout_rx = invoker.take_stdout_receiver();
@@ -135,130 +157,790 @@ impl StepHost for DefaultStepHost {
     }
 }
 
+/// Whether `ACTIONS_RUNNER_USE_PTY` in `environment`, else in the runner's
+/// own environment, asks for steps to run attached to a pseudo-terminal.
+fn pty_requested(environment: &HashMap<String, String>) -> bool {
+    environment
+        .get(constants::variables::agent::USE_PTY)
+        .cloned()
+        .or_else(|| std::env::var(constants::variables::agent::USE_PTY).ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"))
+}
+
 /// Container step host - runs processes inside a Docker container via `docker exec`.
+///
+/// Host paths in the working directory and environment are translated to
+/// their mount points inside the container. Environment values never appear
+/// on the `docker exec` command line: only the names are passed with `-e`,
+/// and docker reads the values from its own environment.
 pub struct ContainerStepHost {
     container_id: String,
+    container: ContainerInfo,
 }
 
 impl ContainerStepHost {
     pub fn new(container_id: String) -> Self {
-        Self { container_id }
+        let mut container = ContainerInfo::new("");
+        container.container_id = Some(container_id.clone());
+        Self {
+            container_id,
+            container,
+        }
     }
-}
 
-#[async_trait]
-impl StepHost for ContainerStepHost {
-    async fn execute_async(
+    /// A step host for a started container; `None` until it has an ID.
+    pub fn for_container(container: &ContainerInfo) -> Option<Self> {
+        let container_id = container.container_id.clone()?;
+        Some(Self {
+            container_id,
+            container: container.clone(),
+        })
+    }
+
+    /// Translate the host paths in a step's environment to container paths.
+    pub fn container_environment(
+        &self,
+        environment: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        environment
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.clone(),
+                    self.container.translate_to_container_path(value),
+                )
+            })
+            .collect()
+    }
+
+    /// Build the `docker exec` argument string for running `file_name` with
+    /// `arguments` in the container.
+    ///
+    /// `arguments` is appended as given, so paths in it must already be
+    /// container paths (see `StepHost::resolve_path`).
+    pub fn build_exec_arguments(
         &self,
         working_directory: &str,
         file_name: &str,
         arguments: &str,
         environment: &HashMap<String, String>,
-        cancel_token: CancellationToken,
-    ) -> Result<StepHostOutput> {
-        let trace = std::sync::Arc::new(StepHostTraceWriter);
+    ) -> String {
+        let mut docker_args = vec!["exec".to_string(), "-i".to_string()];
 
-        // Build docker exec command
-        let mut docker_args = vec![
-            "exec".to_string(),
-        ];
-
-        // Add environment variables
-        for (key, value) in environment {
+        // Only the names; the values come from the docker process environment
+        for key in environment.keys().collect::<BTreeSet<_>>() {
             docker_args.push("-e".to_string());
-            docker_args.push(format!("{}={}", key, value));
+            docker_args.push(quote_argument(key));
         }
 
-        // Set working directory
         if !working_directory.is_empty() {
             docker_args.push("-w".to_string());
-            docker_args.push(working_directory.to_string());
+            docker_args.push(quote_argument(&self.resolve_path(working_directory)));
         }
 
-        // Container ID
-        docker_args.push(self.container_id.clone());
-
-        // Command to execute
-        docker_args.push(file_name.to_string());
-        if !arguments.is_empty() {
-            for arg in arguments.split_whitespace() {
-                docker_args.push(arg.to_string());
-            }
+        docker_args.push(quote_argument(&self.container_id));
+        docker_args.push(quote_argument(file_name));
+        if !arguments.trim().is_empty() {
+            docker_args.push(arguments.trim().to_string());
         }
 
-        let docker_arguments = docker_args.join(" ");
-
-        let mut invoker = ProcessInvoker::new(trace);
-
-        // Capture output
-        let mut stdout_rx = invoker.take_stdout_receiver();
-        let mut stderr_rx = invoker.take_stderr_receiver();
-        let output_lines = std::sync::Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        docker_args.join(" ")
+    }
+}
 
-        let out_lines = output_lines.clone();
-        let stdout_task = tokio::spawn(async move {
-            if let Some(ref mut rx) = stdout_rx {
-                while let Some(event) = rx.recv().await {
-                    tracing::info!(target: "step_host", "{}", event.data);
-                    out_lines.lock().unwrap().push(event.data);
-                }
-            }
-        });
+#[async_trait]
+impl StepHost for ContainerStepHost {
+    fn resolve_path(&self, path: &str) -> String {
+        self.container.translate_to_container_path(path)
+    }
 
-        let err_lines = output_lines.clone();
-        let stderr_task = tokio::spawn(async move {
-            if let Some(ref mut rx) = stderr_rx {
-                while let Some(event) = rx.recv().await {
-                    tracing::info!(target: "step_host", "{}", event.data);
-                    err_lines.lock().unwrap().push(event.data);
-                }
-            }
-        });
+    async fn execute_async(
+        &self,
+        working_directory: &str,
+        file_name: &str,
+        arguments: &str,
+        environment: &HashMap<String, String>,
+        cancel_token: CancellationToken,
+    ) -> Result<StepHostOutput> {
+        let docker_arguments =
+            self.build_exec_arguments(working_directory, file_name, arguments, environment);
+        let docker_environment = self.container_environment(environment);
 
-        let exit_code = invoker
-            .execute(
+        DefaultStepHost::new()
+            .execute_async(
                 "",
                 "docker",
                 &docker_arguments,
-                None,
-                false,
-                false,
+                &docker_environment,
                 cancel_token,
             )
             .await
-            .context("Docker exec failed")?;
+            .context("Docker exec failed")
+    }
+}
 
-        // Drop the invoker to close the channel senders
-        drop(invoker);
+/// Quote an argument so `ProcessInvoker` passes it through as one argument.
+pub(crate) fn quote_argument(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
 
-        let _ = stdout_task.await;
-        let _ = stderr_task.await;
+/// The started job container, when the job runs in one.
+pub fn job_container(context: &ExecutionContext) -> Option<ContainerInfo> {
+    context
+        .global()
+        .container_info
+        .clone()
+        .filter(|container| container.container_id.is_some())
+}
 
-        let lines = match std::sync::Arc::try_unwrap(output_lines) {
-            Ok(mutex) => mutex.into_inner().unwrap(),
-            Err(arc) => arc.lock().unwrap().clone(),
-        };
+/// The step host for a step: the job container when one is running,
+/// otherwise the runner's own machine.
+pub fn create_step_host(context: &ExecutionContext) -> Box<dyn StepHost> {
+    match job_container(context).and_then(|container| ContainerStepHost::for_container(&container))
+    {
+        Some(host) => Box::new(host),
+        None => Box::new(DefaultStepHost::new()),
+    }
+}
 
-        Ok(StepHostOutput {
-            exit_code,
-            output_lines: lines,
+/// Compose the standard `GITHUB_*` variables for a step's process environment
+/// from the github context, the job globals and the step's file command paths.
+///
+/// Empty values are left out so they do not mask anything the step sets itself.
+pub fn github_environment(context: &ExecutionContext) -> HashMap<String, String> {
+    let mut env = HashMap::new();
+
+    if let Some(github) = context.github_context() {
+        let values = [
+            ("GITHUB_SHA", &github.sha),
+            ("GITHUB_REF", &github.git_ref),
+            ("GITHUB_REF_NAME", &github.ref_name),
+            ("GITHUB_REF_TYPE", &github.ref_type),
+            ("GITHUB_HEAD_REF", &github.head_ref),
+            ("GITHUB_BASE_REF", &github.base_ref),
+            ("GITHUB_RUN_ID", &github.run_id),
+            ("GITHUB_RUN_NUMBER", &github.run_number),
+            ("GITHUB_RUN_ATTEMPT", &github.run_attempt),
+            ("GITHUB_ACTOR", &github.actor),
+            ("GITHUB_TRIGGERING_ACTOR", &github.triggering_actor),
+            ("GITHUB_REPOSITORY", &github.repository),
+            ("GITHUB_REPOSITORY_OWNER", &github.repository_owner),
+            ("GITHUB_REPOSITORY_ID", &github.repository_id),
+            ("GITHUB_REPOSITORY_OWNER_ID", &github.repository_owner_id),
+            ("GITHUB_EVENT_NAME", &github.event_name),
+            ("GITHUB_WORKFLOW", &github.workflow),
+            ("GITHUB_WORKFLOW_REF", &github.workflow_ref),
+            ("GITHUB_WORKFLOW_SHA", &github.workflow_sha),
+            ("GITHUB_SERVER_URL", &github.server_url),
+            ("GITHUB_API_URL", &github.api_url),
+            ("GITHUB_GRAPHQL_URL", &github.graphql_url),
+            ("GITHUB_JOB", &github.job),
+            ("GITHUB_RETENTION_DAYS", &github.retention_days),
+            ("GITHUB_WORKSPACE", &github.workspace),
+            ("GITHUB_ACTION_PATH", &github.action_path),
+            ("GITHUB_ACTION_REF", &github.action_ref),
+            ("GITHUB_ACTION_REPOSITORY", &github.action_repository),
+        ];
+        for (name, value) in values {
+            if !value.is_empty() {
+                env.insert(name.to_string(), value.clone());
+            }
+        }
+        if !github.git_ref.is_empty() {
+            env.insert(
+                "GITHUB_REF_PROTECTED".to_string(),
+                github.ref_protected.to_string(),
+            );
+        }
+    }
+
+    // The job's workspace directory is authoritative for GITHUB_WORKSPACE
+    let workspace = context.global().workspace_directory.clone();
+    if !workspace.is_empty() {
+        env.insert("GITHUB_WORKSPACE".to_string(), workspace);
+    }
+
+    // GITHUB_OUTPUT, GITHUB_ENV, GITHUB_PATH, GITHUB_STATE, GITHUB_STEP_SUMMARY
+    for (name, path) in &context.file_command_paths {
+        env.insert(name.clone(), path.clone());
+    }
+
+    env
+}
+
+/// Merge the environment for a step's process.
+///
+/// Later layers win on overlapping keys:
+/// 1. job-level `env`
+/// 2. the step's own environment: step `env`, `INPUT_*`, and finally the
+///    `GITHUB_*` / `RUNNER_*` defaults that a step cannot override (see
+///    `Handler::prepare_execution`)
+///
+/// `ProcessInvoker` applies the result in sorted key order.
+pub fn step_process_environment(context: &ExecutionContext) -> HashMap<String, String> {
+    let mut env = context.global().environment_variables.clone();
+    env.extend(
+        context
+            .step_environment
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone())),
+    );
+    env
+}
+
+/// How a platform names and separates the entries of its `PATH` variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathStyle {
+    pub variable: &'static str,
+    pub separator: char,
+    /// Whether environment variable names ignore case.
+    pub case_insensitive: bool,
+}
+
+impl PathStyle {
+    pub const WINDOWS: PathStyle = PathStyle {
+        variable: "Path",
+        separator: ';',
+        case_insensitive: true,
+    };
+
+    pub const UNIX: PathStyle = PathStyle {
+        variable: "PATH",
+        separator: ':',
+        case_insensitive: false,
+    };
+
+    /// The style of the platform the runner is built for.
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Self::WINDOWS
+        } else {
+            Self::UNIX
+        }
+    }
+}
+
+/// Prepend the paths added through `add-path` and `GITHUB_PATH` to a step's
+/// process environment.
+///
+/// In the job container they go in front of the container's own `PATH`,
+/// not the runner's. When that is unknown and the step sets no `PATH`, the
+/// container's `PATH` is left alone rather than replaced.
+pub fn prepend_path(context: &ExecutionContext, env: &mut HashMap<String, String>) {
+    let paths = &context.global().prepend_path;
+    match job_container(context) {
+        Some(container) => {
+            let style = PathStyle::UNIX;
+            if container.container_runtime_path.is_none() && !env.contains_key(style.variable) {
+                return;
+            }
+            prepend_path_with_style(env, paths, style, container.container_runtime_path);
+        }
+        None => {
+            let style = PathStyle::current();
+            prepend_path_with_style(env, paths, style, std::env::var(style.variable).ok());
+        }
+    }
+}
+
+/// Prepend `paths` to the `PATH` variable in `env`, falling back to
+/// `process_path` when `env` has none.
+///
+/// The most recently added path comes first and repeated paths appear once.
+/// Where variable names ignore case, an entry such as a step-level `PATH` on
+/// Windows is replaced by the platform's spelling instead of being left next
+/// to it, since only one of the two would reach the process.
+pub fn prepend_path_with_style(
+    env: &mut HashMap<String, String>,
+    paths: &[String],
+    style: PathStyle,
+    process_path: Option<String>,
+) {
+    if paths.is_empty() {
+        return;
+    }
+
+    let existing_key = env
+        .keys()
+        .find(|key| {
+            if style.case_insensitive {
+                key.eq_ignore_ascii_case(style.variable)
+            } else {
+                key.as_str() == style.variable
+            }
         })
+        .cloned();
+    let current = existing_key
+        .and_then(|key| env.remove(&key))
+        .or(process_path)
+        .unwrap_or_default();
+
+    let mut entries: Vec<&str> = Vec::with_capacity(paths.len() + 1);
+    for path in paths.iter().rev() {
+        if !entries.contains(&path.as_str()) {
+            entries.push(path);
+        }
+    }
+    // No trailing separator: an empty entry would add the working directory
+    if !current.is_empty() {
+        entries.push(&current);
+    }
+
+    let separator = style.separator.to_string();
+    env.insert(style.variable.to_string(), entries.join(&separator));
+}
+
+/// Resolve a step's `working-directory` input.
+///
+/// Defaults to the workspace. Relative paths are resolved against the
+/// workspace (`GITHUB_WORKSPACE`), not the runner's current directory, and
+/// may not escape it through `..` or symlinks; absolute paths are used as
+/// given. A requested directory must exist, so a typo fails the step with a
+/// clear error instead of an opaque process start failure.
+pub fn resolve_working_directory(
+    context: &ExecutionContext,
+    requested: Option<&str>,
+) -> Result<String> {
+    let workspace = &context.global().workspace_directory;
+    let resolved = match requested.map(str::trim) {
+        Some(dir) if !dir.is_empty() && !Path::new(dir).is_absolute() => {
+            PathUtil::resolve_within(Path::new(workspace), Path::new(dir))
+                .with_context(|| format!("Invalid working-directory '{}'", dir))?
+        }
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => return Ok(workspace.clone()),
+    };
+
+    if !resolved.is_dir() {
+        anyhow::bail!(
+            "The working-directory '{}' does not exist (resolved to '{}')",
+            requested.unwrap_or_default().trim(),
+            resolved.display()
+        );
+    }
+    Ok(resolved.to_string_lossy().to_string())
+}
+
+/// Compose the `RUNNER_*` variables for a step's process environment from
+/// the runner context, mirroring `runner.*` in expressions.
+pub fn runner_environment(context: &ExecutionContext) -> HashMap<String, String> {
+    let mut env = HashMap::new();
+    if let Some(runner) = context.runner_context() {
+        let values = [
+            ("RUNNER_OS", &runner.os),
+            ("RUNNER_ARCH", &runner.arch),
+            ("RUNNER_NAME", &runner.name),
+            ("RUNNER_TEMP", &runner.temp),
+            ("RUNNER_TOOL_CACHE", &runner.tool_cache),
+            ("RUNNER_ENVIRONMENT", &runner.environment),
+            ("RUNNER_DEBUG", &runner.debug),
+        ];
+        for (name, value) in values {
+            if !value.is_empty() {
+                env.insert(name.to_string(), value.clone());
+            }
+        }
     }
+    env
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn paths(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    #[test]
+    fn test_prepend_path_on_windows() {
+        let mut env = HashMap::new();
+        env.insert("PATH".to_string(), r"C:\Windows\system32".to_string());
+        prepend_path_with_style(
+            &mut env,
+            &paths(&[r"C:\tools\node", r"C:\tools\go"]),
+            PathStyle::WINDOWS,
+            Some(r"C:\ignored".to_string()),
+        );
+
+        assert_eq!(env.len(), 1);
+        assert_eq!(
+            env.get("Path").map(String::as_str),
+            Some(r"C:\tools\go;C:\tools\node;C:\Windows\system32")
+        );
+
+        let mut env = HashMap::new();
+        prepend_path_with_style(
+            &mut env,
+            &paths(&[r"C:\tools\node"]),
+            PathStyle::WINDOWS,
+            Some(r"C:\Windows".to_string()),
+        );
+        assert_eq!(
+            env.get("Path").map(String::as_str),
+            Some(r"C:\tools\node;C:\Windows")
+        );
+    }
+
+    #[test]
+    fn test_prepend_path_on_unix() {
+        let mut env = HashMap::new();
+        env.insert("Path".to_string(), "unrelated".to_string());
+        prepend_path_with_style(
+            &mut env,
+            &paths(&["/opt/a", "/opt/b", "/opt/a"]),
+            PathStyle::UNIX,
+            Some("/usr/bin:/bin".to_string()),
+        );
+        assert_eq!(
+            env.get("PATH").map(String::as_str),
+            Some("/opt/a:/opt/b:/usr/bin:/bin")
+        );
+        assert_eq!(env.get("Path").map(String::as_str), Some("unrelated"));
+
+        // An empty PATH gets no trailing separator
+        let mut env = HashMap::new();
+        prepend_path_with_style(&mut env, &paths(&["/opt/a"]), PathStyle::UNIX, None);
+        assert_eq!(env.get("PATH").map(String::as_str), Some("/opt/a"));
+
+        let mut env = HashMap::new();
+        prepend_path_with_style(&mut env, &[], PathStyle::UNIX, Some("/bin".to_string()));
+        assert!(env.is_empty());
+    }
+
+    #[test]
+    fn test_current_path_style_matches_path_variable() {
+        assert_eq!(
+            PathStyle::current().variable,
+            runner_common::constants::PATH_VARIABLE
+        );
+    }
+
     #[test]
     fn test_default_step_host_creation() {
         let host = DefaultStepHost::new();
         let _ = host;
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_default_step_host_uses_pty_when_requested() {
+        let isatty = |use_pty: &str| {
+            let environment = HashMap::from([(
+                constants::variables::agent::USE_PTY.to_string(),
+                use_pty.to_string(),
+            )]);
+            async move {
+                DefaultStepHost::new()
+                    .execute_async(
+                        "",
+                        "sh",
+                        "-c 'if [ -t 1 ]; then echo tty; else echo notty; fi'",
+                        &environment,
+                        CancellationToken::new(),
+                    )
+                    .await
+                    .unwrap()
+                    .output_lines
+            }
+        };
+
+        assert_eq!(isatty("true").await, vec!["tty"]);
+        assert_eq!(isatty("false").await, vec!["notty"]);
+    }
+
     #[test]
     fn test_container_step_host_creation() {
         let host = ContainerStepHost::new("abc123".to_string());
         assert_eq!(host.container_id, "abc123");
     }
+
+    fn test_global() -> crate::execution_context::Global {
+        use crate::execution_context::Global;
+
+        Global {
+            workspace_directory: "/work/hello/hello".to_string(),
+            ..Global::for_test("")
+        }
+    }
+
+    #[test]
+    fn test_github_environment() {
+        use crate::github_context::GitHubContext;
+        use runner_common::host_context::HostContext;
+
+        let mut ctx = ExecutionContext::new_root(
+            HostContext::new("Test"),
+            test_global(),
+            "build".to_string(),
+        );
+        ctx.set_github_context(GitHubContext {
+            sha: "0123abcd".to_string(),
+            git_ref: "refs/heads/main".to_string(),
+            ref_name: "main".to_string(),
+            run_id: "42".to_string(),
+            actor: "octocat".to_string(),
+            repository: "octo/hello".to_string(),
+            event_name: "push".to_string(),
+            server_url: "https://github.com".to_string(),
+            workspace: "/ignored".to_string(),
+            ..Default::default()
+        });
+        ctx.file_command_paths
+            .insert("GITHUB_OUTPUT".to_string(), "/tmp/output.txt".to_string());
+        ctx.file_command_paths
+            .insert("GITHUB_ENV".to_string(), "/tmp/env.txt".to_string());
+        ctx.file_command_paths
+            .insert("GITHUB_PATH".to_string(), "/tmp/path.txt".to_string());
+        ctx.file_command_paths.insert(
+            "GITHUB_STEP_SUMMARY".to_string(),
+            "/tmp/summary.md".to_string(),
+        );
+
+        let env = github_environment(&ctx);
+        let get = |name: &str| env.get(name).map(String::as_str);
+
+        assert_eq!(get("GITHUB_WORKSPACE"), Some("/work/hello/hello"));
+        assert_eq!(get("GITHUB_SHA"), Some("0123abcd"));
+        assert_eq!(get("GITHUB_REF"), Some("refs/heads/main"));
+        assert_eq!(get("GITHUB_REF_NAME"), Some("main"));
+        assert_eq!(get("GITHUB_REF_PROTECTED"), Some("false"));
+        assert_eq!(get("GITHUB_RUN_ID"), Some("42"));
+        assert_eq!(get("GITHUB_ACTOR"), Some("octocat"));
+        assert_eq!(get("GITHUB_REPOSITORY"), Some("octo/hello"));
+        assert_eq!(get("GITHUB_EVENT_NAME"), Some("push"));
+        assert_eq!(get("GITHUB_SERVER_URL"), Some("https://github.com"));
+        assert_eq!(get("GITHUB_OUTPUT"), Some("/tmp/output.txt"));
+        assert_eq!(get("GITHUB_ENV"), Some("/tmp/env.txt"));
+        assert_eq!(get("GITHUB_PATH"), Some("/tmp/path.txt"));
+        assert_eq!(get("GITHUB_STEP_SUMMARY"), Some("/tmp/summary.md"));
+        // Empty fields are not emitted
+        assert_eq!(get("GITHUB_HEAD_REF"), None);
+    }
+
+    #[test]
+    fn test_runner_environment_exports_tool_cache() {
+        use crate::runner_context::RunnerContext;
+        use runner_common::constants::WellKnownDirectory;
+        use runner_common::host_context::HostContext;
+
+        let root = tempfile::tempdir().unwrap();
+        let host = HostContext::new("Test");
+        host.set_root_override(root.path().to_path_buf());
+        let tool_cache = host
+            .get_directory(WellKnownDirectory::Tools)
+            .to_string_lossy()
+            .to_string();
+
+        let mut ctx = ExecutionContext::new_root(host.clone(), test_global(), "build".to_string());
+        ctx.set_runner_context(RunnerContext::from_host(&host, "runner-1", "/work", false));
+
+        let env = runner_environment(&ctx);
+        assert_eq!(env.get("RUNNER_TOOL_CACHE"), Some(&tool_cache));
+        assert_eq!(env.get("RUNNER_NAME").map(String::as_str), Some("runner-1"));
+        assert!(env.contains_key("RUNNER_OS"));
+        assert!(!env.contains_key("RUNNER_DEBUG"));
+        assert_eq!(
+            ctx.runner_context().unwrap().to_value()["tool_cache"],
+            serde_json::Value::String(tool_cache)
+        );
+    }
+
+    #[test]
+    fn test_resolve_working_directory() {
+        use runner_common::host_context::HostContext;
+
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workspace.path().join("app")).unwrap();
+        let canonical = std::fs::canonicalize(workspace.path()).unwrap();
+
+        let mut global = test_global();
+        global.workspace_directory = workspace.path().to_string_lossy().to_string();
+        let ctx = ExecutionContext::new_root(HostContext::new("Test"), global, "build".to_string());
+
+        assert_eq!(
+            resolve_working_directory(&ctx, None).unwrap(),
+            workspace.path().to_string_lossy()
+        );
+        assert_eq!(
+            resolve_working_directory(&ctx, Some("app")).unwrap(),
+            canonical.join("app").to_string_lossy()
+        );
+        assert_eq!(
+            resolve_working_directory(&ctx, Some("./app/../app")).unwrap(),
+            canonical.join("app").to_string_lossy()
+        );
+
+        // Absolute directories are used as given, even outside the workspace
+        let outside = tempfile::tempdir().unwrap();
+        let outside_dir = outside.path().to_string_lossy().to_string();
+        assert_eq!(
+            resolve_working_directory(&ctx, Some(&outside_dir)).unwrap(),
+            outside_dir
+        );
+
+        let err = format!(
+            "{:#}",
+            resolve_working_directory(&ctx, Some("../../etc")).unwrap_err()
+        );
+        assert!(
+            err.contains("Invalid working-directory '../../etc'"),
+            "{}",
+            err
+        );
+        assert!(err.contains("resolves outside of"), "{}", err);
+    }
+
+    #[test]
+    fn test_missing_working_directory_is_an_error() {
+        use runner_common::host_context::HostContext;
+
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("file.txt"), "").unwrap();
+        let mut global = test_global();
+        global.workspace_directory = workspace.path().to_string_lossy().to_string();
+        let ctx = ExecutionContext::new_root(HostContext::new("Test"), global, "build".to_string());
+
+        let err = format!(
+            "{:#}",
+            resolve_working_directory(&ctx, Some("missing/dir")).unwrap_err()
+        );
+        assert!(
+            err.starts_with("The working-directory 'missing/dir' does not exist"),
+            "{}",
+            err
+        );
+
+        // A file is not a directory
+        assert!(resolve_working_directory(&ctx, Some("file.txt")).is_err());
+
+        let missing = workspace.path().join("gone");
+        assert!(resolve_working_directory(&ctx, Some(missing.to_str().unwrap())).is_err());
+    }
+
+    #[test]
+    fn test_step_process_environment_precedence() {
+        use crate::github_context::GitHubContext;
+        use crate::handlers::handler::{ActionContext, Handler, HandlerData};
+        use crate::handlers::script_handler::ScriptHandler;
+        use runner_common::host_context::HostContext;
+
+        let mut global = test_global();
+        global.environment_variables = HashMap::from([
+            ("SHARED".to_string(), "job".to_string()),
+            ("JOB_ONLY".to_string(), "job".to_string()),
+            ("GITHUB_SHA".to_string(), "job-sha".to_string()),
+        ]);
+        let mut ctx =
+            ExecutionContext::new_root(HostContext::new("Test"), global, "build".to_string());
+        ctx.set_github_context(GitHubContext {
+            sha: "0123abcd".to_string(),
+            ..Default::default()
+        });
+
+        let data = HandlerData {
+            inputs: HashMap::new(),
+            environment: HashMap::from([
+                ("SHARED".to_string(), "step".to_string()),
+                ("GITHUB_SHA".to_string(), "step-sha".to_string()),
+            ]),
+            action_context: ActionContext::default(),
+        };
+        ScriptHandler::new().prepare_execution(&mut ctx, &data);
+
+        let env = step_process_environment(&ctx);
+        assert_eq!(env["SHARED"], "step");
+        assert_eq!(env["JOB_ONLY"], "job");
+        assert_eq!(env["GITHUB_SHA"], "0123abcd");
+    }
+
+    fn job_container_info(workspace: &str) -> ContainerInfo {
+        let mut container = ContainerInfo::new("ubuntu:22.04");
+        container.container_id = Some("abc123".to_string());
+        container
+            .path_mappings
+            .insert(workspace.to_string(), "/github/workspace".to_string());
+        container
+    }
+
+    #[test]
+    fn test_container_step_host_translates_workspace_paths() {
+        let host = ContainerStepHost::for_container(&job_container_info("/runner/_work/repo/repo"))
+            .unwrap();
+        let env = HashMap::from([
+            (
+                "GITHUB_WORKSPACE".to_string(),
+                "/runner/_work/repo/repo".to_string(),
+            ),
+            ("MY_SECRET".to_string(), "hunter2".to_string()),
+        ]);
+
+        let script = host.resolve_path("/runner/_work/repo/repo/build.sh");
+        assert_eq!(script, "/github/workspace/build.sh");
+
+        let command = host.build_exec_arguments(
+            "/runner/_work/repo/repo/src",
+            "bash",
+            &format!("-e '{}'", script),
+            &env,
+        );
+        assert_eq!(
+            command,
+            "exec -i -e 'GITHUB_WORKSPACE' -e 'MY_SECRET' -w '/github/workspace/src' 'abc123' 'bash' -e '/github/workspace/build.sh'"
+        );
+        assert!(!command.contains("hunter2"));
+
+        let container_env = host.container_environment(&env);
+        assert_eq!(container_env["GITHUB_WORKSPACE"], "/github/workspace");
+        assert_eq!(container_env["MY_SECRET"], "hunter2");
+    }
+
+    #[test]
+    fn test_prepend_path_in_job_container() {
+        use runner_common::host_context::HostContext;
+
+        let mut global = test_global();
+        global.prepend_path = paths(&["/opt/tool"]);
+        let mut container = job_container_info("/runner/_work/repo/repo");
+        container.container_runtime_path = Some("/usr/local/bin:/usr/bin".to_string());
+        global.container_info = Some(container);
+        let ctx = ExecutionContext::new_root(HostContext::new("Test"), global, "build".to_string());
+
+        let mut env = HashMap::new();
+        prepend_path(&ctx, &mut env);
+        assert_eq!(env["PATH"], "/opt/tool:/usr/local/bin:/usr/bin");
+
+        // Unknown container PATH: nothing to prepend to
+        let mut global = test_global();
+        global.prepend_path = paths(&["/opt/tool"]);
+        global.container_info = Some(job_container_info("/runner/_work/repo/repo"));
+        let ctx = ExecutionContext::new_root(HostContext::new("Test"), global, "build".to_string());
+        let mut env = HashMap::new();
+        prepend_path(&ctx, &mut env);
+        assert!(env.is_empty());
+    }
+
+    #[test]
+    fn test_create_step_host_uses_started_job_container() {
+        use runner_common::host_context::HostContext;
+
+        let mut global = test_global();
+        global.container_info = Some(job_container_info("/runner/_work/repo/repo"));
+        let ctx = ExecutionContext::new_root(HostContext::new("Test"), global, "build".to_string());
+        assert_eq!(
+            create_step_host(&ctx).resolve_path("/runner/_work/repo/repo/a.sh"),
+            "/github/workspace/a.sh"
+        );
+
+        // Not started yet: run on the host
+        let mut global = test_global();
+        let mut container = job_container_info("/runner/_work/repo/repo");
+        container.container_id = None;
+        global.container_info = Some(container);
+        let ctx = ExecutionContext::new_root(HostContext::new("Test"), global, "build".to_string());
+        assert_eq!(
+            create_step_host(&ctx).resolve_path("/runner/_work/repo/repo/a.sh"),
+            "/runner/_work/repo/repo/a.sh"
+        );
+    }
 }
@@ -7,12 +7,14 @@ use std::collections::HashMap;
 
 use runner_common::constants::{self, CURRENT_PLATFORM, OsPlatform};
 use runner_common::util::task_result_util::TaskResult;
+use runner_sdk::IOUtil;
 
-use crate::action_manager::ActionManager;
+use crate::action_manager::{ActionManager, ResolvedAction};
 use crate::action_manifest_manager::ActionManifestManager;
 use crate::container::container_operation_provider::ContainerOperationProvider;
 use crate::execution_context::{ExecutionContext, IStep};
 use crate::handlers::handler::{ActionContext, HandlerData, HandlerFactory};
+use crate::tracking_manager::{CLEANUP_RETRY_ATTEMPTS, CLEANUP_RETRY_DELAY};
 use crate::worker::{AgentJobRequestMessage, JobStep};
 
 /// Manages job initialization and finalization.
@@ -88,7 +90,7 @@ impl JobExtension {
         &self,
         context: &mut ExecutionContext,
         message: &AgentJobRequestMessage,
-        resolved_actions: &HashMap<String, String>,
+        resolved_actions: &HashMap<String, ResolvedAction>,
     ) -> Result<()> {
         for step in &message.steps {
             match step.step_type.as_str() {
@@ -177,7 +179,7 @@ impl JobExtension {
         &self,
         context: &mut ExecutionContext,
         step: &JobStep,
-        resolved_actions: &HashMap<String, String>,
+        resolved_actions: &HashMap<String, ResolvedAction>,
     ) -> Result<()> {
         let action_ref = match step.action_reference() {
             Some(r) => r,
@@ -190,24 +192,60 @@ impl JobExtension {
             }
         };
 
-        // Build cache key
-        let cache_key = if action_ref.path.is_empty() {
-            format!("{}@{}", action_ref.name, action_ref.git_ref)
-        } else {
-            format!(
-                "{}/{}@{}",
-                action_ref.name, action_ref.path, action_ref.git_ref
-            )
-        };
-
-        let action_directory = match resolved_actions.get(&cache_key) {
-            Some(dir) => dir.clone(),
+        let cache_key = ActionManager::build_cache_key(&action_ref);
+        let resolved = match resolved_actions.get(&cache_key) {
+            Some(resolved) => resolved.clone(),
             None => {
                 context.warning(&format!("Action '{}' not resolved.", cache_key));
                 return Ok(());
             }
         };
 
+        let action_directory = match resolved {
+            ResolvedAction::ContainerImage { image } => {
+                // The image is the whole action: no manifest, no pre or post
+                let main_step = ActionStep {
+                    id: step.id.clone(),
+                    display_name: step.display_name.clone(),
+                    condition: step.condition.clone(),
+                    timeout: step.timeout_in_minutes,
+                    continue_on_error: step.continue_on_error,
+                    action_context: ActionContext {
+                        reference: Some(action_ref.clone()),
+                        action_type: "docker".to_string(),
+                        image: Some(image),
+                        ..ActionContext::default()
+                    },
+                    inputs: step.inputs_map(),
+                    environment: step.environment_map(),
+                };
+                context.job_steps.push_back(Box::new(main_step));
+                return Ok(());
+            }
+            ResolvedAction::Local { directory } => {
+                // The manifest is read when the step runs, after the
+                // repository has been checked out. Local actions contribute
+                // no pre or post steps.
+                let main_step = ActionStep {
+                    id: step.id.clone(),
+                    display_name: step.display_name.clone(),
+                    condition: step.condition.clone(),
+                    timeout: step.timeout_in_minutes,
+                    continue_on_error: step.continue_on_error,
+                    action_context: ActionContext {
+                        reference: Some(action_ref.clone()),
+                        action_directory: directory,
+                        ..ActionContext::default()
+                    },
+                    inputs: step.inputs_map(),
+                    environment: step.environment_map(),
+                };
+                context.job_steps.push_back(Box::new(main_step));
+                return Ok(());
+            }
+            ResolvedAction::Repository { directory, .. } => directory,
+        };
+
         // Load action manifest to determine type and entry points
         let definition = match ActionManifestManager::load_action(&action_directory) {
             Ok(def) => def,
@@ -217,17 +255,8 @@ impl JobExtension {
             }
         };
 
-        let action_type = definition.runs.using.clone();
-        let action_context = ActionContext {
-            reference: Some(action_ref.clone()),
-            action_directory: action_directory.clone(),
-            entry_point: definition.runs.main.clone().unwrap_or_default(),
-            action_type: action_type.clone(),
-            pre_entry_point: definition.runs.pre.clone(),
-            post_entry_point: definition.runs.post.clone(),
-            image: definition.runs.image.clone(),
-            dockerfile: definition.runs.dockerfile.clone(),
-        };
+        let action_context =
+            ActionContext::from_definition(Some(action_ref.clone()), action_directory, &definition);
 
         // Create pre step if defined
         if let Some(ref pre_entry) = definition.runs.pre {
@@ -309,7 +338,13 @@ impl JobExtension {
         let temp_dir = context.global().temp_directory.clone();
         if std::path::Path::new(&temp_dir).exists() {
             context.debug(&format!("Cleaning temp directory: {}", temp_dir));
-            let _ = std::fs::remove_dir_all(&temp_dir);
+            if let Err(e) = IOUtil::delete_directory_with_retry(
+                std::path::Path::new(&temp_dir),
+                CLEANUP_RETRY_ATTEMPTS,
+                CLEANUP_RETRY_DELAY,
+            ) {
+                context.warning(&format!("Failed to clean temp directory: {:#}", e));
+            }
         }
 
         context.info("Job finalized.");
@@ -436,12 +471,28 @@ impl IStep for ActionStep {
         &'a self,
         context: &'a mut ExecutionContext,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = anyhow::Result<()>> + Send + 'a>> {
-        let action_type = self.action_context.action_type.clone();
         let inputs = self.inputs.clone();
         let environment = self.environment.clone();
-        let action_context = self.action_context.clone();
+        let mut action_context = self.action_context.clone();
 
         Box::pin(async move {
+            // Local actions are only read once the workspace is populated
+            if action_context.action_type.is_empty() {
+                let definition = ActionManifestManager::load_action(&action_context.action_directory)
+                    .with_context(|| {
+                        format!(
+                            "Can't find an action manifest in '{}'. Did you forget to run actions/checkout before running your local action?",
+                            action_context.action_directory
+                        )
+                    })?;
+                action_context = ActionContext::from_definition(
+                    action_context.reference.take(),
+                    action_context.action_directory.clone(),
+                    &definition,
+                );
+            }
+
+            let action_type = action_context.action_type.clone();
             let handler_data = HandlerData {
                 inputs,
                 environment,
@@ -0,0 +1,238 @@
+// JobPlan – dry run of a job message for `Runner.Worker --validate`.
+// Resolves the job's expression contexts and evaluates every step condition
+// without preparing directories, downloading actions or executing anything.
+//
+// Conditions are evaluated as though every step that runs succeeds, so the
+// plan shows the happy path. Pre and post steps contributed by actions are
+// not part of the plan because they are only known once the actions are
+// downloaded.
+
+use runner_common::constants::WellKnownDirectory;
+use runner_common::host_context::HostContext;
+use runner_common::util::task_result_util::TaskResult;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+use crate::expressions::try_evaluate_condition;
+use crate::job_runner::JobRunner;
+use crate::worker::AgentJobRequestMessage;
+
+/// Whether a planned step would run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepDecision {
+    Run,
+    Skip,
+}
+
+/// The predicted outcome for one step of the job.
+#[derive(Debug, Clone)]
+pub struct PlannedStep {
+    pub id: String,
+    pub display_name: String,
+    /// The condition as evaluated; `success()` when the step has none.
+    pub condition: String,
+    pub decision: StepDecision,
+    /// Why the step would run or be skipped.
+    pub reason: String,
+    /// Whether the condition could not be parsed or evaluated.
+    pub invalid: bool,
+}
+
+/// The steps of a job and whether each would run.
+#[derive(Debug, Clone)]
+pub struct JobPlan {
+    pub job_display_name: String,
+    pub job_id: String,
+    pub steps: Vec<PlannedStep>,
+}
+
+impl JobPlan {
+    /// Build the plan for a job message.
+    ///
+    /// Conditions see the same expression context as during a run, built by
+    /// a root `ExecutionContext` whose directories are never created.
+    pub fn build(host_context: &Arc<HostContext>, message: &AgentJobRequestMessage) -> Self {
+        let work = host_context
+            .get_directory(WellKnownDirectory::Work)
+            .to_string_lossy()
+            .to_string();
+        let mut context = JobRunner::root_context(
+            host_context,
+            message,
+            &work,
+            &work,
+            CancellationToken::new(),
+        );
+
+        let mut steps = Vec::with_capacity(message.steps.len());
+        for step in &message.steps {
+            let expression_context =
+                serde_json::to_value(context.build_expression_context()).unwrap_or_default();
+
+            let condition = if step.condition.trim().is_empty() {
+                "success()".to_string()
+            } else {
+                step.condition.trim().to_string()
+            };
+            let (decision, reason, invalid) = match try_evaluate_condition(
+                &condition,
+                TaskResult::Succeeded,
+                false,
+                &expression_context,
+            ) {
+                Ok(true) => (
+                    StepDecision::Run,
+                    format!("condition '{}' evaluated to true", condition),
+                    false,
+                ),
+                Ok(false) => (
+                    StepDecision::Skip,
+                    format!("condition '{}' evaluated to false", condition),
+                    false,
+                ),
+                Err(e) => (StepDecision::Skip, format!("{:#}", e), true),
+            };
+
+            let result = match decision {
+                StepDecision::Run => TaskResult::Succeeded,
+                StepDecision::Skip => TaskResult::Skipped,
+            };
+            context
+                .steps_context_mut()
+                .record_step(&step.id, result, result, HashMap::new());
+
+            steps.push(PlannedStep {
+                id: step.id.clone(),
+                display_name: step.display_name.clone(),
+                condition,
+                decision,
+                reason,
+                invalid,
+            });
+        }
+
+        Self {
+            job_display_name: message.job_display_name.clone(),
+            job_id: message.job_id.clone(),
+            steps,
+        }
+    }
+
+    /// Whether any step condition could not be parsed or evaluated.
+    pub fn has_invalid_conditions(&self) -> bool {
+        self.steps.iter().any(|step| step.invalid)
+    }
+}
+
+impl fmt::Display for JobPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Job: {} ({})", self.job_display_name, self.job_id)?;
+        for step in &self.steps {
+            let decision = match step.decision {
+                StepDecision::Run => "run ",
+                StepDecision::Skip => "skip",
+            };
+            let name = if step.display_name.is_empty() {
+                &step.id
+            } else {
+                &step.display_name
+            };
+            writeln!(f, "  [{}] {}: {}", decision, name, step.reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_JOB: &str = r#"{
+        "jobId": "job-1",
+        "jobDisplayName": "Build",
+        "contextData": {
+            "github": {
+                "t": 2,
+                "d": [
+                    {"k": "ref", "v": "refs/heads/feature"},
+                    {"k": "event_name", "v": "push"}
+                ]
+            }
+        },
+        "steps": [
+            {"id": "checkout", "displayName": "Checkout", "type": "script", "script": "true"},
+            {"id": "deploy", "displayName": "Deploy", "type": "script", "script": "true",
+             "condition": "github.ref == 'refs/heads/main'"},
+            {"id": "on_push", "displayName": "Push only", "type": "script", "script": "true",
+             "condition": "${{ github.event_name == 'push' }}"},
+            {"id": "notify", "displayName": "Notify", "type": "script", "script": "true",
+             "condition": "failure()"},
+            {"id": "after_deploy", "displayName": "After deploy", "type": "script", "script": "true",
+             "condition": "always() && steps.deploy.outcome == 'skipped'"},
+            {"id": "broken", "displayName": "Broken", "type": "script", "script": "true",
+             "condition": "github.ref == "}
+        ]
+    }"#;
+
+    fn plan() -> JobPlan {
+        let message: AgentJobRequestMessage = serde_json::from_str(SAMPLE_JOB).unwrap();
+        JobPlan::build(&HostContext::new("Test"), &message)
+    }
+
+    #[test]
+    fn test_plan_predicts_run_and_skip() {
+        let plan = plan();
+        let decisions: Vec<(&str, StepDecision)> = plan
+            .steps
+            .iter()
+            .map(|step| (step.id.as_str(), step.decision))
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                ("checkout", StepDecision::Run),
+                ("deploy", StepDecision::Skip),
+                ("on_push", StepDecision::Run),
+                ("notify", StepDecision::Skip),
+                ("after_deploy", StepDecision::Run),
+                ("broken", StepDecision::Skip),
+            ]
+        );
+        assert_eq!(plan.steps[0].condition, "success()");
+    }
+
+    #[test]
+    fn test_plan_reports_invalid_conditions() {
+        let plan = plan();
+        assert!(plan.has_invalid_conditions());
+
+        let broken = plan.steps.iter().find(|step| step.id == "broken").unwrap();
+        assert!(broken.invalid);
+        assert!(
+            broken.reason.starts_with("Invalid condition"),
+            "{}",
+            broken.reason
+        );
+        assert_eq!(plan.steps.iter().filter(|step| step.invalid).count(), 1);
+    }
+
+    #[test]
+    fn test_plan_display() {
+        let output = plan().to_string();
+        assert!(output.starts_with("Job: Build (job-1)\n"), "{}", output);
+        assert!(
+            output.contains(
+                "  [skip] Deploy: condition 'github.ref == 'refs/heads/main'' evaluated to false\n"
+            ),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("  [run ] Checkout: condition 'success()' evaluated to true\n"),
+            "{}",
+            output
+        );
+    }
+}
@@ -6,7 +6,6 @@
 use anyhow::{Context, Result};
 use runner_common::host_context::HostContext;
 use runner_common::util::task_result_util::TaskResult;
-use runner_common::util::var_util::VarUtil;
 use runner_sdk::TraceWriter;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -14,8 +13,11 @@ use tokio_util::sync::CancellationToken;
 
 use crate::execution_context::{ExecutionContext, Global};
 use crate::feature_manager::FeatureManager;
+use crate::github_context::GitHubContext;
 use crate::job_extension::JobExtension;
 use crate::results_client::ResultsClient;
+use crate::run_server::JobCompletion;
+use crate::runner_context::RunnerContext;
 use crate::steps_runner::StepsRunner;
 use crate::tracking_manager::TrackingManager;
 use crate::variables::Variables;
@@ -40,21 +42,18 @@ impl JobRunner {
     /// 4. Delegates to `JobExtension::initialize_job` for step building
     /// 5. Invokes `StepsRunner::run_async` to execute steps
     /// 6. Calls `JobExtension::finalize_job` for cleanup
-    /// 7. Returns the final `TaskResult`
+    /// 7. Returns the final `TaskResult` with per-step results and annotations
     pub async fn run_async(
         &self,
         message: AgentJobRequestMessage,
         cancel_token: CancellationToken,
-    ) -> Result<TaskResult> {
+    ) -> Result<JobCompletion> {
         let trace = self.host_context.get_trace("JobRunner");
         trace.info(&format!(
             "Starting job: {} ({})",
             message.job_display_name, message.job_id
         ));
 
-        // Build Variables from the job message
-        let variables = Variables::from_message(&message, &self.host_context.secret_masker);
-
         // Determine the pipeline directory using TrackingManager
         let tracking_manager = TrackingManager::new(&self.host_context);
         let (pipeline_directory, workspace_directory, _temp_directory) = tracking_manager
@@ -68,55 +67,35 @@ impl JobRunner {
                 (fallback.clone(), format!("{}/workspace", fallback), format!("{}/temp", fallback))
             });
 
-        // Create feature manager
-        let feature_manager = FeatureManager::new(&message);
-
-        // Build Global shared state
-        let global = Global {
-            variables: variables.clone(),
-            endpoints: message.resources.endpoints.clone(),
-            file_table: message.file_table.clone(),
-            environment_variables: message.environment_variables_map(),
-            job_display_name: message.job_display_name.clone(),
-            job_id: message.job_id.clone(),
-            plan_id: message.plan_id(),
-            timeline_id: message.timeline_id(),
-            pipeline_directory: pipeline_directory.clone(),
-            workspace_directory: workspace_directory.clone(),
-            temp_directory: self
-                .host_context
-                .get_directory(runner_common::constants::WellKnownDirectory::Temp)
-                .to_string_lossy()
-                .to_string(),
-            prepend_path: Vec::new(),
-            container_info: None,
-            service_containers: Vec::new(),
-            job_telemetry: Vec::new(),
-            environment_url: None,
-            cancel_token: cancel_token.clone(),
-            feature_manager,
-            write_debug: variables
-                .get("ACTIONS_STEP_DEBUG")
-                .map(|v| v.eq_ignore_ascii_case("true"))
-                .unwrap_or(false),
-        };
-
         // Create the root execution context
-        let mut root_context = ExecutionContext::new_root(
-            Arc::clone(&self.host_context),
-            global,
-            message.job_display_name.clone(),
+        let mut root_context = Self::root_context(
+            &self.host_context,
+            &message,
+            &pipeline_directory,
+            &workspace_directory,
+            cancel_token.clone(),
         );
 
-        // Set runner context
-        self.set_runner_context(&mut root_context);
+        // setup-* actions expect the tool cache to exist
+        if let Err(e) = self.host_context.ensure_tool_cache_directory() {
+            root_context.warning(&format!("Failed to create tool cache directory: {}", e));
+        }
 
         // Initialize job via JobExtension (downloads actions, resolves containers, builds step list)
         let mut job_extension = JobExtension::new();
         if let Err(e) = job_extension.initialize_job(&mut root_context, &message).await {
+            if cancel_token.is_cancelled() {
+                root_context.info("Job was cancelled during initialization.");
+                root_context.complete(TaskResult::Canceled, Some("Job was cancelled"));
+                return Ok(JobCompletion::from_context(
+                    TaskResult::Canceled,
+                    &root_context,
+                ));
+            }
             root_context.error(&format!("Job initialization failed: {:#}", e));
             root_context.complete(TaskResult::Failed, Some("Job initialization failed"));
-            return Ok(root_context.result().unwrap_or(TaskResult::Failed));
+            let result = root_context.result().unwrap_or(TaskResult::Failed);
+            return Ok(JobCompletion::from_context(result, &root_context));
         }
 
         root_context.info("Job initialized successfully.");
@@ -148,6 +127,11 @@ impl JobRunner {
             }
         }
 
+        // A cancelled job is Canceled even if no step observed the cancellation
+        if cancel_token.is_cancelled() {
+            StepsRunner::mark_job_cancelled(&mut root_context);
+        }
+
         // Finalize the job (cleanup)
         job_extension.finalize_job(&mut root_context);
 
@@ -156,46 +140,78 @@ impl JobRunner {
 
         trace.info(&format!("Job completed with result: {}", final_result));
 
-        Ok(final_result)
+        Ok(JobCompletion::from_context(final_result, &root_context))
+    }
+
+    /// Whether `##[debug]` lines are written for the job, and `runner.debug`
+    /// is set: either `ACTIONS_STEP_DEBUG` or `ACTIONS_RUNNER_DEBUG` is true,
+    /// whether set as a secret or as a variable.
+    fn write_debug(variables: &Variables) -> bool {
+        variables.step_debug() || variables.runner_debug()
     }
 
-    /// Populate the runner context with OS, architecture, name, and tool cache info.
-    fn set_runner_context(&self, context: &mut ExecutionContext) {
-        let os = VarUtil::os().to_string();
-        let arch = VarUtil::os_architecture().to_string();
-
-        let tool_cache = self
-            .host_context
-            .get_directory(runner_common::constants::WellKnownDirectory::Tools)
-            .to_string_lossy()
-            .to_string();
-
-        let temp = self
-            .host_context
-            .get_directory(runner_common::constants::WellKnownDirectory::Temp)
-            .to_string_lossy()
-            .to_string();
-
-        let runner_name = context
-            .global()
-            .variables
+    /// Build the job's root execution context from its message: the shared
+    /// `Global` state plus the runner, github and job expression contexts.
+    /// Used both to run the job and to plan it, so it creates no directories.
+    pub(crate) fn root_context(
+        host_context: &Arc<HostContext>,
+        message: &AgentJobRequestMessage,
+        pipeline_directory: &str,
+        workspace_directory: &str,
+        cancel_token: CancellationToken,
+    ) -> ExecutionContext {
+        let variables = Variables::from_message(message, &host_context.secret_masker);
+        let variable_values: HashMap<String, String> = variables
+            .snapshot()
+            .into_iter()
+            .map(|(name, value)| (name, value.value))
+            .collect();
+        let runner_name = variables
             .get("system.runner.name")
             .unwrap_or_else(|| "Hosted Agent".to_string());
+        let write_debug = Self::write_debug(&variables);
 
-        let debug = context.global().write_debug;
-
-        let runner_ctx = crate::runner_context::RunnerContext {
-            os,
-            arch,
-            name: runner_name,
-            tool_cache,
-            temp,
-            debug: if debug { "1".to_string() } else { String::new() },
-            workspace: context.global().workspace_directory.clone(),
-            environment: std::env::var("RUNNER_ENVIRONMENT").unwrap_or_else(|_| "self-hosted".to_string()),
+        let global = Global {
+            variables,
+            endpoints: message.resources.endpoints.clone(),
+            file_table: message.file_table.clone(),
+            environment_variables: message.environment_variables_map(),
+            job_display_name: message.job_display_name.clone(),
+            job_id: message.job_id.clone(),
+            plan_id: message.plan_id(),
+            timeline_id: message.timeline_id(),
+            pipeline_directory: pipeline_directory.to_string(),
+            workspace_directory: workspace_directory.to_string(),
+            temp_directory: host_context
+                .get_directory(runner_common::constants::WellKnownDirectory::Temp)
+                .to_string_lossy()
+                .to_string(),
+            prepend_path: Vec::new(),
+            container_info: None,
+            service_containers: Vec::new(),
+            job_telemetry: Vec::new(),
+            environment_url: None,
+            cancel_token,
+            feature_manager: FeatureManager::from_message(message),
+            write_debug,
         };
 
-        context.set_runner_context(runner_ctx);
+        let mut context = ExecutionContext::new_root(
+            Arc::clone(host_context),
+            global,
+            message.job_display_name.clone(),
+        );
+        context.set_runner_context(RunnerContext::from_host(
+            host_context,
+            &runner_name,
+            workspace_directory,
+            write_debug,
+        ));
+        context.set_github_context(GitHubContext::from_message(message, &variable_values));
+        for (name, value) in message.job_expression_contexts() {
+            context.set_job_context(&name, value);
+        }
+        context
     }
 }
 
@@ -210,4 +226,45 @@ mod tests {
         // Just verify construction doesn't panic
         let _ = runner;
     }
+
+    fn root_context(variables: &str) -> ExecutionContext {
+        let json = format!(
+            r#"{{"jobId":"job-1","jobDisplayName":"Build","variables":{}}}"#,
+            variables
+        );
+        let message: AgentJobRequestMessage = serde_json::from_str(&json).unwrap();
+        JobRunner::root_context(
+            &HostContext::new("Test"),
+            &message,
+            "",
+            "",
+            CancellationToken::new(),
+        )
+    }
+
+    #[test]
+    fn test_step_debug_secret_enables_debug_output() {
+        let mut ctx = root_context(r#"{"ACTIONS_STEP_DEBUG":{"value":"true","isSecret":true}}"#);
+        assert!(ctx.global().write_debug);
+
+        ctx.debug("resolved inputs");
+        assert!(ctx
+            .log_lines()
+            .contains(&"##[debug]resolved inputs".to_string()));
+    }
+
+    #[test]
+    fn test_runner_debug_variable_enables_debug_output() {
+        let ctx = root_context(r#"{"actions_runner_debug":{"value":"1"}}"#);
+        assert!(ctx.global().write_debug);
+    }
+
+    #[test]
+    fn test_debug_output_is_off_by_default() {
+        let mut ctx = root_context(r#"{"ACTIONS_STEP_DEBUG":{"value":"false"}}"#);
+        assert!(!ctx.global().write_debug);
+
+        ctx.debug("resolved inputs");
+        assert!(ctx.log_lines().is_empty());
+    }
 }
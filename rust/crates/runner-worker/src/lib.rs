@@ -18,12 +18,14 @@ pub mod github_context;
 pub mod handlers;
 pub mod issue_matcher;
 pub mod job_extension;
+pub mod job_plan;
 pub mod job_runner;
 pub mod results_client;
 pub mod run_server;
 pub mod runner_context;
 pub mod steps_context;
 pub mod steps_runner;
+pub mod template_token;
 pub mod tracking_manager;
 pub mod variables;
 pub mod worker;
@@ -4,13 +4,20 @@
 // The worker is spawned by the listener with `--pipeIn <path> --pipeOut <path>` arguments.
 // It receives a job message over the IPC pipe, executes all steps, and exits with
 // a return code that encodes the `TaskResult`.
+//
+// For crash-repro, `--jobFile <path>` runs a saved job message end-to-end
+// without the listener. With `--validate`, the job message (from IPC or a
+// job file) is only planned: step conditions are evaluated and the steps that
+// would run or be skipped are printed, without executing anything.
 
 use anyhow::{Context, Result};
 use clap::Parser;
 use runner_common::host_context::HostContext;
 use runner_common::util::task_result_util::{TaskResult, TaskResultUtil};
+use std::path::PathBuf;
 use std::sync::Arc;
 
+use runner_worker::tracking_manager::{TrackingManager, TRACKING_RETENTION};
 use runner_worker::worker::Worker;
 
 /// Command-line arguments for the worker process.
@@ -18,12 +25,20 @@ use runner_worker::worker::Worker;
 #[command(name = "Runner.Worker", about = "GitHub Actions Runner Worker")]
 struct Args {
     /// Path to the IPC socket/pipe for receiving messages from the listener.
-    #[arg(long = "pipeIn")]
-    pipe_in: String,
+    #[arg(long = "pipeIn", required_unless_present = "job_file")]
+    pipe_in: Option<String>,
 
     /// Path to the IPC socket/pipe for sending messages to the listener.
-    #[arg(long = "pipeOut")]
-    pipe_out: String,
+    #[arg(long = "pipeOut", required_unless_present = "job_file")]
+    pipe_out: Option<String>,
+
+    /// Run the job message saved in this file instead of receiving one over IPC.
+    #[arg(long = "jobFile", conflicts_with_all = ["pipe_in", "pipe_out"])]
+    job_file: Option<PathBuf>,
+
+    /// Print which steps would run or be skipped, then exit without running them.
+    #[arg(long = "validate")]
+    validate: bool,
 }
 
 fn main() {
@@ -50,17 +65,47 @@ async fn run(args: Args) -> i32 {
         .init();
 
     tracing::info!("Worker process starting.");
-    tracing::info!("  pipeIn  = {}", args.pipe_in);
-    tracing::info!("  pipeOut = {}", args.pipe_out);
+    if let Some(ref job_file) = args.job_file {
+        tracing::info!("  jobFile = {}", job_file.display());
+    } else {
+        tracing::info!(
+            "  pipeIn  = {}",
+            args.pipe_in.as_deref().unwrap_or_default()
+        );
+        tracing::info!(
+            "  pipeOut = {}",
+            args.pipe_out.as_deref().unwrap_or_default()
+        );
+    }
 
     // Create the host context for the worker process
     let host_context = HostContext::new("Worker");
 
+    if args.validate {
+        return validate(host_context, &args).await;
+    }
+
+    // Drop pipeline directories left behind by crashed or long-unused jobs
+    if let Err(e) = TrackingManager::new(&host_context).prune(TRACKING_RETENTION) {
+        tracing::warn!("Failed to prune stale pipeline directories: {:#}", e);
+    }
+
     // Create the worker service
     let worker = Worker::new(Arc::clone(&host_context));
 
     // Run the worker – returns a TaskResult
-    match worker.run_async(&args.pipe_in, &args.pipe_out).await {
+    let outcome = match args.job_file {
+        Some(ref job_file) => worker.run_from_file(job_file).await,
+        None => {
+            worker
+                .run_async(
+                    args.pipe_in.as_deref().unwrap_or_default(),
+                    args.pipe_out.as_deref().unwrap_or_default(),
+                )
+                .await
+        }
+    };
+    match outcome {
         Ok(result) => {
             let return_code = TaskResultUtil::translate_to_return_code(result);
             tracing::info!(
@@ -76,3 +121,35 @@ async fn run(args: Args) -> i32 {
         }
     }
 }
+
+/// Plan the job without running it and print the plan to stdout.
+async fn validate(host_context: Arc<HostContext>, args: &Args) -> i32 {
+    let worker = Worker::new(host_context);
+    let plan = match args.job_file {
+        Some(ref job_file) => worker.validate_from_file(job_file),
+        None => {
+            worker
+                .validate_async(
+                    args.pipe_in.as_deref().unwrap_or_default(),
+                    args.pipe_out.as_deref().unwrap_or_default(),
+                )
+                .await
+        }
+    };
+
+    match plan {
+        Ok(plan) => {
+            print!("{}", plan);
+            let result = if plan.has_invalid_conditions() {
+                TaskResult::Failed
+            } else {
+                TaskResult::Succeeded
+            };
+            TaskResultUtil::translate_to_return_code(result)
+        }
+        Err(e) => {
+            tracing::error!("Validation failed with error: {:#}", e);
+            TaskResultUtil::translate_to_return_code(TaskResult::Failed)
+        }
+    }
+}
@@ -2,20 +2,40 @@
 //
 // The Results Service uses Twirp-style JSON RPCs. All calls go to the
 // ResultsServiceUrl data key from the SystemVssConnection endpoint, with
-// Bearer token auth using the same access token.
+// that endpoint's authorization (a Bearer token for OAuth).
 //
 // API calls implemented:
 //   1. WorkflowStepsUpdate — report step status (InProgress/Completed)
 //   2. GetStepLogsSignedBlobURL — get a SAS URL to upload step logs
 //   3. Upload step logs to the SAS URL (plain PUT to Azure blob storage)
 //   4. CreateStepLogsMetadata — finalize the log upload with line count
+//   5. GetStepSummarySignedBlobURL / CreateStepSummaryMetadata — the same
+//      flow for the step's `$GITHUB_STEP_SUMMARY` markdown, uploaded to the
+//      blob in blocks (Put Block / Put Block List)
 
 use anyhow::{Context, Result};
-use chrono::Utc;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
 use runner_sdk::TraceWriter;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 use crate::worker::AgentJobRequestMessage;
 
+/// Largest `$GITHUB_STEP_SUMMARY` the Results Service accepts, in KiB.
+pub const MAX_STEP_SUMMARY_SIZE_KB: usize = 1024;
+
+/// Size of each block when uploading a step summary to blob storage.
+const SUMMARY_BLOCK_SIZE: usize = 256 * 1024;
+
+/// How long step updates are collected before they are sent as one batch.
+pub const STEP_UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a single WorkflowStepsUpdate request may take.
+const STEP_UPDATE_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Step status values for the Results Service.
 /// These match the C# StepStatus enum.
 #[derive(Debug, Clone, Copy)]
@@ -47,6 +67,7 @@ pub enum StepConclusion {
 }
 
 /// Information about a step to update via the Results Service.
+#[derive(Debug, Clone)]
 pub struct StepUpdate {
     /// The step's GUID (from the job message step.id).
     pub external_id: String,
@@ -68,8 +89,8 @@ pub struct StepUpdate {
 pub struct ResultsClient {
     /// Base URL of the Results Service (from ResultsServiceUrl data key).
     results_url: String,
-    /// OAuth access token from the SystemVssConnection endpoint.
-    access_token: String,
+    /// `Authorization` header value for the SystemVssConnection endpoint.
+    auth_header: String,
     /// Plan ID (workflow_run_backend_id).
     plan_id: String,
     /// Job ID (workflow_job_run_backend_id).
@@ -82,7 +103,7 @@ impl ResultsClient {
     /// Create a ResultsClient from the job message.
     ///
     /// Extracts the Results Service URL from the SystemVssConnection endpoint's
-    /// `ResultsServiceUrl` data key, and the access token from its authorization.
+    /// `ResultsServiceUrl` data key, and the auth header from its authorization.
     pub fn from_message(message: &AgentJobRequestMessage) -> Result<Self> {
         let endpoint = message
             .resources
@@ -91,12 +112,12 @@ impl ResultsClient {
             .find(|e| e.name == "SystemVssConnection")
             .context("No SystemVssConnection endpoint in job message")?;
 
-        let access_token = endpoint
+        let authorization = endpoint
             .authorization
             .as_ref()
-            .and_then(|a| a.parameters.get("AccessToken"))
-            .context("No AccessToken in SystemVssConnection authorization")?
-            .clone();
+            .context("No authorization on the SystemVssConnection endpoint")?;
+        let auth_header =
+            runner_sdk::VssUtil::auth_header(&authorization.scheme, &authorization.parameters)?;
 
         let results_url = endpoint
             .data
@@ -110,7 +131,7 @@ impl ResultsClient {
 
         Ok(Self {
             results_url,
-            access_token,
+            auth_header,
             plan_id,
             job_id,
             client: reqwest::Client::new(),
@@ -167,9 +188,10 @@ impl ResultsClient {
         let response = self
             .client
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.access_token))
+            .header("Authorization", &self.auth_header)
             .header("Content-Type", "application/json")
             .json(&body)
+            .timeout(STEP_UPDATE_TIMEOUT)
             .send()
             .await
             .context("Failed to send WorkflowStepsUpdate request")?;
@@ -200,7 +222,7 @@ impl ResultsClient {
     pub async fn upload_step_log(
         &self,
         step_id: &str,
-        log_lines: &[String],
+        log_lines: &[(DateTime<Utc>, &str)],
         trace: &dyn TraceWriter,
     ) -> Result<()> {
         if log_lines.is_empty() {
@@ -208,13 +230,7 @@ impl ResultsClient {
             return Ok(());
         }
 
-        // Prefix each line with an ISO 8601 timestamp
-        let now = Utc::now();
-        let log_content: String = log_lines
-            .iter()
-            .map(|line| format!("{} {}", now.format("%Y-%m-%dT%H:%M:%S%.3fZ"), line))
-            .collect::<Vec<_>>()
-            .join("\n");
+        let log_content = format_log_lines(log_lines);
         let line_count = log_lines.len();
 
         trace.info(&format!(
@@ -264,7 +280,7 @@ impl ResultsClient {
         let response = self
             .client
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.access_token))
+            .header("Authorization", &self.auth_header)
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
@@ -354,7 +370,7 @@ impl ResultsClient {
         let response = self
             .client
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.access_token))
+            .header("Authorization", &self.auth_header)
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
@@ -377,4 +393,491 @@ impl ResultsClient {
         ));
         Ok(())
     }
+
+    /// Upload a step's `$GITHUB_STEP_SUMMARY` markdown to the Results Service.
+    ///
+    /// Same flow as the step log upload: get a SAS URL, upload the content to
+    /// blob storage, then finalize with the summary size. The size limit is
+    /// checked before anything is sent, since the service rejects oversized
+    /// summaries without saying why. Content larger than one block is
+    /// uploaded in blocks and committed with a block list.
+    pub async fn upload_step_summary(
+        &self,
+        step_id: &str,
+        summary: &str,
+        trace: &dyn TraceWriter,
+    ) -> Result<()> {
+        if summary.len() > MAX_STEP_SUMMARY_SIZE_KB * 1024 {
+            anyhow::bail!(
+                "$GITHUB_STEP_SUMMARY upload aborted, supports content up to a size of {}k, got {}k.",
+                MAX_STEP_SUMMARY_SIZE_KB,
+                summary.len() / 1024
+            );
+        }
+
+        trace.info(&format!(
+            "Uploading step summary for step {} ({} bytes)",
+            step_id,
+            summary.len()
+        ));
+
+        let body = serde_json::json!({
+            "workflow_run_backend_id": self.plan_id,
+            "workflow_job_run_backend_id": self.job_id,
+            "step_backend_id": step_id,
+        });
+        let response = self
+            .post_twirp("GetStepSummarySignedBlobURL", &body)
+            .await?;
+        let summary_url = response["summary_url"]
+            .as_str()
+            .context("No summary_url in GetStepSummarySignedBlobURL response")?
+            .to_string();
+
+        if summary.len() <= SUMMARY_BLOCK_SIZE {
+            self.upload_to_blob(&summary_url, summary, trace).await?;
+        } else {
+            self.upload_to_blob_in_blocks(&summary_url, summary.as_bytes(), trace)
+                .await?;
+        }
+
+        let body = serde_json::json!({
+            "workflow_run_backend_id": self.plan_id,
+            "workflow_job_run_backend_id": self.job_id,
+            "step_backend_id": step_id,
+            "uploaded_at": Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+            "size": summary.len(),
+        });
+        self.post_twirp("CreateStepSummaryMetadata", &body).await?;
+
+        trace.info(&format!(
+            "Successfully uploaded step summary for step {}",
+            step_id
+        ));
+        Ok(())
+    }
+
+    /// Upload content to Azure blob storage in `SUMMARY_BLOCK_SIZE` blocks.
+    ///
+    /// PUT {sas_url}&comp=block&blockid={id}   (once per block)
+    /// PUT {sas_url}&comp=blocklist            (commit the blocks in order)
+    async fn upload_to_blob_in_blocks(
+        &self,
+        sas_url: &str,
+        content: &[u8],
+        trace: &dyn TraceWriter,
+    ) -> Result<()> {
+        let base_url = url::Url::parse(sas_url).context("Invalid blob SAS URL")?;
+
+        let mut block_ids = Vec::new();
+        for (index, block) in content.chunks(SUMMARY_BLOCK_SIZE).enumerate() {
+            // Block IDs must be base64 and all the same length
+            let block_id = BASE64.encode(format!("block-{:06}", index));
+            let mut url = base_url.clone();
+            url.query_pairs_mut()
+                .append_pair("comp", "block")
+                .append_pair("blockid", &block_id);
+
+            let response = self
+                .client
+                .put(url)
+                .body(block.to_vec())
+                .send()
+                .await
+                .context("Failed to upload block to blob storage")?;
+            let status = response.status();
+            if !status.is_success() {
+                let body_text = response.text().await.unwrap_or_default();
+                anyhow::bail!("Put Block returned HTTP {}: {}", status, body_text);
+            }
+            block_ids.push(block_id);
+        }
+
+        let block_list = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?><BlockList>{}</BlockList>",
+            block_ids
+                .iter()
+                .map(|id| format!("<Latest>{}</Latest>", id))
+                .collect::<String>()
+        );
+        let mut url = base_url;
+        url.query_pairs_mut().append_pair("comp", "blocklist");
+        let response = self
+            .client
+            .put(url)
+            .header("Content-Type", "application/xml")
+            .header("x-ms-blob-content-type", "text/plain")
+            .body(block_list)
+            .send()
+            .await
+            .context("Failed to commit blob block list")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Put Block List returned HTTP {}: {}", status, body_text);
+        }
+
+        trace.info(&format!(
+            "Blob uploaded in {} blocks (HTTP {})",
+            block_ids.len(),
+            status
+        ));
+        Ok(())
+    }
+
+    /// POST a JSON body to a Results Service receiver method and return the
+    /// parsed response.
+    ///
+    /// POST {results_url}/twirp/results.services.receiver.Receiver/{method}
+    async fn post_twirp(
+        &self,
+        method: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let url = format!(
+            "{}/twirp/results.services.receiver.Receiver/{}",
+            self.results_url, method
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", &self.auth_header)
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send {} request", method))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("{} returned HTTP {}: {}", method, status, body_text);
+        }
+
+        response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse {} response", method))
+    }
+}
+
+/// Prefix each line with the ISO 8601 time it was written; the UI shows
+/// per-line timing from these.
+fn format_log_lines(log_lines: &[(DateTime<Utc>, &str)]) -> String {
+    log_lines
+        .iter()
+        .map(|(timestamp, line)| format!("{} {}", timestamp.format("%Y-%m-%dT%H:%M:%S%.3fZ"), line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ---------------------------------------------------------------------------
+// StepUpdateQueue
+// ---------------------------------------------------------------------------
+
+/// Sends step updates to the Results Service in the background, the way the
+/// C# `JobServerQueue` batches timeline record updates.
+///
+/// Updates queued within one interval go out in a single
+/// WorkflowStepsUpdate call, keeping only the latest update for each step,
+/// so a job with many short steps does not flood the service and no step
+/// waits on the network.
+pub struct StepUpdateQueue {
+    sender: mpsc::UnboundedSender<StepUpdate>,
+    task: JoinHandle<()>,
+}
+
+impl StepUpdateQueue {
+    /// Start sending the updates queued every `interval` through `client`.
+    pub fn start(client: Arc<ResultsClient>, interval: Duration) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(async move {
+            let trace = runner_sdk::trace::TracingTraceWriter;
+            let mut change_order = 0;
+            while let Some(first) = receiver.recv().await {
+                tokio::time::sleep(interval).await;
+                let mut batch = vec![first];
+                while let Ok(update) = receiver.try_recv() {
+                    batch.push(update);
+                }
+
+                change_order += 1;
+                if let Err(e) = client
+                    .update_workflow_steps(&latest_per_step(batch), change_order, &trace)
+                    .await
+                {
+                    tracing::warn!("Failed to update step status: {:#}", e);
+                }
+            }
+        });
+        Self { sender, task }
+    }
+
+    /// Queue `update` for the next batch.
+    pub fn queue(&self, update: StepUpdate) {
+        let _ = self.sender.send(update);
+    }
+
+    /// Send the updates still queued and stop.
+    pub async fn finish(self) {
+        drop(self.sender);
+        let _ = self.task.await;
+    }
+}
+
+/// The last update for each step, in the order the steps first appear.
+fn latest_per_step(updates: Vec<StepUpdate>) -> Vec<StepUpdate> {
+    let mut latest: Vec<StepUpdate> = Vec::with_capacity(updates.len());
+    for update in updates {
+        match latest
+            .iter_mut()
+            .find(|existing| existing.external_id == update.external_id)
+        {
+            Some(existing) => *existing = update,
+            None => latest.push(update),
+        }
+    }
+    latest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runner_sdk::test_util::{MockHttpServer, MockResponse};
+    use std::sync::Arc;
+
+    /// Serve Results Service and blob requests on a local port, answering
+    /// GetStepSummarySignedBlobURL and GetStepLogsSignedBlobURL with blob
+    /// URLs on the same server.
+    async fn mock_server() -> MockHttpServer {
+        MockHttpServer::start_with(|request| {
+            let base = format!("http://{}", request.header("host").unwrap());
+            let body = if request.target.ends_with("/GetStepSummarySignedBlobURL") {
+                serde_json::json!({ "summary_url": format!("{}/blob/summary?sig=abc", base) })
+            } else if request.target.ends_with("/GetStepLogsSignedBlobURL") {
+                serde_json::json!({ "logs_url": format!("{}/blob/logs?sig=abc", base) })
+            } else {
+                serde_json::json!({})
+            };
+            MockResponse::json("201 Created", body.to_string())
+        })
+        .await
+    }
+
+    fn client(results_url: &str) -> ResultsClient {
+        ResultsClient {
+            results_url: results_url.to_string(),
+            auth_header: runner_sdk::VssUtil::bearer_header("token"),
+            plan_id: "plan".to_string(),
+            job_id: "job".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_over_limit_summary_is_rejected_locally() {
+        let server = mock_server().await;
+        let summary = "x".repeat((MAX_STEP_SUMMARY_SIZE_KB + 10) * 1024);
+
+        let err = client(server.url())
+            .upload_step_summary("step-1", &summary, &runner_sdk::trace::NullTraceWriter)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "$GITHUB_STEP_SUMMARY upload aborted, supports content up to a size of 1024k, got 1034k."
+        );
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_summary_uploads_in_blocks() {
+        let server = mock_server().await;
+        let summary: String = (0..MAX_STEP_SUMMARY_SIZE_KB * 1024)
+            .map(|i| char::from(b'a' + (i % 26) as u8))
+            .collect();
+
+        client(server.url())
+            .upload_step_summary("step-1", &summary, &runner_sdk::trace::NullTraceWriter)
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        let targets: Vec<&str> = requests.iter().map(|r| r.target.as_str()).collect();
+        assert_eq!(targets.len(), 7);
+        assert!(targets[0].ends_with("/GetStepSummarySignedBlobURL"));
+        assert!(targets[5].ends_with("comp=blocklist"));
+        assert!(targets[6].ends_with("/CreateStepSummaryMetadata"));
+
+        // The blocks reassemble into the summary, in block list order
+        let blocks = &requests[1..5];
+        assert!(blocks
+            .iter()
+            .all(|r| r.method == "PUT" && r.target.contains("comp=block&blockid=")));
+        let uploaded: Vec<u8> = blocks.iter().flat_map(|r| r.body.clone()).collect();
+        assert_eq!(uploaded, summary.as_bytes());
+
+        let block_list = requests[5].body_text();
+        assert_eq!(block_list.matches("<Latest>").count(), 4);
+        assert!(block_list.contains(&format!(
+            "<Latest>{}</Latest>",
+            BASE64.encode("block-000000")
+        )));
+
+        let metadata = requests[6].body_json();
+        assert_eq!(metadata["size"], summary.len());
+        assert_eq!(metadata["step_backend_id"], "step-1");
+    }
+
+    #[tokio::test]
+    async fn test_small_summary_uploads_as_single_blob() {
+        let server = mock_server().await;
+
+        client(server.url())
+            .upload_step_summary("step-1", "# Done", &runner_sdk::trace::NullTraceWriter)
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[1].target, "/blob/summary?sig=abc");
+        assert_eq!(requests[1].body, b"# Done");
+    }
+
+    #[tokio::test]
+    async fn test_step_log_lines_keep_their_timestamps() {
+        let server = mock_server().await;
+        let first = "2024-05-01T10:00:00.250Z".parse::<DateTime<Utc>>().unwrap();
+        let second = "2024-05-01T10:00:03.500Z".parse::<DateTime<Utc>>().unwrap();
+
+        client(server.url())
+            .upload_step_log(
+                "step-1",
+                &[(first, "Compiling"), (second, "##[error]Build failed")],
+                &runner_sdk::trace::NullTraceWriter,
+            )
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[1].target, "/blob/logs?sig=abc");
+        assert_eq!(
+            requests[1].body_text(),
+            "2024-05-01T10:00:00.250Z Compiling\n2024-05-01T10:00:03.500Z ##[error]Build failed"
+        );
+        let metadata = requests[2].body_json();
+        assert_eq!(metadata["line_count"], 2);
+    }
+
+    fn step_update(
+        id: &str,
+        number: u32,
+        status: StepStatus,
+        conclusion: StepConclusion,
+    ) -> StepUpdate {
+        StepUpdate {
+            external_id: id.to_string(),
+            number,
+            name: format!("Step {}", number),
+            status,
+            started_at: Some("2024-05-01T10:00:00.000Z".to_string()),
+            completed_at: matches!(status, StepStatus::Completed)
+                .then(|| "2024-05-01T10:00:01.000Z".to_string()),
+            conclusion,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_step_updates_are_sent_in_one_batch() {
+        let server = mock_server().await;
+        let updates =
+            StepUpdateQueue::start(Arc::new(client(server.url())), Duration::from_millis(50));
+
+        updates.queue(step_update(
+            "a",
+            1,
+            StepStatus::InProgress,
+            StepConclusion::Unknown,
+        ));
+        updates.queue(step_update(
+            "a",
+            1,
+            StepStatus::Completed,
+            StepConclusion::Success,
+        ));
+        updates.queue(step_update(
+            "b",
+            2,
+            StepStatus::InProgress,
+            StepConclusion::Unknown,
+        ));
+        updates.finish().await;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0]
+            .target
+            .ends_with(".WorkflowStepUpdateService/WorkflowStepsUpdate"));
+        let body = requests[0].body_json();
+        assert_eq!(body["change_order"], 1);
+        assert_eq!(
+            body["steps"],
+            serde_json::json!([
+                {
+                    "external_id": "a",
+                    "number": 1,
+                    "name": "Step 1",
+                    "status": 6,
+                    "conclusion": 2,
+                    "started_at": "2024-05-01T10:00:00.000Z",
+                    "completed_at": "2024-05-01T10:00:01.000Z"
+                },
+                {
+                    "external_id": "b",
+                    "number": 2,
+                    "name": "Step 2",
+                    "status": 3,
+                    "conclusion": 0,
+                    "started_at": "2024-05-01T10:00:00.000Z"
+                }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_each_step_update_batch_gets_the_next_change_order() {
+        let server = mock_server().await;
+        let updates =
+            StepUpdateQueue::start(Arc::new(client(server.url())), Duration::from_millis(10));
+
+        updates.queue(step_update(
+            "a",
+            1,
+            StepStatus::InProgress,
+            StepConclusion::Unknown,
+        ));
+        while server.requests().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        updates.queue(step_update(
+            "a",
+            1,
+            StepStatus::Completed,
+            StepConclusion::Failure,
+        ));
+        updates.finish().await;
+
+        let requests = server.requests();
+        let change_orders: Vec<serde_json::Value> = requests
+            .iter()
+            .map(|r| r.body_json()["change_order"].clone())
+            .collect();
+        assert_eq!(
+            change_orders,
+            vec![serde_json::json!(1), serde_json::json!(2)]
+        );
+    }
 }
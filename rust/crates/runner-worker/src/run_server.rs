@@ -1,141 +1,194 @@
 // RunServer mapping `RunServer.cs`.
-// Client for the Actions Run Service – reports job completion back to the server.
+// Job completion reporting – builds the `completejob` body and sends it
+// through the shared Run Service client in `runner_common::run_server`.
 //
 // The Run Service URL comes from the SystemVssConnection endpoint in the job
 // message resources.  The access token comes from the same endpoint's OAuth
 // authorization parameters.
 
 use anyhow::{Context, Result};
-use runner_common::util::task_result_util::TaskResult;
+use chrono::{DateTime, Utc};
+use runner_common::run_server::conclusion_string;
+use runner_common::util::task_result_util::{TaskResult, TaskResultUtil};
 use runner_sdk::TraceWriter;
 
+pub use runner_common::run_server::RunServer;
+
+use crate::execution_context::ExecutionContext;
+use crate::steps_context::{Annotation, AnnotationCounts};
 use crate::worker::AgentJobRequestMessage;
 
-/// Minimal client for the Actions Run Service.
-pub struct RunServer {
-    /// Base URL of the Run Service (SystemVssConnection endpoint URL).
-    base_url: String,
-    /// OAuth access token from the SystemVssConnection endpoint.
-    access_token: String,
-    /// HTTP client
-    client: reqwest::Client,
+/// Per-step entry in the completion payload.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepCompletion {
+    /// Step id (the `steps.<id>` key).
+    pub external_id: String,
+    /// 1-based position of the step within the job.
+    pub number: u32,
+    /// Outcome before `continue-on-error` ("success", "failure", ...).
+    pub outcome: String,
+    /// Conclusion after `continue-on-error`.
+    pub conclusion: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    pub annotations: Vec<Annotation>,
 }
 
-impl RunServer {
-    /// Create a RunServer from the job message's SystemVssConnection endpoint.
-    pub fn from_message(message: &AgentJobRequestMessage) -> Result<Self> {
-        let endpoint = message
-            .resources
-            .endpoints
-            .iter()
-            .find(|e| e.name == "SystemVssConnection")
-            .context("No SystemVssConnection endpoint in job message")?;
-
-        let access_token = endpoint
-            .authorization
-            .as_ref()
-            .and_then(|a| a.parameters.get("AccessToken"))
-            .context("No AccessToken in SystemVssConnection authorization")?
-            .clone();
-
-        let base_url = endpoint.url.trim_end_matches('/').to_string();
-
-        Ok(Self {
-            base_url,
-            access_token,
-            client: reqwest::Client::new(),
+/// Everything reported to the Run Service when a job completes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobCompletion {
+    /// Overall job result.
+    pub result: TaskResult,
+    /// Results of the steps that were recorded, in the order they ran.
+    pub steps: Vec<StepCompletion>,
+    /// The job's annotations: job-level lines, then each step's in order.
+    pub annotations: Vec<Annotation>,
+}
+
+impl JobCompletion {
+    /// A completion that carries only the overall result.
+    pub fn from_result(result: TaskResult) -> Self {
+        Self {
+            result,
+            steps: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Error and warning totals for the job.
+    pub fn annotation_counts(&self) -> AnnotationCounts {
+        AnnotationCounts::from_annotations(&self.annotations)
+    }
+
+    /// The body of the `JobCompleted` IPC message sent to the listener: the
+    /// result as a process return code plus the job's annotation totals.
+    pub fn completed_message_body(&self) -> String {
+        let counts = self.annotation_counts();
+        serde_json::json!({
+            "resultCode": TaskResultUtil::translate_to_return_code(self.result),
+            "errorCount": counts.error_count,
+            "warningCount": counts.warning_count,
         })
+        .to_string()
     }
 
-    /// Report job completion to the Actions Run Service.
-    ///
-    /// POST {base_url}/completejob
-    ///
-    /// This is the critical call that tells the server the job is done.
-    /// Without this, the server considers the job still running and keeps
-    /// sending cancellation messages.
-    pub async fn complete_job(
+    /// Build the completion from the job's root execution context.
+    pub fn from_context(result: TaskResult, context: &ExecutionContext) -> Self {
+        let mut annotations = Annotation::from_log_lines(context.log_lines());
+        let mut steps = Vec::new();
+        for (index, (id, step)) in context.steps_context().ordered_steps().enumerate() {
+            let number = index as u32 + 1;
+            let step_annotations: Vec<Annotation> = step
+                .annotations
+                .iter()
+                .map(|annotation| Annotation {
+                    step_number: Some(number),
+                    ..annotation.clone()
+                })
+                .collect();
+            annotations.extend(step_annotations.iter().cloned());
+            steps.push(StepCompletion {
+                external_id: id.to_string(),
+                number,
+                outcome: step.outcome.clone(),
+                conclusion: step.conclusion.clone(),
+                started_at: step.started_at.map(format_timestamp),
+                completed_at: step.completed_at.map(format_timestamp),
+                annotations: step_annotations,
+            });
+        }
+        Self {
+            result,
+            steps,
+            annotations,
+        }
+    }
+
+    /// Report the completion through `run_server`. The body also carries
+    /// per-step results and an annotation summary so the UI does not need
+    /// separate calls to show them.
+    pub async fn report(
         &self,
+        run_server: &RunServer,
         plan_id: &str,
         job_id: &str,
-        conclusion: TaskResult,
         trace: &dyn TraceWriter,
     ) -> Result<()> {
-        let url = format!("{}/completejob", self.base_url);
-
-        // TaskResult enum values map to camelCase string conclusions:
-        //   Succeeded → "succeeded", SucceededWithIssues → "succeededWithIssues",
-        //   Failed → "failed", Canceled → "canceled", Skipped → "skipped",
-        //   Abandoned → "abandoned"
-        let conclusion_str = match conclusion {
-            TaskResult::Succeeded => "succeeded",
-            TaskResult::SucceededWithIssues => "succeededWithIssues",
-            TaskResult::Failed => "failed",
-            TaskResult::Canceled => "canceled",
-            TaskResult::Skipped => "skipped",
-            TaskResult::Abandoned => "abandoned",
-        };
-
-        let body = serde_json::json!({
-            "planId": plan_id,
-            "jobId": job_id,
-            "conclusion": conclusion_str
-        });
-
+        let counts = self.annotation_counts();
         trace.info(&format!(
-            "Reporting job completion: planId={}, jobId={}, conclusion={}",
-            plan_id, job_id, conclusion_str
+            "Completion includes {} step result(s), {} error(s), {} warning(s)",
+            self.steps.len(),
+            counts.error_count,
+            counts.warning_count
         ));
+        run_server
+            .complete_job(&self.to_payload(plan_id, job_id), trace)
+            .await
+    }
 
-        let mut last_err = None;
-        for attempt in 1..=5 {
-            match self
-                .client
-                .post(&url)
-                .header("Authorization", format!("Bearer {}", self.access_token))
-                .header("Content-Type", "application/json")
-                .json(&body)
-                .send()
-                .await
-            {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        trace.info(&format!(
-                            "Successfully reported job completion (HTTP {})",
-                            status
-                        ));
-                        return Ok(());
-                    }
-                    let body_text = response.text().await.unwrap_or_default();
-                    trace.warning(&format!(
-                        "CompleteJob attempt {}/5 failed: HTTP {} - {}",
-                        attempt, status, body_text
-                    ));
-                    last_err = Some(anyhow::anyhow!(
-                        "CompleteJob returned HTTP {}: {}",
-                        status,
-                        body_text
-                    ));
-                }
-                Err(e) => {
-                    trace.warning(&format!(
-                        "CompleteJob attempt {}/5 failed: {}",
-                        attempt, e
-                    ));
-                    last_err = Some(e.into());
-                }
-            }
+    /// The JSON body for `POST /completejob`.
+    pub fn to_payload(&self, plan_id: &str, job_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "planId": plan_id,
+            "jobId": job_id,
+            "conclusion": conclusion_string(self.result),
+            "stepResults": self.steps,
+            "annotations": self.annotations
+        })
+    }
+}
 
-            if attempt < 5 {
-                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+/// Human-readable summary for `--jobFile` runs: one line per step, then the
+/// job conclusion.
+impl std::fmt::Display for JobCompletion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for step in &self.steps {
+            write!(
+                f,
+                "Step {} '{}': {}",
+                step.number, step.external_id, step.conclusion
+            )?;
+            if step.outcome != step.conclusion {
+                write!(f, " (outcome: {})", step.outcome)?;
             }
+            writeln!(f)?;
         }
-
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("CompleteJob failed after 5 attempts")))
+        let counts = self.annotation_counts();
+        writeln!(
+            f,
+            "Job conclusion: {} ({} error(s), {} warning(s))",
+            conclusion_string(self.result),
+            counts.error_count,
+            counts.warning_count
+        )
     }
 }
 
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+/// Create a RunServer from the job message's SystemVssConnection endpoint.
+pub fn run_server_from_message(message: &AgentJobRequestMessage) -> Result<RunServer> {
+    let endpoint = message
+        .resources
+        .endpoints
+        .iter()
+        .find(|e| e.name == "SystemVssConnection")
+        .context("No SystemVssConnection endpoint in job message")?;
+
+    let access_token = endpoint
+        .authorization
+        .as_ref()
+        .and_then(|a| a.parameters.get("AccessToken"))
+        .context("No AccessToken in SystemVssConnection authorization")?;
+
+    Ok(RunServer::new(&endpoint.url, access_token))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -149,4 +202,160 @@ mod tests {
         assert_eq!(TaskResult::Skipped as i32, 4);
         assert_eq!(TaskResult::Abandoned as i32, 5);
     }
+
+    fn make_root_context() -> ExecutionContext {
+        use crate::execution_context::Global;
+        use runner_common::host_context::HostContext;
+
+        let host = HostContext::new("Test");
+        let global = Global::for_test("");
+        ExecutionContext::new_root(host, global, "test-job".to_string())
+    }
+
+    #[test]
+    fn test_completion_payload_includes_step_results() {
+        use std::collections::HashMap;
+
+        let mut ctx = make_root_context();
+        ctx.error("Job-level failure");
+        {
+            let steps = ctx.steps_context_mut();
+            steps.record_step(
+                "checkout",
+                TaskResult::Succeeded,
+                TaskResult::Succeeded,
+                HashMap::new(),
+            );
+            steps.record_step(
+                "lint",
+                TaskResult::Failed,
+                TaskResult::Succeeded,
+                HashMap::new(),
+            );
+            steps.record_step_annotations(
+                "lint",
+                Annotation::from_log_lines(&[
+                    "##[error]unused import".to_string(),
+                    "##[error]missing docs".to_string(),
+                    "##[warning]long line".to_string(),
+                ]),
+            );
+            steps.record_step(
+                "deploy",
+                TaskResult::Skipped,
+                TaskResult::Skipped,
+                HashMap::new(),
+            );
+            steps.record_step(
+                "test",
+                TaskResult::Failed,
+                TaskResult::Failed,
+                HashMap::new(),
+            );
+            steps.record_step_annotations(
+                "test",
+                Annotation::from_log_lines(&["##[error]1 test failed".to_string()]),
+            );
+        }
+
+        let completion = JobCompletion::from_context(TaskResult::Failed, &ctx);
+        let payload = completion.to_payload("plan-1", "job-1");
+
+        assert_eq!(payload["planId"], "plan-1");
+        assert_eq!(payload["jobId"], "job-1");
+        assert_eq!(payload["conclusion"], "failed");
+
+        let steps = payload["stepResults"].as_array().unwrap();
+        let summary: Vec<(&str, u64, &str, &str)> = steps
+            .iter()
+            .map(|s| {
+                (
+                    s["externalId"].as_str().unwrap(),
+                    s["number"].as_u64().unwrap(),
+                    s["outcome"].as_str().unwrap(),
+                    s["conclusion"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("checkout", 1, "success", "success"),
+                ("lint", 2, "failure", "success"),
+                ("deploy", 3, "skipped", "skipped"),
+                ("test", 4, "failure", "failure"),
+            ]
+        );
+        assert_eq!(steps[0]["annotations"], serde_json::json!([]));
+        assert_eq!(steps[1]["annotations"].as_array().unwrap().len(), 3);
+
+        // Job-level error, then the step annotations, as a list
+        assert_eq!(
+            payload["annotations"],
+            serde_json::json!([
+                {"level": "FAILURE", "message": "Job-level failure"},
+                {"level": "FAILURE", "message": "unused import", "stepNumber": 2},
+                {"level": "FAILURE", "message": "missing docs", "stepNumber": 2},
+                {"level": "WARNING", "message": "long line", "stepNumber": 2},
+                {"level": "FAILURE", "message": "1 test failed", "stepNumber": 4}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_completion_payload_without_steps() {
+        let completion = JobCompletion::from_result(TaskResult::Canceled);
+        let payload = completion.to_payload("plan-1", "job-1");
+
+        assert_eq!(payload["conclusion"], "canceled");
+        assert_eq!(payload["stepResults"].as_array().unwrap().len(), 0);
+        assert_eq!(payload["annotations"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_completed_message_body() {
+        let mut completion = JobCompletion::from_result(TaskResult::Failed);
+        completion.annotations = Annotation::from_log_lines(
+            &["##[error]a", "##[error]b", "##[warning]c", "##[notice]d"].map(String::from),
+        );
+
+        let body: serde_json::Value =
+            serde_json::from_str(&completion.completed_message_body()).unwrap();
+        assert_eq!(
+            body["resultCode"],
+            TaskResultUtil::translate_to_return_code(TaskResult::Failed)
+        );
+        assert_eq!(body["errorCount"], 2);
+        assert_eq!(body["warningCount"], 1);
+    }
+
+    #[test]
+    fn test_completion_summary() {
+        use std::collections::HashMap;
+
+        let mut ctx = make_root_context();
+        {
+            let steps = ctx.steps_context_mut();
+            steps.record_step(
+                "build",
+                TaskResult::Succeeded,
+                TaskResult::Succeeded,
+                HashMap::new(),
+            );
+            steps.record_step(
+                "lint",
+                TaskResult::Failed,
+                TaskResult::Succeeded,
+                HashMap::new(),
+            );
+        }
+
+        let completion = JobCompletion::from_context(TaskResult::Succeeded, &ctx);
+        assert_eq!(
+            completion.to_string(),
+            "Step 1 'build': success\n\
+             Step 2 'lint': success (outcome: failure)\n\
+             Job conclusion: succeeded (0 error(s), 0 warning(s))\n"
+        );
+    }
 }
@@ -1,9 +1,8 @@
 // RunnerContext mapping `RunnerContext.cs`.
 // Populates the `runner.*` expression context from the host environment.
 
-use std::collections::HashMap;
-
-use runner_common::constants;
+use runner_common::constants::{self, WellKnownDirectory};
+use runner_common::host_context::HostContext;
 
 /// The `runner` context available in expressions.
 ///
@@ -89,6 +88,20 @@ impl RunnerContext {
         }
     }
 
+    /// Create a `RunnerContext` for a job, taking the OS and architecture from
+    /// the build target and the temp and tool cache paths from the host
+    /// context directories.
+    pub fn from_host(host: &HostContext, name: &str, workspace: &str, debug: bool) -> Self {
+        let directory = |dir| host.get_directory(dir).to_string_lossy().to_string();
+        Self {
+            tool_cache: directory(WellKnownDirectory::Tools),
+            temp: directory(WellKnownDirectory::Temp),
+            environment: std::env::var("RUNNER_ENVIRONMENT")
+                .unwrap_or_else(|_| "self-hosted".to_string()),
+            ..Self::with_values(name, workspace, "", "", debug)
+        }
+    }
+
     /// Create a `RunnerContext` with custom values (for testing or injection).
     pub fn with_values(
         name: &str,
@@ -116,22 +129,12 @@ impl RunnerContext {
 
     /// Detect the current OS as a GitHub Actions-compatible string.
     fn detect_os() -> String {
-        match constants::CURRENT_PLATFORM {
-            constants::OsPlatform::Linux => "Linux".to_string(),
-            constants::OsPlatform::MacOS => "macOS".to_string(),
-            constants::OsPlatform::Windows => "Windows".to_string(),
-        }
+        constants::CURRENT_PLATFORM.label_name().to_string()
     }
 
-    /// Detect the current CPU architecture.
+    /// Detect the current CPU architecture as a GitHub Actions-compatible string.
     fn detect_arch() -> String {
-        match std::env::consts::ARCH {
-            "x86_64" => "X64".to_string(),
-            "aarch64" => "ARM64".to_string(),
-            "arm" => "ARM".to_string(),
-            "x86" => "X86".to_string(),
-            other => other.to_uppercase(),
-        }
+        constants::CURRENT_ARCHITECTURE.label_name().to_string()
     }
 }
 
@@ -197,4 +200,49 @@ mod tests {
         let ctx_no_debug = RunnerContext::with_values("r", "", "", "", false);
         assert_eq!(ctx_no_debug.debug, "");
     }
+
+    #[test]
+    fn test_from_host_uses_host_directories() {
+        let root = tempfile::tempdir().unwrap();
+        let host = HostContext::new("Test");
+        host.set_root_override(root.path().to_path_buf());
+
+        let ctx = RunnerContext::from_host(&host, "my-runner", "/work", true);
+        assert_eq!(
+            ctx.temp,
+            host.get_directory(WellKnownDirectory::Temp)
+                .to_string_lossy()
+        );
+        assert_eq!(
+            ctx.tool_cache,
+            host.get_directory(WellKnownDirectory::Tools)
+                .to_string_lossy()
+        );
+
+        let val = ctx.to_value();
+        for field in ["os", "arch", "temp", "tool_cache", "name", "debug"] {
+            assert!(val.get(field).is_some(), "missing runner.{}", field);
+        }
+        assert_eq!(val["name"], "my-runner");
+        assert_eq!(val["debug"], "1");
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_runner_expressions_on_linux_x64() {
+        use crate::expressions::evaluate_condition;
+        use runner_common::util::task_result_util::TaskResult;
+
+        let ctx = RunnerContext::with_values("r", "/work", "/tmp", "/tools", false);
+        let expr_ctx = serde_json::json!({ "runner": ctx.to_value() });
+        let eval = |c: &str| evaluate_condition(c, TaskResult::Succeeded, false, &expr_ctx);
+
+        assert!(eval("runner.os == 'Linux'"));
+        assert!(eval("runner.arch == 'X64'"));
+        assert!(eval(
+            "runner.tool_cache == '/tools' && runner.temp == '/tmp'"
+        ));
+        assert!(!eval("runner.os == 'Windows'"));
+        assert!(!eval("runner.debug == '1'"));
+    }
 }
@@ -3,6 +3,7 @@
 
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
 use runner_common::util::task_result_util::TaskResult;
 
 /// Recorded result for a single step.
@@ -16,6 +17,96 @@ pub struct StepResult {
 
     /// Step outputs (key → value).
     pub outputs: HashMap<String, String>,
+
+    /// When the step started running (not part of the `steps` expression context).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+
+    /// When the step completed (not part of the `steps` expression context).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+
+    /// Annotations the step produced (not part of the `steps` expression context).
+    #[serde(skip)]
+    pub annotations: Vec<Annotation>,
+}
+
+impl StepResult {
+    /// How long the step ran, if both timestamps were recorded.
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        match (self.started_at, self.completed_at) {
+            (Some(started), Some(completed)) => Some(completed - started),
+            _ => None,
+        }
+    }
+}
+
+/// Severity of an annotation, as named by the Run Service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AnnotationLevel {
+    Notice,
+    Warning,
+    Failure,
+}
+
+/// An error, warning or notice written to a log, reported with the job.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    pub level: AnnotationLevel,
+    pub message: String,
+    /// 1-based number of the step that wrote it; `None` for the job itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step_number: Option<u32>,
+}
+
+impl Annotation {
+    /// The `##[error]`, `##[warning]` and `##[notice]` lines in a log.
+    pub fn from_log_lines(lines: &[String]) -> Vec<Annotation> {
+        lines
+            .iter()
+            .filter_map(|line| {
+                let (level, message) = if let Some(message) = line.strip_prefix("##[error]") {
+                    (AnnotationLevel::Failure, message)
+                } else if let Some(message) = line.strip_prefix("##[warning]") {
+                    (AnnotationLevel::Warning, message)
+                } else if let Some(message) = line.strip_prefix("##[notice]") {
+                    (AnnotationLevel::Notice, message)
+                } else {
+                    return None;
+                };
+                Some(Annotation {
+                    level,
+                    message: message.to_string(),
+                    step_number: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Number of error and warning annotations written by a step or job.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationCounts {
+    pub error_count: u32,
+    pub warning_count: u32,
+}
+
+impl AnnotationCounts {
+    /// Count the errors and warnings among `annotations`.
+    pub fn from_annotations(annotations: &[Annotation]) -> Self {
+        let mut counts = Self::default();
+        for annotation in annotations {
+            match annotation.level {
+                AnnotationLevel::Failure => counts.error_count += 1,
+                AnnotationLevel::Warning => counts.warning_count += 1,
+                AnnotationLevel::Notice => {}
+            }
+        }
+        counts
+    }
 }
 
 /// Tracks the results and outputs of all executed steps.
@@ -26,6 +117,9 @@ pub struct StepResult {
 pub struct StepsContext {
     /// Map of step id → step result.
     results: HashMap<String, StepResult>,
+
+    /// Step ids in the order they were first recorded.
+    order: Vec<String>,
 }
 
 impl StepsContext {
@@ -33,6 +127,7 @@ impl StepsContext {
     pub fn new() -> Self {
         Self {
             results: HashMap::new(),
+            order: Vec::new(),
         }
     }
 
@@ -48,16 +143,46 @@ impl StepsContext {
         conclusion: TaskResult,
         outputs: HashMap<String, String>,
     ) {
+        if !self.results.contains_key(step_id) {
+            self.order.push(step_id.to_string());
+        }
         self.results.insert(
             step_id.to_string(),
             StepResult {
                 outcome: task_result_to_string(outcome),
                 conclusion: task_result_to_string(conclusion),
                 outputs,
+                started_at: None,
+                completed_at: None,
+                annotations: Vec::new(),
             },
         );
     }
 
+    /// Record when a previously recorded step started and completed.
+    ///
+    /// Does nothing if the step has not been recorded.
+    pub fn record_step_timing(
+        &mut self,
+        step_id: &str,
+        started_at: DateTime<Utc>,
+        completed_at: DateTime<Utc>,
+    ) {
+        if let Some(result) = self.results.get_mut(step_id) {
+            result.started_at = Some(started_at);
+            result.completed_at = Some(completed_at);
+        }
+    }
+
+    /// Record the annotations a previously recorded step produced.
+    ///
+    /// Does nothing if the step has not been recorded.
+    pub fn record_step_annotations(&mut self, step_id: &str, annotations: Vec<Annotation>) {
+        if let Some(result) = self.results.get_mut(step_id) {
+            result.annotations = annotations;
+        }
+    }
+
     /// Check if a step has been recorded.
     pub fn has_step(&self, step_id: &str) -> bool {
         self.results.contains_key(step_id)
@@ -81,6 +206,21 @@ impl StepsContext {
             .map(|s| s.as_str())
     }
 
+    /// Get the time a step started running.
+    pub fn get_started_at(&self, step_id: &str) -> Option<DateTime<Utc>> {
+        self.results.get(step_id).and_then(|r| r.started_at)
+    }
+
+    /// Get the time a step completed.
+    pub fn get_completed_at(&self, step_id: &str) -> Option<DateTime<Utc>> {
+        self.results.get(step_id).and_then(|r| r.completed_at)
+    }
+
+    /// Get how long a step ran.
+    pub fn get_duration(&self, step_id: &str) -> Option<chrono::Duration> {
+        self.results.get(step_id).and_then(|r| r.duration())
+    }
+
     /// Get all outputs for a step.
     pub fn get_outputs(&self, step_id: &str) -> Option<&HashMap<String, String>> {
         self.results.get(step_id).map(|r| &r.outputs)
@@ -91,6 +231,13 @@ impl StepsContext {
         &self.results
     }
 
+    /// Get all recorded steps in the order they ran.
+    pub fn ordered_steps(&self) -> impl Iterator<Item = (&str, &StepResult)> {
+        self.order
+            .iter()
+            .filter_map(|id| self.results.get(id).map(|r| (id.as_str(), r)))
+    }
+
     /// Convert to a serde_json::Value for expression evaluation.
     pub fn to_value(&self) -> serde_json::Value {
         let mut map = serde_json::Map::new();
@@ -193,6 +340,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_record_step_timing() {
+        let mut ctx = StepsContext::new();
+        ctx.record_step(
+            "step1",
+            TaskResult::Succeeded,
+            TaskResult::Succeeded,
+            HashMap::new(),
+        );
+        assert_eq!(ctx.get_duration("step1"), None);
+
+        let started = Utc::now();
+        let completed = started + chrono::Duration::milliseconds(1500);
+        ctx.record_step_timing("step1", started, completed);
+
+        assert_eq!(ctx.get_started_at("step1"), Some(started));
+        assert_eq!(ctx.get_completed_at("step1"), Some(completed));
+        assert_eq!(
+            ctx.get_duration("step1"),
+            Some(chrono::Duration::milliseconds(1500))
+        );
+
+        // Timing is internal and not exposed to expressions
+        let val = ctx.to_value();
+        assert!(val["step1"].get("started_at").is_none());
+    }
+
+    #[test]
+    fn test_record_step_timing_unknown_step() {
+        let mut ctx = StepsContext::new();
+        ctx.record_step_timing("nonexistent", Utc::now(), Utc::now());
+        assert!(!ctx.has_step("nonexistent"));
+    }
+
+    #[test]
+    fn test_ordered_steps_and_annotations() {
+        let mut ctx = StepsContext::new();
+        for id in ["checkout", "build", "test"] {
+            ctx.record_step(
+                id,
+                TaskResult::Succeeded,
+                TaskResult::Succeeded,
+                HashMap::new(),
+            );
+        }
+
+        let lines = vec![
+            "##[error]boom".to_string(),
+            "##[warning]careful".to_string(),
+            "##[warning]again".to_string(),
+            "##[notice]fyi".to_string(),
+            "plain output".to_string(),
+        ];
+        ctx.record_step_annotations("build", Annotation::from_log_lines(&lines));
+
+        let ids: Vec<&str> = ctx.ordered_steps().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec!["checkout", "build", "test"]);
+        let build = &ctx.steps()["build"].annotations;
+        let levels: Vec<(AnnotationLevel, &str)> = build
+            .iter()
+            .map(|a| (a.level, a.message.as_str()))
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                (AnnotationLevel::Failure, "boom"),
+                (AnnotationLevel::Warning, "careful"),
+                (AnnotationLevel::Warning, "again"),
+                (AnnotationLevel::Notice, "fyi"),
+            ]
+        );
+        assert_eq!(
+            AnnotationCounts::from_annotations(build),
+            AnnotationCounts {
+                error_count: 1,
+                warning_count: 2
+            }
+        );
+        assert!(ctx.steps()["test"].annotations.is_empty());
+    }
+
     #[test]
     fn test_missing_step() {
         let ctx = StepsContext::new();
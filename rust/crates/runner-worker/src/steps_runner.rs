@@ -4,7 +4,7 @@
 // Also reports step status and uploads logs to the Results Service.
 
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use runner_common::util::task_result_util::{TaskResult, TaskResultUtil};
 use std::sync::Arc;
 use std::time::Duration;
@@ -14,7 +14,10 @@ use crate::action_command_manager::ActionCommandManager;
 use crate::execution_context::ExecutionContext;
 use crate::expressions::evaluate_condition;
 use crate::file_command_manager::FileCommandManager;
-use crate::results_client::{ResultsClient, StepConclusion, StepStatus, StepUpdate};
+use crate::results_client::{
+    ResultsClient, StepConclusion, StepStatus, StepUpdate, StepUpdateQueue, STEP_UPDATE_INTERVAL,
+};
+use crate::steps_context::Annotation;
 
 /// Executes all steps in a job, in order.
 pub struct StepsRunner {
@@ -45,9 +48,9 @@ impl StepsRunner {
         }
     }
 
-    /// Report a step status update to the Results Service.
-    async fn report_step_status(
-        &self,
+    /// Queue a step status update for the Results Service.
+    fn report_step_status(
+        updates: Option<&StepUpdateQueue>,
         step_id: &str,
         step_number: u32,
         display_name: &str,
@@ -55,10 +58,9 @@ impl StepsRunner {
         conclusion: StepConclusion,
         started_at: Option<&str>,
         completed_at: Option<&str>,
-        change_order: u64,
     ) {
-        if let Some(ref client) = self.results_client {
-            let update = StepUpdate {
+        if let Some(updates) = updates {
+            updates.queue(StepUpdate {
                 external_id: step_id.to_string(),
                 number: step_number,
                 name: display_name.to_string(),
@@ -66,17 +68,12 @@ impl StepsRunner {
                 started_at: started_at.map(|s| s.to_string()),
                 completed_at: completed_at.map(|s| s.to_string()),
                 conclusion,
-            };
-
-            let trace = SimpleTrace;
-            if let Err(e) = client.update_workflow_steps(&[update], change_order, &trace).await {
-                tracing::warn!("Failed to update step status: {:#}", e);
-            }
+            });
         }
     }
 
     /// Upload step logs to the Results Service.
-    async fn upload_logs(&self, step_id: &str, log_lines: &[String]) {
+    async fn upload_logs(&self, step_id: &str, log_lines: &[(DateTime<Utc>, &str)]) {
         if let Some(ref client) = self.results_client {
             let trace = SimpleTrace;
             if let Err(e) = client.upload_step_log(step_id, log_lines, &trace).await {
@@ -85,33 +82,53 @@ impl StepsRunner {
         }
     }
 
+    /// Upload a step's `$GITHUB_STEP_SUMMARY` markdown to the Results Service.
+    async fn upload_summary(&self, step_id: &str, summary: &str) {
+        if let Some(ref client) = self.results_client {
+            let trace = SimpleTrace;
+            if let Err(e) = client.upload_step_summary(step_id, summary, &trace).await {
+                tracing::warn!("Failed to upload step summary: {:#}", e);
+            }
+        }
+    }
+
     /// Run all job steps and post-job steps.
     pub async fn run_async(&self, context: &mut ExecutionContext) -> Result<()> {
         let mut step_number: u32 = 0;
-        let mut change_order: u64 = 0;
+        let updates = self
+            .results_client
+            .as_ref()
+            .map(|client| StepUpdateQueue::start(client.clone(), STEP_UPDATE_INTERVAL));
 
         // Phase 1: Drain the job_steps queue (main steps)
         while let Some(step) = context.job_steps.pop_front() {
             step_number += 1;
             let cancel = context.cancel_token();
 
-            // Check cancellation
-            if cancel.is_cancelled() {
-                context.info(&format!(
-                    "Skipping step '{}' due to job cancellation.",
-                    step.display_name()
-                ));
-                continue;
+            // A cancelled job (e.g. superseded by a newer run in the same
+            // concurrency group) is Canceled, not Failed: success() and
+            // failure() are false from here on, while always() and
+            // cancelled() steps still run.
+            let job_cancelled = cancel.is_cancelled();
+            if job_cancelled {
+                Self::mark_job_cancelled(context);
             }
 
             // Evaluate the condition expression
             let should_run = self.evaluate_step_condition(context, step.as_ref());
 
             if !should_run {
-                context.info(&format!(
-                    "Skipping step '{}' (condition evaluated to false).",
-                    step.display_name()
-                ));
+                if job_cancelled {
+                    context.info(&format!(
+                        "Skipping step '{}' due to job cancellation.",
+                        step.display_name()
+                    ));
+                } else {
+                    context.info(&format!(
+                        "Skipping step '{}' (condition evaluated to false).",
+                        step.display_name()
+                    ));
+                }
                 // Record as skipped in steps context
                 context.steps_context_mut().record_step(
                     step.id(),
@@ -121,8 +138,8 @@ impl StepsRunner {
                 );
 
                 // Report skipped status to Results Service
-                change_order += 1;
-                self.report_step_status(
+                Self::report_step_status(
+                    updates.as_ref(),
                     step.id(),
                     step_number,
                     step.display_name(),
@@ -130,8 +147,7 @@ impl StepsRunner {
                     StepConclusion::Skipped,
                     None,
                     Some(&Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()),
-                    change_order,
-                ).await;
+                );
 
                 continue;
             }
@@ -139,9 +155,10 @@ impl StepsRunner {
             context.info(&format!("Starting step: {}", step.display_name()));
 
             // Report step as InProgress to Results Service
-            let started_at = Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
-            change_order += 1;
-            self.report_step_status(
+            let started = Utc::now();
+            let started_at = started.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+            Self::report_step_status(
+                updates.as_ref(),
                 step.id(),
                 step_number,
                 step.display_name(),
@@ -149,8 +166,7 @@ impl StepsRunner {
                 StepConclusion::Unknown,
                 Some(&started_at),
                 None,
-                change_order,
-            ).await;
+            );
 
             // Create step-level execution context
             let mut step_context = context.create_step_context(
@@ -169,13 +185,16 @@ impl StepsRunner {
                 Duration::from_secs(6 * 60 * 60) // default: 6 hours
             };
 
-            // Run the step with timeout
-            let step_result = self.run_step_with_timeout(
-                &step,
-                &mut step_context,
-                timeout,
-                cancel.clone(),
-            ).await;
+            // Run the step with timeout. A step that runs because of
+            // cancellation must not be cancelled by the same token.
+            let step_cancel = if job_cancelled {
+                CancellationToken::new()
+            } else {
+                cancel.clone()
+            };
+            let step_result = self
+                .run_step_with_timeout(&step, &mut step_context, timeout, step_cancel)
+                .await;
 
             // Process file commands after step execution
             FileCommandManager::process_file_commands(&mut step_context);
@@ -185,18 +204,22 @@ impl StepsRunner {
                 Ok(()) => {
                     let outcome = step_context.result().unwrap_or(TaskResult::Succeeded);
                     let conclusion = if step.continue_on_error() && outcome == TaskResult::Failed {
-                        TaskResult::Succeeded
+                        TaskResult::SucceededWithIssues
                     } else {
                         outcome
                     };
                     (outcome, conclusion)
                 }
+                Err(_) if step_context.result() == Some(TaskResult::Canceled) => {
+                    // continue-on-error does not apply to cancellation
+                    (TaskResult::Canceled, TaskResult::Canceled)
+                }
                 Err(e) => {
                     step_context.error(&format!("Step failed: {:#}", e));
                     let outcome = TaskResult::Failed;
                     let conclusion = if step.continue_on_error() {
                         step_context.info("Step failed but continue-on-error is enabled.");
-                        TaskResult::Succeeded
+                        TaskResult::SucceededWithIssues
                     } else {
                         TaskResult::Failed
                     };
@@ -204,13 +227,18 @@ impl StepsRunner {
                 }
             };
 
-            // Upload step logs to Results Service
-            self.upload_logs(step.id(), step_context.log_lines()).await;
+            // Upload step logs and summary to Results Service
+            let log_lines: Vec<_> = step_context.timestamped_log_lines().collect();
+            self.upload_logs(step.id(), &log_lines).await;
+            if let Some(summary) = step_context.step_summary.take() {
+                self.upload_summary(step.id(), &summary).await;
+            }
 
             // Report step as Completed to Results Service
-            let completed_at = Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
-            change_order += 1;
-            self.report_step_status(
+            let completed = Utc::now();
+            let completed_at = completed.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+            Self::report_step_status(
+                updates.as_ref(),
                 step.id(),
                 step_number,
                 step.display_name(),
@@ -218,8 +246,7 @@ impl StepsRunner {
                 Self::task_result_to_conclusion(conclusion),
                 Some(&started_at),
                 Some(&completed_at),
-                change_order,
-            ).await;
+            );
 
             // Record step outcome and outputs in steps context
             context.steps_context_mut().record_step(
@@ -228,6 +255,13 @@ impl StepsRunner {
                 conclusion,
                 step_context.outputs.clone(),
             );
+            context
+                .steps_context_mut()
+                .record_step_timing(step.id(), started, completed);
+            context.steps_context_mut().record_step_annotations(
+                step.id(),
+                Annotation::from_log_lines(step_context.log_lines()),
+            );
 
             // Merge outputs back to parent context
             for (key, value) in &step_context.outputs {
@@ -243,17 +277,23 @@ impl StepsRunner {
             context.set_result(merged);
 
             context.info(&format!(
-                "Step '{}' completed with outcome={:?}, conclusion={:?}",
+                "Step '{}' completed with outcome={:?}, conclusion={:?} in {:.3}s",
                 step.display_name(),
                 outcome,
-                conclusion
+                conclusion,
+                (completed - started).num_milliseconds() as f64 / 1000.0
             ));
         }
 
         // Phase 2: Execute post-job steps in reverse order (LIFO)
         let post_steps: Vec<_> = context.post_job_steps.drain(..).collect();
         for step in post_steps.into_iter().rev() {
-            let cancel = context.cancel_token();
+            // Post steps clean up after a cancelled job too
+            let mut cancel = context.cancel_token();
+            if cancel.is_cancelled() {
+                Self::mark_job_cancelled(context);
+                cancel = CancellationToken::new();
+            }
 
             context.info(&format!("Running post step: {}", step.display_name()));
 
@@ -276,6 +316,10 @@ impl StepsRunner {
             }
         }
 
+        if let Some(updates) = updates {
+            updates.finish().await;
+        }
+
         Ok(())
     }
 
@@ -310,6 +354,12 @@ impl StepsRunner {
         }
     }
 
+    /// Record that the job was cancelled in its result.
+    pub(crate) fn mark_job_cancelled(context: &mut ExecutionContext) {
+        let merged = TaskResultUtil::merge_task_results(context.result(), TaskResult::Canceled);
+        context.set_result(merged);
+    }
+
     /// Evaluate the `if:` condition expression for a step.
     fn evaluate_step_condition(&self, context: &ExecutionContext, step: &dyn crate::execution_context::IStep) -> bool {
         let condition = step.condition();
@@ -383,6 +433,215 @@ fn task_result_to_outcome_string(result: TaskResult) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution_context::{Global, IStep};
+    use crate::handlers::handler::{Handler, HandlerData};
+    use crate::handlers::script_handler::{ScriptHandler, ScriptHandlerHelpers};
+    use runner_common::host_context::HostContext;
+    use std::collections::HashMap;
+
+    /// A step that sleeps for a fixed time and succeeds.
+    struct SleepStep {
+        id: String,
+        condition: String,
+        millis: u64,
+    }
+
+    impl SleepStep {
+        fn new(id: &str, condition: &str, millis: u64) -> Box<Self> {
+            Box::new(Self {
+                id: id.to_string(),
+                condition: condition.to_string(),
+                millis,
+            })
+        }
+    }
+
+    impl IStep for SleepStep {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        fn display_name(&self) -> &str {
+            &self.id
+        }
+        fn condition(&self) -> &str {
+            &self.condition
+        }
+        fn timeout_in_minutes(&self) -> u32 {
+            0
+        }
+        fn continue_on_error(&self) -> bool {
+            false
+        }
+        fn step_type(&self) -> &str {
+            "script"
+        }
+        fn run_async<'a>(
+            &'a self,
+            _context: &'a mut ExecutionContext,
+        ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(self.millis)).await;
+                Ok(())
+            })
+        }
+    }
+
+    /// A `run:` step executed by the script handler with the default shell.
+    struct ScriptStep {
+        id: String,
+        script: String,
+        continue_on_error: bool,
+    }
+
+    impl IStep for ScriptStep {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        fn display_name(&self) -> &str {
+            &self.id
+        }
+        fn condition(&self) -> &str {
+            ""
+        }
+        fn timeout_in_minutes(&self) -> u32 {
+            0
+        }
+        fn continue_on_error(&self) -> bool {
+            self.continue_on_error
+        }
+        fn step_type(&self) -> &str {
+            "script"
+        }
+        fn run_async<'a>(
+            &'a self,
+            context: &'a mut ExecutionContext,
+        ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
+            Box::pin(async move {
+                let mut inputs = HashMap::new();
+                inputs.insert("script".to_string(), self.script.clone());
+                let data = HandlerData {
+                    inputs,
+                    environment: HashMap::new(),
+                    action_context: Default::default(),
+                };
+                ScriptHandler::new().run_async(context, &data).await
+            })
+        }
+    }
+
+    fn make_test_context(temp_directory: &str) -> ExecutionContext {
+        let host = HostContext::new("Test");
+        let global = Global::for_test(temp_directory);
+        ExecutionContext::new_root(host, global, "test-job".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_step_timestamps_are_recorded() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut ctx = make_test_context(temp.path().to_str().unwrap());
+        for id in ["first", "second"] {
+            ctx.job_steps.push_back(SleepStep::new(id, "", 20));
+        }
+
+        StepsRunner::new().run_async(&mut ctx).await.unwrap();
+
+        let steps = ctx.steps_context();
+        for id in ["first", "second"] {
+            let duration = steps.get_duration(id).unwrap();
+            assert!(duration > chrono::Duration::zero(), "{} duration", id);
+            assert!(steps.get_started_at(id).unwrap() <= steps.get_completed_at(id).unwrap());
+        }
+        assert!(
+            steps.get_completed_at("first").unwrap() <= steps.get_started_at("second").unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cancel_runs_cancelled_steps_and_reports_canceled() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut ctx = make_test_context(temp.path().to_str().unwrap());
+        ctx.job_steps.push_back(SleepStep::new("build", "", 10_000));
+        ctx.job_steps.push_back(SleepStep::new("test", "", 20));
+        ctx.job_steps
+            .push_back(SleepStep::new("notify", "failure()", 20));
+        ctx.job_steps
+            .push_back(SleepStep::new("cleanup", "cancelled()", 20));
+        ctx.job_steps.push_back(SleepStep::new(
+            "report",
+            "always() && steps.build.outcome == 'cancelled'",
+            20,
+        ));
+
+        // A newer run in the same concurrency group cancels this one mid-step
+        let cancel = ctx.cancel_token();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+
+        StepsRunner::new().run_async(&mut ctx).await.unwrap();
+
+        assert_eq!(ctx.result(), Some(TaskResult::Canceled));
+        let steps = ctx.steps_context();
+        assert_eq!(steps.get_outcome("build"), Some("cancelled"));
+        assert_eq!(steps.get_conclusion("build"), Some("cancelled"));
+        assert_eq!(steps.get_outcome("test"), Some("skipped"));
+        assert_eq!(steps.get_outcome("notify"), Some("skipped"));
+        // Cleanup steps run to completion despite the cancelled job token
+        assert_eq!(steps.get_outcome("cleanup"), Some("success"));
+        assert_eq!(steps.get_outcome("report"), Some("success"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_before_start_skips_default_steps() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut ctx = make_test_context(temp.path().to_str().unwrap());
+        ctx.job_steps.push_back(SleepStep::new("build", "", 20));
+        ctx.job_steps
+            .push_back(SleepStep::new("cleanup", "cancelled()", 20));
+        ctx.cancel_token().cancel();
+
+        StepsRunner::new().run_async(&mut ctx).await.unwrap();
+
+        assert_eq!(ctx.result(), Some(TaskResult::Canceled));
+        assert_eq!(ctx.steps_context().get_outcome("build"), Some("skipped"));
+        assert_eq!(ctx.steps_context().get_outcome("cleanup"), Some("success"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_pipeline_fails_job_unless_continue_on_error() {
+        if ScriptHandlerHelpers::get_default_shell() != "bash" {
+            return;
+        }
+        let script_step = |id: &str, continue_on_error| {
+            Box::new(ScriptStep {
+                id: id.to_string(),
+                script: "false | true".to_string(),
+                continue_on_error,
+            })
+        };
+
+        let temp = tempfile::tempdir().unwrap();
+        let mut ctx = make_test_context(temp.path().to_str().unwrap());
+        ctx.job_steps.push_back(script_step("tolerated", true));
+        StepsRunner::new().run_async(&mut ctx).await.unwrap();
+
+        let steps = ctx.steps_context();
+        assert_eq!(steps.get_outcome("tolerated"), Some("failure"));
+        assert_eq!(steps.get_conclusion("tolerated"), Some("success"));
+        assert_eq!(ctx.result(), Some(TaskResult::SucceededWithIssues));
+
+        let mut ctx = make_test_context(temp.path().to_str().unwrap());
+        ctx.job_steps.push_back(script_step("pipeline", false));
+        ctx.job_steps.push_back(SleepStep::new("next", "", 1));
+        StepsRunner::new().run_async(&mut ctx).await.unwrap();
+
+        let steps = ctx.steps_context();
+        assert_eq!(steps.get_outcome("pipeline"), Some("failure"));
+        assert_eq!(steps.get_outcome("next"), Some("skipped"));
+        assert_eq!(ctx.result(), Some(TaskResult::Failed));
+    }
 
     #[test]
     fn test_task_result_to_outcome_string() {
@@ -0,0 +1,212 @@
+// TemplateToken JSON conversion mapping `TemplateTokenJsonConverter.cs`.
+// Step inputs, step environment and job environment arrive in the job message
+// as serialized TemplateTokens. This module turns that polymorphic JSON into
+// plain `serde_json::Value`s.
+//
+// Wire forms written by the C# serializer:
+// - String literal without position info: a bare JSON string
+// - `{"type": 0, "lit": "..."}`                      string literal
+// - `{"type": 1, "seq": [token, ...]}`               sequence
+// - `{"type": 2, "map": [{"Key": token, "Value": token}, ...]}` mapping
+// - `{"type": 3, "expr": "..."}`                     basic expression
+// - `{"type": 4, "directive": "..."}`                insert expression
+// - `{"type": 5, "bool": true}`                      boolean
+// - `{"type": 6, "num": 1.5}`                        number
+// - `{"type": 7}`                                    null
+//
+// Tokens may also carry `file`, `line` and `col`, which are ignored here.
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+const STRING: i64 = 0;
+const SEQUENCE: i64 = 1;
+const MAPPING: i64 = 2;
+const BASIC_EXPRESSION: i64 = 3;
+const INSERT_EXPRESSION: i64 = 4;
+const BOOLEAN: i64 = 5;
+const NUMBER: i64 = 6;
+const NULL: i64 = 7;
+
+/// Convert a serialized TemplateToken into a plain JSON value.
+///
+/// Sequences and mappings are converted recursively. Expressions that were
+/// not evaluated by the server are kept as `${{ ... }}` strings, so they can
+/// still be recognised downstream. JSON that is not a token (e.g. an ordinary
+/// object of strings) is returned as-is.
+pub fn to_json(token: &Value) -> Value {
+    let Value::Object(obj) = token else {
+        return token.clone();
+    };
+
+    let token_type = obj.get("type").and_then(Value::as_i64).or_else(|| {
+        // The discriminator is sometimes omitted; infer it from the payload
+        if obj.contains_key("map") {
+            Some(MAPPING)
+        } else if obj.contains_key("seq") {
+            Some(SEQUENCE)
+        } else if obj.contains_key("lit") {
+            Some(STRING)
+        } else if obj.contains_key("expr") {
+            Some(BASIC_EXPRESSION)
+        } else {
+            None
+        }
+    });
+
+    match token_type {
+        Some(STRING) => obj.get("lit").cloned().unwrap_or_else(|| Value::from("")),
+        Some(SEQUENCE) => Value::Array(
+            obj.get("seq")
+                .and_then(Value::as_array)
+                .map(|items| items.iter().map(to_json).collect())
+                .unwrap_or_default(),
+        ),
+        Some(MAPPING) => Value::Object(
+            obj.get("map")
+                .and_then(Value::as_array)
+                .map(|entries| mapping_entries(entries))
+                .unwrap_or_default(),
+        ),
+        Some(BASIC_EXPRESSION) => expression(obj.get("expr")),
+        Some(INSERT_EXPRESSION) => expression(obj.get("directive")),
+        Some(BOOLEAN) => obj.get("bool").cloned().unwrap_or(Value::Bool(false)),
+        Some(NUMBER) => obj.get("num").cloned().unwrap_or_else(|| Value::from(0)),
+        Some(NULL) => Value::Null,
+        _ => token.clone(),
+    }
+}
+
+/// Convert a mapping token into a flat string map, as used for step inputs
+/// and environment variables.
+///
+/// Scalars become their string form and `null` becomes an empty string.
+/// Nested sequences and mappings are rendered as compact JSON, since a
+/// string is the only value an input or environment variable can hold.
+/// Anything other than a mapping yields an empty map.
+pub fn to_string_map(token: &Value) -> HashMap<String, String> {
+    match to_json(token) {
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| (key, to_string_value(&value)))
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// The string form of a converted token value.
+pub fn to_string_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Array(_) | Value::Object(_) => value.to_string(),
+    }
+}
+
+/// Convert the entries of a `map` array. Entries are `{"Key", "Value"}`
+/// pairs; an array of alternating key and value tokens is also accepted.
+fn mapping_entries(entries: &[Value]) -> Map<String, Value> {
+    let pairs: Vec<(&Value, &Value)> =
+        if entries.iter().all(|entry| key_value_pair(entry).is_some()) {
+            entries.iter().filter_map(key_value_pair).collect()
+        } else {
+            entries
+                .chunks_exact(2)
+                .map(|chunk| (&chunk[0], &chunk[1]))
+                .collect()
+        };
+
+    pairs
+        .into_iter()
+        .map(|(key, value)| (to_string_value(&to_json(key)), to_json(value)))
+        .collect()
+}
+
+/// The key and value tokens of a `{"Key", "Value"}` mapping entry.
+fn key_value_pair(entry: &Value) -> Option<(&Value, &Value)> {
+    let entry = entry.as_object()?;
+    let key = entry.get("Key").or_else(|| entry.get("key"))?;
+    let value = entry.get("Value").or_else(|| entry.get("value"))?;
+    Some((key, value))
+}
+
+/// An unevaluated expression, kept in its `${{ }}` form.
+fn expression(expr: Option<&Value>) -> Value {
+    let expr = expr.and_then(Value::as_str).unwrap_or_default();
+    Value::String(format!("${{{{ {} }}}}", expr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(to_json(&json!("plain")), json!("plain"));
+        assert_eq!(
+            to_json(&json!({"type": 0, "lit": "x", "line": 3})),
+            json!("x")
+        );
+        assert_eq!(to_json(&json!({"type": 5, "bool": true})), json!(true));
+        assert_eq!(to_json(&json!({"type": 6, "num": 2.5})), json!(2.5));
+        assert_eq!(to_json(&json!({"type": 7})), Value::Null);
+        assert_eq!(
+            to_json(&json!({"type": 3, "expr": "github.sha"})),
+            json!("${{ github.sha }}")
+        );
+    }
+
+    #[test]
+    fn test_mapping_in_sequence_in_mapping() {
+        let token = json!({
+            "type": 2,
+            "map": [
+                {"Key": "name", "Value": {"type": 0, "lit": "build"}},
+                {"Key": "targets", "Value": {"type": 1, "seq": [
+                    {"type": 2, "map": [
+                        {"Key": "os", "Value": "linux"},
+                        {"Key": "arch", "Value": {"type": 1, "seq": ["x64", "arm64"]}}
+                    ]},
+                    {"type": 2, "map": [
+                        {"Key": {"type": 0, "lit": "os"}, "Value": "windows"},
+                        {"Key": "debug", "Value": {"type": 5, "bool": false}}
+                    ]}
+                ]}}
+            ]
+        });
+
+        assert_eq!(
+            to_json(&token),
+            json!({
+                "name": "build",
+                "targets": [
+                    {"os": "linux", "arch": ["x64", "arm64"]},
+                    {"os": "windows", "debug": false}
+                ]
+            })
+        );
+
+        let map = to_string_map(&token);
+        assert_eq!(map["name"], "build");
+        let targets: Value = serde_json::from_str(&map["targets"]).unwrap();
+        assert_eq!(targets[0]["arch"][1], "arm64");
+        assert_eq!(targets[1]["os"], "windows");
+    }
+
+    #[test]
+    fn test_alternating_map_and_plain_object() {
+        let token = json!({"type": 2, "map": ["A", "1", {"lit": "B"}, {"type": 6, "num": 2}]});
+        let map = to_string_map(&token);
+        assert_eq!(map["A"], "1");
+        assert_eq!(map["B"], "2");
+
+        let plain = to_string_map(&json!({"script": "echo hello", "count": 3}));
+        assert_eq!(plain["script"], "echo hello");
+        assert_eq!(plain["count"], "3");
+
+        assert!(to_string_map(&json!(["not", "a", "mapping"])).is_empty());
+    }
+}
@@ -5,11 +5,22 @@
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use runner_common::host_context::HostContext;
+use runner_sdk::IOUtil;
 
 use crate::worker::AgentJobRequestMessage;
 
+/// Attempts made to delete a directory between jobs before giving up.
+pub const CLEANUP_RETRY_ATTEMPTS: u32 = 5;
+
+/// Base delay between cleanup attempts; grows with each retry.
+pub const CLEANUP_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Pipeline directories unused for this long are pruned at worker startup.
+pub const TRACKING_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
 /// Tracking configuration persisted between runs.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TrackingConfig {
@@ -63,6 +74,10 @@ impl TrackingManager {
     ///
     /// Reads existing tracking config or creates a new one.
     /// Allocates a unique numbered directory under the work root.
+    ///
+    /// When the job asks for a clean workspace (the default) an existing
+    /// workspace directory is wiped and recreated; with `clean: false` it is
+    /// reused as-is.
     pub fn prepare_pipeline_directory(
         &self,
         message: &AgentJobRequestMessage,
@@ -76,6 +91,19 @@ impl TrackingManager {
         let workspace_dir = pipeline_dir.join(&tracking.workspace_directory);
         let temp_dir = pipeline_dir.join("_temp");
 
+        if message.workspace_clean() {
+            IOUtil::delete_directory_with_retry(
+                &workspace_dir,
+                CLEANUP_RETRY_ATTEMPTS,
+                CLEANUP_RETRY_DELAY,
+            )
+            .context("Failed to clean workspace directory")?;
+        }
+
+        // Start every job with an empty temp directory
+        IOUtil::delete_directory_with_retry(&temp_dir, CLEANUP_RETRY_ATTEMPTS, CLEANUP_RETRY_DELAY)
+            .context("Failed to clean temp directory")?;
+
         // Create all directories
         std::fs::create_dir_all(&pipeline_dir)
             .with_context(|| format!("Failed to create pipeline directory: {:?}", pipeline_dir))?;
@@ -94,6 +122,71 @@ impl TrackingManager {
         ))
     }
 
+    /// Remove stale pipeline directories and their tracking files.
+    ///
+    /// A numbered pipeline directory under the work root is stale when it has
+    /// no readable `.tracking` file (left behind by a crashed job), when its
+    /// workspace directory no longer exists, or when it was last used more
+    /// than `older_than` ago. The global tracking config is dropped once the
+    /// directory it points at is gone. Returns the directories removed.
+    pub fn prune(&self, older_than: Duration) -> Result<Vec<PathBuf>> {
+        let work_path = Path::new(&self.work_directory);
+        if !work_path.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let cutoff = chrono::Duration::from_std(older_than)
+            .ok()
+            .and_then(|age| chrono::Utc::now().checked_sub_signed(age));
+
+        let mut removed = Vec::new();
+        for entry in std::fs::read_dir(work_path)?.flatten() {
+            let path = entry.path();
+            let is_pipeline_dir = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.parse::<u32>().is_ok());
+            if !is_pipeline_dir || !path.is_dir() {
+                continue;
+            }
+
+            let stale = match Self::read_tracking(&path.join(".tracking")) {
+                None => true,
+                Some(config) => {
+                    let last_run_on =
+                        chrono::DateTime::parse_from_rfc3339(&config.last_run_on).ok();
+                    !path.join(&config.workspace_directory).is_dir()
+                        || matches!((last_run_on, cutoff), (Some(last), Some(cutoff)) if last < cutoff)
+                }
+            };
+
+            if stale {
+                tracing::info!("Pruning stale pipeline directory {}", path.display());
+                IOUtil::delete_directory_with_retry(
+                    &path,
+                    CLEANUP_RETRY_ATTEMPTS,
+                    CLEANUP_RETRY_DELAY,
+                )?;
+                removed.push(path);
+            }
+        }
+
+        if let Some(config) = Self::read_tracking(&self.tracking_config_path) {
+            if !work_path.join(&config.pipeline_directory).is_dir() {
+                std::fs::remove_file(&self.tracking_config_path)
+                    .with_context(|| format!("Failed to remove {:?}", self.tracking_config_path))?;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Read a tracking file, returning `None` if it is missing or invalid.
+    fn read_tracking(path: &Path) -> Option<TrackingConfig> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
     /// Extract the repository name from the job message.
     fn extract_repository_name(&self, message: &AgentJobRequestMessage) -> String {
         // Look for the repository name in variables
@@ -265,4 +358,134 @@ mod tests {
         let dir = mgr.allocate_directory().unwrap();
         assert_eq!(dir, "6");
     }
+
+    fn write_pipeline(work: &Path, number: &str, last_run_on: chrono::DateTime<chrono::Utc>) {
+        let pipeline = work.join(number);
+        std::fs::create_dir_all(pipeline.join("repo")).unwrap();
+        let config = TrackingConfig {
+            pipeline_directory: number.to_string(),
+            workspace_directory: "repo".to_string(),
+            repository_name: format!("owner/repo{number}"),
+            build_directories: HashMap::new(),
+            last_run_on: last_run_on.to_rfc3339(),
+        };
+        std::fs::write(
+            pipeline.join(".tracking"),
+            serde_json::to_string(&config).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_prune_removes_stale_and_orphaned_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let work = temp.path();
+        let now = chrono::Utc::now();
+
+        write_pipeline(work, "1", now);
+        write_pipeline(work, "2", now - chrono::Duration::days(60));
+        // Workspace deleted out from under the tracking file
+        write_pipeline(work, "3", now);
+        std::fs::remove_dir_all(work.join("3/repo")).unwrap();
+        // Crashed before the tracking file was written
+        std::fs::create_dir_all(work.join("4/repo")).unwrap();
+        // Not a pipeline directory
+        std::fs::create_dir_all(work.join("_tool/node")).unwrap();
+        std::fs::copy(work.join("2/.tracking"), work.join(".tracking_config.json")).unwrap();
+
+        let mgr = TrackingManager {
+            work_directory: work.to_string_lossy().to_string(),
+            tracking_config_path: work.join(".tracking_config.json"),
+        };
+
+        let mut removed = mgr.prune(Duration::from_secs(7 * 24 * 60 * 60)).unwrap();
+        removed.sort();
+        assert_eq!(
+            removed,
+            vec![work.join("2"), work.join("3"), work.join("4")]
+        );
+
+        assert!(work.join("1/repo").is_dir());
+        assert!(work.join("1/.tracking").is_file());
+        assert!(work.join("_tool/node").is_dir());
+        assert!(!work.join(".tracking_config.json").exists());
+    }
+
+    #[test]
+    fn test_prune_keeps_global_config_for_live_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let work = temp.path();
+        write_pipeline(work, "1", chrono::Utc::now());
+        std::fs::copy(work.join("1/.tracking"), work.join(".tracking_config.json")).unwrap();
+
+        let mgr = TrackingManager {
+            work_directory: work.to_string_lossy().to_string(),
+            tracking_config_path: work.join(".tracking_config.json"),
+        };
+
+        assert!(mgr.prune(TRACKING_RETENTION).unwrap().is_empty());
+        assert!(work.join(".tracking_config.json").is_file());
+
+        let missing = TrackingManager {
+            work_directory: work.join("missing").to_string_lossy().to_string(),
+            tracking_config_path: work.join("missing/.tracking_config.json"),
+        };
+        assert!(missing.prune(TRACKING_RETENTION).unwrap().is_empty());
+    }
+
+    fn message_with_clean(clean: &str) -> AgentJobRequestMessage {
+        serde_json::from_value(serde_json::json!({
+            "jobId": "job-1",
+            "jobDisplayName": "build",
+            "variables": { "system.github.repository": { "value": "owner/repo" } },
+            "workspace": { "clean": clean },
+        }))
+        .unwrap()
+    }
+
+    /// Prepare a workspace for `owner/repo` and leave a build output in it.
+    fn populated_workspace(mgr: &TrackingManager) -> PathBuf {
+        let (_, workspace, _) = mgr
+            .prepare_pipeline_directory(&message_with_clean("true"))
+            .unwrap();
+        let output = PathBuf::from(workspace).join("target/app");
+        std::fs::create_dir_all(output.parent().unwrap()).unwrap();
+        std::fs::write(&output, "binary").unwrap();
+        output
+    }
+
+    #[test]
+    fn test_prepare_cleans_workspace_by_default() {
+        let temp = tempfile::tempdir().unwrap();
+        let mgr = TrackingManager {
+            work_directory: temp.path().to_string_lossy().to_string(),
+            tracking_config_path: temp.path().join(".tracking_config.json"),
+        };
+        let output = populated_workspace(&mgr);
+
+        let (_, workspace, _) = mgr
+            .prepare_pipeline_directory(&message_with_clean("true"))
+            .unwrap();
+
+        assert!(!output.exists());
+        assert!(Path::new(&workspace).is_dir());
+        assert_eq!(std::fs::read_dir(&workspace).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_prepare_reuses_workspace_when_clean_is_false() {
+        let temp = tempfile::tempdir().unwrap();
+        let mgr = TrackingManager {
+            work_directory: temp.path().to_string_lossy().to_string(),
+            tracking_config_path: temp.path().join(".tracking_config.json"),
+        };
+        let output = populated_workspace(&mgr);
+
+        let (_, workspace, _) = mgr
+            .prepare_pipeline_directory(&message_with_clean("false"))
+            .unwrap();
+
+        assert!(output.starts_with(&workspace));
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "binary");
+    }
 }
@@ -2,7 +2,9 @@
 // Thread-safe variable store with secret tracking, expansion, and environment block export.
 
 use parking_lot::RwLock;
+use runner_common::constants;
 use runner_common::secret_masker::SecretMasker;
+use runner_sdk::StringUtil;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -115,6 +117,23 @@ impl Variables {
         inner.store.get(&name.to_lowercase()).map(|v| v.value.clone())
     }
 
+    /// Whether step debug logging is enabled through `ACTIONS_STEP_DEBUG`.
+    pub fn step_debug(&self) -> bool {
+        self.get_bool(constants::variables::actions::STEP_DEBUG)
+    }
+
+    /// Whether runner diagnostic logging is enabled through `ACTIONS_RUNNER_DEBUG`.
+    pub fn runner_debug(&self) -> bool {
+        self.get_bool(constants::variables::actions::RUNNER_DEBUG)
+    }
+
+    /// A variable as a boolean; unset or unparsable values are `false`.
+    fn get_bool(&self, name: &str) -> bool {
+        self.get(name)
+            .and_then(|value| StringUtil::convert_to_bool(value.trim()))
+            .unwrap_or(false)
+    }
+
     /// Try to get the full `VariableValue` (including metadata) by name.
     pub fn try_get_value(&self, name: &str) -> Option<VariableValue> {
         let inner = self.inner.read();
@@ -123,17 +142,18 @@ impl Variables {
 
     /// Set a variable. If it's marked as secret, registers it with the masker.
     /// Returns `false` if the variable is read-only and was not overwritten.
+    /// Overwriting a secret variable keeps it secret.
     pub fn set(&self, name: &str, value: impl Into<String>, is_secret: bool) -> bool {
         let value = value.into();
         let key = name.to_lowercase();
         let mut inner = self.inner.write();
 
         // Check if read-only
-        if let Some(existing) = inner.store.get(&key) {
-            if existing.is_read_only {
-                return false;
-            }
-        }
+        let is_secret = match inner.store.get(&key) {
+            Some(existing) if existing.is_read_only => return false,
+            Some(existing) => is_secret || existing.is_secret,
+            None => is_secret,
+        };
 
         if is_secret {
             if let Some(ref masker) = self.secret_masker {
@@ -279,8 +299,8 @@ impl Variables {
     pub fn merge_from(&self, other: &Variables) {
         let other_snap = other.snapshot();
         for (name, var) in other_snap {
-            if !var.is_read_only {
-                self.set(&name, var.value, var.is_secret);
+            if !var.is_read_only && !self.set(&name, var.value, var.is_secret) {
+                tracing::warn!("Can't update read-only variable '{}'", name);
             }
         }
     }
@@ -384,4 +404,39 @@ mod tests {
         assert_eq!(vars.get("MY_SECRET"), Some("password123".to_string()));
         assert_eq!(masker.mask_secrets("password123"), "***");
     }
+
+    #[test]
+    fn test_debug_toggles() {
+        let vars = Variables::new();
+        assert!(!vars.step_debug());
+        assert!(!vars.runner_debug());
+
+        vars.set("actions_step_debug", "True", true);
+        vars.set("ACTIONS_RUNNER_DEBUG", "1", false);
+        assert!(vars.step_debug());
+        assert!(vars.runner_debug());
+
+        vars.set("ACTIONS_STEP_DEBUG", "false", true);
+        vars.set("ACTIONS_RUNNER_DEBUG", "yes", false);
+        assert!(!vars.step_debug());
+        assert!(!vars.runner_debug());
+    }
+
+    #[test]
+    fn test_set_keeps_secret_variable_masked() {
+        let masker = Arc::new(SecretMasker::new());
+        let vars = Variables::with_masker(masker.clone());
+        vars.set("DEPLOY_KEY", "first-key", true);
+
+        assert!(vars.set("DEPLOY_KEY", "rotated-key", false));
+
+        let stored = vars.try_get_value("DEPLOY_KEY").unwrap();
+        assert!(stored.is_secret);
+        assert_eq!(stored.value, "rotated-key");
+        assert_eq!(masker.mask_secrets("key=rotated-key"), "key=***");
+
+        // Plain variables are not masked
+        vars.set("REGION", "us-east-1", false);
+        assert_eq!(masker.mask_secrets("us-east-1"), "us-east-1");
+    }
 }
@@ -3,16 +3,20 @@
 // and listens for cancellation messages concurrently.
 
 use anyhow::{Context, Result};
+use runner_common::constants::WellKnownConfigFile;
 use runner_common::host_context::HostContext;
 use runner_common::process_channel::{MessageType, ProcessChannel};
 use runner_common::secret_masker::SecretMasker;
 use runner_common::util::task_result_util::TaskResult;
 use runner_sdk::TraceWriter;
+use std::path::Path;
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 
+use crate::job_plan::JobPlan;
 use crate::job_runner::JobRunner;
-use crate::run_server::RunServer;
+use crate::run_server::{run_server_from_message, JobCompletion};
+use crate::template_token;
 
 /// Deserialized job request message from the listener.
 /// Maps `Pipelines.AgentJobRequestMessage` from the C# runner.
@@ -112,54 +116,44 @@ impl AgentJobRequestMessage {
             .unwrap_or_default()
     }
 
+    /// Whether the workspace should be wiped before the job runs
+    /// (`workspace.clean`). Unset or unrecognized values clean; only an
+    /// explicit `false` keeps the existing directory for incremental builds.
+    pub fn workspace_clean(&self) -> bool {
+        match self.workspace.as_ref().and_then(|w| w.get("clean")) {
+            Some(serde_json::Value::Bool(clean)) => *clean,
+            Some(serde_json::Value::String(clean)) => {
+                runner_sdk::StringUtil::convert_to_bool(clean).unwrap_or(true)
+            }
+            _ => true,
+        }
+    }
+
     /// Convert the TemplateToken environment variables into a flat HashMap.
-    /// TemplateTokens are complex polymorphic types from C#. Simple scalars
-    /// serialize as plain JSON values; mappings use `{"type": 2, "map": [...]}`.
+    /// Later mappings override earlier ones.
     pub fn environment_variables_map(&self) -> std::collections::HashMap<String, String> {
         let mut result = std::collections::HashMap::new();
         for token in &self.environment_variables {
-            Self::extract_env_from_template_token(token, &mut result);
+            result.extend(template_token::to_string_map(token));
         }
         result
     }
 
-    fn extract_env_from_template_token(
-        token: &serde_json::Value,
-        out: &mut std::collections::HashMap<String, String>,
-    ) {
-        // TemplateToken serialisation:
-        // - If the token is a plain JSON object with a "map" array, it's a
-        //   MappingToken where entries alternate key, value, key, value, ...
-        // - Each sub-token that's a plain string/number/bool is a literal.
-        // - Sub-tokens can also be objects with a "lit" field.
-        if let Some(obj) = token.as_object() {
-            if let Some(map_arr) = obj.get("map").and_then(|v| v.as_array()) {
-                // Pairs: key, value, key, value, ...
-                let mut iter = map_arr.iter();
-                while let (Some(k), Some(v)) = (iter.next(), iter.next()) {
-                    let key = Self::template_token_to_string(k);
-                    let val = Self::template_token_to_string(v);
-                    if let (Some(k), Some(v)) = (key, val) {
-                        out.insert(k, v);
-                    }
-                }
-            }
-        }
-    }
-
-    fn template_token_to_string(token: &serde_json::Value) -> Option<String> {
-        match token {
-            serde_json::Value::String(s) => Some(s.clone()),
-            serde_json::Value::Number(n) => Some(n.to_string()),
-            serde_json::Value::Bool(b) => Some(b.to_string()),
-            serde_json::Value::Object(obj) => {
-                // Object form: {"type": N, "lit": "value"} or similar
-                obj.get("lit")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            }
-            _ => None,
-        }
+    /// The job-level expression contexts carried in `contextData`: `matrix`,
+    /// `strategy` and `needs`, converted to plain JSON. Contexts the message
+    /// does not carry are left out.
+    pub fn job_expression_contexts(&self) -> std::collections::HashMap<String, serde_json::Value> {
+        ["matrix", "strategy", "needs"]
+            .into_iter()
+            .filter_map(|name| {
+                self.context_data.get(name).map(|data| {
+                    (
+                        name.to_string(),
+                        crate::github_context::context_data_to_json(data),
+                    )
+                })
+            })
+            .collect()
     }
 
     /// Check if job containers are defined.
@@ -291,78 +285,28 @@ impl JobStep {
     }
 
     /// Extract the inputs as a flat HashMap.
-    /// C# sends inputs as a TemplateToken (type=2 MappingToken with map array).
-    /// Each map entry has `Key` and `Value` (PascalCase) with `lit` string values.
+    /// C# sends inputs as a TemplateToken mapping; a plain JSON object is also accepted.
     pub fn inputs_map(&self) -> std::collections::HashMap<String, String> {
-        let mut result = std::collections::HashMap::new();
-        if let Some(ref inputs_val) = self.inputs {
-            if let Some(obj) = inputs_val.as_object() {
-                if let Some(map_arr) = obj.get("map").and_then(|v| v.as_array()) {
-                    for entry in map_arr {
-                        // Format: {"Key": {"type": 0, "lit": "name"}, "Value": {"type": 0, "lit": "value"}}
-                        if let Some(entry_obj) = entry.as_object() {
-                            let key = entry_obj
-                                .get("Key")
-                                .or_else(|| entry_obj.get("key"))
-                                .and_then(|k| AgentJobRequestMessage::template_token_to_string(k));
-                            let val = entry_obj
-                                .get("Value")
-                                .or_else(|| entry_obj.get("value"))
-                                .and_then(|v| AgentJobRequestMessage::template_token_to_string(v));
-                            if let (Some(k), Some(v)) = (key, val) {
-                                result.insert(k, v);
-                            }
-                        }
-                    }
-                } else {
-                    // Simple object mapping fallback
-                    for (k, v) in obj {
-                        if let Some(s) = v.as_str() {
-                            result.insert(k.clone(), s.to_string());
-                        }
-                    }
-                }
-            }
-        }
-        result
+        self.inputs
+            .as_ref()
+            .map(template_token::to_string_map)
+            .unwrap_or_default()
     }
 
     /// Extract the environment as a flat HashMap.
-    /// C# sends environment as TemplateToken or a simple mapping.
+    /// C# sends environment as a TemplateToken mapping; a plain JSON object is also accepted.
     pub fn environment_map(&self) -> std::collections::HashMap<String, String> {
-        let mut result = std::collections::HashMap::new();
-        if let Some(ref env_val) = self.environment {
-            // If it's a simple JSON object with string values, extract directly
-            if let Some(obj) = env_val.as_object() {
-                // Check if it looks like a TemplateToken (has "type" and "map" fields)
-                if let Some(map_arr) = obj.get("map").and_then(|v| v.as_array()) {
-                    let mut iter = map_arr.iter();
-                    while let (Some(k), Some(v)) = (iter.next(), iter.next()) {
-                        if let (Some(key), Some(val)) = (
-                            AgentJobRequestMessage::template_token_to_string(k),
-                            AgentJobRequestMessage::template_token_to_string(v),
-                        ) {
-                            result.insert(key, val);
-                        }
-                    }
-                } else {
-                    // Simple object mapping
-                    for (k, v) in obj {
-                        if let Some(s) = v.as_str() {
-                            result.insert(k.clone(), s.to_string());
-                        }
-                    }
-                }
-            }
-        }
-        result
+        self.environment
+            .as_ref()
+            .map(template_token::to_string_map)
+            .unwrap_or_default()
     }
 }
 
 /// Action reference in a step.
 /// C# has polymorphic ActionStepDefinitionReference with subclasses
 /// RepositoryPathReference, ContainerRegistryReference, ScriptReference.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ActionReference {
     #[serde(default)]
@@ -482,6 +426,29 @@ pub struct ContainerCredentials {
     pub password: String,
 }
 
+/// Variables that always hold a token, whether or not the server marks them secret.
+const TOKEN_VARIABLES: &[&str] = &[
+    runner_common::constants::variables::system::GITHUB_TOKEN,
+    runner_common::constants::variables::system::ACCESS_TOKEN,
+];
+
+/// Look up a string entry in a context data object. Handles both plain JSON
+/// objects and the serialized `PipelineContextData` form
+/// (`{"t":2,"d":[{"k":"token","v":"..."}]}`).
+fn context_data_string(data: &serde_json::Value, key: &str) -> Option<String> {
+    if let Some(entries) = data.get("d").and_then(|d| d.as_array()) {
+        return entries
+            .iter()
+            .find(|entry| entry.get("k").and_then(|k| k.as_str()) == Some(key))
+            .and_then(|entry| entry.get("v"))
+            .and_then(|v| v.as_str().or_else(|| v.get("s").and_then(|s| s.as_str())))
+            .map(|s| s.to_string());
+    }
+    data.get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
 /// The worker service – top level orchestration.
 pub struct Worker {
     host_context: Arc<HostContext>,
@@ -530,49 +497,7 @@ impl Worker {
 
         trace.info("Received job message from listener.");
 
-        // Log the raw body length for diagnostics
-        trace.info(&format!(
-            "Job message body size: {} bytes",
-            msg.body.len()
-        ));
-
-        // Log a preview of the body (first 500 chars) for debugging
-        let preview: String = msg.body.chars().take(500).collect();
-        trace.info(&format!("Job message preview: {}", preview));
-
-        // Deserialize the job message
-        let job_message: AgentJobRequestMessage = match serde_json::from_str(&msg.body) {
-            Ok(m) => m,
-            Err(e) => {
-                trace.error(&format!(
-                    "Failed to deserialize AgentJobRequestMessage: {}",
-                    e
-                ));
-                // Log more body context for debugging
-                let extended_preview: String = msg.body.chars().take(2000).collect();
-                trace.error(&format!("Raw body (first 2000 chars): {}", extended_preview));
-                return Err(anyhow::anyhow!(
-                    "Failed to deserialize AgentJobRequestMessage: {}",
-                    e
-                ));
-            }
-        };
-
-        trace.info(&format!(
-            "Job: {} ({})",
-            job_message.job_display_name, job_message.job_id
-        ));
-        trace.info(&format!(
-            "Plan ID: {}, Timeline ID: {}",
-            job_message.plan_id(),
-            job_message.timeline_id()
-        ));
-        trace.info(&format!(
-            "Steps: {}, Variables: {}, Endpoints: {}",
-            job_message.steps.len(),
-            job_message.variables.len(),
-            job_message.resources.endpoints.len()
-        ));
+        let job_message = Self::parse_job_message(&msg.body, &trace)?;
 
         // Initialize secret masker from job variables
         self.initialize_secrets(&job_message);
@@ -590,26 +515,24 @@ impl Worker {
         };
 
         // Run the job
-        let job_runner = JobRunner::new(Arc::clone(&self.host_context));
-        let result = job_runner
-            .run_async(job_message.clone(), cancel_token.clone())
-            .await
-            .unwrap_or_else(|e| {
-                tracing::error!("JobRunner failed: {:#}", e);
-                TaskResult::Failed
-            });
+        let completion = self.run_job(&job_message, cancel_token.clone()).await;
+        let result = completion.result;
 
         // Report job completion to the server
         // This is critical — without it the server thinks the job is still running
         // and the broker will endlessly flood cancellation messages.
-        match RunServer::from_message(&job_message) {
+        match run_server_from_message(&job_message) {
             Ok(run_server) => {
+                let run_server = run_server.with_pending_completions(
+                    self.host_context
+                        .get_config_file(WellKnownConfigFile::PendingCompletions),
+                );
                 let report_trace = self.host_context.get_trace("Worker.CompleteJob");
-                if let Err(e) = run_server
-                    .complete_job(
+                if let Err(e) = completion
+                    .report(
+                        &run_server,
                         &job_message.plan_id(),
                         &job_message.job_id,
-                        result,
                         &report_trace,
                     )
                     .await
@@ -630,22 +553,196 @@ impl Worker {
         let _ = cancel_handle.await;
 
         // Notify the listener that the job is done
-        let result_code = runner_common::util::task_result_util::TaskResultUtil::translate_to_return_code(result);
         let _ = channel_out
-            .send_async(MessageType::NewJobRequest, &result_code.to_string())
+            .send_async(
+                MessageType::JobCompleted,
+                &completion.completed_message_body(),
+            )
             .await;
+        let _ = channel_out.flush().await;
 
         trace.info(&format!("Worker completed with result: {}", result));
 
         Ok(result)
     }
 
-    /// Initialize the secret masker from job variables that are marked as secret.
+    /// Crash-repro entry point. Loads a saved job message from `path` and runs
+    /// it end-to-end without the listener: there is no IPC channel, and job
+    /// completion is printed to stdout instead of reported to the Run Service.
+    pub async fn run_from_file(&self, path: &Path) -> Result<TaskResult> {
+        let trace = self.host_context.get_trace("Worker");
+        trace.info(&format!("Loading job message from {}", path.display()));
+
+        let body = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read job message file {}", path.display()))?;
+        let job_message = Self::parse_job_message(&body, &trace)?;
+
+        self.initialize_secrets(&job_message);
+
+        // Ctrl-C cancels the job the same way a CancelRequest from the listener would
+        let cancel_token = CancellationToken::new();
+        let cancel_child = cancel_token.clone();
+        let cancel_handle = tokio::spawn(async move {
+            tokio::select! {
+                _ = cancel_child.cancelled() => {}
+                _ = tokio::signal::ctrl_c() => cancel_child.cancel(),
+            }
+        });
+
+        let completion = self.run_job(&job_message, cancel_token.clone()).await;
+        let result = completion.result;
+        trace.info("Running from a job file; not reporting completion to the Run Service.");
+        print!("{}", completion);
+
+        cancel_token.cancel();
+        let _ = cancel_handle.await;
+
+        trace.info(&format!("Worker completed with result: {}", result));
+
+        Ok(result)
+    }
+
+    /// Validate entry point. Receives the job message over IPC and returns
+    /// its plan without running anything. Once connected, the listener is
+    /// always sent a job completed message so it does not treat the job as
+    /// crashed: succeeded for a valid plan, failed otherwise.
+    pub async fn validate_async(&self, pipe_in: &str, pipe_out: &str) -> Result<JobPlan> {
+        let trace = self.host_context.get_trace("Worker");
+
+        let mut channel_in = ProcessChannel::new();
+        channel_in
+            .start_client(pipe_in)
+            .await
+            .context("Failed to connect inbound IPC channel")?;
+        let mut channel_out = ProcessChannel::new();
+        channel_out
+            .start_client(pipe_out)
+            .await
+            .context("Failed to connect outbound IPC channel")?;
+
+        let plan = async {
+            let msg = channel_in
+                .receive_async()
+                .await
+                .context("Failed to receive job message from listener")?;
+            if msg.message_type != MessageType::NewJobRequest {
+                anyhow::bail!("Expected NewJobRequest message, got {}", msg.message_type);
+            }
+
+            let job_message = Self::parse_job_message(&msg.body, &trace)?;
+            self.initialize_secrets(&job_message);
+            Ok(JobPlan::build(&self.host_context, &job_message))
+        }
+        .await;
+
+        let result = match plan {
+            Ok(ref plan) if !plan.has_invalid_conditions() => TaskResult::Succeeded,
+            _ => TaskResult::Failed,
+        };
+        let completion = JobCompletion::from_result(result);
+        let _ = channel_out
+            .send_async(
+                MessageType::JobCompleted,
+                &completion.completed_message_body(),
+            )
+            .await;
+        let _ = channel_out.flush().await;
+
+        plan
+    }
+
+    /// Validate a job message saved in `path`, returning its plan without
+    /// running anything.
+    pub fn validate_from_file(&self, path: &Path) -> Result<JobPlan> {
+        let trace = self.host_context.get_trace("Worker");
+        trace.info(&format!("Validating job message from {}", path.display()));
+
+        let body = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read job message file {}", path.display()))?;
+        let job_message = Self::parse_job_message(&body, &trace)?;
+        self.initialize_secrets(&job_message);
+
+        Ok(JobPlan::build(&self.host_context, &job_message))
+    }
+
+    /// Deserialize the job message body and log a summary of it.
+    fn parse_job_message(body: &str, trace: &dyn TraceWriter) -> Result<AgentJobRequestMessage> {
+        // Log the raw body length for diagnostics
+        trace.info(&format!("Job message body size: {} bytes", body.len()));
+
+        // Log a preview of the body (first 500 chars) for debugging
+        let preview: String = body.chars().take(500).collect();
+        trace.info(&format!("Job message preview: {}", preview));
+
+        // Deserialize the job message
+        let job_message: AgentJobRequestMessage = match serde_json::from_str(body) {
+            Ok(m) => m,
+            Err(e) => {
+                trace.error(&format!(
+                    "Failed to deserialize AgentJobRequestMessage: {}",
+                    e
+                ));
+                // Log more body context for debugging
+                let extended_preview: String = body.chars().take(2000).collect();
+                trace.error(&format!(
+                    "Raw body (first 2000 chars): {}",
+                    extended_preview
+                ));
+                return Err(anyhow::anyhow!(
+                    "Failed to deserialize AgentJobRequestMessage: {}",
+                    e
+                ));
+            }
+        };
+
+        trace.info(&format!(
+            "Job: {} ({})",
+            job_message.job_display_name, job_message.job_id
+        ));
+        trace.info(&format!(
+            "Plan ID: {}, Timeline ID: {}",
+            job_message.plan_id(),
+            job_message.timeline_id()
+        ));
+        trace.info(&format!(
+            "Steps: {}, Variables: {}, Endpoints: {}",
+            job_message.steps.len(),
+            job_message.variables.len(),
+            job_message.resources.endpoints.len()
+        ));
+
+        Ok(job_message)
+    }
+
+    /// Run the job through the `JobRunner`, mapping a runner error to a failed job.
+    async fn run_job(
+        &self,
+        job_message: &AgentJobRequestMessage,
+        cancel_token: CancellationToken,
+    ) -> JobCompletion {
+        let job_runner = JobRunner::new(Arc::clone(&self.host_context));
+        job_runner
+            .run_async(job_message.clone(), cancel_token)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("JobRunner failed: {:#}", e);
+                JobCompletion::from_result(TaskResult::Failed)
+            })
+    }
+
+    /// Initialize the secret masker from job variables that are marked as secret,
+    /// plus the tokens the job carries whether or not they are flagged: the
+    /// GitHub token, endpoint authorization parameters (the bearer tokens for
+    /// the service connections and the OIDC token request), and the `github`
+    /// context token.
     fn initialize_secrets(&self, message: &AgentJobRequestMessage) {
         let masker = &self.host_context.secret_masker;
 
-        for (_name, var) in &message.variables {
-            if var.is_secret && !var.value.is_empty() {
+        for (name, var) in &message.variables {
+            let is_token = TOKEN_VARIABLES
+                .iter()
+                .any(|token_name| name.eq_ignore_ascii_case(token_name));
+            if (var.is_secret || is_token) && !var.value.is_empty() {
                 masker.add_value(&var.value);
             }
         }
@@ -661,6 +758,16 @@ impl Worker {
             }
         }
 
+        if let Some(token) = message
+            .context_data
+            .get("github")
+            .and_then(|github| context_data_string(github, "token"))
+        {
+            if !token.is_empty() {
+                masker.add_value(&token);
+            }
+        }
+
         // Container credentials are in TemplateToken format now.
         // We'll add masking for those when we implement proper container support.
     }
@@ -738,6 +845,57 @@ mod tests {
         assert_eq!(msg.timeline_id(), "tl-1");
     }
 
+    #[test]
+    fn test_template_token_inputs_and_environment() {
+        let json = r#"{
+            "jobId": "abc-123",
+            "environmentVariables": [
+                {"type": 2, "map": [{"Key": "CI", "Value": "true"}, {"Key": "LEVEL", "Value": "1"}]},
+                {"type": 2, "map": [{"Key": "LEVEL", "Value": {"type": 6, "num": 2}}]}
+            ],
+            "steps": [{
+                "id": "step1",
+                "type": "action",
+                "inputs": {"type": 2, "map": [
+                    {"Key": {"type": 0, "lit": "path"}, "Value": {"type": 0, "lit": "dist"}},
+                    {"Key": "include", "Value": {"type": 1, "seq": [
+                        {"type": 2, "map": [{"Key": "os", "Value": "linux"}]}
+                    ]}}
+                ]},
+                "environment": {"type": 2, "map": [
+                    {"Key": "SHA", "Value": {"type": 3, "expr": "github.sha"}}
+                ]}
+            }]
+        }"#;
+        let msg: AgentJobRequestMessage = serde_json::from_str(json).unwrap();
+
+        let env = msg.environment_variables_map();
+        assert_eq!(env["CI"], "true");
+        assert_eq!(env["LEVEL"], "2");
+
+        let inputs = msg.steps[0].inputs_map();
+        assert_eq!(inputs["path"], "dist");
+        assert_eq!(inputs["include"], r#"[{"os":"linux"}]"#);
+        assert_eq!(msg.steps[0].environment_map()["SHA"], "${{ github.sha }}");
+    }
+
+    #[test]
+    fn test_workspace_clean() {
+        let clean = |workspace: &str| {
+            let json = format!(r#"{{"jobId":"abc-123"{}}}"#, workspace);
+            serde_json::from_str::<AgentJobRequestMessage>(&json)
+                .unwrap()
+                .workspace_clean()
+        };
+
+        assert!(clean(""));
+        assert!(clean(r#","workspace":{}"#));
+        assert!(clean(r#","workspace":{"clean":"true"}"#));
+        assert!(clean(r#","workspace":{"clean":true}"#));
+        assert!(!clean(r#","workspace":{"clean":"false"}"#));
+        assert!(!clean(r#","workspace":{"clean":false}"#));
+    }
+
     #[test]
     fn test_deserialize_file_table_as_strings() {
         let json = r#"{
@@ -775,4 +933,185 @@ mod tests {
         assert_eq!(step.condition, "success()");
         assert_eq!(step.timeout_in_minutes, 30);
     }
+
+    fn write_job_file(dir: &Path, body: &str) -> std::path::PathBuf {
+        let path = dir.join("job.json");
+        std::fs::write(&path, body).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_run_from_file_completes_without_ipc() {
+        let root = tempfile::tempdir().unwrap();
+        let host = HostContext::new("Test");
+        host.set_root_override(root.path().to_path_buf());
+
+        let job_file = write_job_file(
+            root.path(),
+            r#"{
+                "jobId": "job-1",
+                "jobDisplayName": "Repro Job",
+                "plan": {"planId": "plan-1"},
+                "timeline": {"id": "tl-1"},
+                "steps": [
+                    {"id": "step1", "displayName": "Say hello", "type": "script", "script": "echo hello"}
+                ]
+            }"#,
+        );
+
+        let worker = Worker::new(host);
+        let result = worker.run_from_file(&job_file).await.unwrap();
+        assert_eq!(result, TaskResult::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn test_run_from_file_reports_failed_step() {
+        let root = tempfile::tempdir().unwrap();
+        let host = HostContext::new("Test");
+        host.set_root_override(root.path().to_path_buf());
+
+        let job_file = write_job_file(
+            root.path(),
+            r#"{
+                "jobId": "job-2",
+                "jobDisplayName": "Failing Job",
+                "steps": [
+                    {"id": "step1", "displayName": "Fail", "type": "script", "script": "exit 3"}
+                ]
+            }"#,
+        );
+
+        let worker = Worker::new(host);
+        let result = worker.run_from_file(&job_file).await.unwrap();
+        assert_eq!(result, TaskResult::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_run_from_file_rejects_invalid_message() {
+        let root = tempfile::tempdir().unwrap();
+        let host = HostContext::new("Test");
+        host.set_root_override(root.path().to_path_buf());
+
+        let worker = Worker::new(Arc::clone(&host));
+        let job_file = write_job_file(root.path(), "not json");
+        assert!(worker.run_from_file(&job_file).await.is_err());
+
+        let missing = root.path().join("missing.json");
+        let err = worker.run_from_file(&missing).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("Failed to read job message file"));
+    }
+
+    #[test]
+    fn test_validate_from_file_plans_without_running() {
+        let root = tempfile::tempdir().unwrap();
+        let host = HostContext::new("Test");
+        host.set_root_override(root.path().to_path_buf());
+
+        let marker = root.path().join("ran");
+        let job_file = write_job_file(
+            root.path(),
+            &serde_json::json!({
+                "jobId": "job-3",
+                "jobDisplayName": "Validate Job",
+                "steps": [
+                    {"id": "step1", "displayName": "Touch", "type": "script",
+                     "script": format!("touch '{}'", marker.display())},
+                    {"id": "step2", "displayName": "Cleanup", "type": "script",
+                     "script": "true", "condition": "failure()"}
+                ]
+            })
+            .to_string(),
+        );
+
+        let plan = Worker::new(host).validate_from_file(&job_file).unwrap();
+        assert_eq!(plan.steps.len(), 2);
+        assert_eq!(plan.steps[0].decision, crate::job_plan::StepDecision::Run);
+        assert_eq!(plan.steps[1].decision, crate::job_plan::StepDecision::Skip);
+        assert!(!marker.exists());
+        assert!(!root.path().join("_work").exists());
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_failed_completion_for_bad_message() {
+        let root = tempfile::tempdir().unwrap();
+        let host = HostContext::new("Test");
+        host.set_root_override(root.path().to_path_buf());
+
+        let mut listener_out = ProcessChannel::new();
+        let pipe_in = listener_out.start_server(root.path()).unwrap();
+        let mut listener_in = ProcessChannel::new();
+        let pipe_out = listener_in.start_server(root.path()).unwrap();
+
+        let worker = Worker::new(host);
+        let listener = async {
+            listener_out.accept().await.unwrap();
+            listener_out
+                .send_async(MessageType::NewJobRequest, "not a job message")
+                .await
+                .unwrap();
+            listener_in.accept().await.unwrap();
+            listener_in.receive_async().await.unwrap()
+        };
+        let (plan, completed) = tokio::join!(worker.validate_async(&pipe_in, &pipe_out), listener);
+
+        assert!(plan.is_err());
+        assert_eq!(completed.message_type, MessageType::JobCompleted);
+        let body: serde_json::Value = serde_json::from_str(&completed.body).unwrap();
+        assert_eq!(
+            body["resultCode"],
+            runner_common::util::task_result_util::TaskResultUtil::translate_to_return_code(
+                TaskResult::Failed
+            )
+        );
+    }
+
+    #[test]
+    fn test_initialize_secrets_masks_unflagged_tokens() {
+        let json = r#"{
+            "jobId": "job-1",
+            "variables": {
+                "system.github.token": {"value": "ghs_variableToken123", "isSecret": false},
+                "some.setting": {"value": "not-a-secret", "isSecret": false}
+            },
+            "resources": {
+                "endpoints": [
+                    {
+                        "name": "SystemVssConnection",
+                        "url": "https://pipelines.example.com/",
+                        "authorization": {"scheme": "OAuth", "parameters": {"AccessToken": "vssBearerToken456"}}
+                    },
+                    {
+                        "name": "ActionsIdTokenRequest",
+                        "url": "https://token.example.com/",
+                        "authorization": {"scheme": "OAuth", "parameters": {"AccessToken": "oidcRequestToken789"}}
+                    }
+                ]
+            },
+            "contextData": {
+                "github": {"t": 2, "d": [
+                    {"k": "repository", "v": "octo/repo"},
+                    {"k": "token", "v": "ghs_contextToken000"}
+                ]}
+            }
+        }"#;
+        let msg: AgentJobRequestMessage = serde_json::from_str(json).unwrap();
+        let host = HostContext::new("Test");
+        let worker = Worker::new(Arc::clone(&host));
+        worker.initialize_secrets(&msg);
+
+        let output = host.secret_masker.mask_secrets(
+            "ghs_variableToken123 vssBearerToken456 oidcRequestToken789 ghs_contextToken000 not-a-secret octo/repo",
+        );
+        assert_eq!(output, "*** *** *** *** not-a-secret octo/repo");
+    }
+
+    #[test]
+    fn test_context_data_string_plain_object() {
+        let github = serde_json::json!({"token": "ghs_plain", "actor": "octocat"});
+        assert_eq!(
+            context_data_string(&github, "token"),
+            Some("ghs_plain".to_string())
+        );
+        assert_eq!(context_data_string(&github, "missing"), None);
+    }
 }